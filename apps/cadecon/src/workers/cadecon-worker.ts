@@ -47,6 +47,8 @@ function handleTraceJob(req: Extract<CaDeconWorkerInbound, { type: 'trace-job' }
       pve: number;
       iterations: number;
       converged: boolean;
+      noise_std: number;
+      snr_db: number;
     };
 
     if (cancelled) {
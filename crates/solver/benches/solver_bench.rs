@@ -0,0 +1,159 @@
+//! Performance regression benchmarks for the solver hot paths.
+//!
+//! Run with: `cargo bench --bench solver_bench`
+//!
+//! Every input is a fixed-seed synthetic trace, so runs are comparable across
+//! commits. Sample counts, measurement times and bootstrap resamples are kept
+//! small so the whole suite, HTML report included, finishes in well under a
+//! minute on CI.
+
+use std::time::Duration;
+
+use calab_solver::bench::{reset_fft_plans, subtract_rolling_baseline, threshold_search};
+use calab_solver::{ConvMode, InDecaBuilder, Solver};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const TAU_RISE: f64 = 0.02;
+const TAU_DECAY: f64 = 0.4;
+const FS: f64 = 30.0;
+const LAMBDA: f64 = 0.01;
+const SEED: u64 = 0x5EED;
+
+/// FISTA iterations per `step_batch` measurement.
+const STEPS: u32 = 20;
+
+/// Deterministic calcium-like trace: sparse unit spikes through the
+/// double-exponential kernel plus uniform noise, from a 64-bit LCG.
+fn synthetic_trace(n: usize, seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let d = (-1.0 / (FS * TAU_DECAY)).exp();
+    let r = (-1.0 / (FS * TAU_RISE)).exp();
+    let (mut slow, mut fast) = (0.0_f64, 0.0_f64);
+    (0..n)
+        .map(|_| {
+            if next() < 0.02 {
+                slow += 1.0;
+                fast += 1.0;
+            }
+            let sample = 1.0 + (slow - fast) + 0.1 * (next() - 0.5);
+            slow *= d;
+            fast *= r;
+            sample as f32
+        })
+        .collect()
+}
+
+fn bench_step_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step_batch");
+    for mode in [ConvMode::Fft, ConvMode::BandedAR2] {
+        for n in [1_000, 10_000, 100_000] {
+            let trace = synthetic_trace(n, SEED);
+            let mut solver = Solver::new();
+            solver.set_params(TAU_RISE, TAU_DECAY, LAMBDA, FS);
+            solver.set_conv_mode(mode);
+            group.throughput(Throughput::Elements((n as u64) * STEPS as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{mode:?}"), n),
+                &trace,
+                |b, trace| {
+                    b.iter(|| {
+                        solver.set_trace(trace);
+                        black_box(solver.step_batch(STEPS))
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Calls per measurement in `bench_fft_plan_cache`.
+const PLAN_CACHE_CALLS: usize = 100;
+
+/// 100 same-length FFT solves, each starting from dropped plans (as after a
+/// kernel-length change): `cached` rebuilds from the solver's `FftPlanCache`,
+/// `uncached` empties the cache first and re-plans every call.
+fn bench_fft_plan_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft_plan_cache");
+    let n = 10_000;
+    let trace = synthetic_trace(n, SEED);
+    for keep_cache in [true, false] {
+        let mut solver = Solver::new();
+        solver.set_params(TAU_RISE, TAU_DECAY, LAMBDA, FS);
+        solver.set_conv_mode(ConvMode::Fft);
+        solver.set_trace(&trace);
+        let name = if keep_cache { "cached" } else { "uncached" };
+        group.bench_with_input(BenchmarkId::new(name, n), &trace, |b, trace| {
+            b.iter(|| {
+                for _ in 0..PLAN_CACHE_CALLS {
+                    reset_fft_plans(&mut solver, keep_cache);
+                    solver.set_trace(trace);
+                    black_box(solver.step_batch(1));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_rolling_baseline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("subtract_rolling_baseline");
+    let window = (30.0 * FS) as usize;
+    for n in [1_000, 100_000] {
+        let trace = synthetic_trace(n, SEED);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &trace, |b, trace| {
+            b.iter_batched_ref(
+                || trace.clone(),
+                |work| subtract_rolling_baseline(work, window, 0.1),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_threshold_search(c: &mut Criterion) {
+    let n = 10_000;
+    let y = synthetic_trace(n, SEED);
+    // A relaxed solution with graded amplitudes, peak-normalized as the
+    // pipeline hands it to the search.
+    let s_relaxed: Vec<f32> = synthetic_trace(n, SEED ^ 0xFF)
+        .iter()
+        .map(|&v| ((v - 1.0) * 2.0).clamp(0.0, 1.0))
+        .collect();
+    c.bench_function("threshold_search/10000", |b| {
+        b.iter(|| black_box(threshold_search(&s_relaxed, &y, TAU_RISE, TAU_DECAY, FS)))
+    });
+}
+
+fn bench_solve_trace(c: &mut Criterion) {
+    let trace = synthetic_trace(3_000, SEED);
+    let config = InDecaBuilder::default()
+        .tau_rise(TAU_RISE)
+        .tau_decay(TAU_DECAY)
+        .fs(FS)
+        .build()
+        .expect("valid InDeCa parameters");
+    c.bench_function("solve_trace/3000", |b| {
+        b.iter(|| black_box(config.solve(&trace, None)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(10)
+        .nresamples(10_000)
+        .warm_up_time(Duration::from_millis(300))
+        .measurement_time(Duration::from_secs(1));
+    targets = bench_step_batch, bench_fft_plan_cache, bench_rolling_baseline,
+        bench_threshold_search, bench_solve_trace
+}
+criterion_main!(benches);
@@ -0,0 +1,805 @@
+//! Post-deconvolution spike-train statistics.
+//!
+//! Inter-spike intervals (ISIs) are measured between frames whose spike count
+//! reaches `min_count`, so several spikes binned into one frame count as one
+//! event. Everything here works on the original-rate `s_counts` returned by
+//! the solvers. [`kfold_cv_lambda`] / [`select_lambda_cv`] instead score a
+//! lambda by how well a fit to the rest of the trace predicts held-out frames.
+//! [`event_aligned_average`] / [`event_aligned_raster`] cut trials around
+//! stimulus or behavioural event frames. [`cell_pair_correlations`] /
+//! [`pairwise_correlation_index`] measure population synchrony, and
+//! [`spike_triggered_average`] / [`spike_triggered_covariance`] recover the
+//! mean (and spread) of the trace around inferred spikes — the usual sanity
+//! check on an estimated kernel. [`zscore`], [`robust_zscore`] and
+//! [`normalize_dff`] are the usual per-trace normalisations.
+//! [`estimate_trace_snr`] rates how far a fit stands above its residual.
+
+use crate::banded::BandedAR2;
+use crate::peak_seed::median_and_mad;
+use crate::{CalabError, Solver};
+
+/// FISTA iterations between held-out refreshes in [`kfold_cv_lambda`].
+const CV_BATCH: u32 = 10;
+/// Iteration cap for each fold's training solve.
+const CV_MAX_ITERS: u32 = 2000;
+
+/// A run of spike frames whose successive ISIs are all within the burst
+/// criterion (see [`detect_bursts`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BurstEvent {
+    /// First spike frame of the burst.
+    pub start_frame: usize,
+    /// Last spike frame of the burst (inclusive).
+    pub end_frame: usize,
+    /// Number of spike frames in the burst.
+    pub n_spikes: usize,
+    /// Sum of `s_counts` over the burst's spike frames.
+    pub total_amplitude: f32,
+    /// Highest instantaneous rate, 1 / shortest ISI (0 for a single spike).
+    pub peak_rate_hz: f64,
+}
+
+/// ISIs in seconds between adjacent spike frames (`s_counts[t] >= min_count`).
+///
+/// Returns an empty vector when fewer than two frames qualify.
+pub fn spike_isis(s_counts: &[f32], fs: f64, min_count: f32) -> Vec<f64> {
+    let frames: Vec<usize> = (0..s_counts.len())
+        .filter(|&t| s_counts[t] >= min_count)
+        .collect();
+    frames
+        .windows(2)
+        .map(|w| (w[1] - w[0]) as f64 / fs)
+        .collect()
+}
+
+/// Mean ISI in seconds; NaN for an empty slice.
+pub fn isi_mean(isis: &[f64]) -> f64 {
+    if isis.is_empty() {
+        return f64::NAN;
+    }
+    isis.iter().sum::<f64>() / isis.len() as f64
+}
+
+/// Coefficient of variation, population standard deviation over mean
+/// (1 for a Poisson process, 0 for perfectly regular firing). NaN for an
+/// empty slice or a zero mean.
+pub fn isi_cv(isis: &[f64]) -> f64 {
+    let mean = isi_mean(isis);
+    if mean.is_nan() || mean == 0.0 {
+        return f64::NAN;
+    }
+    let var = isis.iter().map(|&x| (x - mean) * (x - mean)).sum::<f64>() / isis.len() as f64;
+    var.sqrt() / mean
+}
+
+/// Histogram of ISIs over `n_bins` equal bins spanning `[0, max_isi_s]`.
+///
+/// Returns the `n_bins + 1` bin edges and the per-bin counts. As in
+/// `numpy.histogram`, bins are half-open except the last, which includes
+/// `max_isi_s`; longer ISIs are not counted. `n_bins == 0` or a non-positive
+/// `max_isi_s` gives empty edges and counts.
+pub fn isi_histogram(isis: &[f64], n_bins: usize, max_isi_s: f64) -> (Vec<f64>, Vec<usize>) {
+    if n_bins == 0 || max_isi_s.is_nan() || max_isi_s <= 0.0 {
+        return (Vec::new(), Vec::new());
+    }
+    let width = max_isi_s / n_bins as f64;
+    let edges: Vec<f64> = (0..=n_bins).map(|i| i as f64 * width).collect();
+    let mut counts = vec![0_usize; n_bins];
+    for &isi in isis {
+        if (0.0..=max_isi_s).contains(&isi) {
+            let bin = ((isi / width) as usize).min(n_bins - 1);
+            counts[bin] += 1;
+        }
+    }
+    (edges, counts)
+}
+
+/// Group spike frames (`s_counts > 0`) into bursts: maximal runs in which
+/// every ISI is at most `max_isi_s`, kept when they contain at least
+/// `min_burst_spikes` spike frames. With `min_burst_spikes <= 1` every spike
+/// belongs to a burst, isolated ones as single-spike bursts.
+pub fn detect_bursts(
+    s_counts: &[f32],
+    fs: f64,
+    max_isi_s: f64,
+    min_burst_spikes: usize,
+) -> Vec<BurstEvent> {
+    let frames: Vec<usize> = (0..s_counts.len()).filter(|&t| s_counts[t] > 0.0).collect();
+    let mut events = Vec::new();
+    let mut start = 0;
+    while start < frames.len() {
+        let mut end = start;
+        let mut min_gap = usize::MAX;
+        while end + 1 < frames.len() {
+            let gap = frames[end + 1] - frames[end];
+            if gap as f64 / fs > max_isi_s {
+                break;
+            }
+            min_gap = min_gap.min(gap);
+            end += 1;
+        }
+        let n_spikes = end - start + 1;
+        if n_spikes >= min_burst_spikes {
+            events.push(BurstEvent {
+                start_frame: frames[start],
+                end_frame: frames[end],
+                n_spikes,
+                total_amplitude: frames[start..=end].iter().map(|&t| s_counts[t]).sum(),
+                peak_rate_hz: if n_spikes > 1 {
+                    fs / min_gap as f64
+                } else {
+                    0.0
+                },
+            });
+        }
+        start = end + 1;
+    }
+    events
+}
+
+/// Bursts per second over a recording of `total_duration_s` seconds; NaN for
+/// a non-positive duration.
+pub fn burst_rate(events: &[BurstEvent], total_duration_s: f64) -> f64 {
+    if total_duration_s.is_nan() || total_duration_s <= 0.0 {
+        return f64::NAN;
+    }
+    events.len() as f64 / total_duration_s
+}
+
+/// Per-frame mean and standard error of `trace` across trials aligned on
+/// `event_frames`.
+///
+/// Each trial is the window `event - pre_frames .. event + post_frames`, so
+/// index `pre_frames` of the result is the event frame. Events closer than
+/// `pre_frames` to the start, or than `post_frames` to the end, are skipped.
+/// The standard error uses the sample standard deviation; it is NaN with
+/// fewer than two trials, and both vectors are NaN with none.
+pub fn event_aligned_average(
+    trace: &[f32],
+    event_frames: &[usize],
+    pre_frames: usize,
+    post_frames: usize,
+) -> (Vec<f32>, Vec<f32>) {
+    let len = pre_frames + post_frames;
+    let trials: Vec<&[f32]> =
+        aligned_windows(trace, event_frames, pre_frames, post_frames).collect();
+    let m = trials.len() as f64;
+    let mut mean = vec![f32::NAN; len];
+    let mut sem = vec![f32::NAN; len];
+    if trials.is_empty() {
+        return (mean, sem);
+    }
+    for j in 0..len {
+        let mu = trials.iter().map(|w| w[j] as f64).sum::<f64>() / m;
+        mean[j] = mu as f32;
+        if trials.len() > 1 {
+            let var = trials
+                .iter()
+                .map(|w| (w[j] as f64 - mu).powi(2))
+                .sum::<f64>()
+                / (m - 1.0);
+            sem[j] = (var / m).sqrt() as f32;
+        }
+    }
+    (mean, sem)
+}
+
+/// `s_counts` around each event, one row per kept trial, using the windows
+/// and edge rule of [`event_aligned_average`].
+pub fn event_aligned_raster(
+    s_counts: &[f32],
+    event_frames: &[usize],
+    pre_frames: usize,
+    post_frames: usize,
+) -> Vec<Vec<f32>> {
+    aligned_windows(s_counts, event_frames, pre_frames, post_frames)
+        .map(<[f32]>::to_vec)
+        .collect()
+}
+
+/// Windows `x[event - pre .. event + post]` for the events that fit.
+fn aligned_windows<'a>(
+    x: &'a [f32],
+    event_frames: &'a [usize],
+    pre: usize,
+    post: usize,
+) -> impl Iterator<Item = &'a [f32]> {
+    event_frames
+        .iter()
+        .filter(move |&&e| e >= pre && e + post <= x.len())
+        .map(move |&e| &x[e - pre..e + post])
+}
+
+/// Mean and standard error of `trace` around each spike frame
+/// (`s_counts[t] >= min_count`).
+///
+/// Each snippet is `trace[t - pre_frames ..= t + post_frames]`, so the result
+/// has `pre_frames + post_frames + 1` entries with the spike at index
+/// `pre_frames`. Spikes whose window runs off either end are skipped; NaN
+/// handling follows [`event_aligned_average`].
+pub fn spike_triggered_average(
+    trace: &[f32],
+    s_counts: &[f32],
+    pre_frames: usize,
+    post_frames: usize,
+    min_count: f32,
+) -> (Vec<f32>, Vec<f32>) {
+    let n = trace.len().min(s_counts.len());
+    let spikes: Vec<usize> = (0..n).filter(|&t| s_counts[t] >= min_count).collect();
+    event_aligned_average(&trace[..n], &spikes, pre_frames, post_frames + 1)
+}
+
+/// Spike-triggered covariance of `trace`: the `L x L` covariance (row-major,
+/// `L = pre_frames + post_frames + 1`) of the snippets of
+/// [`spike_triggered_average`] around every frame with `s_counts[t] > 0`.
+///
+/// Each snippet is weighted by its spike count, so fractional near-zero counts
+/// barely contribute, and the covariance is taken about the count-weighted
+/// mean with denominator `sum(counts) - 1`. All NaN when the counts of the
+/// kept snippets sum to 1 or less.
+pub fn spike_triggered_covariance(
+    trace: &[f32],
+    s_counts: &[f32],
+    pre_frames: usize,
+    post_frames: usize,
+) -> Vec<f64> {
+    let len = pre_frames + post_frames + 1;
+    let n = trace.len().min(s_counts.len());
+    let snippets: Vec<(f64, &[f32])> = (pre_frames..n.saturating_sub(post_frames))
+        .filter(|&t| s_counts[t] > 0.0)
+        .map(|t| (s_counts[t] as f64, &trace[t - pre_frames..=t + post_frames]))
+        .collect();
+    let total: f64 = snippets.iter().map(|&(w, _)| w).sum();
+    if total <= 1.0 {
+        return vec![f64::NAN; len * len];
+    }
+    let mut mean = vec![0.0_f64; len];
+    for &(w, x) in &snippets {
+        for (m, &v) in mean.iter_mut().zip(x) {
+            *m += w * v as f64;
+        }
+    }
+    mean.iter_mut().for_each(|m| *m /= total);
+    let mut cov = vec![0.0_f64; len * len];
+    for &(w, x) in &snippets {
+        for i in 0..len {
+            let di = x[i] as f64 - mean[i];
+            for j in i..len {
+                cov[i * len + j] += w * di * (x[j] as f64 - mean[j]);
+            }
+        }
+    }
+    for i in 0..len {
+        for j in i..len {
+            let c = cov[i * len + j] / (total - 1.0);
+            cov[i * len + j] = c;
+            cov[j * len + i] = c;
+        }
+    }
+    cov
+}
+
+/// Pearson correlation of every cell pair's binned spike train, in the order
+/// (0, 1), (0, 2), ..., (1, 2), ... — `n * (n - 1) / 2` values for `n` cells.
+///
+/// Each train is summed over consecutive bins of `bin_size_frames` frames
+/// (0 counts as 1); a trailing partial bin is dropped, and cells of unequal
+/// length are cut to the shortest. A pair involving a train that is constant
+/// over the bins (e.g. a silent cell) has NaN correlation.
+pub fn cell_pair_correlations(spike_matrix: &[Vec<f32>], bin_size_frames: usize) -> Vec<f64> {
+    let bin = bin_size_frames.max(1);
+    let n_bins = spike_matrix.iter().map(Vec::len).min().unwrap_or(0) / bin;
+    let binned: Vec<Vec<f64>> = spike_matrix
+        .iter()
+        .map(|cell| {
+            cell[..n_bins * bin]
+                .chunks(bin)
+                .map(|c| c.iter().map(|&v| v as f64).sum())
+                .collect()
+        })
+        .collect();
+    let mut out = Vec::with_capacity(binned.len() * binned.len().saturating_sub(1) / 2);
+    for (i, a) in binned.iter().enumerate() {
+        for b in &binned[i + 1..] {
+            out.push(pearson(a, b));
+        }
+    }
+    out
+}
+
+/// Pairwise Correlation Index: the mean of [`cell_pair_correlations`] over
+/// the pairs with a defined (non-NaN) correlation. NaN with fewer than two
+/// cells or when no pair is defined.
+pub fn pairwise_correlation_index(spike_matrix: &[Vec<f32>], bin_size_frames: usize) -> f64 {
+    let defined: Vec<f64> = cell_pair_correlations(spike_matrix, bin_size_frames)
+        .into_iter()
+        .filter(|r| !r.is_nan())
+        .collect();
+    if defined.is_empty() {
+        return f64::NAN;
+    }
+    defined.iter().sum::<f64>() / defined.len() as f64
+}
+
+/// Pearson correlation of two equal-length series; NaN if either is constant
+/// or they are empty.
+fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a.iter().zip(b) {
+        let (dx, dy) = (x - mean_a, y - mean_b);
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return f64::NAN;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Scale from the median absolute deviation to a Gaussian standard deviation.
+const MAD_TO_SIGMA: f32 = 1.4826;
+
+/// `(x - mean) / std` with the population standard deviation. A constant
+/// trace maps to zeros. Errors on an empty trace.
+pub fn zscore(trace: &[f32]) -> Result<Vec<f32>, CalabError> {
+    check_nonempty(trace)?;
+    let n = trace.len() as f64;
+    let mean = trace.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let var = trace
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let scale = if var > 0.0 { var.sqrt() } else { 1.0 };
+    Ok(trace
+        .iter()
+        .map(|&v| ((v as f64 - mean) / scale) as f32)
+        .collect())
+}
+
+/// `(x - median) / (1.4826 * MAD)`: the z-score with outlier-resistant
+/// location and scale, the MAD scaled so Gaussian noise gets unit spread (as
+/// the solver's own noise estimate does). A zero MAD leaves `x - median`.
+/// Errors on an empty trace.
+pub fn robust_zscore(trace: &[f32]) -> Result<Vec<f32>, CalabError> {
+    check_nonempty(trace)?;
+    let (median, mad) = median_and_mad(trace);
+    let scale = if mad > 0.0 { MAD_TO_SIGMA * mad } else { 1.0 };
+    Ok(trace.iter().map(|&v| (v - median) / scale).collect())
+}
+
+/// Elementwise `(trace - baseline) / max(baseline, 1e-6)` for a per-sample
+/// baseline such as a rolling F0. Errors on an empty trace or a `baseline`
+/// of a different length.
+pub fn normalize_dff(trace: &[f32], baseline: &[f32]) -> Result<Vec<f32>, CalabError> {
+    check_nonempty(trace)?;
+    if baseline.len() != trace.len() {
+        return Err(CalabError::DimensionMismatch {
+            expected: trace.len(),
+            got: baseline.len(),
+        });
+    }
+    Ok(trace
+        .iter()
+        .zip(baseline)
+        .map(|(&f, &f0)| (f - f0) / f0.max(1e-6))
+        .collect())
+}
+
+/// Fit SNR in dB: `10·log10(Σ(alpha·K*s)² / Σ(trace - alpha·K*s - baseline)²)`,
+/// the power of the reconvolved spikes (baseline excluded) over the power of
+/// the residual, with `K` the forward model of `banded`.
+///
+/// Only the overlapping length of `trace` and `s_counts` is used. A perfect
+/// fit gives `+inf`, an empty spike train `-inf`, and an empty trace NaN.
+pub(crate) fn estimate_trace_snr(
+    trace: &[f32],
+    s_counts: &[f32],
+    alpha: f64,
+    baseline: f64,
+    banded: &BandedAR2,
+) -> f64 {
+    let n = trace.len().min(s_counts.len());
+    let mut reconv = vec![0.0_f32; n];
+    banded.convolve_forward(&s_counts[..n], &mut reconv);
+    let (mut signal, mut noise) = (0.0_f64, 0.0_f64);
+    for (&y, &r) in trace[..n].iter().zip(&reconv) {
+        let fit = alpha * r as f64;
+        let residual = y as f64 - fit - baseline;
+        signal += fit * fit;
+        noise += residual * residual;
+    }
+    10.0 * (signal / noise).log10()
+}
+
+fn check_nonempty(trace: &[f32]) -> Result<(), CalabError> {
+    if trace.is_empty() {
+        return Err(CalabError::invalid("trace", "[]", "must not be empty"));
+    }
+    Ok(())
+}
+
+/// Held-out mean squared error of `lambda` under `k`-fold cross-validation.
+///
+/// The trace is split into `k` contiguous folds. For each fold the solver is
+/// trained on the other `k - 1`: the held-out frames start as a straight line
+/// between their neighbours and are overwritten with the current fit every
+/// [`CV_BATCH`] iterations, so at convergence they carry no residual and the
+/// fit is that of the remaining frames alone. The error is then measured
+/// against the original held-out samples and pooled over all folds.
+///
+/// `k` is reduced so every fold spans at least one kernel length, and raised
+/// to 2 if smaller. Returns NaN when the trace has fewer than `k` frames.
+pub fn kfold_cv_lambda(
+    trace: &[f32],
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda: f64,
+    fs: f64,
+    k: usize,
+) -> f64 {
+    let n = trace.len();
+    let mut solver = Solver::new();
+    solver.set_params(tau_rise, tau_decay, lambda, fs);
+    let k = k.min(n / solver.kernel.len().max(1)).max(2);
+    if n < k {
+        return f64::NAN;
+    }
+
+    let mut sq_err = 0.0;
+    let mut masked = trace.to_vec();
+    for fold in 0..k {
+        let (lo, hi) = (fold * n / k, (fold + 1) * n / k);
+        masked.copy_from_slice(trace);
+        interpolate_gap(&mut masked, lo, hi);
+        solver.set_trace(&masked);
+
+        let mut done = 0;
+        while done < CV_MAX_ITERS {
+            let converged = solver.step_batch(CV_BATCH);
+            done += CV_BATCH;
+            let fit = solver.get_reconvolution_with_baseline();
+            solver.trace[lo..hi].copy_from_slice(&fit[lo..hi]);
+            if converged {
+                break;
+            }
+        }
+
+        let fit = solver.get_reconvolution_with_baseline();
+        sq_err += trace[lo..hi]
+            .iter()
+            .zip(&fit[lo..hi])
+            .map(|(&y, &p)| (y as f64 - p as f64).powi(2))
+            .sum::<f64>();
+    }
+    sq_err / n as f64
+}
+
+/// The lambda in `lambdas` with the lowest [`kfold_cv_lambda`] error.
+///
+/// Lambdas whose error is NaN are skipped; returns NaN if `lambdas` is empty
+/// or no lambda could be scored.
+pub fn select_lambda_cv(
+    trace: &[f32],
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    lambdas: &[f64],
+    k: usize,
+) -> f64 {
+    lambdas
+        .iter()
+        .map(|&lambda| {
+            let err = kfold_cv_lambda(trace, tau_rise, tau_decay, lambda, fs, k);
+            (lambda, err)
+        })
+        .filter(|(_, err)| !err.is_nan())
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(f64::NAN, |(lambda, _)| lambda)
+}
+
+/// Replace `x[lo..hi]` with a straight line between `x[lo - 1]` and `x[hi]`
+/// (held flat at the edge that exists when the gap touches an end).
+fn interpolate_gap(x: &mut [f32], lo: usize, hi: usize) {
+    let left = if lo > 0 { Some(x[lo - 1]) } else { None };
+    let right = x.get(hi).copied();
+    let (a, b) = match (left, right) {
+        (Some(a), Some(b)) => (a, b),
+        (Some(a), None) => (a, a),
+        (None, Some(b)) => (b, b),
+        (None, None) => (0.0, 0.0),
+    };
+    let span = (hi - lo + 1) as f32;
+    for (j, v) in x[lo..hi].iter_mut().enumerate() {
+        let t = (j + 1) as f32 / span;
+        *v = a + (b - a) * t;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::lcg_noise;
+
+    #[test]
+    fn spike_triggered_average_recovers_kernel() {
+        let kernel = [0.0_f32, 1.0, 0.6, 0.3];
+        let mut s = vec![0.0_f32; 60];
+        for &t in &[1, 10, 25, 40, 58] {
+            s[t] = 1.0;
+        }
+        s[33] = 0.2; // below min_count
+        let mut trace = vec![0.0_f32; 60];
+        for t in 0..60 {
+            for (k, &h) in kernel.iter().enumerate() {
+                if t >= k {
+                    trace[t] += h * s[t - k];
+                }
+            }
+        }
+
+        // Spikes at 1 and 58 run off the edges; 10, 25, 40 are kept.
+        let (mean, sem) = spike_triggered_average(&trace, &s, 2, 3, 0.5);
+        assert_eq!(mean.len(), 6);
+        for (j, &m) in mean.iter().enumerate() {
+            let expected = if j >= 2 { kernel[j - 2] } else { 0.0 };
+            assert!((m - expected).abs() < 1e-6, "lag {j}: {m}");
+            assert!(sem[j].abs() < 1e-6);
+        }
+
+        // Count-weighted: snippets around 3 (count 1) and 7 (count 2).
+        let x: Vec<f32> = (0..12).map(|t| ((t * t) % 7) as f32).collect();
+        let mut counts = vec![0.0_f32; 12];
+        counts[3] = 1.0;
+        counts[7] = 2.0;
+        let cov = spike_triggered_covariance(&x, &counts, 1, 1);
+        let (a, b) = (&x[2..=4], &x[6..=8]);
+        let mean: Vec<f64> = (0..3).map(|i| (a[i] + 2.0 * b[i]) as f64 / 3.0).collect();
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = ((a[i] as f64 - mean[i]) * (a[j] as f64 - mean[j])
+                    + 2.0 * (b[i] as f64 - mean[i]) * (b[j] as f64 - mean[j]))
+                    / 2.0;
+                assert!((cov[i * 3 + j] - expected).abs() < 1e-9);
+            }
+        }
+        assert!(spike_triggered_covariance(&trace, &[0.0; 60], 2, 3)
+            .iter()
+            .all(|c| c.is_nan()));
+    }
+
+    #[test]
+    fn pair_correlations_and_pci() {
+        let a = vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        let b = a.clone();
+        let c: Vec<f32> = a.iter().map(|v| 1.0 - v).collect();
+        let silent = vec![0.0; 8];
+
+        let r = cell_pair_correlations(&[a.clone(), b.clone(), c.clone()], 1);
+        assert_eq!(r.len(), 3);
+        assert!((r[0] - 1.0).abs() < 1e-12); // (a, b)
+        assert!((r[1] + 1.0).abs() < 1e-12); // (a, c)
+        assert!((r[2] + 1.0).abs() < 1e-12); // (b, c)
+        assert!(
+            (pairwise_correlation_index(&[a.clone(), b.clone(), c], 1) + 1.0 / 3.0).abs() < 1e-12
+        );
+
+        // Silent cells give undefined pairs, which the index skips.
+        let r = cell_pair_correlations(&[a.clone(), silent.clone(), b.clone()], 1);
+        assert!(r[0].is_nan() && r[2].is_nan());
+        assert!((pairwise_correlation_index(&[a.clone(), silent, b], 1) - 1.0).abs() < 1e-12);
+
+        // In 3-frame bins every bin holds one spike (the partial bin is dropped).
+        assert!(cell_pair_correlations(&[a.clone(), a.clone()], 3)[0].is_nan());
+        assert!(pairwise_correlation_index(&[a], 1).is_nan());
+        assert!(pairwise_correlation_index(&[], 1).is_nan());
+    }
+
+    #[test]
+    fn normalisations() {
+        let trace = [1.0_f32, 2.0, 3.0, 4.0, 100.0];
+        let z = zscore(&trace).unwrap();
+        let mean: f32 = z.iter().sum::<f32>() / 5.0;
+        let var: f32 = z.iter().map(|v| v * v).sum::<f32>() / 5.0;
+        assert!(mean.abs() < 1e-6 && (var - 1.0).abs() < 1e-5);
+        assert_eq!(zscore(&[3.0; 4]).unwrap(), vec![0.0; 4]);
+
+        // Median 3, MAD 1: the outlier barely moves the others.
+        let r = robust_zscore(&trace).unwrap();
+        assert!((r[0] + 2.0 / 1.4826).abs() < 1e-5);
+        assert_eq!(r[2], 0.0);
+        assert!((r[4] - 97.0 / 1.4826).abs() < 1e-3);
+
+        let dff = normalize_dff(&[3.0, 2.0, 1.0], &[2.0, 2.0, 0.0]).unwrap();
+        assert_eq!(&dff[..2], &[0.5, 0.0]);
+        assert!((dff[2] - 1e6).abs() < 1.0);
+
+        assert!(zscore(&[]).is_err());
+        assert!(robust_zscore(&[]).is_err());
+        assert!(normalize_dff(&[], &[]).is_err());
+        assert_eq!(
+            normalize_dff(&trace, &[1.0; 4]),
+            Err(CalabError::DimensionMismatch {
+                expected: 5,
+                got: 4
+            })
+        );
+    }
+
+    #[test]
+    fn trace_snr_of_clean_fit() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 900;
+        let mut s_counts = vec![0.0_f32; n];
+        for t in (30..n).step_by(20) {
+            s_counts[t] = 1.0;
+        }
+        let mut reconv = vec![0.0_f32; n];
+        banded.convolve_forward(&s_counts, &mut reconv);
+        let mut state = 5_u64;
+        let trace: Vec<f32> = reconv
+            .iter()
+            .map(|&r| 10.0 * r + 1.0 + 0.1 * crate::indeca::lcg_gaussian(&mut state) as f32)
+            .collect();
+
+        let snr = estimate_trace_snr(&trace, &s_counts, 10.0, 1.0, &banded);
+        assert!(snr > 30.0, "snr {snr}");
+        // Ten times the noise amplitude costs 20 dB.
+        let noisy: Vec<f32> = trace
+            .iter()
+            .zip(&reconv)
+            .map(|(&y, &r)| 10.0 * (y - 10.0 * r - 1.0) + 10.0 * r + 1.0)
+            .collect();
+        let drop = snr - estimate_trace_snr(&noisy, &s_counts, 10.0, 1.0, &banded);
+        assert!((drop - 20.0).abs() < 0.1, "drop {drop}");
+        assert!(estimate_trace_snr(&trace, &[0.0; 900], 10.0, 1.0, &banded) == f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn event_aligned_trials_skip_edges() {
+        let trace: Vec<f32> = (0..50).map(|t| t as f32).collect();
+        // 2 is too close to the start, 48 too close to the end.
+        let events = [2, 10, 20, 30, 48];
+        let raster = event_aligned_raster(&trace, &events, 3, 4);
+        assert_eq!(raster.len(), 3);
+        assert_eq!(raster[0], vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0]);
+
+        let (mean, sem) = event_aligned_average(&trace, &events, 3, 4);
+        assert_eq!(mean.len(), 7);
+        assert_eq!(mean[3], 20.0);
+        // Trials are offsets of 10 apart: sample std 10, SEM 10 / sqrt(3).
+        for &e in &sem {
+            assert!((e - 10.0 / 3.0_f32.sqrt()).abs() < 1e-4);
+        }
+
+        let (mean, sem) = event_aligned_average(&trace, &[10], 3, 4);
+        assert_eq!(mean[3], 10.0);
+        assert!(sem.iter().all(|v| v.is_nan()));
+        let (mean, _) = event_aligned_average(&trace, &[1], 3, 4);
+        assert!(mean.iter().all(|v| v.is_nan()));
+        assert!(event_aligned_raster(&trace, &[], 3, 4).is_empty());
+    }
+
+    #[test]
+    fn isis_between_spike_frames() {
+        let mut s = vec![0.0_f32; 100];
+        s[10] = 1.0;
+        s[25] = 2.0;
+        s[40] = 0.4; // below min_count
+        s[70] = 1.0;
+        let isis = spike_isis(&s, 10.0, 0.5);
+        assert_eq!(isis, vec![1.5, 4.5]);
+
+        assert!((isi_mean(&isis) - 3.0).abs() < 1e-12);
+        assert!((isi_cv(&isis) - 0.5).abs() < 1e-12);
+        assert_eq!(isi_cv(&[2.0, 2.0, 2.0]), 0.0);
+
+        s[25] = 0.0;
+        s[70] = 0.0;
+        assert!(spike_isis(&s, 10.0, 0.5).is_empty());
+        assert!(spike_isis(&[], 10.0, 0.5).is_empty());
+        assert!(isi_mean(&[]).is_nan());
+        assert!(isi_cv(&[]).is_nan());
+    }
+
+    #[test]
+    fn histogram_bins_match_numpy_convention() {
+        let isis = [0.0, 0.1, 0.25, 0.5, 0.99, 1.0, 1.5];
+        let (edges, counts) = isi_histogram(&isis, 4, 1.0);
+        assert_eq!(edges, vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+        // 1.0 falls in the closed last bin; 1.5 is out of range.
+        assert_eq!(counts, vec![2, 1, 1, 2]);
+
+        let (edges, counts) = isi_histogram(&isis, 0, 1.0);
+        assert!(edges.is_empty() && counts.is_empty());
+        let (edges, counts) = isi_histogram(&isis, 4, 0.0);
+        assert!(edges.is_empty() && counts.is_empty());
+    }
+
+    #[test]
+    fn bursts_group_close_spikes() {
+        let fs = 20.0;
+        let mut s = vec![0.0_f32; 200];
+        // Burst of 3 (gaps 2 and 4 frames), lone spike, burst of 2 (gap 1).
+        for (t, v) in [
+            (10, 1.0),
+            (12, 2.0),
+            (16, 1.0),
+            (60, 1.0),
+            (120, 1.0),
+            (121, 1.0),
+        ] {
+            s[t] = v;
+        }
+        let bursts = detect_bursts(&s, fs, 0.2, 2);
+        assert_eq!(bursts.len(), 2);
+        assert_eq!(
+            bursts[0],
+            BurstEvent {
+                start_frame: 10,
+                end_frame: 16,
+                n_spikes: 3,
+                total_amplitude: 4.0,
+                peak_rate_hz: 10.0,
+            }
+        );
+        assert_eq!((bursts[1].start_frame, bursts[1].end_frame), (120, 121));
+        assert_eq!(bursts[1].peak_rate_hz, 20.0);
+        assert!((burst_rate(&bursts, 10.0) - 0.2).abs() < 1e-12);
+        assert!(burst_rate(&bursts, 0.0).is_nan());
+
+        // A tighter ISI limit splits the first burst; min 1 keeps every spike.
+        assert_eq!(detect_bursts(&s, fs, 0.1, 2).len(), 2);
+        let singles = detect_bursts(&s, fs, 0.0, 1);
+        assert_eq!(singles.len(), 6);
+        assert!(singles
+            .iter()
+            .all(|b| b.n_spikes == 1 && b.peak_rate_hz == 0.0));
+        assert!(detect_bursts(&[0.0; 10], fs, 0.2, 1).is_empty());
+    }
+
+    #[test]
+    fn cv_rejects_extreme_lambdas() {
+        let kernel = crate::kernel::build_kernel(0.02, 0.4, 30.0);
+        let n = 900;
+        let mut trace = lcg_noise(n, 0.05, 7);
+        for (j, s) in (20..n).step_by(47).enumerate() {
+            let amp = 0.6 + 0.2 * (j % 3) as f32;
+            for (t, &kv) in kernel.iter().enumerate().take(n - s) {
+                trace[s + t] += amp * kv;
+            }
+        }
+
+        // Contiguous held-out folds hold spikes no fit can predict, so the
+        // curve is shallow; it still rejects both an empty fit and one that
+        // chases the noise.
+        let errs: Vec<f64> = [50.0, 0.3, 0.001]
+            .iter()
+            .map(|&l| kfold_cv_lambda(&trace, 0.02, 0.4, l, 30.0, 5))
+            .collect();
+        assert!(errs[1] < errs[0] && errs[1] < errs[2], "cv errors {errs:?}");
+        assert_eq!(
+            select_lambda_cv(&trace, 0.02, 0.4, 30.0, &[50.0, 0.3, 0.001], 5),
+            0.3
+        );
+
+        // k is capped so folds span a kernel length; tiny inputs give NaN.
+        assert!(kfold_cv_lambda(&trace[..200], 0.02, 0.4, 0.05, 30.0, 50).is_finite());
+        assert!(kfold_cv_lambda(&[1.0], 0.02, 0.4, 0.05, 30.0, 5).is_nan());
+        assert!(select_lambda_cv(&trace, 0.02, 0.4, 30.0, &[], 5).is_nan());
+    }
+
+    #[test]
+    fn gap_interpolation_joins_neighbours() {
+        let mut x = [0.0_f32, 9.0, 9.0, 9.0, 4.0];
+        interpolate_gap(&mut x, 1, 4);
+        assert_eq!(x, [0.0, 1.0, 2.0, 3.0, 4.0]);
+        let mut x = [9.0_f32, 9.0, 2.0];
+        interpolate_gap(&mut x, 0, 2);
+        assert_eq!(x, [2.0, 2.0, 2.0]);
+    }
+}
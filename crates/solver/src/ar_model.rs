@@ -0,0 +1,281 @@
+/// General AR(p) convolution engine for indicators with more than two
+/// exponential components.
+///
+/// The model c[t] = a1*c[t-1] + ... + ap*c[t-p] + s[t-1] generalizes
+/// `BandedAR2`: forward and adjoint are O(T·p) recursions with the same
+/// one-sample source delay and the same impulse-peak normalization, so a
+/// two-component model reproduces `BandedAR2` (and `build_kernel`) sample for
+/// sample.
+///
+/// `from_time_constants` maps each time constant tau_i to a root
+/// r_i = exp(-dt/tau_i) and expands prod_i (1 - r_i z^-1) into the AR
+/// coefficients. By partial fractions, the impulse response is then
+/// h[n+1] = sum_i A_i r_i^n with A_i = r_i^(p-1) / prod_{j != i} (r_i - r_j)
+/// (for distinct roots), a signed mixture of exponentials — for two taus the
+/// familiar decay-minus-rise bi-exponential.
+#[derive(Clone, Debug)]
+pub struct ARpModel {
+    coeffs: Vec<f64>,     // a1..ap
+    coeffs_f32: Vec<f32>, // a1..ap, as used by the f32 recursions
+    impulse_peak: f64,    // peak of the raw impulse response (for normalization)
+    lipschitz: f64,       // Lipschitz constant of the peak-normalized operator
+    kernel_len: usize,    // samples until the slowest component decays below 1e-6
+    scale: f64,           // output scale relative to unit peak (1.0 = peak norm)
+}
+
+impl ARpModel {
+    /// Build the AR(p) model whose characteristic roots are exp(-1/(fs*tau))
+    /// for each of the `p` time constants (seconds, in any order). A
+    /// non-positive tau contributes a zero root, like `tau_rise = 0.0` does
+    /// for `build_kernel`; an empty slice gives a pure one-sample delay.
+    pub fn from_time_constants(taus: &[f64], fs: f64) -> ARpModel {
+        let dt = 1.0 / fs;
+        let roots: Vec<f64> = taus
+            .iter()
+            .map(|&tau| if tau > 0.0 { (-dt / tau).exp() } else { 0.0 })
+            .collect();
+
+        // prod_i (1 - r_i z^-1) = 1 - a1 z^-1 - ... - ap z^-p
+        let mut poly = vec![1.0_f64];
+        for &r in &roots {
+            let mut next = poly.clone();
+            next.push(0.0);
+            for (k, &c) in poly.iter().enumerate() {
+                next[k + 1] -= r * c;
+            }
+            poly = next;
+        }
+        let coeffs: Vec<f64> = poly[1..].iter().map(|&c| -c).collect();
+
+        // Kernel length: until decay drops below 1e-6 of peak (as build_kernel)
+        let tau_max = taus.iter().cloned().fold(0.0_f64, f64::max);
+        let kernel_len = (((-1e-6_f64.ln()) * tau_max / dt).ceil() as usize).max(2);
+
+        let mut model = ARpModel {
+            coeffs_f32: coeffs.iter().map(|&a| a as f32).collect(),
+            coeffs,
+            impulse_peak: 1.0,
+            lipschitz: 1.0,
+            kernel_len,
+            scale: 1.0,
+        };
+        let raw = model.raw_impulse_response();
+        model.impulse_peak = raw.iter().cloned().fold(0.0_f64, f64::max).max(1e-12);
+        model.lipschitz =
+            compute_arp_lipschitz(&model.coeffs) / (model.impulse_peak * model.impulse_peak);
+        model
+    }
+
+    /// Rebuild for new time constants, keeping the output scale.
+    pub(crate) fn update(&mut self, taus: &[f64], fs: f64) {
+        let scale = self.scale;
+        *self = Self::from_time_constants(taus, fs);
+        self.scale = scale;
+    }
+
+    /// Model order p.
+    pub fn order(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    /// AR coefficients a1..ap.
+    pub fn coeffs(&self) -> &[f64] {
+        &self.coeffs
+    }
+
+    /// Unit-peak impulse response (h[0] = 0), long enough for the slowest
+    /// component to decay below 1e-6 of the peak.
+    pub fn impulse_response(&self) -> Vec<f32> {
+        self.raw_impulse_response()
+            .iter()
+            .map(|&v| (v / self.impulse_peak) as f32)
+            .collect()
+    }
+
+    /// Raw (unnormalized) impulse response in f64, one-sample delayed.
+    fn raw_impulse_response(&self) -> Vec<f64> {
+        let n = self.kernel_len;
+        let mut h = vec![0.0_f64; n];
+        h[1] = 1.0;
+        for t in 2..n {
+            h[t] = self
+                .coeffs
+                .iter()
+                .enumerate()
+                .take(t)
+                .map(|(k, &a)| a * h[t - 1 - k])
+                .sum();
+        }
+        h
+    }
+
+    /// Scale the impulse response to `scale` times its unit-peak shape.
+    pub(crate) fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// Return the raw impulse response peak (`KernelNorm::None` scale).
+    pub(crate) fn impulse_peak(&self) -> f64 {
+        self.impulse_peak
+    }
+
+    /// Return the Lipschitz constant of the normalized (and scaled) operator.
+    pub(crate) fn lipschitz(&self) -> f64 {
+        self.lipschitz * self.scale * self.scale
+    }
+
+    /// Forward convolution: s -> normalized AR(p) output, O(T·p).
+    ///
+    /// `output[t] = sum_k a_k*output[t-k] + source[t-1]*scale/peak`, with
+    /// `output[0] = 0` and terms before the start of the trace absent.
+    pub(crate) fn convolve_forward(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+
+        output[0] = 0.0;
+        for t in 1..n {
+            let mut acc = source[t - 1] * inv_peak;
+            for (k, &a) in self.coeffs_f32.iter().enumerate().take(t) {
+                acc += a * output[t - 1 - k];
+            }
+            output[t] = acc;
+        }
+    }
+
+    /// Adjoint convolution: normalized adjoint, O(T·p).
+    ///
+    /// Backward recursion `b[t] = source[t]*scale/peak + sum_k a_k*b[t+k]`,
+    /// stored shifted up one index for the source delay
+    /// (`output[t-1] = b[t]`, `output[n-1] = 0`).
+    pub(crate) fn convolve_adjoint(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+
+        output[n - 1] = 0.0;
+        for t in (1..n).rev() {
+            let mut acc = source[t] * inv_peak;
+            for (k, &a) in self.coeffs_f32.iter().enumerate() {
+                // b[t + k + 1] lives at output[t + k]
+                if t + k + 1 >= n {
+                    break;
+                }
+                acc += a * output[t + k];
+            }
+            output[t - 1] = acc;
+        }
+    }
+}
+
+/// Lipschitz constant of the raw AR(p) operator: max_w 1/|A(e^{jw})|^2 with
+/// A(z) = 1 - sum_k a_k z^-k, over a dense frequency grid.
+fn compute_arp_lipschitz(coeffs: &[f64]) -> f64 {
+    let n_freqs = 4096;
+    let mut max_power = 0.0_f64;
+
+    for i in 0..=n_freqs {
+        let w = std::f64::consts::PI * (i as f64) / (n_freqs as f64);
+        let mut re = 1.0_f64;
+        let mut im = 0.0_f64;
+        for (k, &a) in coeffs.iter().enumerate() {
+            let (s, c) = (w * (k + 1) as f64).sin_cos();
+            re -= a * c;
+            im += a * s;
+        }
+        let denom_sq = re * re + im * im;
+        if denom_sq > 1e-30 {
+            max_power = max_power.max(1.0 / denom_sq);
+        }
+    }
+
+    max_power.max(1e-10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::banded::{check_adjoint_op, BandedAR2};
+    use crate::kernel::build_kernel;
+
+    #[test]
+    fn two_taus_match_banded_ar2() {
+        let model = ARpModel::from_time_constants(&[0.4, 0.02], 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        assert_eq!(model.order(), 2);
+        assert!((model.lipschitz() - banded.lipschitz()).abs() < 1e-9 * banded.lipschitz());
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let h = model.impulse_response();
+        assert_eq!(h.len(), kernel.len());
+        for (i, (&a, &b)) in h.iter().zip(&kernel).enumerate() {
+            assert!((a - b).abs() < 1e-5, "sample {i}: AR(p) {a} vs kernel {b}");
+        }
+
+        let x: Vec<f32> = (0..300).map(|i| ((i * 7) % 13) as f32 * 0.1).collect();
+        let (mut a, mut b) = (vec![0.0_f32; 300], vec![0.0_f32; 300]);
+        model.convolve_forward(&x, &mut a);
+        banded.convolve_forward(&x, &mut b);
+        for (t, (&u, &v)) in a.iter().zip(&b).enumerate() {
+            assert!((u - v).abs() < 1e-4 * v.abs().max(1.0), "sample {t}");
+        }
+    }
+
+    #[test]
+    fn impulse_response_matches_partial_fractions() {
+        let taus = [0.02, 0.3, 1.5];
+        let fs = 30.0;
+        let model = ARpModel::from_time_constants(&taus, fs);
+        assert_eq!(model.order(), 3);
+
+        let roots: Vec<f64> = taus.iter().map(|&tau| (-1.0 / (fs * tau)).exp()).collect();
+        let weights: Vec<f64> = roots
+            .iter()
+            .enumerate()
+            .map(|(i, &ri)| {
+                let denom: f64 = roots
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, &rj)| ri - rj)
+                    .product();
+                ri.powi(roots.len() as i32 - 1) / denom
+            })
+            .collect();
+
+        let h = model.impulse_response();
+        assert_eq!(h[0], 0.0);
+        let peak = h.iter().cloned().fold(0.0_f32, f32::max);
+        assert!((peak - 1.0).abs() < 1e-6);
+        for n in 1..h.len() {
+            let closed: f64 = roots
+                .iter()
+                .zip(&weights)
+                .map(|(&r, &a)| a * r.powi(n as i32 - 1))
+                .sum::<f64>()
+                / model.impulse_peak;
+            assert!((h[n] as f64 - closed).abs() < 1e-5, "sample {n}");
+        }
+    }
+
+    #[test]
+    fn adjoint_identity() {
+        for taus in [&[0.4][..], &[0.05, 0.3, 1.5], &[0.01, 0.1, 0.5, 2.0]] {
+            let model = ARpModel::from_time_constants(taus, 30.0);
+            let err = check_adjoint_op(
+                |x, out| model.convolve_forward(x, out),
+                |y, out| model.convolve_adjoint(y, out),
+                200,
+                1e-3,
+                4,
+            );
+            assert!(err.is_ok(), "taus {taus:?}: {err:?}");
+        }
+    }
+}
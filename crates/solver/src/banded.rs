@@ -1,4 +1,5 @@
 use crate::kernel::clamp_tau_rise;
+use crate::CalabError;
 
 /// Banded AR(2) convolution engine — O(T) replacement for FFT-based O(T log T).
 ///
@@ -22,16 +23,69 @@ use crate::kernel::clamp_tau_rise;
 /// peak of 1.0 in the output regardless of sampling rate. The one-sample delay
 /// leaves the peak *value* unchanged (it is the max over the same geometric
 /// sequence), so the impulse-peak and Lipschitz calculations are unaffected.
+///
+/// `set_scale` multiplies the peak-normalized response by a constant, which is
+/// how the solver applies a non-peak `KernelNorm`.
+#[derive(Clone, Copy)]
 pub(crate) struct BandedAR2 {
     g1: f64,           // d + r (sum of AR2 roots)
     g2: f64,           // -(d * r) (negative product of AR2 roots)
+    d: f64,            // decay root exp(-dt / tau_decay)
+    r: f64,            // rise root exp(-dt / tau_rise)
     impulse_peak: f64, // peak of raw AR2 impulse response (for normalization)
-    lipschitz: f64,    // Lipschitz constant of the normalized operator
+    lipschitz: f64,    // Lipschitz constant of the peak-normalized operator
+    scale: f64,        // output scale relative to unit peak (1.0 = peak norm)
+}
+
+/// Relative level (of the peak) below which `BandedAR2::impulse_response`
+/// truncates the decaying tail; matches `build_kernel`.
+const IMPULSE_TAIL_TOL: f64 = 1e-6;
+
+/// Largest event density (events per sample) for which
+/// `BandedAR2::convolve_forward_sparse` skips the dense recursion.
+pub(crate) const SPARSE_MAX_DENSITY: f64 = 0.05;
+
+/// Largest per-event work (events × impulse length) per output sample for
+/// which the sparse path wins: each event is a vectorizable pass, while the
+/// dense recursion is one dependent multiply-add chain (measured crossover
+/// near 20 at 30 Hz).
+const SPARSE_MAX_WORK_PER_SAMPLE: usize = 16;
+
+/// Recursion state carried between `BandedAR2::convolve_forward_block` calls:
+/// the last two output samples and, for the one-sample source delay, the last
+/// (peak-normalized) source sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct AR2State {
+    pub(crate) c_prev1: f32,
+    pub(crate) c_prev2: f32,
+    pub(crate) s_prev: f32,
+}
+
+impl AR2State {
+    /// State before any sample: all zeros, as at the start of a trace.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
 }
 
 impl BandedAR2 {
     /// Create a new BandedAR2 with the given tau parameters.
-    pub(crate) fn new(tau_rise: f64, tau_decay: f64, fs: f64) -> Self {
+    ///
+    /// Fails with `NumericalFailure` when the AR(2) roots are not inside the
+    /// unit circle (a non-positive or non-finite tau or `fs`), where the
+    /// recursion would diverge.
+    pub(crate) fn new(tau_rise: f64, tau_decay: f64, fs: f64) -> Result<Self, CalabError> {
+        let banded = Self::unchecked(tau_rise, tau_decay, fs);
+        if !banded.is_stable() {
+            return Err(CalabError::NumericalFailure(
+                "AR2 roots outside unit circle".to_string(),
+            ));
+        }
+        Ok(banded)
+    }
+
+    /// Build the engine without the stability check.
+    fn unchecked(tau_rise: f64, tau_decay: f64, fs: f64) -> Self {
         let tau_rise = clamp_tau_rise(tau_rise, tau_decay);
         let dt = 1.0 / fs;
         let d = (-dt / tau_decay).exp();
@@ -44,14 +98,50 @@ impl BandedAR2 {
         BandedAR2 {
             g1,
             g2,
+            d,
+            r,
             impulse_peak,
             lipschitz,
+            scale: 1.0,
         }
     }
 
-    /// Recompute coefficients after parameter change.
-    pub(crate) fn update(&mut self, tau_rise: f64, tau_decay: f64, fs: f64) {
-        *self = Self::new(tau_rise, tau_decay, fs);
+    /// Fit the AR(2) model to an empirical autocorrelation `acf` (lags 0, 1,
+    /// 2, ...; only the first three are used) via `ar2_taus_from_acf`.
+    pub(crate) fn fit_from_acf(acf: &[f64], fs: f64) -> Result<Self, CalabError> {
+        let (tau_rise, tau_decay) = ar2_taus_from_acf(acf, fs)?;
+        Self::new(tau_rise, tau_decay, fs)
+    }
+
+    /// Recompute coefficients after parameter change. Keeps the output scale.
+    /// On an unstable parameter set the engine is left unchanged.
+    pub(crate) fn update(
+        &mut self,
+        tau_rise: f64,
+        tau_decay: f64,
+        fs: f64,
+    ) -> Result<(), CalabError> {
+        let scale = self.scale;
+        *self = Self::new(tau_rise, tau_decay, fs)?;
+        self.scale = scale;
+        Ok(())
+    }
+
+    /// Whether both AR(2) roots lie strictly inside the unit circle.
+    pub(crate) fn is_stable(&self) -> bool {
+        self.d.abs() < 1.0 && self.r.abs() < 1.0
+    }
+
+    /// Distance of the larger root from the unit circle,
+    /// `min(1 - |d|, 1 - |r|)`; positive exactly when the engine is stable.
+    pub(crate) fn stability_margin(&self) -> f64 {
+        (1.0 - self.d.abs()).min(1.0 - self.r.abs())
+    }
+
+    /// Scale the impulse response to `scale` times its unit-peak shape (the
+    /// divisor becomes `impulse_peak / scale`).
+    pub(crate) fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
     }
 
     /// Forward convolution: s -> normalized AR2 output, O(T).
@@ -69,7 +159,7 @@ impl BandedAR2 {
 
         let g1 = self.g1 as f32;
         let g2 = self.g2 as f32;
-        let inv_peak = (1.0 / self.impulse_peak) as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
 
         // Delayed source: output[0] is always 0 (no s[-1] term); the g2*output[-1]
         // term at t=1 is likewise absent.
@@ -82,6 +172,219 @@ impl BandedAR2 {
         }
     }
 
+    /// Causal, streaming form of `convolve_forward`: processes the next block
+    /// of `source` and writes as many samples of `output`, carrying the
+    /// recursion across calls in `state`. Feeding a trace block by block from
+    /// `initial_state()` reproduces `convolve_forward` on the whole trace
+    /// exactly. `output` must be at least as long as `source`.
+    pub(crate) fn convolve_forward_block(
+        &self,
+        source: &[f32],
+        output: &mut [f32],
+        state: &mut AR2State,
+    ) {
+        let g1 = self.g1 as f32;
+        let g2 = self.g2 as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+
+        let AR2State {
+            mut c_prev1,
+            mut c_prev2,
+            mut s_prev,
+        } = *state;
+        for (out, &s) in output.iter_mut().zip(source) {
+            let c = g1 * c_prev1 + g2 * c_prev2 + s_prev;
+            *out = c;
+            c_prev2 = c_prev1;
+            c_prev1 = c;
+            s_prev = s * inv_peak;
+        }
+        *state = AR2State {
+            c_prev1,
+            c_prev2,
+            s_prev,
+        };
+    }
+
+    /// Normalized (and scaled) impulse response, `h[0] = 0` for the source
+    /// delay, truncated once past the peak it falls below
+    /// `IMPULSE_TAIL_TOL` of the peak, and at `max_len` samples.
+    pub(crate) fn impulse_response(&self, max_len: usize) -> Vec<f32> {
+        let g1 = self.g1 as f32;
+        let g2 = self.g2 as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+        let tail = (IMPULSE_TAIL_TOL * self.scale) as f32;
+
+        let mut h = Vec::with_capacity(max_len.min(1024));
+        let (mut c_prev1, mut c_prev2) = (0.0_f32, 0.0_f32);
+        let mut peak = 0.0_f32;
+        for k in 0..max_len {
+            let c = if k == 0 {
+                0.0
+            } else if k == 1 {
+                inv_peak
+            } else {
+                g1 * c_prev1 + g2 * c_prev2
+            };
+            if k > 1 && c < peak && c.abs() < tail {
+                break;
+            }
+            peak = peak.max(c);
+            h.push(c);
+            c_prev2 = c_prev1;
+            c_prev1 = c;
+        }
+        h
+    }
+
+    /// Forward convolution of a sparse source given as `(position, amplitude)`
+    /// events into `output[..n]`, adding the truncated impulse response
+    /// (`impulse_response`) once per event instead of running the recursion
+    /// over every sample. Matches `convolve_forward` on the dense source up to
+    /// the truncated tail (below 1e-6 of peak). Falls back to the dense
+    /// recursion when events exceed `SPARSE_MAX_DENSITY` of `n` or the
+    /// per-event work exceeds `SPARSE_MAX_WORK_PER_SAMPLE` per sample (long
+    /// impulse responses at high rates). Events at or past `n` are ignored.
+    pub(crate) fn convolve_forward_sparse(
+        &self,
+        spikes: &[(usize, f32)],
+        n: usize,
+        output: &mut [f32],
+    ) {
+        let output = &mut output[..n];
+        if spikes.len() as f64 > SPARSE_MAX_DENSITY * n as f64 {
+            self.convolve_sparse_dense(spikes, output);
+            return;
+        }
+        let h = self.impulse_response(n);
+        if spikes.len() * h.len() > SPARSE_MAX_WORK_PER_SAMPLE * n {
+            self.convolve_sparse_dense(spikes, output);
+            return;
+        }
+        output.fill(0.0);
+        for &(t, a) in spikes.iter().filter(|&&(t, _)| t < n) {
+            for (out, &hk) in output[t..].iter_mut().zip(&h) {
+                *out += a * hk;
+            }
+        }
+    }
+
+    /// Dense fallback of `convolve_forward_sparse`.
+    fn convolve_sparse_dense(&self, spikes: &[(usize, f32)], output: &mut [f32]) {
+        let n = output.len();
+        let mut source = vec![0.0_f32; n];
+        for &(t, a) in spikes.iter().filter(|&&(t, _)| t < n) {
+            source[t] += a;
+        }
+        self.convolve_forward(&source, output);
+    }
+
+    /// Recursion state at the start of a trace, for `convolve_forward_block`.
+    pub(crate) fn initial_state(&self) -> AR2State {
+        AR2State::new()
+    }
+
+    /// Forward convolution of eight equal-length traces at once.
+    ///
+    /// Uses the AVX2 kernel (`convolve_forward_simd8`) when the CPU supports
+    /// AVX2 and FMA (detected at runtime, so a portable x86_64 build still
+    /// takes it), and eight scalar `convolve_forward` calls otherwise.
+    /// Every output must be at least as long as the sources.
+    pub(crate) fn convolve_forward_x8(&self, sources: &[&[f32]; 8], outputs: &mut [&mut [f32]; 8]) {
+        let n = sources[0].len();
+        assert!(
+            sources.iter().all(|s| s.len() == n) && outputs.iter().all(|o| o.len() >= n),
+            "convolve_forward_x8: traces must have equal length"
+        );
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("avx2") && std::arch::is_x86_feature_detected!("fma")
+        {
+            // SAFETY: AVX2 and FMA were detected above; lengths were checked.
+            unsafe { self.convolve_forward_simd8(sources, outputs) };
+            return;
+        }
+        for (source, output) in sources.iter().zip(outputs.iter_mut()) {
+            self.convolve_forward(source, &mut output[..n]);
+        }
+    }
+
+    /// `convolve_forward` on eight traces, one per AVX2 lane.
+    ///
+    /// The AR2 recursion is sequential in time, so the parallelism is across
+    /// traces: each block of eight samples is loaded from every trace and
+    /// transposed so a register holds one time step of all eight, run through
+    /// eight fused multiply-add recursion steps, and transposed back for the
+    /// stores. Results match the scalar path to f32 rounding (FMA rounds once
+    /// where the scalar code rounds twice).
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2 and FMA, all sources must have the same
+    /// length and every output must be at least that long.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,fma")]
+    pub(crate) unsafe fn convolve_forward_simd8(
+        &self,
+        sources: &[&[f32]; 8],
+        outputs: &mut [&mut [f32]; 8],
+    ) {
+        use std::arch::x86_64::*;
+
+        let n = sources[0].len();
+        if n == 0 {
+            return;
+        }
+        let g1 = _mm256_set1_ps(self.g1 as f32);
+        let g2 = _mm256_set1_ps(self.g2 as f32);
+        let inv_peak = _mm256_set1_ps((self.scale / self.impulse_peak) as f32);
+
+        // Lane k carries trace k. output[0] = 0 and output[t] uses source[t-1],
+        // so source sample j produces output j + 1.
+        for output in outputs.iter_mut() {
+            output[0] = 0.0;
+        }
+        let mut prev1 = _mm256_setzero_ps(); // output[t-1]
+        let mut prev2 = _mm256_setzero_ps(); // output[t-2]
+        let mut step = |src: __m256| {
+            let out = _mm256_fmadd_ps(
+                g1,
+                prev1,
+                _mm256_fmadd_ps(g2, prev2, _mm256_mul_ps(src, inv_peak)),
+            );
+            prev2 = prev1;
+            prev1 = out;
+            out
+        };
+
+        let mut j = 0;
+        while j + 8 < n {
+            let mut block = [_mm256_setzero_ps(); 8];
+            for (row, source) in block.iter_mut().zip(sources) {
+                *row = _mm256_loadu_ps(source.as_ptr().add(j));
+            }
+            transpose8(&mut block);
+            for row in block.iter_mut() {
+                *row = step(*row);
+            }
+            transpose8(&mut block);
+            for (row, output) in block.iter().zip(outputs.iter_mut()) {
+                _mm256_storeu_ps(output.as_mut_ptr().add(j + 1), *row);
+            }
+            j += 8;
+        }
+        let mut lanes = [0.0_f32; 8];
+        while j + 1 < n {
+            for (lane, source) in lanes.iter_mut().zip(sources) {
+                *lane = source[j];
+            }
+            _mm256_storeu_ps(lanes.as_mut_ptr(), step(_mm256_loadu_ps(lanes.as_ptr())));
+            for (lane, output) in lanes.iter().zip(outputs.iter_mut()) {
+                output[j + 1] = *lane;
+            }
+            j += 1;
+        }
+    }
+
     /// Adjoint convolution: normalized adjoint, O(T).
     ///
     /// Pre-scales input by 1/peak so the backward AR2 recursion directly
@@ -100,7 +403,7 @@ impl BandedAR2 {
 
         let g1 = self.g1 as f32;
         let g2 = self.g2 as f32;
-        let inv_peak = (1.0 / self.impulse_peak) as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
 
         output[n - 1] = 0.0;
         let mut a_tp1 = 0.0_f32; // a[t+1]
@@ -115,18 +418,298 @@ impl BandedAR2 {
         }
     }
 
-    /// Return the cached Lipschitz constant (of the normalized operator).
+    /// Return the Lipschitz constant of the normalized (and scaled) operator.
     pub(crate) fn lipschitz(&self) -> f64 {
-        self.lipschitz
+        self.lipschitz * self.scale * self.scale
+    }
+
+    /// DC gain of the normalized operator: sum of its impulse response,
+    /// scale / ((1 - g1 - g2) * peak). The one-sample delay leaves it unchanged.
+    pub(crate) fn dc_gain(&self) -> f64 {
+        self.scale / ((1.0 - self.g1 - self.g2) * self.impulse_peak)
     }
 
-    /// Return the raw AR2 impulse response peak (for diagnostics).
-    #[allow(dead_code)]
+    /// Raw power response `|H(e^{j2πf/fs})|^2` of the AR(2) recursion at each
+    /// frequency in Hz, as in the Lipschitz estimate (before peak
+    /// normalization and scale; at DC it is `(1 / (1 - g1 - g2))^2`).
+    pub(crate) fn frequency_response(&self, freqs_hz: &[f64], fs: f64) -> Vec<f64> {
+        freqs_hz
+            .iter()
+            .map(|&f| ar2_power(self.g1, self.g2, 2.0 * std::f64::consts::PI * f / fs))
+            .collect()
+    }
+
+    /// The -3 dB frequency in Hz: where the power response falls to half its
+    /// DC value. With both roots in (0, 1) the response decreases
+    /// monotonically up to Nyquist, so this is found by bisection; returns
+    /// `fs / 2` if the response never drops that far.
+    pub(crate) fn cutoff_frequency_hz(&self, fs: f64) -> f64 {
+        let half = 0.5 * ar2_power(self.g1, self.g2, 0.0);
+        let (mut lo, mut hi) = (0.0_f64, std::f64::consts::PI);
+        if ar2_power(self.g1, self.g2, hi) >= half {
+            return fs / 2.0;
+        }
+        for _ in 0..60 {
+            let mid = 0.5 * (lo + hi);
+            if ar2_power(self.g1, self.g2, mid) > half {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        0.5 * (lo + hi) * fs / (2.0 * std::f64::consts::PI)
+    }
+
+    /// Return the raw AR2 impulse response peak (`KernelNorm::None` scale).
     pub(crate) fn impulse_peak(&self) -> f64 {
         self.impulse_peak
     }
 }
 
+/// In-place 8×8 transpose of eight AVX registers (rows ↔ lanes).
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn transpose8(r: &mut [std::arch::x86_64::__m256; 8]) {
+    use std::arch::x86_64::*;
+
+    let t0 = _mm256_unpacklo_ps(r[0], r[1]);
+    let t1 = _mm256_unpackhi_ps(r[0], r[1]);
+    let t2 = _mm256_unpacklo_ps(r[2], r[3]);
+    let t3 = _mm256_unpackhi_ps(r[2], r[3]);
+    let t4 = _mm256_unpacklo_ps(r[4], r[5]);
+    let t5 = _mm256_unpackhi_ps(r[4], r[5]);
+    let t6 = _mm256_unpacklo_ps(r[6], r[7]);
+    let t7 = _mm256_unpackhi_ps(r[6], r[7]);
+    let s0 = _mm256_shuffle_ps::<0x44>(t0, t2);
+    let s1 = _mm256_shuffle_ps::<0xEE>(t0, t2);
+    let s2 = _mm256_shuffle_ps::<0x44>(t1, t3);
+    let s3 = _mm256_shuffle_ps::<0xEE>(t1, t3);
+    let s4 = _mm256_shuffle_ps::<0x44>(t4, t6);
+    let s5 = _mm256_shuffle_ps::<0xEE>(t4, t6);
+    let s6 = _mm256_shuffle_ps::<0x44>(t5, t7);
+    let s7 = _mm256_shuffle_ps::<0xEE>(t5, t7);
+    r[0] = _mm256_permute2f128_ps::<0x20>(s0, s4);
+    r[1] = _mm256_permute2f128_ps::<0x20>(s1, s5);
+    r[2] = _mm256_permute2f128_ps::<0x20>(s2, s6);
+    r[3] = _mm256_permute2f128_ps::<0x20>(s3, s7);
+    r[4] = _mm256_permute2f128_ps::<0x31>(s0, s4);
+    r[5] = _mm256_permute2f128_ps::<0x31>(s1, s5);
+    r[6] = _mm256_permute2f128_ps::<0x31>(s2, s6);
+    r[7] = _mm256_permute2f128_ps::<0x31>(s3, s7);
+}
+
+/// Banded AR(1) convolution engine — single-exponential kernel, one tap.
+///
+/// The AR(1) model c[t] = g*c[t-1] + s[t-1] with g = exp(-dt/tau_decay) is the
+/// tau_rise → 0 limit of `BandedAR2`: the calcium jumps on the sample after the
+/// spike and decays geometrically. It carries the same one-sample source delay
+/// as `BandedAR2`, so its impulse response matches `build_kernel(0.0, ..)`
+/// sample-for-sample (h[0] = 0, h[n] = g^(n-1)).
+///
+/// Normalization follows `BandedAR2`: forward and adjoint are pre-scaled by
+/// 1/impulse_peak. For a pure decay the raw peak is the impulse itself (1.0),
+/// so the scaling is the identity but kept for symmetry with the AR(2) path.
+pub(crate) struct BandedAR1 {
+    g: f64,            // exp(-dt / tau_decay)
+    impulse_peak: f64, // peak of raw AR1 impulse response (for normalization)
+    lipschitz: f64,    // Lipschitz constant of the peak-normalized operator
+    scale: f64,        // output scale relative to unit peak (1.0 = peak norm)
+}
+
+impl BandedAR1 {
+    /// Create a new BandedAR1 for the given decay time constant.
+    pub(crate) fn new(tau_decay: f64, fs: f64) -> Self {
+        let g = (-1.0 / (fs * tau_decay)).exp();
+        let impulse_peak = compute_impulse_peak(g, 0.0, tau_decay, fs);
+        let lipschitz = compute_banded_lipschitz(g, 0.0) / (impulse_peak * impulse_peak);
+        BandedAR1 {
+            g,
+            impulse_peak,
+            lipschitz,
+            scale: 1.0,
+        }
+    }
+
+    /// Recompute coefficients after parameter change. Keeps the output scale.
+    pub(crate) fn update(&mut self, tau_decay: f64, fs: f64) {
+        let scale = self.scale;
+        *self = Self::new(tau_decay, fs);
+        self.scale = scale;
+    }
+
+    /// Scale the impulse response to `scale` times its unit-peak shape.
+    pub(crate) fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// Forward convolution: s -> normalized AR1 output, O(T).
+    pub(crate) fn convolve_forward(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+
+        let g = self.g as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+
+        output[0] = 0.0;
+        for t in 1..n {
+            output[t] = g * output[t - 1] + source[t - 1] * inv_peak;
+        }
+    }
+
+    /// Adjoint convolution: normalized adjoint, O(T).
+    ///
+    /// Backward recursion `a[t] = s[t]/peak + g*a[t+1]`, shifted up one index
+    /// for the source delay (`output[t-1] = a[t]`, `output[n-1] = 0`).
+    pub(crate) fn convolve_adjoint(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+
+        let g = self.g as f32;
+        let inv_peak = (self.scale / self.impulse_peak) as f32;
+
+        output[n - 1] = 0.0;
+        let mut a_tp1 = 0.0_f32; // a[t+1]
+        for t in (1..n).rev() {
+            let a_t = source[t] * inv_peak + g * a_tp1;
+            output[t - 1] = a_t;
+            a_tp1 = a_t;
+        }
+    }
+
+    /// Return the Lipschitz constant of the normalized (and scaled) operator.
+    pub(crate) fn lipschitz(&self) -> f64 {
+        self.lipschitz * self.scale * self.scale
+    }
+}
+
+/// Dot-product test of a forward/adjoint operator pair.
+///
+/// Draws `n_pairs` deterministic vector pairs (x, y) of length `n` from an
+/// LCG seeded by `n` and measures `|<Kx,y> - <x,K^T y>| / (|<Kx,y>| + 1e-20)`
+/// for each. Returns the largest relative error, or an error naming the worst
+/// pair if it exceeds `tol`. Intended for validating a new convolution mode.
+pub fn check_adjoint_op<F, G>(
+    forward: F,
+    adjoint: G,
+    n: usize,
+    tol: f64,
+    n_pairs: usize,
+) -> Result<f64, String>
+where
+    F: Fn(&[f32], &mut [f32]),
+    G: Fn(&[f32], &mut [f32]),
+{
+    let mut state = n as u64;
+    let mut draw = |v: &mut [f32]| {
+        for e in v.iter_mut() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *e = (((state >> 33) as f64) / ((1u64 << 31) as f64) - 1.0) as f32;
+        }
+    };
+    let dot =
+        |a: &[f32], b: &[f32]| -> f64 { a.iter().zip(b).map(|(&p, &q)| p as f64 * q as f64).sum() };
+
+    let (mut x, mut y) = (vec![0.0_f32; n], vec![0.0_f32; n]);
+    let (mut kx, mut kty) = (vec![0.0_f32; n], vec![0.0_f32; n]);
+    let mut worst = (0.0_f64, 0, 0.0_f64, 0.0_f64);
+    for pair in 0..n_pairs {
+        draw(&mut x);
+        draw(&mut y);
+        forward(&x, &mut kx);
+        adjoint(&y, &mut kty);
+        let lhs = dot(&kx, &y);
+        let rhs = dot(&x, &kty);
+        let rel_err = (lhs - rhs).abs() / (lhs.abs() + 1e-20);
+        if rel_err > worst.0 {
+            worst = (rel_err, pair, lhs, rhs);
+        }
+    }
+
+    let (rel_err, pair, lhs, rhs) = worst;
+    if rel_err > tol {
+        return Err(format!(
+            "adjoint mismatch on pair {pair} of {n_pairs} (n = {n}): \
+             <Kx,y> = {lhs}, <x,K^T y> = {rhs}, relative error {rel_err:.3e} > tol {tol:.3e}"
+        ));
+    }
+    Ok(rel_err)
+}
+
+/// `(is_stable, stability_margin)` of the AR(2) engine for these taus,
+/// without failing on an unstable set.
+pub(crate) fn ar2_stability(tau_rise: f64, tau_decay: f64, fs: f64) -> (bool, f64) {
+    let banded = BandedAR2::unchecked(tau_rise, tau_decay, fs);
+    (banded.is_stable(), banded.stability_margin())
+}
+
+/// Solve the Yule-Walker equations for an AR(2) process,
+///
+///   [acf[1], acf[2]] = [[acf[0], acf[1]], [acf[1], acf[0]]] * [g1, g2],
+///
+/// and convert the characteristic roots of z^2 - g1*z - g2 to
+/// `(tau_rise, tau_decay)` in seconds. Both roots must be real and in (0, 1)
+/// (a stable, non-oscillating rise-then-decay); a double root, a negative
+/// root or an oscillating fit is rejected with `InvalidParameter`, as are
+/// fewer than three lags and a non-positive `acf[0]` or `fs`.
+pub(crate) fn ar2_taus_from_acf(acf: &[f64], fs: f64) -> Result<(f64, f64), CalabError> {
+    if !(fs.is_finite() && fs > 0.0) {
+        return Err(CalabError::invalid("fs", fs, "must be positive"));
+    }
+    let [r0, r1, r2] = match acf {
+        [r0, r1, r2, ..] => [*r0, *r1, *r2],
+        _ => {
+            return Err(CalabError::invalid(
+                "acf",
+                format!("{} lags", acf.len()),
+                "needs at least lags 0, 1 and 2",
+            ))
+        }
+    };
+    let det = r0 * r0 - r1 * r1;
+    if !(r0.is_finite() && r1.is_finite() && r2.is_finite()) || r0 <= 0.0 || det <= 0.0 {
+        return Err(CalabError::invalid(
+            "acf",
+            format!("[{r0}, {r1}, {r2}]"),
+            "needs acf[0] > |acf[1]| and finite lags",
+        ));
+    }
+    let g1 = r1 * (r0 - r2) / det;
+    let g2 = (r0 * r2 - r1 * r1) / det;
+
+    let disc = g1 * g1 + 4.0 * g2;
+    let unstable = || {
+        CalabError::invalid(
+            "acf",
+            format!("g1={g1:.6}, g2={g2:.6}"),
+            "fitted AR(2) roots are not two distinct reals in (0, 1)",
+        )
+    };
+    if disc.is_nan() || disc < 0.0 {
+        return Err(unstable());
+    }
+    let d = 0.5 * (g1 + disc.sqrt());
+    let r = 0.5 * (g1 - disc.sqrt());
+    if r <= 0.0 || d >= 1.0 {
+        return Err(unstable());
+    }
+    let tau_decay = -1.0 / (fs * d.ln());
+    let tau_rise = -1.0 / (fs * r.ln());
+    if tau_decay <= tau_rise {
+        return Err(CalabError::invalid(
+            "acf",
+            format!("tau_rise={tau_rise}, tau_decay={tau_decay}"),
+            "fitted tau_decay must exceed tau_rise",
+        ));
+    }
+    Ok((tau_rise, tau_decay))
+}
+
 /// Compute the peak of the raw AR2 impulse response.
 ///
 /// Runs the AR2 recursion c[t] = g1*c[t-1] + g2*c[t-2] + delta[t] until
@@ -164,32 +747,66 @@ fn compute_banded_lipschitz(g1: f64, g2: f64) -> f64 {
 
     for k in 0..=n_freqs {
         let w = std::f64::consts::PI * (k as f64) / (n_freqs as f64);
-        // H(e^{jw}) = 1 / (1 - g1*e^{-jw} - g2*e^{-2jw})
-        // Denominator: (1 - g1*cos(w) - g2*cos(2w)) + j*(g1*sin(w) + g2*sin(2w))
-        // Use double-angle identities: cos(2w) = 2cos^2(w)-1, sin(2w) = 2sin(w)cos(w)
-        let cw = w.cos();
-        let sw = w.sin();
-        let c2w = 2.0 * cw * cw - 1.0;
-        let s2w = 2.0 * sw * cw;
-        let re = 1.0 - g1 * cw - g2 * c2w;
-        let im = g1 * sw + g2 * s2w;
-        let denom_sq = re * re + im * im;
-        if denom_sq > 1e-30 {
-            max_power = max_power.max(1.0 / denom_sq);
-        }
+        max_power = max_power.max(ar2_power(g1, g2, w));
     }
 
     max_power.max(1e-10)
 }
 
+/// Raw power response `|H(e^{jw})|^2` of the AR(2) recursion at angular
+/// frequency `w` (radians per sample); 0 where the denominator vanishes.
+fn ar2_power(g1: f64, g2: f64, w: f64) -> f64 {
+    // H(e^{jw}) = 1 / (1 - g1*e^{-jw} - g2*e^{-2jw})
+    // Denominator: (1 - g1*cos(w) - g2*cos(2w)) + j*(g1*sin(w) + g2*sin(2w))
+    // Use double-angle identities: cos(2w) = 2cos^2(w)-1, sin(2w) = 2sin(w)cos(w)
+    let cw = w.cos();
+    let sw = w.sin();
+    let c2w = 2.0 * cw * cw - 1.0;
+    let s2w = 2.0 * sw * cw;
+    let re = 1.0 - g1 * cw - g2 * c2w;
+    let im = g1 * sw + g2 * s2w;
+    let denom_sq = re * re + im * im;
+    if denom_sq > 1e-30 {
+        1.0 / denom_sq
+    } else {
+        0.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::kernel::{build_kernel, tau_to_ar2};
 
+    #[test]
+    fn frequency_response_dc_and_cutoff() {
+        let fs = 30.0;
+        let banded = BandedAR2::new(0.02, 0.4, fs).unwrap();
+        let dc = (1.0 / (1.0 - banded.g1 - banded.g2)).powi(2);
+        let response = banded.frequency_response(&[0.0, 1.0, 5.0, fs / 2.0], fs);
+        assert!((response[0] - dc).abs() < 1e-9 * dc);
+        assert!(response.windows(2).all(|w| w[1] < w[0]));
+
+        let fc = banded.cutoff_frequency_hz(fs);
+        assert!(fc > 0.0 && fc < fs / 2.0);
+        let at_fc = banded.frequency_response(&[fc], fs)[0];
+        assert!(
+            (at_fc - 0.5 * dc).abs() < 1e-6 * dc,
+            "{at_fc} vs {}",
+            0.5 * dc
+        );
+        // A slower decay passes less bandwidth.
+        assert!(
+            BandedAR2::new(0.02, 1.0, fs)
+                .unwrap()
+                .cutoff_frequency_hz(fs)
+                < fc
+        );
+    }
+
     #[test]
     fn g1_g2_match_tau_to_ar2() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let (g1, g2) = tau_to_ar2(0.02, 0.4, 30.0);
         assert!(
             (banded.g1 - g1).abs() < 1e-15,
@@ -205,10 +822,142 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unstable_taus_are_rejected() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        assert!(banded.is_stable());
+        let d = (-1.0_f64 / (30.0 * 0.4)).exp();
+        assert!((banded.stability_margin() - (1.0 - d)).abs() < 1e-15);
+
+        assert!(matches!(
+            BandedAR2::new(0.02, -0.4, 30.0),
+            Err(CalabError::NumericalFailure(_))
+        ));
+        assert!(BandedAR2::new(0.02, 0.4, f64::NAN).is_err());
+        let (stable, margin) = ar2_stability(0.02, -0.4, 30.0);
+        assert!(!stable && margin < 0.0);
+
+        // A failed update leaves the engine as it was.
+        let mut updated = banded;
+        assert!(updated.update(0.02, -0.4, 30.0).is_err());
+        assert_eq!(updated.g1, banded.g1);
+    }
+
+    #[test]
+    fn fit_from_acf_recovers_taus() {
+        // Theoretical ACF of an AR(2) process driven by white noise.
+        let fs = 30.0;
+        let (g1, g2) = tau_to_ar2(0.1, 0.8, fs);
+        let r1 = g1 / (1.0 - g2);
+        let acf = [1.0, r1, g1 * r1 + g2, 0.0];
+        let (tau_rise, tau_decay) = ar2_taus_from_acf(&acf, fs).unwrap();
+        assert!((tau_rise - 0.1).abs() < 1e-9, "tau_rise {tau_rise}");
+        assert!((tau_decay - 0.8).abs() < 1e-9, "tau_decay {tau_decay}");
+        let banded = BandedAR2::fit_from_acf(&acf, fs).unwrap();
+        assert!((banded.g1 - g1).abs() < 1e-12 && (banded.g2 - g2).abs() < 1e-12);
+
+        // AR(1)-shaped ACF: g2 = 0, so the rise root is 0 — no rise phase.
+        let ar1 = [1.0, 0.9, 0.81];
+        assert!(matches!(
+            ar2_taus_from_acf(&ar1, fs),
+            Err(CalabError::InvalidParameter { .. })
+        ));
+        // Oscillating fit (complex roots).
+        assert!(ar2_taus_from_acf(&[1.0, 0.2, -0.6], fs).is_err());
+        assert!(ar2_taus_from_acf(&[1.0, 0.5], fs).is_err());
+    }
+
+    #[test]
+    fn sparse_forward_matches_dense() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 3000;
+        let spikes = [(10_usize, 1.0_f32), (500, 2.0), (505, 1.0), (2990, 1.0)];
+        let mut source = vec![0.0_f32; n];
+        for &(t, a) in &spikes {
+            source[t] = a;
+        }
+        let mut dense = vec![0.0_f32; n];
+        banded.convolve_forward(&source, &mut dense);
+
+        let mut sparse = vec![f32::NAN; n];
+        banded.convolve_forward_sparse(&spikes, n, &mut sparse);
+        for (i, (a, b)) in sparse.iter().zip(&dense).enumerate() {
+            assert!((a - b).abs() < 1e-5, "sample {i}: {a} vs {b}");
+        }
+        // Denser than SPARSE_MAX_DENSITY: the dense fallback is exact.
+        let many: Vec<(usize, f32)> = (0..n).step_by(10).map(|t| (t, 1.0)).collect();
+        let mut source = vec![0.0_f32; n];
+        for &(t, a) in &many {
+            source[t] = a;
+        }
+        banded.convolve_forward(&source, &mut dense);
+        banded.convolve_forward_sparse(&many, n, &mut sparse);
+        assert_eq!(sparse, dense);
+    }
+
+    #[test]
+    fn block_forward_matches_whole_trace() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 300;
+        let source: Vec<f32> = (0..n).map(|i| ((i * 37) % 11) as f32 / 10.0).collect();
+        let mut whole = vec![0.0_f32; n];
+        banded.convolve_forward(&source, &mut whole);
+
+        let mut streamed = vec![0.0_f32; n];
+        let mut state = banded.initial_state();
+        let mut start = 0;
+        for len in [1, 0, 2, 7, 64, 1, 100].into_iter().cycle() {
+            let end = (start + len).min(n);
+            banded.convolve_forward_block(
+                &source[start..end],
+                &mut streamed[start..end],
+                &mut state,
+            );
+            start = end;
+            if start == n {
+                break;
+            }
+        }
+        assert_eq!(streamed, whole);
+    }
+
+    #[test]
+    fn forward_x8_matches_scalar() {
+        // Runs the AVX2 kernel on CPUs with AVX2 and FMA, the scalar loop elsewhere.
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        // 8·k + 3 samples: exercises both the transposed blocks and the tail.
+        let n = 203;
+        let sources: Vec<Vec<f32>> = (0..8)
+            .map(|k| {
+                (0..n)
+                    .map(|i| ((i * (k + 3)) % 17) as f32 * 0.1 + (i as f32 * 0.05).sin().max(0.0))
+                    .collect()
+            })
+            .collect();
+        let mut outputs = vec![vec![f32::NAN; n]; 8];
+        {
+            let srcs: [&[f32]; 8] = std::array::from_fn(|k| sources[k].as_slice());
+            let mut iter = outputs.iter_mut();
+            let mut outs: [&mut [f32]; 8] =
+                std::array::from_fn(|_| iter.next().unwrap().as_mut_slice());
+            banded.convolve_forward_x8(&srcs, &mut outs);
+        }
+        for (source, output) in sources.iter().zip(&outputs) {
+            let mut expected = vec![0.0_f32; n];
+            banded.convolve_forward(source, &mut expected);
+            for (t, (&a, &b)) in output.iter().zip(&expected).enumerate() {
+                assert!(
+                    (a - b).abs() <= 1e-5 * b.abs().max(1.0),
+                    "sample {t}: x8 {a} vs scalar {b}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn adjoint_identity() {
         // <K*x, y> == <x, K^T*y> for deterministic vectors
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 200;
 
         let x: Vec<f32> = (0..n).map(|i| (i as f32 * 0.3).sin()).collect();
@@ -247,7 +996,7 @@ mod tests {
         // peak = 1.0. With the one-sample source delay the spike sample itself
         // is zero and the response rises starting the sample after — matching
         // the build_kernel convention (h[0] = 0).
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 200;
 
         let mut signal = vec![0.0_f32; n];
@@ -307,7 +1056,7 @@ mod tests {
         // build_kernel sample-for-sample. This is the direct regression guard for
         // the one-sample alignment between the two conv modes.
         for &(tau_r, tau_d, fs) in &[(0.02, 0.4, 30.0), (0.05, 0.25, 100.0), (0.01, 0.15, 300.0)] {
-            let banded = BandedAR2::new(tau_r, tau_d, fs);
+            let banded = BandedAR2::new(tau_r, tau_d, fs).unwrap();
             let kernel = build_kernel(tau_r, tau_d, fs);
             let n = kernel.len();
 
@@ -422,7 +1171,7 @@ mod tests {
     fn lipschitz_positive() {
         // The normalized Lipschitz constant should be positive and finite
         for &fs in &[30.0, 100.0, 300.0] {
-            let banded = BandedAR2::new(0.02, 0.4, fs);
+            let banded = BandedAR2::new(0.02, 0.4, fs).unwrap();
             assert!(
                 banded.lipschitz() > 0.0 && banded.lipschitz().is_finite(),
                 "fs={}: Lipschitz should be positive and finite, got {}",
@@ -436,7 +1185,7 @@ mod tests {
     fn impulse_response_peak_is_one() {
         // After normalization, the impulse peak should be ~1.0 at any sampling rate
         for &fs in &[30.0, 100.0, 300.0, 1000.0] {
-            let banded = BandedAR2::new(0.02, 0.4, fs);
+            let banded = BandedAR2::new(0.02, 0.4, fs).unwrap();
             let n = (5.0 * 0.4 * fs).ceil() as usize + 10;
 
             let mut impulse = vec![0.0_f32; n];
@@ -465,4 +1214,116 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn ar1_adjoint_identity() {
+        let banded = BandedAR1::new(0.4, 30.0);
+        let n = 200;
+
+        let x: Vec<f32> = (0..n).map(|i| (i as f32 * 0.3).sin()).collect();
+        let y: Vec<f32> = (0..n).map(|i| (i as f32 * 0.7 + 1.0).cos()).collect();
+
+        let mut kx = vec![0.0_f32; n];
+        banded.convolve_forward(&x, &mut kx);
+
+        let mut kty = vec![0.0_f32; n];
+        banded.convolve_adjoint(&y, &mut kty);
+
+        let lhs: f64 = kx
+            .iter()
+            .zip(y.iter())
+            .map(|(&a, &b)| a as f64 * b as f64)
+            .sum();
+        let rhs: f64 = x
+            .iter()
+            .zip(kty.iter())
+            .map(|(&a, &b)| a as f64 * b as f64)
+            .sum();
+
+        let rel_err = (lhs - rhs).abs() / lhs.abs().max(1e-10);
+        assert!(
+            rel_err < 1e-3,
+            "Adjoint identity violated: <Kx,y>={} vs <x,K^Ty>={} (rel_err={})",
+            lhs,
+            rhs,
+            rel_err
+        );
+    }
+
+    #[test]
+    fn check_adjoint_op_accepts_engines_and_flags_wrong_adjoint() {
+        use crate::fft::FftConvolver;
+        use crate::kernel::build_kernel;
+        use std::cell::RefCell;
+
+        let n = 300;
+        let ar2 = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let err = check_adjoint_op(
+            |x, out| ar2.convolve_forward(x, out),
+            |y, out| ar2.convolve_adjoint(y, out),
+            n,
+            1e-3,
+            8,
+        )
+        .unwrap();
+        assert!(err < 1e-3);
+
+        let ar1 = BandedAR1::new(0.4, 30.0);
+        assert!(check_adjoint_op(
+            |x, out| ar1.convolve_forward(x, out),
+            |y, out| ar1.convolve_adjoint(y, out),
+            n,
+            1e-3,
+            8,
+        )
+        .is_ok());
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let fft = RefCell::new(FftConvolver::new());
+        fft.borrow_mut().ensure_buffers(n, &kernel);
+        assert!(check_adjoint_op(
+            |x, out| fft.borrow_mut().convolve_forward(x, n, out),
+            |y, out| fft.borrow_mut().convolve_adjoint(y, n, out),
+            n,
+            1e-3,
+            8,
+        )
+        .is_ok());
+
+        // The forward operator is not its own adjoint.
+        let msg = check_adjoint_op(
+            |x, out| ar2.convolve_forward(x, out),
+            |y, out| ar2.convolve_forward(y, out),
+            n,
+            1e-3,
+            8,
+        )
+        .unwrap_err();
+        assert!(msg.contains("adjoint mismatch"), "{msg}");
+    }
+
+    #[test]
+    fn ar1_impulse_response_matches_single_exp_kernel() {
+        let banded = BandedAR1::new(0.4, 30.0);
+        let kernel = build_kernel(0.0, 0.4, 30.0);
+        let n = kernel.len();
+
+        let mut impulse = vec![0.0_f32; n];
+        impulse[0] = 1.0;
+        let mut out = vec![0.0_f32; n];
+        banded.convolve_forward(&impulse, &mut out);
+
+        for i in 0..n {
+            assert!(
+                (out[i] - kernel[i]).abs() < 1e-4,
+                "AR1 impulse[{}]={} vs single-exp kernel {}",
+                i,
+                out[i],
+                kernel[i]
+            );
+        }
+        assert!(
+            (banded.lipschitz() - 1.0 / (1.0 - banded.g).powi(2)).abs() < 1e-6 * banded.lipschitz()
+        );
+    }
 }
@@ -8,6 +8,15 @@
 ///
 /// Uses a coordinate-compressed Fenwick tree (Binary Indexed Tree) for
 /// O(N log M) sliding-window k-th element queries, where M = distinct values.
+/// `StreamingPercentile` gives the same baseline one sample at a time.
+///
+/// Also holds the rolling variance / standard deviation and the
+/// percentile-of-rolling-std noise floor built on them, ΔF/F0
+/// normalisation against a rolling or fixed F0, and the Anscombe
+/// variance-stabilizing transform for shot-noise-limited traces.
+use std::collections::VecDeque;
+
+use crate::validate::Diagnostic;
 
 /// Default rolling-baseline percentile (quantile of the causal window taken as
 /// the floor estimate). Single source of truth for the callers that subtract a
@@ -27,7 +36,7 @@ pub fn baseline_window(tau_d: f64, fs: f64) -> usize {
 
 /// Wrapper for f32 that provides total ordering (NaN sorts last).
 #[derive(Clone, Copy)]
-struct OrderedF32(f32);
+pub(crate) struct OrderedF32(pub(crate) f32);
 
 impl PartialEq for OrderedF32 {
     fn eq(&self, other: &Self) -> bool {
@@ -50,13 +59,13 @@ impl Ord for OrderedF32 {
 
 /// Fenwick tree (Binary Indexed Tree) supporting point updates and prefix sums.
 /// Used for O(log M) k-th element queries via binary lifting.
-struct FenwickTree {
+pub(crate) struct FenwickTree {
     tree: Vec<i32>,
     msb: usize, // highest power of 2 <= (tree.len() - 1)
 }
 
 impl FenwickTree {
-    fn new(size: usize) -> Self {
+    pub(crate) fn new(size: usize) -> Self {
         let mut msb = 1;
         while msb <= size {
             msb <<= 1;
@@ -69,7 +78,7 @@ impl FenwickTree {
     }
 
     /// Add `delta` to position `i` (0-indexed).
-    fn update(&mut self, mut i: usize, delta: i32) {
+    pub(crate) fn update(&mut self, mut i: usize, delta: i32) {
         i += 1; // convert to 1-indexed
         while i < self.tree.len() {
             self.tree[i] += delta;
@@ -77,9 +86,20 @@ impl FenwickTree {
         }
     }
 
+    /// Sum of positions `0..=i` (0-indexed).
+    pub(crate) fn prefix_sum(&self, i: usize) -> i32 {
+        let mut i = i + 1;
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.tree[i];
+            i &= i - 1; // i -= lowbit(i)
+        }
+        sum
+    }
+
     /// Find the 0-indexed position of the k-th element (1-based k).
     /// Uses binary lifting: O(log M) time.
-    fn kth(&self, mut k: i32) -> usize {
+    pub(crate) fn kth(&self, mut k: i32) -> usize {
         let n = self.tree.len() - 1;
         let mut pos = 0;
         let mut bit = self.msb;
@@ -102,10 +122,38 @@ impl FenwickTree {
 /// `trace[max(0, t-window+1)..=t]` (causal window, min_periods=1 at edges).
 /// O(N log M) via coordinate-compressed Fenwick tree, where M = distinct values.
 pub fn subtract_rolling_baseline(trace: &mut [f32], window: usize, quantile: f64) {
+    subtract_rolling_baseline_impl(trace, None, window, quantile);
+}
+
+/// Like [`subtract_rolling_baseline`], but samples with `excluded[t] == true`
+/// never enter the percentile window (e.g. detector-saturated frames, whose
+/// clamped values would otherwise drag the floor estimate). The baseline is
+/// still subtracted from every sample. Where a window holds no valid samples
+/// the previous baseline is carried forward (the first valid value before any).
+pub fn subtract_rolling_baseline_masked(
+    trace: &mut [f32],
+    excluded: &[bool],
+    window: usize,
+    quantile: f64,
+) {
+    debug_assert_eq!(trace.len(), excluded.len());
+    subtract_rolling_baseline_impl(trace, Some(excluded), window, quantile);
+}
+
+fn subtract_rolling_baseline_impl(
+    trace: &mut [f32],
+    excluded: Option<&[bool]>,
+    window: usize,
+    quantile: f64,
+) {
     let n = trace.len();
     if n == 0 || window == 0 {
         return;
     }
+    let is_valid = |t: usize| match excluded {
+        Some(mask) => !mask[t],
+        None => true,
+    };
 
     // Coordinate compression: sort + dedup trace values, assign indices via binary search.
     let mut sorted_vals: Vec<OrderedF32> = trace.iter().map(|&v| OrderedF32(v)).collect();
@@ -118,27 +166,38 @@ pub fn subtract_rolling_baseline(trace: &mut [f32], window: usize, quantile: f64
 
     let mut fenwick = FenwickTree::new(m);
     let mut baselines = Vec::with_capacity(n);
+    let mut win_size = 0usize;
+    let mut last = (0..n)
+        .find(|&t| is_valid(t))
+        .map(|t| trace[t])
+        .unwrap_or(0.0);
 
     for t in 0..n {
         // Add the new element entering the window.
-        let ci = compress(trace[t]);
-        fenwick.update(ci, 1);
+        if is_valid(t) {
+            fenwick.update(compress(trace[t]), 1);
+            win_size += 1;
+        }
 
         // Remove the element leaving the window.
-        if t >= window {
-            let old_ci = compress(trace[t - window]);
-            fenwick.update(old_ci, -1);
+        if t >= window && is_valid(t - window) {
+            fenwick.update(compress(trace[t - window]), -1);
+            win_size -= 1;
+        }
+
+        if win_size == 0 {
+            baselines.push(last);
+            continue;
         }
 
-        // Current window size.
-        let win_size = (t + 1).min(window);
         // k-th index (0-based rank), matching the original: ((win_size - 1) * quantile).round()
         let k = ((win_size as f64 - 1.0) * quantile).round() as usize;
         let k = k.min(win_size - 1);
 
         // Find the (k+1)-th smallest element (Fenwick kth uses 1-based k).
         let coord = fenwick.kth((k + 1) as i32);
-        baselines.push(sorted_vals[coord].0);
+        last = sorted_vals[coord].0;
+        baselines.push(last);
     }
 
     for (v, &b) in trace.iter_mut().zip(baselines.iter()) {
@@ -146,10 +205,465 @@ pub fn subtract_rolling_baseline(trace: &mut [f32], window: usize, quantile: f64
     }
 }
 
+/// Causal rolling percentile updated one sample at a time, for online use
+/// where [`subtract_rolling_baseline`] cannot see the whole trace.
+///
+/// `push` returns exactly the baseline `subtract_rolling_baseline` computes
+/// at the same position (same window, same rank rounding). Coordinate
+/// compression needs the values up front, so the window is handled in
+/// epochs of `window` samples: the Fenwick tree indexes the previous epoch's
+/// values (every sample that can still leave the window), while the
+/// current epoch's arrivals go into a small sorted buffer. When an epoch
+/// completes it becomes the window, and the tree is rebuilt from it, so each
+/// sample costs O(log window) amortized plus a sorted insert.
+pub struct StreamingPercentile {
+    tree: FenwickTree,
+    /// Distinct values of the previous epoch (tree coordinates).
+    sorted_vals: Vec<OrderedF32>,
+    /// Current epoch's samples, ascending.
+    recent: Vec<OrderedF32>,
+    /// Window contents, oldest first.
+    window_deque: VecDeque<f32>,
+    window: usize,
+    quantile: f64,
+    /// Samples of the previous epoch still in the window.
+    tree_count: usize,
+}
+
+impl StreamingPercentile {
+    /// Empty estimator over a causal `window` (at least 1) at `quantile`
+    /// (clamped to [0, 1]).
+    pub fn new(window: usize, quantile: f64) -> Self {
+        let window = window.max(1);
+        StreamingPercentile {
+            tree: FenwickTree::new(0),
+            sorted_vals: Vec::new(),
+            recent: Vec::with_capacity(window),
+            window_deque: VecDeque::with_capacity(window + 1),
+            window,
+            quantile: quantile.clamp(0.0, 1.0),
+            tree_count: 0,
+        }
+    }
+
+    /// Empty the window, keeping `window` and `quantile`.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.window, self.quantile);
+    }
+
+    /// Push `data` without reading the baseline, e.g. history recorded
+    /// before the stream starts.
+    pub fn warmup(&mut self, data: &[f32]) {
+        for &v in data {
+            self.push(v);
+        }
+    }
+
+    /// Add one sample and return the `quantile`-th value of the last
+    /// `window` samples (fewer while the window fills).
+    pub fn push(&mut self, value: f32) -> f32 {
+        // The current epoch is complete and is exactly the window: index it.
+        if self.recent.len() == self.window {
+            self.sorted_vals.clear();
+            self.sorted_vals.extend_from_slice(&self.recent);
+            self.sorted_vals.dedup();
+            self.tree = FenwickTree::new(self.sorted_vals.len());
+            let mut c = 0;
+            for v in &self.recent {
+                while self.sorted_vals[c] != *v {
+                    c += 1;
+                }
+                self.tree.update(c, 1);
+            }
+            self.tree_count = self.recent.len();
+            self.recent.clear();
+        }
+
+        let v = OrderedF32(value);
+        let at = self.recent.partition_point(|x| *x < v);
+        self.recent.insert(at, v);
+        self.window_deque.push_back(value);
+        // The sample leaving entered `window` pushes ago, before the current
+        // epoch began, so it is in the tree.
+        if self.window_deque.len() > self.window {
+            let old = OrderedF32(self.window_deque.pop_front().unwrap());
+            let c = self.sorted_vals.binary_search(&old).unwrap();
+            self.tree.update(c, -1);
+            self.tree_count -= 1;
+        }
+
+        let total = self.tree_count + self.recent.len();
+        let k = ((total as f64 - 1.0) * self.quantile).round() as usize;
+        self.kth(k.min(total - 1))
+    }
+
+    /// The `k`-th smallest (0-based) window value: the least value `x` with
+    /// at least `k + 1` window samples `<= x`, searched in both halves.
+    fn kth(&self, k: usize) -> f32 {
+        let need = k + 1;
+        let recent_le = |x: OrderedF32| self.recent.partition_point(|r| *r <= x);
+        let tree_le = |x: OrderedF32| match self.sorted_vals.partition_point(|s| *s <= x) {
+            0 => 0,
+            c => self.tree.prefix_sum(c - 1) as usize,
+        };
+        let count_le = |x: OrderedF32| recent_le(x) + tree_le(x);
+
+        let mut best: Option<OrderedF32> = None;
+        let j = self.recent.partition_point(|&r| count_le(r) < need);
+        if let Some(&r) = self.recent.get(j) {
+            best = Some(r);
+        }
+        // A tree coordinate whose count dropped to zero can only be reached
+        // here if a recent value at or below it already qualifies.
+        let c = self.sorted_vals.partition_point(|&s| count_le(s) < need);
+        if let Some(&s) = self.sorted_vals.get(c) {
+            best = Some(best.map_or(s, |b| b.min(s)));
+        }
+        best.map_or(f32::NAN, |b| b.0)
+    }
+}
+
+/// Number of lower-envelope blocks the photobleaching fit summarizes the trace into.
+const BLEACH_BLOCKS: usize = 64;
+
+/// Asymmetric reweighting pass count and the weight given to envelope points
+/// lying above the current fit (transients). Points below keep weight 1, so the
+/// fit settles onto the floor of the trace rather than its mean.
+const BLEACH_IRLS_ITERS: usize = 10;
+const BLEACH_ABOVE_WEIGHT: f64 = 0.05;
+
+/// Weighted least-squares `(F0, offset)` for `y ≈ F0·exp(-t/tau) + offset` at
+/// a fixed `tau`. Returns `(F0, offset, weighted SSE)`, or `None` when the two
+/// basis columns are collinear under the weights.
+fn fit_exp_offset(t: &[f64], y: &[f64], w: &[f64], tau: f64) -> Option<(f64, f64, f64)> {
+    let (mut see, mut se, mut sw, mut sey, mut sy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for i in 0..t.len() {
+        let e = (-t[i] / tau).exp();
+        see += w[i] * e * e;
+        se += w[i] * e;
+        sw += w[i];
+        sey += w[i] * e * y[i];
+        sy += w[i] * y[i];
+    }
+    let det = see * sw - se * se;
+    if det.abs() <= 1e-12 * see * sw {
+        return None;
+    }
+    let f0 = (sey * sw - se * sy) / det;
+    let offset = (see * sy - se * sey) / det;
+    let mut sse = 0.0;
+    for i in 0..t.len() {
+        let r = y[i] - f0 * (-t[i] / tau).exp() - offset;
+        sse += w[i] * r * r;
+    }
+    Some((f0, offset, sse))
+}
+
+/// Best `tau` in `[lo, hi]` by a log-spaced grid followed by golden-section
+/// refinement around the best grid point.
+fn fit_bleach_tau(t: &[f64], y: &[f64], w: &[f64], lo: f64, hi: f64) -> f64 {
+    const GRID: usize = 48;
+    let sse_at = |tau: f64| fit_exp_offset(t, y, w, tau).map_or(f64::INFINITY, |(_, _, e)| e);
+    let (llo, lhi) = (lo.ln(), hi.ln());
+    let step = (lhi - llo) / (GRID - 1) as f64;
+    let mut best = 0;
+    let mut best_sse = f64::INFINITY;
+    for k in 0..GRID {
+        let e = sse_at((llo + k as f64 * step).exp());
+        if e < best_sse {
+            best_sse = e;
+            best = k;
+        }
+    }
+    // Golden-section search in log(tau) over the bracketing grid cells.
+    let inv_phi = 0.5 * (5.0_f64.sqrt() - 1.0);
+    let mut a = llo + best.saturating_sub(1) as f64 * step;
+    let mut b = llo + (best + 1).min(GRID - 1) as f64 * step;
+    for _ in 0..30 {
+        let c = b - inv_phi * (b - a);
+        let d = a + inv_phi * (b - a);
+        if sse_at(c.exp()) < sse_at(d.exp()) {
+            b = d;
+        } else {
+            a = c;
+        }
+    }
+    (0.5 * (a + b)).exp()
+}
+
+/// Fit an exponential photobleaching trend `F(t) = F0·exp(-t/tau_bl) + offset`
+/// to the lower envelope of `trace`. Returns `(F0, tau_bl)` with `tau_bl` in
+/// seconds; the offset is left to the rolling baseline.
+///
+/// The trace is summarized as a low quantile per block (the envelope), then
+/// fit by iteratively reweighted least squares with asymmetric weights so
+/// calcium transients above the floor do not pull the trend up.
+///
+/// A fitted `tau_bl` shorter than the recording is treated as degenerate (it
+/// usually means the fit locked onto an onset transient or a step rather than
+/// bleaching): `tau_bl` is clipped to 10× the duration and `F0` refit, which
+/// leaves a gentle near-linear trend. Traces with fewer than two samples
+/// return `(0.0, f64::INFINITY)`, a no-op for [`subtract_photobleaching`].
+pub fn fit_photobleaching(trace: &[f32], fs: f64) -> (f64, f64) {
+    let n = trace.len();
+    if n < 2 || fs <= 0.0 {
+        return (0.0, f64::INFINITY);
+    }
+    let duration = n as f64 / fs;
+    let tau_max = 10.0 * duration;
+
+    // Lower envelope: one low quantile per block, at the block's center time.
+    let n_blocks = BLEACH_BLOCKS.min(n);
+    let mut t = Vec::with_capacity(n_blocks);
+    let mut y = Vec::with_capacity(n_blocks);
+    let mut block: Vec<f32> = Vec::new();
+    for b in 0..n_blocks {
+        let start = b * n / n_blocks;
+        let end = (b + 1) * n / n_blocks;
+        block.clear();
+        block.extend_from_slice(&trace[start..end]);
+        block.sort_unstable_by(|a, b| a.total_cmp(b));
+        let k = ((block.len() - 1) as f64 * DEFAULT_BASELINE_QUANTILE).round() as usize;
+        t.push((start + end - 1) as f64 * 0.5 / fs);
+        y.push(block[k] as f64);
+    }
+
+    let mut w = vec![1.0_f64; t.len()];
+    let mut tau = tau_max;
+    for _ in 0..BLEACH_IRLS_ITERS {
+        tau = fit_bleach_tau(&t, &y, &w, duration / 20.0, tau_max);
+        let Some((f0, offset, _)) = fit_exp_offset(&t, &y, &w, tau) else {
+            return (0.0, f64::INFINITY);
+        };
+        for i in 0..t.len() {
+            let r = y[i] - f0 * (-t[i] / tau).exp() - offset;
+            w[i] = if r > 0.0 { BLEACH_ABOVE_WEIGHT } else { 1.0 };
+        }
+    }
+
+    if tau < duration {
+        tau = tau_max;
+    }
+    match fit_exp_offset(&t, &y, &w, tau) {
+        Some((f0, _, _)) => (f0, tau),
+        None => (0.0, f64::INFINITY),
+    }
+}
+
+/// Subtract a fitted photobleaching trend `F0·exp(-t/tau_bl)` in place, with
+/// `t = i / fs`. A negative `F0` (a brightening trend, which photobleaching
+/// cannot produce) or a non-positive / non-finite `tau_bl` leaves the trace
+/// unmodified.
+pub fn subtract_photobleaching(trace: &mut [f32], f0: f64, tau_bl: f64, fs: f64) {
+    if f0 < 0.0 || !(tau_bl > 0.0 && tau_bl.is_finite()) || fs <= 0.0 {
+        return;
+    }
+    let decay = (-1.0 / (fs * tau_bl)).exp();
+    let mut trend = f0;
+    for v in trace.iter_mut() {
+        *v -= trend as f32;
+        trend *= decay;
+    }
+}
+
+/// Smallest F0 used as a ΔF/F0 denominator; below it the denominator is 1.
+const MIN_DFF_F0: f32 = 1e-6;
+
+/// ΔF/F0 with F0 the rolling `quantile` baseline of
+/// [`subtract_rolling_baseline`] (causal `window`).
+///
+/// Where F0 falls below 1e-6 (a zero or negative floor, e.g. an already
+/// baseline-subtracted trace) the denominator is replaced by 1, leaving
+/// `F - F0`, and a warning counting the affected samples is returned.
+///
+/// `indeca::solve_trace` estimates its own baseline and amplitude scale, and
+/// works better on raw fluorescence than on a pre-normalised ΔF/F0 trace,
+/// whose division by a noisy F0 distorts transient shapes.
+pub fn compute_dff_rolling(
+    trace: &[f32],
+    window: usize,
+    quantile: f64,
+) -> (Vec<f32>, Vec<Diagnostic>) {
+    let mut detrended = trace.to_vec();
+    subtract_rolling_baseline(&mut detrended, window, quantile);
+    let mut small = 0;
+    let dff = trace
+        .iter()
+        .zip(&detrended)
+        .map(|(&f, &df)| {
+            let f0 = f - df;
+            if f0 < MIN_DFF_F0 {
+                small += 1;
+                df
+            } else {
+                df / f0
+            }
+        })
+        .collect();
+    (dff, small_f0_warning(small))
+}
+
+/// ΔF/F0 against a fixed `f0`; an `f0` below 1e-6 is replaced by 1 with a
+/// warning (see [`compute_dff_rolling`]).
+pub fn compute_dff_global(trace: &[f32], f0: f64) -> (Vec<f32>, Vec<Diagnostic>) {
+    let (denom, warnings) = if f0 < MIN_DFF_F0 as f64 || f0.is_nan() {
+        (1.0, small_f0_warning(trace.len()))
+    } else {
+        (f0, Vec::new())
+    };
+    let dff = trace
+        .iter()
+        .map(|&f| ((f as f64 - f0) / denom) as f32)
+        .collect();
+    (dff, warnings)
+}
+
+fn small_f0_warning(n_samples: usize) -> Vec<Diagnostic> {
+    if n_samples == 0 {
+        return Vec::new();
+    }
+    vec![Diagnostic::warning(
+        "f0",
+        format!("F0 below {MIN_DFF_F0:e} at {n_samples} samples; divided by 1 there instead"),
+    )]
+}
+
+/// Population variance of every `window`-sample span of `trace`, as one
+/// value per sample: entry `t` covers `trace[t-window+1..=t]`, and the first
+/// `window - 1` entries repeat the first full window's value. A window longer
+/// than the trace uses the whole trace (every entry equal); `window == 0` is
+/// treated as 1.
+///
+/// The window slides with Welford add/remove updates in f64, so the cost is
+/// O(N) regardless of `window`.
+pub fn rolling_variance(trace: &[f32], window: usize) -> Vec<f32> {
+    let n = trace.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let window = window.clamp(1, n);
+    let mut mean = 0.0_f64;
+    let mut m2 = 0.0_f64;
+    for (i, &x) in trace[..window].iter().enumerate() {
+        let x = x as f64;
+        let d = x - mean;
+        mean += d / (i + 1) as f64;
+        m2 += d * (x - mean);
+    }
+    let w = window as f64;
+    let mut out = vec![(m2.max(0.0) / w) as f32; n];
+    for t in window..n {
+        // Swap trace[t - window] out for trace[t]; the count stays `window`.
+        let (x_new, x_old) = (trace[t] as f64, trace[t - window] as f64);
+        let delta = x_new - x_old;
+        let mean_new = mean + delta / w;
+        m2 += delta * (x_new - mean_new + x_old - mean);
+        mean = mean_new;
+        out[t] = (m2.max(0.0) / w) as f32;
+    }
+    out
+}
+
+/// Square root of [`rolling_variance`].
+pub fn rolling_std(trace: &[f32], window: usize) -> Vec<f32> {
+    let mut out = rolling_variance(trace, window);
+    for v in out.iter_mut() {
+        *v = v.sqrt();
+    }
+    out
+}
+
+/// Robust noise level: the `percentile`-th (0–100, clamped, linearly
+/// interpolated) value of [`rolling_std`]. A low percentile picks the quiet
+/// stretches between transients, where the rolling spread is noise alone.
+/// NaN for an empty trace.
+pub fn estimate_noise_floor(trace: &[f32], window: usize, percentile: f64) -> f64 {
+    let mut stds = rolling_std(trace, window);
+    if stds.is_empty() {
+        return f64::NAN;
+    }
+    stds.sort_unstable_by(f32::total_cmp);
+    let pos = percentile.clamp(0.0, 100.0) / 100.0 * (stds.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    let frac = pos - lo as f64;
+    stds[lo] as f64 + frac * (stds[hi] as f64 - stds[lo] as f64)
+}
+
+/// Anscombe transform `2 * sqrt(max(0, x) + 3/8)`: maps Poisson counts to
+/// values with approximately unit variance, whatever their mean, so shot
+/// noise looks like the Gaussian noise the solver assumes.
+pub fn anscombe_transform(trace: &[f32]) -> Vec<f32> {
+    trace
+        .iter()
+        .map(|&x| (2.0 * ((x as f64).max(0.0) + 0.375).sqrt()) as f32)
+        .collect()
+}
+
+/// Inverse of [`anscombe_transform`] with a bias correction,
+/// `(x / 2)^2 - 3/8 + 1 / (4 * sqrt(x))`, floored at 0. Non-positive inputs,
+/// which the forward transform never produces, map to 0.
+pub fn anscombe_inverse_transform(trace: &[f32]) -> Vec<f32> {
+    trace
+        .iter()
+        .map(|&x| {
+            let x = x as f64;
+            if x <= 0.0 {
+                return 0.0;
+            }
+            ((x / 2.0).powi(2) - 0.375 + 0.25 / x.sqrt()).max(0.0) as f32
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn anscombe_stabilizes_poisson_variance() {
+        // Poisson counts by inversion of the CDF from an LCG uniform.
+        let poisson = |lambda: f64, n: usize, mut state: u64| -> Vec<f32> {
+            (0..n)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    let u = (state >> 11) as f64 / (1u64 << 53) as f64;
+                    let (mut k, mut p) = (0u32, (-lambda).exp());
+                    let mut cdf = p;
+                    while u > cdf {
+                        k += 1;
+                        p *= lambda / k as f64;
+                        cdf += p;
+                    }
+                    k as f32
+                })
+                .collect()
+        };
+        let var = |x: &[f32]| {
+            let mean = x.iter().map(|&v| v as f64).sum::<f64>() / x.len() as f64;
+            x.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / x.len() as f64
+        };
+        for (lambda, seed) in [(5.0, 1), (50.0, 2), (400.0, 3)] {
+            let counts = poisson(lambda, 20_000, seed);
+            assert!((var(&counts) / lambda - 1.0).abs() < 0.1);
+            let stabilized = var(&anscombe_transform(&counts));
+            assert!(
+                (stabilized - 1.0).abs() < 0.1,
+                "lambda {lambda}: {stabilized}"
+            );
+        }
+
+        let x = [0.0_f32, 3.0, 100.0, 1e4];
+        let back = anscombe_inverse_transform(&anscombe_transform(&x));
+        for (&b, &v) in back.iter().zip(&x) {
+            // The bias term adds 1 / (4 * sqrt(y)), at most ~0.23 at x = 0.
+            assert!(b >= v && b - v < 0.25, "{v} -> {b}");
+        }
+        assert_eq!(anscombe_inverse_transform(&[-1.0, 0.0]), vec![0.0, 0.0]);
+    }
+
     #[test]
     fn window_size_computation() {
         // tau_d=0.4, fs=30 → kernel_len = ceil(5*0.4*30) = ceil(60) = 60 → window = 300
@@ -306,4 +820,216 @@ mod tests {
             );
         }
     }
+
+    /// The streaming estimator reproduces the batch baseline sample by sample.
+    #[test]
+    fn streaming_percentile_matches_batch() {
+        let mut rng_state = 7u64;
+        let mut next = || {
+            rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            ((rng_state >> 33) as f32) / (u32::MAX as f32 / 2.0) - 0.5
+        };
+        // Continuous values, then coarsely quantized ones (many ties).
+        let smooth: Vec<f32> = (0..1500).map(|_| next()).collect();
+        let ties: Vec<f32> = (0..600).map(|_| (next() * 8.0).round()).collect();
+
+        for trace in [&smooth, &ties] {
+            for (window, quantile) in [(1, 0.2), (7, 0.0), (50, 0.2), (300, 0.5), (64, 1.0)] {
+                let mut detrended = trace.clone();
+                subtract_rolling_baseline(&mut detrended, window, quantile);
+                let mut stream = StreamingPercentile::new(window, quantile);
+                for (t, (&v, &d)) in trace.iter().zip(&detrended).enumerate() {
+                    let b = stream.push(v);
+                    assert_eq!(v - b, d, "window {window} q {quantile} t {t}");
+                }
+            }
+        }
+
+        // Warm-up history counts toward the window.
+        let mut warm = StreamingPercentile::new(50, 0.2);
+        warm.warmup(&smooth[..100]);
+        let mut cold = StreamingPercentile::new(50, 0.2);
+        let mut last = 0.0;
+        for &v in &smooth[..101] {
+            last = cold.push(v);
+        }
+        assert_eq!(warm.push(smooth[100]), last);
+    }
+
+    #[test]
+    fn masked_samples_excluded_from_window() {
+        // A run of high (saturated) values that would dominate the window if
+        // counted; masked out, the floor stays at the surrounding level.
+        let mut trace = vec![1.0_f32; 60];
+        for v in &mut trace[20..50] {
+            *v = 100.0;
+        }
+        let excluded: Vec<bool> = trace.iter().map(|&v| v >= 100.0).collect();
+        subtract_rolling_baseline_masked(&mut trace, &excluded, 10, 0.2);
+
+        for &v in &trace[..20] {
+            assert!(v.abs() < 1e-6, "unmasked floor should be ~0, got {}", v);
+        }
+        // Inside the masked run the window is empty → baseline 1.0 carried forward.
+        for &v in &trace[30..50] {
+            assert!(
+                (v - 99.0).abs() < 1e-6,
+                "carried baseline expected, got {}",
+                v
+            );
+        }
+    }
+
+    #[test]
+    fn empty_mask_matches_unmasked() {
+        let mut a: Vec<f32> = (0..200).map(|i| ((i as f32) * 0.37).sin()).collect();
+        let mut b = a.clone();
+        subtract_rolling_baseline(&mut a, 30, 0.2);
+        subtract_rolling_baseline_masked(&mut b, &[false; 200], 30, 0.2);
+        assert_eq!(a, b);
+    }
+
+    /// Synthetic bleaching trace: F0·exp(-t/tau) + offset plus sparse
+    /// positive transients and deterministic jitter.
+    fn bleached_trace(n: usize, fs: f64, f0: f64, tau: f64, offset: f64) -> Vec<f32> {
+        let mut state: u64 = 7;
+        (0..n)
+            .map(|i| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let jitter = ((state >> 33) as f64 / (1u64 << 31) as f64 - 0.5) * 0.02;
+                let t = i as f64 / fs;
+                let transient = if i % 300 < 20 {
+                    0.5 * (-((i % 300) as f64) / 8.0).exp()
+                } else {
+                    0.0
+                };
+                (f0 * (-t / tau).exp() + offset + transient + jitter) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn photobleaching_fit_recovers_trend() {
+        // 100 s at 30 Hz, tau_bl = 150 s (> duration, so no clipping).
+        let trace = bleached_trace(3000, 30.0, 0.8, 150.0, 1.0);
+        let (f0, tau) = fit_photobleaching(&trace, 30.0);
+        assert!((tau / 150.0 - 1.0).abs() < 0.3, "tau_bl = {tau}");
+        assert!((f0 / 0.8 - 1.0).abs() < 0.3, "F0 = {f0}");
+
+        let mut corrected = trace.clone();
+        subtract_photobleaching(&mut corrected, f0, tau, 30.0);
+        let head: f32 = corrected[..300]
+            .iter()
+            .cloned()
+            .fold(f32::INFINITY, f32::min);
+        let tail: f32 = corrected[2700..]
+            .iter()
+            .cloned()
+            .fold(f32::INFINITY, f32::min);
+        assert!(
+            (head - tail).abs() < 0.05,
+            "floor should be flat after correction: head {head}, tail {tail}"
+        );
+    }
+
+    #[test]
+    fn photobleaching_edge_cases() {
+        // Fast decay (tau < duration) is clipped to 10x duration.
+        let trace = bleached_trace(3000, 30.0, 0.8, 20.0, 1.0);
+        let (_, tau) = fit_photobleaching(&trace, 30.0);
+        assert!(
+            (tau - 1000.0).abs() < 1e-9,
+            "expected clip to 1000 s, got {tau}"
+        );
+
+        // Negative F0 leaves the trace untouched.
+        let mut t = vec![1.0_f32, 2.0, 3.0];
+        subtract_photobleaching(&mut t, -0.5, 10.0, 30.0);
+        assert_eq!(t, vec![1.0, 2.0, 3.0]);
+
+        // Single sample: no-op fit.
+        let (f0, tau) = fit_photobleaching(&[5.0], 30.0);
+        let mut one = vec![5.0_f32];
+        subtract_photobleaching(&mut one, f0, tau, 30.0);
+        assert_eq!(one, vec![5.0]);
+    }
+
+    #[test]
+    fn rolling_variance_matches_direct() {
+        let trace: Vec<f32> = (0..200)
+            .map(|i| ((i * 37 % 11) as f32) * 0.3 + (i as f32 * 0.05).sin())
+            .collect();
+        let window = 16;
+        let var = rolling_variance(&trace, window);
+        assert_eq!(var.len(), trace.len());
+        let direct = |span: &[f32]| {
+            let m = span.iter().map(|&v| v as f64).sum::<f64>() / span.len() as f64;
+            span.iter().map(|&v| (v as f64 - m).powi(2)).sum::<f64>() / span.len() as f64
+        };
+        for t in window - 1..trace.len() {
+            let d = direct(&trace[t + 1 - window..=t]);
+            assert!((var[t] as f64 - d).abs() < 1e-4, "t={t}: {} vs {d}", var[t]);
+        }
+        assert!(var[..window - 1].iter().all(|&v| v == var[window - 1]));
+
+        let std = rolling_std(&trace, window);
+        assert!((std[100] - var[100].sqrt()).abs() < 1e-6);
+
+        // A window past the end uses the whole trace.
+        let whole = rolling_variance(&trace, 1000);
+        assert!(whole
+            .iter()
+            .all(|&v| (v as f64 - direct(&trace)).abs() < 1e-4));
+        assert!(rolling_variance(&trace, 0).iter().all(|&v| v == 0.0));
+        assert!(rolling_variance(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn noise_floor_ignores_transients() {
+        // Alternating ±0.1 noise (std 0.1) with large transients on top.
+        let mut trace: Vec<f32> = (0..2000)
+            .map(|i| if i % 2 == 0 { 0.1 } else { -0.1 })
+            .collect();
+        for s in (100..2000).step_by(400) {
+            for (k, v) in trace[s..(s + 60).min(2000)].iter_mut().enumerate() {
+                *v += 5.0 * (-(k as f32) / 15.0).exp();
+            }
+        }
+        let floor = estimate_noise_floor(&trace, 20, 10.0);
+        assert!((floor - 0.1).abs() < 0.01, "noise floor {floor}");
+        assert!(estimate_noise_floor(&trace, 20, 100.0) > 1.0);
+        assert!(estimate_noise_floor(&[], 20, 10.0).is_nan());
+    }
+
+    #[test]
+    fn dff_against_rolling_and_fixed_f0() {
+        // Floor of 2 with a transient to 3: ΔF/F0 of 0.5 at the peak.
+        let mut trace = vec![2.0_f32; 100];
+        trace[40] = 3.0;
+        let (dff, warnings) = compute_dff_rolling(&trace, 20, 0.2);
+        assert!(warnings.is_empty());
+        assert!((dff[40] - 0.5).abs() < 1e-6);
+        assert!(dff[..40].iter().all(|&v| v.abs() < 1e-6));
+
+        let (dff, warnings) = compute_dff_global(&trace, 2.0);
+        assert!(warnings.is_empty());
+        assert_eq!(dff[40], 0.5);
+        assert_eq!(dff[0], 0.0);
+
+        // A zero floor falls back to F - F0 and says so.
+        let (dff, warnings) = compute_dff_global(&trace, 0.0);
+        assert_eq!(dff, trace);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, crate::validate::Severity::Warning);
+
+        let mut centred = trace.clone();
+        centred[..50].fill(0.0);
+        centred[70] = 2.5;
+        let (dff, warnings) = compute_dff_rolling(&centred, 20, 0.2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.starts_with("F0 below"));
+        assert!((dff[70] - 0.25).abs() < 1e-6);
+    }
 }
@@ -1,3 +1,6 @@
+use crate::indeca::lcg_gaussian;
+use crate::CalabError;
+
 /// Two-component bi-exponential fitting: extract tau_rise and tau_decay from a free-form kernel.
 ///
 /// # Physical motivation
@@ -266,6 +269,237 @@ pub fn fit_biexponential(
     best
 }
 
+/// Fit a slow-only bi-exponential with the time constants restricted to
+/// `tau_r ∈ [tau_r_min, tau_r_max]` and `tau_d ∈ [tau_d_min, tau_d_max]` (seconds).
+///
+/// For kernels where the indicator's kinetics are known to lie in a range, so the
+/// unconstrained fit cannot wander to an implausible corner. Runs a 20×20 log grid
+/// over the box, then (when `refine`) alternating golden-section passes that stay
+/// inside it. There is no fast component: the bounds describe the calcium kernel
+/// only. The ranges must not overlap (`tau_r_min < tau_r_max < tau_d_min`), so
+/// every pair in the box has `tau_d > tau_r`.
+pub fn fit_biexponential_bounded(
+    h_free: &[f32],
+    fs: f64,
+    tau_r_min: f64,
+    tau_r_max: f64,
+    tau_d_min: f64,
+    tau_d_max: f64,
+    refine: bool,
+) -> Result<BiexpResult, CalabError> {
+    if !(fs.is_finite() && fs > 0.0) {
+        return Err(CalabError::invalid("fs", fs, "must be positive and finite"));
+    }
+    for (name, lo, hi) in [
+        ("tau_r_min", tau_r_min, tau_r_max),
+        ("tau_d_min", tau_d_min, tau_d_max),
+    ] {
+        if !(lo.is_finite() && hi.is_finite() && lo > 0.0 && lo <= hi) {
+            return Err(CalabError::invalid(
+                name,
+                lo,
+                format!("bounds must be positive, finite and min <= max (max = {hi})"),
+            ));
+        }
+    }
+    if tau_r_min >= tau_r_max {
+        return Err(CalabError::invalid(
+            "tau_r_min",
+            tau_r_min,
+            format!("must be below tau_r_max = {tau_r_max}"),
+        ));
+    }
+    if tau_d_min <= tau_r_max {
+        return Err(CalabError::invalid(
+            "tau_d_min",
+            tau_d_min,
+            format!("must exceed tau_r_max = {tau_r_max} (decay slower than rise)"),
+        ));
+    }
+    if h_free.is_empty() {
+        return Ok(BiexpResult::sentinel());
+    }
+
+    let dt = 1.0 / fs;
+    let grid_n = 20;
+    // Clamped so exp/ln round-off cannot step outside the box at the endpoints.
+    let log_space = |lo: f64, hi: f64, i: usize| {
+        (lo.ln() + (hi.ln() - lo.ln()) * i as f64 / (grid_n - 1) as f64)
+            .exp()
+            .clamp(lo, hi)
+    };
+
+    let mut best = BiexpResult::sentinel();
+    for i in 0..grid_n {
+        let tau_r = log_space(tau_r_min, tau_r_max, i);
+        for j in 0..grid_n {
+            let tau_d = log_space(tau_d_min, tau_d_max, j);
+            let (beta, _, residual) = eval_two_component(h_free, tau_r, tau_d, 0.0, 0.0, dt, 0);
+            if residual < best.residual {
+                best = BiexpResult {
+                    tau_rise: tau_r,
+                    tau_decay: tau_d,
+                    beta,
+                    residual,
+                    tau_rise_fast: 0.0,
+                    tau_decay_fast: 0.0,
+                    beta_fast: 0.0,
+                    fit_mode: FitMode::SlowOnly,
+                };
+            }
+        }
+    }
+
+    if refine && best.residual.is_finite() {
+        let (mut tau_r, mut tau_d) = (best.tau_rise, best.tau_decay);
+        for step in 0..40 {
+            if step % 2 == 0 {
+                let lo = (tau_r * 0.5).max(tau_r_min);
+                let hi = (tau_r * 2.0).min(tau_r_max).min(tau_d * 0.99);
+                if lo < hi {
+                    tau_r = golden_bracket(lo, hi, |x| {
+                        eval_two_component(h_free, x, tau_d, 0.0, 0.0, dt, 0).2
+                    });
+                }
+            } else {
+                let lo = (tau_d * 0.5).max(tau_d_min).max(tau_r * 1.01);
+                let hi = (tau_d * 2.0).min(tau_d_max);
+                if lo < hi {
+                    tau_d = golden_bracket(lo, hi, |x| {
+                        eval_two_component(h_free, tau_r, x, 0.0, 0.0, dt, 0).2
+                    });
+                }
+            }
+        }
+        let (beta, _, residual) = eval_two_component(h_free, tau_r, tau_d, 0.0, 0.0, dt, 0);
+        if residual < best.residual {
+            best.tau_rise = tau_r;
+            best.tau_decay = tau_d;
+            best.beta = beta;
+            best.residual = residual;
+        }
+    }
+
+    best.fit_mode = best.classify();
+    Ok(best)
+}
+
+/// Sample the slow template `beta * (exp(-t/tau_d) - exp(-t/tau_r))` at
+/// `t = i / fs` for `i < n_samples` — the curve a [`BiexpResult`] describes,
+/// for plotting against the free kernel.
+pub fn eval_biexp(tau_rise: f64, tau_decay: f64, beta: f64, fs: f64, n_samples: usize) -> Vec<f32> {
+    let dt = 1.0 / fs;
+    (0..n_samples)
+        .map(|i| {
+            let t = i as f64 * dt;
+            (beta * ((-t / tau_decay).exp() - (-t / tau_rise).exp())) as f32
+        })
+        .collect()
+}
+
+/// Spread of the bi-exponential parameters across bootstrap replicates, from
+/// [`fit_biexponential_bootstrap`]. Standard deviations are sample (n − 1)
+/// estimates; `raw_results` holds every replicate fit in draw order.
+#[derive(Clone)]
+#[cfg_attr(feature = "jsbindings", derive(serde::Serialize))]
+pub struct BiexpBootstrap {
+    pub tau_rise_mean: f64,
+    pub tau_rise_std: f64,
+    pub tau_decay_mean: f64,
+    pub tau_decay_std: f64,
+    pub beta_mean: f64,
+    pub beta_std: f64,
+    pub raw_results: Vec<BiexpResult>,
+}
+
+/// Parametric-bootstrap uncertainty for [`fit_biexponential`].
+///
+/// Fits `h_free` once, takes the RMS of the residual against the fitted
+/// template (slow plus fast component) as the noise level, then refits
+/// `n_bootstrap` copies of `h_free` perturbed with Gaussian noise of that
+/// standard deviation (seeded LCG, so results are reproducible). With
+/// `n_bootstrap == 0` the means are the point estimate, the deviations zero
+/// and `raw_results` empty.
+pub fn fit_biexponential_bootstrap(
+    h_free: &[f32],
+    fs: f64,
+    n_bootstrap: usize,
+    seed: u64,
+    refine: bool,
+) -> BiexpBootstrap {
+    let n = h_free.len();
+    let best = fit_biexponential(h_free, fs, refine, 0, None);
+
+    let raw_results: Vec<BiexpResult> = if n_bootstrap == 0 || n == 0 {
+        Vec::new()
+    } else {
+        let template = eval_biexp(best.tau_rise, best.tau_decay, best.beta, fs, n);
+        let fast = if best.has_fast_component() {
+            eval_biexp(
+                best.tau_rise_fast,
+                best.tau_decay_fast,
+                best.beta_fast,
+                fs,
+                n,
+            )
+        } else {
+            vec![0.0; n]
+        };
+        let sse: f64 = h_free
+            .iter()
+            .zip(template.iter().zip(&fast))
+            .map(|(&h, (&s, &f))| (h as f64 - s as f64 - f as f64).powi(2))
+            .sum();
+        let sigma = (sse / n as f64).sqrt();
+
+        let mut state = seed;
+        let mut perturbed = vec![0.0_f32; n];
+        (0..n_bootstrap)
+            .map(|_| {
+                for (p, &h) in perturbed.iter_mut().zip(h_free) {
+                    *p = h + (sigma * lcg_gaussian(&mut state)) as f32;
+                }
+                fit_biexponential(&perturbed, fs, refine, 0, None)
+            })
+            .collect()
+    };
+
+    if raw_results.is_empty() {
+        return BiexpBootstrap {
+            tau_rise_mean: best.tau_rise,
+            tau_rise_std: 0.0,
+            tau_decay_mean: best.tau_decay,
+            tau_decay_std: 0.0,
+            beta_mean: best.beta,
+            beta_std: 0.0,
+            raw_results,
+        };
+    }
+    let (tau_rise_mean, tau_rise_std) = mean_std(raw_results.iter().map(|r| r.tau_rise));
+    let (tau_decay_mean, tau_decay_std) = mean_std(raw_results.iter().map(|r| r.tau_decay));
+    let (beta_mean, beta_std) = mean_std(raw_results.iter().map(|r| r.beta));
+    BiexpBootstrap {
+        tau_rise_mean,
+        tau_rise_std,
+        tau_decay_mean,
+        tau_decay_std,
+        beta_mean,
+        beta_std,
+        raw_results,
+    }
+}
+
+/// Mean and sample standard deviation (zero for a single value).
+fn mean_std(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let count = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / count;
+    if count < 2.0 {
+        return (mean, 0.0);
+    }
+    let var = values.map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    (mean, var.sqrt())
+}
+
 /// Refine a candidate BiexpResult in-place via golden-section search.
 fn refine_candidate(
     h_free: &[f32],
@@ -775,6 +1009,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bounded_fit_stays_in_box() {
+        let fs = 30.0;
+        let h = make_biexp(0.08, 0.5, 2.0, fs, 60);
+        assert_eq!(eval_biexp(0.08, 0.5, 2.0, fs, 60), h);
+
+        // Truth inside the box: recovered as by the unconstrained fit.
+        let r = fit_biexponential_bounded(&h, fs, 0.02, 0.15, 0.2, 1.0, true).unwrap();
+        assert!((r.tau_rise - 0.08).abs() / 0.08 < 0.05, "{}", r.tau_rise);
+        assert!((r.tau_decay - 0.5).abs() / 0.5 < 0.05, "{}", r.tau_decay);
+        assert_eq!(r.fit_mode, FitMode::SlowOnly);
+
+        // Truth outside the box: the fit is pinned within it.
+        let r = fit_biexponential_bounded(&h, fs, 0.01, 0.04, 0.8, 2.0, true).unwrap();
+        assert!((0.01..=0.04).contains(&r.tau_rise), "{}", r.tau_rise);
+        assert!((0.8..=2.0).contains(&r.tau_decay), "{}", r.tau_decay);
+
+        assert!(fit_biexponential_bounded(&h, fs, 0.2, 0.1, 0.2, 1.0, true).is_err());
+        assert!(fit_biexponential_bounded(&h, fs, 0.0, 0.1, 0.2, 1.0, true).is_err());
+        assert!(fit_biexponential_bounded(&h, 0.0, 0.02, 0.1, 0.2, 1.0, true).is_err());
+        // Degenerate or overlapping ranges are rejected.
+        assert!(fit_biexponential_bounded(&h, fs, 0.1, 0.1, 0.2, 1.0, true).is_err());
+        assert!(fit_biexponential_bounded(&h, fs, 0.02, 0.3, 0.2, 1.0, true).is_err());
+        assert!(fit_biexponential_bounded(&h, fs, 0.5, 1.0, 0.1, 0.2, true).is_err());
+        let empty = fit_biexponential_bounded(&[], fs, 0.02, 0.1, 0.2, 1.0, true).unwrap();
+        assert_eq!(empty.fit_mode, FitMode::Empty);
+    }
+
+    #[test]
+    fn bootstrap_spread_tracks_noise() {
+        let fs = 30.0;
+        let clean = make_biexp(0.08, 0.5, 1.0, fs, 90);
+        let mut state = 0x5EED_u64;
+        let noisy: Vec<f32> = clean
+            .iter()
+            .map(|&v| v + (0.02 * lcg_gaussian(&mut state)) as f32)
+            .collect();
+
+        let boot = fit_biexponential_bootstrap(&noisy, fs, 30, 42, true);
+        assert_eq!(boot.raw_results.len(), 30);
+        assert!(boot.tau_decay_std > 0.0 && boot.tau_rise_std > 0.0);
+        assert!(
+            (boot.tau_decay_mean - 0.5).abs() < 3.0 * boot.tau_decay_std + 0.05,
+            "tau_decay {} ± {}",
+            boot.tau_decay_mean,
+            boot.tau_decay_std
+        );
+
+        // Same seed, same replicates; a clean kernel leaves almost no spread.
+        let again = fit_biexponential_bootstrap(&noisy, fs, 30, 42, true);
+        assert_eq!(again.tau_decay_mean, boot.tau_decay_mean);
+        let clean_boot = fit_biexponential_bootstrap(&clean, fs, 30, 42, true);
+        assert!(clean_boot.tau_decay_std < boot.tau_decay_std);
+
+        let point = fit_biexponential_bootstrap(&noisy, fs, 0, 42, true);
+        assert!(point.raw_results.is_empty() && point.tau_decay_std == 0.0);
+    }
+
     #[test]
     fn clean_biexp_has_near_zero_beta_fast() {
         let h = make_biexp(0.08, 0.5, 2.0, 30.0, 60);
@@ -0,0 +1,413 @@
+//! Fluent builders for `Solver` and the InDeCa single-trace solve.
+//!
+//! `Solver` is configured through a sequence of `set_*` calls whose order
+//! matters (`set_params` before `set_trace`, `set_conv_mode` after
+//! `set_params`). The builders collect the configuration, validate it once in
+//! `build()`, and apply it in the right order, so a missing or out-of-range
+//! parameter is reported instead of silently producing a wrong solve.
+
+use crate::indeca::{self, BoundaryMode, InDecaResult, SolveOptions};
+use crate::upsample::UpsampleMethod;
+use crate::{CalabError, Constraint, ConvMode, Solver};
+
+fn require(value: Option<f64>, name: &str) -> Result<f64, CalabError> {
+    value.ok_or_else(|| CalabError::invalid(name, "<unset>", "required parameter was not set"))
+}
+
+/// Shared kernel / rate validation for both builders.
+fn check_kernel(tau_rise: f64, tau_decay: f64, fs: f64) -> Result<(), CalabError> {
+    if !(tau_decay > 0.0 && tau_decay.is_finite()) {
+        return Err(CalabError::invalid(
+            "tau_decay",
+            tau_decay,
+            "must be positive and finite",
+        ));
+    }
+    if !(tau_rise >= 0.0 && tau_rise < tau_decay) {
+        return Err(CalabError::invalid(
+            "tau_rise",
+            tau_rise,
+            format!("must be in [0, tau_decay) = [0, {tau_decay})"),
+        ));
+    }
+    if !(fs > 0.0 && fs.is_finite()) {
+        return Err(CalabError::invalid("fs", fs, "must be positive and finite"));
+    }
+    Ok(())
+}
+
+fn check_lambda(lambda: f64) -> Result<(), CalabError> {
+    if !(lambda >= 0.0 && lambda.is_finite()) {
+        return Err(CalabError::invalid(
+            "lambda",
+            lambda,
+            "must be non-negative and finite",
+        ));
+    }
+    Ok(())
+}
+
+/// Builder for a configured [`Solver`].
+///
+/// `tau_rise`, `tau_decay`, `lambda` and `fs` are required; the engine,
+/// constraint and filter settings default to those of `Solver::new()`, with
+/// the engine chosen by `set_params` (`BandedAR1` for `tau_rise = 0`).
+#[derive(Clone, Debug, Default)]
+pub struct SolverBuilder {
+    tau_rise: Option<f64>,
+    tau_decay: Option<f64>,
+    lambda: Option<f64>,
+    fs: Option<f64>,
+    conv_mode: Option<ConvMode>,
+    constraint: Option<Constraint>,
+    hp_filter: bool,
+    lp_filter: bool,
+}
+
+impl SolverBuilder {
+    pub fn tau_rise(mut self, tau_rise: f64) -> Self {
+        self.tau_rise = Some(tau_rise);
+        self
+    }
+
+    pub fn tau_decay(mut self, tau_decay: f64) -> Self {
+        self.tau_decay = Some(tau_decay);
+        self
+    }
+
+    pub fn lambda(mut self, lambda: f64) -> Self {
+        self.lambda = Some(lambda);
+        self
+    }
+
+    pub fn fs(mut self, fs: f64) -> Self {
+        self.fs = Some(fs);
+        self
+    }
+
+    pub fn conv_mode(mut self, mode: ConvMode) -> Self {
+        self.conv_mode = Some(mode);
+        self
+    }
+
+    pub fn constraint(mut self, constraint: Constraint) -> Self {
+        self.constraint = Some(constraint);
+        self
+    }
+
+    /// Enable the high-pass half of the bandpass filter (off by default).
+    pub fn hp_filter(mut self, enabled: bool) -> Self {
+        self.hp_filter = enabled;
+        self
+    }
+
+    /// Enable the low-pass half of the bandpass filter (off by default).
+    pub fn lp_filter(mut self, enabled: bool) -> Self {
+        self.lp_filter = enabled;
+        self
+    }
+
+    /// Validate the configuration and build the solver.
+    ///
+    /// Errors name the first missing or invalid parameter. `BandedAR1` is
+    /// only accepted with `tau_rise = 0`, the single-exponential kernel it
+    /// models.
+    pub fn build(&self) -> Result<Solver, CalabError> {
+        let tau_rise = require(self.tau_rise, "tau_rise")?;
+        let tau_decay = require(self.tau_decay, "tau_decay")?;
+        let lambda = require(self.lambda, "lambda")?;
+        let fs = require(self.fs, "fs")?;
+        check_kernel(tau_rise, tau_decay, fs)?;
+        check_lambda(lambda)?;
+        if self.conv_mode == Some(ConvMode::BandedAR1) && tau_rise != 0.0 {
+            return Err(CalabError::invalid(
+                "tau_rise",
+                tau_rise,
+                "conv_mode BandedAR1 needs tau_rise = 0 (single exponential)",
+            ));
+        }
+
+        let mut solver = Solver::new();
+        solver.set_params(tau_rise, tau_decay, lambda, fs);
+        if let Some(mode) = self.conv_mode {
+            solver.set_conv_mode(mode);
+        }
+        if let Some(constraint) = self.constraint {
+            solver.set_constraint(constraint);
+        }
+        solver.set_hp_filter_enabled(self.hp_filter);
+        solver.set_lp_filter_enabled(self.lp_filter);
+        Ok(solver)
+    }
+}
+
+/// Builder for InDeCa single-trace solves ([`indeca::solve_trace_opts`]).
+///
+/// `tau_rise`, `tau_decay` and `fs` are required. Defaults match the Python
+/// `solve_trace`: no upsampling, 500 iterations, tol 1e-4, filters off,
+/// `lambda = 0` and all `SolveOptions` off.
+#[derive(Clone, Debug)]
+pub struct InDecaBuilder {
+    tau_rise: Option<f64>,
+    tau_decay: Option<f64>,
+    fs: Option<f64>,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_filter: bool,
+    lp_filter: bool,
+    lambda: f64,
+    opts: SolveOptions,
+}
+
+impl Default for InDecaBuilder {
+    fn default() -> Self {
+        InDecaBuilder {
+            tau_rise: None,
+            tau_decay: None,
+            fs: None,
+            upsample_factor: 1,
+            max_iters: 500,
+            tol: 1e-4,
+            hp_filter: false,
+            lp_filter: false,
+            lambda: 0.0,
+            opts: SolveOptions::default(),
+        }
+    }
+}
+
+impl InDecaBuilder {
+    pub fn tau_rise(mut self, tau_rise: f64) -> Self {
+        self.tau_rise = Some(tau_rise);
+        self
+    }
+
+    pub fn tau_decay(mut self, tau_decay: f64) -> Self {
+        self.tau_decay = Some(tau_decay);
+        self
+    }
+
+    pub fn fs(mut self, fs: f64) -> Self {
+        self.fs = Some(fs);
+        self
+    }
+
+    pub fn upsample_factor(mut self, factor: usize) -> Self {
+        self.upsample_factor = factor;
+        self
+    }
+
+    pub fn max_iters(mut self, max_iters: u32) -> Self {
+        self.max_iters = max_iters;
+        self
+    }
+
+    pub fn tol(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    pub fn hp_filter(mut self, enabled: bool) -> Self {
+        self.hp_filter = enabled;
+        self
+    }
+
+    pub fn lp_filter(mut self, enabled: bool) -> Self {
+        self.lp_filter = enabled;
+        self
+    }
+
+    /// Fixed sparsity penalty; 0 lets the threshold search alone set sparsity.
+    pub fn lambda(mut self, lambda: f64) -> Self {
+        self.lambda = lambda;
+        self
+    }
+
+    pub fn noise_constrained(mut self, enabled: bool) -> Self {
+        self.opts.noise_constrained = enabled;
+        self
+    }
+
+    pub fn saturation_value(mut self, value: Option<f32>) -> Self {
+        self.opts.saturation_value = value;
+        self
+    }
+
+    pub fn photobleaching_correction(mut self, enabled: bool) -> Self {
+        self.opts.photobleaching_correction = enabled;
+        self
+    }
+
+    pub fn boundary_mode(mut self, mode: BoundaryMode) -> Self {
+        self.opts.boundary_mode = mode;
+        self
+    }
+
+    pub fn upsample_method(mut self, method: UpsampleMethod) -> Self {
+        self.opts.upsample_method = method;
+        self
+    }
+
+    pub fn variance_stabilize(mut self, enabled: bool) -> Self {
+        self.opts.variance_stabilize = enabled;
+        self
+    }
+
+    /// Validate the configuration. Errors name the first missing or invalid
+    /// parameter.
+    pub fn build(&self) -> Result<InDecaConfig, CalabError> {
+        let tau_rise = require(self.tau_rise, "tau_rise")?;
+        let tau_decay = require(self.tau_decay, "tau_decay")?;
+        let fs = require(self.fs, "fs")?;
+        check_kernel(tau_rise, tau_decay, fs)?;
+        check_lambda(self.lambda)?;
+        if self.upsample_factor == 0 {
+            return Err(CalabError::invalid(
+                "upsample_factor",
+                0,
+                "must be at least 1",
+            ));
+        }
+        if self.tol.is_nan() || self.tol <= 0.0 {
+            return Err(CalabError::invalid("tol", self.tol, "must be positive"));
+        }
+        Ok(InDecaConfig {
+            tau_rise,
+            tau_decay,
+            fs,
+            upsample_factor: self.upsample_factor,
+            max_iters: self.max_iters,
+            tol: self.tol,
+            hp_filter: self.hp_filter,
+            lp_filter: self.lp_filter,
+            lambda: self.lambda,
+            opts: self.opts.clone(),
+        })
+    }
+}
+
+/// Validated InDeCa solve parameters, produced by [`InDecaBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct InDecaConfig {
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_filter: bool,
+    lp_filter: bool,
+    lambda: f64,
+    opts: SolveOptions,
+}
+
+impl InDecaConfig {
+    /// Solve one trace, optionally warm-started from a previous result's
+    /// spike counts (at the original sampling rate).
+    pub fn solve(
+        &self,
+        trace: &[f32],
+        warm_counts: Option<&[f32]>,
+    ) -> Result<InDecaResult, CalabError> {
+        indeca::solve_trace_opts(
+            trace,
+            self.tau_rise,
+            self.tau_decay,
+            self.fs,
+            self.upsample_factor,
+            self.max_iters,
+            self.tol,
+            warm_counts,
+            self.hp_filter,
+            self.lp_filter,
+            self.lambda,
+            self.opts.clone(),
+            None,
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::build_kernel;
+
+    #[test]
+    fn solver_builder_applies_configuration() {
+        let solver = SolverBuilder::default()
+            .tau_rise(0.02)
+            .tau_decay(0.4)
+            .lambda(0.01)
+            .fs(30.0)
+            .conv_mode(ConvMode::BandedAR2)
+            .constraint(Constraint::Box01)
+            .build()
+            .unwrap();
+        assert!(solver.conv_mode == ConvMode::BandedAR2);
+        assert!(solver.constraint == Constraint::Box01);
+        assert_eq!(solver.kernel, build_kernel(0.02, 0.4, 30.0));
+    }
+
+    #[test]
+    fn solver_builder_reports_missing_and_invalid_parameters() {
+        let err = SolverBuilder::default()
+            .tau_rise(0.02)
+            .lambda(0.01)
+            .fs(30.0)
+            .build()
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&err, CalabError::InvalidParameter { name, .. } if name == "tau_decay"),
+            "{err}"
+        );
+
+        let base = SolverBuilder::default()
+            .tau_decay(0.4)
+            .lambda(0.01)
+            .fs(30.0);
+        assert!(base.clone().tau_rise(0.5).build().is_err());
+        assert!(base.clone().tau_rise(0.02).fs(0.0).build().is_err());
+        assert!(base
+            .clone()
+            .tau_rise(0.02)
+            .conv_mode(ConvMode::BandedAR1)
+            .build()
+            .is_err());
+        let ar1 = base.tau_rise(0.0).build().unwrap();
+        assert!(ar1.conv_mode == ConvMode::BandedAR1);
+    }
+
+    #[test]
+    fn indeca_builder_matches_direct_call() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let mut trace = vec![0.0_f32; 300];
+        for &s in &[30usize, 120, 200] {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if s + k < trace.len() {
+                    trace[s + k] += kv;
+                }
+            }
+        }
+        let config = InDecaBuilder::default()
+            .tau_rise(0.02)
+            .tau_decay(0.4)
+            .fs(30.0)
+            .build()
+            .unwrap();
+        let via_builder = config.solve(&trace, None).unwrap();
+        let direct = indeca::solve_trace(
+            &trace, 0.02, 0.4, 30.0, 1, 500, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        assert_eq!(via_builder.s_counts, direct.s_counts);
+
+        assert!(InDecaBuilder::default()
+            .tau_rise(0.02)
+            .fs(30.0)
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! Binary checkpoints of a `Solver` mid-solve.
+//!
+//! A checkpoint holds everything needed to resume FISTA where it stopped:
+//! the model parameters, the (possibly filtered) trace, the iterates and the
+//! step-size state. The format is a small hand-rolled layout rather than a
+//! serde encoding so that files stay readable across dependency upgrades:
+//!
+//! ```text
+//! magic   b"CLCK"
+//! version u8 (currently 2; version 1 files load with an auto kernel length)
+//! f64     tau_rise, tau_decay, lambda, fs, tolerance, lambda2,
+//!         lipschitz_constant, ls_scale, t_fista, baseline, baseline_ema
+//! u32     iteration, refractory_frames
+//! u8      conv_mode, constraint, kernel_norm, memory_mode, kernel tag,
+//!         flags (converged | filtered << 1 | baseline_ema_init << 2 | line_search << 3)
+//! f64     tau_fast, tau_slow, fraction_fast   (kernel tag 1 only)
+//! u8, f64 kernel length mode (0 auto, 1 frames, 2 seconds) and its value
+//!         (version 2 and later)
+//! arrays  kernel, trace, solution, solution_prev, gradient
+//!         (each a u32 length followed by little-endian f32 values)
+//! ```
+//!
+//! All integers and floats are little-endian. The solver mode, stopping
+//! criterion and filter selection are not saved; set them again after
+//! loading if they differ from the defaults.
+
+use std::path::Path;
+
+use crate::io::{format_err, IoError};
+use crate::{
+    validate, CalabError, Constraint, ConvMode, KernelLengthMode, KernelNorm, KernelType,
+    MemoryMode, Solver,
+};
+
+const MAGIC: &[u8; 4] = b"CLCK";
+const VERSION: u8 = 2;
+
+const KERNEL_DOUBLE_EXP: u8 = 0;
+const KERNEL_TRIPLE_EXP: u8 = 1;
+
+impl Solver {
+    /// Write the complete solve state to `path` (see the module docs for the
+    /// layout). AR(p) kernels (`set_ar_params`) cannot be checkpointed: their
+    /// time constants are not kept after the model is built.
+    pub fn save_checkpoint(&self, path: &Path) -> Result<(), IoError> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        for v in [
+            self.tau_rise,
+            self.tau_decay,
+            self.lambda,
+            self.fs,
+            self.tolerance,
+            self.lambda2,
+            self.lipschitz_constant,
+            self.ls_scale,
+            self.t_fista,
+            self.baseline,
+            self.baseline_ema,
+        ] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.iteration.to_le_bytes());
+        buf.extend_from_slice(&(self.refractory_frames as u32).to_le_bytes());
+
+        let kernel_tag = match self.kernel_type {
+            KernelType::DoubleExp => KERNEL_DOUBLE_EXP,
+            KernelType::TripleExp { .. } => KERNEL_TRIPLE_EXP,
+            KernelType::ARp { .. } => {
+                return Err(format_err("AR(p) kernels cannot be checkpointed"))
+            }
+        };
+        let flags = self.converged as u8
+            | (self.filtered as u8) << 1
+            | (self.baseline_ema_init as u8) << 2
+            | (self.line_search as u8) << 3;
+        buf.extend_from_slice(&[
+            self.conv_mode as u8,
+            self.constraint as u8,
+            self.kernel_norm as u8,
+            self.memory_mode as u8,
+            kernel_tag,
+            flags,
+        ]);
+        if let KernelType::TripleExp {
+            tau_fast,
+            tau_slow,
+            fraction_fast,
+        } = self.kernel_type
+        {
+            for v in [tau_fast, tau_slow, fraction_fast] {
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        let (length_tag, length_value) = match self.kernel_length_mode {
+            KernelLengthMode::Auto => (0_u8, 0.0),
+            KernelLengthMode::FixedFrames(n) => (1, n as f64),
+            KernelLengthMode::FixedSeconds(s) => (2, s),
+        };
+        buf.push(length_tag);
+        buf.extend_from_slice(&length_value.to_le_bytes());
+
+        let n = self.active_len;
+        for array in [
+            &self.kernel[..],
+            &self.trace[..n],
+            &self.solution[..n],
+            &self.solution_prev[..n],
+            &self.gradient[..n],
+        ] {
+            buf.extend_from_slice(&(array.len() as u32).to_le_bytes());
+            for v in array {
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+
+        std::fs::write(path, buf)?;
+        Ok(())
+    }
+
+    /// Rebuild a solver from a file written by `save_checkpoint`.
+    ///
+    /// Saved parameters that `validate::validate_solver_params` rejects
+    /// return `IoError::Solver` with `CalabError::InvalidParameter`. The
+    /// kernel is rebuilt from the saved parameters and must have the saved
+    /// length, otherwise this returns `IoError::Solver` with
+    /// `CalabError::DimensionMismatch`; the saved kernel values are then used
+    /// as is. An unknown magic, an unsupported version or a truncated file is
+    /// an `IoError::Format`. The next `step_batch` continues from the saved
+    /// iterate and momentum; only the objective history used for convergence
+    /// starts over.
+    pub fn load_checkpoint(path: &Path) -> Result<Solver, IoError> {
+        let bytes = std::fs::read(path)?;
+        let mut r = Reader { bytes: &bytes };
+        if r.take(4)? != MAGIC {
+            return Err(format_err("not a solver checkpoint (bad magic)"));
+        }
+        let version = r.u8()?;
+        if version == 0 || version > VERSION {
+            return Err(format_err(format!(
+                "unsupported checkpoint version {version} (expected 1 to {VERSION})"
+            )));
+        }
+
+        let tau_rise = r.f64()?;
+        let tau_decay = r.f64()?;
+        let lambda = r.f64()?;
+        let fs = r.f64()?;
+        let tolerance = r.f64()?;
+        let lambda2 = r.f64()?;
+        let lipschitz_constant = r.f64()?;
+        let ls_scale = r.f64()?;
+        let t_fista = r.f64()?;
+        let baseline = r.f64()?;
+        let baseline_ema = r.f64()?;
+        let iteration = r.u32()?;
+        let refractory_frames = r.u32()? as usize;
+        let conv_mode = match r.u8()? {
+            0 => ConvMode::Fft,
+            1 => ConvMode::BandedAR2,
+            2 => ConvMode::BandedAR1,
+            3 => ConvMode::BandedARp,
+            4 => ConvMode::FftOverlapAdd,
+            v => return Err(format_err(format!("unknown conv mode {v}"))),
+        };
+        let constraint = match r.u8()? {
+            0 => Constraint::NonNegative,
+            1 => Constraint::Box01,
+            v => return Err(format_err(format!("unknown constraint {v}"))),
+        };
+        let kernel_norm = match r.u8()? {
+            0 => KernelNorm::Peak,
+            1 => KernelNorm::Area,
+            2 => KernelNorm::L2,
+            3 => KernelNorm::None,
+            v => return Err(format_err(format!("unknown kernel norm {v}"))),
+        };
+        let memory_mode = match r.u8()? {
+            0 => MemoryMode::Standard,
+            1 => MemoryMode::Low,
+            v => return Err(format_err(format!("unknown memory mode {v}"))),
+        };
+        let kernel_tag = r.u8()?;
+        let flags = r.u8()?;
+
+        let mut solver = Solver::new();
+        solver.memory_mode = memory_mode;
+        solver.kernel_norm = kernel_norm;
+        let triple_exp = match kernel_tag {
+            KERNEL_DOUBLE_EXP => None,
+            KERNEL_TRIPLE_EXP => Some((r.f64()?, r.f64()?, r.f64()?)),
+            v => return Err(format_err(format!("unknown kernel type {v}"))),
+        };
+        if version >= 2 {
+            solver.kernel_length_mode = match (r.u8()?, r.f64()?) {
+                (0, _) => KernelLengthMode::Auto,
+                (1, n) => KernelLengthMode::FixedFrames(n as usize),
+                (2, s) => KernelLengthMode::FixedSeconds(s),
+                (v, _) => return Err(format_err(format!("unknown kernel length mode {v}"))),
+            };
+        }
+        // `set_params` panics in debug builds on parameters that `validate`
+        // rejects, so a corrupt file is turned into an error first.
+        if let Some(d) = validate::validate_solver_params(tau_rise, tau_decay, lambda, fs)
+            .into_iter()
+            .find(|d| d.severity == validate::Severity::Error)
+        {
+            let value = match d.field {
+                "tau_rise" => tau_rise,
+                "tau_decay" => tau_decay,
+                "lambda" => lambda,
+                _ => fs,
+            };
+            return Err(CalabError::invalid(d.field, value, d.message).into());
+        }
+        match triple_exp {
+            None => solver.set_params(tau_rise, tau_decay, lambda, fs),
+            Some((tau_fast, tau_slow, fraction_fast)) => solver.set_triple_exp_params(
+                tau_rise,
+                tau_fast,
+                tau_slow,
+                fraction_fast,
+                lambda,
+                fs,
+            ),
+        }
+        solver.set_conv_mode(conv_mode);
+        solver.set_constraint(constraint);
+
+        let kernel = r.f32_array()?;
+        if kernel.len() != solver.kernel.len() {
+            return Err(CalabError::DimensionMismatch {
+                expected: solver.kernel.len(),
+                got: kernel.len(),
+            }
+            .into());
+        }
+        solver.kernel = kernel;
+
+        let trace = r.f32_array()?;
+        let n = trace.len();
+        solver.set_trace(&trace);
+        let solution = r.f32_array()?;
+        let solution_prev = r.f32_array()?;
+        let gradient = r.f32_array()?;
+        for (values, buf) in [
+            (&solution, &mut solver.solution),
+            (&solution_prev, &mut solver.solution_prev),
+            (&gradient, &mut solver.gradient),
+        ] {
+            if values.len() != n {
+                return Err(format_err(format!(
+                    "checkpoint array has {} samples, trace has {n}",
+                    values.len()
+                )));
+            }
+            buf[..n].copy_from_slice(values);
+        }
+
+        solver.tolerance = tolerance;
+        solver.lambda2 = lambda2;
+        solver.lipschitz_constant = lipschitz_constant;
+        solver.ls_scale = ls_scale;
+        solver.t_fista = t_fista;
+        solver.baseline = baseline;
+        solver.baseline_ema = baseline_ema;
+        solver.iteration = iteration;
+        solver.refractory_frames = refractory_frames;
+        solver.converged = flags & 1 != 0;
+        solver.filtered = flags & 2 != 0;
+        solver.baseline_ema_init = flags & 4 != 0;
+        solver.line_search = flags & 8 != 0;
+        Ok(solver)
+    }
+}
+
+/// Bounds-checked little-endian reads over the checkpoint bytes.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], IoError> {
+        if self.bytes.len() < n {
+            return Err(format_err("checkpoint is truncated"));
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, IoError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, IoError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, IoError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32_array(&mut self) -> Result<Vec<f32>, IoError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.saturating_mul(4))?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::build_kernel;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("calab_ckpt_{}_{name}.bin", std::process::id()))
+    }
+
+    #[test]
+    fn checkpoint_resumes_identically() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let mut trace = vec![0.0_f32; 300];
+        for s in [30, 120, 200] {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if s + k < trace.len() {
+                    trace[s + k] += kv;
+                }
+            }
+        }
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.02, 30.0);
+        solver.set_constraint(Constraint::Box01);
+        solver.set_trace(&trace);
+        solver.step_batch(20);
+
+        let path = temp_path("resume");
+        solver.save_checkpoint(&path).unwrap();
+        let loaded = Solver::load_checkpoint(&path);
+        std::fs::remove_file(&path).ok();
+        let mut loaded = loaded.unwrap();
+
+        assert_eq!(loaded.iteration_count(), 20);
+        assert_eq!(loaded.get_solution(), solver.get_solution());
+        assert_eq!(loaded.get_kernel(), solver.get_kernel());
+        assert_eq!(loaded.constraint, Constraint::Box01);
+
+        solver.step_batch(15);
+        loaded.step_batch(15);
+        assert_eq!(loaded.get_solution(), solver.get_solution());
+    }
+
+    #[test]
+    fn checkpoint_rejects_bad_files() {
+        let mut solver = Solver::new();
+        solver.set_trace(&[0.0; 50]);
+        let path = temp_path("bad");
+        solver.save_checkpoint(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+
+        // tau_rise (bytes 5..13) past tau_decay, then a NaN tau_decay (13..21).
+        let mut bad_params = Vec::new();
+        for (at, value) in [(5, 10.0_f64), (13, f64::NAN)] {
+            let mut corrupt = bytes.clone();
+            corrupt[at..at + 8].copy_from_slice(&value.to_le_bytes());
+            std::fs::write(&path, &corrupt).unwrap();
+            bad_params.push(Solver::load_checkpoint(&path));
+        }
+
+        // Shorten the saved kernel's length prefix by one sample.
+        let kernel_len_at = bytes.len() - 4 * (4 + 4 * 50) - 4 - 4 * solver.kernel.len();
+        let shorter = (solver.kernel.len() as u32 - 1).to_le_bytes();
+        bytes[kernel_len_at..kernel_len_at + 4].copy_from_slice(&shorter);
+        std::fs::write(&path, &bytes).unwrap();
+        let mismatch = Solver::load_checkpoint(&path);
+
+        bytes[4] = VERSION + 1;
+        std::fs::write(&path, &bytes).unwrap();
+        let version = Solver::load_checkpoint(&path);
+
+        std::fs::write(&path, &bytes[..20]).unwrap();
+        let truncated = Solver::load_checkpoint(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(
+            mismatch,
+            Err(IoError::Solver(CalabError::DimensionMismatch { got, .. }))
+                if got == solver.kernel.len() - 1
+        ));
+        for (result, field) in bad_params.iter().zip(["tau_rise", "tau_decay"]) {
+            assert!(matches!(
+                result,
+                Err(IoError::Solver(CalabError::InvalidParameter { name, .. })) if name == field
+            ));
+        }
+        assert!(matches!(version, Err(IoError::Format(_))));
+        assert!(matches!(truncated, Err(IoError::Format(_))));
+    }
+
+    #[test]
+    fn checkpoint_keeps_kernel_length_mode() {
+        let mut solver = Solver::new();
+        solver.set_kernel_length_mode(KernelLengthMode::FixedFrames(40));
+        solver.set_params(0.02, 0.4, 0.02, 30.0);
+        solver.set_trace(&[0.0; 50]);
+        let path = temp_path("length");
+        solver.save_checkpoint(&path).unwrap();
+        let loaded = Solver::load_checkpoint(&path);
+        std::fs::remove_file(&path).ok();
+        let loaded = loaded.unwrap();
+        assert_eq!(
+            loaded.kernel_length_mode(),
+            KernelLengthMode::FixedFrames(40)
+        );
+        assert_eq!(loaded.get_kernel().len(), 40);
+    }
+}
@@ -0,0 +1,212 @@
+//! Least-squares debiasing of a converged L1 solution.
+//!
+//! The L1 penalty shrinks every nonzero amplitude toward zero. Once the
+//! support S (the detected spikes) is known, refitting
+//! min ||K_S x_S + b - y||^2 on S alone, with the baseline b held fixed,
+//! removes that bias without changing which spikes are present. The normal
+//! equations K_S^T K_S x_S = K_S^T (y - b) are solved by conjugate gradient,
+//! each iteration costing one forward and one adjoint convolution.
+
+use crate::{ConvMode, Solver};
+
+#[cfg(feature = "jsbindings")]
+use wasm_bindgen::prelude::*;
+
+/// Entries above this are treated as part of the support.
+const SUPPORT_THRESHOLD: f32 = 1e-6;
+
+#[cfg_attr(feature = "jsbindings", wasm_bindgen)]
+impl Solver {
+    /// Refit the amplitudes on the current support by unconstrained least
+    /// squares (conjugate gradient, at most `max_cg_iters` iterations,
+    /// warm-started from the current solution). Entries off the support are
+    /// set to exactly zero. Returns the number of CG iterations run.
+    ///
+    /// Intended for after convergence; an empty support returns 0 without
+    /// touching the solution. Refitted amplitudes are not clamped, so the
+    /// `Box01` bound may no longer hold.
+    pub fn run_debiasing(&mut self, max_cg_iters: u32) -> u32 {
+        let n = self.active_len;
+        let support: Vec<usize> = (0..n)
+            .filter(|&i| self.solution[i] > SUPPORT_THRESHOLD)
+            .collect();
+        if support.is_empty() {
+            return 0;
+        }
+        let m = support.len();
+
+        // Start from the current amplitudes with the off-support entries dropped.
+        let mut x: Vec<f32> = support.iter().map(|&i| self.solution[i]).collect();
+
+        // r = K_S^T (y - b - K_S x), the negative gradient on the support.
+        self.scatter(&support, &x);
+        self.convolve_scratch(false);
+        let baseline = self.baseline as f32;
+        for i in 0..n {
+            self.residual_buf[i] = self.trace[i] - baseline - self.reconvolution[i];
+        }
+        self.convolve_scratch(true);
+        let mut r: Vec<f32> = support.iter().map(|&i| self.gradient[i]).collect();
+
+        let mut p = r.clone();
+        let mut ap = vec![0.0_f32; m];
+        let mut rr = dot(&r, &r);
+        let stop = rr * self.tolerance * self.tolerance;
+        let mut iters = 0;
+        while iters < max_cg_iters && rr > stop {
+            self.apply_normal(&support, &p, &mut ap);
+            let pap = dot(&p, &ap);
+            if pap <= 0.0 {
+                break;
+            }
+            let alpha = (rr / pap) as f32;
+            for j in 0..m {
+                x[j] += alpha * p[j];
+                r[j] -= alpha * ap[j];
+            }
+            let rr_new = dot(&r, &r);
+            let beta = (rr_new / rr) as f32;
+            for j in 0..m {
+                p[j] = r[j] + beta * p[j];
+            }
+            rr = rr_new;
+            iters += 1;
+        }
+
+        self.solution[..n].fill(0.0);
+        for (&i, &v) in support.iter().zip(&x) {
+            self.solution[i] = v;
+        }
+        // Restart momentum from the debiased point.
+        self.solution_prev[..n].copy_from_slice(&self.solution[..n]);
+        self.t_fista = 1.0;
+        self.reconvolution_stale = true;
+        self.debiased = true;
+        iters
+    }
+
+    /// Whether `run_debiasing` has refitted the current solution (cleared by
+    /// `set_trace` and `reset`).
+    pub fn debiased(&self) -> bool {
+        self.debiased
+    }
+}
+
+impl Solver {
+    /// out = K_S^T K_S v, using `solution_prev` as the full-length scratch.
+    fn apply_normal(&mut self, support: &[usize], v: &[f32], out: &mut [f32]) {
+        self.scatter(support, v);
+        self.convolve_scratch(false);
+        let n = self.active_len;
+        self.residual_buf[..n].copy_from_slice(&self.reconvolution[..n]);
+        self.convolve_scratch(true);
+        for (o, &i) in out.iter_mut().zip(support) {
+            *o = self.gradient[i];
+        }
+    }
+
+    /// Zero `solution_prev` and place `v` on the support.
+    fn scatter(&mut self, support: &[usize], v: &[f32]) {
+        self.solution_prev[..self.active_len].fill(0.0);
+        for (&i, &val) in support.iter().zip(v) {
+            self.solution_prev[i] = val;
+        }
+    }
+
+    /// Forward: `reconvolution = K * solution_prev`; adjoint:
+    /// `gradient = K^T * residual_buf`.
+    fn convolve_scratch(&mut self, adjoint: bool) {
+        let n = self.active_len;
+        if adjoint {
+            match self.conv_mode {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                    self.fft
+                        .convolve_adjoint(&self.residual_buf[..n], n, &mut self.gradient[..n])
+                }
+                ConvMode::BandedAR2 => self
+                    .banded
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            }
+        } else {
+            match self.conv_mode {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => self.fft.convolve_forward(
+                    &self.solution_prev[..n],
+                    n,
+                    &mut self.reconvolution[..n],
+                ),
+                ConvMode::BandedAR2 => self
+                    .banded
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+            }
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f64 {
+    a.iter().zip(b).map(|(&x, &y)| x as f64 * y as f64).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::build_kernel;
+    use crate::test_util::build_trace;
+    use crate::Solver;
+
+    #[test]
+    fn debiasing_restores_shrunk_amplitudes() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let spikes = [(40, 1.0), (150, 2.0), (400, 1.5)];
+        let trace = build_trace(&kernel, 600, &spikes);
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.05, 30.0);
+        solver.set_trace(&trace);
+        assert!((0..500).any(|_| solver.step_batch(10)));
+        let lasso = solver.get_solution();
+        assert!(!solver.debiased());
+
+        let iters = solver.run_debiasing(100);
+        assert!(iters > 0 && solver.debiased());
+        let debiased = solver.get_solution();
+
+        // Same support; each spike's total amplitude moves from the shrunk
+        // L1 value to the true one.
+        for (i, (&a, &b)) in lasso.iter().zip(&debiased).enumerate() {
+            assert_eq!(a > 1e-6, b != 0.0, "support changed at {i}");
+        }
+        for &(s, amp) in &spikes {
+            let window = s.saturating_sub(2)..s + 3;
+            let before: f32 = lasso[window.clone()].iter().sum();
+            let after: f32 = debiased[window].iter().sum();
+            assert!(before < 0.95 * amp, "spike {s}: L1 amplitude {before}");
+            assert!(
+                (after - amp).abs() < 0.02 * amp,
+                "spike {s}: debiased {after}"
+            );
+        }
+
+        solver.set_trace(&trace);
+        assert!(!solver.debiased());
+    }
+
+    #[test]
+    fn debiasing_skips_empty_support() {
+        let mut solver = Solver::new();
+        solver.set_trace(&[0.0; 100]);
+        assert_eq!(solver.run_debiasing(50), 0);
+        assert!(!solver.debiased());
+        assert!(solver.get_solution().iter().all(|&v| v == 0.0));
+    }
+}
@@ -0,0 +1,84 @@
+//! Crate-wide error type for recoverable input and numerical failures.
+//!
+//! Public functions that used to panic (or silently degrade) on bad input
+//! return `Result<_, CalabError>` instead, so the FFI layers can raise a
+//! proper exception (`From<CalabError> for PyErr` in `py_api`, `JsError` /
+//! `to_js_value` on the WASM side) rather than aborting the module.
+
+use core::fmt::Display;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::{String, ToString};
+
+/// Error returned by fallible public APIs.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CalabError {
+    /// A parameter is missing or outside its valid range.
+    #[error("invalid {name} = {value}: {reason}")]
+    InvalidParameter {
+        name: String,
+        value: String,
+        reason: String,
+    },
+    /// Two inputs that must agree in length do not.
+    #[error("dimension mismatch: expected {expected}, got {got}")]
+    DimensionMismatch { expected: usize, got: usize },
+    /// A computation produced no usable result (non-finite values, a
+    /// singular system, ...).
+    #[error("numerical failure: {0}")]
+    NumericalFailure(String),
+}
+
+impl CalabError {
+    /// Shorthand for `InvalidParameter`.
+    pub fn invalid(name: &str, value: impl Display, reason: impl Into<String>) -> Self {
+        CalabError::InvalidParameter {
+            name: name.to_string(),
+            value: value.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Convert to a JS `Error` carrying the display message.
+    #[cfg(feature = "jsbindings")]
+    pub fn to_js_value(&self) -> wasm_bindgen::JsValue {
+        wasm_bindgen::JsError::new(&self.to_string()).into()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::CalabError;
+    use crate::kernel_est::estimate_free_kernel;
+
+    #[test]
+    fn display_names_parameter_and_value() {
+        let err = CalabError::invalid("tau_decay", -1.0, "must be positive");
+        assert_eq!(err.to_string(), "invalid tau_decay = -1: must be positive");
+    }
+
+    #[test]
+    fn mismatched_lengths_are_reported_not_asserted() {
+        let err = estimate_free_kernel(
+            &[1.0; 10],
+            &[0.0; 9],
+            &[1.0],
+            &[0.0],
+            &[10],
+            4,
+            100,
+            1e-4,
+            None,
+            0.0,
+            0.0,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            CalabError::DimensionMismatch {
+                expected: 10,
+                got: 9
+            }
+        );
+    }
+}
@@ -1,7 +1,97 @@
-use realfft::RealFftPlanner;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 use rustfft::num_complex::Complex;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
+/// Overlap-add is used only for signals longer than this many kernel lengths;
+/// shorter ones are convolved in one piece.
+const OVERLAP_ADD_MIN_KERNELS: usize = 16;
+
+/// Overlap-add block length, in kernel lengths.
+const OVERLAP_ADD_BLOCK_KERNELS: usize = 4;
+
+/// Number of FFT lengths an `FftPlanCache` keeps plans for.
+const FFT_PLAN_CACHE_CAPACITY: usize = 8;
+
+/// Forward and inverse real-FFT plans for one padded length.
+#[derive(Clone)]
+pub(crate) struct FftPlan {
+    pub(crate) fwd: Arc<dyn RealToComplex<f32>>,
+    pub(crate) inv: Arc<dyn ComplexToReal<f32>>,
+}
+
+/// Per-solver cache of FFT plans keyed by padded length, holding the 8 most
+/// recently used.
+///
+/// Each miss plans with a fresh `RealFftPlanner` (whose own cache would
+/// otherwise keep every length ever planned), so evicting a length frees its
+/// twiddle factors. Owned by the `FftConvolver` inside each `Solver`, so no
+/// locking is needed.
+pub(crate) struct FftPlanCache {
+    plans: HashMap<usize, FftPlan>,
+    mru: VecDeque<usize>, // most recently used length first
+    capacity: usize,
+}
+
+impl FftPlanCache {
+    pub(crate) fn new() -> Self {
+        FftPlanCache {
+            plans: HashMap::with_capacity(FFT_PLAN_CACHE_CAPACITY),
+            mru: VecDeque::with_capacity(FFT_PLAN_CACHE_CAPACITY + 1),
+            capacity: FFT_PLAN_CACHE_CAPACITY,
+        }
+    }
+
+    /// Plans for `len`, from the cache when present, otherwise built and
+    /// inserted (evicting the least recently used length when full).
+    pub(crate) fn get(&mut self, len: usize) -> FftPlan {
+        if let Some(plan) = self.plans.get(&len) {
+            let plan = plan.clone();
+            self.touch(len);
+            return plan;
+        }
+        let mut planner = RealFftPlanner::new();
+        let plan = FftPlan {
+            fwd: planner.plan_fft_forward(len),
+            inv: planner.plan_fft_inverse(len),
+        };
+        if self.capacity > 0 {
+            self.plans.insert(len, plan.clone());
+            self.mru.push_front(len);
+            if self.mru.len() > self.capacity {
+                if let Some(evicted) = self.mru.pop_back() {
+                    self.plans.remove(&evicted);
+                }
+            }
+        }
+        plan
+    }
+
+    /// Whether plans for `len` are cached.
+    pub(crate) fn contains(&self, len: usize) -> bool {
+        self.plans.contains_key(&len)
+    }
+
+    /// Number of cached lengths.
+    pub(crate) fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    /// Drop every cached plan.
+    pub(crate) fn clear(&mut self) {
+        self.plans.clear();
+        self.mru.clear();
+    }
+
+    /// Move `len` to the most-recently-used position.
+    fn touch(&mut self, len: usize) {
+        if let Some(pos) = self.mru.iter().position(|&l| l == len) {
+            self.mru.remove(pos);
+        }
+        self.mru.push_front(len);
+    }
+}
+
 /// Self-contained FFT convolution engine.
 ///
 /// Owns all FFT plans, scratch buffers, and the pre-computed kernel spectrum.
@@ -10,9 +100,17 @@ use std::sync::Arc;
 /// eliminating the per-iteration `to_vec()` copies that were previously needed.
 ///
 /// Buffers grow but never shrink to prevent WASM memory fragmentation.
+///
+/// Set up with `ensure_buffers_overlap_add`, long signals are convolved by
+/// overlap-add in blocks of `4 * kernel_len` samples, so the FFT buffers are
+/// O(kernel_len) instead of O(signal_len).
 pub(crate) struct FftConvolver {
-    planner: RealFftPlanner<f32>,
-    fft_len: usize, // padded FFT length (power of 2), 0 = uninitialized
+    // Returning to a length seen recently (after `invalidate` or a
+    // kernel-length change) reuses its twiddle factors instead of re-planning.
+    plans: FftPlanCache,
+    fft_len: usize,    // padded FFT length (power of 2), 0 = uninitialized
+    block_len: usize,  // overlap-add block length, 0 = whole-signal transforms
+    kernel_len: usize, // length of the kernel behind the cached spectra
 
     // Cached FFT plans (Arc from planner, avoids hash-map lookup per call)
     plan_fwd: Option<Arc<dyn realfft::RealToComplex<f32>>>,
@@ -33,8 +131,10 @@ pub(crate) struct FftConvolver {
 impl FftConvolver {
     pub(crate) fn new() -> Self {
         FftConvolver {
-            planner: RealFftPlanner::new(),
+            plans: FftPlanCache::new(),
             fft_len: 0,
+            block_len: 0,
+            kernel_len: 0,
             plan_fwd: None,
             plan_inv: None,
             kernel_fft: Vec::new(),
@@ -52,23 +152,62 @@ impl FftConvolver {
         self.fft_len
     }
 
+    /// Overlap-add block length (0 = whole-signal transforms).
+    pub(crate) fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Whether the current FFT length can convolve `signal_len` samples with
+    /// a kernel of `k_len` taps (per block in overlap-add mode).
+    pub(crate) fn covers(&self, signal_len: usize, k_len: usize) -> bool {
+        let span = if self.block_len > 0 {
+            self.block_len
+        } else {
+            signal_len
+        };
+        self.fft_len > 0 && span + k_len - 1 <= self.fft_len
+    }
+
     /// Invalidate cached FFT length, forcing a full rebuild on next `ensure_buffers`.
+    /// The plan cache is kept, so the rebuild only re-plans lengths not seen recently.
     pub(crate) fn invalidate(&mut self) {
         self.fft_len = 0;
         self.plan_fwd = None;
         self.plan_inv = None;
     }
 
+    /// The per-length plan cache.
+    pub(crate) fn plan_cache(&mut self) -> &mut FftPlanCache {
+        &mut self.plans
+    }
+
     /// Ensure FFT buffers are allocated for the given signal + kernel size.
     /// Recomputes kernel FFT when the padded FFT length changes.
     /// Buffers grow but never shrink.
     pub(crate) fn ensure_buffers(&mut self, signal_len: usize, kernel: &[f32]) {
+        if signal_len == 0 || kernel.is_empty() {
+            return;
+        }
+        self.block_len = 0;
+        self.ensure_fft_len(signal_len + kernel.len() - 1, kernel);
+    }
+
+    /// Like `ensure_buffers`, but signals longer than 16 kernel lengths are
+    /// set up for overlap-add in blocks of `4 * kernel_len`, with an FFT
+    /// length independent of `signal_len`.
+    pub(crate) fn ensure_buffers_overlap_add(&mut self, signal_len: usize, kernel: &[f32]) {
         let k_len = kernel.len();
-        if signal_len == 0 || k_len == 0 {
+        if signal_len <= OVERLAP_ADD_MIN_KERNELS * k_len {
+            self.ensure_buffers(signal_len, kernel);
             return;
         }
+        self.block_len = OVERLAP_ADD_BLOCK_KERNELS * k_len;
+        self.ensure_fft_len(self.block_len + k_len - 1, kernel);
+    }
 
-        let min_len = signal_len + k_len - 1;
+    /// Set up plans, buffers and the kernel spectra for an FFT of at least
+    /// `min_len` samples.
+    fn ensure_fft_len(&mut self, min_len: usize, kernel: &[f32]) {
         let padded_len = min_len.next_power_of_two();
 
         if padded_len == self.fft_len {
@@ -97,9 +236,8 @@ impl FftConvolver {
                 .resize(spectrum_len, Complex::new(0.0, 0.0));
         }
 
-        // Cache FFT plans and allocate scratch
-        let fwd = self.planner.plan_fft_forward(padded_len);
-        let inv = self.planner.plan_fft_inverse(padded_len);
+        // Look up (or build) the FFT plans and allocate scratch
+        let FftPlan { fwd, inv } = self.plans.get(padded_len);
         let fwd_scratch = fwd.get_scratch_len();
         let inv_scratch = inv.get_scratch_len();
         if self.fft_scratch_fwd.len() < fwd_scratch {
@@ -123,6 +261,7 @@ impl FftConvolver {
         let k_len = kernel.len();
         let padded_len = self.fft_len;
         let spectrum_len = padded_len / 2 + 1;
+        self.kernel_len = k_len;
 
         // Zero-pad kernel into fft_input
         self.fft_input[..k_len].copy_from_slice(&kernel[..k_len]);
@@ -172,6 +311,11 @@ impl FftConvolver {
         output: &mut [f32],
         use_conjugate: bool,
     ) {
+        if self.block_len > 0 && signal_len > self.block_len {
+            self.overlap_add(source, signal_len, output, use_conjugate);
+            return;
+        }
+
         let padded_len = self.fft_len;
         let spectrum_len = padded_len / 2 + 1;
 
@@ -214,6 +358,75 @@ impl FftConvolver {
             output[i] = self.fft_output[i] * scale;
         }
     }
+
+    /// Overlap-add convolution in blocks of `block_len` source samples.
+    ///
+    /// Each block's linear convolution (forward) or correlation (adjoint) is
+    /// at most `block_len + kernel_len - 1` samples long, which fits the FFT
+    /// length without wrap-around. Forward spills the block's tail into the
+    /// following samples; the adjoint result at negative lags lands at the
+    /// end of the circular output and spills into the preceding samples.
+    fn overlap_add(
+        &mut self,
+        source: &[f32],
+        signal_len: usize,
+        output: &mut [f32],
+        use_conjugate: bool,
+    ) {
+        let padded_len = self.fft_len;
+        let spectrum_len = padded_len / 2 + 1;
+        let spill = self.kernel_len.saturating_sub(1);
+        let scale = 1.0 / padded_len as f32;
+
+        output[..signal_len].fill(0.0);
+        let fwd = self.plan_fwd.as_ref().expect("plans not initialized");
+        let inv = self.plan_inv.as_ref().expect("plans not initialized");
+        let kernel_spectrum = if use_conjugate {
+            &self.kernel_conj_fft
+        } else {
+            &self.kernel_fft
+        };
+
+        let mut start = 0;
+        while start < signal_len {
+            let len = self.block_len.min(signal_len - start);
+            self.fft_input[..len].copy_from_slice(&source[start..start + len]);
+            self.fft_input[len..padded_len].fill(0.0);
+            fwd.process_with_scratch(
+                &mut self.fft_input[..padded_len],
+                &mut self.fft_spectrum[..spectrum_len],
+                &mut self.fft_scratch_fwd,
+            )
+            .unwrap();
+            for (x, &k) in self.fft_spectrum[..spectrum_len]
+                .iter_mut()
+                .zip(kernel_spectrum)
+            {
+                *x *= k;
+            }
+            inv.process_with_scratch(
+                &mut self.fft_spectrum[..spectrum_len],
+                &mut self.fft_output[..padded_len],
+                &mut self.fft_scratch_inv,
+            )
+            .unwrap();
+
+            if use_conjugate {
+                for (i, &v) in self.fft_output[..len].iter().enumerate() {
+                    output[start + i] += v * scale;
+                }
+                for d in 1..=spill.min(start) {
+                    output[start - d] += self.fft_output[padded_len - d] * scale;
+                }
+            } else {
+                let end = (start + len + spill).min(signal_len);
+                for (o, &v) in output[start..end].iter_mut().zip(&self.fft_output) {
+                    *o += v * scale;
+                }
+            }
+            start += len;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -250,6 +463,50 @@ mod tests {
         }
     }
 
+    /// Re-planning a previously used length hands back the planner's cached
+    /// plan, and an unchanged length skips planning altogether.
+    #[test]
+    fn replanning_reuses_cached_plans() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let mut conv = FftConvolver::new();
+        conv.ensure_buffers(10_000, &kernel);
+        let fwd = conv.plan_fwd.clone().unwrap();
+        let inv = conv.plan_inv.clone().unwrap();
+
+        conv.ensure_buffers(10_000, &kernel);
+        assert!(Arc::ptr_eq(&fwd, conv.plan_fwd.as_ref().unwrap()));
+
+        conv.ensure_buffers(100, &kernel);
+        assert!(!Arc::ptr_eq(&fwd, conv.plan_fwd.as_ref().unwrap()));
+        conv.invalidate();
+        conv.ensure_buffers(10_000, &kernel);
+        assert!(Arc::ptr_eq(&fwd, conv.plan_fwd.as_ref().unwrap()));
+        assert!(Arc::ptr_eq(&inv, conv.plan_inv.as_ref().unwrap()));
+    }
+
+    /// Lengths beyond the 8 most recently used are evicted; a hit refreshes
+    /// a length's position.
+    #[test]
+    fn plan_cache_keeps_eight_most_recent_lengths() {
+        let mut cache = FftPlanCache::new();
+        let first = cache.get(64);
+        for k in 7..14 {
+            cache.get(1 << k);
+        }
+        assert_eq!(cache.len(), 8);
+        // Touch 64 so 128 becomes the oldest, then overflow by one.
+        assert!(Arc::ptr_eq(&first.fwd, &cache.get(64).fwd));
+        cache.get(1 << 14);
+        assert_eq!(cache.len(), 8);
+        assert!(cache.contains(64));
+        assert!(!cache.contains(128));
+        assert!(cache.contains(1 << 14));
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+        assert!(!Arc::ptr_eq(&first.fwd, &cache.get(64).fwd));
+    }
+
     /// Adjoint identity: <Kx, y> == <x, K^T y> for deterministic vectors.
     #[test]
     fn adjoint_identity() {
@@ -293,4 +550,42 @@ mod tests {
             rel_err
         );
     }
+
+    /// Overlap-add matches the whole-signal transform, forward and adjoint,
+    /// with an FFT length set by the kernel rather than the signal.
+    #[test]
+    fn overlap_add_matches_whole_signal() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 40 * kernel.len() + 17;
+        let x: Vec<f32> = (0..n).map(|i| (i as f32 * 0.37).sin().max(0.0)).collect();
+
+        let mut whole = FftConvolver::new();
+        whole.ensure_buffers(n, &kernel);
+        let mut blocked = FftConvolver::new();
+        blocked.ensure_buffers_overlap_add(n, &kernel);
+        assert_eq!(blocked.block_len(), 4 * kernel.len());
+        assert!(blocked.fft_len() < whole.fft_len());
+
+        for adjoint in [false, true] {
+            let (mut a, mut b) = (vec![0.0_f32; n], vec![0.0_f32; n]);
+            if adjoint {
+                whole.convolve_adjoint(&x, n, &mut a);
+                blocked.convolve_adjoint(&x, n, &mut b);
+            } else {
+                whole.convolve_forward(&x, n, &mut a);
+                blocked.convolve_forward(&x, n, &mut b);
+            }
+            for (i, (&u, &v)) in a.iter().zip(&b).enumerate() {
+                assert!(
+                    (u - v).abs() < 1e-4 * u.abs().max(1.0),
+                    "adjoint={adjoint} sample {i}: whole {u} vs overlap-add {v}"
+                );
+            }
+        }
+
+        // Short signals fall back to a single transform.
+        let mut short = FftConvolver::new();
+        short.ensure_buffers_overlap_add(8 * kernel.len(), &kernel);
+        assert_eq!(short.block_len(), 0);
+    }
 }
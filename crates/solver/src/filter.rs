@@ -3,6 +3,9 @@ use rustfft::num_complex::Complex;
 use std::f32::consts::PI;
 use std::sync::Arc;
 
+use crate::baseline::{FenwickTree, OrderedF32};
+use crate::CalabError;
+
 /// Margin factors for deriving bandpass cutoffs from kernel time constants.
 /// HP cutoff = 1/(2π·τ_decay·M_HP), LP cutoff = M_LP/(2π·τ_rise).
 /// HP uses 16× to preserve the slow calcium decay tail (~40s period for
@@ -89,7 +92,7 @@ impl BandpassFilter {
         let tau_rise = tau_rise as f32;
         let tau_decay = tau_decay as f32;
 
-        if tau_rise <= 0.0 || tau_decay <= 0.0 || fs <= 0.0 {
+        if tau_rise < 0.0 || tau_decay <= 0.0 || fs <= 0.0 {
             self.valid = false;
             return;
         }
@@ -98,8 +101,13 @@ impl BandpassFilter {
 
         // High-pass: removes sub-calcium drift
         self.f_hp = 1.0 / (2.0 * PI * tau_decay * MARGIN_FACTOR_HP);
-        // Low-pass: removes supra-calcium noise
-        self.f_lp = MARGIN_FACTOR_LP / (2.0 * PI * tau_rise);
+        // Low-pass: removes supra-calcium noise. tau_rise = 0 (single-exponential
+        // kernel) has no rise band to protect, so the cutoff sits at Nyquist.
+        self.f_lp = if tau_rise > 0.0 {
+            MARGIN_FACTOR_LP / (2.0 * PI * tau_rise)
+        } else {
+            nyquist
+        };
 
         // Clamp low-pass to Nyquist
         self.f_lp = self.f_lp.min(nyquist);
@@ -301,6 +309,436 @@ impl BandpassFilter {
     }
 }
 
+/// Which pre-solve filter `Solver::apply_filter` runs.
+#[derive(Clone, Debug)]
+pub enum FilterType {
+    /// FFT bandpass with cutoffs derived from the kernel time constants
+    /// (`BandpassFilter`, toggled via `set_hp_filter_enabled` /
+    /// `set_lp_filter_enabled`). The default.
+    Bandpass,
+    /// Zero-phase IIR Butterworth with explicit cutoffs.
+    Butterworth(ButterworthFilter),
+    /// Savitzky-Golay smoothing (`savitzky_golay`): a local polynomial fit,
+    /// so it preserves DC and baseline estimation still runs.
+    SavitzkyGolay { window: usize, poly_order: usize },
+    /// Causal one-pole exponential smoothing (`ExponentialSmoothFilter`);
+    /// preserves DC but delays the trace.
+    Exponential(ExponentialSmoothFilter),
+    /// Exponential smoothing run forward then backward
+    /// (`ForwardBackwardExponential`): zero phase, preserves DC.
+    ZeroPhaseExponential(ForwardBackwardExponential),
+    /// Sliding median over `2 * half_window + 1` samples (`median_filter_1d`),
+    /// which removes isolated impulsive artifacts; preserves DC.
+    Median { half_window: usize },
+}
+
+/// One biquad in direct form II transposed: `b0 b1 b2 / 1 a1 a2`.
+#[derive(Clone, Copy, Debug)]
+struct Sos {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Sos {
+    /// Run the section over `x` in place from zero initial state.
+    fn run(&self, x: &mut [f64]) {
+        let (mut z1, mut z2) = (0.0, 0.0);
+        for v in x.iter_mut() {
+            let input = *v;
+            let out = self.b[0] * input + z1;
+            z1 = self.b[1] * input - self.a[0] * out + z2;
+            z2 = self.b[2] * input - self.a[1] * out;
+            *v = out;
+        }
+    }
+}
+
+/// Butterworth IIR filter (order 1–4) as cascaded second-order sections,
+/// applied forward and backward for zero phase (`filtfilt`), so the effective
+/// magnitude response is squared and the order doubled.
+///
+/// `low_hz` is the high-pass edge and `high_hz` the low-pass edge; set one for
+/// a pure high-/low-pass, both for a bandpass (HP and LP cascaded). Sections
+/// come from the bilinear transform with frequency prewarping, computed once
+/// at construction for the given sampling rate.
+#[derive(Clone, Debug)]
+pub struct ButterworthFilter {
+    pub order: u8,
+    pub low_hz: Option<f64>,
+    pub high_hz: Option<f64>,
+    fs: f64,
+    sections: Vec<Sos>,
+}
+
+impl ButterworthFilter {
+    /// Design the filter for sampling rate `fs`.
+    ///
+    /// Errors on an order outside 1–4, no cutoff, a cutoff outside
+    /// (0, fs/2), or `low_hz >= high_hz`.
+    pub fn new(
+        order: u8,
+        low_hz: Option<f64>,
+        high_hz: Option<f64>,
+        fs: f64,
+    ) -> Result<Self, CalabError> {
+        if !(1..=4).contains(&order) {
+            return Err(CalabError::invalid(
+                "order",
+                order,
+                "Butterworth order must be 1-4",
+            ));
+        }
+        if low_hz.is_none() && high_hz.is_none() {
+            return Err(CalabError::invalid(
+                "low_hz/high_hz",
+                "None",
+                "Butterworth filter needs at least one cutoff",
+            ));
+        }
+        let nyquist = fs / 2.0;
+        for (name, f) in [("low_hz", low_hz), ("high_hz", high_hz)] {
+            if let Some(f) = f {
+                if !(f > 0.0 && f < nyquist) {
+                    return Err(CalabError::invalid(
+                        name,
+                        f,
+                        format!("cutoff must be in (0, {nyquist}) Hz"),
+                    ));
+                }
+            }
+        }
+        if let (Some(lo), Some(hi)) = (low_hz, high_hz) {
+            if lo >= hi {
+                return Err(CalabError::invalid(
+                    "low_hz",
+                    lo,
+                    format!("must be below high_hz ({hi})"),
+                ));
+            }
+        }
+
+        let mut sections = Vec::new();
+        if let Some(f) = low_hz {
+            sections.extend(butterworth_sections(order, f, fs, true));
+        }
+        if let Some(f) = high_hz {
+            sections.extend(butterworth_sections(order, f, fs, false));
+        }
+        Ok(ButterworthFilter {
+            order,
+            low_hz,
+            high_hz,
+            fs,
+            sections,
+        })
+    }
+
+    /// Sampling rate the sections were designed for.
+    pub fn fs(&self) -> f64 {
+        self.fs
+    }
+
+    /// Zero-phase filter `trace` in place. Returns false (trace untouched) when
+    /// it is too short to filter (< 8 samples, matching `BandpassFilter`).
+    ///
+    /// The ends are padded with an odd reflection about the end samples (one
+    /// period of the lowest cutoff, capped at `n - 1`) to suppress start-up
+    /// transients, as `scipy.signal.filtfilt` does.
+    pub fn apply(&self, trace: &mut [f32]) -> bool {
+        let n = trace.len();
+        if n < 8 {
+            return false;
+        }
+        let f_min = self.low_hz.or(self.high_hz).unwrap_or(self.fs / 2.0);
+        let pad = ((self.fs / f_min).ceil() as usize)
+            .max(6 * self.order as usize)
+            .min(n - 1);
+
+        let first = trace[0] as f64;
+        let last = trace[n - 1] as f64;
+        let mut x = Vec::with_capacity(n + 2 * pad);
+        x.extend((1..=pad).rev().map(|i| 2.0 * first - trace[i] as f64));
+        x.extend(trace.iter().map(|&v| v as f64));
+        x.extend((1..=pad).map(|i| 2.0 * last - trace[n - 1 - i] as f64));
+
+        for sos in &self.sections {
+            sos.run(&mut x);
+        }
+        x.reverse();
+        for sos in &self.sections {
+            sos.run(&mut x);
+        }
+        x.reverse();
+
+        for (t, &v) in trace.iter_mut().zip(&x[pad..pad + n]) {
+            *t = v as f32;
+        }
+        true
+    }
+}
+
+/// Second-order sections of an order-`order` Butterworth high- or low-pass
+/// at `fc` Hz, via the prewarped bilinear transform.
+fn butterworth_sections(order: u8, fc: f64, fs: f64, highpass: bool) -> Vec<Sos> {
+    let k = (std::f64::consts::PI * fc / fs).tan();
+    let n = order as usize;
+    let mut sections = Vec::with_capacity(n.div_ceil(2));
+
+    // Conjugate pole pairs: s^2 + s/Q + 1 with Q = 1 / (2 sin((2i-1)π / 2N)).
+    for i in 1..=n / 2 {
+        let theta = (2 * i - 1) as f64 * std::f64::consts::PI / (2 * n) as f64;
+        let inv_q = 2.0 * theta.sin();
+        let norm = 1.0 / (1.0 + k * inv_q + k * k);
+        let a = [2.0 * (k * k - 1.0) * norm, (1.0 - k * inv_q + k * k) * norm];
+        let b = if highpass {
+            [norm, -2.0 * norm, norm]
+        } else {
+            let b0 = k * k * norm;
+            [b0, 2.0 * b0, b0]
+        };
+        sections.push(Sos { b, a });
+    }
+
+    // Odd order: one real pole at s = -1.
+    if n % 2 == 1 {
+        let norm = 1.0 / (1.0 + k);
+        let a = [(k - 1.0) * norm, 0.0];
+        let b = if highpass {
+            [norm, -norm, 0.0]
+        } else {
+            [k * norm, k * norm, 0.0]
+        };
+        sections.push(Sos { b, a });
+    }
+    sections
+}
+
+/// One-pole low-pass `y[t] = alpha * x[t] + (1 - alpha) * y[t-1]`, run
+/// forward only, so it lags the trace by about `(1 - alpha) / alpha`
+/// samples. The state starts at `x[0]`, so a constant trace passes unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExponentialSmoothFilter {
+    pub alpha: f64,
+}
+
+impl ExponentialSmoothFilter {
+    /// Errors unless `alpha` is in (0, 1]; 1 leaves the trace unchanged.
+    pub fn new(alpha: f64) -> Result<Self, CalabError> {
+        validate_smoothing_alpha(alpha)?;
+        Ok(ExponentialSmoothFilter { alpha })
+    }
+
+    /// Smooth `trace` in place. Returns false (trace untouched) when empty.
+    pub fn apply(&self, trace: &mut [f32]) -> bool {
+        if trace.is_empty() {
+            return false;
+        }
+        exponential_pass(trace.iter_mut(), self.alpha);
+        true
+    }
+}
+
+/// [`ExponentialSmoothFilter`] applied forward and then backward: zero phase,
+/// with the one-pass power response squared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForwardBackwardExponential {
+    pub alpha: f64,
+}
+
+impl ForwardBackwardExponential {
+    /// Errors unless `alpha` is in (0, 1].
+    pub fn new(alpha: f64) -> Result<Self, CalabError> {
+        validate_smoothing_alpha(alpha)?;
+        Ok(ForwardBackwardExponential { alpha })
+    }
+
+    /// Smooth `trace` in place. Returns false (trace untouched) when empty.
+    pub fn apply(&self, trace: &mut [f32]) -> bool {
+        if trace.is_empty() {
+            return false;
+        }
+        exponential_pass(trace.iter_mut(), self.alpha);
+        exponential_pass(trace.iter_mut().rev(), self.alpha);
+        true
+    }
+}
+
+fn validate_smoothing_alpha(alpha: f64) -> Result<(), CalabError> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err(CalabError::invalid(
+            "alpha",
+            alpha,
+            "smoothing factor must be in (0, 1]",
+        ));
+    }
+    Ok(())
+}
+
+/// One exponential-smoothing pass over `samples` in iteration order, starting
+/// from the first sample.
+fn exponential_pass<'a>(samples: impl Iterator<Item = &'a mut f32>, alpha: f64) {
+    let mut state: Option<f64> = None;
+    for v in samples {
+        let x = *v as f64;
+        let y = state.map_or(x, |prev| alpha * x + (1.0 - alpha) * prev);
+        state = Some(y);
+        *v = y as f32;
+    }
+}
+
+/// Sliding-window median over `trace[t - half_window ..= t + half_window]`,
+/// with samples beyond either end taken as zero. Removes impulsive artifacts
+/// narrower than `half_window + 1` samples while keeping step edges; note
+/// the zero padding pulls the first and last `half_window` samples of an
+/// offset trace toward 0. `half_window = 0` returns a copy.
+///
+/// O(N log M) via the coordinate-compressed Fenwick tree of the rolling
+/// baseline, where M is the number of distinct values.
+pub fn median_filter_1d(trace: &[f32], half_window: usize) -> Vec<f32> {
+    let n = trace.len();
+    if half_window == 0 || n == 0 {
+        return trace.to_vec();
+    }
+    let mut sorted_vals: Vec<OrderedF32> = trace.iter().map(|&v| OrderedF32(v)).collect();
+    sorted_vals.push(OrderedF32(0.0));
+    sorted_vals.sort_unstable();
+    sorted_vals.dedup();
+    let compress = |v: f32| -> usize { sorted_vals.binary_search(&OrderedF32(v)).unwrap() };
+    // Zero-padded sample `i` of the virtual sequence starting `half_window`
+    // before the trace.
+    let padded = |i: usize| -> f32 {
+        i.checked_sub(half_window)
+            .and_then(|t| trace.get(t))
+            .copied()
+            .unwrap_or(0.0)
+    };
+
+    let window = 2 * half_window + 1;
+    let mut fenwick = FenwickTree::new(sorted_vals.len());
+    for i in 0..window - 1 {
+        fenwick.update(compress(padded(i)), 1);
+    }
+    let mut out = Vec::with_capacity(n);
+    for t in 0..n {
+        // The window for output t covers padded[t ..= t + 2 * half_window].
+        fenwick.update(compress(padded(t + window - 1)), 1);
+        out.push(sorted_vals[fenwick.kth(half_window as i32 + 1)].0);
+        fenwick.update(compress(padded(t)), -1);
+    }
+    out
+}
+
+/// Savitzky-Golay smoothing: least-squares fit of a degree-`poly_order`
+/// polynomial over each `window`-sample neighbourhood, evaluated at the
+/// centre. Interior samples use the symmetric convolution coefficients; the
+/// first and last `window / 2` samples evaluate the fit over the first/last
+/// full window at their own offset (scipy's `mode="interp"`), so any
+/// polynomial of degree <= `poly_order` passes through unchanged.
+///
+/// A `window` longer than the trace is clipped to the largest odd length
+/// that fits. Errors on an even or zero `window`, or when `poly_order` is not
+/// below the (clipped) window.
+pub fn savitzky_golay(
+    trace: &[f32],
+    window: usize,
+    poly_order: usize,
+) -> Result<Vec<f32>, CalabError> {
+    let n = trace.len();
+    if window == 0 || window % 2 == 0 {
+        return Err(CalabError::invalid(
+            "window",
+            window,
+            "must be a positive odd number",
+        ));
+    }
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    let window = if window > n { n - (1 - n % 2) } else { window };
+    if poly_order >= window {
+        return Err(CalabError::invalid(
+            "poly_order",
+            poly_order,
+            format!("must be less than the window ({window})"),
+        ));
+    }
+
+    let rows = savitzky_golay_rows(window, poly_order);
+    let half = window / 2;
+    let dot = |row: &[f64], start: usize| -> f32 {
+        row.iter()
+            .zip(&trace[start..start + window])
+            .map(|(&c, &v)| c * v as f64)
+            .sum::<f64>() as f32
+    };
+
+    let mut out = vec![0.0_f32; n];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = if i < half {
+            dot(&rows[i], 0)
+        } else if i >= n - half {
+            dot(&rows[i + window - n], n - window)
+        } else {
+            dot(&rows[half], i - half)
+        };
+    }
+    Ok(out)
+}
+
+/// Smoothing matrix `H = A (A^T A)^{-1} A^T` for a `window`-point polynomial
+/// fit of degree `poly_order`: row `i` holds the weights that evaluate the fit
+/// at window position `i`. Abscissae are scaled to [-1, 1] for conditioning.
+fn savitzky_golay_rows(window: usize, poly_order: usize) -> Vec<Vec<f64>> {
+    let half = window / 2;
+    let scale = half.max(1) as f64;
+    let m = poly_order + 1;
+    let a: Vec<Vec<f64>> = (0..window)
+        .map(|j| {
+            let x = (j as f64 - half as f64) / scale;
+            (0..m).map(|k| x.powi(k as i32)).collect()
+        })
+        .collect();
+
+    // Solve (A^T A) X = A^T by Gauss-Jordan with partial pivoting.
+    let mut g: Vec<Vec<f64>> = (0..m)
+        .map(|r| {
+            let mut row: Vec<f64> = (0..m)
+                .map(|c| a.iter().map(|aj| aj[r] * aj[c]).sum())
+                .collect();
+            row.extend(a.iter().map(|aj| aj[r]));
+            row
+        })
+        .collect();
+    for col in 0..m {
+        let pivot = (col..m)
+            .max_by(|&p, &q| g[p][col].abs().total_cmp(&g[q][col].abs()))
+            .unwrap_or(col);
+        g.swap(col, pivot);
+        let d = g[col][col];
+        for v in g[col].iter_mut() {
+            *v /= d;
+        }
+        for r in 0..m {
+            if r != col {
+                let f = g[r][col];
+                if f != 0.0 {
+                    for c in col..m + window {
+                        g[r][c] -= f * g[col][c];
+                    }
+                }
+            }
+        }
+    }
+
+    a.iter()
+        .map(|ai| {
+            (0..window)
+                .map(|j| (0..m).map(|k| ai[k] * g[k][m + j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -575,4 +1013,205 @@ mod tests {
             filtered_power / original_power
         );
     }
+
+    fn sine(freq: f64, fs: f64, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / fs).sin() as f32)
+            .collect()
+    }
+
+    fn rms(x: &[f32]) -> f64 {
+        (x.iter().map(|&v| (v as f64).powi(2)).sum::<f64>() / x.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn butterworth_hp_attenuates_below_cutoff() {
+        let fs = 30.0;
+        let filt = ButterworthFilter::new(4, Some(2.0), None, fs).unwrap();
+        let mut x = sine(1.0, fs, 900);
+        let before = rms(&x[150..750]);
+        assert!(filt.apply(&mut x));
+        let after = rms(&x[150..750]);
+        let atten_db = 20.0 * (before / after).log10();
+        assert!(atten_db > 20.0, "1 Hz attenuation {atten_db:.1} dB");
+    }
+
+    #[test]
+    fn butterworth_lp_passes_band_with_zero_phase() {
+        let fs = 30.0;
+        let filt = ButterworthFilter::new(2, None, Some(5.0), fs).unwrap();
+        let mut x = sine(0.5, fs, 600);
+        let orig = x.clone();
+        filt.apply(&mut x);
+        // Passband: amplitude and phase preserved in the interior.
+        for i in 100..500 {
+            assert!(
+                (x[i] - orig[i]).abs() < 0.01,
+                "sample {i}: {} vs {}",
+                x[i],
+                orig[i]
+            );
+        }
+    }
+
+    #[test]
+    fn butterworth_rejects_bad_designs() {
+        assert!(ButterworthFilter::new(0, Some(1.0), None, 30.0).is_err());
+        assert!(ButterworthFilter::new(5, Some(1.0), None, 30.0).is_err());
+        assert!(ButterworthFilter::new(2, None, None, 30.0).is_err());
+        assert!(ButterworthFilter::new(2, Some(20.0), None, 30.0).is_err());
+        assert!(ButterworthFilter::new(2, Some(3.0), Some(2.0), 30.0).is_err());
+        assert!(ButterworthFilter::new(3, Some(0.5), Some(5.0), 30.0).is_ok());
+    }
+
+    #[test]
+    fn solver_runs_selected_butterworth() {
+        let fs = 30.0;
+        let mut solver = crate::Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, fs);
+        assert!(!solver.filter_enabled());
+
+        solver.set_filter_type(FilterType::Butterworth(
+            ButterworthFilter::new(2, Some(0.5), None, fs).unwrap(),
+        ));
+        assert!(solver.filter_enabled());
+        assert!(matches!(solver.filter_type(), FilterType::Butterworth(_)));
+
+        let trace: Vec<f32> = sine(3.0, fs, 600).iter().map(|v| v + 5.0).collect();
+        solver.set_trace(&trace);
+        assert!(solver.apply_filter());
+        let filtered = solver.get_trace();
+        let mean = filtered[100..500].iter().sum::<f32>() / 400.0;
+        assert!(
+            mean.abs() < 0.05,
+            "HP Butterworth should remove DC, mean {mean}"
+        );
+    }
+
+    #[test]
+    fn exponential_smoothing_response() {
+        let trace = [1.0_f32, 0.0, 0.0, 2.0];
+        let mut fwd = trace;
+        assert!(ExponentialSmoothFilter::new(0.5).unwrap().apply(&mut fwd));
+        assert_eq!(fwd, [1.0, 0.5, 0.25, 1.125]);
+        let mut constant = [3.0_f32; 50];
+        ForwardBackwardExponential::new(0.3)
+            .unwrap()
+            .apply(&mut constant);
+        assert!(constant.iter().all(|&v| (v - 3.0).abs() < 1e-6));
+        assert!(ExponentialSmoothFilter::new(0.0).is_err());
+        assert!(ForwardBackwardExponential::new(1.5).is_err());
+
+        // Zero-phase alpha = 0.3: > 20 dB down at fs/4, and 2 × 7.45 dB at
+        // fs/8 (one pass gives alpha² / (1 - 2(1-alpha)cos w + (1-alpha)²)).
+        let fs = 32.0;
+        let filter = ForwardBackwardExponential::new(0.3).unwrap();
+        for (freq, min_db) in [(fs / 4.0, 20.0), (fs / 8.0, 14.5)] {
+            let input = sine(freq, fs, 2048);
+            let mut out = input.clone();
+            filter.apply(&mut out);
+            let db = 20.0 * (rms(&input[256..1792]) / rms(&out[256..1792])).log10();
+            assert!(db > min_db, "{freq} Hz: {db:.1} dB");
+        }
+        // A pulse comes out symmetric about its position (no phase shift).
+        let mut pulse = vec![0.0_f32; 81];
+        pulse[40] = 1.0;
+        filter.apply(&mut pulse);
+        for k in 1..10 {
+            assert!((pulse[40 - k] - pulse[40 + k]).abs() < 1e-4, "lag {k}");
+        }
+    }
+
+    #[test]
+    fn median_filter_removes_impulse_artifacts() {
+        let n = 400;
+        let mut state: u64 = 5;
+        let clean: Vec<f32> = sine(0.5, 30.0, n)
+            .iter()
+            .map(|&v| 0.8 * v + 0.2 * crate::indeca::lcg_gaussian(&mut state) as f32)
+            .collect();
+        let range = clean.iter().fold(0.0_f32, |m, &v| m.max(v.abs()));
+        let mut trace = clean.clone();
+        for t in (20..n).step_by(37) {
+            trace[t] += if t % 2 == 0 { 5.0 } else { -5.0 } * range;
+        }
+
+        let filtered = median_filter_1d(&trace, 3);
+        assert_eq!(filtered.len(), n);
+        let worst = filtered.iter().fold(0.0_f32, |m, &v| m.max(v.abs()));
+        assert!(worst <= 2.0 * range, "max |y| {worst} vs range {range}");
+
+        // Brute-force check, zero padding included.
+        let h = 2;
+        let small = [4.0_f32, -1.0, 7.0, 7.0, 2.0, 9.0, -3.0];
+        let expected: Vec<f32> = (0..small.len())
+            .map(|t| {
+                let mut w: Vec<f32> = (t as isize - h..=t as isize + h)
+                    .map(|i| {
+                        small
+                            .get(i as usize)
+                            .copied()
+                            .filter(|_| i >= 0)
+                            .unwrap_or(0.0)
+                    })
+                    .collect();
+                w.sort_by(f32::total_cmp);
+                w[h as usize]
+            })
+            .collect();
+        assert_eq!(median_filter_1d(&small, 2), expected);
+        assert_eq!(median_filter_1d(&small, 0), small.to_vec());
+    }
+
+    #[test]
+    fn savitzky_golay_recovers_polynomials_exactly() {
+        let n = 60;
+        let poly = |i: usize| {
+            let t = i as f64 / n as f64 - 0.5;
+            (2.0 - 3.0 * t + 4.0 * t * t - 5.0 * t * t * t) as f32
+        };
+        let trace: Vec<f32> = (0..n).map(poly).collect();
+        for (window, poly_order) in [(11, 3), (7, 4), (21, 5)] {
+            let out = savitzky_golay(&trace, window, poly_order).unwrap();
+            let max_err = out
+                .iter()
+                .zip(&trace)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0_f32, f32::max);
+            assert!(
+                max_err < 1e-4,
+                "window {window}, order {poly_order}: max error {max_err}"
+            );
+        }
+
+        // Window longer than the trace is clipped (10 -> 9 samples); a line
+        // still passes through.
+        let line: Vec<f32> = (0..10).map(|i| 0.5 * i as f32 - 1.0).collect();
+        let out = savitzky_golay(&line, 31, 1).unwrap();
+        for (a, b) in out.iter().zip(&line) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn savitzky_golay_smooths_noise_and_rejects_bad_params() {
+        let mut state = 1u64;
+        let noisy: Vec<f32> = (0..400)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+            })
+            .collect();
+        let smooth = savitzky_golay(&noisy, 15, 2).unwrap();
+        let var = |x: &[f32]| x.iter().map(|v| v * v).sum::<f32>() / x.len() as f32;
+        assert!(var(&smooth[20..380]) < 0.3 * var(&noisy[20..380]));
+
+        assert!(savitzky_golay(&noisy, 5, 5).is_err());
+        assert!(savitzky_golay(&noisy, 6, 2).is_err());
+        assert!(savitzky_golay(&noisy, 0, 0).is_err());
+        assert!(savitzky_golay(&noisy[..3], 9, 3).is_err());
+        assert!(savitzky_golay(&[], 5, 2).unwrap().is_empty());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{Constraint, ConvMode, Solver};
+use crate::{Constraint, ConvMode, Regularizer, Solver, SolverMode, StoppingCriterion};
 
 #[cfg(feature = "jsbindings")]
 use wasm_bindgen::prelude::*;
@@ -19,15 +19,27 @@ impl Solver {
     ///
     /// Uses FFT-based O(n log n) convolutions instead of time-domain O(n*k), and
     /// primal residual convergence criterion to eliminate one convolution per iteration.
+    ///
+    /// In `SolverMode::Admm`, `SolverMode::StochasticFista` and
+    /// `SolverMode::Diagonal` the batch is delegated to that iteration instead.
     pub fn step_batch(&mut self, n_steps: u32) -> bool {
+        match self.solver_mode {
+            SolverMode::Fista => {}
+            SolverMode::Admm { rho } => return self.step_batch_admm(n_steps, rho),
+            SolverMode::StochasticFista {
+                block_size,
+                variance_reduction,
+            } => return self.step_batch_stochastic(n_steps, block_size, variance_reduction),
+            SolverMode::Diagonal => return self.step_batch_diagonal(n_steps),
+        }
+
         let n = self.active_len;
         if n == 0 {
             self.converged = true;
             return true;
         }
 
-        let step_size = 1.0 / self.lipschitz_constant;
-        let threshold = step_size * self.effective_lambda();
+        let lambda = self.effective_lambda();
         let tol_sq = self.tolerance * self.tolerance;
 
         for _ in 0..n_steps {
@@ -38,9 +50,16 @@ impl Solver {
             // solution_prev holds the extrapolated point y_k
             // (on first iteration, y_0 = x_0 = solution = zeros)
 
+            if self.rolling_lipschitz_window > 0
+                && self.iteration > 0
+                && self.iteration % self.rolling_lipschitz_window as u32 == 0
+            {
+                self.rolling_lipschitz_step();
+            }
+
             // 1. Forward convolution at y_k: reconvolution = K * y_k
             match self.conv_mode {
-                ConvMode::Fft => self.fft.convolve_forward(
+                ConvMode::Fft | ConvMode::FftOverlapAdd => self.fft.convolve_forward(
                     &self.solution_prev[..n],
                     n,
                     &mut self.reconvolution[..n],
@@ -48,6 +67,12 @@ impl Solver {
                 ConvMode::BandedAR2 => self
                     .banded
                     .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
             }
 
             // 1b. Compute baseline: b = mean(trace - K*y_k)
@@ -68,18 +93,38 @@ impl Solver {
 
             // 3. Adjoint convolution: gradient = K^T * residual
             match self.conv_mode {
-                ConvMode::Fft => {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => {
                     self.fft
                         .convolve_adjoint(&self.residual_buf[..n], n, &mut self.gradient[..n])
                 }
                 ConvMode::BandedAR2 => self
                     .banded
                     .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            }
+
+            // 3b. Elastic-net ridge term: gradient += lambda2 * y_k
+            if self.lambda2 > 0.0 {
+                let lambda2_f32 = self.lambda2 as f32;
+                for i in 0..n {
+                    self.gradient[i] += lambda2_f32 * self.solution_prev[i];
+                }
             }
 
             // 4. Loop A (fused): save x_k + proximal gradient step
             //    x_{k+1} = prox(y_k - step_size * gradient)
             //    Constraint match hoisted outside inner loop for SIMD auto-vectorization.
+            let step_size = if self.line_search {
+                self.backtrack_step(n, lambda)
+            } else {
+                1.0 / self.smooth_lipschitz()
+            };
+            let threshold = step_size * lambda;
             let step_f32 = step_size as f32;
             let thresh_f32 = threshold as f32;
             match self.constraint {
@@ -101,6 +146,10 @@ impl Solver {
                 }
             }
 
+            if self.refractory_frames > 1 {
+                enforce_refractory(&mut self.solution[..n], self.refractory_frames);
+            }
+
             self.iteration += 1;
 
             // 5+6. Fused Loop B+C: convergence/restart accumulators + momentum extrapolation.
@@ -113,6 +162,7 @@ impl Solver {
             let mut diff_sq = 0.0_f64;
             let mut xk_sq = 0.0_f64;
             let mut dot = 0.0_f64;
+            let mut gm_sq = 0.0_f64;
 
             // Constraint match hoisted outside the inner loop for SIMD auto-vectorization.
             // The `dot` accumulator is always computed (one fma per element) to avoid
@@ -127,7 +177,9 @@ impl Solver {
                         let d = x_new_f64 - x_old_f64;
                         diff_sq += d * d;
                         xk_sq += x_old_f64 * x_old_f64;
-                        dot += (self.solution_prev[i] as f64 - x_new_f64) * d;
+                        let gm = self.solution_prev[i] as f64 - x_new_f64;
+                        dot += gm * d;
+                        gm_sq += gm * gm;
                         self.solution_prev[i] = (x_new + momentum * (x_new - x_old)).max(0.0);
                     }
                 }
@@ -140,7 +192,9 @@ impl Solver {
                         let d = x_new_f64 - x_old_f64;
                         diff_sq += d * d;
                         xk_sq += x_old_f64 * x_old_f64;
-                        dot += (self.solution_prev[i] as f64 - x_new_f64) * d;
+                        let gm = self.solution_prev[i] as f64 - x_new_f64;
+                        dot += gm * d;
+                        gm_sq += gm * gm;
                         self.solution_prev[i] =
                             (x_new + momentum * (x_new - x_old)).clamp(0.0, 1.0);
                     }
@@ -151,16 +205,53 @@ impl Solver {
             // Undo the speculative momentum by setting solution_prev = solution.
             // This is correct because with momentum=0, y_{k+1} = x_{k+1} = solution,
             // and solution already satisfies Box01 from the prox step.
-            if check_restart && dot > 0.0 {
+            // With a restart threshold the inner product must exceed that
+            // fraction of ||y_k - x_{k+1}|| * ||x_{k+1} - x_k|| (cosine margin).
+            let restarted =
+                check_restart && dot > self.restart_threshold * (gm_sq * diff_sq).sqrt();
+            if restarted {
                 self.t_fista = 1.0;
                 self.solution_prev[..n].copy_from_slice(&self.solution[..n]);
             } else {
                 self.t_fista = t_new;
             }
 
-            // 7. Convergence check using primal residual (squared comparison)
-            if self.iteration > 5 && diff_sq < tol_sq * (xk_sq + 1e-20) {
-                self.converged = true;
+            // 7. Convergence check: primal residual (squared comparison) or,
+            //    every few iterations, the relative duality gap
+            let mut objective = None;
+            match self.stopping_criterion {
+                StoppingCriterion::PrimalResidual => {
+                    if self.iteration > 5 && diff_sq < tol_sq * (xk_sq + 1e-20) {
+                        self.converged = true;
+                    }
+                }
+                StoppingCriterion::DualGap {
+                    max_iters_between_checks,
+                } => {
+                    if self.iteration % max_iters_between_checks.max(1) == 0 {
+                        let gap = self.relative_dual_gap();
+                        objective = Some(self.prev_objective);
+                        if gap < self.tolerance {
+                            self.converged = true;
+                        }
+                    }
+                }
+            }
+
+            if self.history.enabled() {
+                if objective.is_none() {
+                    self.convolve_solution();
+                    let value = self.objective_from_reconvolution();
+                    self.prev_objective = value;
+                    objective = Some(value);
+                }
+                self.history.push(crate::IterRecord {
+                    iteration: self.iteration,
+                    diff_sq_rel: diff_sq / (xk_sq + 1e-20),
+                    t_fista: self.t_fista,
+                    restarted,
+                    objective,
+                });
             }
 
             // Mark reconvolution as stale (it currently holds K*y_k, not K*x_{k+1})
@@ -169,11 +260,313 @@ impl Solver {
 
         self.converged
     }
+
+    /// Use the elastic-net penalty `lambda1 * G_dc * ||x||_1 + lambda2/2 * ||x||^2`
+    /// (`Regularizer::ElasticNet`); `lambda2 = 0` is plain L1.
+    pub fn set_elastic_net(&mut self, lambda1: f64, lambda2: f64) {
+        self.set_regularizer(Regularizer::ElasticNet { lambda1, lambda2 });
+    }
+
+    /// Keep nonzero solution entries at least `n` frames apart (0 or 1 turns
+    /// this off). After each FISTA proximal step a causal scan zeroes the
+    /// `n - 1` entries following every nonzero one.
+    ///
+    /// This is a post-hoc projection, not the proximal operator of a
+    /// refractory constraint: the feasible set is not convex, the earliest
+    /// spike in a cluster always wins regardless of amplitude, and the
+    /// objective is no longer guaranteed to decrease, so convergence is only
+    /// heuristic. ADMM, stochastic and diagonal FISTA ignore this setting.
+    pub fn set_refractory_frames(&mut self, n: usize) {
+        self.refractory_frames = n;
+        self.converged = false;
+    }
+
+    /// Minimum spike spacing enforced by `step_batch` (0 = off).
+    pub fn refractory_frames(&self) -> usize {
+        self.refractory_frames
+    }
+
+    /// Only restart FISTA momentum when the gradient-mapping inner product
+    /// `<y_k - x_{k+1}, x_{k+1} - x_k>` exceeds `relative_tol` times
+    /// `||y_k - x_{k+1}|| * ||x_{k+1} - x_k||`, i.e. when the two directions
+    /// disagree by more than that cosine. The default 0 restarts on any
+    /// positive inner product; a small margin (e.g. 0.1) stops near-zero
+    /// gradients on sparse solutions from resetting momentum spuriously.
+    /// Negative values are treated as 0.
+    pub fn set_restart_threshold(&mut self, relative_tol: f64) {
+        self.restart_threshold = relative_tol.max(0.0);
+    }
+
+    /// Relative adaptive-restart margin (see `set_restart_threshold`).
+    pub fn restart_threshold(&self) -> f64 {
+        self.restart_threshold
+    }
+}
+
+// Methods wasm-bindgen cannot export (generic or non-C-like enum arguments).
+impl Solver {
+    /// Select the convergence test used by subsequent FISTA `step_batch` calls.
+    pub fn set_stopping_criterion(&mut self, criterion: StoppingCriterion) {
+        self.stopping_criterion = criterion;
+        self.converged = false;
+    }
+
+    /// The convergence test currently used by `step_batch`.
+    pub fn stopping_criterion(&self) -> StoppingCriterion {
+        self.stopping_criterion
+    }
+
+    /// Select the penalty for subsequent `step_batch` calls. The ridge term
+    /// raises the step-size Lipschitz constant to `L + lambda2`; the
+    /// constraint's proximal operator is unchanged. A later `set_params`
+    /// replaces `lambda1` but keeps `lambda2`.
+    pub fn set_regularizer(&mut self, regularizer: Regularizer) {
+        match regularizer {
+            Regularizer::L1 => self.lambda2 = 0.0,
+            Regularizer::ElasticNet { lambda1, lambda2 } => {
+                self.lambda = lambda1;
+                self.lambda2 = lambda2;
+            }
+        }
+        self.converged = false;
+    }
+
+    /// The penalty currently used by `step_batch`.
+    pub fn regularizer(&self) -> Regularizer {
+        if self.lambda2 > 0.0 {
+            Regularizer::ElasticNet {
+                lambda1: self.lambda,
+                lambda2: self.lambda2,
+            }
+        } else {
+            Regularizer::L1
+        }
+    }
+
+    /// Armijo backtracking for the step from `y = solution_prev`, called with
+    /// the gradient at `y` in `gradient` and the residual `K·y + b - trace` in
+    /// `residual_buf`; `f` includes the elastic-net ridge term. Halves the step from `1/(ls_scale·L)` until
+    /// `f(x⁺) ≤ f(y) + ⟨g, x⁺ - y⟩ + ||x⁺ - y||² / (2·step)` holds for the
+    /// proximal point `x⁺`, giving up after `log2(1/tol)` halvings.
+    ///
+    /// Uses `residual_buf` (trial point) and `reconvolution` (`K·x⁺`) as
+    /// scratch; the FISTA step overwrites both afterwards.
+    fn backtrack_step(&mut self, n: usize, lambda: f64) -> f64 {
+        // Slack on the test for f32 rounding in the convolutions.
+        const LS_SLACK: f64 = 1e-6;
+
+        let f_y = 0.5
+            * self.residual_buf[..n]
+                .iter()
+                .map(|&r| r as f64 * r as f64)
+                .sum::<f64>()
+            + 0.5
+                * self.lambda2
+                * self.solution_prev[..n]
+                    .iter()
+                    .map(|&v| v as f64 * v as f64)
+                    .sum::<f64>();
+        let max_halvings = (1.0 / self.tolerance).log2().ceil().max(1.0) as u32;
+        let baseline = self.baseline as f32;
+        let mut halvings = 0;
+        loop {
+            let step = 1.0 / (self.ls_scale * self.smooth_lipschitz());
+            let (step_f32, thresh_f32) = (step as f32, (step * lambda) as f32);
+            let mut lin = 0.0_f64;
+            let mut dist_sq = 0.0_f64;
+            for i in 0..n {
+                let y = self.solution_prev[i];
+                let z = y - step_f32 * self.gradient[i] - thresh_f32;
+                let x = match self.constraint {
+                    Constraint::NonNegative => z.max(0.0),
+                    Constraint::Box01 => z.clamp(0.0, 1.0),
+                };
+                self.residual_buf[i] = x;
+                let d = (x - y) as f64;
+                lin += self.gradient[i] as f64 * d;
+                dist_sq += d * d;
+            }
+            match self.conv_mode {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => self.fft.convolve_forward(
+                    &self.residual_buf[..n],
+                    n,
+                    &mut self.reconvolution[..n],
+                ),
+                ConvMode::BandedAR2 => self
+                    .banded
+                    .convolve_forward(&self.residual_buf[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_forward(&self.residual_buf[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_forward(&self.residual_buf[..n], &mut self.reconvolution[..n]),
+            }
+            let f_x = 0.5
+                * (0..n)
+                    .map(|i| {
+                        let r = (self.reconvolution[i] + baseline - self.trace[i]) as f64;
+                        r * r
+                    })
+                    .sum::<f64>()
+                + 0.5
+                    * self.lambda2
+                    * self.residual_buf[..n]
+                        .iter()
+                        .map(|&v| v as f64 * v as f64)
+                        .sum::<f64>();
+            let bound = f_y + lin + dist_sq / (2.0 * step);
+            if f_x <= bound + LS_SLACK * (bound.abs() + 1.0) || halvings == max_halvings {
+                return step;
+            }
+            self.ls_scale *= 2.0;
+            halvings += 1;
+        }
+    }
+
+    /// Duality gap of the current solution, relative to `|f(x)| + 1` where
+    /// `f(x) = ½||K·x + b - y||²`.
+    ///
+    /// With residual `r = y - b - K·x`, the primal objective is
+    /// `P(x) = ½||r||² + λ·Σx` and the dual is evaluated at a scaled residual
+    /// `θ`: `D(θ) = ½||y - b||² - ½||y - b - θ||² - h*(Kᵀθ)`, where `h*` is
+    /// the conjugate of the penalty plus constraint. For `NonNegative`, `h*`
+    /// is the indicator of `Kᵀθ ≤ λ`, so `θ = r·min(1, λ / max(Kᵀr))`; for
+    /// `Box01`, `h*(v) = Σ max(0, vᵢ - λ)` is finite everywhere and `θ = r`.
+    /// The gap `P(x) - D(θ)` is non-negative and zero only at the optimum.
+    ///
+    /// The elastic-net ridge term is handled as a Lasso on the augmented
+    /// operator `[K; √λ₂·I]`: `f` gains `½λ₂||x||²`, `Kᵀr` becomes
+    /// `Kᵀr - λ₂·x`, and the extra residual rows `-√λ₂·x` enter `D(θ)`.
+    ///
+    /// Uses `reconvolution`, `residual_buf` and `gradient` as scratch; all
+    /// three are recomputed by the next iteration. The primal objective is
+    /// kept in `prev_objective` for the iteration history.
+    pub(crate) fn relative_dual_gap(&mut self) -> f64 {
+        let n = self.active_len;
+        match self.conv_mode {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                self.fft
+                    .convolve_forward(&self.solution[..n], n, &mut self.reconvolution[..n])
+            }
+            ConvMode::BandedAR2 => self
+                .banded
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+            ConvMode::BandedAR1 => self
+                .banded_ar1
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+            ConvMode::BandedARp => self
+                .arp
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+        }
+        let baseline = self.baseline as f32;
+        for i in 0..n {
+            self.residual_buf[i] = self.trace[i] - baseline - self.reconvolution[i];
+        }
+        match self.conv_mode {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                self.fft
+                    .convolve_adjoint(&self.residual_buf[..n], n, &mut self.gradient[..n])
+            }
+            ConvMode::BandedAR2 => self
+                .banded
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            ConvMode::BandedAR1 => self
+                .banded_ar1
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            ConvMode::BandedARp => self
+                .arp
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+        }
+
+        let x_sq: f64 = self.solution[..n]
+            .iter()
+            .map(|&v| v as f64 * v as f64)
+            .sum();
+        if self.lambda2 > 0.0 {
+            let lambda2_f32 = self.lambda2 as f32;
+            for i in 0..n {
+                self.gradient[i] -= lambda2_f32 * self.solution[i];
+            }
+        }
+
+        let lambda = self.effective_lambda();
+        let r = &self.residual_buf[..n];
+        let ktr = &self.gradient[..n]; // Kᵀr - λ₂·x = -∇f(x)
+        let f: f64 =
+            0.5 * r.iter().map(|&v| v as f64 * v as f64).sum::<f64>() + 0.5 * self.lambda2 * x_sq;
+        let l1: f64 = self.solution[..n].iter().map(|&v| v as f64).sum();
+        let primal = f + lambda * l1;
+        self.prev_objective = primal;
+
+        let (scale, conjugate) = match self.constraint {
+            Constraint::NonNegative => {
+                let max_ktr = ktr.iter().fold(0.0_f64, |m, &v| m.max(v as f64));
+                let scale = if max_ktr > lambda {
+                    lambda / max_ktr
+                } else {
+                    1.0
+                };
+                (scale, 0.0)
+            }
+            Constraint::Box01 => {
+                let conjugate = ktr.iter().map(|&v| (v as f64 - lambda).max(0.0)).sum();
+                (1.0, conjugate)
+            }
+        };
+        // ½||y-b||² - ½||y-b-θ||² with y-b = r + K·x and θ = scale·r.
+        let dual = r
+            .iter()
+            .zip(&self.reconvolution[..n])
+            .map(|(&ri, &kx)| {
+                let (ri, yb) = (ri as f64, ri as f64 + kx as f64);
+                let rest = yb - scale * ri;
+                0.5 * (yb * yb - rest * rest)
+            })
+            .sum::<f64>()
+            - 0.5 * scale * scale * self.lambda2 * x_sq
+            - conjugate;
+
+        (primal - dual).max(0.0) / (f.abs() + 1.0)
+    }
+
+    /// `step_batch` with an early exit: `should_stop` is polled before every
+    /// iteration, and once it returns true the batch ends without marking the
+    /// solver converged. Returns true if converged.
+    pub fn step_batch_until(
+        &mut self,
+        n_steps: u32,
+        mut should_stop: impl FnMut() -> bool,
+    ) -> bool {
+        for _ in 0..n_steps {
+            if self.converged || should_stop() {
+                break;
+            }
+            self.step_batch(1);
+        }
+        self.converged
+    }
+}
+
+/// Causal refractory scan: zero the `n - 1` entries after each nonzero one,
+/// so surviving nonzeros are at least `n` apart.
+fn enforce_refractory(solution: &mut [f32], n: usize) {
+    let mut i = 0;
+    while i < solution.len() {
+        if solution[i] > 0.0 {
+            let end = (i + n).min(solution.len());
+            solution[i + 1..end].fill(0.0);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::kernel::build_kernel;
+    use crate::test_util::build_trace;
     use crate::Solver;
 
     /// Helper: create a solver with given params and run to convergence
@@ -194,19 +587,6 @@ mod tests {
         total_batches
     }
 
-    /// Helper: build an f32 trace from kernel convolved with spikes
-    fn build_trace(kernel: &[f32], n: usize, spikes: &[usize]) -> Vec<f32> {
-        let mut trace = vec![0.0_f32; n];
-        for &s in spikes {
-            for (k, &kv) in kernel.iter().enumerate() {
-                if s + k < n {
-                    trace[s + k] += kv;
-                }
-            }
-        }
-        trace
-    }
-
     // Test 1: Delta impulse recovery
     // trace = kernel (convolving a single spike at t=0 produces the kernel)
     // Solver should recover a spike at t=0 and near-zeros elsewhere
@@ -287,7 +667,11 @@ mod tests {
         solver.set_params(0.02, 0.4, 0.01, 30.0);
 
         let kernel = build_kernel(0.02, 0.4, 30.0);
-        let trace = build_trace(&kernel, 200, &[10, 50, 100, 150]);
+        let trace = build_trace(
+            &kernel,
+            200,
+            &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)],
+        );
 
         solve_to_convergence(&mut solver, &trace, 100, 10);
 
@@ -333,7 +717,7 @@ mod tests {
     #[test]
     fn deterministic_output() {
         let kernel = build_kernel(0.02, 0.4, 30.0);
-        let trace = build_trace(&kernel, 150, &[10, 50, 100]);
+        let trace = build_trace(&kernel, 150, &[(10, 1.0), (50, 1.0), (100, 1.0)]);
 
         // Run 1
         let mut solver1 = Solver::new();
@@ -367,7 +751,7 @@ mod tests {
 
         let kernel = build_kernel(0.02, 0.4, 30.0);
         let n = 200;
-        let trace = build_trace(&kernel, n, &[10, 50, 100, 150]);
+        let trace = build_trace(&kernel, n, &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)]);
 
         solve_to_convergence(&mut solver, &trace, 200, 10);
 
@@ -394,7 +778,11 @@ mod tests {
     #[test]
     fn warm_start_faster_convergence() {
         let kernel = build_kernel(0.02, 0.4, 30.0);
-        let trace = build_trace(&kernel, 200, &[10, 50, 100, 150]);
+        let trace = build_trace(
+            &kernel,
+            200,
+            &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)],
+        );
 
         // Cold start solve with original lambda
         let mut solver = Solver::new();
@@ -491,7 +879,7 @@ mod tests {
         let kernel = build_kernel(0.02, 0.4, 30.0);
         let n = 200;
         let dc_offset = 5.0_f32;
-        let mut trace = build_trace(&kernel, n, &[10, 50, 100, 150]);
+        let mut trace = build_trace(&kernel, n, &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)]);
         for i in 0..n {
             trace[i] += dc_offset;
         }
@@ -529,7 +917,7 @@ mod tests {
     fn lambda_scaling_affects_sparsity() {
         let kernel = build_kernel(0.02, 0.4, 30.0);
         let n = 200;
-        let trace = build_trace(&kernel, n, &[10, 50, 100, 150]);
+        let trace = build_trace(&kernel, n, &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)]);
 
         // Solve with low lambda
         let mut solver_low = Solver::new();
@@ -561,13 +949,11 @@ mod tests {
 
         let kernel = build_kernel(0.02, 0.4, 30.0);
         let n = 200;
-        let trace = build_trace(&kernel, n, &[10, 50, 100, 150]);
+        let trace = build_trace(&kernel, n, &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)]);
         solver.set_trace(&trace);
 
         // Set up a known signal in solution_prev
-        for i in 0..n {
-            solver.solution_prev[i] = trace[i];
-        }
+        solver.solution_prev[..n].copy_from_slice(&trace);
 
         // FFT-based forward convolution
         let mut fft_result = vec![0.0_f32; n];
@@ -610,7 +996,11 @@ mod tests {
         use crate::ConvMode;
 
         let kernel = build_kernel(0.02, 0.4, 30.0);
-        let trace = build_trace(&kernel, 200, &[10, 50, 100, 150]);
+        let trace = build_trace(
+            &kernel,
+            200,
+            &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)],
+        );
 
         let mut solver = Solver::new();
         solver.set_params(0.02, 0.4, 0.01, 30.0);
@@ -645,7 +1035,7 @@ mod tests {
 
         let kernel = build_kernel(0.02, 0.4, 30.0);
         let n = 200;
-        let trace = build_trace(&kernel, n, &[10, 50, 100, 150]);
+        let trace = build_trace(&kernel, n, &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)]);
 
         // Scale trace so solution would exceed 1.0 without box constraint
         let scaled_trace: Vec<f32> = trace.iter().map(|&v| v * 5.0).collect();
@@ -659,11 +1049,680 @@ mod tests {
         let solution = solver.get_solution();
         for (i, &v) in solution.iter().enumerate() {
             assert!(
-                v >= 0.0 && v <= 1.0,
+                (0.0..=1.0).contains(&v),
                 "Box01 solution at index {} should be in [0,1], got {}",
                 i,
                 v
             );
         }
     }
+
+    // Test 14: tau_rise = 0 sentinel selects BandedAR1 and recovers spikes
+    #[test]
+    fn zero_tau_rise_uses_ar1_path() {
+        use crate::ConvMode;
+
+        let kernel = build_kernel(0.0, 0.4, 30.0);
+        let trace = build_trace(
+            &kernel,
+            200,
+            &[(10, 1.0), (50, 1.0), (100, 1.0), (150, 1.0)],
+        );
+
+        let mut solver = Solver::new();
+        solver.set_params(0.0, 0.4, 0.001, 30.0);
+        assert!(solver.conv_mode == ConvMode::BandedAR1);
+        solve_to_convergence(&mut solver, &trace, 200, 10);
+        assert!(solver.converged(), "AR1 solver should converge");
+
+        let solution = solver.get_solution();
+        for &pos in &[10usize, 50, 100, 150] {
+            assert!(
+                solution[pos] > 0.5,
+                "expected a spike at {}, got {}",
+                pos,
+                solution[pos]
+            );
+        }
+
+        // A positive tau_rise returns to the two-tap banded engine.
+        solver.set_params(0.02, 0.4, 0.001, 30.0);
+        assert!(solver.conv_mode == ConvMode::BandedAR2);
+    }
+
+    // Test 15: Triple-exponential kernel — FFT matches time domain, Lipschitz
+    // is computed for the new kernel and banded modes are refused
+    #[test]
+    fn triple_exp_fft_matches_time_domain() {
+        use crate::kernel::{build_triple_exp_kernel, compute_lipschitz};
+        use crate::{ConvMode, KernelType};
+
+        let mut solver = Solver::new();
+        solver.set_conv_mode(ConvMode::BandedAR2);
+        solver.set_triple_exp_params(0.02, 0.2, 1.5, 0.6, 0.01, 30.0);
+        assert!(solver.conv_mode == ConvMode::Fft);
+        assert!(matches!(solver.kernel_type(), KernelType::TripleExp { .. }));
+
+        let kernel = build_triple_exp_kernel(0.02, 0.2, 1.5, 0.6, 30.0);
+        assert_eq!(solver.kernel, kernel);
+        assert_eq!(solver.lipschitz_constant, compute_lipschitz(&kernel));
+
+        let n = 300;
+        let trace = build_trace(&kernel, n, &[(10, 1.0), (60, 1.0), (140, 1.0), (220, 1.0)]);
+        solver.set_trace(&trace);
+        let mut fft_result = vec![0.0_f32; n];
+        solver.fft.convolve_forward(&trace, n, &mut fft_result);
+
+        let mut td_result = vec![0.0_f32; n];
+        for (t, out) in td_result.iter_mut().enumerate() {
+            *out = (0..kernel.len().min(t + 1))
+                .map(|k| kernel[k] * trace[t - k])
+                .sum();
+        }
+        let num: f64 = fft_result
+            .iter()
+            .zip(&td_result)
+            .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+            .sum();
+        let den: f64 = td_result.iter().map(|&b| (b as f64).powi(2)).sum();
+        let rel_err = (num / den).sqrt();
+        assert!(
+            rel_err < 1e-3,
+            "FFT vs time-domain relative error {rel_err}"
+        );
+
+        solver.set_conv_mode(ConvMode::BandedAR2);
+        assert!(solver.conv_mode == ConvMode::Fft);
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        assert!(solver.kernel_type() == KernelType::DoubleExp);
+        solver.set_conv_mode(ConvMode::BandedAR2);
+        assert!(solver.conv_mode == ConvMode::BandedAR2);
+    }
+
+    // Test 16: reset() clears the solve but keeps the trace, so a second
+    // solve reproduces the first
+    #[test]
+    fn reset_restarts_solve_on_same_trace() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 200, &[(10, 1.0), (60, 1.0), (140, 1.0)]);
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solve_to_convergence(&mut solver, &trace, 200, 10);
+        let first = solver.get_solution();
+        let first_iters = solver.iteration_count();
+
+        solver.reset();
+        assert_eq!(solver.iteration_count(), 0);
+        assert!(!solver.converged());
+        assert!(solver.get_solution().iter().all(|&v| v == 0.0));
+        assert_eq!(solver.get_trace(), trace);
+
+        for _ in 0..200 {
+            if solver.step_batch(10) {
+                break;
+            }
+        }
+        assert_eq!(solver.iteration_count(), first_iters);
+        assert_eq!(solver.get_solution(), first);
+    }
+
+    // Test 17: step_batch_until stops as soon as should_stop fires and
+    // otherwise matches step_batch
+    #[test]
+    fn step_batch_until_stops_early() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 200, &[(10, 1.0), (60, 1.0), (140, 1.0)]);
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.set_trace(&trace);
+        let mut polls = 0;
+        let converged = solver.step_batch_until(50, || {
+            polls += 1;
+            polls > 7
+        });
+        assert!(!converged);
+        assert_eq!(solver.iteration_count(), 7);
+
+        let mut reference = Solver::new();
+        reference.set_params(0.02, 0.4, 0.01, 30.0);
+        reference.set_trace(&trace);
+        reference.step_batch(7);
+        assert_eq!(solver.get_solution(), reference.get_solution());
+    }
+
+    // Test 18: sparse getters agree with the dense solution
+    #[test]
+    fn sparse_solution_matches_dense() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 200, &[(10, 1.0), (60, 1.0), (140, 1.0)]);
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solve_to_convergence(&mut solver, &trace, 200, 10);
+        let dense = solver.get_solution();
+
+        let sparse = solver.get_sparse_solution(1e-6);
+        let expected: Vec<(u32, f32)> = dense
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v >= 1e-6)
+            .map(|(i, &v)| (i as u32, v))
+            .collect();
+        assert!(!sparse.is_empty() && sparse.len() < dense.len());
+        assert_eq!(sparse, expected);
+        assert_eq!(solver.get_spike_count(1e-6) as usize, sparse.len());
+
+        let interleaved = solver.get_sparse_solution_interleaved(1e-6);
+        assert_eq!(interleaved.len(), 2 * sparse.len());
+        assert_eq!(interleaved[0], sparse[0].0 as f32);
+        assert_eq!(interleaved[1], sparse[0].1);
+    }
+
+    // Test 19: the dual-gap criterion stops only on a small certified gap,
+    // checked every `max_iters_between_checks` iterations
+    #[test]
+    fn dual_gap_stopping_certifies_solution() {
+        use crate::{Constraint, StoppingCriterion};
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 300, &[(20, 1.0), (90, 1.0), (95, 1.0), (200, 1.0)]);
+
+        for constraint in [Constraint::NonNegative, Constraint::Box01] {
+            let mut solver = Solver::new();
+            solver.set_params(0.02, 0.4, 0.01, 30.0);
+            solver.set_constraint(constraint);
+            solver.set_stopping_criterion(StoppingCriterion::DualGap {
+                max_iters_between_checks: 7,
+            });
+            solver.set_trace(&trace);
+            let converged = (0..500).any(|_| solver.step_batch(10));
+            assert!(converged, "{constraint:?}: dual gap never closed");
+            assert_eq!(solver.iteration_count() % 7, 0);
+            assert!(solver.relative_dual_gap() < solver.tolerance);
+
+            let mut reference = Solver::new();
+            reference.set_params(0.02, 0.4, 0.01, 30.0);
+            reference.set_constraint(constraint);
+            reference.set_trace(&trace);
+            assert!((0..500).any(|_| reference.step_batch(10)));
+            let max_diff = solver
+                .get_solution()
+                .iter()
+                .zip(reference.get_solution())
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0_f32, f32::max);
+            assert!(max_diff < 0.05, "{constraint:?}: max diff {max_diff}");
+
+            // Early on the gap is far from closed.
+            let mut early = Solver::new();
+            early.set_params(0.02, 0.4, 0.01, 30.0);
+            early.set_constraint(constraint);
+            early.set_trace(&trace);
+            early.step_batch(2);
+            assert!(early.relative_dual_gap() > early.tolerance);
+        }
+    }
+
+    // Test 20: line search recovers from an underestimated Lipschitz constant
+    #[test]
+    fn line_search_recovers_from_small_lipschitz() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 300, &[(20, 1.0), (90, 1.0), (95, 1.0), (200, 1.0)]);
+
+        let mut reference = Solver::new();
+        reference.set_params(0.02, 0.4, 0.01, 30.0);
+        solve_to_convergence(&mut reference, &trace, 500, 10);
+        let expected = reference.get_solution();
+
+        // Pretend L is 16x smaller than it is: the fixed step overshoots.
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.lipschitz_constant /= 16.0;
+        solver.set_line_search(true);
+        solve_to_convergence(&mut solver, &trace, 500, 10);
+        assert!(solver.converged());
+        assert!(solver.ls_scale >= 2.0, "backtracking never triggered");
+        let max_diff = solver
+            .get_solution()
+            .iter()
+            .zip(&expected)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0_f32, f32::max);
+        assert!(max_diff < 0.05, "line-search solution off by {max_diff}");
+
+        // With the true L the condition holds at the first trial step.
+        let mut exact = Solver::new();
+        exact.set_params(0.02, 0.4, 0.01, 30.0);
+        exact.set_line_search(true);
+        solve_to_convergence(&mut exact, &trace, 500, 10);
+        assert_eq!(exact.ls_scale, 1.0);
+        assert_eq!(exact.get_solution(), expected);
+    }
+
+    // Test 21: elastic net reduces to L1 at lambda2 = 0, shrinks the solution
+    // for lambda2 > 0, and its ridge term is consistent with the dual gap
+    #[test]
+    fn elastic_net_ridge_term() {
+        use crate::{Regularizer, StoppingCriterion};
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 300, &[(20, 1.0), (90, 1.0), (95, 1.0), (200, 1.0)]);
+
+        let mut l1 = Solver::new();
+        l1.set_params(0.02, 0.4, 0.01, 30.0);
+        solve_to_convergence(&mut l1, &trace, 500, 10);
+
+        let mut zero_ridge = Solver::new();
+        zero_ridge.set_params(0.02, 0.4, 0.5, 30.0);
+        zero_ridge.set_regularizer(Regularizer::ElasticNet {
+            lambda1: 0.01,
+            lambda2: 0.0,
+        });
+        assert_eq!(zero_ridge.regularizer(), Regularizer::L1);
+        solve_to_convergence(&mut zero_ridge, &trace, 500, 10);
+        assert_eq!(zero_ridge.get_solution(), l1.get_solution());
+
+        let mut enet = Solver::new();
+        enet.set_params(0.02, 0.4, 0.01, 30.0);
+        let lambda2 = 0.2 * enet.lipschitz_constant;
+        enet.set_elastic_net(0.01, lambda2);
+        assert_eq!(
+            enet.regularizer(),
+            Regularizer::ElasticNet {
+                lambda1: 0.01,
+                lambda2
+            }
+        );
+        enet.set_stopping_criterion(StoppingCriterion::DualGap {
+            max_iters_between_checks: 5,
+        });
+        solve_to_convergence(&mut enet, &trace, 500, 10);
+        assert!(enet.converged(), "elastic-net dual gap never closed");
+        assert!(enet.relative_dual_gap() < enet.tolerance);
+
+        let norm = |x: &[f32]| x.iter().map(|&v| v * v).sum::<f32>();
+        assert!(norm(&enet.get_solution()) < norm(&l1.get_solution()));
+    }
+
+    // Test 22: power-iteration Lipschitz re-estimate agrees with the analytic
+    // constant for every convolution engine
+    #[test]
+    fn reestimated_lipschitz_matches_analytic() {
+        use crate::ConvMode;
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 2000, &[(100, 1.0), (900, 1.0)]);
+        for mode in [ConvMode::Fft, ConvMode::BandedAR2, ConvMode::BandedAR1] {
+            let mut solver = Solver::new();
+            solver.set_conv_mode(mode);
+            solver.set_params(0.02, 0.4, 0.01, 30.0);
+            solver.set_trace(&trace);
+            let analytic = solver.get_lipschitz();
+            assert_eq!(solver.reestimate_lipschitz(0), analytic);
+
+            let estimate = solver.reestimate_lipschitz(300);
+            assert_eq!(solver.get_lipschitz(), estimate);
+            assert!(
+                estimate <= analytic * 1.001 && estimate >= analytic * 0.95,
+                "{mode:?}: power iteration {estimate} vs analytic {analytic}"
+            );
+        }
+    }
+
+    // Test 23: each kernel normalization gives its defining property, and the
+    // banded AR(2) impulse response and Lipschitz constant follow the FFT kernel
+    #[test]
+    fn kernel_norm_modes() {
+        use crate::{ConvMode, KernelNorm};
+
+        let fs = 30.0;
+        let mut solver = Solver::new();
+        solver.set_conv_mode(ConvMode::BandedAR2);
+        solver.set_params(0.02, 0.4, 0.01, fs);
+        let peak_lipschitz = solver.get_lipschitz();
+        let raw_peak = solver.banded.impulse_peak();
+        assert!(raw_peak > 1.0);
+
+        for norm in [
+            KernelNorm::Peak,
+            KernelNorm::Area,
+            KernelNorm::L2,
+            KernelNorm::None,
+        ] {
+            solver.set_kernel_norm(norm);
+            assert_eq!(solver.kernel_norm(), norm);
+            let kernel: Vec<f64> = solver.get_kernel().iter().map(|&k| k as f64).collect();
+            let (peak, sum, sum_sq) = (
+                kernel.iter().cloned().fold(0.0, f64::max),
+                kernel.iter().sum::<f64>(),
+                kernel.iter().map(|k| k * k).sum::<f64>(),
+            );
+            let (property, expected) = match norm {
+                KernelNorm::Peak => (peak, 1.0),
+                KernelNorm::Area => (sum / fs, 1.0),
+                KernelNorm::L2 => (sum_sq, 1.0),
+                KernelNorm::None => (peak, raw_peak),
+            };
+            assert!(
+                (property - expected).abs() < 1e-4 * expected,
+                "{norm:?}: {property} vs {expected}"
+            );
+
+            // Banded impulse response matches the FFT kernel sample for sample.
+            let mut impulse = vec![0.0_f32; kernel.len()];
+            impulse[0] = 1.0;
+            let mut response = vec![0.0_f32; kernel.len()];
+            solver.banded.convolve_forward(&impulse, &mut response);
+            for (i, (&r, &k)) in response.iter().zip(&kernel).enumerate() {
+                assert!((r as f64 - k).abs() < 1e-4 * peak, "{norm:?} sample {i}");
+            }
+
+            // The peak is the factor applied to the unit-peak operator.
+            let lipschitz = solver.get_lipschitz();
+            assert!(
+                (lipschitz - peak_lipschitz * peak * peak).abs() < 1e-5 * lipschitz,
+                "{norm:?}: Lipschitz {lipschitz}"
+            );
+        }
+    }
+
+    // Test 24: AR(p) engine — a three-component kernel recovers its spikes in
+    // BandedARp and FFT mode alike, and a double-exponential kernel in
+    // BandedARp mode matches BandedAR2
+    #[test]
+    fn arp_engine_solves() {
+        use crate::{ARpModel, ConvMode, KernelType};
+
+        let taus = [0.03, 0.3, 1.5];
+        let kernel = ARpModel::from_time_constants(&taus, 30.0).impulse_response();
+        let spikes = [40, 200, 420];
+        let trace = build_trace(&kernel, 600, &spikes.map(|s| (s, 1.0)));
+
+        let mut solutions = Vec::new();
+        for mode in [ConvMode::BandedARp, ConvMode::Fft, ConvMode::BandedAR2] {
+            let mut solver = Solver::new();
+            solver.set_ar_params(&taus, 0.01, 30.0);
+            assert_eq!(solver.kernel_type(), KernelType::ARp { order: 3 });
+            assert_eq!(solver.get_kernel(), kernel);
+            solver.set_conv_mode(mode);
+            let expected = if mode == ConvMode::Fft {
+                ConvMode::Fft
+            } else {
+                ConvMode::BandedARp
+            };
+            assert_eq!(solver.conv_mode, expected);
+            solve_to_convergence(&mut solver, &trace, 500, 10);
+            let sol = solver.get_solution();
+            for &s in &spikes {
+                let mass: f32 = sol[s.saturating_sub(2)..s + 3].iter().sum();
+                assert!(mass > 0.8, "{mode:?}: spike {s} mass {mass}");
+            }
+            solutions.push(sol);
+        }
+        for (&a, &b) in solutions[0].iter().zip(&solutions[1]) {
+            assert!((a - b).abs() < 0.05, "BandedARp {a} vs FFT {b}");
+        }
+
+        let bi_kernel = build_kernel(0.02, 0.4, 30.0);
+        let bi_trace = build_trace(&bi_kernel, 300, &[(30, 1.0), (150, 1.0)]);
+        let mut ar2 = Solver::new();
+        ar2.set_conv_mode(ConvMode::BandedAR2);
+        ar2.set_params(0.02, 0.4, 0.01, 30.0);
+        let mut arp = Solver::new();
+        arp.set_conv_mode(ConvMode::BandedARp);
+        arp.set_params(0.02, 0.4, 0.01, 30.0);
+        assert!((arp.get_lipschitz() - ar2.get_lipschitz()).abs() < 1e-9 * ar2.get_lipschitz());
+        solve_to_convergence(&mut ar2, &bi_trace, 500, 10);
+        solve_to_convergence(&mut arp, &bi_trace, 500, 10);
+        for (&a, &b) in arp.get_solution().iter().zip(&ar2.get_solution()) {
+            assert!((a - b).abs() < 1e-3, "BandedARp {a} vs BandedAR2 {b}");
+        }
+    }
+
+    // Test 25: MemoryMode::Low runs the overlap-add FFT engine and matches
+    // the whole-trace FFT solve
+    #[test]
+    fn low_memory_mode_matches_fft() {
+        use crate::{ConvMode, MemoryMode};
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 20 * kernel.len();
+        let spikes = [40, 700, 1500, n - 60];
+        let trace = build_trace(&kernel, n, &spikes.map(|s| (s, 1.0)));
+
+        let mut fft = Solver::new();
+        fft.set_params(0.02, 0.4, 0.01, 30.0);
+        fft.set_conv_mode(ConvMode::Fft);
+        let mut low = Solver::new();
+        low.set_params(0.02, 0.4, 0.01, 30.0);
+        low.set_conv_mode(ConvMode::Fft);
+        low.set_memory_mode(MemoryMode::Low);
+        assert_eq!(low.conv_mode, ConvMode::FftOverlapAdd);
+
+        solve_to_convergence(&mut fft, &trace, 500, 10);
+        solve_to_convergence(&mut low, &trace, 500, 10);
+        assert!(low.fft.block_len() > 0 && low.fft.fft_len() < fft.fft.fft_len());
+        for (i, (&a, &b)) in low
+            .get_solution()
+            .iter()
+            .zip(&fft.get_solution())
+            .enumerate()
+        {
+            assert!(
+                (a - b).abs() < 1e-3,
+                "sample {i}: overlap-add {a} vs FFT {b}"
+            );
+        }
+
+        // Fft requests stay on overlap-add until the budget is restored;
+        // banded engines are untouched.
+        low.set_conv_mode(ConvMode::Fft);
+        assert_eq!(low.conv_mode, ConvMode::FftOverlapAdd);
+        low.set_memory_mode(MemoryMode::Standard);
+        assert_eq!(low.conv_mode, ConvMode::Fft);
+        low.set_conv_mode(ConvMode::BandedAR2);
+        low.set_memory_mode(MemoryMode::Low);
+        assert_eq!(low.conv_mode, ConvMode::BandedAR2);
+        assert_eq!(low.memory_mode(), MemoryMode::Low);
+    }
+
+    // Test 26: Refractory projection keeps nonzero entries n frames apart
+    #[test]
+    fn refractory_frames_space_spikes() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(
+            &kernel,
+            400,
+            &[(50, 1.0), (52, 1.0), (150, 1.0), (300, 1.0), (303, 1.0)],
+        );
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.set_refractory_frames(5);
+        assert_eq!(solver.refractory_frames(), 5);
+        solve_to_convergence(&mut solver, &trace, 300, 10);
+
+        let sol = solver.get_solution();
+        let support: Vec<usize> = (0..sol.len()).filter(|&i| sol[i] > 0.0).collect();
+        assert!(!support.is_empty());
+        for w in support.windows(2) {
+            assert!(w[1] - w[0] >= 5, "spikes at {} and {}", w[0], w[1]);
+        }
+        // Every true cluster is still represented.
+        for s in [50, 150, 300] {
+            assert!(
+                support.iter().any(|&i| i.abs_diff(s) <= 3),
+                "lost spike {s}"
+            );
+        }
+    }
+
+    // Test 27: rolling Lipschitz re-estimation tracks the windowed operator
+    // on a drifting trace and still converges
+    #[test]
+    fn rolling_lipschitz_tracks_window() {
+        use crate::ConvMode;
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let spikes: Vec<usize> = (0..20).map(|i| 60 + 140 * i).collect();
+        let unit: Vec<(usize, f32)> = spikes.iter().map(|&s| (s, 1.0)).collect();
+        let trace: Vec<f32> = build_trace(&kernel, 3000, &unit)
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| v * (1.0 + i as f32 / 1500.0))
+            .collect();
+        let window = 100;
+        for mode in [ConvMode::Fft, ConvMode::BandedAR2] {
+            let mut local = Solver::new();
+            local.set_conv_mode(mode);
+            local.set_params(0.02, 0.4, 0.01, 30.0);
+            local.set_trace(&trace[..window]);
+            let truth = local.reestimate_lipschitz(300);
+
+            let mut solver = Solver::new();
+            solver.set_conv_mode(mode);
+            solver.set_params(0.02, 0.4, 0.01, 30.0);
+            solver.set_rolling_lipschitz(window);
+            assert_eq!(solver.rolling_lipschitz_window(), window);
+            solve_to_convergence(&mut solver, &trace, 500, 10);
+            assert!(solver.converged(), "{mode:?}: did not converge");
+            assert!(solver.iteration_count() >= window as u32);
+
+            let rolling = solver.get_lipschitz();
+            assert!(
+                (rolling - truth).abs() < 0.2 * truth,
+                "{mode:?}: rolling {rolling} vs windowed {truth}"
+            );
+            let sol = solver.get_solution();
+            for &s in &spikes {
+                let mass: f32 = sol[s.saturating_sub(2)..s + 3].iter().sum();
+                assert!(mass > 0.3, "{mode:?}: lost spike {s} ({mass})");
+            }
+        }
+    }
+
+    // Test 28: compute_objective matches a direct evaluation of the L1
+    // objective and refreshes a stale reconvolution
+    #[test]
+    fn compute_objective_matches_direct_evaluation() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 400, &[(50, 1.0), (200, 1.0), (310, 1.0)]);
+
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solve_to_convergence(&mut solver, &trace, 300, 10);
+        assert!(solver.get_objective().is_infinite());
+
+        let objective = solver.compute_objective();
+        assert_eq!(solver.get_objective(), objective);
+
+        let x = solver.get_solution();
+        let mut kx = vec![0.0_f64; x.len()];
+        for (t, out) in kx.iter_mut().enumerate() {
+            for (k, &kv) in kernel.iter().enumerate().take(t + 1) {
+                *out += kv as f64 * x[t - k] as f64;
+            }
+        }
+        let b = solver.baseline;
+        let data: f64 = kx
+            .iter()
+            .zip(&trace)
+            .map(|(&k, &y)| (k + b - y as f64).powi(2))
+            .sum();
+        let l1: f64 = x.iter().map(|&v| v as f64).sum();
+        let direct = 0.5 * data + solver.effective_lambda() * l1;
+        assert!(
+            (objective - direct).abs() < 1e-4 * direct.max(1e-3),
+            "objective {objective} vs direct {direct}"
+        );
+    }
+
+    // Test 29: a restart threshold suppresses spurious momentum resets on
+    // noisy sparse traces. The effect varies per trace, so compare the total
+    // iteration count over several noise draws.
+    #[test]
+    fn restart_threshold_reduces_iterations() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let clean = build_trace(
+            &kernel,
+            1000,
+            &[(60, 1.0), (250, 1.0), (420, 1.0), (700, 1.0), (880, 1.0)],
+        );
+
+        let total_iterations = |relative_tol: f64| -> u32 {
+            [1u64, 2, 3, 5, 7, 11, 13]
+                .iter()
+                .map(|&seed| {
+                    let mut state = seed;
+                    let trace: Vec<f32> = clean
+                        .iter()
+                        .map(|&v| v + 0.3 * crate::indeca::lcg_gaussian(&mut state) as f32)
+                        .collect();
+                    let mut solver = Solver::new();
+                    solver.set_params(0.02, 0.4, 0.001, 30.0);
+                    solver.tolerance = 1e-5;
+                    solver.set_restart_threshold(relative_tol);
+                    solve_to_convergence(&mut solver, &trace, 200, 10);
+                    assert!(solver.converged());
+                    solver.iteration_count()
+                })
+                .sum()
+        };
+        let plain = total_iterations(0.0);
+        let margin = total_iterations(0.1);
+        assert!(
+            margin as f64 <= 0.9 * plain as f64,
+            "{margin} iterations with a margin vs {plain} without"
+        );
+
+        let mut solver = Solver::new();
+        solver.set_restart_threshold(-1.0);
+        assert_eq!(solver.restart_threshold(), 0.0);
+    }
+
+    // Test 30: a window far shorter than the kernel is clamped to a fraction
+    // of the full-trace constant, so a noisy solve still converges without
+    // the objective rising, and the next trace starts from the full constant
+    #[test]
+    fn short_rolling_window_stays_stable_on_noise() {
+        use crate::ConvMode;
+
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let spikes: Vec<(usize, f32)> = (0..10).map(|i| (40 + 90 * i, 1.0)).collect();
+        let noise = crate::rng::lcg_noise(1000, 0.3, 11);
+        let trace: Vec<f32> = build_trace(&kernel, 1000, &spikes)
+            .iter()
+            .zip(&noise)
+            .map(|(&v, &e)| v + e)
+            .collect();
+        for mode in [ConvMode::Fft, ConvMode::BandedAR2] {
+            let mut solver = Solver::new();
+            solver.set_conv_mode(mode);
+            solver.set_params(0.02, 0.4, 0.01, 30.0);
+            let full = solver.get_lipschitz();
+            solver.set_rolling_lipschitz(4);
+            solver.set_trace(&trace);
+
+            let mut prev = f64::INFINITY;
+            for _ in 0..300 {
+                let done = solver.step_batch(10);
+                let objective = solver.compute_objective();
+                assert!(
+                    objective <= prev * (1.0 + 1e-6),
+                    "{mode:?}: objective rose from {prev} to {objective}"
+                );
+                prev = objective;
+                if done {
+                    break;
+                }
+            }
+            assert!(solver.converged(), "{mode:?}: did not converge");
+            assert!(solver.get_lipschitz() >= 0.8 * full * (1.0 - 1e-9));
+
+            solver.set_trace(&trace);
+            assert_eq!(solver.get_lipschitz(), full, "{mode:?}");
+        }
+    }
 }
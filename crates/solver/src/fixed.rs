@@ -0,0 +1,470 @@
+//! Heap-free FISTA solver for `no_std` targets (`no_alloc` feature).
+//!
+//! `FixedSolver<N>` needs only `core` and `alloc` (`alloc` only for the
+//! message strings in `CalabError`), so it also builds with
+//! `--no-default-features --features no_alloc`, where the crate root is
+//! `#![no_std]` and every std-backed module is compiled out. It mirrors the
+//! heap-allocating `Solver` FISTA loop in BandedAR2 mode (adaptive restart,
+//! primal-residual convergence, jointly estimated scalar baseline) but keeps
+//! every working buffer in a `[f32; N]` array, so a solve performs no
+//! allocation at all.
+//!
+//! The AR(2) engine is a self-contained copy of `banded::BandedAR2`'s forward
+//! and adjoint recursions. Two pieces differ because `core` has no float
+//! transcendentals: `exp` and `sqrt` are computed by the small helpers below,
+//! and the Lipschitz constant is the closed form `dc_gain^2` — exact here
+//! because both AR roots lie in (0, 1), so the impulse response is
+//! non-negative and `|H(e^{jw})|` peaks at DC.
+//!
+//! With `N = 2250` (75 s at 30 Hz) the six buffers take ~54 KB, which fits
+//! comfortably on a thread stack or in a `static`.
+
+use alloc::format;
+
+use crate::{CalabError, Constraint};
+
+/// `exp(x)` from `core` arithmetic: `x = k ln 2 + r` with `|r| < ln 2`, a
+/// 20-term Taylor series for `exp(r)` and the `2^k` factor built from its
+/// exponent bits. Relative error is at the f64 rounding level.
+fn exp(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+    let k = (x * core::f64::consts::LOG2_E) as i32;
+    if k < -1022 {
+        return 0.0;
+    }
+    if k > 1023 {
+        return f64::INFINITY;
+    }
+    let r = x - k as f64 * core::f64::consts::LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for i in 1..=20 {
+        term *= r / i as f64;
+        sum += term;
+    }
+    sum * f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+/// `sqrt(x)` for `x >= 0` by Newton iteration from a bit-level first guess.
+fn sqrt(x: f64) -> f64 {
+    if x <= 0.0 || !x.is_finite() {
+        return if x == 0.0 { 0.0 } else { f64::NAN };
+    }
+    let mut y = f64::from_bits((x.to_bits() >> 1) + (1023_u64 << 51));
+    for _ in 0..6 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+/// Peak-normalized banded AR(2) convolution (see `banded::BandedAR2`).
+#[derive(Clone, Copy)]
+struct Ar2 {
+    g1: f32,
+    g2: f32,
+    inv_peak: f32,
+    dc_gain: f64,
+}
+
+impl Ar2 {
+    /// Fails with `NumericalFailure` when the AR(2) roots are not inside the
+    /// unit circle, as `BandedAR2::new` does.
+    fn new(tau_rise: f64, tau_decay: f64, fs: f64) -> Result<Self, CalabError> {
+        // `kernel::clamp_tau_rise`: keep the biexponential from collapsing.
+        let tau_rise = if (tau_rise - tau_decay).abs() < 1e-6 * tau_decay.max(tau_rise).max(1e-12) {
+            tau_decay * 0.5
+        } else {
+            tau_rise
+        };
+        let dt = 1.0 / fs;
+        let d = exp(-dt / tau_decay);
+        let r = exp(-dt / tau_rise);
+        if !(d.abs() < 1.0 && r.abs() < 1.0) {
+            return Err(CalabError::NumericalFailure(format!(
+                "unstable AR(2) model (decay root {d}, rise root {r}) for \
+                 tau_rise = {tau_rise}, tau_decay = {tau_decay}, fs = {fs}"
+            )));
+        }
+        let g1 = d + r;
+        let g2 = -(d * r);
+
+        // Raw impulse peak, as `banded::compute_impulse_peak`.
+        let max_steps = (5.0 * tau_decay * fs) as usize + 11;
+        let (mut c_prev2, mut c_prev1, mut peak) = (0.0_f64, 1.0_f64, 1.0_f64);
+        for _ in 1..max_steps {
+            let c = g1 * c_prev1 + g2 * c_prev2;
+            if c > peak {
+                peak = c;
+            }
+            if c < peak * 0.95 {
+                break;
+            }
+            c_prev2 = c_prev1;
+            c_prev1 = c;
+        }
+
+        Ok(Ar2 {
+            g1: g1 as f32,
+            g2: g2 as f32,
+            inv_peak: (1.0 / peak) as f32,
+            dc_gain: 1.0 / ((1.0 - g1 - g2) * peak),
+        })
+    }
+
+    fn lipschitz(&self) -> f64 {
+        self.dc_gain * self.dc_gain
+    }
+
+    /// `BandedAR2::convolve_forward`: one-sample source delay, output[0] = 0.
+    fn convolve_forward(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+        output[0] = 0.0;
+        if n > 1 {
+            output[1] = source[0] * self.inv_peak;
+        }
+        for t in 2..n {
+            output[t] =
+                self.g1 * output[t - 1] + self.g2 * output[t - 2] + source[t - 1] * self.inv_peak;
+        }
+    }
+
+    /// `BandedAR2::convolve_adjoint`: backward recursion shifted for the delay.
+    fn convolve_adjoint(&self, source: &[f32], output: &mut [f32]) {
+        let n = source.len();
+        if n == 0 {
+            return;
+        }
+        output[n - 1] = 0.0;
+        let mut a_tp1 = 0.0_f32;
+        let mut a_tp2 = 0.0_f32;
+        for t in (0..n).rev() {
+            let a_t = source[t] * self.inv_peak + self.g1 * a_tp1 + self.g2 * a_tp2;
+            if t >= 1 {
+                output[t - 1] = a_t;
+            }
+            a_tp2 = a_tp1;
+            a_tp1 = a_t;
+        }
+    }
+}
+
+/// Fixed-capacity FISTA solver for traces of at most `N` samples.
+pub struct FixedSolver<const N: usize> {
+    lambda: f64,
+    engine: Ar2,
+    constraint: Constraint,
+
+    trace: [f32; N],
+    solution: [f32; N],
+    solution_prev: [f32; N],
+    gradient: [f32; N],
+    reconvolution: [f32; N],
+    residual_buf: [f32; N],
+
+    active_len: usize,
+    iteration: u32,
+    t_fista: f64,
+    converged: bool,
+    tolerance: f64,
+    baseline: f64,
+}
+
+impl<const N: usize> FixedSolver<N> {
+    /// Create a solver for the given kernel time constants, sparsity and rate.
+    /// Fails with `NumericalFailure` when the taus give an unstable AR(2)
+    /// model.
+    pub fn new(tau_rise: f64, tau_decay: f64, lambda: f64, fs: f64) -> Result<Self, CalabError> {
+        Ok(FixedSolver {
+            lambda,
+            engine: Ar2::new(tau_rise, tau_decay, fs)?,
+            constraint: Constraint::NonNegative,
+            trace: [0.0; N],
+            solution: [0.0; N],
+            solution_prev: [0.0; N],
+            gradient: [0.0; N],
+            reconvolution: [0.0; N],
+            residual_buf: [0.0; N],
+            active_len: 0,
+            iteration: 0,
+            t_fista: 1.0,
+            converged: false,
+            tolerance: 1e-4,
+            baseline: 0.0,
+        })
+    }
+
+    /// Maximum trace length this solver can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Set the constraint type (NonNegative or Box01).
+    pub fn set_constraint(&mut self, c: Constraint) {
+        self.constraint = c;
+    }
+
+    /// Load a trace and reset iteration state for a fresh solve.
+    ///
+    /// Fails with `DimensionMismatch` (and leaves the solver untouched) when
+    /// the trace is longer than the compile-time capacity `N`.
+    pub fn set_trace(&mut self, trace: &[f32]) -> Result<(), CalabError> {
+        let n = trace.len();
+        if n > N {
+            return Err(CalabError::DimensionMismatch {
+                expected: N,
+                got: n,
+            });
+        }
+        self.active_len = n;
+        self.trace[..n].copy_from_slice(trace);
+        self.solution[..n].fill(0.0);
+        self.solution_prev[..n].fill(0.0);
+        self.gradient[..n].fill(0.0);
+        self.reconvolution[..n].fill(0.0);
+        self.residual_buf[..n].fill(0.0);
+        self.iteration = 0;
+        self.t_fista = 1.0;
+        self.converged = false;
+        self.baseline = 0.0;
+        Ok(())
+    }
+
+    /// The current solution for the active region (borrowed, no copy).
+    pub fn solution(&self) -> &[f32] {
+        &self.solution[..self.active_len]
+    }
+
+    /// Latest raw baseline estimate `mean(trace - K*y_k)`.
+    pub fn baseline(&self) -> f64 {
+        self.baseline
+    }
+
+    /// Returns whether the solver has converged.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Returns the current iteration count.
+    pub fn iteration_count(&self) -> u32 {
+        self.iteration
+    }
+
+    /// Run n_steps of FISTA iterations. Returns true if converged.
+    ///
+    /// Same iteration as the heap-allocating `Solver::step_batch` in BandedAR2
+    /// mode; see there for the restart and convergence rationale.
+    pub fn step_batch(&mut self, n_steps: u32) -> bool {
+        let n = self.active_len;
+        if n == 0 {
+            self.converged = true;
+            return true;
+        }
+
+        let step_size = 1.0 / self.engine.lipschitz();
+        let thresh_f32 = (step_size * self.lambda * self.engine.dc_gain) as f32;
+        let step_f32 = step_size as f32;
+        let tol_sq = self.tolerance * self.tolerance;
+
+        for _ in 0..n_steps {
+            if self.converged {
+                return true;
+            }
+
+            self.engine
+                .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]);
+            let mut sum = 0.0_f64;
+            for i in 0..n {
+                sum += (self.trace[i] - self.reconvolution[i]) as f64;
+            }
+            self.baseline = sum / n as f64;
+
+            let baseline_f32 = self.baseline as f32;
+            for i in 0..n {
+                self.residual_buf[i] = self.reconvolution[i] + baseline_f32 - self.trace[i];
+            }
+            self.engine
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]);
+
+            let (lo, hi) = match self.constraint {
+                Constraint::NonNegative => (0.0_f32, f32::INFINITY),
+                Constraint::Box01 => (0.0_f32, 1.0_f32),
+            };
+            for i in 0..n {
+                self.residual_buf[i] = self.solution[i];
+                let z = self.solution_prev[i] - step_f32 * self.gradient[i];
+                self.solution[i] = (z - thresh_f32).clamp(lo, hi);
+            }
+
+            self.iteration += 1;
+
+            let t_new = (1.0 + sqrt(1.0 + 4.0 * self.t_fista * self.t_fista)) / 2.0;
+            let momentum = ((self.t_fista - 1.0) / t_new) as f32;
+
+            let mut diff_sq = 0.0_f64;
+            let mut xk_sq = 0.0_f64;
+            let mut dot = 0.0_f64;
+            for i in 0..n {
+                let x_new = self.solution[i];
+                let x_old = self.residual_buf[i];
+                let d = x_new as f64 - x_old as f64;
+                diff_sq += d * d;
+                xk_sq += x_old as f64 * x_old as f64;
+                dot += (self.solution_prev[i] as f64 - x_new as f64) * d;
+                self.solution_prev[i] = (x_new + momentum * (x_new - x_old)).clamp(lo, hi);
+            }
+
+            if self.iteration > 1 && dot > 0.0 {
+                self.t_fista = 1.0;
+                self.solution_prev[..n].copy_from_slice(&self.solution[..n]);
+            } else {
+                self.t_fista = t_new;
+            }
+
+            if self.iteration > 5 && diff_sq < tol_sq * (xk_sq + 1e-20) {
+                self.converged = true;
+            }
+        }
+
+        self.converged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exp, sqrt, FixedSolver};
+    use crate::CalabError;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn solve_to_convergence<const N: usize>(solver: &mut FixedSolver<N>, trace: &[f32]) {
+        solver.set_trace(trace).unwrap();
+        for _ in 0..200 {
+            if solver.step_batch(10) {
+                break;
+            }
+        }
+    }
+
+    /// Peak-normalized impulse response of the solver's own engine — the
+    /// `build_kernel` shape (h[0] = 0, rising after the spike).
+    fn kernel<const N: usize>(solver: &FixedSolver<N>, len: usize) -> Vec<f32> {
+        let mut delta = vec![0.0_f32; len];
+        delta[0] = 1.0;
+        let mut out = vec![0.0_f32; len];
+        solver.engine.convolve_forward(&delta, &mut out);
+        out
+    }
+
+    #[test]
+    fn core_exp_and_sqrt_match_reference_values() {
+        // -1/12 and -1/0.6 are the decay and rise exponents for
+        // tau = (0.02, 0.4) at 30 Hz.
+        for (x, want) in [
+            (0.0, 1.0),
+            (-1.0, 0.36787944117144233),
+            (-0.08333333333333333, 0.9200444146293233),
+            (-1.6666666666666667, 0.18887560283756183),
+            (2.5, 12.182493960703473),
+        ] {
+            assert!(
+                (exp(x) - want).abs() < 1e-14 * want,
+                "exp({x}) = {}",
+                exp(x)
+            );
+        }
+        assert_eq!(exp(-1e6), 0.0);
+        for x in [1.0, 2.0, 5.0, 1e-8, 1e8] {
+            let y = sqrt(x);
+            assert!((y * y - x).abs() < 1e-14 * x, "sqrt({x}) = {y}");
+        }
+        assert_eq!(sqrt(0.0), 0.0);
+    }
+
+    #[test]
+    fn delta_impulse_recovery() {
+        let mut solver = FixedSolver::<2250>::new(0.02, 0.4, 0.001, 30.0).unwrap();
+
+        // The trace IS the kernel (what you'd get from a single spike at t=0)
+        let trace = kernel(&solver, 200);
+        let peak = trace.iter().cloned().fold(0.0_f32, f32::max);
+        assert!((peak - 1.0).abs() < 1e-5, "kernel peak = {}", peak);
+        solve_to_convergence(&mut solver, &trace);
+
+        let solution = solver.solution();
+        assert_eq!(solution.len(), trace.len());
+
+        let max_idx = solution
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap()
+            .0;
+        assert!(
+            max_idx <= 2,
+            "Max spike should be near t=0, got index {}",
+            max_idx
+        );
+
+        let spike_val = solution[max_idx];
+        assert!(
+            spike_val > 0.1,
+            "Primary spike should be > 0.1, got {}",
+            spike_val
+        );
+
+        let sum_others: f32 = solution
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != max_idx)
+            .map(|(_, v)| v)
+            .sum();
+        assert!(
+            sum_others < spike_val,
+            "Sum of non-spike values ({}) should be less than spike ({})",
+            sum_others,
+            spike_val
+        );
+    }
+
+    #[test]
+    fn zero_trace_produces_zero_solution() {
+        let mut solver = FixedSolver::<2250>::new(0.02, 0.4, 0.01, 30.0).unwrap();
+
+        let trace = vec![0.0_f32; 100];
+        solve_to_convergence(&mut solver, &trace);
+
+        let max_val = solver.solution().iter().cloned().fold(0.0_f32, f32::max);
+        assert!(
+            max_val < 1e-6,
+            "Zero trace should produce zero solution, max = {}",
+            max_val
+        );
+    }
+
+    #[test]
+    fn rejects_trace_longer_than_capacity() {
+        let mut solver = FixedSolver::<16>::new(0.02, 0.4, 0.01, 30.0).unwrap();
+        assert_eq!(solver.capacity(), 16);
+        assert_eq!(
+            solver.set_trace(&[0.0; 17]),
+            Err(CalabError::DimensionMismatch {
+                expected: 16,
+                got: 17
+            })
+        );
+        assert!(solver.set_trace(&[0.0; 16]).is_ok());
+    }
+
+    #[test]
+    fn unstable_taus_are_rejected() {
+        assert!(matches!(
+            FixedSolver::<16>::new(0.02, -0.4, 0.01, 30.0),
+            Err(CalabError::NumericalFailure(_))
+        ));
+    }
+}
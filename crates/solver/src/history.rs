@@ -0,0 +1,166 @@
+//! Optional per-iteration record of FISTA's internal state, for diagnosing
+//! slow convergence.
+//!
+//! Records live in a bounded window: the buffer holds up to twice the
+//! requested capacity and drops its older half when full, so pushes are
+//! amortised O(1) and the newest `capacity` records are always one
+//! contiguous, oldest-first slice.
+
+use crate::Solver;
+
+#[cfg(feature = "jsbindings")]
+use wasm_bindgen::prelude::*;
+
+/// State of one FISTA iteration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "jsbindings", derive(serde::Serialize))]
+pub struct IterRecord {
+    /// Iteration count after this step (1-based).
+    pub iteration: u32,
+    /// `||x_{k+1} - x_k||^2 / ||x_k||^2`, the quantity the primal-residual
+    /// stopping test compares against `tolerance^2`.
+    pub diff_sq_rel: f64,
+    /// Momentum parameter after the step (1.0 right after a restart).
+    pub t_fista: f64,
+    /// Whether adaptive restart reset the momentum.
+    pub restarted: bool,
+    /// Objective at `x_{k+1}` (see `Solver::compute_objective`). Always set
+    /// by FISTA; optional so other iterations can record without it.
+    pub objective: Option<f64>,
+}
+
+/// Bounded record buffer behind `Solver::enable_history`.
+#[derive(Default)]
+pub(crate) struct History {
+    capacity: usize,
+    records: Vec<IterRecord>,
+}
+
+impl History {
+    pub(crate) fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub(crate) fn push(&mut self, record: IterRecord) {
+        if self.records.len() == 2 * self.capacity {
+            self.records.drain(..self.capacity);
+        }
+        self.records.push(record);
+    }
+
+    fn records(&self) -> &[IterRecord] {
+        let start = self.records.len().saturating_sub(self.capacity);
+        &self.records[start..]
+    }
+}
+
+#[cfg_attr(feature = "jsbindings", wasm_bindgen)]
+impl Solver {
+    /// Record the state of each FISTA iteration, keeping the most recent
+    /// `capacity` records (`0` turns recording off). Clears any existing
+    /// history. Only the plain FISTA iteration records; the ADMM and
+    /// stochastic modes do not.
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.history = History {
+            capacity,
+            records: Vec::with_capacity(2 * capacity),
+        };
+    }
+
+    /// Drop the recorded iterations, keeping recording enabled. The history
+    /// is otherwise kept across `set_trace` and `reset`; each record's
+    /// `iteration` restarts from 1 with the new solve.
+    pub fn clear_history(&mut self) {
+        self.history.records.clear();
+    }
+
+    /// The recorded iterations as an array of objects (see `IterRecord`).
+    #[cfg(feature = "jsbindings")]
+    pub fn get_history_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.get_history()).unwrap_or(JsValue::NULL)
+    }
+}
+
+impl Solver {
+    /// The recorded iterations, oldest first (at most the capacity given to
+    /// `enable_history`; empty when recording is off).
+    pub fn get_history(&self) -> &[IterRecord] {
+        self.history.records()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::build_kernel;
+    use crate::StoppingCriterion;
+
+    fn spike_trace(n: usize) -> Vec<f32> {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let mut trace = vec![0.0_f32; n];
+        for s in [40, 150, 320] {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if s + k < n {
+                    trace[s + k] += kv;
+                }
+            }
+        }
+        trace
+    }
+
+    #[test]
+    fn history_keeps_most_recent_records() {
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.set_trace(&spike_trace(500));
+        assert!(solver.get_history().is_empty());
+
+        solver.enable_history(8);
+        solver.step_batch(5);
+        let iterations: Vec<u32> = solver.get_history().iter().map(|r| r.iteration).collect();
+        assert_eq!(iterations, vec![1, 2, 3, 4, 5]);
+
+        solver.step_batch(20);
+        let history = solver.get_history();
+        assert_eq!(history.len(), 8);
+        let last = solver.iteration_count();
+        assert_eq!(history[0].iteration, last - 7);
+        assert_eq!(history[7].iteration, last);
+        assert!(history.iter().all(|r| r.objective.is_some()));
+        assert_eq!(history[7].objective, Some(solver.get_objective()));
+        assert!(history
+            .iter()
+            .all(|r| r.diff_sq_rel >= 0.0 && r.t_fista >= 1.0));
+        assert!(history.iter().all(|r| !r.restarted || r.t_fista == 1.0));
+
+        solver.clear_history();
+        assert!(solver.get_history().is_empty());
+        solver.step_batch(1);
+        assert_eq!(solver.get_history().len(), 1);
+
+        solver.enable_history(0);
+        solver.step_batch(3);
+        assert!(solver.get_history().is_empty());
+    }
+
+    #[test]
+    fn history_objective_decreases_with_dual_gap_checks() {
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.set_stopping_criterion(StoppingCriterion::DualGap {
+            max_iters_between_checks: 4,
+        });
+        solver.enable_history(100);
+        solver.set_trace(&spike_trace(500));
+        solver.step_batch(40);
+
+        let objectives: Vec<f64> = solver
+            .get_history()
+            .iter()
+            .map(|r| r.objective.expect("FISTA iteration without objective"))
+            .collect();
+        assert_eq!(objectives.len(), 40);
+        assert!(objectives.windows(2).all(|w| w[1] <= w[0] * 1.01));
+        assert!(objectives[39] < 0.5 * objectives[0]);
+    }
+}
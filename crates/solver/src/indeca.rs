@@ -12,13 +12,17 @@
 ///
 /// The AR2 forward model is peak-normalized so that a single spike produces
 /// a peak of 1.0 regardless of sampling rate, making alpha rate-independent.
+use crate::analysis::estimate_trace_snr;
 use crate::banded::BandedAR2;
-use crate::threshold::{threshold_search_opts, Selection, ThresholdResult};
+use crate::threshold::{threshold_search_opts, Selection, ThresholdCriterion, ThresholdResult};
 use crate::upsample::{
-    downsample_average, downsample_binary, upsample_counts_to_binary, upsample_trace,
+    downsample_average, downsample_binary, downsample_lowpass, upsample_counts_to_binary,
+    upsample_trace, UpsampleMethod, LOWPASS_ORDER_PER_FACTOR,
 };
-use crate::{Constraint, ConvMode, Solver};
+use crate::{CalabError, Constraint, ConvMode, Solver};
 use realfft::RealFftPlanner;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Optional spike-inference behaviors. The library default is off (`MaxPve`,
 /// preserving the historical output); the CaDecon app enables `noise_constrained`
@@ -27,9 +31,218 @@ use realfft::RealFftPlanner;
 /// `noise_constrained` chooses the binarization threshold at the data-derived
 /// noise floor instead of maximizing fit, suppressing low-SNR spurious spikes
 /// without changing the default (max-PVE) output.
-#[derive(Clone, Copy, Default)]
+///
+/// `saturation_value` marks frames with `trace[t] >= saturation_value` as
+/// detector-saturated: they are excluded from the rolling-baseline window and
+/// get zero weight in the alpha/baseline least-squares fit, since a clamped
+/// peak misrepresents the transient amplitude.
+///
+/// `photobleaching_correction` fits an exponential bleaching trend to the
+/// trace's lower envelope ([`crate::baseline::fit_photobleaching`]) and
+/// subtracts it before filtering and rolling-baseline subtraction.
+///
+/// `boundary_mode` selects how the trace edges are handled during the FISTA
+/// solve (see [`BoundaryMode`]).
+///
+/// `upsample_method` selects the interpolation onto the upsampled grid when
+/// `upsample_factor > 1` (see [`UpsampleMethod`]).
+///
+/// `threshold_criterion` is the objective of the threshold grid search when
+/// `noise_constrained` is off (see [`ThresholdCriterion`]).
+///
+/// `scale_stop` decides when the scale iteration loop stops early (see
+/// [`ScaleStopCriterion`]); the default is the historical alpha convergence.
+///
+/// `alpha_init` picks the first prescale of the scale loop (see
+/// [`AlphaInit`]); the default is the historical peak-to-trough.
+///
+/// `variance_stabilize` applies the Anscombe transform
+/// ([`crate::baseline::anscombe_transform`]) to the raw trace before
+/// anything else, for shot-noise-limited recordings whose noise variance
+/// grows with the signal. The fit is then made in the stabilized domain:
+/// `alpha`, `baseline`, `filtered_trace`, `noise_std` and `snr_db` are in
+/// Anscombe units, and a reconvolved fit maps back to photon counts through
+/// [`crate::baseline::anscombe_inverse_transform`]. `saturation_value` still
+/// applies to the raw trace.
+#[derive(Clone, Default, Debug)]
 pub struct SolveOptions {
     pub noise_constrained: bool,
+    pub saturation_value: Option<f32>,
+    pub photobleaching_correction: bool,
+    pub boundary_mode: BoundaryMode,
+    pub upsample_method: UpsampleMethod,
+    pub threshold_criterion: ThresholdCriterion,
+    pub scale_stop: ScaleStopCriterion,
+    pub alpha_init: AlphaInit,
+    pub variance_stabilize: bool,
+}
+
+/// Initial alpha (prescale) estimate for the scale loop, computed on the
+/// interior of the filtered, baseline-subtracted trace.
+///
+/// `PeakTrough` is the interior range — a safe overestimate, since the kernel
+/// is peak-normalized, but one that grows with noise extremes and overlapping
+/// transients. `Percentile(p)` is the `p`-th percentile (0–100) above the
+/// interior minimum, so `Percentile(100.0)` equals `PeakTrough`. `Histogram`
+/// fits a two-component Gaussian mixture to the histogram of interior values
+/// and uses the distance between the two means (the noise floor and the
+/// active-transient mode). `UserProvided(v)` uses `v` as given. Every variant
+/// falls back to `PeakTrough` when it yields a non-positive or non-finite
+/// value, and that to 1.0 for a flat trace.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum AlphaInit {
+    #[default]
+    PeakTrough,
+    Percentile(f64),
+    Histogram,
+    UserProvided(f64),
+}
+
+/// Upper bound on scale iterations in [`solve_trace`].
+pub const MAX_SCALE_ITERS: usize = 10;
+
+/// Early-stopping rule for the scale iteration loop, checked after every
+/// iteration (the loop also stops at [`MAX_SCALE_ITERS`] and when the fitted
+/// alpha collapses to zero).
+///
+/// `AlphaConvergence(rtol)` stops once the least-squares alpha is within
+/// `rtol` (relative) of the prescale it was fit under — the prescale matched
+/// the true amplitude. `PveImprovement` stops once PVE has increased by less
+/// than `min_delta` over each of the last `patience` iterations (a `patience`
+/// of 0 counts as 1). `AnyOf` stops as soon as any of its criteria does; an
+/// empty list never stops early.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScaleStopCriterion {
+    AlphaConvergence(f64),
+    PveImprovement { min_delta: f64, patience: usize },
+    AnyOf(Vec<ScaleStopCriterion>),
+}
+
+impl Default for ScaleStopCriterion {
+    fn default() -> Self {
+        ScaleStopCriterion::AlphaConvergence(0.05)
+    }
+}
+
+impl ScaleStopCriterion {
+    /// Whether to stop after the iteration with relative scale error
+    /// `scale_err`; `pve_history` holds the PVE of every iteration so far,
+    /// the current one last.
+    fn should_stop(&self, scale_err: f64, pve_history: &[f64]) -> bool {
+        match self {
+            ScaleStopCriterion::AlphaConvergence(rtol) => scale_err < *rtol,
+            ScaleStopCriterion::PveImprovement {
+                min_delta,
+                patience,
+            } => {
+                let patience = (*patience).max(1);
+                pve_history.len() > patience
+                    && pve_history
+                        .windows(2)
+                        .rev()
+                        .take(patience)
+                        .all(|w| w[1] - w[0] < *min_delta)
+            }
+            ScaleStopCriterion::AnyOf(criteria) => criteria
+                .iter()
+                .any(|c| c.should_stop(scale_err, pve_history)),
+        }
+    }
+}
+
+/// Edge handling for the FISTA solve inside the scale loop.
+///
+/// `Zero` solves the trace as-is and normalizes the relaxed solution by its
+/// interior peak, so the start-of-trace transient artifact (a trace subset
+/// starting mid-transient) cannot dominate. `Mirror` and `Periodic` instead
+/// extend the trace by the boundary padding on each side — reflected about
+/// the end samples, or wrapped around — and trim the solution back before the
+/// threshold search, so the artifact lands in the discarded extension and
+/// spikes near the edges are kept.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    #[default]
+    Zero,
+    Mirror,
+    Periodic,
+}
+
+/// Pipeline stage reported by a [`ProgressEvent`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stage {
+    /// Upsampling, filtering and baseline subtraction are done.
+    Filter,
+    /// One scale iteration (prescale → FISTA → threshold search) finished.
+    ScaleIteration,
+    /// The scale loop has stopped; the final result is being assembled.
+    Converged,
+}
+
+/// Progress report passed to a [`ProgressFn`]. `pve` and `alpha` are those of
+/// the iteration just finished (the selected iterate for `Converged`, zero
+/// for `Filter`); `scale_iter` counts from 0.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressEvent {
+    pub stage: Stage,
+    pub scale_iter: usize,
+    pub fista_iter: u32,
+    pub pve: f64,
+    pub alpha: f64,
+}
+
+/// Progress callback for [`solve_trace`].
+pub type ProgressFn = Box<dyn Fn(ProgressEvent) + Send>;
+
+/// Shared cancellation flag for [`solve_trace`]. Clones share the flag, so a
+/// clone kept by another thread (or a UI handler) can stop a running solve.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation. Takes effect at the next FISTA iteration.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Invoke the progress callback, if any. A panicking callback is reported and
+/// otherwise ignored so it cannot abort the solve.
+fn report(on_progress: &Option<ProgressFn>, event: ProgressEvent) {
+    if let Some(f) = on_progress {
+        let call = std::panic::AssertUnwindSafe(|| f(event));
+        if std::panic::catch_unwind(call).is_err() {
+            eprintln!(
+                "solve_trace: progress callback panicked at {:?}; ignored",
+                event.stage
+            );
+        }
+    }
+}
+
+/// Per-sample saturation mask at the upsampled rate. An upsampled sample is
+/// saturated when either original frame it interpolates between is, so the
+/// mask covers the full linear ramp into and out of a clipped run.
+fn saturation_mask(trace: &[f32], saturation_value: f32, upsample_factor: usize) -> Vec<bool> {
+    let factor = upsample_factor.max(1);
+    let n = trace.len();
+    let clipped: Vec<bool> = trace.iter().map(|&v| v >= saturation_value).collect();
+    let mut mask = vec![false; n * factor];
+    for i in 0..n {
+        let next = i + 1 < n && clipped[i + 1];
+        mask[i * factor] = clipped[i];
+        for j in 1..factor {
+            mask[i * factor + j] = clipped[i] || next;
+        }
+    }
+    mask
 }
 
 /// Raw measurement-noise std from the high-frequency band of the periodogram
@@ -70,6 +283,80 @@ pub fn high_band_sigma(raw_trace: &[f32]) -> f64 {
     (acc / cnt as f64).max(0.0).sqrt()
 }
 
+/// Lambda returned by [`select_lambda`] when no noise can be measured
+/// (constant or empty trace). Matches the `Solver::new` default.
+const DEFAULT_AUTO_LAMBDA: f64 = 0.01;
+
+/// Below this ratio of difference-based to residual-based sigma, the trace is
+/// treated as pre-filtered: smoothing correlates neighbouring noise samples,
+/// which collapses the first-difference spread while the broadband residual
+/// spread still reflects the noise amplitude the solver will see.
+const PREFILTER_SIGMA_RATIO: f64 = 0.5;
+
+/// Robust std estimate: 1.4826 × median absolute deviation from the median.
+fn mad_sigma(x: &[f32]) -> f64 {
+    if x.is_empty() {
+        return 0.0;
+    }
+    let mut v: Vec<f32> = x.to_vec();
+    let mid = v.len() / 2;
+    let median = *v.select_nth_unstable_by(mid, |a, b| a.total_cmp(b)).1;
+    for d in v.iter_mut() {
+        *d = (*d - median).abs();
+    }
+    let mad = *v.select_nth_unstable_by(mid, |a, b| a.total_cmp(b)).1;
+    1.4826 * mad as f64
+}
+
+/// Pick an L1 penalty from the trace's noise floor via the universal
+/// soft-threshold rule `lambda = sigma * sqrt(2 ln n)` (Donoho & Johnstone).
+///
+/// `sigma` is the MAD-based std of the high-frequency residual — the trace
+/// minus its rolling-percentile baseline, so slow drift does not count as noise
+/// and the MAD keeps sparse transients from inflating the estimate.
+///
+/// Edge cases:
+/// - traces shorter than 3 kernel lengths cannot fill the baseline window, so
+///   sigma comes from the MAD of first differences (÷√2) instead;
+/// - pre-filtered traces (difference sigma far below residual sigma) always use
+///   the residual sigma, so smoothing does not drive lambda toward zero;
+/// - constant traces (sigma ≈ 0) return [`DEFAULT_AUTO_LAMBDA`].
+pub fn select_lambda(trace: &[f32], tau_rise: f64, tau_decay: f64, fs: f64) -> f64 {
+    let n = trace.len();
+    if n < 2 {
+        return DEFAULT_AUTO_LAMBDA;
+    }
+
+    let kernel_len = crate::kernel::build_kernel(tau_rise, tau_decay, fs).len();
+    let short = n < 3 * kernel_len;
+
+    let mut residual = trace.to_vec();
+    if !short {
+        crate::baseline::subtract_rolling_baseline(
+            &mut residual,
+            crate::baseline::baseline_window(tau_decay, fs),
+            crate::baseline::DEFAULT_BASELINE_QUANTILE,
+        );
+    }
+    let sigma_resid = mad_sigma(&residual);
+
+    let diffs: Vec<f32> = trace.windows(2).map(|w| w[1] - w[0]).collect();
+    let sigma_diff = mad_sigma(&diffs) / std::f64::consts::SQRT_2;
+
+    let prefiltered = sigma_diff < PREFILTER_SIGMA_RATIO * sigma_resid;
+    let sigma = if short && !prefiltered {
+        sigma_diff
+    } else {
+        sigma_resid
+    };
+
+    let scale = trace.iter().fold(0.0_f32, |m, &v| m.max(v.abs())) as f64;
+    if sigma <= 1e-9 * scale.max(1e-30) {
+        return DEFAULT_AUTO_LAMBDA;
+    }
+    sigma * (2.0 * (n as f64).ln()).sqrt()
+}
+
 fn variance(x: &[f32]) -> f64 {
     let n = x.len();
     if n == 0 {
@@ -150,6 +437,109 @@ pub struct InDecaResult {
     pub pve: f64,
     pub iterations: u32,
     pub converged: bool,
+    /// Original-rate frames at or above `SolveOptions::saturation_value`.
+    pub n_saturated_frames: usize,
+    /// Std of the fit residual `filtered_trace - alpha·K*s_counts - baseline`
+    /// over inactive frames (`s_counts < 0.1·threshold`); the full-trace
+    /// residual std when every frame is active.
+    pub noise_std: f64,
+    /// Fit SNR in dB over `filtered_trace` (see
+    /// `analysis::estimate_trace_snr`).
+    pub snr_db: f64,
+}
+
+/// Wire format for `InDecaResult::to_json`: the arrays are base64-encoded
+/// little-endian f32 bytes, everything else is plain JSON.
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct InDecaResultJson {
+    s_counts: String,
+    filtered_trace: Option<String>,
+    alpha: f64,
+    baseline: f64,
+    threshold: f64,
+    pve: f64,
+    iterations: u32,
+    converged: bool,
+    n_saturated_frames: usize,
+    noise_std: f64,
+    /// `None` for a non-finite SNR, which JSON numbers cannot hold.
+    #[serde(default)]
+    snr_db: Option<f64>,
+}
+
+#[cfg(feature = "json")]
+impl InDecaResult {
+    /// Serialize to a self-contained JSON object. `s_counts` and
+    /// `filtered_trace` are stored as base64 of their raw little-endian f32
+    /// bytes, so the round trip through `from_json` is bit-exact; the scalar
+    /// fields stay readable. A non-finite `snr_db` is written as `null` and
+    /// reads back as NaN.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&InDecaResultJson {
+            s_counts: encode_f32_base64(&self.s_counts),
+            filtered_trace: self.filtered_trace.as_deref().map(encode_f32_base64),
+            alpha: self.alpha,
+            baseline: self.baseline,
+            threshold: self.threshold,
+            pve: self.pve,
+            iterations: self.iterations,
+            converged: self.converged,
+            n_saturated_frames: self.n_saturated_frames,
+            noise_std: self.noise_std,
+            snr_db: self.snr_db.is_finite().then_some(self.snr_db),
+        })
+    }
+
+    /// Parse the output of `to_json`. Invalid base64, or an array whose byte
+    /// length is not a multiple of 4, is reported as a `serde_json` data
+    /// error.
+    pub fn from_json(s: &str) -> Result<InDecaResult, serde_json::Error> {
+        let wire: InDecaResultJson = serde_json::from_str(s)?;
+        Ok(InDecaResult {
+            s_counts: decode_f32_base64(&wire.s_counts)?,
+            filtered_trace: wire
+                .filtered_trace
+                .as_deref()
+                .map(decode_f32_base64)
+                .transpose()?,
+            alpha: wire.alpha,
+            baseline: wire.baseline,
+            threshold: wire.threshold,
+            pve: wire.pve,
+            iterations: wire.iterations,
+            converged: wire.converged,
+            n_saturated_frames: wire.n_saturated_frames,
+            noise_std: wire.noise_std,
+            snr_db: wire.snr_db.unwrap_or(f64::NAN),
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+fn encode_f32_base64(values: &[f32]) -> String {
+    use base64::Engine;
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[cfg(feature = "json")]
+fn decode_f32_base64(encoded: &str) -> Result<Vec<f32>, serde_json::Error> {
+    use base64::Engine;
+    use serde::de::Error;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(serde_json::Error::custom)?;
+    if bytes.len() % 4 != 0 {
+        return Err(serde_json::Error::custom(format!(
+            "f32 array has {} bytes, not a multiple of 4",
+            bytes.len()
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
 }
 
 /// Run bounded FISTA on a (possibly upsampled) trace.
@@ -185,6 +575,7 @@ pub fn solve_bounded(
         Constraint::Box01,
         false,
         0.0,
+        &|| false,
     )
 }
 
@@ -197,6 +588,9 @@ pub fn solve_bounded(
 /// `baseline_subtracted`: when true, the trace has already had its baseline
 /// removed externally (via rolling-percentile subtraction), so FISTA should
 /// skip its internal baseline estimation (sets `solver.filtered = true`).
+///
+/// `should_stop` is polled between FISTA iterations; once it returns true the
+/// current iterate is returned unconverged.
 fn solve_upsampled(
     solver: &mut Solver,
     upsampled: &[f32],
@@ -211,6 +605,7 @@ fn solve_upsampled(
     constraint: Constraint,
     baseline_subtracted: bool,
     lambda: f64,
+    should_stop: &dyn Fn() -> bool,
 ) -> (Vec<f32>, Option<Vec<f32>>, u32, bool) {
     solver.set_conv_mode(ConvMode::BandedAR2);
     solver.set_params(tau_r, tau_d, lambda, fs_up);
@@ -244,7 +639,7 @@ fn solve_upsampled(
     let batch_size = 50;
     let max_batches = max_iters.div_ceil(batch_size);
     for _ in 0..max_batches {
-        if solver.step_batch(batch_size) {
+        if solver.step_batch_until(batch_size, should_stop) || should_stop() {
             break;
         }
         if solver.iteration_count() >= max_iters {
@@ -290,6 +685,131 @@ fn estimate_alpha_interior(trace: &[f32], pad: usize) -> f64 {
     }
 }
 
+/// Initial alpha by `init` (see [`AlphaInit`]), falling back to
+/// [`estimate_alpha_interior`].
+fn initial_alpha(trace: &[f32], pad: usize, init: AlphaInit) -> f64 {
+    let inner = interior_slice(trace, pad);
+    let alpha = match init {
+        AlphaInit::PeakTrough => f64::NAN,
+        AlphaInit::Percentile(p) => percentile_above_min(inner, p),
+        AlphaInit::Histogram => histogram_mode_gap(inner),
+        AlphaInit::UserProvided(v) => v,
+    };
+    if alpha.is_finite() && alpha > 1e-10 {
+        alpha
+    } else {
+        estimate_alpha_interior(trace, pad)
+    }
+}
+
+/// `p`-th percentile (clamped to 0–100, nearest rank) of `values` minus
+/// their minimum. NaN for an empty slice or a NaN `p`.
+fn percentile_above_min(values: &[f32], p: f64) -> f64 {
+    if values.is_empty() || p.is_nan() {
+        return f64::NAN;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let rank = (p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    (sorted[rank] - sorted[0]) as f64
+}
+
+/// Bins and EM iterations for [`histogram_mode_gap`].
+const ALPHA_HIST_BINS: usize = 64;
+const ALPHA_HIST_EM_ITERS: usize = 100;
+
+/// Distance between the means of a two-component Gaussian mixture fit by EM
+/// to the histogram of `values`. The components start at the 10th and 90th
+/// percentiles. NaN when the values are empty or flat.
+fn histogram_mode_gap(values: &[f32]) -> f64 {
+    let lo = values.iter().copied().fold(f32::INFINITY, f32::min) as f64;
+    let hi = values.iter().copied().fold(f32::NEG_INFINITY, f32::max) as f64;
+    let range = hi - lo;
+    if range.is_nan() || range <= 1e-10 {
+        return f64::NAN;
+    }
+    let width = range / ALPHA_HIST_BINS as f64;
+    let mut counts = [0.0_f64; ALPHA_HIST_BINS];
+    for &v in values {
+        let bin = (((v as f64 - lo) / width) as usize).min(ALPHA_HIST_BINS - 1);
+        counts[bin] += 1.0;
+    }
+    let centers: Vec<f64> = (0..ALPHA_HIST_BINS)
+        .map(|b| lo + (b as f64 + 0.5) * width)
+        .collect();
+
+    let mut mean = [
+        lo + percentile_above_min(values, 10.0),
+        lo + percentile_above_min(values, 90.0),
+    ];
+    let mut var = [(range / 4.0).powi(2); 2];
+    let mut weight = [0.5_f64; 2];
+    let min_var = (width * width) / 4.0;
+    let mut resp = vec![[0.0_f64; 2]; ALPHA_HIST_BINS];
+    for _ in 0..ALPHA_HIST_EM_ITERS {
+        // E-step: per-bin responsibilities.
+        for (r, &x) in resp.iter_mut().zip(&centers) {
+            let dens = [0, 1].map(|k| {
+                weight[k] * (-(x - mean[k]).powi(2) / (2.0 * var[k])).exp() / var[k].sqrt()
+            });
+            let total = dens[0] + dens[1];
+            *r = if total > 0.0 {
+                [dens[0] / total, dens[1] / total]
+            } else {
+                [0.5, 0.5]
+            };
+        }
+        // M-step, weighting each bin center by its count.
+        for k in 0..2 {
+            let nk: f64 = resp.iter().zip(&counts).map(|(r, c)| r[k] * c).sum();
+            if nk <= 0.0 {
+                continue;
+            }
+            mean[k] = resp
+                .iter()
+                .zip(&counts)
+                .zip(&centers)
+                .map(|((r, c), x)| r[k] * c * x)
+                .sum::<f64>()
+                / nk;
+            var[k] = (resp
+                .iter()
+                .zip(&counts)
+                .zip(&centers)
+                .map(|((r, c), x)| r[k] * c * (x - mean[k]).powi(2))
+                .sum::<f64>()
+                / nk)
+                .max(min_var);
+            weight[k] = nk / values.len() as f64;
+        }
+    }
+    (mean[1] - mean[0]).abs()
+}
+
+/// Extend `s` by `pad` samples on each side: reflected about the end samples
+/// (without repeating them) for `Mirror`, wrapped around for `Periodic`.
+/// `Zero` (or a zero pad) returns a plain copy. `pad` is clamped to
+/// `s.len() - 1`.
+fn extend_boundary(s: &[f32], pad: usize, mode: BoundaryMode) -> Vec<f32> {
+    let n = s.len();
+    let pad = pad.min(n.saturating_sub(1));
+    let mut out = Vec::with_capacity(n + 2 * pad);
+    match mode {
+        BoundaryMode::Zero => out.extend_from_slice(s),
+        BoundaryMode::Mirror => {
+            out.extend(s[1..=pad].iter().rev());
+            out.extend_from_slice(s);
+            out.extend(s[n - 1 - pad..n - 1].iter().rev());
+        }
+        BoundaryMode::Periodic => {
+            out.extend_from_slice(&s[n - pad..]);
+            out.extend_from_slice(s);
+            out.extend_from_slice(&s[..pad]);
+        }
+    }
+    out
+}
+
 /// Maximum value in the interior of a slice, excluding `pad` samples from each end.
 ///
 /// Falls back to the full slice when the interior is empty.
@@ -316,6 +836,16 @@ fn interior_peak(s: &[f32], pad: usize) -> f32 {
 /// `warm_counts`: optional spike counts from a previous iteration at the **original**
 /// sampling rate. These are upsampled to a binary trace at the upsampled rate and
 /// used as FISTA warm-start, which typically reduces iterations by 30-60%.
+///
+/// `on_progress`: optional callback invoked after filtering, after every scale
+/// iteration and once the scale loop stops (see [`ProgressEvent`]).
+///
+/// `cancel`: checked at the top of each scale iteration and between FISTA
+/// iterations; once cancelled the loop stops and the best iterate so far is
+/// returned (all-zero spikes with `alpha = 0` if no iteration had completed).
+///
+/// Fails with `NumericalFailure` if `tau_r`, `tau_d` or `fs` is
+/// non-positive or non-finite (the AR(2) model would be unstable).
 #[allow(clippy::too_many_arguments)]
 pub fn solve_trace(
     trace: &[f32],
@@ -329,7 +859,9 @@ pub fn solve_trace(
     hp_enabled: bool,
     lp_enabled: bool,
     lambda: f64,
-) -> InDecaResult {
+    on_progress: Option<ProgressFn>,
+    cancel: Option<CancelToken>,
+) -> Result<InDecaResult, CalabError> {
     solve_trace_opts(
         trace,
         tau_r,
@@ -343,6 +875,8 @@ pub fn solve_trace(
         lp_enabled,
         lambda,
         SolveOptions::default(),
+        on_progress,
+        cancel,
     )
 }
 
@@ -362,13 +896,112 @@ pub fn solve_trace_opts(
     lp_enabled: bool,
     lambda: f64,
     opts: SolveOptions,
-) -> InDecaResult {
+    on_progress: Option<ProgressFn>,
+    cancel: Option<CancelToken>,
+) -> Result<InDecaResult, CalabError> {
+    solve_trace_until(
+        trace,
+        tau_r,
+        tau_d,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        warm_counts,
+        hp_enabled,
+        lp_enabled,
+        lambda,
+        opts,
+        on_progress,
+        &|| cancel.as_ref().is_some_and(CancelToken::is_cancelled),
+    )
+}
+
+/// [`solve_trace_opts`] with cancellation through an arbitrary `should_stop`
+/// poll (e.g. a JS `AbortSignal`, which is not `Send`).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve_trace_until(
+    trace: &[f32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_counts: Option<&[f32]>,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda: f64,
+    opts: SolveOptions,
+    on_progress: Option<ProgressFn>,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<InDecaResult, CalabError> {
+    solve_trace_with(
+        &mut Solver::new(),
+        trace,
+        tau_r,
+        tau_d,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        warm_counts,
+        hp_enabled,
+        lp_enabled,
+        lambda,
+        opts,
+        on_progress,
+        should_stop,
+    )
+}
+
+/// [`solve_trace_until`] on a caller-owned `solver`, so its buffers can be
+/// allocated ahead of time and reused across traces (the JS bindings keep one
+/// for interactive use). Every stage reconfigures the solver before using
+/// it, so the result does not depend on what it solved before.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve_trace_with(
+    solver: &mut Solver,
+    trace: &[f32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_counts: Option<&[f32]>,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda: f64,
+    opts: SolveOptions,
+    on_progress: Option<ProgressFn>,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<InDecaResult, CalabError> {
     let fs_up = fs * upsample_factor as f64;
-    let upsampled = upsample_trace(trace, upsample_factor);
+    // Rejects unstable taus before any stage configures the solver with them.
+    let banded = BandedAR2::new(tau_r, tau_d, fs_up)?;
+    // Shot-noise-limited input is stabilized first, so every later stage
+    // (bleaching, filter, baseline, noise estimate) sees unit-variance noise;
+    // saturation is still judged on the raw values.
+    let raw_trace = trace;
+    let stabilized;
+    let trace = if opts.variance_stabilize {
+        stabilized = crate::baseline::anscombe_transform(raw_trace);
+        &stabilized[..]
+    } else {
+        raw_trace
+    };
+    let mut upsampled = opts.upsample_method.upsample(trace, upsample_factor);
+
+    // Optional photobleaching removal, fit at the original rate and subtracted
+    // on the upsampled grid (sample i sits at t = i / fs_up either way).
+    if opts.photobleaching_correction {
+        let (f0, tau_bl) = crate::baseline::fit_photobleaching(trace, fs);
+        crate::baseline::subtract_photobleaching(&mut upsampled, f0, tau_bl, fs_up);
+    }
 
-    // Single solver allocation reused across all solve_upsampled calls.
+    // One solver reused across all solve_upsampled calls.
     // set_trace() resets all state; buffers grow but never shrink.
-    let mut solver = Solver::new();
 
     // ── Step 1: Apply optional bandpass filter + rolling baseline subtraction ──
     // Apply bandpass filter directly (if HP/LP enabled), then
@@ -386,12 +1019,43 @@ pub fn solve_trace_opts(
         upsampled
     };
 
+    // Saturated frames (clamped at the sensor maximum) are kept out of both the
+    // baseline window and the alpha/baseline fit.
+    let (n_saturated_frames, saturated) = match opts.saturation_value {
+        Some(sat) => (
+            raw_trace.iter().filter(|&&v| v >= sat).count(),
+            Some(saturation_mask(raw_trace, sat, upsample_factor)),
+        ),
+        None => (0, None),
+    };
+    let fit_weights: Option<Vec<f32>> = saturated
+        .as_ref()
+        .map(|m| m.iter().map(|&s| if s { 0.0 } else { 1.0 }).collect());
+
     // Rolling-percentile baseline subtraction: brings the floor to ~0.
     let bl_window = crate::baseline::baseline_window(tau_d, fs_up);
-    crate::baseline::subtract_rolling_baseline(
-        &mut working_trace,
-        bl_window,
-        crate::baseline::DEFAULT_BASELINE_QUANTILE,
+    match &saturated {
+        Some(mask) => crate::baseline::subtract_rolling_baseline_masked(
+            &mut working_trace,
+            mask,
+            bl_window,
+            crate::baseline::DEFAULT_BASELINE_QUANTILE,
+        ),
+        None => crate::baseline::subtract_rolling_baseline(
+            &mut working_trace,
+            bl_window,
+            crate::baseline::DEFAULT_BASELINE_QUANTILE,
+        ),
+    }
+    report(
+        &on_progress,
+        ProgressEvent {
+            stage: Stage::Filter,
+            scale_iter: 0,
+            fista_iter: 0,
+            pve: 0.0,
+            alpha: 0.0,
+        },
     );
 
     // ── Step 2: Boundary padding + initial alpha estimate ───────────────
@@ -403,14 +1067,30 @@ pub fn solve_trace_opts(
     let pad = crate::threshold::boundary_padding(tau_d, fs_up).min(working_trace.len() / 4);
 
     // Estimate alpha from the interior of the trace only (excluding edges).
-    let mut alpha_est = estimate_alpha_interior(&working_trace, pad);
+    let mut alpha_est = initial_alpha(&working_trace, pad, opts.alpha_init);
 
     // Convert original-rate spike counts to upsampled-rate binary for warm-start.
     // upsample_counts_to_binary centers spikes on original sample positions,
     // matching the centered bins used by downsample_binary.
     let warm_binary = warm_counts.map(|counts| upsample_counts_to_binary(counts, upsample_factor));
 
-    let banded = BandedAR2::new(tau_r, tau_d, fs_up);
+    // Mirror/Periodic solve on a trace extended by `ext` samples per side and
+    // trim the solution back; the extension (and warm start) is zero for Zero.
+    let ext = match opts.boundary_mode {
+        BoundaryMode::Zero => 0,
+        BoundaryMode::Mirror | BoundaryMode::Periodic => {
+            pad.min(working_trace.len().saturating_sub(1))
+        }
+    };
+    let warm_binary = warm_binary.map(|warm| {
+        if ext == 0 || warm.len() != working_trace.len() {
+            warm
+        } else {
+            let mut padded = vec![0.0_f32; warm.len() + 2 * ext];
+            padded[ext..ext + warm.len()].copy_from_slice(&warm);
+            padded
+        }
+    });
 
     // Noise-constrained threshold selection needs the per-sample noise std of the
     // filtered trace at the original grid. Estimated LP-cutoff-agnostically from
@@ -429,15 +1109,14 @@ pub fn solve_trace_opts(
             ),
         }
     } else {
-        Selection::MaxPve
+        opts.threshold_criterion.into()
     };
 
     // ── Step 3: Scale iteration loop ────────────────────────────────────
     // Each round: prescale by alpha_est → Box[0,1] FISTA → threshold search
     // against the *original* trace → lstsq recovers alpha directly.
-    // Converges when alpha_lstsq ≈ alpha_est (prescale matches true amplitude).
-    const MAX_SCALE_ITERS: usize = 10;
-    const SCALE_RTOL: f64 = 0.05;
+    // Converges when alpha_lstsq ≈ alpha_est (prescale matches true amplitude);
+    // `opts.scale_stop` decides when that, or a PVE plateau, ends the loop.
 
     let mut best_pve = f64::NEG_INFINITY;
     let mut best_scale_err = f64::INFINITY;
@@ -447,8 +1126,14 @@ pub fn solve_trace_opts(
     let wt_len = working_trace.len();
     let mut scaled = vec![0.0_f32; wt_len];
     let mut s_normalized = vec![0.0_f32; wt_len];
+    let mut last_scale_iter = 0;
+    let mut pve_history = Vec::with_capacity(MAX_SCALE_ITERS);
 
     for scale_iter in 0..MAX_SCALE_ITERS {
+        if should_stop() {
+            break;
+        }
+
         // Fill scaled buffer in-place (multiply by reciprocal instead of dividing).
         let inv_alpha = 1.0 / alpha_est as f32;
         for i in 0..wt_len {
@@ -463,9 +1148,17 @@ pub fn solve_trace_opts(
             None
         };
 
-        let (s_relaxed, _, iterations, converged) = solve_upsampled(
-            &mut solver,
-            &scaled,
+        let extended;
+        let solve_input = if ext > 0 {
+            extended = extend_boundary(&scaled, ext, opts.boundary_mode);
+            &extended[..]
+        } else {
+            &scaled[..]
+        };
+
+        let (mut s_relaxed, _, iterations, converged) = solve_upsampled(
+            solver,
+            solve_input,
             tau_r,
             tau_d,
             fs_up,
@@ -477,12 +1170,19 @@ pub fn solve_trace_opts(
             Constraint::Box01,
             true, // trace is baseline-subtracted → skip FISTA baseline estimation
             lambda,
+            should_stop,
         );
+        if ext > 0 {
+            s_relaxed.truncate(ext + wt_len);
+            s_relaxed.drain(..ext);
+        }
 
         // Normalize relaxed solution to [0,1] before threshold search.
         // Use the interior peak only (excluding boundary padding) so that edge
-        // artifacts from trace subsets starting mid-transient don't dominate.
-        let s_peak = interior_peak(&s_relaxed, pad);
+        // artifacts from trace subsets starting mid-transient don't dominate;
+        // with an extended boundary the artifacts were trimmed off already.
+        let peak_pad = if ext > 0 { 0 } else { pad };
+        let s_peak = interior_peak(&s_relaxed, peak_pad);
         if s_peak > 1e-10 {
             let inv_peak = 1.0 / s_peak;
             for i in 0..s_relaxed.len() {
@@ -510,6 +1210,7 @@ pub fn solve_trace_opts(
             upsample_factor,
             f64::INFINITY,
             selection,
+            fit_weights.as_deref(),
         );
 
         // Scale-loop convergence error: how close the lstsq-recovered alpha is to
@@ -524,14 +1225,14 @@ pub fn solve_trace_opts(
         // true alpha (fit against the original trace).
         //
         // MaxPve keeps the highest-PVE iterate (historical behavior). Under
-        // NoiseFloor, ranking by PVE would defeat the criterion — the inner search
-        // deliberately stops at the noise floor (below max PVE), so a max-PVE outer
+        // NoiseFloor or MinAic, ranking by PVE would defeat the criterion — the
+        // inner search deliberately stops short of max PVE, so a max-PVE outer
         // pick would re-select the densest-fitting iteration and re-launder the
         // sparsity. Instead select the best-calibrated prescale (smallest scale
         // error) — the scale loop's own fixed point, which is criterion-neutral.
         let is_better = match selection {
             Selection::MaxPve => pve > best_pve,
-            Selection::NoiseFloor { .. } => scale_err < best_scale_err,
+            Selection::NoiseFloor { .. } | Selection::MinAic => scale_err < best_scale_err,
         };
         if is_better {
             best_pve = pve;
@@ -547,8 +1248,20 @@ pub fn solve_trace_opts(
             ));
         }
 
-        // Converged: alpha_lstsq ≈ alpha_est means the prescale was correct.
-        if scale_err < SCALE_RTOL {
+        last_scale_iter = scale_iter;
+        report(
+            &on_progress,
+            ProgressEvent {
+                stage: Stage::ScaleIteration,
+                scale_iter,
+                fista_iter: iterations,
+                pve,
+                alpha: alpha_lstsq,
+            },
+        );
+
+        pve_history.push(pve);
+        if opts.scale_stop.should_stop(scale_err, &pve_history) {
             break;
         }
 
@@ -562,18 +1275,38 @@ pub fn solve_trace_opts(
     // ── Step 4: Extract best result ─────────────────────────────────────
     let (s_binary, alpha, baseline, threshold, pve, iterations, converged) = best_result
         .unwrap_or_else(|| {
-            // Fallback: no valid result found (shouldn't happen)
+            // Fallback: cancelled before the first scale iteration finished.
             (vec![0.0; wt_len], 0.0, 0.0, 0.0, 0.0, 0, false)
         });
+    report(
+        &on_progress,
+        ProgressEvent {
+            stage: Stage::Converged,
+            scale_iter: last_scale_iter,
+            fista_iter: iterations,
+            pve,
+            alpha,
+        },
+    );
 
     // Downsample binary spike train to original rate using centered bins
     let s_counts = downsample_binary(&s_binary, upsample_factor);
 
     // Downsample filtered trace to original rate directly from working_trace
-    // (working_trace is not modified after baseline subtraction).
-    let filtered_trace = Some(downsample_average(&working_trace, upsample_factor));
+    // (working_trace is not modified after baseline subtraction), low-passed
+    // first so nothing above the original Nyquist rate aliases back in.
+    let filtered_trace = downsample_lowpass(
+        &working_trace,
+        upsample_factor,
+        LOWPASS_ORDER_PER_FACTOR * upsample_factor,
+    );
+    let banded = BandedAR2::new(tau_r, tau_d, fs)?;
+    let residual = fit_residual(&filtered_trace, &s_counts, alpha, baseline, &banded);
+    let noise_std = inactive_residual_std(&residual, &s_counts, threshold);
+    let snr_db = estimate_trace_snr(&filtered_trace, &s_counts, alpha, baseline, &banded);
+    let filtered_trace = Some(filtered_trace);
 
-    InDecaResult {
+    Ok(InDecaResult {
         s_counts,
         filtered_trace,
         alpha,
@@ -582,53 +1315,731 @@ pub fn solve_trace_opts(
         pve,
         iterations,
         converged,
-    }
+        n_saturated_frames,
+        noise_std,
+        snr_db,
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::kernel::build_kernel;
-
-    /// Build a clean trace: convolve spikes through the kernel.
-    fn make_trace(tau_r: f64, tau_d: f64, fs: f64, n: usize, spike_pos: &[usize]) -> Vec<f32> {
-        let kernel = build_kernel(tau_r, tau_d, fs);
-        let mut trace = vec![0.0_f32; n];
-        for &s in spike_pos {
-            for (k, &kv) in kernel.iter().enumerate() {
-                if s + k < n {
-                    trace[s + k] += kv;
-                }
-            }
+/// Coarse-to-fine InDeCa solve: run [`solve_trace`] on bin-averaged copies of
+/// the trace (`downsample_average`) from the coarsest scale down, each level
+/// warm-started from the previous level's spikes (`upsample_counts_to_binary`),
+/// and finish with a full-rate solve that inherits the last warm start.
+///
+/// `downsample_scales` lists the decimation factors in decreasing order, e.g.
+/// `[8, 4, 2, 1]`; each must divide the one before it. Factors of 1 are
+/// skipped since the final full-rate solve always runs. A coarse level at
+/// factor `d` gets `max_iters / d` FISTA iterations. The result, including
+/// `iterations`, is that of the final full-rate solve.
+///
+/// `on_progress` receives the events of every level, coarsest first; `cancel`
+/// stops whichever level is running and skips the rest.
+///
+/// Fails with `InvalidParameter` if `downsample_scales` is empty, contains 0,
+/// is not strictly decreasing or a factor does not divide the one before it.
+#[allow(clippy::too_many_arguments)]
+pub fn solve_trace_multiscale(
+    trace: &[f32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda: f64,
+    downsample_scales: &[usize],
+    on_progress: Option<ProgressFn>,
+    cancel: Option<CancelToken>,
+) -> Result<InDecaResult, CalabError> {
+    let invalid_scales = |reason: &str| {
+        CalabError::invalid(
+            "downsample_scales",
+            format!("{downsample_scales:?}"),
+            reason,
+        )
+    };
+    if downsample_scales.is_empty() {
+        return Err(invalid_scales("must list at least one factor"));
+    }
+    if downsample_scales.contains(&0) {
+        return Err(invalid_scales("factors must be at least 1"));
+    }
+    for pair in downsample_scales.windows(2) {
+        if pair[1] >= pair[0] {
+            return Err(invalid_scales("factors must be strictly decreasing"));
+        }
+        if pair[0] % pair[1] != 0 {
+            return Err(invalid_scales("each factor must divide the one before it"));
         }
-        trace
     }
 
-    #[test]
-    fn outputs_in_range() {
-        let trace = make_trace(0.02, 0.4, 30.0, 300, &[20, 80, 150, 220]);
-        let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 500, 1e-4, None, false, false, 0.0,
-        );
+    let level_progress = fan_out_progress(on_progress);
 
-        // Spike counts should be non-negative
-        for (i, &v) in result.s_counts.iter().enumerate() {
-            assert!(v >= 0.0, "Negative spike count at {}: {}", i, v);
+    let mut warm: Option<(Vec<f32>, usize)> = None;
+    for &scale in downsample_scales.iter().filter(|&&d| d > 1) {
+        if cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            break;
         }
-
-        // Output length should match input
-        assert_eq!(result.s_counts.len(), trace.len());
+        let coarse = downsample_average(trace, scale);
+        let warm_counts =
+            warm.map(|(counts, prev)| refine_counts(&counts, prev / scale, coarse.len()));
+        let result = solve_trace(
+            &coarse,
+            tau_r,
+            tau_d,
+            fs / scale as f64,
+            1,
+            (max_iters / scale as u32).max(1),
+            tol,
+            warm_counts.as_deref(),
+            hp_enabled,
+            lp_enabled,
+            lambda,
+            level_progress(),
+            cancel.clone(),
+        )?;
+        warm = Some((result.s_counts, scale));
     }
 
-    #[test]
-    fn known_spike_detection() {
-        let spike_positions = [30, 100, 200];
-        let alpha_true = 10.0_f32;
-        let baseline_true = 2.0_f32;
-        let kernel = build_kernel(0.02, 0.4, 30.0);
-        let n = 300;
-        let mut trace = vec![baseline_true; n];
-        for &pos in &spike_positions {
+    let warm_counts = warm.map(|(counts, prev)| refine_counts(&counts, prev, trace.len()));
+    solve_trace(
+        trace,
+        tau_r,
+        tau_d,
+        fs,
+        1,
+        max_iters,
+        tol,
+        warm_counts.as_deref(),
+        hp_enabled,
+        lp_enabled,
+        lambda,
+        level_progress(),
+        cancel,
+    )
+}
+
+/// One boxed callback per sub-solve, all forwarding to the caller's.
+fn fan_out_progress(on_progress: Option<ProgressFn>) -> impl Fn() -> Option<ProgressFn> {
+    let shared = on_progress.map(|f| Arc::new(std::sync::Mutex::new(f)));
+    move || {
+        shared
+            .clone()
+            .map(|f| -> ProgressFn { Box::new(move |e| (f.lock().unwrap())(e)) })
+    }
+}
+
+/// [`solve_trace`] over overlapping segments, so only one segment's upsampled
+/// buffers are alive at a time — for long recordings.
+///
+/// Segments are `segment_len` frames long and start every `segment_len -
+/// overlap` frames; the last one is cut at the end of the trace, and no
+/// segment starts once the previous one has reached it. Each segment after
+/// the first is warm-started with the previous segment's `s_counts` over
+/// their shared `overlap` frames. `s_counts` (and `filtered_trace`) are
+/// stitched with a linear crossfade across each overlap, so the result has
+/// `trace.len()` frames. Per-segment scalars (`alpha`, `baseline`,
+/// `threshold`, `pve`, `noise_std`, `snr_db`) are averaged weighted by the
+/// frames each segment contributes; `iterations` and `n_saturated_frames`
+/// are summed and `converged` requires every segment to converge. A trace
+/// no longer than `segment_len` is solved in one piece.
+///
+/// `on_progress` receives every segment's events in order; `cancel` stops
+/// the running segment and skips the rest, whose frames are left at zero.
+///
+/// Fails if `segment_len` is zero or `overlap >= segment_len`, and on the
+/// taus [`solve_trace`] rejects.
+#[allow(clippy::too_many_arguments)]
+pub fn solve_trace_segmented(
+    trace: &[f32],
+    segment_len: usize,
+    overlap: usize,
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda: f64,
+    on_progress: Option<ProgressFn>,
+    cancel: Option<CancelToken>,
+) -> Result<InDecaResult, CalabError> {
+    if segment_len == 0 {
+        return Err(CalabError::invalid(
+            "segment_len",
+            segment_len,
+            "must be at least 1",
+        ));
+    }
+    if overlap >= segment_len {
+        return Err(CalabError::invalid(
+            "overlap",
+            overlap,
+            format!("must be shorter than segment_len ({segment_len})"),
+        ));
+    }
+    let n = trace.len();
+    let segment_progress = fan_out_progress(on_progress);
+    let solve = |segment: &[f32], warm: Option<&[f32]>| {
+        solve_trace(
+            segment,
+            tau_r,
+            tau_d,
+            fs,
+            upsample_factor,
+            max_iters,
+            tol,
+            warm,
+            hp_enabled,
+            lp_enabled,
+            lambda,
+            segment_progress(),
+            cancel.clone(),
+        )
+    };
+    if n <= segment_len {
+        return solve(trace, None);
+    }
+
+    let step = segment_len - overlap;
+    let mut starts = vec![0];
+    while starts[starts.len() - 1] + segment_len < n {
+        starts.push(starts[starts.len() - 1] + step);
+    }
+
+    let mut s_sum = vec![0.0_f32; n];
+    let mut filtered_sum: Option<Vec<f32>> = None;
+    let mut frame_weight = vec![0.0_f32; n];
+    let mut scalars = [0.0_f64; 6];
+    let mut total_weight = 0.0_f64;
+    let mut iterations = 0;
+    let mut converged = true;
+    let mut n_saturated_frames = 0;
+    let mut prev: Option<(usize, Vec<f32>)> = None;
+    for (k, &start) in starts.iter().enumerate() {
+        if k > 0 && cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            break;
+        }
+        let end = (start + segment_len).min(n);
+        let warm = prev.map(|(prev_start, counts)| {
+            let mut warm = vec![0.0_f32; end - start];
+            let shared = &counts[start - prev_start..];
+            warm[..shared.len()].copy_from_slice(shared);
+            warm
+        });
+        let result = solve(&trace[start..end], warm.as_deref())?;
+
+        let next_start = starts.get(k + 1).copied();
+        let mut segment_weight = 0.0_f64;
+        for i in start..end {
+            let mut w = 1.0_f32;
+            if k > 0 && i < start + overlap {
+                w *= (i - start + 1) as f32 / (overlap + 1) as f32;
+            }
+            if let Some(next) = next_start.filter(|&next| i >= next) {
+                w *= 1.0 - (i - next + 1) as f32 / (overlap + 1) as f32;
+            }
+            s_sum[i] += w * result.s_counts[i - start];
+            if let Some(filtered) = &result.filtered_trace {
+                filtered_sum.get_or_insert_with(|| vec![0.0; n])[i] += w * filtered[i - start];
+            }
+            frame_weight[i] += w;
+            segment_weight += w as f64;
+        }
+        for (acc, value) in scalars.iter_mut().zip([
+            result.alpha,
+            result.baseline,
+            result.threshold,
+            result.pve,
+            result.noise_std,
+            result.snr_db,
+        ]) {
+            *acc += segment_weight * value;
+        }
+        total_weight += segment_weight;
+        iterations += result.iterations;
+        converged &= result.converged;
+        n_saturated_frames += result.n_saturated_frames;
+        prev = Some((start, result.s_counts));
+    }
+
+    let normalize = |sum: &mut Vec<f32>| {
+        for (v, &w) in sum.iter_mut().zip(&frame_weight) {
+            *v = if w > 0.0 { *v / w } else { 0.0 };
+        }
+    };
+    normalize(&mut s_sum);
+    if let Some(filtered) = filtered_sum.as_mut() {
+        normalize(filtered);
+    }
+    let [alpha, baseline, threshold, pve, noise_std, snr_db] = scalars.map(|v| {
+        if total_weight > 0.0 {
+            v / total_weight
+        } else {
+            0.0
+        }
+    });
+    Ok(InDecaResult {
+        s_counts: s_sum,
+        filtered_trace: filtered_sum,
+        alpha,
+        baseline,
+        threshold,
+        pve,
+        iterations,
+        converged,
+        n_saturated_frames,
+        noise_std,
+        snr_db,
+    })
+}
+
+/// Carry coarse spike counts to a grid `factor` times finer, zero-padded to
+/// `len` (bin-averaging truncates, so the coarse grid can be a few bins short).
+fn refine_counts(counts: &[f32], factor: usize, len: usize) -> Vec<f32> {
+    let mut fine = upsample_counts_to_binary(counts, factor.max(1));
+    fine.resize(len, 0.0);
+    fine
+}
+
+/// Original-rate fit residual `y - alpha·K*s - baseline`.
+fn fit_residual(
+    y: &[f32],
+    s_counts: &[f32],
+    alpha: f64,
+    baseline: f64,
+    banded: &BandedAR2,
+) -> Vec<f32> {
+    let n = y.len().min(s_counts.len());
+    let mut reconv = vec![0.0_f32; n];
+    banded.convolve_forward(&s_counts[..n], &mut reconv);
+    (0..n)
+        .map(|i| y[i] - (alpha * reconv[i] as f64 + baseline) as f32)
+        .collect()
+}
+
+/// Residual std over inactive frames (`s_counts < 0.1·threshold`), falling
+/// back to all frames when none are inactive.
+fn inactive_residual_std(residual: &[f32], s_counts: &[f32], threshold: f64) -> f64 {
+    let cutoff = (0.1 * threshold) as f32;
+    let inactive: Vec<f32> = residual
+        .iter()
+        .zip(s_counts)
+        .filter(|&(_, &c)| c < cutoff)
+        .map(|(&r, _)| r)
+        .collect();
+    if inactive.is_empty() {
+        variance(residual).sqrt()
+    } else {
+        variance(&inactive).sqrt()
+    }
+}
+
+/// Standard normal draw from a 64-bit LCG (Box–Muller, one value per call).
+pub(crate) fn lcg_gaussian(state: &mut u64) -> f64 {
+    let mut uniform = || {
+        *state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        // Top 53 bits → (0, 1]; never 0 so ln() stays finite.
+        ((*state >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    };
+    let u1 = uniform();
+    let u2 = uniform();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Linear-interpolated quantile of an ascending-sorted slice.
+fn sorted_quantile(sorted: &[f32], q: f64) -> f32 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    let frac = (pos - lo as f64) as f32;
+    sorted[lo] + frac * (sorted[hi] - sorted[lo])
+}
+
+/// Parametric-bootstrap confidence band for the spike counts of `trace`.
+///
+/// Solves the trace once to get the fit and its residual noise level
+/// ([`InDecaResult::noise_std`]), then re-solves
+/// `n_bootstrap` copies of the trace perturbed with Gaussian noise of that
+/// variance (seeded LCG, so results are reproducible). Returns per-frame
+/// `(lower, upper)` quantiles of `s_counts` at `alpha_ci / 2` and
+/// `1 - alpha_ci / 2`, each the length of `trace`. Replicates that detect no
+/// spikes contribute zeros and are kept in the quantiles.
+///
+/// Replicates are warm-started from the point estimate. With
+/// `n_bootstrap == 0` both bounds equal the point estimate. Fails on the
+/// taus [`solve_trace`] rejects.
+#[allow(clippy::too_many_arguments)]
+pub fn bootstrap_spike_ci(
+    trace: &[f32],
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    n_bootstrap: usize,
+    alpha_ci: f64,
+    seed: u64,
+) -> Result<(Vec<f32>, Vec<f32>), CalabError> {
+    const MAX_ITERS: u32 = 500;
+    const TOL: f64 = 1e-4;
+    let n = trace.len();
+    let point = solve_trace(
+        trace,
+        tau_rise,
+        tau_decay,
+        fs,
+        upsample_factor,
+        MAX_ITERS,
+        TOL,
+        None,
+        false,
+        false,
+        0.0,
+        None,
+        None,
+    )?;
+    if n_bootstrap == 0 || n == 0 {
+        return Ok((point.s_counts.clone(), point.s_counts));
+    }
+
+    let sigma = point.noise_std;
+
+    let mut state = seed;
+    let mut samples = vec![0.0_f32; n_bootstrap * n]; // [time][replicate]
+    let mut perturbed = vec![0.0_f32; n];
+    for b in 0..n_bootstrap {
+        for (p, &v) in perturbed.iter_mut().zip(trace) {
+            *p = v + (sigma * lcg_gaussian(&mut state)) as f32;
+        }
+        let replicate = solve_trace(
+            &perturbed,
+            tau_rise,
+            tau_decay,
+            fs,
+            upsample_factor,
+            MAX_ITERS,
+            TOL,
+            Some(&point.s_counts),
+            false,
+            false,
+            0.0,
+            None,
+            None,
+        )?;
+        for (t, &c) in replicate.s_counts.iter().enumerate() {
+            samples[t * n_bootstrap + b] = c;
+        }
+    }
+
+    let q = alpha_ci.clamp(0.0, 1.0) / 2.0;
+    let mut lower = Vec::with_capacity(n);
+    let mut upper = Vec::with_capacity(n);
+    for row in samples.chunks_mut(n_bootstrap) {
+        row.sort_unstable_by(|a, b| a.total_cmp(b));
+        lower.push(sorted_quantile(row, q));
+        upper.push(sorted_quantile(row, 1.0 - q));
+    }
+    Ok((lower, upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::build_kernel;
+    use crate::rng::lcg_noise;
+
+    /// Build a clean trace: convolve spikes through the kernel.
+    fn make_trace(tau_r: f64, tau_d: f64, fs: f64, n: usize, spike_pos: &[usize]) -> Vec<f32> {
+        let kernel = build_kernel(tau_r, tau_d, fs);
+        let mut trace = vec![0.0_f32; n];
+        for &s in spike_pos {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if s + k < n {
+                    trace[s + k] += kv;
+                }
+            }
+        }
+        trace
+    }
+
+    #[test]
+    fn outputs_in_range() {
+        let trace = make_trace(0.02, 0.4, 30.0, 300, &[20, 80, 150, 220]);
+        let result = solve_trace(
+            &trace, 0.02, 0.4, 30.0, 1, 500, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+
+        // Spike counts should be non-negative
+        for (i, &v) in result.s_counts.iter().enumerate() {
+            assert!(v >= 0.0, "Negative spike count at {}: {}", i, v);
+        }
+
+        // Output length should match input
+        assert_eq!(result.s_counts.len(), trace.len());
+    }
+
+    #[test]
+    fn alpha_init_strategies() {
+        // Bimodal values: noise floor around 0, active mode around 4.
+        let mut state = 0x5EED_u64;
+        let values: Vec<f32> = (0..2000)
+            .map(|i| {
+                let mode = if i % 4 == 0 { 4.0 } else { 0.0 };
+                mode + 0.3 * lcg_gaussian(&mut state) as f32
+            })
+            .collect();
+        let gap = histogram_mode_gap(&values);
+        assert!((gap - 4.0).abs() < 0.2, "gap {gap}");
+        let ptp = estimate_alpha_interior(&values, 0);
+        assert_eq!(percentile_above_min(&values, 100.0), ptp);
+        assert!(percentile_above_min(&values, 50.0) < ptp);
+        assert_eq!(initial_alpha(&values, 0, AlphaInit::UserProvided(2.5)), 2.5);
+        // Unusable values fall back to peak-to-trough.
+        assert_eq!(
+            initial_alpha(&values, 0, AlphaInit::UserProvided(-1.0)),
+            ptp
+        );
+        assert_eq!(initial_alpha(&[1.0; 50], 0, AlphaInit::Histogram), 1.0);
+
+        // Noisy isolated transients on a high baseline: the noise extremes
+        // inflate peak-to-trough, the mixture fit does not.
+        use std::sync::{Arc, Mutex};
+        let n = 1200;
+        let spikes: Vec<usize> = (30..n - 30).step_by(60).collect();
+        let mut trace = make_trace(0.02, 0.4, 30.0, n, &spikes);
+        for v in trace.iter_mut() {
+            *v = 100.0 + 3.0 * *v + (1.5 * lcg_gaussian(&mut state)) as f32;
+        }
+        let count_iters = |alpha_init| {
+            let count = Arc::new(Mutex::new(0_usize));
+            let sink = Arc::clone(&count);
+            let on_progress: ProgressFn = Box::new(move |e| {
+                if e.stage == Stage::ScaleIteration {
+                    *sink.lock().unwrap() += 1;
+                }
+            });
+            let opts = SolveOptions {
+                alpha_init,
+                ..Default::default()
+            };
+            solve_trace_opts(
+                &trace,
+                0.02,
+                0.4,
+                30.0,
+                1,
+                500,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                opts,
+                Some(on_progress),
+                None,
+            )
+            .unwrap();
+            let n = *count.lock().unwrap();
+            n
+        };
+        let peak_trough = count_iters(AlphaInit::PeakTrough);
+        let histogram = count_iters(AlphaInit::Histogram);
+        assert!(histogram < peak_trough, "{histogram} vs {peak_trough}");
+    }
+
+    #[test]
+    fn scale_stop_criteria() {
+        let alpha = ScaleStopCriterion::AlphaConvergence(0.05);
+        assert!(alpha.should_stop(0.01, &[0.5]));
+        assert!(!alpha.should_stop(0.2, &[0.5]));
+
+        let plateau = ScaleStopCriterion::PveImprovement {
+            min_delta: 0.01,
+            patience: 2,
+        };
+        assert!(!plateau.should_stop(1.0, &[0.5, 0.505]));
+        assert!(!plateau.should_stop(1.0, &[0.5, 0.6, 0.605]));
+        assert!(plateau.should_stop(1.0, &[0.5, 0.6, 0.605, 0.606]));
+
+        let any = ScaleStopCriterion::AnyOf(vec![alpha, plateau]);
+        assert!(any.should_stop(0.01, &[0.5]));
+        assert!(any.should_stop(1.0, &[0.5, 0.6, 0.605, 0.6]));
+        assert!(!ScaleStopCriterion::AnyOf(vec![]).should_stop(0.0, &[0.5, 0.5, 0.5]));
+
+        // End to end: with alpha convergence disabled, a PVE plateau still
+        // ends the loop well before MAX_SCALE_ITERS.
+        use std::sync::{Arc, Mutex};
+        let trace = make_trace(0.02, 0.4, 30.0, 300, &[20, 80, 150, 220]);
+        let count_iters = |scale_stop| {
+            let events = Arc::new(Mutex::new(0_usize));
+            let sink = Arc::clone(&events);
+            let on_progress: ProgressFn = Box::new(move |e| {
+                if e.stage == Stage::ScaleIteration {
+                    *sink.lock().unwrap() += 1;
+                }
+            });
+            let opts = SolveOptions {
+                scale_stop,
+                ..Default::default()
+            };
+            solve_trace_opts(
+                &trace,
+                0.02,
+                0.4,
+                30.0,
+                1,
+                500,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                opts,
+                Some(on_progress),
+                None,
+            )
+            .unwrap();
+            let n = *events.lock().unwrap();
+            n
+        };
+        assert_eq!(
+            count_iters(ScaleStopCriterion::AnyOf(vec![])),
+            MAX_SCALE_ITERS
+        );
+        let plateau_iters = count_iters(ScaleStopCriterion::PveImprovement {
+            min_delta: 1.0,
+            patience: 1,
+        });
+        assert_eq!(plateau_iters, 2);
+    }
+
+    #[test]
+    fn progress_reports_every_stage() {
+        use std::sync::{Arc, Mutex};
+
+        let trace = make_trace(0.02, 0.4, 30.0, 300, &[20, 80, 150, 220]);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let on_progress: ProgressFn = Box::new(move |e| sink.lock().unwrap().push(e));
+        let result = solve_trace(
+            &trace,
+            0.02,
+            0.4,
+            30.0,
+            1,
+            500,
+            1e-4,
+            None,
+            false,
+            false,
+            0.0,
+            Some(on_progress),
+            None,
+        )
+        .unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.first().unwrap().stage, Stage::Filter);
+        let last = events.last().unwrap();
+        assert_eq!(last.stage, Stage::Converged);
+        assert_eq!((last.alpha, last.pve), (result.alpha, result.pve));
+        let n_scale = events
+            .iter()
+            .filter(|e| e.stage == Stage::ScaleIteration)
+            .count();
+        assert_eq!(n_scale, events.len() - 2);
+        assert_eq!(last.scale_iter, n_scale - 1);
+
+        // A panicking callback is swallowed; the solve still completes.
+        let panicking: ProgressFn = Box::new(|_| panic!("callback failure"));
+        let survived = solve_trace(
+            &trace,
+            0.02,
+            0.4,
+            30.0,
+            1,
+            500,
+            1e-4,
+            None,
+            false,
+            false,
+            0.0,
+            Some(panicking),
+            None,
+        )
+        .unwrap();
+        assert_eq!(survived.s_counts, result.s_counts);
+    }
+
+    #[test]
+    fn cancel_stops_scale_loop() {
+        use std::sync::{Arc, Mutex};
+
+        // The trace is scaled so the first round's prescale is off and the
+        // scale loop needs more than one iteration.
+        let trace: Vec<f32> = make_trace(0.02, 0.4, 30.0, 300, &[20, 80, 150, 220])
+            .iter()
+            .map(|v| 5.0 * v + 1.0)
+            .collect();
+        let solve = |on_progress, cancel| {
+            solve_trace(
+                &trace,
+                0.02,
+                0.4,
+                30.0,
+                1,
+                500,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                on_progress,
+                cancel,
+            )
+            .unwrap()
+        };
+
+        // Cancelled before any scale iteration: zeroed result, no panic.
+        let token = CancelToken::new();
+        token.cancel();
+        let early = solve(None, Some(token));
+        assert!(early.s_counts.iter().all(|&v| v == 0.0));
+        assert_eq!((early.alpha, early.iterations), (0.0, 0));
+        assert_eq!(early.s_counts.len(), trace.len());
+
+        // Cancelled from the progress callback after the first round: that
+        // round's result is kept.
+        let token = CancelToken::new();
+        let handle = token.clone();
+        let rounds = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&rounds);
+        let on_progress: ProgressFn = Box::new(move |e| {
+            if e.stage == Stage::ScaleIteration {
+                *counter.lock().unwrap() += 1;
+                handle.cancel();
+            }
+        });
+        let stopped = solve(Some(on_progress), Some(token));
+        assert_eq!(*rounds.lock().unwrap(), 1);
+        assert!(stopped.alpha > 0.0 && stopped.iterations > 0);
+    }
+
+    #[test]
+    fn known_spike_detection() {
+        let spike_positions = [30, 100, 200];
+        let alpha_true = 10.0_f32;
+        let baseline_true = 2.0_f32;
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 300;
+        let mut trace = vec![baseline_true; n];
+        for &pos in &spike_positions {
             for (k, &kv) in kernel.iter().enumerate() {
                 if pos + k < n {
                     trace[pos + k] += alpha_true * kv;
@@ -636,8 +2047,9 @@ mod tests {
             }
         }
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, false, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
 
         // Check that spikes are detected near the true positions
         let mut detected = 0;
@@ -687,12 +2099,50 @@ mod tests {
         // (but not guaranteed in all cases, so we just check it runs)
     }
 
+    #[test]
+    fn reused_solver_matches_fresh_solve() {
+        let long = make_trace(0.02, 0.4, 30.0, 400, &[30, 120, 250, 330]);
+        let short = make_trace(0.02, 0.4, 30.0, 150, &[20, 90]);
+        let solve = |solver: &mut Solver, trace: &[f32], hp: bool| {
+            solve_trace_with(
+                solver,
+                trace,
+                0.02,
+                0.4,
+                30.0,
+                2,
+                300,
+                1e-4,
+                None,
+                hp,
+                hp,
+                0.0,
+                SolveOptions::default(),
+                None,
+                &|| false,
+            )
+            .unwrap()
+        };
+
+        // A filtered long trace first, then an unfiltered short one on the
+        // same solver: no state may leak from the first solve.
+        let mut reused = Solver::new();
+        solve(&mut reused, &long, true);
+        let again = solve(&mut reused, &short, false);
+        let fresh = solve(&mut Solver::new(), &short, false);
+        assert_eq!(again.s_counts, fresh.s_counts);
+        assert_eq!(again.alpha, fresh.alpha);
+        assert_eq!(again.baseline, fresh.baseline);
+        assert_eq!(again.iterations, fresh.iterations);
+    }
+
     #[test]
     fn upsampled_output_length() {
         let trace = make_trace(0.02, 0.4, 30.0, 100, &[20, 50]);
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 10, 200, 1e-3, None, false, false, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 10, 200, 1e-3, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
 
         // Output should be same length as input regardless of upsample factor
         assert_eq!(
@@ -706,8 +2156,9 @@ mod tests {
     fn zero_trace() {
         let trace = vec![0.0_f32; 100];
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 100, 1e-4, None, false, false, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 1, 100, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
         let total_spikes: f32 = result.s_counts.iter().sum();
         assert!(
             total_spikes < 1e-6,
@@ -742,8 +2193,9 @@ mod tests {
         }
 
         let result = solve_trace(
-            &trace, tau_r, tau_d, fs, 10, 500, 1e-4, None, false, false, 0.0,
-        );
+            &trace, tau_r, tau_d, fs, 10, 500, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
 
         let total_counts: f32 = result.s_counts.iter().sum();
 
@@ -751,7 +2203,7 @@ mod tests {
         // several upsampled bins. The count may exceed the true spike count, but
         // alpha × count (total energy) should still be conserved.
         assert!(
-            total_counts >= 2.0 && total_counts <= 30.0,
+            (2.0..=30.0).contains(&total_counts),
             "Expected spike counts in [2, 30] at 10x upsample, got {}",
             total_counts
         );
@@ -808,8 +2260,9 @@ mod tests {
         let subset = &full_trace[subset_start..subset_end];
 
         let result = solve_trace(
-            subset, tau_r, tau_d, fs, 1, 1000, 1e-4, None, false, false, 0.0,
-        );
+            subset, tau_r, tau_d, fs, 1, 1000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
         let total_spikes: f32 = result.s_counts.iter().sum();
 
         // Should detect interior spikes, not just the edge artifact
@@ -823,6 +2276,108 @@ mod tests {
         assert!(result.pve > 0.7, "PVE should be > 0.7, got {}", result.pve);
     }
 
+    #[test]
+    fn extend_boundary_reflects_and_wraps() {
+        let s = [0.0, 1.0, 2.0, 3.0, 4.0];
+        assert_eq!(
+            extend_boundary(&s, 2, BoundaryMode::Mirror),
+            [2.0, 1.0, 0.0, 1.0, 2.0, 3.0, 4.0, 3.0, 2.0]
+        );
+        assert_eq!(
+            extend_boundary(&s, 2, BoundaryMode::Periodic),
+            [3.0, 4.0, 0.0, 1.0, 2.0, 3.0, 4.0, 0.0, 1.0]
+        );
+        assert_eq!(extend_boundary(&s, 2, BoundaryMode::Zero), s);
+        // Pad longer than the trace is clamped.
+        assert_eq!(extend_boundary(&s, 9, BoundaryMode::Mirror).len(), 13);
+    }
+
+    /// Extended-boundary solves return a full-length result and keep spikes
+    /// that fall inside the boundary padding at either end.
+    #[test]
+    fn extended_boundary_keeps_edge_spikes() {
+        let (tau_r, tau_d, fs) = (0.02, 0.4, 30.0);
+        let n = 400;
+        let spike_positions = [8, 120, 250, n - 8];
+        let mut trace = make_trace(tau_r, tau_d, fs, n, &spike_positions);
+        for v in trace.iter_mut() {
+            *v = 1.0 + 4.0 * *v;
+        }
+
+        for boundary_mode in [
+            BoundaryMode::Zero,
+            BoundaryMode::Mirror,
+            BoundaryMode::Periodic,
+        ] {
+            let result = solve_trace_opts(
+                &trace,
+                tau_r,
+                tau_d,
+                fs,
+                1,
+                1000,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                SolveOptions {
+                    boundary_mode,
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(result.s_counts.len(), n);
+            let detected: Vec<usize> = (0..n).filter(|&i| result.s_counts[i] > 0.0).collect();
+            assert_eq!(detected, spike_positions, "{boundary_mode:?}");
+            assert!(
+                (result.alpha - 4.0).abs() < 0.2,
+                "{boundary_mode:?}: alpha {}",
+                result.alpha
+            );
+        }
+    }
+
+    /// Spline upsampling recovers the same spikes as linear at factor 4.
+    #[test]
+    fn cubic_upsampling_recovers_spikes() {
+        let (tau_r, tau_d, fs) = (0.02, 0.4, 30.0);
+        let n = 300;
+        let spike_positions = [30, 120, 210];
+        let mut trace = make_trace(tau_r, tau_d, fs, n, &spike_positions);
+        for v in trace.iter_mut() {
+            *v = 1.0 + 4.0 * *v;
+        }
+
+        for upsample_method in [UpsampleMethod::Linear, UpsampleMethod::Cubic] {
+            let result = solve_trace_opts(
+                &trace,
+                tau_r,
+                tau_d,
+                fs,
+                4,
+                1000,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                SolveOptions {
+                    upsample_method,
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(result.s_counts.len(), n);
+            let detected: Vec<usize> = (0..n).filter(|&i| result.s_counts[i] > 0.0).collect();
+            assert_eq!(detected, spike_positions, "{upsample_method:?}");
+        }
+    }
+
     /// High baseline should not prevent spike detection.
     /// Real calcium traces often have baseline >> transient amplitude.
     #[test]
@@ -846,8 +2401,9 @@ mod tests {
         }
 
         let result = solve_trace(
-            &trace, tau_r, tau_d, fs, 1, 1000, 1e-4, None, false, false, 0.0,
-        );
+            &trace, tau_r, tau_d, fs, 1, 1000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
         let total_spikes: f32 = result.s_counts.iter().sum();
 
         assert!(
@@ -875,8 +2431,9 @@ mod tests {
         }
 
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, true, true, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, true, true, 0.0, None, None,
+        )
+        .unwrap();
 
         // Output length should match input
         assert_eq!(result.s_counts.len(), trace.len());
@@ -921,8 +2478,9 @@ mod tests {
         }
 
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, true, false, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, true, false, 0.0, None, None,
+        )
+        .unwrap();
 
         assert_eq!(result.s_counts.len(), trace.len());
 
@@ -957,8 +2515,9 @@ mod tests {
         }
 
         let result = solve_trace(
-            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, true, 0.0,
-        );
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, true, 0.0, None, None,
+        )
+        .unwrap();
 
         assert_eq!(result.s_counts.len(), trace.len());
         assert!(result.filtered_trace.is_some());
@@ -973,20 +2532,6 @@ mod tests {
         );
     }
 
-    /// Deterministic white-ish noise in [-amp, amp) (variance ≈ amp²/3).
-    fn lcg_noise(n: usize, amp: f32, seed: u64) -> Vec<f32> {
-        let mut state = seed;
-        (0..n)
-            .map(|_| {
-                state = state
-                    .wrapping_mul(6364136223846793005)
-                    .wrapping_add(1442695040888963407);
-                let u = ((state >> 32) as f64) / ((1u64 << 31) as f64) - 1.0;
-                (u as f32) * amp
-            })
-            .collect()
-    }
-
     #[test]
     fn high_band_sigma_recovers_white_noise_std() {
         // On pure white noise the high-band periodogram mean estimates the noise
@@ -1047,8 +2592,12 @@ mod tests {
             0.0,
             SolveOptions {
                 noise_constrained: true,
+                ..Default::default()
             },
-        );
+            None,
+            None,
+        )
+        .unwrap();
 
         assert_eq!(constrained.s_counts.len(), n);
         let count: f32 = constrained.s_counts.iter().sum();
@@ -1063,4 +2612,584 @@ mod tests {
             constrained.pve
         );
     }
+
+    #[test]
+    fn saturation_mask_reduces_alpha_bias() {
+        // Isolated spikes plus a three-spike burst; the burst peak is clipped so
+        // exactly the top 5 frames sit at the sensor maximum (100.0). The clamped
+        // plateau is fit with too few spikes, inflating alpha; masking the
+        // saturated frames pulls alpha back toward the truth.
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 400;
+        let floor = 20.0_f32;
+        let clip = 100.0_f32;
+        let mut signal = vec![0.0_f32; n];
+        for &pos in &[30usize, 100, 101, 102, 200, 300] {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if pos + k < n {
+                    signal[pos + k] += kv;
+                }
+            }
+        }
+        let mut sorted = signal.clone();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        let alpha_true = (clip - floor) / sorted[4];
+        let trace: Vec<f32> = signal
+            .iter()
+            .map(|&v| (floor + alpha_true * v).min(clip))
+            .collect();
+
+        let plain = solve_trace(
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        let masked = solve_trace_opts(
+            &trace,
+            0.02,
+            0.4,
+            30.0,
+            1,
+            1000,
+            1e-4,
+            None,
+            false,
+            false,
+            0.0,
+            SolveOptions {
+                saturation_value: Some(clip),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(plain.n_saturated_frames, 0);
+        assert_eq!(masked.n_saturated_frames, 5);
+        assert!(
+            masked.alpha < plain.alpha,
+            "masking should lower alpha: masked {} vs plain {}",
+            masked.alpha,
+            plain.alpha
+        );
+        assert!(
+            (masked.alpha - alpha_true as f64).abs() < (plain.alpha - alpha_true as f64).abs(),
+            "masked alpha {} should be closer to the truth {} than plain {}",
+            masked.alpha,
+            alpha_true,
+            plain.alpha
+        );
+    }
+
+    #[test]
+    fn select_lambda_matches_universal_threshold_on_white_noise() {
+        let n = 3000;
+        let amp = 0.3_f32;
+        let trace: Vec<f32> = lcg_noise(n, amp, 0xFEED).iter().map(|&e| 5.0 + e).collect();
+        let sigma_true = (amp as f64) / 3.0_f64.sqrt();
+        let expected = sigma_true * (2.0 * (n as f64).ln()).sqrt();
+        let lambda = select_lambda(&trace, 0.02, 0.4, 30.0);
+        assert!(
+            (lambda - expected).abs() < 0.3 * expected,
+            "lambda {} should be near the universal threshold {}",
+            lambda,
+            expected
+        );
+    }
+
+    #[test]
+    fn select_lambda_edge_cases() {
+        // Constant trace: no measurable noise → default.
+        assert_eq!(
+            select_lambda(&[3.0; 500], 0.02, 0.4, 30.0),
+            DEFAULT_AUTO_LAMBDA
+        );
+        assert_eq!(select_lambda(&[], 0.02, 0.4, 30.0), DEFAULT_AUTO_LAMBDA);
+
+        // Short trace (< 3 kernel lengths) still yields a finite positive value.
+        let short = lcg_noise(40, 0.3, 7);
+        let lambda = select_lambda(&short, 0.02, 0.4, 30.0);
+        assert!(
+            lambda.is_finite() && lambda > 0.0,
+            "short-trace lambda {}",
+            lambda
+        );
+
+        // Pre-filtered: a moving-average-smoothed trace keeps a lambda on the
+        // order of its residual spread instead of collapsing with the
+        // first-difference spread.
+        let raw = lcg_noise(3000, 0.3, 11);
+        let smooth: Vec<f32> = (0..raw.len())
+            .map(|i| {
+                let lo = i.saturating_sub(4);
+                let hi = (i + 5).min(raw.len());
+                raw[lo..hi].iter().sum::<f32>() / (hi - lo) as f32
+            })
+            .collect();
+        let sigma_smooth = mad_sigma(&smooth);
+        let lambda = select_lambda(&smooth, 0.02, 0.4, 30.0);
+        let floor = 0.5 * sigma_smooth * (2.0 * 3000f64.ln()).sqrt();
+        assert!(
+            lambda > floor,
+            "pre-filtered lambda {} should not collapse below {}",
+            lambda,
+            floor
+        );
+    }
+
+    #[test]
+    fn photobleaching_correction_improves_fit() {
+        // Sparse spikes on a strongly bleaching floor (tau_bl = 1.5x duration).
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 1800;
+        let fs = 30.0;
+        let spikes = [150usize, 500, 820, 1200, 1550];
+        let mut trace = vec![0.0_f32; n];
+        for &pos in &spikes {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if pos + k < n {
+                    trace[pos + k] += 2.0 * kv;
+                }
+            }
+        }
+        let tau_bl = 1.5 * n as f64 / fs;
+        for (i, v) in trace.iter_mut().enumerate() {
+            *v += (5.0 * (-(i as f64) / fs / tau_bl).exp()) as f32 + 1.0;
+        }
+
+        let run = |photobleaching_correction| {
+            solve_trace_opts(
+                &trace,
+                0.02,
+                0.4,
+                fs,
+                1,
+                1000,
+                1e-4,
+                None,
+                false,
+                false,
+                0.0,
+                SolveOptions {
+                    photobleaching_correction,
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        let plain = run(false);
+        let corrected = run(true);
+        let count = |r: &InDecaResult| r.s_counts.iter().sum::<f32>();
+        assert_eq!(count(&corrected), spikes.len() as f32);
+        // The rolling baseline lags the decaying floor; removing the trend
+        // first leaves a flat floor and a near-perfect fit.
+        assert!(
+            corrected.pve > plain.pve && corrected.pve > 0.999,
+            "pve plain {} corrected {}",
+            plain.pve,
+            corrected.pve
+        );
+        assert!((corrected.alpha / 2.0 - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn variance_stabilized_solve_on_shot_noise() {
+        // Photon counts: 20 background + 60 per unit of kernel, with Gaussian
+        // noise of Poisson variance (sqrt of the mean).
+        let (n, fs) = (600, 30.0);
+        let spikes = [60, 200, 330, 470];
+        let clean = make_trace(0.05, 0.5, fs, n, &spikes);
+        let mut state: u64 = 17;
+        let photons: Vec<f32> = clean
+            .iter()
+            .map(|&c| {
+                let mean = 20.0 + 60.0 * c as f64;
+                (mean + mean.sqrt() * lcg_gaussian(&mut state)) as f32
+            })
+            .collect();
+
+        let result = solve_trace_opts(
+            &photons,
+            0.05,
+            0.5,
+            fs,
+            1,
+            1000,
+            1e-4,
+            None,
+            false,
+            false,
+            0.0,
+            SolveOptions {
+                variance_stabilize: true,
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .unwrap();
+        for &s in &spikes {
+            assert!(
+                result.s_counts[s - 1..=s + 1].iter().sum::<f32>() >= 1.0,
+                "missed spike at {s}"
+            );
+        }
+        // Alpha is in Anscombe units: the peak of 2 * sqrt(x + 3/8) above
+        // background, i.e. well below the 60-photon amplitude.
+        let expected = 2.0 * ((80.375_f64).sqrt() - (20.375_f64).sqrt());
+        assert!(
+            (result.alpha - expected).abs() < 0.3 * expected,
+            "alpha {} vs {expected}",
+            result.alpha
+        );
+    }
+
+    #[test]
+    fn bootstrap_ci_brackets_clear_spikes() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 600;
+        let spikes = [100usize, 300, 450];
+        let noise = lcg_noise(n, 0.05, 0xC1);
+        let mut trace = noise.clone();
+        for &pos in &spikes {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if pos + k < n {
+                    trace[pos + k] += 2.0 * kv;
+                }
+            }
+        }
+
+        let (lower, upper) = bootstrap_spike_ci(&trace, 0.02, 0.4, 30.0, 1, 20, 0.1, 42).unwrap();
+        assert_eq!(lower.len(), n);
+        assert_eq!(upper.len(), n);
+        assert!(lower.iter().zip(&upper).all(|(l, u)| l <= u));
+
+        // Each clear spike is detected within ±1 frame in the upper band.
+        for &pos in &spikes {
+            let hit: f32 = upper[pos - 1..=pos + 1].iter().sum();
+            assert!(hit >= 1.0, "spike at {pos} missing from upper CI");
+        }
+        // Deterministic for a fixed seed.
+        let again = bootstrap_spike_ci(&trace, 0.02, 0.4, 30.0, 1, 20, 0.1, 42).unwrap();
+        assert_eq!(again.0, lower);
+        assert_eq!(again.1, upper);
+
+        // No replicates: both bounds equal the point estimate.
+        let (l0, u0) = bootstrap_spike_ci(&trace, 0.02, 0.4, 30.0, 1, 0, 0.1, 42).unwrap();
+        assert_eq!(l0, u0);
+    }
+
+    #[test]
+    fn noise_std_matches_injected_noise() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = 900;
+        // Uniform noise on [-a, a] has std a/sqrt(3).
+        let amp = 0.3_f32;
+        let mut trace = lcg_noise(n, amp, 0xABCD);
+        for &pos in &[60usize, 250, 480, 700] {
+            for (k, &kv) in kernel.iter().enumerate() {
+                if pos + k < n {
+                    trace[pos + k] += 4.0 * kv;
+                }
+            }
+        }
+        let result = solve_trace(
+            &trace, 0.02, 0.4, 30.0, 1, 1000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        let expected = amp as f64 / 3.0_f64.sqrt();
+        // Four amplitude-4 transients over 900 noisy frames: about 12 dB.
+        assert!(result.snr_db > 6.0, "snr_db {}", result.snr_db);
+        assert!(
+            (result.noise_std / expected - 1.0).abs() < 0.25,
+            "noise_std {} vs injected {expected}",
+            result.noise_std
+        );
+    }
+
+    #[test]
+    fn inactive_residual_std_falls_back_to_all_frames() {
+        let residual = [1.0_f32, -1.0, 1.0, -1.0];
+        // Every frame active → full-trace std.
+        assert!((inactive_residual_std(&residual, &[1.0; 4], 0.5) - 1.0).abs() < 1e-9);
+        // Only the two zero-count frames are used.
+        let s = inactive_residual_std(&[3.0, 5.0, 0.0, 0.0], &[0.0, 0.0, 1.0, 1.0], 0.5);
+        assert!((s - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn multiscale_warm_start_cuts_fista_iterations() {
+        use std::sync::{Arc, Mutex};
+
+        // Long flat trace with rare spikes and a little noise.
+        let (n, fs) = (3000, 30.0);
+        let spikes = [200usize, 900, 1700, 2400, 2750];
+        let mut trace = make_trace(0.1, 0.6, fs, n, &spikes);
+        let mut state: u64 = 7;
+        for v in trace.iter_mut() {
+            *v += 0.005 * lcg_gaussian(&mut state) as f32;
+        }
+
+        // Total FISTA iterations over every scale iteration (and every level).
+        let counter = || {
+            let total = Arc::new(Mutex::new(0_u32));
+            let sink = Arc::clone(&total);
+            let on_progress: ProgressFn = Box::new(move |e| {
+                if e.stage == Stage::ScaleIteration {
+                    *sink.lock().unwrap() += e.fista_iter;
+                }
+            });
+            (total, on_progress)
+        };
+
+        let (cold_total, on_progress) = counter();
+        solve_trace(
+            &trace,
+            0.1,
+            0.6,
+            fs,
+            1,
+            2000,
+            1e-4,
+            None,
+            false,
+            false,
+            0.0,
+            Some(on_progress),
+            None,
+        )
+        .unwrap();
+        let (multi_total, on_progress) = counter();
+        let result = solve_trace_multiscale(
+            &trace,
+            0.1,
+            0.6,
+            fs,
+            2000,
+            1e-4,
+            false,
+            false,
+            0.0,
+            &[8, 4, 2, 1],
+            Some(on_progress),
+            None,
+        )
+        .unwrap();
+
+        let cold = *cold_total.lock().unwrap();
+        let multi = *multi_total.lock().unwrap();
+        assert!(
+            multi * 10 <= cold * 6,
+            "multiscale took {multi} FISTA iterations vs {cold} cold"
+        );
+        assert_eq!(result.s_counts.len(), n);
+        for &s in &spikes {
+            assert!(
+                result.s_counts[s.saturating_sub(1)..=s + 1]
+                    .iter()
+                    .sum::<f32>()
+                    >= 1.0,
+                "missed spike at {s}"
+            );
+        }
+    }
+
+    #[test]
+    fn segmented_solve_stitches_full_length() {
+        let (n, fs) = (1200, 30.0);
+        let spikes = [100usize, 290, 310, 560, 820, 1100];
+        let mut trace = make_trace(0.1, 0.6, fs, n, &spikes);
+        let mut state: u64 = 11;
+        for v in trace.iter_mut() {
+            *v = 3.0 * *v + 0.5 + 0.01 * lcg_gaussian(&mut state) as f32;
+        }
+
+        // Starts at 0, 250, 500, 750, 1000: spikes 290/310 sit in an overlap.
+        let result = solve_trace_segmented(
+            &trace, 300, 50, 0.1, 0.6, fs, 1, 2000, 1e-4, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        assert_eq!(result.s_counts.len(), n);
+        for &s in &spikes {
+            let near: f32 = result.s_counts[s - 1..=s + 1].iter().sum();
+            assert!((near - 1.0).abs() < 0.35, "spike at {s}: {near}");
+        }
+        let total: f32 = result.s_counts.iter().sum();
+        assert!((total - spikes.len() as f32).abs() < 1.0, "total {total}");
+        assert!((result.alpha - 3.0).abs() < 0.5, "alpha {}", result.alpha);
+        let whole = solve_trace(
+            &trace, 0.1, 0.6, fs, 1, 2000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        assert!((result.baseline - whole.baseline).abs() < 0.05);
+
+        // A trace that fits in one segment is a plain solve.
+        let short = &trace[..250];
+        let whole = solve_trace(
+            short, 0.1, 0.6, fs, 1, 2000, 1e-4, None, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        let seg = solve_trace_segmented(
+            short, 300, 50, 0.1, 0.6, fs, 1, 2000, 1e-4, false, false, 0.0, None, None,
+        )
+        .unwrap();
+        assert_eq!(seg.s_counts, whole.s_counts);
+        assert_eq!(seg.alpha, whole.alpha);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip_is_bit_exact() {
+        let result = InDecaResult {
+            s_counts: vec![0.0, 1.0, 2.0, f32::MIN_POSITIVE, 0.1],
+            filtered_trace: Some(vec![0.25, -1.5e-7, 3.3]),
+            alpha: 1.75,
+            baseline: -0.01,
+            threshold: 0.4,
+            pve: 0.93,
+            iterations: 412,
+            converged: true,
+            n_saturated_frames: 3,
+            noise_std: 0.05,
+            snr_db: 21.5,
+        };
+        let json = result.to_json().unwrap();
+        assert!(json.contains("\"iterations\":412"), "{json}");
+
+        let back = InDecaResult::from_json(&json).unwrap();
+        assert_eq!(back.s_counts, result.s_counts);
+        assert_eq!(back.filtered_trace, result.filtered_trace);
+        assert_eq!(
+            (back.alpha, back.baseline, back.threshold, back.pve),
+            (result.alpha, result.baseline, result.threshold, result.pve)
+        );
+        assert_eq!((back.iterations, back.converged), (412, true));
+        assert_eq!((back.n_saturated_frames, back.noise_std), (3, 0.05));
+        assert_eq!(back.snr_db, 21.5);
+        let perfect = InDecaResult {
+            snr_db: f64::INFINITY,
+            ..InDecaResult::from_json(&json).unwrap()
+        };
+        assert!(InDecaResult::from_json(&perfect.to_json().unwrap())
+            .unwrap()
+            .snr_db
+            .is_nan());
+
+        let no_trace = InDecaResult {
+            filtered_trace: None,
+            ..result
+        };
+        let back = InDecaResult::from_json(&no_trace.to_json().unwrap()).unwrap();
+        assert!(back.filtered_trace.is_none());
+
+        // "AAAA" decodes to three bytes, which cannot be an f32 array.
+        let encoded = encode_f32_base64(&no_trace.s_counts);
+        assert!(InDecaResult::from_json(&json.replacen(&encoded, "AAAAAA==", 1)).is_ok());
+        assert!(InDecaResult::from_json(&json.replacen(&encoded, "AAAA", 1)).is_err());
+        assert!(InDecaResult::from_json(&json.replacen(&encoded, "not base64!", 1)).is_err());
+    }
+
+    /// Unstable taus come back as `NumericalFailure` from every entry point
+    /// instead of panicking, including with the bandpass filter enabled.
+    #[test]
+    fn unstable_taus_are_an_error_not_a_panic() {
+        let trace = vec![1.0_f32; 200];
+        for (tau_r, tau_d, fs) in [(0.02, -0.4, 30.0), (0.02, 0.4, f64::NAN)] {
+            for hp in [false, true] {
+                let err = solve_trace(
+                    &trace, tau_r, tau_d, fs, 1, 50, 1e-4, None, hp, false, 0.0, None, None,
+                )
+                .err()
+                .expect("unstable taus must be rejected");
+                assert!(matches!(err, CalabError::NumericalFailure(_)), "{err}");
+            }
+        }
+        assert!(solve_trace_multiscale(
+            &trace,
+            0.02,
+            -0.4,
+            30.0,
+            50,
+            1e-4,
+            false,
+            false,
+            0.0,
+            &[2, 1],
+            None,
+            None
+        )
+        .is_err());
+        assert!(solve_trace_segmented(
+            &trace, 100, 20, 0.02, -0.4, 30.0, 1, 50, 1e-4, false, false, 0.0, None, None
+        )
+        .is_err());
+        assert!(bootstrap_spike_ci(&trace, 0.02, -0.4, 30.0, 1, 2, 0.1, 7).is_err());
+    }
+
+    #[test]
+    fn multiscale_rejects_bad_scale_lists() {
+        let trace = vec![1.0_f32; 200];
+        let bad: [&[usize]; 5] = [&[], &[4, 0], &[2, 4, 1], &[4, 4, 1], &[6, 4, 1]];
+        for scales in bad {
+            let err = solve_trace_multiscale(
+                &trace, 0.02, 0.4, 30.0, 50, 1e-4, false, false, 0.0, scales, None, None,
+            )
+            .err()
+            .expect("bad scale list must be rejected");
+            assert!(
+                matches!(&err, CalabError::InvalidParameter { name, .. } if name == "downsample_scales"),
+                "{scales:?}: {err}"
+            );
+        }
+        assert!(solve_trace_multiscale(
+            &trace,
+            0.02,
+            0.4,
+            30.0,
+            50,
+            1e-4,
+            false,
+            false,
+            0.0,
+            &[1],
+            None,
+            None,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn segmented_rejects_bad_segment_geometry() {
+        let trace = vec![1.0_f32; 200];
+        for (segment_len, overlap, field) in [
+            (0, 0, "segment_len"),
+            (50, 50, "overlap"),
+            (50, 80, "overlap"),
+        ] {
+            let err = solve_trace_segmented(
+                &trace,
+                segment_len,
+                overlap,
+                0.02,
+                0.4,
+                30.0,
+                1,
+                50,
+                1e-4,
+                false,
+                false,
+                0.0,
+                None,
+                None,
+            )
+            .err()
+            .expect("bad segment geometry must be rejected");
+            assert!(
+                matches!(&err, CalabError::InvalidParameter { name, .. } if name == field),
+                "({segment_len}, {overlap}): {err}"
+            );
+        }
+    }
 }
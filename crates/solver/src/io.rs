@@ -0,0 +1,746 @@
+//! Trace file readers: minimal `.npy` and column-per-cell CSV.
+//!
+//! The `.npy` reader parses the NumPy format directly (magic string, version,
+//! header dict, raw data) so traces can be loaded without a Python round trip.
+//! It supports format versions 1–3, `<f4`/`>f4`/`<f8`/`>f8` dtypes (float64
+//! is converted to f32 on load) and Fortran-order arrays, which are
+//! transposed to C order. Anything else is a `IoError::Format`.
+//!
+//! The CSV reader streams the file line by line, one column per cell.
+//!
+//! `read_suite2p_f` loads a Suite2p `plane*/` output directory (`F.npy`,
+//! `Fneu.npy`, `iscell.npy`) with the same `.npy` parser.
+//! `neuropil_correct` and `estimate_neuropil_coeff` handle the neuropil
+//! subtraction for a single ROI.
+
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::CalabError;
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Error from reading or parsing a trace file.
+#[derive(Debug, thiserror::Error)]
+pub enum IoError {
+    /// The file could not be read.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The contents are malformed or unsupported (not a float `.npy` array,
+    /// ragged CSV rows, ...).
+    #[error("invalid trace file: {0}")]
+    Format(String),
+    /// The file parsed but its contents cannot be applied (a solver
+    /// checkpoint whose kernel does not match its parameters, ...).
+    #[error("{0}")]
+    Solver(#[from] CalabError),
+}
+
+pub(crate) fn format_err(msg: impl Into<String>) -> IoError {
+    IoError::Format(msg.into())
+}
+
+/// Read a `.npy` file as f32. Returns the data in C (row-major) order and
+/// the array shape (empty for a 0-d scalar).
+pub fn read_npy_f32(path: &Path) -> Result<(Vec<f32>, Vec<usize>), IoError> {
+    let bytes = std::fs::read(path)?;
+    parse_npy_f32(&bytes)
+}
+
+/// Read a 2-D `.npy` file as `(flat_data, n_rows, n_cols)` in row-major
+/// order. A 1-D array is read as a single row.
+pub fn read_npy_f32_2d(path: &Path) -> Result<(Vec<f32>, usize, usize), IoError> {
+    let (data, shape) = read_npy_f32(path)?;
+    match shape[..] {
+        [n] => Ok((data, 1, n)),
+        [rows, cols] => Ok((data, rows, cols)),
+        _ => Err(format_err(format!(
+            "expected a 1-D or 2-D array, got shape {shape:?}"
+        ))),
+    }
+}
+
+/// Parse in-memory `.npy` bytes (see `read_npy_f32`).
+pub fn parse_npy_f32(bytes: &[u8]) -> Result<(Vec<f32>, Vec<usize>), IoError> {
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(format_err("missing \\x93NUMPY magic string"));
+    }
+    let major = bytes[6];
+    let (header_len, header_start) = match major {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 => {
+            if bytes.len() < 12 {
+                return Err(format_err("truncated header length"));
+            }
+            let len = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+            (len as usize, 12)
+        }
+        _ => return Err(format_err(format!("unsupported format version {major}"))),
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start {
+        return Err(format_err("truncated header"));
+    }
+    let header = std::str::from_utf8(&bytes[header_start..data_start])
+        .map_err(|_| format_err("header is not valid text"))?;
+
+    let descr = header_str_value(header, "descr")?;
+    let fortran_order = match header_raw_value(header, "fortran_order")? {
+        v if v.starts_with("True") => true,
+        v if v.starts_with("False") => false,
+        _ => return Err(format_err("fortran_order must be True or False")),
+    };
+    let shape = parse_shape(header)?;
+
+    let (big_endian, width) = match descr {
+        "<f4" | "|f4" | "=f4" => (false, 4),
+        ">f4" => (true, 4),
+        "<f8" | "=f8" => (false, 8),
+        ">f8" => (true, 8),
+        _ => {
+            return Err(format_err(format!(
+                "unsupported dtype '{descr}' (expected float32 or float64)"
+            )))
+        }
+    };
+
+    let n_bytes = shape
+        .iter()
+        .try_fold(1usize, |a, &d| a.checked_mul(d))
+        .and_then(|count| count.checked_mul(width))
+        .ok_or_else(|| format_err(format!("shape {shape:?} overflows the address space")))?;
+    let raw = &bytes[data_start..];
+    if raw.len() < n_bytes {
+        return Err(format_err(format!(
+            "expected {n_bytes} data bytes for shape {shape:?}, found {}",
+            raw.len()
+        )));
+    }
+    let data: Vec<f32> = raw[..n_bytes]
+        .chunks_exact(width)
+        .map(|c| match (width, big_endian) {
+            (4, false) => f32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+            (4, true) => f32::from_be_bytes([c[0], c[1], c[2], c[3]]),
+            (_, false) => f64::from_le_bytes(c.try_into().unwrap()) as f32,
+            (_, true) => f64::from_be_bytes(c.try_into().unwrap()) as f32,
+        })
+        .collect();
+
+    let data = if fortran_order && shape.len() > 1 {
+        fortran_to_c_order(&data, &shape)
+    } else {
+        data
+    };
+    Ok((data, shape))
+}
+
+/// Text following `'key':` in the header dict, leading whitespace trimmed.
+fn header_raw_value<'a>(header: &'a str, key: &str) -> Result<&'a str, IoError> {
+    let pattern = format!("'{key}':");
+    let pos = header
+        .find(&pattern)
+        .ok_or_else(|| format_err(format!("header has no '{key}' entry")))?;
+    Ok(header[pos + pattern.len()..].trim_start())
+}
+
+/// Quoted string value of `key` in the header dict.
+fn header_str_value<'a>(header: &'a str, key: &str) -> Result<&'a str, IoError> {
+    let rest = header_raw_value(header, key)?;
+    let quote = rest
+        .chars()
+        .next()
+        .filter(|&c| c == '\'' || c == '"')
+        .ok_or_else(|| format_err(format!("'{key}' is not a string")))?;
+    let end = rest[1..]
+        .find(quote)
+        .ok_or_else(|| format_err(format!("unterminated '{key}' string")))?;
+    Ok(&rest[1..1 + end])
+}
+
+/// Parse the `'shape': (a, b, ...)` tuple.
+fn parse_shape(header: &str) -> Result<Vec<usize>, IoError> {
+    let rest = header_raw_value(header, "shape")?;
+    let inner = rest
+        .strip_prefix('(')
+        .and_then(|r| r.find(')').map(|end| &r[..end]))
+        .ok_or_else(|| format_err("'shape' is not a tuple"))?;
+    inner
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.trim_end_matches('L')
+                .parse::<usize>()
+                .map_err(|_| format_err(format!("bad shape dimension '{s}'")))
+        })
+        .collect()
+}
+
+/// Reorder column-major `data` of the given shape into row-major order.
+fn fortran_to_c_order(data: &[f32], shape: &[usize]) -> Vec<f32> {
+    let ndim = shape.len();
+    let mut f_strides = vec![1usize; ndim];
+    for k in 1..ndim {
+        f_strides[k] = f_strides[k - 1] * shape[k - 1];
+    }
+    let mut out = Vec::with_capacity(data.len());
+    let mut idx = vec![0usize; ndim];
+    for _ in 0..data.len() {
+        let offset: usize = idx.iter().zip(&f_strides).map(|(i, s)| i * s).sum();
+        out.push(data[offset]);
+        // Advance the C-order multi-index (last axis fastest).
+        for k in (0..ndim).rev() {
+            idx[k] += 1;
+            if idx[k] < shape[k] {
+                break;
+            }
+            idx[k] = 0;
+        }
+    }
+    out
+}
+
+/// Traces read from a CSV file, cell-major like the batch solvers expect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvTraces {
+    /// Flattened `(n_cells, n_timepoints)` array, row-major; missing values are NaN.
+    pub data: Vec<f32>,
+    pub n_cells: usize,
+    pub n_timepoints: usize,
+    /// Header names of the numeric columns, or `cell_0`, `cell_1`, ... without a header.
+    pub cell_names: Vec<String>,
+}
+
+/// Field values treated as missing (NaN) rather than non-numeric.
+const CSV_MISSING: [&str; 5] = ["", "nan", "na", "n/a", "null"];
+
+fn parse_csv_value(field: &str) -> Option<f32> {
+    let field = field.trim().trim_matches('"').trim();
+    if CSV_MISSING.iter().any(|m| field.eq_ignore_ascii_case(m)) {
+        return Some(f32::NAN);
+    }
+    field.parse::<f32>().ok()
+}
+
+/// Read a CSV file with one column per cell and one row per frame.
+///
+/// The file is streamed line by line. Empty fields and `NaN`/`NA`/`null`
+/// become NaN. Columns whose first data value is non-numeric (a frame label
+/// or timestamp string, say) are dropped along with their header names; a
+/// non-numeric value later in a kept column, or a row with a different
+/// number of fields than the first, is an `IoError::Format` naming the line.
+/// Quoted fields are unquoted but may not contain the delimiter.
+pub fn read_csv_traces(path: &Path, delimiter: u8, has_header: bool) -> Result<CsvTraces, IoError> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let delimiter = delimiter as char;
+
+    let mut header: Option<Vec<String>> = None;
+    let mut n_fields = 0;
+    let mut keep: Vec<usize> = Vec::new();
+    let mut columns: Vec<Vec<f32>> = Vec::new();
+
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_no = line_idx + 1;
+        let line = line.trim_start_matches('\u{feff}').trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(delimiter).collect();
+
+        if has_header && header.is_none() {
+            n_fields = fields.len();
+            header = Some(
+                fields
+                    .iter()
+                    .map(|f| f.trim().trim_matches('"').to_string())
+                    .collect(),
+            );
+            continue;
+        }
+
+        if columns.is_empty() && keep.is_empty() {
+            // First data row: fixes the field count and which columns are numeric.
+            if header.is_none() {
+                n_fields = fields.len();
+            } else if fields.len() != n_fields {
+                return Err(format_err(format!(
+                    "line {line_no} has {} fields, header has {n_fields}",
+                    fields.len()
+                )));
+            }
+            keep = (0..n_fields)
+                .filter(|&c| parse_csv_value(fields[c]).is_some())
+                .collect();
+            if keep.is_empty() {
+                return Err(format_err(format!("line {line_no} has no numeric columns")));
+            }
+            columns = vec![Vec::new(); keep.len()];
+        } else if fields.len() != n_fields {
+            return Err(format_err(format!(
+                "line {line_no} has {} fields, expected {n_fields}",
+                fields.len()
+            )));
+        }
+
+        for (col, &c) in columns.iter_mut().zip(&keep) {
+            let value = parse_csv_value(fields[c]).ok_or_else(|| {
+                format_err(format!(
+                    "line {line_no}, column {}: non-numeric value '{}'",
+                    c + 1,
+                    fields[c].trim()
+                ))
+            })?;
+            col.push(value);
+        }
+    }
+
+    let n_cells = columns.len();
+    let n_timepoints = columns.first().map_or(0, Vec::len);
+    let cell_names = match header {
+        Some(names) if !keep.is_empty() => keep.iter().map(|&c| names[c].clone()).collect(),
+        _ => (0..n_cells).map(|i| format!("cell_{i}")).collect(),
+    };
+    Ok(CsvTraces {
+        data: columns.concat(),
+        n_cells,
+        n_timepoints,
+        cell_names,
+    })
+}
+
+/// Guess a CSV delimiter from the first line: tab if it has more tabs than
+/// commas, otherwise comma (also when the file cannot be read).
+pub fn detect_delimiter(path: &Path) -> u8 {
+    let Ok(file) = std::fs::File::open(path) else {
+        return b',';
+    };
+    let mut first = String::new();
+    if std::io::BufReader::new(file).read_line(&mut first).is_err() {
+        return b',';
+    }
+    let tabs = first.matches('\t').count();
+    let commas = first.matches(',').count();
+    if tabs > commas {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/// Fluorescence traces from a Suite2p output directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suite2pData {
+    /// Raw ROI fluorescence, one row per ROI (`F.npy`).
+    pub f: Vec<Vec<f32>>,
+    /// Neuropil fluorescence, same shape as `f` (`Fneu.npy`); empty when the
+    /// file is missing.
+    pub f_neuropil: Vec<Vec<f32>>,
+    /// Suite2p's cell classification (first column of `iscell.npy`); all
+    /// true when the file is missing.
+    pub iscell: Vec<bool>,
+    /// Frame rate from `ops.npy`, NaN when it cannot be found.
+    pub fs: f64,
+}
+
+/// Read the Suite2p traces next to `stat_path` (a plane directory's
+/// `stat.npy`).
+///
+/// `F.npy` is required. A missing `Fneu.npy` leaves `f_neuropil` empty (so
+/// `suite2p_correct_neuropil` returns `F` unchanged) and a missing
+/// `iscell.npy` marks every ROI as a cell. `Fneu.npy` or `iscell.npy` whose
+/// ROI count (or frame count) disagrees with `F.npy`, or a 2-D
+/// `iscell.npy` with no columns, is an `IoError::Format`.
+///
+/// `ops.npy` is a pickled dict that this reader does not unpickle; `fs` is
+/// recovered by scanning it for the pickled `'fs'` float and is NaN if the
+/// file is missing or stores the rate some other way.
+pub fn read_suite2p_f(stat_path: &Path) -> Result<Suite2pData, IoError> {
+    let dir = stat_path.parent().unwrap_or(Path::new("."));
+    let f = read_npy_rows(&dir.join("F.npy"))?;
+    let n_frames = f.first().map_or(0, Vec::len);
+
+    let fneu_path = dir.join("Fneu.npy");
+    let f_neuropil = if fneu_path.exists() {
+        let fneu = read_npy_rows(&fneu_path)?;
+        if fneu.len() != f.len() || fneu.first().map_or(0, Vec::len) != n_frames {
+            return Err(format_err(format!(
+                "Fneu.npy is {}x{}, F.npy is {}x{n_frames}",
+                fneu.len(),
+                fneu.first().map_or(0, Vec::len),
+                f.len()
+            )));
+        }
+        fneu
+    } else {
+        Vec::new()
+    };
+
+    let iscell_path = dir.join("iscell.npy");
+    let iscell = if iscell_path.exists() {
+        let (data, rows, cols) = read_npy_f32_2d(&iscell_path)?;
+        // A 1-D file reads as one row of flags; (n, 2) has a probability column.
+        let flags: Vec<bool> = if rows == 1 {
+            data.iter().map(|&v| v > 0.5).collect()
+        } else if cols == 0 {
+            return Err(format_err("iscell.npy has no columns"));
+        } else {
+            data.chunks_exact(cols).map(|r| r[0] > 0.5).collect()
+        };
+        if flags.len() != f.len() {
+            return Err(format_err(format!(
+                "iscell.npy has {} ROIs, F.npy has {}",
+                flags.len(),
+                f.len()
+            )));
+        }
+        flags
+    } else {
+        vec![true; f.len()]
+    };
+
+    let fs = std::fs::read(dir.join("ops.npy"))
+        .ok()
+        .and_then(|bytes| pickled_float(&bytes, "fs"))
+        .unwrap_or(f64::NAN);
+
+    Ok(Suite2pData {
+        f,
+        f_neuropil,
+        iscell,
+        fs,
+    })
+}
+
+/// Neuropil-corrected traces `F - coeff * Fneu` (Suite2p's default `coeff`
+/// is 0.7). Returns `F` unchanged when no neuropil traces were loaded.
+pub fn suite2p_correct_neuropil(data: &Suite2pData, coeff: f64) -> Vec<Vec<f32>> {
+    if data.f_neuropil.is_empty() {
+        return data.f.clone();
+    }
+    data.f
+        .iter()
+        .zip(&data.f_neuropil)
+        .map(|(f, fneu)| neuropil_correct(f, fneu, coeff))
+        .collect()
+}
+
+/// One neuropil-corrected trace, `F - coeff * Fneu`. Samples past the end of
+/// the shorter input are dropped.
+pub fn neuropil_correct(f: &[f32], f_neu: &[f32], coeff: f64) -> Vec<f32> {
+    let coeff = coeff as f32;
+    f.iter().zip(f_neu).map(|(&a, &b)| a - coeff * b).collect()
+}
+
+/// Fraction of frames, lowest rolling std of `F` first, that
+/// `estimate_neuropil_coeff` treats as free of cell activity.
+const QUIET_FRAME_FRACTION: f64 = 0.2;
+
+/// Estimate the neuropil coefficient for one ROI: the `coeff` in `[0, 1]`
+/// minimising the variance of `F - coeff * Fneu` over its quietest frames.
+///
+/// Quiet frames are the 20% with the lowest `window`-sample rolling std of
+/// `F` (see [`crate::baseline::rolling_std`]), where the fluorescence should
+/// be mostly neuropil. The variance is quadratic in `coeff`, so the minimiser
+/// is `cov(F, Fneu) / var(Fneu)` over those frames, clamped to `[0, 1]`.
+/// Returns 0.0 when there are fewer than two frames or `Fneu` is constant on
+/// the quiet frames (nothing to subtract). As in `neuropil_correct`, only the
+/// overlapping length of the two inputs is used.
+pub fn estimate_neuropil_coeff(f: &[f32], f_neu: &[f32], window: usize) -> f64 {
+    let n = f.len().min(f_neu.len());
+    if n < 2 {
+        return 0.0;
+    }
+    let (f, f_neu) = (&f[..n], &f_neu[..n]);
+    let activity = crate::baseline::rolling_std(f, window);
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| activity[a].total_cmp(&activity[b]));
+    let n_quiet = ((n as f64 * QUIET_FRAME_FRACTION).ceil() as usize).clamp(2, n);
+    let quiet = &order[..n_quiet];
+
+    let count = n_quiet as f64;
+    let mean_f = quiet.iter().map(|&t| f[t] as f64).sum::<f64>() / count;
+    let mean_neu = quiet.iter().map(|&t| f_neu[t] as f64).sum::<f64>() / count;
+    let (mut cov, mut var_neu) = (0.0_f64, 0.0_f64);
+    for &t in quiet {
+        let dn = f_neu[t] as f64 - mean_neu;
+        cov += (f[t] as f64 - mean_f) * dn;
+        var_neu += dn * dn;
+    }
+    if var_neu <= f64::EPSILON * count * mean_neu.abs().max(1.0).powi(2) {
+        return 0.0;
+    }
+    (cov / var_neu).clamp(0.0, 1.0)
+}
+
+/// Read a 1-D or 2-D `.npy` file as a vector of rows.
+fn read_npy_rows(path: &Path) -> Result<Vec<Vec<f32>>, IoError> {
+    let (data, rows, cols) = read_npy_f32_2d(path)?;
+    if cols == 0 {
+        return Ok(vec![Vec::new(); rows]);
+    }
+    Ok(data.chunks_exact(cols).map(<[f32]>::to_vec).collect())
+}
+
+/// Value of a float stored under `key` in a pickled dict: the key as a
+/// SHORT_BINUNICODE string, an optional MEMOIZE, then a BINFLOAT
+/// (big-endian f64).
+fn pickled_float(bytes: &[u8], key: &str) -> Option<f64> {
+    const SHORT_BINUNICODE: u8 = 0x8c;
+    const MEMOIZE: u8 = 0x94;
+    const BINFLOAT: u8 = b'G';
+
+    let mut pattern = vec![SHORT_BINUNICODE, key.len() as u8];
+    pattern.extend_from_slice(key.as_bytes());
+    let pos = bytes.windows(pattern.len()).position(|w| w == pattern)?;
+    let mut rest = &bytes[pos + pattern.len()..];
+    if rest.first() == Some(&MEMOIZE) {
+        rest = &rest[1..];
+    }
+    match rest {
+        [BINFLOAT, v @ ..] if v.len() >= 8 => Some(f64::from_be_bytes(v[..8].try_into().ok()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a version-1 `.npy` byte buffer with a 64-byte aligned header.
+    fn npy_bytes(descr: &str, fortran: bool, shape: &str, payload: &[u8]) -> Vec<u8> {
+        let fortran = if fortran { "True" } else { "False" };
+        let mut header =
+            format!("{{'descr': '{descr}', 'fortran_order': {fortran}, 'shape': {shape}, }}");
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut out = NPY_MAGIC.to_vec();
+        out.extend_from_slice(&[1, 0]);
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn parses_little_and_big_endian_float32() {
+        let values = [1.5_f32, -2.0, 3.25];
+        let le: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let be: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+
+        let (data, shape) = parse_npy_f32(&npy_bytes("<f4", false, "(3,)", &le)).unwrap();
+        assert_eq!((data.as_slice(), shape), (&values[..], vec![3]));
+        let (data, _) = parse_npy_f32(&npy_bytes(">f4", false, "(3,)", &be)).unwrap();
+        assert_eq!(data, values);
+    }
+
+    #[test]
+    fn converts_float64_and_transposes_fortran_order() {
+        // 2x3 array [[0, 1, 2], [3, 4, 5]] stored column-major as f64.
+        let col_major = [0.0_f64, 3.0, 1.0, 4.0, 2.0, 5.0];
+        let payload: Vec<u8> = col_major.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let (data, shape) = parse_npy_f32(&npy_bytes("<f8", true, "(2, 3)", &payload)).unwrap();
+        assert_eq!(shape, vec![2, 3]);
+        assert_eq!(data, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn reads_2d_file_from_disk() {
+        let values: Vec<f32> = (0..8).map(|i| i as f32 * 0.5).collect();
+        let payload: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let path = std::env::temp_dir().join(format!("calab_io_test_{}.npy", std::process::id()));
+        std::fs::write(&path, npy_bytes("<f4", false, "(2, 4)", &payload)).unwrap();
+        let result = read_npy_f32_2d(&path);
+        std::fs::remove_file(&path).ok();
+        let (data, rows, cols) = result.unwrap();
+        assert_eq!((rows, cols), (2, 4));
+        assert_eq!(data, values);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let payload = [0u8; 8];
+        assert!(matches!(
+            parse_npy_f32(b"not a numpy file"),
+            Err(IoError::Format(_))
+        ));
+        assert!(parse_npy_f32(&npy_bytes("<i4", false, "(2,)", &payload)).is_err());
+        // Shape says 4 floats but only 2 are present.
+        assert!(parse_npy_f32(&npy_bytes("<f4", false, "(4,)", &payload)).is_err());
+        // Element count (and byte count) past usize::MAX.
+        for shape in ["(4294967296, 4294967296)", "(4611686018427387904,)"] {
+            assert!(matches!(
+                parse_npy_f32(&npy_bytes("<f4", false, shape, &payload)),
+                Err(IoError::Format(_))
+            ));
+        }
+        assert!(matches!(
+            read_npy_f32(Path::new("/nonexistent/calab.npy")),
+            Err(IoError::Io(_))
+        ));
+    }
+
+    fn write_temp(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("calab_io_{}_{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn csv_reads_columns_as_cells_with_missing_values() {
+        let path = write_temp(
+            "cells.csv",
+            "frame,cell A,cell B\nf0,1.0,2.0\nf1,,4.5\nf2,3.0,NaN\n",
+        );
+        assert_eq!(detect_delimiter(&path), b',');
+        let result = read_csv_traces(&path, b',', true);
+        std::fs::remove_file(&path).ok();
+        let traces = result.unwrap();
+
+        // The non-numeric "frame" column is dropped.
+        assert_eq!(traces.cell_names, vec!["cell A", "cell B"]);
+        assert_eq!((traces.n_cells, traces.n_timepoints), (2, 3));
+        assert_eq!(traces.data[0], 1.0);
+        assert!(traces.data[1].is_nan());
+        assert_eq!(traces.data[2], 3.0);
+        assert_eq!(traces.data[3..5], [2.0, 4.5]);
+        assert!(traces.data[5].is_nan());
+    }
+
+    #[test]
+    fn csv_without_header_and_tab_delimited() {
+        let path = write_temp("cells.tsv", "1\t2\t3\n4\t5\t6\n");
+        assert_eq!(detect_delimiter(&path), b'\t');
+        let result = read_csv_traces(&path, b'\t', false);
+        std::fs::remove_file(&path).ok();
+        let traces = result.unwrap();
+        assert_eq!(traces.cell_names, vec!["cell_0", "cell_1", "cell_2"]);
+        assert_eq!(traces.data, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn csv_rejects_ragged_rows() {
+        let path = write_temp("ragged.csv", "a,b\n1,2\n3\n");
+        let result = read_csv_traces(&path, b',', true);
+        std::fs::remove_file(&path).ok();
+        match result {
+            Err(IoError::Format(msg)) => assert!(msg.contains("line 3"), "{msg}"),
+            other => panic!("expected a format error, got {other:?}"),
+        }
+    }
+
+    fn write_suite2p_dir(name: &str, with_fneu: bool) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("calab_s2p_{}_{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let f: Vec<u8> = [1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        std::fs::write(dir.join("F.npy"), npy_bytes("<f4", false, "(2, 3)", &f)).unwrap();
+        if with_fneu {
+            let fneu: Vec<u8> = [1.0_f32, 1.0, 1.0, 2.0, 2.0, 2.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect();
+            std::fs::write(
+                dir.join("Fneu.npy"),
+                npy_bytes("<f4", false, "(2, 3)", &fneu),
+            )
+            .unwrap();
+        }
+        let iscell: Vec<u8> = [1.0_f64, 0.9, 0.0, 0.2]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        std::fs::write(
+            dir.join("iscell.npy"),
+            npy_bytes("<f8", false, "(2, 2)", &iscell),
+        )
+        .unwrap();
+        // Fragment of a protocol-4 pickled ops dict: 'fs': 30.0 after a MEMOIZE.
+        let mut ops = npy_bytes("|O", false, "()", b"\x80\x04}\x94");
+        ops.extend_from_slice(&[0x8c, 2, b'f', b's', 0x94, b'G']);
+        ops.extend_from_slice(&30.0_f64.to_be_bytes());
+        std::fs::write(dir.join("ops.npy"), ops).unwrap();
+        dir
+    }
+
+    #[test]
+    fn suite2p_reads_and_corrects_neuropil() {
+        let dir = write_suite2p_dir("full", true);
+        let data = read_suite2p_f(&dir.join("stat.npy"));
+        std::fs::remove_dir_all(&dir).ok();
+        let data = data.unwrap();
+        assert_eq!(data.f, vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert_eq!(data.iscell, vec![true, false]);
+        assert_eq!(data.fs, 30.0);
+        let corrected = suite2p_correct_neuropil(&data, 0.5);
+        assert_eq!(corrected, vec![vec![0.5, 1.5, 2.5], vec![3.0, 4.0, 5.0]]);
+    }
+
+    #[test]
+    fn suite2p_rejects_iscell_without_columns() {
+        let dir = write_suite2p_dir("iscell_no_cols", true);
+        std::fs::write(
+            dir.join("iscell.npy"),
+            npy_bytes("<f8", false, "(2, 0)", &[]),
+        )
+        .unwrap();
+        let result = read_suite2p_f(&dir.join("stat.npy"));
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(
+            matches!(&result, Err(IoError::Format(msg)) if msg.contains("no columns")),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    #[test]
+    fn suite2p_without_fneu_returns_raw_f() {
+        let dir = write_suite2p_dir("no_fneu", false);
+        std::fs::remove_file(dir.join("ops.npy")).unwrap();
+        let data = read_suite2p_f(&dir.join("stat.npy"));
+        let missing = read_suite2p_f(&dir.join("missing").join("stat.npy"));
+        std::fs::remove_dir_all(&dir).ok();
+        let data = data.unwrap();
+        assert!(data.f_neuropil.is_empty());
+        assert!(data.fs.is_nan());
+        assert_eq!(suite2p_correct_neuropil(&data, 0.7), data.f);
+        assert!(matches!(missing, Err(IoError::Io(_))));
+    }
+
+    #[test]
+    fn neuropil_coeff_recovered_from_quiet_frames() {
+        let n = 2000;
+        let mut f_neu = vec![0.0_f32; n];
+        let mut f = vec![0.0_f32; n];
+        let mut seed = 7_u64;
+        for t in 0..n {
+            f_neu[t] = 1.0
+                + 0.3 * (t as f32 * 0.05).sin()
+                + 0.05 * crate::indeca::lcg_gaussian(&mut seed) as f32;
+            f[t] = 0.6 * f_neu[t] + 0.01 * crate::indeca::lcg_gaussian(&mut seed) as f32;
+        }
+        // Large transients on a few stretches must not bias the estimate.
+        for start in [200, 700, 1300] {
+            for k in 0..150 {
+                f[start + k] += 4.0 * (-(k as f32) / 30.0).exp();
+            }
+        }
+        let coeff = estimate_neuropil_coeff(&f, &f_neu, 20);
+        assert!((coeff - 0.6).abs() < 0.05, "coeff {coeff}");
+
+        let corrected = neuropil_correct(&f, &f_neu, coeff);
+        assert_eq!(corrected.len(), n);
+        assert!((corrected[0] - (f[0] - coeff as f32 * f_neu[0])).abs() < 1e-6);
+
+        // Anti-correlated neuropil clamps to 0, constant neuropil gives 0.
+        let inverted: Vec<f32> = f_neu.iter().map(|&v| 2.0 - v).collect();
+        assert_eq!(estimate_neuropil_coeff(&inverted, &f_neu, 20), 0.0);
+        assert_eq!(estimate_neuropil_coeff(&f, &vec![1.0; n], 20), 0.0);
+        assert_eq!(estimate_neuropil_coeff(&[], &[], 20), 0.0);
+    }
+}
@@ -2,13 +2,223 @@
 ///
 /// These functions are exposed to JavaScript via wasm-bindgen and use
 /// serde-wasm-bindgen for complex return types (InDecaResult, BiexpResult).
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
 use wasm_bindgen::prelude::*;
 
+use crate::banded::BandedAR2;
+use crate::baseline;
 use crate::biexp_fit;
 use crate::indeca;
 use crate::kernel_est;
 use crate::peak_seed;
+use crate::presets::Preset;
+use crate::threshold;
 use crate::upsample;
+use crate::{CalabError, Solver};
+
+// Interfaces for the serialized results and typed signatures for the
+// functions below that return them (those are `skip_typescript`, since the
+// generated declarations would type them as `any`).
+#[wasm_bindgen(typescript_custom_section)]
+const TS_INDECA: &'static str = include_str!("../wasm_indeca.d.ts");
+
+thread_local! {
+    /// Cancel tokens handed out to JS, keyed by id. A token is removed when
+    /// the solve it was passed to returns.
+    static CANCEL_TOKENS: RefCell<HashMap<u32, indeca::CancelToken>> = RefCell::new(HashMap::new());
+    static NEXT_CANCEL_ID: Cell<u32> = const { Cell::new(1) };
+    /// Solver allocated by `indeca_preallocate`, reused by the synchronous
+    /// solves on this thread.
+    static PREALLOCATED_SOLVER: RefCell<Option<Solver>> = const { RefCell::new(None) };
+}
+
+/// Allocate a solver sized for traces of up to `max_trace_len` samples at
+/// `upsample_factor` and keep it for the synchronous solve entry points
+/// (`indeca_solve_trace`, `indeca_solve_trace_abortable`,
+/// `indeca_solve_batch`), which then reuse its buffers instead of allocating
+/// per call. Meant for interactive scrubbing, where the same few trace
+/// lengths are solved repeatedly. Calling it again replaces the solver;
+/// longer traces still work and grow the buffers.
+#[wasm_bindgen]
+pub fn indeca_preallocate(max_trace_len: usize, upsample_factor: usize) {
+    let mut solver = Solver::new();
+    solver.set_params(0.02, 0.4, 0.0, 30.0);
+    solver.set_trace(&vec![0.0; max_trace_len * upsample_factor.max(1)]);
+    PREALLOCATED_SOLVER.with(|s| *s.borrow_mut() = Some(solver));
+}
+
+/// Seed the global random stream (`rng::set_global_seed`) drawn on by the
+/// stochastic operations, e.g. the block order of stochastic FISTA. Call it
+/// before the first stochastic call for reproducible results.
+#[wasm_bindgen]
+pub fn indeca_set_seed(seed: u64) {
+    crate::rng::set_global_seed(seed);
+}
+
+/// Allocate a cancel token and return its id for `indeca_solve_trace` /
+/// `indeca_cancel_solve`.
+#[wasm_bindgen]
+pub fn indeca_new_cancel_token() -> u32 {
+    let id = NEXT_CANCEL_ID.with(|next| {
+        let id = next.get();
+        next.set(id.wrapping_add(1).max(1));
+        id
+    });
+    CANCEL_TOKENS.with(|m| m.borrow_mut().insert(id, indeca::CancelToken::new()));
+    id
+}
+
+/// Request cancellation of the solve holding `token_id`. Unknown or already
+/// finished ids are ignored.
+#[wasm_bindgen]
+pub fn indeca_cancel_solve(token_id: u32) {
+    CANCEL_TOKENS.with(|m| {
+        if let Some(token) = m.borrow().get(&token_id) {
+            token.cancel();
+        }
+    });
+}
+
+fn lookup_cancel_token(token_id: Option<u32>) -> Option<indeca::CancelToken> {
+    token_id.and_then(|id| CANCEL_TOKENS.with(|m| m.borrow().get(&id).cloned()))
+}
+
+fn release_cancel_token(token_id: Option<u32>) {
+    if let Some(id) = token_id {
+        CANCEL_TOKENS.with(|m| m.borrow_mut().remove(&id));
+    }
+}
+
+fn nonfinite_trace_error(fn_name: &str, trace: &[f32]) -> Option<JsError> {
+    crate::first_nonfinite(trace).map(|i| {
+        JsError::new(&format!(
+            "{fn_name}: trace contains a non-finite value (NaN or infinity) at index {i}"
+        ))
+    })
+}
+
+/// Payload of the JS `on_progress` callback.
+#[derive(serde::Serialize)]
+struct JsProgress {
+    iter: usize,
+    pve: f64,
+    alpha: f64,
+    fista_iters: u32,
+}
+
+/// JS progress callback plus the token it cancels when it throws.
+struct JsProgressCallback {
+    callback: js_sys::Function,
+    abort: indeca::CancelToken,
+}
+
+// SAFETY: `ProgressFn` must be `Send`, but the callback is only ever invoked
+// by the synchronous `indeca_solve_trace` on the thread that created it; it is
+// never passed to another thread (the threaded entry point takes no callback).
+unsafe impl Send for JsProgressCallback {}
+
+impl JsProgressCallback {
+    fn into_progress_fn(self) -> indeca::ProgressFn {
+        // Call through a method so the closure captures `self` as a whole
+        // (and with it the `Send` impl) rather than its fields.
+        Box::new(move |event| self.on_event(event))
+    }
+
+    fn on_event(&self, event: indeca::ProgressEvent) {
+        if event.stage != indeca::Stage::ScaleIteration || self.abort.is_cancelled() {
+            return;
+        }
+        let payload = JsProgress {
+            iter: event.scale_iter,
+            pve: event.pve,
+            alpha: event.alpha,
+            fista_iters: event.fista_iter,
+        };
+        let arg = serde_wasm_bindgen::to_value(&payload).unwrap_or(JsValue::NULL);
+        if self.callback.call1(&JsValue::NULL, &arg).is_err() {
+            self.abort.cancel();
+        }
+    }
+}
+
+/// Scalar arguments shared by `indeca_solve_trace` and
+/// `indeca_solve_trace_threaded`.
+#[derive(Clone, Copy)]
+struct TraceSolveParams {
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda: f64,
+    noise_constrained: bool,
+}
+
+impl TraceSolveParams {
+    fn run(
+        &self,
+        trace: &[f32],
+        warm_counts: &[f32],
+        on_progress: Option<indeca::ProgressFn>,
+        cancel: Option<indeca::CancelToken>,
+    ) -> Result<indeca::InDecaResult, CalabError> {
+        self.run_until(trace, warm_counts, on_progress, &|| {
+            cancel
+                .as_ref()
+                .is_some_and(indeca::CancelToken::is_cancelled)
+        })
+    }
+
+    fn run_until(
+        &self,
+        trace: &[f32],
+        warm_counts: &[f32],
+        on_progress: Option<indeca::ProgressFn>,
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<indeca::InDecaResult, CalabError> {
+        let warm = if warm_counts.is_empty() {
+            None
+        } else {
+            Some(warm_counts)
+        };
+        // Taken out for the duration of the solve, so a progress callback that
+        // starts another solve gets a fresh solver rather than a borrow panic.
+        let preallocated = PREALLOCATED_SOLVER.with(|s| s.borrow_mut().take());
+        let reuse = preallocated.is_some();
+        let mut solver = preallocated.unwrap_or_else(Solver::new);
+        let result = indeca::solve_trace_with(
+            &mut solver,
+            trace,
+            self.tau_r,
+            self.tau_d,
+            self.fs,
+            self.upsample_factor,
+            self.max_iters,
+            self.tol,
+            warm,
+            self.hp_enabled,
+            self.lp_enabled,
+            self.lambda,
+            indeca::SolveOptions {
+                noise_constrained: self.noise_constrained,
+                ..Default::default()
+            },
+            on_progress,
+            should_stop,
+        );
+        if reuse {
+            PREALLOCATED_SOLVER.with(|s| {
+                s.borrow_mut().get_or_insert(solver);
+            });
+        }
+        result
+    }
+}
 
 /// Solve a single trace using the InDeCa pipeline.
 ///
@@ -16,12 +226,24 @@ use crate::upsample;
 /// sampling rate. Pass an empty slice for cold-start.
 ///
 /// Returns a JsValue containing the serialized InDecaResult:
-/// { s_counts, alpha, baseline, threshold, pve, iterations, converged }
+/// { s_counts, alpha, baseline, threshold, pve, iterations, converged, n_saturated_frames,
+///   noise_std, snr_db }
 ///
 /// Throws a JS error (rather than returning garbage) if `trace` contains a
 /// non-finite value — a NaN/Inf would otherwise propagate silently and yield
-/// results indistinguishable from a legitimately hard trace.
-#[wasm_bindgen]
+/// results indistinguishable from a legitimately hard trace — or if the taus
+/// and `fs` give an unstable AR(2) model.
+///
+/// `cancel_token_id`: optional id from `indeca_new_cancel_token`. After
+/// `indeca_cancel_solve(id)` the solve returns its best result so far; the
+/// token is released when the solve returns.
+///
+/// `on_progress`: optional function called after each scale iteration with
+/// `{ iter, pve, alpha, fista_iters }`. It runs synchronously on the thread
+/// executing the solve (the Web Worker hosting the WASM module, not the JS main
+/// thread), so it cannot update the DOM directly; post a message instead. If it
+/// throws, the solve stops and returns its best result so far.
+#[wasm_bindgen(skip_typescript)]
 pub fn indeca_solve_trace(
     trace: &[f32],
     tau_r: f64,
@@ -35,34 +257,279 @@ pub fn indeca_solve_trace(
     warm_counts: &[f32],
     lambda: f64,
     noise_constrained: bool,
+    cancel_token_id: Option<u32>,
+    on_progress: Option<js_sys::Function>,
 ) -> Result<JsValue, JsError> {
-    if let Some(i) = crate::first_nonfinite(trace) {
-        return Err(JsError::new(&format!(
-            "indeca_solve_trace: trace contains a non-finite value (NaN or infinity) at index {i}"
-        )));
+    if let Some(err) = nonfinite_trace_error("indeca_solve_trace", trace) {
+        release_cancel_token(cancel_token_id);
+        return Err(err);
     }
-    let warm = if warm_counts.is_empty() {
-        None
-    } else {
-        Some(warm_counts)
-    };
-    let result = indeca::solve_trace_opts(
-        trace,
+    let params = TraceSolveParams {
         tau_r,
         tau_d,
         fs,
         upsample_factor,
         max_iters,
         tol,
-        warm,
         hp_enabled,
         lp_enabled,
         lambda,
-        indeca::SolveOptions { noise_constrained },
-    );
+        noise_constrained,
+    };
+    // A throwing callback aborts through the cancel token, so make sure there
+    // is one even when the caller did not pass an id.
+    let cancel = lookup_cancel_token(cancel_token_id)
+        .or_else(|| on_progress.as_ref().map(|_| indeca::CancelToken::new()));
+    let on_progress = on_progress
+        .zip(cancel.clone())
+        .map(|(callback, abort)| JsProgressCallback { callback, abort }.into_progress_fn());
+    let result = params.run(trace, warm_counts, on_progress, cancel);
+    release_cancel_token(cancel_token_id);
+    let result = result.map_err(|e| JsError::new(&format!("indeca_solve_trace: {e}")))?;
     Ok(serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL))
 }
 
+/// Set when `init_thread_pool` has finished starting the Web Worker pool.
+#[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+static THREAD_POOL_READY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Start a pool of `n_threads` Web Workers (via `wasm-bindgen-rayon`) for
+/// `indeca_solve_trace_threaded`. Await the returned promise before solving.
+///
+/// Only a wasm build with atomics has a pool: it needs `SharedArrayBuffer`,
+/// which browsers only enable for cross-origin-isolated pages, i.e. pages
+/// served with `Cross-Origin-Opener-Policy: same-origin` and
+/// `Cross-Origin-Embedder-Policy: require-corp`. Other builds resolve
+/// immediately and the threaded entry point runs synchronously.
+#[wasm_bindgen(skip_typescript)]
+pub fn init_thread_pool(n_threads: usize) -> js_sys::Promise {
+    #[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+    {
+        let started = wasm_bindgen_rayon::init_thread_pool(n_threads);
+        wasm_bindgen_futures::future_to_promise(async move {
+            wasm_bindgen_futures::JsFuture::from(started).await?;
+            THREAD_POOL_READY.store(true, std::sync::atomic::Ordering::Release);
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "atomics")))]
+    {
+        let _ = n_threads;
+        js_sys::Promise::resolve(&JsValue::UNDEFINED)
+    }
+}
+
+/// `indeca_solve_trace` on the Web Worker pool, keeping the calling thread
+/// free. Returns a promise for the same result object (rejected on a
+/// non-finite trace or unstable taus). Runs synchronously, resolving an already-settled
+/// promise, when `init_thread_pool` has not completed or the build has no
+/// thread support.
+///
+/// The trace is copied into the worker. A cancel token id works as for
+/// `indeca_solve_trace`, and `indeca_cancel_solve` from the calling thread
+/// reaches a solve running on a worker. There is no `on_progress`: JS
+/// functions cannot be called from a pool thread.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_solve_trace_threaded(
+    trace: &[f32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    warm_counts: &[f32],
+    lambda: f64,
+    noise_constrained: bool,
+    cancel_token_id: Option<u32>,
+) -> js_sys::Promise {
+    if let Some(err) = nonfinite_trace_error("indeca_solve_trace_threaded", trace) {
+        release_cancel_token(cancel_token_id);
+        return js_sys::Promise::reject(&err.into());
+    }
+    let params = TraceSolveParams {
+        tau_r,
+        tau_d,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        hp_enabled,
+        lp_enabled,
+        lambda,
+        noise_constrained,
+    };
+    let cancel = lookup_cancel_token(cancel_token_id);
+
+    #[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+    if THREAD_POOL_READY.load(std::sync::atomic::Ordering::Acquire) {
+        let (trace, warm_counts) = (trace.to_vec(), warm_counts.to_vec());
+        let (tx, rx) = futures_channel::oneshot::channel();
+        rayon::spawn(move || {
+            let _ = tx.send(params.run(&trace, &warm_counts, None, cancel));
+        });
+        return wasm_bindgen_futures::future_to_promise(async move {
+            let result = rx.await;
+            release_cancel_token(cancel_token_id);
+            let result = result.map_err(|_| {
+                JsValue::from(JsError::new(
+                    "indeca_solve_trace_threaded: worker dropped the solve",
+                ))
+            })?;
+            result_to_js("indeca_solve_trace_threaded", result)
+        });
+    }
+
+    let result = params.run(trace, warm_counts, None, cancel);
+    release_cancel_token(cancel_token_id);
+    match result_to_js("indeca_solve_trace_threaded", result) {
+        Ok(value) => js_sys::Promise::resolve(&value),
+        Err(err) => js_sys::Promise::reject(&err),
+    }
+}
+
+/// Serialize a solve result for a promise, or the solve error (prefixed with
+/// `func`) to reject it with.
+fn result_to_js(
+    func: &str,
+    result: Result<indeca::InDecaResult, CalabError>,
+) -> Result<JsValue, JsValue> {
+    match result {
+        Ok(result) => Ok(serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)),
+        Err(e) => Err(JsError::new(&format!("{func}: {e}")).into()),
+    }
+}
+
+/// `indeca_solve_trace` cancellable through a standard `AbortSignal`.
+///
+/// The signal is polled at the top of each scale iteration and between FISTA
+/// iterations. Once it is aborted the solve stops and the promise resolves
+/// (it does not reject) with the best result so far, so
+/// `controller.abort()` yields a usable partial `InDecaResult`; check
+/// `signal.aborted` to tell it apart from a complete solve. An already
+/// aborted signal resolves with the all-zero fallback result. Non-finite
+/// traces and unstable taus reject the promise.
+///
+/// The solve runs synchronously on the calling thread, so JS code can only
+/// abort it while it runs from inside `on_progress` (same contract as for
+/// `indeca_solve_trace`, including aborting when the callback throws); an
+/// abort queued by a timer or message handler is seen by the next solve.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_solve_trace_abortable(
+    trace: &[f32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    warm_counts: &[f32],
+    lambda: f64,
+    noise_constrained: bool,
+    signal: web_sys::AbortSignal,
+    on_progress: Option<js_sys::Function>,
+) -> js_sys::Promise {
+    if let Some(err) = nonfinite_trace_error("indeca_solve_trace_abortable", trace) {
+        return js_sys::Promise::reject(&err.into());
+    }
+    let params = TraceSolveParams {
+        tau_r,
+        tau_d,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        hp_enabled,
+        lp_enabled,
+        lambda,
+        noise_constrained,
+    };
+    let callback_abort = indeca::CancelToken::new();
+    let on_progress = on_progress.map(|callback| {
+        JsProgressCallback {
+            callback,
+            abort: callback_abort.clone(),
+        }
+        .into_progress_fn()
+    });
+    // Latched so the signal is not queried again once it has fired.
+    let aborted = Cell::new(false);
+    let should_stop = || {
+        if !aborted.get() && (callback_abort.is_cancelled() || signal.aborted()) {
+            aborted.set(true);
+        }
+        aborted.get()
+    };
+    let result = params.run_until(trace, warm_counts, on_progress, &should_stop);
+    match result_to_js("indeca_solve_trace_abortable", result) {
+        Ok(value) => js_sys::Promise::resolve(&value),
+        Err(err) => js_sys::Promise::reject(&err),
+    }
+}
+
+/// Solve several traces in one call, sequentially, with shared parameters.
+///
+/// `traces_flat` is the concatenation of the traces and `trace_lengths` gives
+/// each one's length (traces may differ in length). Each trace is solved cold
+/// as by `indeca_solve_trace` with `lambda = 0`, no noise constraint and no
+/// cancellation, saving one WASM boundary crossing per trace.
+///
+/// Returns a JS array of serialized InDecaResult objects in input order.
+/// Throws if `traces_flat.length != sum(trace_lengths)`, a trace contains a
+/// non-finite value (the message names the trace) or the taus give an
+/// unstable AR(2) model.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_solve_batch(
+    traces_flat: &[f32],
+    trace_lengths: &[u32],
+    tau_r: f64,
+    tau_d: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+) -> Result<JsValue, JsError> {
+    let total_len: usize = trace_lengths.iter().map(|&l| l as usize).sum();
+    if traces_flat.len() != total_len {
+        return Err(JsError::new(
+            "indeca_solve_batch: traces_flat length must equal sum(trace_lengths)",
+        ));
+    }
+    let params = TraceSolveParams {
+        tau_r,
+        tau_d,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        hp_enabled,
+        lp_enabled,
+        lambda: 0.0,
+        noise_constrained: false,
+    };
+    let mut results = Vec::with_capacity(trace_lengths.len());
+    let mut offset = 0;
+    for (i, &len) in trace_lengths.iter().enumerate() {
+        let trace = &traces_flat[offset..offset + len as usize];
+        offset += len as usize;
+        if let Some(err) = nonfinite_trace_error(&format!("indeca_solve_batch: trace {i}"), trace) {
+            return Err(err);
+        }
+        results.push(
+            params
+                .run(trace, &[], None, None)
+                .map_err(|e| JsError::new(&format!("indeca_solve_batch: trace {i}: {e}")))?,
+        );
+    }
+    Ok(serde_wasm_bindgen::to_value(&results).unwrap_or(JsValue::NULL))
+}
+
 /// Estimate a free-form kernel from multiple traces and their spike trains.
 ///
 /// `warm_kernel`: optional kernel from a previous iteration. Pass an empty slice
@@ -104,7 +571,7 @@ pub fn indeca_estimate_kernel(
     } else {
         Some(warm_kernel)
     };
-    Ok(kernel_est::estimate_free_kernel(
+    kernel_est::estimate_free_kernel(
         traces_flat,
         spikes_flat,
         alphas,
@@ -115,7 +582,9 @@ pub fn indeca_estimate_kernel(
         tol,
         warm,
         smooth_lambda,
-    ))
+        0.0,
+    )
+    .map_err(|e| JsError::new(&format!("indeca_estimate_kernel: {e}")))
 }
 
 /// Fit a bi-exponential model to a free-form kernel.
@@ -127,7 +596,7 @@ pub fn indeca_estimate_kernel(
 ///
 /// Returns a JsValue containing the serialized BiexpResult:
 /// { tau_rise, tau_decay, beta, residual, tau_rise_fast, tau_decay_fast, beta_fast }
-#[wasm_bindgen]
+#[wasm_bindgen(skip_typescript)]
 pub fn indeca_fit_biexponential(
     h_free: &[f32],
     fs: f64,
@@ -161,19 +630,120 @@ pub fn indeca_fit_biexponential(
     serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
 }
 
+/// Fit a slow-only bi-exponential with the time constants restricted to
+/// `tau_r ∈ [tau_r_min, tau_r_max]` and `tau_d ∈ [tau_d_min, tau_d_max]`
+/// (seconds), for indicators with known kinetics. The ranges must satisfy
+/// `tau_r_min < tau_r_max < tau_d_min <= tau_d_max`.
+///
+/// Returns the serialized BiexpResult, or throws on invalid bounds.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_fit_biexponential_bounded(
+    h_free: &[f32],
+    fs: f64,
+    tau_r_min: f64,
+    tau_r_max: f64,
+    tau_d_min: f64,
+    tau_d_max: f64,
+    refine: bool,
+) -> Result<JsValue, JsError> {
+    let result = biexp_fit::fit_biexponential_bounded(
+        h_free, fs, tau_r_min, tau_r_max, tau_d_min, tau_d_max, refine,
+    )
+    .map_err(|e| JsError::new(&format!("indeca_fit_biexponential_bounded: {e}")))?;
+    Ok(serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL))
+}
+
+/// Binarize a relaxed solution by threshold search on the original-rate grid:
+/// each candidate spike train is convolved through the AR(2) kernel for
+/// `tau_rise` / `tau_decay` at `fs`, fit with a non-negative alpha plus
+/// baseline, and the one with the smallest residual is kept. `s_relaxed` and
+/// `y` must have the same length. Optional `weights` (non-negative, one per
+/// frame of `y`) down-weight artifact frames in the fit.
+///
+/// Returns a JsValue containing the serialized ThresholdResult:
+/// { s_binary, alpha, baseline, threshold, pve, error }
+///
+/// Throws on mismatched lengths, negative weights, a non-finite `y` or
+/// unstable time constants.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_threshold_search(
+    s_relaxed: &[f32],
+    y: &[f32],
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    weights: Option<Vec<f32>>,
+) -> Result<JsValue, JsError> {
+    let js_err = |e: CalabError| JsError::new(&format!("indeca_threshold_search: {e}"));
+    if let Some(err) = nonfinite_trace_error("indeca_threshold_search", y) {
+        return Err(err);
+    }
+    if s_relaxed.len() != y.len() {
+        return Err(js_err(CalabError::DimensionMismatch {
+            expected: s_relaxed.len(),
+            got: y.len(),
+        }));
+    }
+    if let Some(w) = &weights {
+        if w.len() != y.len() {
+            return Err(js_err(CalabError::DimensionMismatch {
+                expected: y.len(),
+                got: w.len(),
+            }));
+        }
+        if let Some(&v) = w.iter().find(|&&v| v < 0.0) {
+            return Err(js_err(CalabError::invalid(
+                "weights",
+                v,
+                "must be non-negative",
+            )));
+        }
+    }
+    let banded = BandedAR2::new(tau_rise, tau_decay, fs).map_err(js_err)?;
+    let result = threshold::threshold_search(
+        s_relaxed,
+        y,
+        &banded,
+        tau_decay,
+        fs,
+        1,
+        f64::INFINITY,
+        f64::INFINITY,
+        weights.as_deref(),
+    );
+    Ok(serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL))
+}
+
 /// Compute the upsample factor for a given sampling rate and target rate.
 #[wasm_bindgen]
 pub fn indeca_compute_upsample_factor(fs: f64, target_fs: f64) -> usize {
     upsample::compute_upsample_factor(fs, target_fs)
 }
 
+/// Subtract a causal rolling-percentile baseline from `trace` in place, with
+/// the window `indeca_solve_trace` uses for `tau_d` at `fs`. `quantile` is the
+/// floor percentile in [0, 1] (the pipeline uses 0.2). The typed array passed
+/// from JS is updated in place.
+#[wasm_bindgen]
+pub fn indeca_subtract_baseline(trace: &mut [f32], tau_d: f64, fs: f64, quantile: f64) {
+    let window = baseline::baseline_window(tau_d, fs);
+    baseline::subtract_rolling_baseline(trace, window, quantile);
+}
+
+/// Rolling-baseline window in samples for `tau_d` at `fs`
+/// (`5 * ceil(5 * tau_d * fs)`).
+#[wasm_bindgen]
+pub fn indeca_baseline_window(tau_d: f64, fs: f64) -> usize {
+    baseline::baseline_window(tau_d, fs)
+}
+
 /// Run peak-seeded spike detection on a single trace.
 ///
 /// Returns a JsValue containing the serialized SeedTraceResult:
 /// { s_counts, alpha, baseline }
 ///
 /// Throws a JS error if `trace` contains a non-finite value.
-#[wasm_bindgen]
+#[wasm_bindgen(skip_typescript)]
 pub fn seed_trace(trace: &[f32], fs: f64) -> Result<JsValue, JsError> {
     if let Some(i) = crate::first_nonfinite(trace) {
         return Err(JsError::new(&format!(
@@ -183,3 +753,43 @@ pub fn seed_trace(trace: &[f32], fs: f64) -> Result<JsValue, JsError> {
     let result = peak_seed::seed_trace(trace, fs);
     Ok(serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL))
 }
+
+#[derive(serde::Serialize)]
+struct PresetParams {
+    tau_rise: f64,
+    tau_decay: f64,
+}
+
+/// Kernel time constants for a named indicator preset at sampling rate `fs`.
+///
+/// Returns a JsValue `{ tau_rise, tau_decay }` (seconds). Throws a JS error for
+/// an unknown preset name (matching is case-insensitive).
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_preset_params(name: &str, fs: f64) -> Result<JsValue, JsError> {
+    let preset = Preset::from_name(name)
+        .ok_or_else(|| JsError::new(&format!("indeca_preset_params: unknown preset '{name}'")))?;
+    let (tau_rise, tau_decay) = preset.params_at_fs(fs);
+    Ok(serde_wasm_bindgen::to_value(&PresetParams {
+        tau_rise,
+        tau_decay,
+    })
+    .unwrap_or(JsValue::NULL))
+}
+
+#[derive(serde::Serialize)]
+struct NpyArray {
+    data: Vec<f32>,
+    shape: Vec<usize>,
+}
+
+/// Parse the bytes of a `.npy` file (float32/float64, either byte order,
+/// C or Fortran order).
+///
+/// Returns a JsValue `{ data, shape }` with `data` flattened in row-major
+/// order. Throws a JS error for malformed or unsupported files.
+#[wasm_bindgen(skip_typescript)]
+pub fn indeca_read_npy(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let (data, shape) = crate::io::parse_npy_f32(bytes)
+        .map_err(|e| JsError::new(&format!("indeca_read_npy: {e}")))?;
+    Ok(serde_wasm_bindgen::to_value(&NpyArray { data, shape }).unwrap_or(JsValue::NULL))
+}
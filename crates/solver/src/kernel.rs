@@ -1,3 +1,5 @@
+use crate::KernelNorm;
+
 /// Clamp tau_rise away from tau_decay to prevent degenerate zero kernels.
 /// When tau_rise ≈ tau_decay, the biexponential exp(-t/τ_d) - exp(-t/τ_r) collapses to zero.
 pub(crate) fn clamp_tau_rise(tau_rise: f64, tau_decay: f64) -> f64 {
@@ -13,22 +15,27 @@ pub(crate) fn clamp_tau_rise(tau_rise: f64, tau_decay: f64) -> f64 {
 /// h(t) = exp(-t/tau_decay) - exp(-t/tau_rise), normalized so max(h) = 1.0.
 /// Kernel length extends until the decay envelope drops below 1e-6 of peak.
 /// Computed in f64 for precision, returned as Vec<f32>.
+///
+/// `tau_rise = 0.0` gives the single-exponential limit: h(0) = 0 and
+/// h(t) = exp(-(t - dt)/tau_decay) afterwards (the `BandedAR1` impulse response).
 pub fn build_kernel(tau_rise: f64, tau_decay: f64, fs: f64) -> Vec<f32> {
     let tau_rise = clamp_tau_rise(tau_rise, tau_decay);
 
     let dt = 1.0 / fs;
-
-    // Kernel length: until decay drops below 1e-6 of peak
-    // -ln(1e-6) = 6*ln(10) ~ 13.8155
-    let kernel_len = ((-1e-6_f64.ln()) * tau_decay / dt).ceil() as usize;
-    let kernel_len = kernel_len.max(2); // at least 2 samples
+    let kernel_len = suggest_kernel_length(tau_decay, fs);
 
     let mut kernel_f64 = Vec::with_capacity(kernel_len);
     let mut peak = 0.0_f64;
 
     for i in 0..kernel_len {
         let t = (i as f64) * dt;
-        let val = (-t / tau_decay).exp() - (-t / tau_rise).exp();
+        let val = if tau_rise > 0.0 {
+            (-t / tau_decay).exp() - (-t / tau_rise).exp()
+        } else if i == 0 {
+            0.0 // rise term exp(-t/0) is a step: zero at the spike sample
+        } else {
+            (-t / tau_decay).exp()
+        };
         kernel_f64.push(val);
         if val > peak {
             peak = val;
@@ -45,6 +52,90 @@ pub fn build_kernel(tau_rise: f64, tau_decay: f64, fs: f64) -> Vec<f32> {
     kernel_f64.iter().map(|&v| v as f32).collect()
 }
 
+/// Kernel length in samples used by `build_kernel` (and `KernelLengthMode::Auto`):
+/// until the decay envelope drops below 1e-6 of peak, at least 2 samples.
+pub fn suggest_kernel_length(tau_decay: f64, fs: f64) -> usize {
+    // -ln(1e-6) = 6*ln(10) ~ 13.8155
+    let kernel_len = ((-1e-6_f64.ln()) * tau_decay * fs).ceil() as usize;
+    kernel_len.max(2)
+}
+
+/// Factor that takes a unit-peak kernel to the normalization `norm`.
+///
+/// `raw_peak` is the peak of the unnormalized AR impulse response, used only
+/// by `KernelNorm::None`; `fs` converts the sample sum to an integral for
+/// `KernelNorm::Area`. Degenerate (all-zero) kernels keep a factor of 1.
+pub(crate) fn kernel_norm_scale(kernel: &[f32], norm: KernelNorm, fs: f64, raw_peak: f64) -> f64 {
+    let scale = match norm {
+        KernelNorm::Peak => 1.0,
+        KernelNorm::Area => fs / kernel.iter().map(|&k| k as f64).sum::<f64>(),
+        KernelNorm::L2 => {
+            1.0 / kernel
+                .iter()
+                .map(|&k| k as f64 * k as f64)
+                .sum::<f64>()
+                .sqrt()
+        }
+        KernelNorm::None => raw_peak,
+    };
+    if scale.is_finite() && scale > 0.0 {
+        scale
+    } else {
+        1.0
+    }
+}
+
+/// Kernel shape used by the solver.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KernelType {
+    /// `build_kernel`: rise plus a single decay. Supports every `ConvMode`.
+    DoubleExp,
+    /// `build_triple_exp_kernel`: rise plus fast and slow decay components.
+    /// FFT convolution only (the banded engines are AR(1)/AR(2)).
+    TripleExp {
+        tau_fast: f64,
+        tau_slow: f64,
+        fraction_fast: f64,
+    },
+    /// `set_ar_params`: impulse response of an order-`order` `ARpModel`.
+    /// FFT or `BandedARp` convolution.
+    ARp { order: usize },
+}
+
+/// Build a triple-exponential kernel (shared rise, fast and slow decay)
+/// normalized to peak = 1.0.
+///
+/// h(t) = f * h_fast(t) + (1 - f) * h_slow(t), where h_fast and h_slow are the
+/// unit-peak `build_kernel` shapes for (tau_rise, tau_fast) and
+/// (tau_rise, tau_slow), and f = `fraction_fast` clamped to [0, 1]. The length
+/// follows the slower decay. Some GCaMP7 variants need the slow tail.
+pub fn build_triple_exp_kernel(
+    tau_rise: f64,
+    tau_fast: f64,
+    tau_slow: f64,
+    fraction_fast: f64,
+    fs: f64,
+) -> Vec<f32> {
+    let frac = fraction_fast.clamp(0.0, 1.0);
+    let (fast, slow) = (
+        build_kernel(tau_rise, tau_fast, fs),
+        build_kernel(tau_rise, tau_slow, fs),
+    );
+    let kernel_len = fast.len().max(slow.len());
+    let at = |k: &[f32], i: usize| k.get(i).map_or(0.0, |&v| v as f64);
+
+    let mut kernel_f64: Vec<f64> = (0..kernel_len)
+        .map(|i| frac * at(&fast, i) + (1.0 - frac) * at(&slow, i))
+        .collect();
+    let peak = kernel_f64.iter().cloned().fold(0.0_f64, f64::max);
+    if peak > 0.0 {
+        for v in kernel_f64.iter_mut() {
+            *v /= peak;
+        }
+    }
+    kernel_f64.iter().map(|&v| v as f32).collect()
+}
+
 /// Derive AR(2) coefficients (g1, g2) from tau parameters.
 ///
 /// The AR(2) process c[t] = g1*c[t-1] + g2*c[t-2] + s[t] has characteristic
@@ -66,45 +157,99 @@ pub fn tau_to_ar2(tau_rise: f64, tau_decay: f64, fs: f64) -> (f64, f64) {
     (g1, g2)
 }
 
+/// Power-iteration budget and seed used by `compute_lipschitz`. The iteration
+/// stops early once the estimate settles, so the budget only matters for
+/// kernels whose two largest eigenvalues are nearly equal.
+const LIPSCHITZ_POWER_ITERS: u32 = 500;
+const LIPSCHITZ_SEED: u64 = 0;
+
 /// Compute the Lipschitz constant of the gradient of (1/2)||y - K*s||^2.
 ///
 /// L = max_w |H(w)|^2, where H(w) is the DFT of the kernel. This equals the
 /// largest eigenvalue of K^T K for a circulant convolution matrix, and is a
 /// tight upper bound for the Toeplitz (causal) convolution matrix used in practice.
 ///
-/// Computed via direct DFT in f64 — O(n²) but zero-allocation and fast for
-/// typical kernel sizes (~200–600 samples). Only runs on parameter changes.
+/// Delegates to [`estimate_lipschitz_power_iter`], which works for any kernel
+/// shape, including free-form kernels from `estimate_free_kernel`. Only runs on
+/// parameter changes.
 pub fn compute_lipschitz(kernel: &[f32]) -> f64 {
+    estimate_lipschitz_power_iter(kernel, LIPSCHITZ_POWER_ITERS, LIPSCHITZ_SEED)
+}
+
+/// Largest eigenvalue of C^T C by the power method, where C is circular
+/// convolution with `kernel` over `(2 * len).next_power_of_two()` samples (so
+/// the wrap-around never overlaps the kernel itself).
+///
+/// Starts from a pseudo-random vector drawn from `seed`, so the result is
+/// deterministic, and runs at most `n_iters` iterations (at least one),
+/// stopping early once the Rayleigh quotient changes by less than 1e-10
+/// relative. The estimate approaches L from below. Each iteration is two
+/// real FFTs in f64. Returns 1e-10 for an empty or all-zero kernel.
+pub fn estimate_lipschitz_power_iter(kernel: &[f32], n_iters: u32, seed: u64) -> f64 {
     let n = kernel.len();
     if n == 0 {
         return 1e-10;
     }
-
-    let fft_len = (2 * n).next_power_of_two();
-    let inv = 2.0 * std::f64::consts::PI / (fft_len as f64);
-    let mut max_power = 0.0_f64;
-
-    // Pre-cast kernel to f64 once instead of per-frequency
-    let kernel_f64: Vec<f64> = kernel.iter().map(|&k| k as f64).collect();
-
-    // Real kernel has symmetric spectrum: only need 0..=fft_len/2
-    for w in 0..=fft_len / 2 {
-        let freq = inv * (w as f64);
-        let mut re = 0.0_f64;
-        let mut im = 0.0_f64;
-        for (k, &hk) in kernel_f64.iter().enumerate() {
-            let angle = freq * (k as f64);
-            let (s, c) = angle.sin_cos();
-            re += hk * c;
-            im -= hk * s;
+    let m = (2 * n).next_power_of_two();
+    let mut planner = realfft::RealFftPlanner::<f64>::new();
+    let fwd = planner.plan_fft_forward(m);
+    let inv = planner.plan_fft_inverse(m);
+
+    // |H|^2 / m: C^T C in the frequency domain, with the inverse FFT scale folded in.
+    let mut padded = vec![0.0_f64; m];
+    for (p, &k) in padded.iter_mut().zip(kernel) {
+        *p = k as f64;
+    }
+    let mut spectrum = fwd.make_output_vec();
+    fwd.process(&mut padded, &mut spectrum)
+        .expect("FFT buffers sized by the planner");
+    let gain: Vec<f64> = spectrum.iter().map(|h| h.norm_sqr() / m as f64).collect();
+
+    // SplitMix64 start vector in [-1, 1).
+    let mut state = seed;
+    let mut x: Vec<f64> = (0..m)
+        .map(|_| {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^= z >> 31;
+            (z >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+        })
+        .collect();
+    let norm = x.iter().map(|v| v * v).sum::<f64>().sqrt();
+    x.iter_mut().for_each(|v| *v /= norm);
+
+    let mut scratch = x.clone();
+    let mut lambda = 0.0_f64;
+    for _ in 0..n_iters.max(1) {
+        // z = C^T C x; x has unit norm, so <x, z> is the Rayleigh quotient.
+        scratch.copy_from_slice(&x);
+        fwd.process(&mut scratch, &mut spectrum)
+            .expect("FFT buffers sized by the planner");
+        for (s, &g) in spectrum.iter_mut().zip(&gain) {
+            *s *= g;
+        }
+        // The DC and Nyquist bins of a real signal's spectrum are real.
+        spectrum[0].im = 0.0;
+        spectrum[m / 2].im = 0.0;
+        inv.process(&mut spectrum, &mut scratch)
+            .expect("FFT buffers sized by the planner");
+
+        let prev = lambda;
+        lambda = x.iter().zip(&scratch).map(|(a, b)| a * b).sum();
+        let z_norm = scratch.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if z_norm <= 0.0 {
+            break;
+        }
+        for (xi, &zi) in x.iter_mut().zip(&scratch) {
+            *xi = zi / z_norm;
         }
-        let power = re * re + im * im;
-        if power > max_power {
-            max_power = power;
+        if (lambda - prev).abs() <= 1e-10 * lambda {
+            break;
         }
     }
-
-    max_power.max(1e-10)
+    lambda.max(1e-10)
 }
 
 #[cfg(test)]
@@ -254,4 +399,69 @@ mod tests {
             l1_norm * l1_norm
         );
     }
+
+    #[test]
+    fn power_iteration_matches_dft_peak() {
+        // Reference: max |H(w)|^2 over the same DFT grid by direct summation.
+        let dft_peak = |kernel: &[f32]| {
+            let m = (2 * kernel.len()).next_power_of_two();
+            (0..=m / 2)
+                .map(|w| {
+                    let freq = 2.0 * std::f64::consts::PI * w as f64 / m as f64;
+                    let (mut re, mut im) = (0.0_f64, 0.0_f64);
+                    for (k, &hk) in kernel.iter().enumerate() {
+                        let (s, c) = (freq * k as f64).sin_cos();
+                        re += hk as f64 * c;
+                        im -= hk as f64 * s;
+                    }
+                    re * re + im * im
+                })
+                .fold(0.0_f64, f64::max)
+        };
+
+        // Low-pass calcium kernel, a band-pass free-form shape and a kernel
+        // with a sign change (peak away from DC).
+        let free_form: Vec<f32> = (0..40)
+            .map(|i| ((i as f32) * 0.9).sin() * (-(i as f32) / 12.0).exp())
+            .collect();
+        for kernel in [
+            build_kernel(0.02, 0.4, 30.0),
+            build_kernel(0.1, 1.5, 100.0),
+            free_form,
+            vec![1.0, -1.0],
+        ] {
+            let exact = dft_peak(&kernel);
+            let estimate = estimate_lipschitz_power_iter(&kernel, 500, 7);
+            assert!(
+                (estimate - exact).abs() <= 1e-6 * exact,
+                "power iteration {estimate} vs DFT {exact}"
+            );
+            assert!(estimate <= exact * (1.0 + 1e-9));
+            assert_eq!(compute_lipschitz(&kernel), compute_lipschitz(&kernel));
+        }
+
+        assert_eq!(estimate_lipschitz_power_iter(&[], 10, 0), 1e-10);
+        assert_eq!(estimate_lipschitz_power_iter(&[0.0; 8], 10, 0), 1e-10);
+    }
+
+    #[test]
+    fn triple_exp_kernel_mixes_components() {
+        let fs = 30.0;
+        let k = build_triple_exp_kernel(0.02, 0.2, 1.5, 0.7, fs);
+        let peak = k.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        assert!((peak - 1.0).abs() < 1e-6);
+        assert_eq!(k.len(), build_kernel(0.02, 1.5, fs).len());
+
+        // The slow component keeps a tail the fast biexponential has lost.
+        let fast = build_kernel(0.02, 0.2, fs);
+        let t = (1.0 * fs) as usize;
+        assert!(k[t] > 5.0 * fast[t], "tail {} vs fast {}", k[t], fast[t]);
+
+        // fraction_fast = 1 is the plain fast kernel (zero-padded).
+        let pure = build_triple_exp_kernel(0.02, 0.2, 1.5, 1.0, fs);
+        for (i, &v) in pure.iter().enumerate() {
+            let expected = fast.get(i).copied().unwrap_or(0.0);
+            assert!((v - expected).abs() < 1e-6);
+        }
+    }
 }
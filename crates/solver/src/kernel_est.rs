@@ -2,11 +2,15 @@
 ///
 /// Given observed traces and inferred spike trains, estimate the shared
 /// calcium kernel h by solving:
-///   min_h (1/2)||y - S*h||^2 + λ_smooth * ||Δh||_1  subject to h >= 0
+///   min_h (1/2)||y - S*h||^2 + λ_smooth * ||Δh||_1 + λ_curv * ||Δ²h||^2
+///   subject to h >= 0
 /// where S is the spike convolution matrix, y is the concatenated traces,
-/// and ||Δh||_1 is the total variation (L1 norm of first differences).
+/// ||Δh||_1 is the total variation (L1 norm of first differences) and
+/// ||Δ²h||^2 the squared second differences (curvature).
 ///
-/// Uses FISTA with non-negativity constraint and optional TV smoothness penalty.
+/// Uses FISTA with non-negativity constraint, optional TV smoothness penalty
+/// (proximal step) and optional curvature penalty (smooth, so it joins the
+/// gradient and leaves the proximal operator unchanged).
 ///
 /// # Why free-form estimation instead of direct (tau_r, tau_d) optimization
 ///
@@ -39,6 +43,7 @@
 /// See also: OASIS (Friedrich et al. 2017) uses autocovariance-based
 /// estimation (Yule-Walker) for initial time constants, avoids joint
 /// (tau_r, tau_d) optimization, and refines on isolated large events only.
+use crate::CalabError;
 
 /// In-place 1D total variation proximal operator (Chambolle 2004).
 ///
@@ -107,8 +112,14 @@ fn prox_tv_1d(x: &mut [f64], lambda: f64) {
 /// - `tol`: convergence tolerance
 /// - `warm_start`: optional previous kernel estimate for warm-starting FISTA
 /// - `smooth_lambda`: TV-L1 smoothness penalty weight (0 = no smoothness)
+/// - `curvature_lambda`: second-difference penalty weight (0 = off); damps the
+///   oscillations sparse spike data leaves in the kernel
 ///
-/// Returns the estimated kernel of length `kernel_length`.
+/// Returns the estimated kernel of length `kernel_length` (all zeros when
+/// there is no data). Errors with `DimensionMismatch` when `alphas` /
+/// `baselines` do not have one entry per trace or `traces` / `spike_trains`
+/// do not sum to `trace_lengths`, and `InvalidParameter` for
+/// `kernel_length == 0`.
 pub fn estimate_free_kernel(
     traces: &[f32],
     spike_trains: &[f32],
@@ -120,46 +131,197 @@ pub fn estimate_free_kernel(
     tol: f64,
     warm_start: Option<&[f32]>,
     smooth_lambda: f64,
-) -> Vec<f32> {
-    let n_traces = trace_lengths.len();
-    let total_len: usize = trace_lengths.iter().sum();
-
-    // Length invariants. These are guaranteed by the FFI wrappers, which
-    // validate and return a typed error (see js_indeca / py_api). The
-    // debug_assert keeps the contract loud for internal callers and tests;
-    // the release-mode guard degrades to an empty kernel rather than panicking
-    // across the WASM/PyO3 boundary (a panic there aborts the module).
-    debug_assert_eq!(alphas.len(), n_traces);
-    debug_assert_eq!(baselines.len(), n_traces);
-    debug_assert_eq!(traces.len(), total_len);
-    debug_assert_eq!(spike_trains.len(), total_len);
-
-    if alphas.len() != n_traces
-        || baselines.len() != n_traces
-        || traces.len() != total_len
-        || spike_trains.len() != total_len
-        || kernel_length == 0
-        || total_len == 0
-    {
-        return vec![0.0; kernel_length];
-    }
+    curvature_lambda: f64,
+) -> Result<Vec<f32>, CalabError> {
+    let input = KernelEstInput {
+        traces,
+        spike_trains,
+        alphas,
+        baselines,
+        trace_lengths,
+    };
+    let session = Session::new(&input)?;
+    fista_kernel(
+        &mut [session],
+        kernel_length,
+        max_iters,
+        tol,
+        warm_start,
+        smooth_lambda,
+        curvature_lambda,
+    )
+}
 
-    // Build adjusted targets: y_adj = (y - baseline) / alpha
-    let mut y_adj = vec![0.0_f32; total_len];
-    let mut offset = 0;
-    for i in 0..n_traces {
-        let len = trace_lengths[i];
-        let alpha = alphas[i];
-        let baseline = baselines[i];
-        if alpha.abs() < 1e-20 {
-            // Skip traces with zero alpha (no spikes detected)
+/// One recording session's inputs to [`estimate_free_kernel_parallel`],
+/// laid out as the corresponding arguments of [`estimate_free_kernel`].
+#[derive(Clone, Copy, Debug)]
+pub struct KernelEstInput<'a> {
+    pub traces: &'a [f32],
+    pub spike_trains: &'a [f32],
+    pub alphas: &'a [f64],
+    pub baselines: &'a [f64],
+    pub trace_lengths: &'a [usize],
+}
+
+/// [`estimate_free_kernel`] over several recording sessions, fitting one
+/// kernel shared by all of them.
+///
+/// Each FISTA iteration computes the per-session gradients S^T (S h - y)
+/// independently, in parallel when the `rayon` dependency is enabled (the
+/// `pybindings` feature), and sums them afterwards; every session owns its
+/// scratch buffers, so no locking is needed. The result matches
+/// `estimate_free_kernel` on the concatenated sessions up to float rounding.
+///
+/// Errors as `estimate_free_kernel`, checking each session's lengths.
+pub fn estimate_free_kernel_parallel(
+    session_inputs: &[KernelEstInput],
+    kernel_length: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_start: Option<&[f32]>,
+    smooth_lambda: f64,
+    curvature_lambda: f64,
+) -> Result<Vec<f32>, CalabError> {
+    let mut sessions = session_inputs
+        .iter()
+        .map(Session::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    fista_kernel(
+        &mut sessions,
+        kernel_length,
+        max_iters,
+        tol,
+        warm_start,
+        smooth_lambda,
+        curvature_lambda,
+    )
+}
+
+/// A validated session: adjusted targets plus the scratch buffers for its
+/// share of the gradient.
+struct Session<'a> {
+    spikes: &'a [f32],
+    trace_lengths: &'a [usize],
+    y_adj: Vec<f32>,
+    sh: Vec<f32>,
+    grad: Vec<f64>,
+}
+
+impl<'a> Session<'a> {
+    fn new(input: &KernelEstInput<'a>) -> Result<Self, CalabError> {
+        let n_traces = input.trace_lengths.len();
+        let total_len: usize = input.trace_lengths.iter().sum();
+
+        // Length invariants. The FFI wrappers check these first to give
+        // array-specific messages; internal callers get a typed error rather than
+        // a panic across the WASM/PyO3 boundary (a panic there aborts the module).
+        for got in [input.alphas.len(), input.baselines.len()] {
+            if got != n_traces {
+                return Err(CalabError::DimensionMismatch {
+                    expected: n_traces,
+                    got,
+                });
+            }
+        }
+        for got in [input.traces.len(), input.spike_trains.len()] {
+            if got != total_len {
+                return Err(CalabError::DimensionMismatch {
+                    expected: total_len,
+                    got,
+                });
+            }
+        }
+
+        // Build adjusted targets: y_adj = (y - baseline) / alpha
+        let mut y_adj = vec![0.0_f32; total_len];
+        let mut offset = 0;
+        for i in 0..n_traces {
+            let len = input.trace_lengths[i];
+            let alpha = input.alphas[i];
+            let baseline = input.baselines[i];
+            if alpha.abs() < 1e-20 {
+                // Skip traces with zero alpha (no spikes detected)
+                offset += len;
+                continue;
+            }
+            for j in 0..len {
+                y_adj[offset + j] = ((input.traces[offset + j] as f64 - baseline) / alpha) as f32;
+            }
             offset += len;
-            continue;
         }
-        for j in 0..len {
-            y_adj[offset + j] = ((traces[offset + j] as f64 - baseline) / alpha) as f32;
+
+        Ok(Session {
+            spikes: input.spike_trains,
+            trace_lengths: input.trace_lengths,
+            y_adj,
+            sh: vec![0.0; total_len],
+            grad: Vec::new(),
+        })
+    }
+
+    /// `grad = S^T (S h - y_adj)`, or `S^T S h` when `with_target` is false.
+    fn gradient(&mut self, h: &[f32], with_target: bool) {
+        self.grad.resize(h.len(), 0.0);
+        convolve_spikes_kernel(self.spikes, self.trace_lengths, h, &mut self.sh);
+        if with_target {
+            for (v, &y) in self.sh.iter_mut().zip(&self.y_adj) {
+                *v -= y;
+            }
         }
-        offset += len;
+        adjoint_spikes_kernel(
+            &self.sh,
+            self.spikes,
+            self.trace_lengths,
+            h.len(),
+            &mut self.grad,
+        );
+    }
+}
+
+/// Sum of the sessions' gradients (see [`Session::gradient`]) into `out`.
+fn total_gradient(sessions: &mut [Session], h: &[f32], with_target: bool, out: &mut [f64]) {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        if sessions.len() > 1 {
+            sessions
+                .par_iter_mut()
+                .for_each(|s| s.gradient(h, with_target));
+        } else {
+            sessions.iter_mut().for_each(|s| s.gradient(h, with_target));
+        }
+    }
+    #[cfg(not(feature = "rayon"))]
+    sessions.iter_mut().for_each(|s| s.gradient(h, with_target));
+
+    out.fill(0.0);
+    for s in sessions.iter() {
+        for (o, &g) in out.iter_mut().zip(&s.grad) {
+            *o += g;
+        }
+    }
+}
+
+/// The FISTA solve shared by [`estimate_free_kernel`] and
+/// [`estimate_free_kernel_parallel`].
+fn fista_kernel(
+    sessions: &mut [Session],
+    kernel_length: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_start: Option<&[f32]>,
+    smooth_lambda: f64,
+    curvature_lambda: f64,
+) -> Result<Vec<f32>, CalabError> {
+    if kernel_length == 0 {
+        return Err(CalabError::invalid(
+            "kernel_length",
+            0,
+            "kernel must have at least one sample",
+        ));
+    }
+    if sessions.iter().all(|s| s.y_adj.is_empty()) {
+        return Ok(vec![0.0; kernel_length]);
     }
 
     // FISTA for kernel estimation: min_h (1/2)||y_adj - S*h||^2  s.t. h >= 0
@@ -176,7 +338,6 @@ pub fn estimate_free_kernel(
         for val in v.iter_mut() {
             *val /= norm;
         }
-        let mut sv = vec![0.0_f32; total_len]; // S*v
         let mut stv = vec![0.0_f64; kernel_length]; // S^T S v
         let mut eigenvalue = 1.0_f64;
 
@@ -187,11 +348,8 @@ pub fn estimate_free_kernel(
             for (dst, &src) in v_f32.iter_mut().zip(v.iter()) {
                 *dst = src as f32;
             }
-            // S*v: convolve spikes with v
-            convolve_spikes_kernel(spike_trains, trace_lengths, &v_f32, &mut sv);
-
             // S^T (S*v)
-            adjoint_spikes_kernel(&sv, spike_trains, trace_lengths, kernel_length, &mut stv);
+            total_gradient(sessions, &v_f32, false, &mut stv);
 
             // eigenvalue estimate = ||S^T S v||
             eigenvalue = stv.iter().map(|&x| x * x).sum::<f64>().sqrt();
@@ -207,6 +365,8 @@ pub fn estimate_free_kernel(
         }
         eigenvalue.max(1.0)
     };
+    // ||D2^T D2|| <= 16, so the curvature term adds at most 2 * 16 * λ_curv.
+    let lipschitz = lipschitz + 32.0 * curvature_lambda.max(0.0);
     let step_size = 1.0 / lipschitz;
 
     let mut h = vec![0.0_f32; kernel_length];
@@ -220,29 +380,16 @@ pub fn estimate_free_kernel(
     let mut gradient = vec![0.0_f64; kernel_length];
     let mut t_fista = 1.0_f64;
 
-    // Working buffer for S*h (convolution result)
-    let mut sh = vec![0.0_f32; total_len];
-
     let mut z = vec![0.0_f64; kernel_length];
+    let mut curvature = vec![0.0_f64; kernel_length.saturating_sub(2)];
 
     for iter in 0..max_iters {
-        // Forward: S*h (convolve each trace's spikes with h)
-        convolve_spikes_kernel(spike_trains, trace_lengths, &h_prev, &mut sh);
-
-        // Residual: r = S*h - y_adj (compute in-place in sh)
-        for i in 0..total_len {
-            sh[i] -= y_adj[i];
+        // Gradient: S^T * (S*h - y_adj), summed over sessions
+        total_gradient(sessions, &h_prev, true, &mut gradient);
+        if curvature_lambda > 0.0 {
+            add_curvature_gradient(&h_prev, curvature_lambda, &mut gradient, &mut curvature);
         }
 
-        // Gradient: S^T * r
-        adjoint_spikes_kernel(
-            &sh,
-            spike_trains,
-            trace_lengths,
-            kernel_length,
-            &mut gradient,
-        );
-
         // Proximal gradient step: gradient descent on data-fidelity, then
         // TV proximal operator, then non-negativity projection.
         for k in 0..kernel_length {
@@ -283,7 +430,28 @@ pub fn estimate_free_kernel(
         t_fista = t_new;
     }
 
-    h
+    Ok(h)
+}
+
+/// Add the gradient of `lambda * sum_k (h[k+1] - 2 h[k] + h[k-1])^2`,
+/// `2 lambda D2^T D2 h`, to `gradient`. In the interior this is
+/// `2 lambda (h[k-2] - 4 h[k-1] + 6 h[k] - 4 h[k+1] + h[k+2])`; the first and
+/// last two taps see only the differences that exist. `d2` is scratch of
+/// length `h.len() - 2`.
+fn add_curvature_gradient(h: &[f32], lambda: f64, gradient: &mut [f64], d2: &mut [f64]) {
+    let n = h.len();
+    if n < 3 {
+        return;
+    }
+    for k in 0..n - 2 {
+        d2[k] = h[k + 2] as f64 - 2.0 * h[k + 1] as f64 + h[k] as f64;
+    }
+    let scale = 2.0 * lambda;
+    for (k, &d) in d2.iter().enumerate() {
+        gradient[k] += scale * d;
+        gradient[k + 1] -= 2.0 * scale * d;
+        gradient[k + 2] += scale * d;
+    }
 }
 
 /// Adjoint of spike convolution: output[k] += sum_t input[t] * s[t-k].
@@ -310,7 +478,12 @@ fn adjoint_spikes_kernel(
 }
 
 /// Convolve spike trains with kernel h: output[t] = sum_k h[k] * s[t-k].
-fn convolve_spikes_kernel(spikes: &[f32], trace_lengths: &[usize], h: &[f32], output: &mut [f32]) {
+pub(crate) fn convolve_spikes_kernel(
+    spikes: &[f32],
+    trace_lengths: &[usize],
+    h: &[f32],
+    output: &mut [f32],
+) {
     let k_len = h.len();
     let mut offset = 0;
     for &len in trace_lengths {
@@ -326,10 +499,117 @@ fn convolve_spikes_kernel(spikes: &[f32], trace_lengths: &[usize], h: &[f32], ou
     }
 }
 
+/// Diagnostics for judging whether an estimated kernel is trustworthy
+/// (see [`kernel_quality`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct KernelQuality {
+    /// Fraction of the variance of `h` explained by the bi-exponential with the
+    /// given time constants, amplitude fit by least squares (1 = exact shape).
+    pub pve_biexp: f64,
+    /// Peak over mean, `max(h) / mean(h)`; NaN when the mean is not positive.
+    pub peak_sharpness: f64,
+    /// Fraction of samples below `-NEGATIVITY_EPS`.
+    pub negativity_fraction: f64,
+    /// Time of the peak sample in seconds.
+    pub peak_time_s: f64,
+    /// Euclidean norm of `h`.
+    pub l2_norm: f64,
+}
+
+/// Samples at or above this negative value count as zero, not negative.
+const NEGATIVITY_EPS: f32 = 1e-6;
+
+/// Summarize the shape of an estimated kernel `h` sampled at `fs` against
+/// the bi-exponential with `tau_rise` / `tau_decay` (seconds), e.g. the
+/// result of `fit_biexponential` on it. A low `pve_biexp` or a large
+/// `negativity_fraction` means the free kernel does not look like a calcium
+/// transient. An empty `h` gives NaN everywhere except `l2_norm = 0`.
+pub fn kernel_quality(h: &[f32], tau_rise: f64, tau_decay: f64, fs: f64) -> KernelQuality {
+    let n = h.len();
+    let l2_norm = h.iter().map(|&v| v as f64 * v as f64).sum::<f64>().sqrt();
+    if n == 0 {
+        return KernelQuality {
+            pve_biexp: f64::NAN,
+            peak_sharpness: f64::NAN,
+            negativity_fraction: f64::NAN,
+            peak_time_s: f64::NAN,
+            l2_norm,
+        };
+    }
+
+    let template = crate::biexp_fit::eval_biexp(tau_rise, tau_decay, 1.0, fs, n);
+    let (mut th, mut tt) = (0.0_f64, 0.0_f64);
+    for (&hv, &tv) in h.iter().zip(&template) {
+        th += hv as f64 * tv as f64;
+        tt += tv as f64 * tv as f64;
+    }
+    let beta = if tt > 0.0 { th / tt } else { 0.0 };
+    let mean = h.iter().map(|&v| v as f64).sum::<f64>() / n as f64;
+    let (mut ss_res, mut ss_tot) = (0.0_f64, 0.0_f64);
+    for (&hv, &tv) in h.iter().zip(&template) {
+        let r = hv as f64 - beta * tv as f64;
+        ss_res += r * r;
+        ss_tot += (hv as f64 - mean) * (hv as f64 - mean);
+    }
+    let pve_biexp = if ss_tot > 0.0 {
+        1.0 - ss_res / ss_tot
+    } else {
+        f64::NAN
+    };
+
+    let (peak_idx, &peak) = h
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .expect("h is non-empty");
+    KernelQuality {
+        pve_biexp,
+        peak_sharpness: if mean > 0.0 {
+            peak as f64 / mean
+        } else {
+            f64::NAN
+        },
+        negativity_fraction: h.iter().filter(|&&v| v < -NEGATIVITY_EPS).count() as f64 / n as f64,
+        peak_time_s: peak_idx as f64 / fs,
+        l2_norm,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn kernel_quality_scores_biexp_shape() {
+        let fs = 30.0;
+        let h = crate::biexp_fit::eval_biexp(0.05, 0.5, 0.8, fs, 60);
+        let q = kernel_quality(&h, 0.05, 0.5, fs);
+        assert!(q.pve_biexp > 0.99, "pve {}", q.pve_biexp);
+        assert_eq!(q.negativity_fraction, 0.0);
+        let peak = h.iter().cloned().fold(f32::MIN, f32::max) as f64;
+        let mean = h.iter().map(|&v| v as f64).sum::<f64>() / h.len() as f64;
+        assert!((q.peak_sharpness - peak / mean).abs() < 1e-9);
+        // Analytic peak of exp(-t/0.5) - exp(-t/0.05) is near 0.128 s.
+        assert!(
+            (q.peak_time_s - 0.128).abs() <= 1.0 / fs,
+            "{}",
+            q.peak_time_s
+        );
+
+        // Wrong time constants and an oscillating tail both degrade the scores.
+        let mut bad = h.clone();
+        for (i, v) in bad.iter_mut().enumerate().skip(20) {
+            *v = if i % 2 == 0 { 0.3 } else { -0.3 };
+        }
+        let q_bad = kernel_quality(&bad, 0.05, 0.5, fs);
+        assert!(q_bad.pve_biexp < 0.5, "pve {}", q_bad.pve_biexp);
+        assert!((q_bad.negativity_fraction - 20.0 / 60.0).abs() < 1e-12);
+        assert!(kernel_quality(&h, 0.3, 3.0, fs).pve_biexp < q.pve_biexp);
+
+        let empty = kernel_quality(&[], 0.05, 0.5, fs);
+        assert!(empty.pve_biexp.is_nan() && empty.l2_norm == 0.0);
+    }
+
     #[test]
     fn prox_tv_reduces_total_variation() {
         // Noisy step function: TV should decrease after prox
@@ -435,7 +715,9 @@ mod tests {
             1e-5,
             None,
             0.0,
-        );
+            0.0,
+        )
+        .unwrap();
 
         // Normalize both kernels to unit peak for comparison
         let est_peak = est_kernel.iter().cloned().fold(0.0_f32, f32::max);
@@ -479,7 +761,9 @@ mod tests {
             1e-4,
             None,
             0.0,
-        );
+            0.0,
+        )
+        .unwrap();
 
         for (i, &v) in kernel.iter().enumerate() {
             assert!(
@@ -501,14 +785,81 @@ mod tests {
         let baselines = vec![0.0, 0.0, 0.0];
 
         let kernel = estimate_free_kernel(
-            &traces, &spikes, &alphas, &baselines, &lengths, 20, 50, 1e-4, None, 0.0,
-        );
+            &traces, &spikes, &alphas, &baselines, &lengths, 20, 50, 1e-4, None, 0.0, 0.0,
+        )
+        .unwrap();
         assert_eq!(kernel.len(), 20);
     }
 
+    #[test]
+    fn parallel_sessions_match_concatenated_estimate() {
+        let k_len = 30;
+        let true_kernel = make_exponential_kernel(0.02, 0.4, 30.0, k_len);
+        let trace_len = 200;
+        let mut traces = Vec::new();
+        let mut spikes = Vec::new();
+        for (i, positions) in [[10, 60, 130], [20, 80, 160], [30, 100, 170], [5, 90, 150]]
+            .iter()
+            .enumerate()
+        {
+            let mut trace = vec![0.5 * i as f32; trace_len];
+            let mut s = vec![0.0_f32; trace_len];
+            for &pos in positions {
+                s[pos] = 1.0;
+                for (k, &hv) in true_kernel.iter().enumerate().take(trace_len - pos) {
+                    trace[pos + k] += 2.0 * hv;
+                }
+            }
+            traces.extend_from_slice(&trace);
+            spikes.extend_from_slice(&s);
+        }
+        let alphas = [2.0; 4];
+        let baselines = [0.0, 0.5, 1.0, 1.5];
+        let lengths = [trace_len; 4];
+
+        let serial = estimate_free_kernel(
+            &traces, &spikes, &alphas, &baselines, &lengths, k_len, 300, 1e-6, None, 0.0, 0.0,
+        )
+        .unwrap();
+        // Two sessions of two traces each.
+        let half = 2 * trace_len;
+        let sessions = [
+            KernelEstInput {
+                traces: &traces[..half],
+                spike_trains: &spikes[..half],
+                alphas: &alphas[..2],
+                baselines: &baselines[..2],
+                trace_lengths: &lengths[..2],
+            },
+            KernelEstInput {
+                traces: &traces[half..],
+                spike_trains: &spikes[half..],
+                alphas: &alphas[2..],
+                baselines: &baselines[2..],
+                trace_lengths: &lengths[2..],
+            },
+        ];
+        let parallel =
+            estimate_free_kernel_parallel(&sessions, k_len, 300, 1e-6, None, 0.0, 0.0).unwrap();
+        for (a, b) in serial.iter().zip(&parallel) {
+            assert!((a - b).abs() < 1e-4, "{a} vs {b}");
+        }
+
+        let bad = KernelEstInput {
+            alphas: &alphas[..1],
+            ..sessions[0]
+        };
+        assert!(estimate_free_kernel_parallel(&[bad], k_len, 10, 1e-4, None, 0.0, 0.0).is_err());
+        assert_eq!(
+            estimate_free_kernel_parallel(&[], k_len, 10, 1e-4, None, 0.0, 0.0).unwrap(),
+            vec![0.0; k_len]
+        );
+    }
+
     #[test]
     fn empty_input() {
-        let kernel = estimate_free_kernel(&[], &[], &[], &[], &[], 10, 100, 1e-4, None, 0.0);
+        let kernel =
+            estimate_free_kernel(&[], &[], &[], &[], &[], 10, 100, 1e-4, None, 0.0, 0.0).unwrap();
         assert_eq!(kernel.len(), 10);
         assert!(kernel.iter().all(|&v| v == 0.0));
     }
@@ -571,7 +922,9 @@ mod tests {
             1e-4,
             None,
             0.0,
-        );
+            0.0,
+        )
+        .unwrap();
 
         let peak = kernel.iter().cloned().fold(0.0_f32, f32::max);
         assert!(
@@ -640,7 +993,9 @@ mod tests {
             1e-6,
             None,
             0.0,
-        );
+            0.0,
+        )
+        .unwrap();
 
         let kernel_smooth = estimate_free_kernel(
             &all_traces,
@@ -653,7 +1008,9 @@ mod tests {
             1e-6,
             None,
             0.001,
-        );
+            0.0,
+        )
+        .unwrap();
 
         // Total variation = sum of |h[k+1] - h[k]|
         let tv = |h: &[f32]| -> f64 {
@@ -676,4 +1033,92 @@ mod tests {
         let peak = kernel_smooth.iter().cloned().fold(0.0_f32, f32::max);
         assert!(peak > 0.0, "Smoothed kernel should have positive peak");
     }
+
+    /// With few spikes and noisy traces the free kernel picks up noise as
+    /// wiggles; the curvature penalty removes most of them.
+    #[test]
+    fn curvature_penalty_reduces_second_differences() {
+        let k_len = 40;
+        let trace_len = 400;
+        let true_kernel: Vec<f32> = (0..k_len)
+            .map(|k| (-(k as f64) / 12.0).exp() as f32)
+            .collect();
+
+        let mut traces = Vec::new();
+        let mut spikes = Vec::new();
+        for i in 0..2 {
+            let mut s = vec![0.0_f32; trace_len];
+            for pos in [30 + 11 * i, 180 + 7 * i, 320] {
+                s[pos] = 1.0;
+            }
+            let mut trace = vec![0.0_f32; trace_len];
+            for t in 0..trace_len {
+                for k in 0..k_len.min(t + 1) {
+                    trace[t] += s[t - k] * true_kernel[k];
+                }
+                trace[t] += 0.15 * ((t as f64 * 2.3 + i as f64).sin() as f32);
+            }
+            traces.extend_from_slice(&trace);
+            spikes.extend_from_slice(&s);
+        }
+        let estimate = |curvature_lambda| {
+            estimate_free_kernel(
+                &traces,
+                &spikes,
+                &[1.0, 1.0],
+                &[0.0, 0.0],
+                &[trace_len, trace_len],
+                k_len,
+                1000,
+                1e-7,
+                None,
+                0.0,
+                curvature_lambda,
+            )
+            .unwrap()
+        };
+        let d2_norm = |h: &[f32]| -> f64 {
+            h.windows(3)
+                .map(|w| (w[2] as f64 - 2.0 * w[1] as f64 + w[0] as f64).powi(2))
+                .sum::<f64>()
+                .sqrt()
+        };
+
+        let plain = estimate(0.0);
+        let smooth = estimate(1.0);
+        let (d2_plain, d2_smooth) = (d2_norm(&plain), d2_norm(&smooth));
+        assert!(
+            d2_smooth < 0.5 * d2_plain,
+            "second differences {d2_smooth:.4} vs {d2_plain:.4}"
+        );
+        // Still the decaying shape, not flattened away.
+        assert!(smooth[2] > smooth[30] && smooth[2] > 0.5, "{smooth:?}");
+    }
+
+    #[test]
+    fn curvature_gradient_matches_finite_differences() {
+        let h = [0.3_f32, 1.0, 0.7, 0.9, 0.2, 0.4];
+        let lambda = 0.5;
+        let penalty = |h: &[f64]| -> f64 {
+            lambda
+                * h.windows(3)
+                    .map(|w| (w[2] - 2.0 * w[1] + w[0]).powi(2))
+                    .sum::<f64>()
+        };
+        let mut grad = vec![0.0_f64; h.len()];
+        let mut scratch = vec![0.0_f64; h.len() - 2];
+        add_curvature_gradient(&h, lambda, &mut grad, &mut scratch);
+        let h64: Vec<f64> = h.iter().map(|&v| v as f64).collect();
+        for k in 0..h.len() {
+            let (mut up, mut down) = (h64.clone(), h64.clone());
+            up[k] += 1e-6;
+            down[k] -= 1e-6;
+            let numeric = (penalty(&up) - penalty(&down)) / 2e-6;
+            assert!(
+                (grad[k] - numeric).abs() < 1e-6,
+                "k={k}: {} vs {numeric}",
+                grad[k]
+            );
+        }
+    }
 }
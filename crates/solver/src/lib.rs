@@ -1,21 +1,77 @@
+// Every std-backed module below sits behind the default `std` feature;
+// without it the crate is `no_std` + `alloc`. `no_alloc` adds the
+// fixed-capacity `FixedSolver<N>` from `fixed` on top of whatever else is
+// enabled.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod admm;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+mod ar_model;
+#[cfg(feature = "std")]
 mod banded;
+#[cfg(feature = "std")]
 pub(crate) mod baseline;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub(crate) mod biexp_fit;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod debias;
+mod error;
+#[cfg(feature = "std")]
 mod fft;
+#[cfg(feature = "std")]
 mod filter;
+#[cfg(feature = "std")]
 mod fista;
+#[cfg(feature = "no_alloc")]
+mod fixed;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub(crate) mod indeca;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
 mod kernel;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub(crate) mod kernel_est;
+#[cfg(feature = "std")]
+mod path;
+#[cfg(feature = "std")]
 pub(crate) mod peak_seed;
+#[cfg(feature = "std")]
+mod pfista;
+#[cfg(feature = "std")]
+pub mod presets;
+#[cfg(feature = "std")]
+pub mod rng;
+#[cfg(feature = "std")]
+mod sfista;
+#[cfg(feature = "std")]
 pub(crate) mod simulate;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(all(test, feature = "std"))]
+mod test_util;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub(crate) mod threshold;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub(crate) mod upsample;
+#[cfg(feature = "std")]
+pub mod validate;
 
 #[cfg(feature = "pybindings")]
 mod py_api;
@@ -25,20 +81,90 @@ mod js_indeca;
 #[cfg(feature = "jsbindings")]
 mod js_simulate;
 
-use banded::BandedAR2;
+#[cfg(feature = "std")]
+pub use ar_model::ARpModel;
+#[cfg(feature = "std")]
+pub use banded::check_adjoint_op;
+#[cfg(feature = "std")]
+use banded::{BandedAR1, BandedAR2};
+#[cfg(feature = "std")]
+pub use builder::{InDecaBuilder, InDecaConfig, SolverBuilder};
+pub use error::CalabError;
+#[cfg(feature = "std")]
 use filter::BandpassFilter;
+#[cfg(feature = "std")]
+pub use filter::{
+    median_filter_1d, savitzky_golay, ButterworthFilter, ExponentialSmoothFilter, FilterType,
+    ForwardBackwardExponential,
+};
+#[cfg(feature = "no_alloc")]
+pub use fixed::FixedSolver;
+#[cfg(feature = "std")]
+pub use history::IterRecord;
+#[cfg(feature = "std")]
 use kernel::{build_kernel, compute_lipschitz};
+#[cfg(feature = "std")]
+pub use kernel::{
+    build_triple_exp_kernel, estimate_lipschitz_power_iter, suggest_kernel_length, KernelType,
+};
+#[cfg(feature = "std")]
+pub use sfista::VRMode;
+#[cfg(feature = "std")]
 use std::io::{Cursor, Read};
 
 #[cfg(feature = "jsbindings")]
 use wasm_bindgen::prelude::*;
 
+/// Crate-internal entry points for `benches/solver_bench.rs`. Not part of
+/// the public API; may change without notice.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::baseline::subtract_rolling_baseline;
+
+    /// Drop the solver's current FFT plans so the next `set_trace` rebuilds
+    /// them; with `keep_cache = false` the per-length plan cache is emptied
+    /// too, so the rebuild plans from scratch.
+    pub fn reset_fft_plans(solver: &mut crate::Solver, keep_cache: bool) {
+        solver.fft.invalidate();
+        if !keep_cache {
+            solver.fft.plan_cache().clear();
+        }
+    }
+
+    /// InDeCa threshold search at the original rate (no upsampling, alpha
+    /// uncapped, automatic grid); returns the selected threshold.
+    pub fn threshold_search(
+        s_relaxed: &[f32],
+        y: &[f32],
+        tau_rise: f64,
+        tau_decay: f64,
+        fs: f64,
+    ) -> f64 {
+        let banded = crate::banded::BandedAR2::new(tau_rise, tau_decay, fs)
+            .expect("threshold_search needs stable taus");
+        crate::threshold::threshold_search(
+            s_relaxed,
+            y,
+            &banded,
+            tau_decay,
+            fs,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        )
+        .threshold
+    }
+}
+
 /// Index of the first non-finite (NaN or ±infinity) value in `data`, if any.
 ///
 /// Used by the FFI boundaries (PyO3 / WASM) to reject non-finite input traces
 /// up front. A NaN would otherwise propagate silently — e.g. `total_cmp` sorts
 /// NaN last, corrupting the rolling-baseline percentile — and yield garbage
 /// alpha/PVE results that are indistinguishable from a legitimately hard trace.
+#[cfg(feature = "std")]
 pub(crate) fn first_nonfinite(data: &[f32]) -> Option<usize> {
     data.iter().position(|v| !v.is_finite())
 }
@@ -47,20 +173,62 @@ pub(crate) fn first_nonfinite(data: &[f32]) -> Option<usize> {
 /// is smoothed as `ema = W·raw + (1-W)·ema` purely to keep the displayed value
 /// steady across iterations; it does not enter the solve. Named here so the
 /// smoothing strength is a single, documented knob rather than a bare literal.
+#[cfg(feature = "std")]
 const BASELINE_EMA_WEIGHT: f64 = 0.3;
 
+/// Lower bound on a `set_rolling_lipschitz` estimate, as a fraction of the
+/// full-trace constant of the current convolution mode. A short window (or
+/// a power vector that has not warmed up yet) underestimates the operator
+/// norm; the floor caps the resulting step at `1 / (fraction · L)`.
+#[cfg(feature = "std")]
+const ROLLING_LIPSCHITZ_FLOOR: f64 = 0.8;
+
 /// Convolution mode for forward/adjoint operations in FISTA.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "jsbindings", wasm_bindgen)]
 pub enum ConvMode {
     /// FFT-based O(T log T) per call — the original implementation.
     Fft = 0,
     /// Banded AR(2) recursion O(T) per call — faster for long traces.
     BandedAR2 = 1,
+    /// Banded AR(1) single-exponential recursion O(T) — for indicators with a
+    /// negligible rise time. Selected automatically by `set_params` when
+    /// `tau_rise == 0.0`.
+    BandedAR1 = 2,
+    /// General AR(p) recursion (`ARpModel`) O(T·p) — for indicators with
+    /// three or more exponential components. Selected by `set_ar_params`; with
+    /// a double-exponential kernel it runs the equivalent AR(2) model.
+    BandedARp = 3,
+    /// FFT by overlap-add in blocks of `4 * kernel_len` — O(kernel_len) FFT
+    /// buffers regardless of trace length. Traces up to 16 kernel lengths are
+    /// convolved in one piece, as in `Fft`. Selected in place of `Fft` under
+    /// `MemoryMode::Low`.
+    FftOverlapAdd = 4,
+}
+
+#[cfg(feature = "std")]
+impl ConvMode {
+    /// Whether this mode runs on the FFT engine.
+    pub(crate) fn is_fft(self) -> bool {
+        matches!(self, ConvMode::Fft | ConvMode::FftOverlapAdd)
+    }
+}
+
+/// Memory budget for the FFT engine, set with `set_memory_mode`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "jsbindings", wasm_bindgen)]
+pub enum MemoryMode {
+    /// Whole-trace FFTs, buffers of `next_power_of_two(T + kernel_len)`
+    /// (default).
+    Standard = 0,
+    /// Overlap-add FFTs (`ConvMode::FftOverlapAdd`) for long recordings.
+    Low = 1,
 }
 
 /// Constraint type for the proximal step.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "jsbindings", wasm_bindgen)]
 pub enum Constraint {
     /// Current: max(0, z - threshold) — L1 + non-negativity.
@@ -69,6 +237,113 @@ pub enum Constraint {
     Box01 = 1,
 }
 
+/// Normalization of the kernel and of the banded engines' impulse response,
+/// which sets the units of the solution amplitudes.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "jsbindings", wasm_bindgen)]
+pub enum KernelNorm {
+    /// Peak of 1.0: a unit spike peaks at 1.0 regardless of fs (default).
+    Peak = 0,
+    /// Unit area, `sum(h) / fs = 1`: amplitudes are integrated fluorescence.
+    Area = 1,
+    /// Unit L2 norm, `sum(h^2) = 1`.
+    L2 = 2,
+    /// Raw AR recursion response (unit impulse into
+    /// `c[t] = g1*c[t-1] + g2*c[t-2] + s[t-1]`), whose peak grows with fs.
+    /// Triple-exponential kernels have no AR form and keep unit peak.
+    None = 3,
+}
+
+/// Length of the explicit kernel built by `set_params` /
+/// `set_triple_exp_params`, i.e. the one the FFT modes convolve with and
+/// `get_kernel` returns. The banded engines run the AR recursion and are not
+/// truncated.
+///
+/// Not wasm-bindgen exportable (data-carrying variants); set from Rust.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum KernelLengthMode {
+    /// Until the decay drops below 1e-6 of peak (`suggest_kernel_length`).
+    #[default]
+    Auto,
+    /// Exactly this many frames (at least 2).
+    FixedFrames(usize),
+    /// This duration in seconds, rounded up to whole frames (at least 2).
+    FixedSeconds(f64),
+}
+
+#[cfg(feature = "std")]
+impl KernelLengthMode {
+    /// Frame count of a fixed mode at `fs`; `None` for `Auto`.
+    pub fn fixed_frames(&self, fs: f64) -> Option<usize> {
+        match *self {
+            KernelLengthMode::Auto => None,
+            KernelLengthMode::FixedFrames(n) => Some(n.max(2)),
+            KernelLengthMode::FixedSeconds(s) => Some(((s * fs).ceil() as usize).max(2)),
+        }
+    }
+}
+
+/// Iteration scheme used by `step_batch`.
+///
+/// Not wasm-bindgen exportable (data-carrying variant); set from Rust or via
+/// the PyO3 `Solver.set_solver_mode`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SolverMode {
+    /// Accelerated proximal gradient with adaptive restart (default).
+    Fista,
+    /// Linearized ADMM on the split `x = z`: one gradient step for `x`,
+    /// soft-threshold + constraint for `z`, scaled dual update `u += x - z`.
+    /// `rho <= 0.0` selects the default penalty `0.1 * lipschitz_constant`.
+    Admm { rho: f64 },
+    /// Stochastic block proximal gradient (see `sfista`): each step updates
+    /// the solution from one random `block_size`-sample block, so per-step
+    /// cost is O(block_size log block_size) regardless of trace length.
+    StochasticFista {
+        block_size: usize,
+        variance_reduction: VRMode,
+    },
+    /// FISTA with a per-coordinate step from the diagonal of K^T K (see
+    /// `pfista`), which lengthens the steps of the weakly observed frames at
+    /// the end of the trace.
+    Diagonal,
+}
+
+/// Convergence test applied by FISTA's `step_batch`.
+///
+/// Not wasm-bindgen exportable (data-carrying variant); set from Rust or via
+/// the PyO3 `Solver.set_stopping_criterion`. ADMM keeps its own test.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StoppingCriterion {
+    /// Relative change of the iterate, `||x_{k+1} - x_k|| < tol * ||x_k||`
+    /// (default). Cheap, but not a certificate of optimality.
+    PrimalResidual,
+    /// Relative duality gap `gap / (|f(x)| + 1) < tol`, evaluated every
+    /// `max_iters_between_checks` iterations. Each check costs an extra forward
+    /// and adjoint convolution; the gap bounds the objective's distance from
+    /// the optimum.
+    DualGap { max_iters_between_checks: u32 },
+}
+
+/// Penalty on the solution in the FISTA objective.
+///
+/// Not wasm-bindgen exportable (data-carrying variant); set from Rust, via the
+/// PyO3 `Solver.set_regularizer`, or from JS with `set_elastic_net`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Regularizer {
+    /// `lambda * G_dc * ||x||_1` with the lambda from `set_params` (default).
+    L1,
+    /// Adds a ridge term `lambda2/2 * ||x||^2` to the L1 penalty, spreading
+    /// weight across correlated candidates instead of picking one. `lambda1`
+    /// replaces the L1 lambda (scaled by G_dc as usual); `lambda2` is unscaled
+    /// and must be non-negative.
+    ElasticNet { lambda1: f64, lambda2: f64 },
+}
+
 /// FISTA solver for calcium deconvolution.
 ///
 /// Minimizes (1/2)||y - K*s - b||^2 + lambda*G_dc*||s||_1 subject to s >= 0,
@@ -77,6 +352,7 @@ pub enum Constraint {
 /// so the sparsity slider is effective across all kernel configurations.
 ///
 /// Pre-allocated buffers grow but never shrink to prevent WASM memory fragmentation.
+#[cfg(feature = "std")]
 #[cfg_attr(feature = "jsbindings", wasm_bindgen)]
 pub struct Solver {
     // Parameters
@@ -84,6 +360,10 @@ pub struct Solver {
     tau_decay: f64,
     lambda: f64,
     fs: f64,
+    kernel_type: KernelType,
+    kernel_norm: KernelNorm,
+    kernel_length_mode: KernelLengthMode,
+    memory_mode: MemoryMode,
 
     // Pre-allocated working buffers (f32 to halve memory per worker)
     pub(crate) trace: Vec<f32>,
@@ -104,6 +384,8 @@ pub struct Solver {
     pub(crate) prev_objective: f64,
     pub(crate) tolerance: f64,
     pub(crate) lipschitz_constant: f64,
+    /// Ridge weight of `Regularizer::ElasticNet` (0 for pure L1).
+    pub(crate) lambda2: f64,
 
     // Baseline and kernel scaling
     pub(crate) baseline: f64,
@@ -114,15 +396,39 @@ pub struct Solver {
     // Convolution engines
     pub(crate) fft: fft::FftConvolver,
     pub(crate) banded: BandedAR2,
+    pub(crate) banded_ar1: BandedAR1,
+    pub(crate) arp: ARpModel,
     pub(crate) conv_mode: ConvMode,
     pub(crate) constraint: Constraint,
+    pub(crate) solver_mode: SolverMode,
+    pub(crate) stopping_criterion: StoppingCriterion,
+    pub(crate) line_search: bool,
+    /// Backtracking multiplier on `lipschitz_constant` (≥ 1); reset per trace.
+    pub(crate) ls_scale: f64,
+    /// Minimum spacing between nonzero solution entries (0/1 = off).
+    pub(crate) refractory_frames: usize,
+    /// Relative margin the adaptive-restart inner product must exceed (0 = any positive).
+    pub(crate) restart_threshold: f64,
+    pub(crate) admm_dual: Vec<f32>, // scaled dual variable u (ADMM mode only)
+    pub(crate) sfista: sfista::StochasticState, // stochastic mode only
+    pub(crate) pfista: pfista::DiagonalState, // diagonal mode only
     pub(crate) reconvolution_stale: bool, // dirty flag for lazy reconvolution
+    pub(crate) debiased: bool,      // solution refitted by run_debiasing (debias.rs)
+    /// `set_rolling_lipschitz` window (0 = off) and its carried power vector.
+    rolling_lipschitz_window: usize,
+    rolling_power_vec: Vec<f32>,
+    history: history::History, // enable_history records (history.rs)
 
     // Bandpass filter
     bandpass: BandpassFilter,
+    filter_type: FilterType,
     pub(crate) filtered: bool, // true after apply_filter() succeeded on current trace
+
+    // Findings from the last set_params (see validate.rs)
+    param_diagnostics: Vec<validate::Diagnostic>,
 }
 
+#[cfg(feature = "std")]
 #[cfg_attr(feature = "jsbindings", wasm_bindgen)]
 impl Solver {
     /// Create a new Solver with default parameters.
@@ -137,6 +443,10 @@ impl Solver {
             tau_decay: 0.4,
             lambda: 0.01,
             fs: 30.0,
+            kernel_type: KernelType::DoubleExp,
+            kernel_norm: KernelNorm::Peak,
+            kernel_length_mode: KernelLengthMode::Auto,
+            memory_mode: MemoryMode::Standard,
             trace: Vec::new(),
             solution: Vec::new(),
             solution_prev: Vec::new(),
@@ -151,17 +461,35 @@ impl Solver {
             prev_objective: f64::INFINITY,
             tolerance: 1e-4,
             lipschitz_constant: 1.0,
+            lambda2: 0.0,
             baseline: 0.0,
             baseline_ema: 0.0,
             baseline_ema_init: false,
             kernel_dc_gain: 1.0,
             fft: fft::FftConvolver::new(),
-            banded: BandedAR2::new(0.02, 0.4, 30.0),
+            banded: BandedAR2::new(0.02, 0.4, 30.0).expect("default taus are stable"),
+            banded_ar1: BandedAR1::new(0.4, 30.0),
+            arp: ARpModel::from_time_constants(&[0.4, 0.02], 30.0),
             conv_mode: ConvMode::Fft,
             constraint: Constraint::NonNegative,
+            solver_mode: SolverMode::Fista,
+            stopping_criterion: StoppingCriterion::PrimalResidual,
+            line_search: false,
+            ls_scale: 1.0,
+            rolling_lipschitz_window: 0,
+            rolling_power_vec: Vec::new(),
+            history: history::History::default(),
+            refractory_frames: 0,
+            restart_threshold: 0.0,
+            admm_dual: Vec::new(),
+            sfista: sfista::StochasticState::new(),
+            pfista: pfista::DiagonalState::new(),
             reconvolution_stale: true,
+            debiased: false,
             bandpass: BandpassFilter::new(),
+            filter_type: FilterType::Bandpass,
             filtered: false,
+            param_diagnostics: Vec::new(),
         };
 
         // Build kernel with default params
@@ -173,35 +501,149 @@ impl Solver {
     }
 
     /// Update solver parameters and rebuild kernel.
+    ///
+    /// `tau_rise = 0.0` is a sentinel for a single-exponential kernel and
+    /// switches to the `BandedAR1` engine; a positive `tau_rise` while in
+    /// `BandedAR1` mode switches back to `BandedAR2`.
+    ///
+    /// The parameters are checked with `validate` and the findings kept in
+    /// `param_diagnostics()`, together with an error when the BandedAR2
+    /// engine rejects the taus as unstable (it then keeps its previous
+    /// coefficients). Debug builds also print warnings and panic on errors.
     pub fn set_params(&mut self, tau_rise: f64, tau_decay: f64, lambda: f64, fs: f64) {
+        if tau_rise == 0.0 {
+            self.conv_mode = ConvMode::BandedAR1;
+        } else if self.conv_mode == ConvMode::BandedAR1 {
+            self.conv_mode = ConvMode::BandedAR2;
+        }
         self.tau_rise = tau_rise;
         self.tau_decay = tau_decay;
         self.lambda = lambda;
         self.fs = fs;
+        self.kernel_type = KernelType::DoubleExp;
         self.kernel = build_kernel(tau_rise, tau_decay, fs);
-        self.kernel_dc_gain = self.kernel.iter().map(|&k| k as f64).sum();
+        self.apply_kernel_length();
+        self.apply_kernel_norm();
         self.bandpass.update_cutoffs(tau_rise, tau_decay, fs);
 
         // Update convolution engines (only the active one + compute Lipschitz)
+        let mut engine_error = None;
         match self.conv_mode {
             ConvMode::BandedAR2 => {
-                self.banded.update(tau_rise, tau_decay, fs);
+                // Unstable taus keep the previous coefficients; the failure
+                // is reported in `param_diagnostics()` below.
+                engine_error = self.banded.update(tau_rise, tau_decay, fs).err();
+            }
+            ConvMode::BandedAR1 => {
+                self.banded_ar1.update(tau_decay, fs);
+            }
+            ConvMode::BandedARp => {
+                self.update_arp_from_params();
             }
-            ConvMode::Fft => {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
                 // banded will be updated lazily if conv_mode switches
             }
         }
         self.lipschitz_constant = self.current_lipschitz();
+        self.refresh_fft_kernel();
+
+        self.param_diagnostics = validate::validate_with_lipschitz(
+            tau_rise,
+            tau_decay,
+            lambda,
+            fs,
+            self.lipschitz_constant,
+        );
+        if let Some(len) = self.kernel_length_mode.fixed_frames(fs) {
+            self.param_diagnostics
+                .extend(validate::check_kernel_length(len, tau_decay, fs));
+        }
+        if let Some(err) = engine_error {
+            self.param_diagnostics
+                .push(validate::Diagnostic::error("tau_decay", err.to_string()));
+        }
+        #[cfg(debug_assertions)]
+        for d in &self.param_diagnostics {
+            match d.severity {
+                validate::Severity::Warning => eprintln!("warning: {}: {}", d.field, d.message),
+                validate::Severity::Error => panic!("invalid {}: {}", d.field, d.message),
+            }
+        }
+    }
+
+    /// Switch to a triple-exponential kernel (`build_triple_exp_kernel`) with
+    /// a fast and a slow decay component.
+    ///
+    /// The banded engines only model AR(1)/AR(2) kernels, so this selects
+    /// `ConvMode::Fft` (and `set_conv_mode` keeps it) until the next
+    /// `set_params`. Bandpass cutoffs follow (tau_rise, tau_slow).
+    pub fn set_triple_exp_params(
+        &mut self,
+        tau_rise: f64,
+        tau_fast: f64,
+        tau_slow: f64,
+        fraction_fast: f64,
+        lambda: f64,
+        fs: f64,
+    ) {
+        self.conv_mode = self.fft_mode();
+        self.tau_rise = tau_rise;
+        self.tau_decay = tau_slow;
+        self.lambda = lambda;
+        self.fs = fs;
+        self.kernel_type = KernelType::TripleExp {
+            tau_fast,
+            tau_slow,
+            fraction_fast,
+        };
+        self.kernel = build_triple_exp_kernel(tau_rise, tau_fast, tau_slow, fraction_fast, fs);
+        self.apply_kernel_length();
+        self.apply_kernel_norm();
+        self.bandpass.update_cutoffs(tau_rise, tau_slow, fs);
+        self.lipschitz_constant = compute_lipschitz(&self.kernel);
+        self.refresh_fft_kernel();
+    }
+
+    /// Switch to the AR(p) kernel of `ARpModel::from_time_constants(taus, fs)`
+    /// for indicators with three or more exponential components.
+    ///
+    /// Selects `ConvMode::BandedARp` (`set_conv_mode` also allows `Fft`)
+    /// until the next `set_params`. Bandpass cutoffs follow the fastest and
+    /// slowest time constants; a single tau is treated as a pure decay.
+    pub fn set_ar_params(&mut self, taus: &[f64], lambda: f64, fs: f64) {
+        self.conv_mode = ConvMode::BandedARp;
+        self.tau_decay = taus.iter().cloned().fold(0.0, f64::max);
+        self.tau_rise = if taus.len() > 1 {
+            taus.iter().cloned().fold(f64::INFINITY, f64::min).max(0.0)
+        } else {
+            0.0
+        };
+        self.lambda = lambda;
+        self.fs = fs;
+        self.arp.update(taus, fs);
+        self.kernel_type = KernelType::ARp {
+            order: self.arp.order(),
+        };
+        self.kernel = self.arp.impulse_response();
+        self.apply_kernel_norm();
+        self.bandpass
+            .update_cutoffs(self.tau_rise, self.tau_decay, fs);
+        self.lipschitz_constant = self.current_lipschitz();
+        self.refresh_fft_kernel();
+    }
 
-        // Update kernel FFT if buffers are already set up and large enough.
-        // On re-enqueue quanta with unchanged trace length, this avoids a full
-        // FFT plan + buffer rebuild in ensure_buffers.
-        if self.conv_mode == ConvMode::Fft && self.fft.fft_len() > 0 && self.active_len > 0 {
-            let min_len = self.active_len + self.kernel.len() - 1;
-            if min_len <= self.fft.fft_len() {
+    /// Update kernel FFT if buffers are already set up and large enough.
+    /// On re-enqueue quanta with unchanged trace length, this avoids a full
+    /// FFT plan + buffer rebuild in ensure_buffers. When the kernel outgrows
+    /// the current FFT length, the new length's plans come from the solver's
+    /// `FftPlanCache` and are only built if not used recently.
+    fn refresh_fft_kernel(&mut self) {
+        if self.conv_mode.is_fft() && self.fft.fft_len() > 0 && self.active_len > 0 {
+            if self.fft.covers(self.active_len, self.kernel.len()) {
                 self.fft.prepare_kernel(&self.kernel);
             } else {
                 self.fft.invalidate();
+                self.ensure_fft_buffers(self.active_len);
             }
         }
     }
@@ -220,6 +662,7 @@ impl Solver {
             self.gradient.resize(n, 0.0);
             self.reconvolution.resize(n, 0.0);
             self.residual_buf.resize(n, 0.0);
+            self.admm_dual.resize(n, 0.0);
         }
 
         // Copy trace data and zero out solution buffers for active region
@@ -230,22 +673,29 @@ impl Solver {
         self.gradient[..n].fill(0.0);
         self.reconvolution[..n].fill(0.0);
         self.residual_buf[..n].fill(0.0);
+        self.admm_dual[..n].fill(0.0);
 
         // Reset iteration state
         self.iteration = 0;
         self.t_fista = 1.0;
         self.converged = false;
         self.prev_objective = f64::INFINITY;
+        self.ls_scale = 1.0;
+        self.sfista.reset();
+        self.debiased = false;
         self.baseline = 0.0;
         self.baseline_ema = 0.0;
         self.baseline_ema_init = false;
         self.filtered = false;
         self.reconvolution_stale = true;
 
-        // Prepare FFT infrastructure for this trace length (skip if using banded mode)
-        if self.conv_mode == ConvMode::Fft {
-            self.fft.ensure_buffers(self.active_len, &self.kernel);
+        if self.rolling_lipschitz_window > 0 {
+            self.lipschitz_constant = self.current_lipschitz();
+            self.rolling_power_vec.clear();
         }
+
+        // Prepare FFT infrastructure for this trace length (skip if using banded mode)
+        self.ensure_fft_buffers(self.active_len);
     }
 
     /// Returns a copy of the kernel.
@@ -265,6 +715,25 @@ impl Solver {
         self.solution[..self.active_len].to_vec()
     }
 
+    /// Number of solution entries at or above `threshold`, counted in place.
+    pub fn get_spike_count(&self, threshold: f32) -> u32 {
+        self.solution[..self.active_len]
+            .iter()
+            .filter(|&&v| v >= threshold)
+            .count() as u32
+    }
+
+    /// Entries at or above `threshold` as a flat `[index, value, index, value, ...]`
+    /// array (a `Float32Array` in JS), so only the nonzero part crosses the
+    /// boundary. Indices are exact up to 2^24 samples.
+    #[cfg_attr(feature = "jsbindings", wasm_bindgen(js_name = get_sparse_solution))]
+    pub fn get_sparse_solution_interleaved(&self, threshold: f32) -> Vec<f32> {
+        self.get_sparse_solution(threshold)
+            .into_iter()
+            .flat_map(|(i, v)| [i as f32, v])
+            .collect()
+    }
+
     /// Returns the reconvolution (K * solution) for the active region.
     /// Computes the reconvolution lazily if it is stale (not computed during iteration).
     ///
@@ -291,6 +760,27 @@ impl Solver {
             .collect()
     }
 
+    /// Objective at the current solution,
+    /// `0.5 * ||K*x + b - y||^2 + lambda_eff * ||x||_1` (plus the elastic-net
+    /// ridge `0.5 * lambda2 * ||x||^2`), with `b` the solver's baseline and
+    /// `lambda_eff` the DC-gain-scaled lambda FISTA uses. Refreshes the
+    /// reconvolution first if it is stale. The value is also what
+    /// `get_objective` returns afterwards.
+    pub fn compute_objective(&mut self) -> f64 {
+        if self.reconvolution_stale {
+            self.compute_reconvolution();
+        }
+        self.prev_objective = self.objective_from_reconvolution();
+        self.prev_objective
+    }
+
+    /// The most recently computed objective — by `compute_objective`, a
+    /// dual-gap stopping check, or a recorded history entry. Infinity until
+    /// one has been computed for the current trace.
+    pub fn get_objective(&self) -> f64 {
+        self.prev_objective
+    }
+
     /// Returns the estimated scalar baseline (EMA-smoothed for stable display).
     /// Lazily computes reconvolution if stale, to ensure the EMA is up to date.
     pub fn get_baseline(&mut self) -> f64 {
@@ -326,39 +816,341 @@ impl Solver {
         self.solution_prev[..n].copy_from_slice(&self.solution[..n]);
     }
 
-    /// Set the convolution mode (FFT or BandedAR2).
+    /// Clear the solve state while keeping the loaded trace, parameters and
+    /// buffer allocations: zeros the solution, gradient and reconvolution
+    /// buffers and restarts the iteration (`iteration`, `t_fista`,
+    /// `converged`, baseline estimate). Lets one solver be reused across
+    /// cells without reallocating.
+    pub fn reset(&mut self) {
+        self.solution.fill(0.0);
+        self.solution_prev.fill(0.0);
+        self.gradient.fill(0.0);
+        self.reconvolution.fill(0.0);
+        self.residual_buf.fill(0.0);
+        self.admm_dual.fill(0.0);
+
+        self.iteration = 0;
+        self.t_fista = 1.0;
+        self.converged = false;
+        self.prev_objective = f64::INFINITY;
+        self.ls_scale = 1.0;
+        self.sfista.reset();
+        self.debiased = false;
+        self.baseline = 0.0;
+        self.baseline_ema = 0.0;
+        self.baseline_ema_init = false;
+        self.reconvolution_stale = true;
+
+        if self.rolling_lipschitz_window > 0 {
+            self.lipschitz_constant = self.current_lipschitz();
+            self.rolling_power_vec.clear();
+        }
+    }
+
+    /// Set the convolution mode (FFT, BandedAR2 or BandedAR1).
     /// Recomputes the Lipschitz constant for the selected mode.
     /// Does NOT reset solution/iteration state — warm-start is preserved.
+    ///
+    /// Under `MemoryMode::Low`, `Fft` selects `FftOverlapAdd`.
     pub fn set_conv_mode(&mut self, mode: ConvMode) {
+        let mode = if mode.is_fft() { self.fft_mode() } else { mode };
+        // A triple-exponential kernel has no banded (AR(1)/AR(2)) form, and an
+        // AR(p) kernel only runs on the AR(p) engine or FFT.
+        let mode = match self.kernel_type {
+            KernelType::TripleExp { .. } => self.fft_mode(),
+            KernelType::ARp { .. } if !mode.is_fft() => ConvMode::BandedARp,
+            KernelType::ARp { .. } | KernelType::DoubleExp => mode,
+        };
         self.conv_mode = mode;
         match mode {
             ConvMode::BandedAR2 => {
                 // Ensure banded coefficients are current (may have been skipped in set_params)
-                self.banded.update(self.tau_rise, self.tau_decay, self.fs);
+                let _ = self.banded.update(self.tau_rise, self.tau_decay, self.fs);
+            }
+            ConvMode::BandedAR1 => {
+                self.banded_ar1.update(self.tau_decay, self.fs);
+            }
+            ConvMode::BandedARp => {
+                // An AR(p) kernel keeps its model; a double-exponential one
+                // runs the equivalent AR(2) model.
+                if self.kernel_type == KernelType::DoubleExp {
+                    self.update_arp_from_params();
+                }
             }
-            ConvMode::Fft => {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
                 // Ensure FFT buffers exist if switching to FFT mode with an active trace
                 if self.active_len > 0 {
-                    self.fft.ensure_buffers(self.active_len, &self.kernel);
+                    self.ensure_fft_buffers(self.active_len);
                 }
             }
         }
         self.lipschitz_constant = self.current_lipschitz();
     }
 
+    /// Select the FFT memory budget. `Low` switches the `Fft` engine to
+    /// overlap-add (`FftOverlapAdd`) and `Standard` switches it back; the
+    /// banded engines need no FFT buffers and are left as they are. Like
+    /// `set_conv_mode`, the iteration state is kept.
+    pub fn set_memory_mode(&mut self, mode: MemoryMode) {
+        self.memory_mode = mode;
+        if self.conv_mode.is_fft() {
+            self.set_conv_mode(self.conv_mode);
+        }
+    }
+
+    /// The active FFT memory budget.
+    pub fn memory_mode(&self) -> MemoryMode {
+        self.memory_mode
+    }
+
+    /// Select the kernel normalization (`Peak` by default) and rescale the
+    /// kernel, the banded engines and the Lipschitz constant to match.
+    /// Solution amplitudes are in the new units from the next iteration on;
+    /// like `set_conv_mode`, the iteration state is kept.
+    pub fn set_kernel_norm(&mut self, norm: KernelNorm) {
+        self.kernel_norm = norm;
+        self.kernel = match self.kernel_type {
+            KernelType::DoubleExp => build_kernel(self.tau_rise, self.tau_decay, self.fs),
+            KernelType::TripleExp {
+                tau_fast,
+                tau_slow,
+                fraction_fast,
+            } => build_triple_exp_kernel(self.tau_rise, tau_fast, tau_slow, fraction_fast, self.fs),
+            KernelType::ARp { .. } => self.arp.impulse_response(),
+        };
+        if !matches!(self.kernel_type, KernelType::ARp { .. }) {
+            self.apply_kernel_length();
+        }
+        self.apply_kernel_norm();
+        self.lipschitz_constant = self.current_lipschitz();
+        self.refresh_fft_kernel();
+        self.reconvolution_stale = true;
+    }
+
+    /// The active kernel normalization.
+    pub fn kernel_norm(&self) -> KernelNorm {
+        self.kernel_norm
+    }
+
     /// Set the constraint type (NonNegative or Box01).
     pub fn set_constraint(&mut self, c: Constraint) {
         self.constraint = c;
     }
 
+    /// Enable Armijo backtracking on the FISTA step (off by default).
+    ///
+    /// Each iteration starts from step `1/L` and halves it (doubling `L`)
+    /// until the sufficient-decrease condition holds, at the cost of one extra
+    /// forward convolution per trial. The increased `L` carries over to later
+    /// iterations of the same trace. ADMM ignores this setting.
+    pub fn set_line_search(&mut self, enabled: bool) {
+        self.line_search = enabled;
+        self.ls_scale = 1.0;
+    }
+
+    /// Whether backtracking line search is enabled.
+    pub fn line_search_enabled(&self) -> bool {
+        self.line_search
+    }
+
+    /// Lipschitz constant of the data term used for the step size (the
+    /// elastic-net ridge and line-search scaling are not included).
+    pub fn get_lipschitz(&self) -> f64 {
+        self.lipschitz_constant
+    }
+
+    /// Re-estimate the Lipschitz constant by `n_iters` steps of power
+    /// iteration on K^T K with the active convolution engine, store it, and
+    /// return it. Uses the current trace length, or twice the kernel length
+    /// when no trace is loaded. `n_iters == 0` leaves the constant unchanged.
+    ///
+    /// Power iteration approaches the top eigenvalue from below, so few
+    /// iterations give an estimate smaller than the analytic one.
+    pub fn reestimate_lipschitz(&mut self, n_iters: u32) -> f64 {
+        let n = if self.active_len > 0 {
+            self.active_len
+        } else {
+            2 * self.kernel.len()
+        };
+        if n_iters == 0 || n == 0 {
+            return self.lipschitz_constant;
+        }
+        self.ensure_fft_buffers(n);
+
+        let mut v = vec![(1.0 / (n as f64).sqrt()) as f32; n];
+        let mut kv = vec![0.0_f32; n];
+        let mut ktkv = vec![0.0_f32; n];
+        let mut estimate = self.lipschitz_constant;
+        for _ in 0..n_iters {
+            estimate = self.power_step(&mut v, &mut kv, &mut ktkv);
+            if v.iter().all(|&x| x == 0.0) {
+                break;
+            }
+        }
+        self.lipschitz_constant = estimate.max(1e-10);
+        self.lipschitz_constant
+    }
+
+    /// Re-estimate the Lipschitz constant during FISTA: every `window_frames`
+    /// iterations, one power-iteration step on K^T K restricted to a
+    /// `window_frames`-long block replaces the constant (and so the step size).
+    /// The power vector carries over between windows, so the estimate keeps
+    /// sharpening at the cost of one extra forward/adjoint pair per window.
+    /// `0` turns this off; the window is capped at the trace length.
+    ///
+    /// Convolution is shift-invariant, so the block operator is the same
+    /// anywhere in the trace and the estimate depends only on the window
+    /// length. Power iteration approaches from below: a short window gives
+    /// a smaller constant, hence longer steps, than the full trace, so each
+    /// estimate is clamped to at least 0.8× the full-trace constant.
+    /// `set_trace` and `reset` restore the full-trace constant and restart
+    /// the power vector.
+    pub fn set_rolling_lipschitz(&mut self, window_frames: usize) {
+        self.rolling_lipschitz_window = window_frames;
+        self.rolling_power_vec.clear();
+    }
+
+    /// Window of `set_rolling_lipschitz` (0 = off).
+    pub fn rolling_lipschitz_window(&self) -> usize {
+        self.rolling_lipschitz_window
+    }
+
+    /// Truncate or zero-extend a freshly built double/triple-exponential
+    /// kernel to the fixed length of `kernel_length_mode` (no-op for `Auto`).
+    /// Past the `Auto` length the true kernel is below 1e-6 of peak, so the
+    /// zero extension is exact to that level.
+    fn apply_kernel_length(&mut self) {
+        if let Some(len) = self.kernel_length_mode.fixed_frames(self.fs) {
+            self.kernel.resize(len, 0.0);
+        }
+    }
+
+    /// Scale the freshly built unit-peak `kernel` to `kernel_norm`, pass the
+    /// factor to the banded engines and refresh the DC gain.
+    fn apply_kernel_norm(&mut self) {
+        let raw_peak = match self.kernel_type {
+            KernelType::DoubleExp => BandedAR2::new(self.tau_rise, self.tau_decay, self.fs)
+                .map_or(1.0, |banded| banded.impulse_peak()),
+            KernelType::TripleExp { .. } => 1.0,
+            KernelType::ARp { .. } => self.arp.impulse_peak(),
+        };
+        let scale = kernel::kernel_norm_scale(&self.kernel, self.kernel_norm, self.fs, raw_peak);
+        if scale != 1.0 {
+            for k in self.kernel.iter_mut() {
+                *k = (*k as f64 * scale) as f32;
+            }
+        }
+        self.banded.set_scale(scale);
+        self.banded_ar1.set_scale(scale);
+        self.arp.set_scale(scale);
+        self.kernel_dc_gain = self.kernel.iter().map(|&k| k as f64).sum();
+    }
+
+    /// Rebuild the AR(p) engine as the AR(2) model of the double-exponential
+    /// parameters (decay and clamped rise), keeping its output scale.
+    fn update_arp_from_params(&mut self) {
+        let tau_rise = kernel::clamp_tau_rise(self.tau_rise, self.tau_decay);
+        self.arp.update(&[self.tau_decay, tau_rise], self.fs);
+    }
+
+    /// FFT convolution mode for the current memory budget.
+    fn fft_mode(&self) -> ConvMode {
+        match self.memory_mode {
+            MemoryMode::Standard => ConvMode::Fft,
+            MemoryMode::Low => ConvMode::FftOverlapAdd,
+        }
+    }
+
+    /// Set up the FFT engine for `n` samples when an FFT mode is active.
+    pub(crate) fn ensure_fft_buffers(&mut self, n: usize) {
+        match self.conv_mode {
+            ConvMode::Fft => self.fft.ensure_buffers(n, &self.kernel),
+            ConvMode::FftOverlapAdd => self.fft.ensure_buffers_overlap_add(n, &self.kernel),
+            ConvMode::BandedAR2 | ConvMode::BandedAR1 | ConvMode::BandedARp => {}
+        }
+    }
+
+    /// `kv = K v` and `ktkv = K^T K v` with the active engine (FFT buffers
+    /// must already be set up for `v.len()`).
+    pub(crate) fn apply_gram(&mut self, v: &[f32], kv: &mut [f32], ktkv: &mut [f32]) {
+        let n = v.len();
+        match self.conv_mode {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                self.fft.convolve_forward(v, n, kv);
+                self.fft.convolve_adjoint(kv, n, ktkv);
+            }
+            ConvMode::BandedAR2 => {
+                self.banded.convolve_forward(v, kv);
+                self.banded.convolve_adjoint(kv, ktkv);
+            }
+            ConvMode::BandedAR1 => {
+                self.banded_ar1.convolve_forward(v, kv);
+                self.banded_ar1.convolve_adjoint(kv, ktkv);
+            }
+            ConvMode::BandedARp => {
+                self.arp.convolve_forward(v, kv);
+                self.arp.convolve_adjoint(kv, ktkv);
+            }
+        }
+    }
+
+    /// One power-iteration step on K^T K: returns the Rayleigh quotient
+    /// `||K v||^2` of the unit vector `v` and replaces `v` with the normalised
+    /// `K^T K v` (all zeros if that vanishes). `kv` and `ktkv` are scratch of
+    /// the same length.
+    fn power_step(&mut self, v: &mut [f32], kv: &mut [f32], ktkv: &mut [f32]) -> f64 {
+        self.apply_gram(v, kv, ktkv);
+        // v has unit norm, so ||K v||^2 is the Rayleigh quotient.
+        let estimate = kv.iter().map(|&x| x as f64 * x as f64).sum();
+        let norm = ktkv
+            .iter()
+            .map(|&x| x as f64 * x as f64)
+            .sum::<f64>()
+            .sqrt();
+        if norm == 0.0 {
+            v.fill(0.0);
+        } else {
+            for (vi, &wi) in v.iter_mut().zip(ktkv.iter()) {
+                *vi = (wi as f64 / norm) as f32;
+            }
+        }
+        estimate
+    }
+
+    /// The `set_rolling_lipschitz` update, run from `step_batch` every
+    /// `rolling_lipschitz_window` iterations.
+    pub(crate) fn rolling_lipschitz_step(&mut self) {
+        let w = self.rolling_lipschitz_window.min(self.active_len);
+        if w == 0 {
+            return;
+        }
+        let mut v = std::mem::take(&mut self.rolling_power_vec);
+        if v.len() != w || v.iter().all(|&x| x == 0.0) {
+            v = vec![(1.0 / (w as f64).sqrt()) as f32; w];
+        }
+        let mut kv = vec![0.0_f32; w];
+        let mut ktkv = vec![0.0_f32; w];
+        let estimate = self.power_step(&mut v, &mut kv, &mut ktkv);
+        self.lipschitz_constant = estimate.max(self.current_lipschitz() * ROLLING_LIPSCHITZ_FLOOR);
+        self.rolling_power_vec = v;
+    }
+
     /// Lipschitz constant for the current convolution mode.
     fn current_lipschitz(&self) -> f64 {
         match self.conv_mode {
-            ConvMode::Fft => compute_lipschitz(&self.kernel),
+            ConvMode::Fft | ConvMode::FftOverlapAdd => compute_lipschitz(&self.kernel),
             ConvMode::BandedAR2 => self.banded.lipschitz(),
+            ConvMode::BandedAR1 => self.banded_ar1.lipschitz(),
+            ConvMode::BandedARp => self.arp.lipschitz(),
         }
     }
 
+    /// Lipschitz constant of the smooth part of the objective, including the
+    /// elastic-net ridge term: `L + lambda2`.
+    pub(crate) fn smooth_lipschitz(&self) -> f64 {
+        self.lipschitz_constant + self.lambda2
+    }
+
     /// Effective lambda scaled by kernel DC gain: lambda * G_dc.
     pub(crate) fn effective_lambda(&self) -> f64 {
         self.lambda * self.kernel_dc_gain
@@ -389,17 +1181,32 @@ impl Solver {
     /// Called lazily when get_reconvolution() or get_reconvolution_with_baseline() is invoked
     /// and reconvolution_stale is true.
     fn compute_reconvolution(&mut self) {
-        let n = self.active_len;
-        if n == 0 {
+        if self.active_len == 0 {
             return;
         }
+        self.convolve_solution();
+        self.finish_reconvolution();
+    }
+
+    /// `reconvolution = K * solution` with the active engine (time-domain
+    /// when the FFT engine is not set up), leaving the baseline untouched.
+    pub(crate) fn convolve_solution(&mut self) {
+        let n = self.active_len;
 
         match self.conv_mode {
             ConvMode::BandedAR2 => {
                 self.banded
                     .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]);
             }
-            ConvMode::Fft if self.fft.fft_len() > 0 => {
+            ConvMode::BandedAR1 => {
+                self.banded_ar1
+                    .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]);
+            }
+            ConvMode::BandedARp => {
+                self.arp
+                    .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]);
+            }
+            ConvMode::Fft | ConvMode::FftOverlapAdd if self.fft.fft_len() > 0 => {
                 self.fft
                     .convolve_forward(&self.solution[..n], n, &mut self.reconvolution[..n]);
             }
@@ -416,10 +1223,30 @@ impl Solver {
                 }
             }
         }
+    }
+
+    /// Objective at `solution`, assuming `reconvolution` holds `K * solution`.
+    pub(crate) fn objective_from_reconvolution(&self) -> f64 {
+        let n = self.active_len;
+        let baseline = self.baseline as f32;
+        let data: f64 = (0..n)
+            .map(|i| {
+                let r = (self.reconvolution[i] + baseline - self.trace[i]) as f64;
+                r * r
+            })
+            .sum();
+        let x = &self.solution[..n];
+        let l1: f64 = x.iter().map(|&v| (v as f64).abs()).sum();
+        let ridge: f64 = x.iter().map(|&v| v as f64 * v as f64).sum();
+        0.5 * data + self.effective_lambda() * l1 + 0.5 * self.lambda2 * ridge
+    }
 
+    /// Refresh the display baseline from a freshly computed reconvolution.
+    fn finish_reconvolution(&mut self) {
         // Recompute baseline at current solution for display alignment.
         // In step_batch, baseline is skipped when filtered (cancels in gradient),
         // but the display path always needs it to align fit with trace.
+        let n = self.active_len;
         let raw = compute_raw_baseline(&self.trace[..n], &self.reconvolution[..n], n);
         self.update_baseline_ema(raw);
 
@@ -454,19 +1281,51 @@ impl Solver {
         self.bandpass.set_lp_enabled(enabled);
     }
 
+    /// Whether `apply_filter` would filter: the kernel-derived bandpass with
+    /// HP or LP enabled, or an explicit filter selected via `set_filter_type`
+    /// (see `filter_type` for which one).
     pub fn filter_enabled(&self) -> bool {
-        self.bandpass.is_enabled()
+        match &self.filter_type {
+            FilterType::Bandpass => self.bandpass.is_enabled(),
+            FilterType::Butterworth(_)
+            | FilterType::SavitzkyGolay { .. }
+            | FilterType::Exponential(_)
+            | FilterType::ZeroPhaseExponential(_)
+            | FilterType::Median { .. } => true,
+        }
     }
 
-    /// Apply bandpass filter to the active trace region. Returns true if filtering was applied.
+    /// Apply the selected filter to the active trace region. Returns true if filtering was applied.
     ///
     /// Sets `self.filtered = true` only when HP is active, because HP removes DC and
     /// baseline estimation should be skipped. LP-only preserves DC, so baseline
     /// estimation must still run.
     pub fn apply_filter(&mut self) -> bool {
         let n = self.active_len;
-        let applied = self.bandpass.apply(&mut self.trace[..n]);
-        if applied && self.bandpass.is_hp_enabled() {
+        let (applied, removes_dc) = match &self.filter_type {
+            FilterType::Bandpass => (
+                self.bandpass.apply(&mut self.trace[..n]),
+                self.bandpass.is_hp_enabled(),
+            ),
+            FilterType::Butterworth(bw) => (bw.apply(&mut self.trace[..n]), bw.low_hz.is_some()),
+            FilterType::SavitzkyGolay { window, poly_order } => {
+                match filter::savitzky_golay(&self.trace[..n], *window, *poly_order) {
+                    Ok(smoothed) if n > 0 => {
+                        self.trace[..n].copy_from_slice(&smoothed);
+                        (true, false)
+                    }
+                    _ => (false, false),
+                }
+            }
+            FilterType::Exponential(f) => (f.apply(&mut self.trace[..n]), false),
+            FilterType::ZeroPhaseExponential(f) => (f.apply(&mut self.trace[..n]), false),
+            FilterType::Median { half_window } => {
+                let filtered = filter::median_filter_1d(&self.trace[..n], *half_window);
+                self.trace[..n].copy_from_slice(&filtered);
+                (n > 0, false)
+            }
+        };
+        if applied && removes_dc {
             self.filtered = true;
         }
         applied
@@ -553,7 +1412,62 @@ impl Solver {
     }
 }
 
+// Methods taking or returning non-C-like enums, which wasm-bindgen cannot export.
+#[cfg(feature = "std")]
+impl Solver {
+    /// Select the filter run by `apply_filter`.
+    ///
+    /// `FilterType::Bandpass` (default) derives its cutoffs from the kernel and
+    /// follows `set_params`; a `Butterworth` filter keeps the cutoffs and
+    /// sampling rate it was designed with. `SavitzkyGolay`, `Median` and the
+    /// exponential smoothers work in samples and never remove DC, so baseline
+    /// estimation keeps running after them.
+    pub fn set_filter_type(&mut self, filter_type: FilterType) {
+        self.filter_type = filter_type;
+    }
+
+    /// The filter currently selected for `apply_filter`.
+    pub fn filter_type(&self) -> &FilterType {
+        &self.filter_type
+    }
+
+    /// Kernel shape set by the last `set_params` / `set_triple_exp_params`.
+    pub fn kernel_type(&self) -> KernelType {
+        self.kernel_type
+    }
+
+    /// Select the explicit kernel length. Takes effect at the next
+    /// `set_params` / `set_triple_exp_params` (or `set_kernel_norm`), so call
+    /// it before them. A fixed length shorter than `ceil(3 * tau_decay * fs)`
+    /// is reported as a warning in `param_diagnostics`.
+    pub fn set_kernel_length_mode(&mut self, mode: KernelLengthMode) {
+        self.kernel_length_mode = mode;
+    }
+
+    /// The explicit kernel length setting.
+    pub fn kernel_length_mode(&self) -> KernelLengthMode {
+        self.kernel_length_mode
+    }
+
+    /// `(index, value)` pairs of the solution entries at or above `threshold`,
+    /// without allocating the dense solution.
+    pub fn get_sparse_solution(&self, threshold: f32) -> Vec<(u32, f32)> {
+        self.solution[..self.active_len]
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| v >= threshold)
+            .map(|(i, &v)| (i as u32, v))
+            .collect()
+    }
+
+    /// Validation findings for the parameters of the last `set_params`.
+    pub fn param_diagnostics(&self) -> &[validate::Diagnostic] {
+        &self.param_diagnostics
+    }
+}
+
 /// Compute the mean residual (trace - reconvolution) as the raw baseline estimate.
+#[cfg(feature = "std")]
 pub(crate) fn compute_raw_baseline(trace: &[f32], reconvolution: &[f32], n: usize) -> f64 {
     let mut sum = 0.0_f64;
     for i in 0..n {
@@ -563,6 +1477,7 @@ pub(crate) fn compute_raw_baseline(trace: &[f32], reconvolution: &[f32], n: usiz
 }
 
 /// Byte length of serialized solver state for a trace of length `n`.
+#[cfg(feature = "std")]
 fn state_byte_len(n: usize) -> usize {
     4 + 8 + 4 + 8 + 2 * n * 4 // u32 + f64 + u32 + f64 + 2×n×f32
 }
@@ -572,25 +1487,28 @@ fn state_byte_len(n: usize) -> usize {
 // Each panics on short reads, which cannot occur when the caller has already
 // validated the total buffer length (as load_state does above).
 
+#[cfg(feature = "std")]
 fn read_u32_le(cur: &mut Cursor<&[u8]>) -> u32 {
     let mut buf = [0u8; 4];
     cur.read_exact(&mut buf).unwrap();
     u32::from_le_bytes(buf)
 }
 
+#[cfg(feature = "std")]
 fn read_f32_le(cur: &mut Cursor<&[u8]>) -> f32 {
     let mut buf = [0u8; 4];
     cur.read_exact(&mut buf).unwrap();
     f32::from_le_bytes(buf)
 }
 
+#[cfg(feature = "std")]
 fn read_f64_le(cur: &mut Cursor<&[u8]>) -> f64 {
     let mut buf = [0u8; 8];
     cur.read_exact(&mut buf).unwrap();
     f64::from_le_bytes(buf)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod finite_guard_tests {
     use super::first_nonfinite;
 
@@ -10,6 +10,7 @@
 /// The result provides initial tau_rise, tau_decay for the normal iterative pipeline.
 use crate::biexp_fit::{fit_biexponential, BiexpResult};
 use crate::kernel_est::estimate_free_kernel;
+use crate::CalabError;
 
 /// Result of per-trace seed spike detection.
 /// Mirrors the shape of InDecaResult so it can slot into the same kernel estimation pipeline.
@@ -223,12 +224,13 @@ pub fn find_seed_spikes(trace: &[f32], fs: f64, cfg: &SeedConfig) -> Vec<usize>
 /// - `fs`: sampling rate
 ///
 /// Returns `SeedKernelResult` with free kernel and fitted tau parameters.
-/// Returns default fallback values if no seed spikes are found.
+/// Returns default fallback values if no seed spikes are found, and the
+/// `estimate_free_kernel` error if `trace_lengths` does not match `traces_flat`.
 pub fn seed_kernel_estimate(
     traces_flat: &[f32],
     trace_lengths: &[usize],
     fs: f64,
-) -> SeedKernelResult {
+) -> Result<SeedKernelResult, CalabError> {
     let total_len: usize = trace_lengths.iter().sum();
 
     let seed_cfg = SeedConfig::default();
@@ -242,7 +244,7 @@ pub fn seed_kernel_estimate(
     // across the PyO3 boundary.
     debug_assert_eq!(traces_flat.len(), total_len);
     if traces_flat.len() != total_len {
-        return SeedKernelResult {
+        return Ok(SeedKernelResult {
             free_kernel: vec![0.0; kernel_length],
             tau_rise: 0.02,
             tau_decay: 0.4,
@@ -252,7 +254,7 @@ pub fn seed_kernel_estimate(
             tau_decay_fast: 0.0,
             beta_fast: 0.0,
             n_seed_spikes: 0,
-        };
+        });
     }
 
     let mut spike_trains = vec![0.0_f32; total_len];
@@ -277,7 +279,7 @@ pub fn seed_kernel_estimate(
     }
 
     if total_seed_spikes == 0 {
-        return SeedKernelResult {
+        return Ok(SeedKernelResult {
             free_kernel: vec![0.0; kernel_length],
             tau_rise: 0.02,
             tau_decay: 0.4,
@@ -287,7 +289,7 @@ pub fn seed_kernel_estimate(
             tau_decay_fast: 0.0,
             beta_fast: 0.0,
             n_seed_spikes: 0,
-        };
+        });
     }
 
     let free_kernel = estimate_free_kernel(
@@ -301,7 +303,8 @@ pub fn seed_kernel_estimate(
         1e-5,
         None,
         0.001, // light TV smoothness for cleaner kernel from sparse seeds
-    );
+        0.0,
+    )?;
 
     let BiexpResult {
         tau_rise,
@@ -314,7 +317,7 @@ pub fn seed_kernel_estimate(
         fit_mode: _,
     } = fit_biexponential(&free_kernel, fs, true, 0, None);
 
-    SeedKernelResult {
+    Ok(SeedKernelResult {
         free_kernel,
         tau_rise,
         tau_decay,
@@ -324,7 +327,7 @@ pub fn seed_kernel_estimate(
         tau_decay_fast,
         beta_fast,
         n_seed_spikes: total_seed_spikes,
-    }
+    })
 }
 
 #[cfg(test)]
@@ -422,7 +425,7 @@ mod tests {
             trace_lengths.push(n);
         }
 
-        let result = seed_kernel_estimate(&all_traces, &trace_lengths, fs);
+        let result = seed_kernel_estimate(&all_traces, &trace_lengths, fs).unwrap();
 
         assert!(
             result.n_seed_spikes > 0,
@@ -472,7 +475,7 @@ mod tests {
             *v += 0.5 * ((i as f64 * 0.7).sin() as f32);
         }
 
-        let result = seed_kernel_estimate(&trace, &[n], fs);
+        let result = seed_kernel_estimate(&trace, &[n], fs).unwrap();
 
         assert!(
             result.n_seed_spikes >= 2,
@@ -493,7 +496,7 @@ mod tests {
     #[test]
     fn flat_trace_returns_defaults() {
         let trace = vec![5.0_f32; 300];
-        let result = seed_kernel_estimate(&trace, &[300], 30.0);
+        let result = seed_kernel_estimate(&trace, &[300], 30.0).unwrap();
 
         assert_eq!(result.n_seed_spikes, 0);
         assert_eq!(result.tau_rise, 0.02);
@@ -502,7 +505,7 @@ mod tests {
 
     #[test]
     fn empty_traces() {
-        let result = seed_kernel_estimate(&[], &[], 30.0);
+        let result = seed_kernel_estimate(&[], &[], 30.0).unwrap();
         assert_eq!(result.n_seed_spikes, 0);
     }
 }
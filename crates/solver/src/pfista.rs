@@ -0,0 +1,297 @@
+//! Diagonally preconditioned FISTA.
+//!
+//! Plain FISTA takes the isotropic step 1/L. The Gram matrix K^T K of a
+//! truncated convolution is Toeplitz except near the end of the trace, where
+//! a frame only sees the head of the kernel: its diagonal is
+//! D[i] = sum_{k < n - i} kernel[k]^2, constant in the interior and falling
+//! to kernel[0]^2 at the last frame. `SolverMode::Diagonal` steps each
+//! coordinate by 1/(c * D[i]) instead, with the soft threshold scaled to
+//! match, so the weakly observed frames are not held back by the step size
+//! of the well-observed ones.
+//!
+//! D^{-1} alone does not majorize K^T K, so the scale c is the largest
+//! eigenvalue of D^{-1/2} K^T K D^{-1/2}, estimated by power iteration and
+//! padded by `SCALE_MARGIN`. It plays the role of the Lipschitz constant in
+//! the D-weighted norm; in the interior c * D[i] is close to L, so the step
+//! there is the FISTA step. D and c depend only on the kernel and the trace
+//! length and are cached until either (or the Lipschitz constant) changes.
+//!
+//! Buffer roles match plain FISTA (`solution` = x_k, `solution_prev` = y_k).
+//! Line search and the refractory projection do not apply; convergence uses
+//! the primal-residual test.
+
+use crate::{Constraint, ConvMode, Solver};
+
+/// Power-iteration steps for the scale of the preconditioned operator.
+const SCALE_POWER_ITERS: usize = 30;
+
+/// Safety factor on the scale: power iteration approaches from below.
+const SCALE_MARGIN: f64 = 1.05;
+
+/// Per-coordinate steps and the key they were computed for.
+pub(crate) struct DiagonalState {
+    /// 1 / (c * D[i]) for each frame.
+    steps: Vec<f32>,
+    /// (trace length, kernel length, Lipschitz constant bits).
+    key: Option<(usize, usize, u64)>,
+}
+
+impl DiagonalState {
+    pub(crate) fn new() -> Self {
+        DiagonalState {
+            steps: Vec::new(),
+            key: None,
+        }
+    }
+}
+
+/// Diagonal of K^T K for an `n`-frame causal convolution with `kernel`:
+/// `D[i] = sum_{k < min(len, n - i)} kernel[k]^2`.
+pub(crate) fn gram_diagonal(kernel: &[f32], n: usize) -> Vec<f64> {
+    // head[m] = sum of the first m squared taps.
+    let mut head = Vec::with_capacity(kernel.len() + 1);
+    head.push(0.0_f64);
+    for &k in kernel {
+        head.push(head.last().unwrap() + k as f64 * k as f64);
+    }
+    (0..n).map(|i| head[(n - i).min(kernel.len())]).collect()
+}
+
+impl Solver {
+    /// Per-coordinate steps 1 / (c * D[i]) of `SolverMode::Diagonal` for the
+    /// current trace, recomputed when the trace length, kernel or Lipschitz
+    /// constant has changed.
+    pub(crate) fn diagonal_steps(&mut self) -> &[f32] {
+        let n = self.active_len;
+        let key = (n, self.kernel.len(), self.lipschitz_constant.to_bits());
+        if self.pfista.key != Some(key) {
+            let floor = 1e-12 * self.lipschitz_constant.max(1e-12);
+            let diag: Vec<f64> = gram_diagonal(&self.kernel, n)
+                .into_iter()
+                .map(|d| d.max(floor))
+                .collect();
+            let scale = self.preconditioned_scale(&diag) * SCALE_MARGIN;
+            self.pfista.steps = diag.iter().map(|&d| (1.0 / (scale * d)) as f32).collect();
+            self.pfista.key = Some(key);
+        }
+        &self.pfista.steps
+    }
+
+    /// Largest eigenvalue of D^{-1/2} K^T K D^{-1/2} by power iteration.
+    fn preconditioned_scale(&mut self, diag: &[f64]) -> f64 {
+        let n = diag.len();
+        self.ensure_fft_buffers(n);
+        let inv_sqrt: Vec<f64> = diag.iter().map(|&d| 1.0 / d.sqrt()).collect();
+        let mut v = vec![(1.0 / (n as f64).sqrt()) as f32; n];
+        let mut sv = vec![0.0_f32; n];
+        let mut kv = vec![0.0_f32; n];
+        let mut ktkv = vec![0.0_f32; n];
+        let mut estimate = 1.0;
+        for _ in 0..SCALE_POWER_ITERS {
+            for i in 0..n {
+                sv[i] = (v[i] as f64 * inv_sqrt[i]) as f32;
+            }
+            self.apply_gram(&sv, &mut kv, &mut ktkv);
+            // v has unit norm, so ||K D^{-1/2} v||^2 is the Rayleigh quotient.
+            estimate = kv.iter().map(|&x| x as f64 * x as f64).sum::<f64>();
+            let w: Vec<f64> = (0..n).map(|i| ktkv[i] as f64 * inv_sqrt[i]).collect();
+            let norm = w.iter().map(|x| x * x).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                break;
+            }
+            for (vi, wi) in v.iter_mut().zip(&w) {
+                *vi = (wi / norm) as f32;
+            }
+        }
+        estimate.max(1e-12)
+    }
+
+    /// Run n_steps of diagonally preconditioned FISTA. Returns true if
+    /// converged.
+    ///
+    /// Same iteration as `step_batch` (gradient at the extrapolated point,
+    /// prox, momentum, gradient-mapping adaptive restart with the
+    /// `set_restart_threshold` margin) with the step and soft threshold of
+    /// coordinate i scaled to 1 / (c * D[i] + lambda2).
+    pub(crate) fn step_batch_diagonal(&mut self, n_steps: u32) -> bool {
+        let n = self.active_len;
+        if n == 0 {
+            self.converged = true;
+            return true;
+        }
+
+        self.diagonal_steps();
+        let steps = std::mem::take(&mut self.pfista.steps);
+        let lambda = self.effective_lambda();
+        let lambda2 = self.lambda2;
+        let (lo, hi) = match self.constraint {
+            Constraint::NonNegative => (0.0_f32, f32::INFINITY),
+            Constraint::Box01 => (0.0_f32, 1.0_f32),
+        };
+        let tol_sq = self.tolerance * self.tolerance;
+
+        for _ in 0..n_steps {
+            if self.converged {
+                break;
+            }
+
+            // 1. Forward convolution at y_k, baseline and residual (as FISTA).
+            match self.conv_mode {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => self.fft.convolve_forward(
+                    &self.solution_prev[..n],
+                    n,
+                    &mut self.reconvolution[..n],
+                ),
+                ConvMode::BandedAR2 => self
+                    .banded
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_forward(&self.solution_prev[..n], &mut self.reconvolution[..n]),
+            }
+            if !self.filtered {
+                let raw =
+                    crate::compute_raw_baseline(&self.trace[..n], &self.reconvolution[..n], n);
+                self.update_baseline_ema(raw);
+            }
+            let baseline_f32 = self.baseline as f32;
+            for i in 0..n {
+                self.residual_buf[i] = self.reconvolution[i] + baseline_f32 - self.trace[i];
+            }
+
+            // 2. Gradient of the data term: K^T (K*y_k + b - y)
+            match self.conv_mode {
+                ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                    self.fft
+                        .convolve_adjoint(&self.residual_buf[..n], n, &mut self.gradient[..n])
+                }
+                ConvMode::BandedAR2 => self
+                    .banded
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedAR1 => self
+                    .banded_ar1
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+                ConvMode::BandedARp => self
+                    .arp
+                    .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            }
+
+            // 3. Scaled prox step, momentum and restart accumulators in one pass:
+            //    x_{k+1,i} = prox_{t_i * lambda}(y_i - t_i * (grad_i + lambda2 * y_i))
+            self.iteration += 1;
+            let t_new = (1.0 + (1.0 + 4.0 * self.t_fista * self.t_fista).sqrt()) / 2.0;
+            let momentum = ((self.t_fista - 1.0) / t_new) as f32;
+            let mut diff_sq = 0.0_f64;
+            let mut xk_sq = 0.0_f64;
+            let mut dot = 0.0_f64;
+            let mut gm_sq = 0.0_f64;
+            for i in 0..n {
+                let y = self.solution_prev[i];
+                let t = steps[i] as f64;
+                // Ridge term folded into the step: 1 / (c * D + lambda2).
+                let t = t / (1.0 + t * lambda2);
+                let z = y as f64 - t * (self.gradient[i] as f64 + lambda2 * y as f64);
+                let x_new = ((z - t * lambda) as f32).clamp(lo, hi);
+                let x_old = self.solution[i];
+                self.solution[i] = x_new;
+
+                let d = x_new as f64 - x_old as f64;
+                let gm = y as f64 - x_new as f64;
+                diff_sq += d * d;
+                xk_sq += x_old as f64 * x_old as f64;
+                dot += gm * d;
+                gm_sq += gm * gm;
+                self.solution_prev[i] = (x_new + momentum * (x_new - x_old)).clamp(lo, hi);
+            }
+
+            // 4. Adaptive restart (see `step_batch`).
+            let restarted =
+                self.iteration > 1 && dot > self.restart_threshold * (gm_sq * diff_sq).sqrt();
+            if restarted {
+                self.t_fista = 1.0;
+                self.solution_prev[..n].copy_from_slice(&self.solution[..n]);
+            } else {
+                self.t_fista = t_new;
+            }
+
+            // 5. Primal-residual convergence test.
+            if self.iteration > 5 && diff_sq < tol_sq * (xk_sq + 1e-20) {
+                self.converged = true;
+            }
+
+            self.reconvolution_stale = true;
+        }
+
+        self.pfista.steps = steps;
+        self.converged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gram_diagonal;
+    use crate::kernel::build_kernel;
+    use crate::test_util::{build_trace, solve_to_convergence};
+    use crate::{ConvMode, SolverMode};
+
+    #[test]
+    fn gram_diagonal_matches_column_norms() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let n = kernel.len() + 20;
+        let diag = gram_diagonal(&kernel, n);
+        for (i, &d) in diag.iter().enumerate() {
+            // Column i of K is the kernel shifted to start at frame i.
+            let col: f64 = (i..n.min(i + kernel.len()))
+                .map(|t| (kernel[t - i] as f64).powi(2))
+                .sum();
+            assert!(
+                (d - col).abs() < 1e-9 * col.max(1.0),
+                "frame {i}: {d} vs {col}"
+            );
+        }
+        assert!((diag[n - 1] - (kernel[0] as f64).powi(2)).abs() < 1e-12);
+        assert_eq!(diag[0], diag[19]);
+    }
+
+    #[test]
+    fn diagonal_mode_matches_fista() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        // The last spikes sit in the weakly observed tail of the trace.
+        let trace = build_trace(
+            &kernel,
+            600,
+            &[(40, 1.0), (150, 2.0), (400, 1.5), (592, 1.0), (597, 0.8)],
+        );
+
+        for conv in [ConvMode::Fft, ConvMode::BandedAR2] {
+            let mut fista = solve_to_convergence(SolverMode::Fista, Some(conv), None, &trace, 1000);
+            let mut diag =
+                solve_to_convergence(SolverMode::Diagonal, Some(conv), None, &trace, 1000);
+            assert!(fista.converged() && diag.converged());
+            let (f_obj, d_obj) = (fista.compute_objective(), diag.compute_objective());
+            assert!(
+                d_obj <= f_obj * (1.0 + 1e-3),
+                "{conv:?}: diagonal objective {d_obj} vs FISTA {f_obj}"
+            );
+            let tail: f32 = diag.get_solution()[585..].iter().sum();
+            assert!((tail - 1.8).abs() < 0.3, "{conv:?}: tail mass {tail}");
+            assert!(diag.iteration_count() <= fista.iteration_count());
+        }
+
+        // Interior steps match the FISTA step; tail steps are longer.
+        let mut solver = solve_to_convergence(
+            SolverMode::Diagonal,
+            Some(ConvMode::Fft),
+            None,
+            &trace,
+            1000,
+        );
+        let step_fista = 1.0 / solver.lipschitz_constant;
+        let steps = solver.diagonal_steps().to_vec();
+        assert!((steps[300] as f64 / step_fista - 1.0).abs() < 0.2);
+        assert!(steps[599] > 5.0 * steps[300]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Calcium indicator presets: literature kernel time constants by frame rate.
+//!
+//! Apparent kernel time constants depend on the imaging rate: at low frame
+//! rates the rise is blurred across a frame and fitted rise times come out
+//! longer. Each indicator therefore stores (fps, tau_rise, tau_decay) points
+//! measured at the frame rates reported in the source papers, and
+//! `Preset::params_at_fs` interpolates linearly between them (clamping to the
+//! nearest measured rate outside the table).
+//!
+//! Values are single-AP kernel fits at the reported imaging rates:
+//! GCaMP6 — Chen et al., 2013; jGCaMP7 — Dana et al., 2019;
+//! jGCaMP8 — Zhang et al., 2023; RCaMP2 — Inoue et al., 2015;
+//! XCaMP-Gf — Inoue et al., 2019.
+
+/// One literature measurement: (fps, tau_rise [s], tau_decay [s]).
+type TauPoint = (f64, f64, f64);
+
+const GCAMP6S: &[TauPoint] = &[(7.5, 0.50, 1.90), (30.0, 0.40, 1.80), (100.0, 0.20, 1.70)];
+const GCAMP6F: &[TauPoint] = &[(7.5, 0.15, 0.70), (30.0, 0.10, 0.60), (100.0, 0.05, 0.40)];
+const GCAMP7S: &[TauPoint] = &[(15.0, 0.30, 1.50), (30.0, 0.25, 1.40), (100.0, 0.15, 1.30)];
+const GCAMP7F: &[TauPoint] = &[(15.0, 0.08, 0.55), (30.0, 0.06, 0.50), (100.0, 0.03, 0.35)];
+const JGCAMP8S: &[TauPoint] = &[(30.0, 0.06, 0.90), (100.0, 0.02, 0.80)];
+const JGCAMP8F: &[TauPoint] = &[(30.0, 0.05, 0.30), (100.0, 0.01, 0.20)];
+const RCAMP2: &[TauPoint] = &[(30.0, 0.07, 0.70), (100.0, 0.04, 0.60)];
+const XCAMP: &[TauPoint] = &[(30.0, 0.03, 0.25), (100.0, 0.01, 0.20)];
+
+/// Genetically encoded calcium indicators with built-in kernel parameters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    GCaMP6s,
+    GCaMP6f,
+    GCaMP7s,
+    GCaMP7f,
+    JGCaMP8s,
+    JGCaMP8f,
+    RCaMP2,
+    XCaMP,
+}
+
+impl Preset {
+    /// Every preset, in display order.
+    pub const ALL: [Preset; 8] = [
+        Preset::GCaMP6s,
+        Preset::GCaMP6f,
+        Preset::GCaMP7s,
+        Preset::GCaMP7f,
+        Preset::JGCaMP8s,
+        Preset::JGCaMP8f,
+        Preset::RCaMP2,
+        Preset::XCaMP,
+    ];
+
+    /// Display name, e.g. `"jGCaMP8f"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::GCaMP6s => "GCaMP6s",
+            Preset::GCaMP6f => "GCaMP6f",
+            Preset::GCaMP7s => "GCaMP7s",
+            Preset::GCaMP7f => "GCaMP7f",
+            Preset::JGCaMP8s => "jGCaMP8s",
+            Preset::JGCaMP8f => "jGCaMP8f",
+            Preset::RCaMP2 => "RCaMP2",
+            Preset::XCaMP => "XCaMP",
+        }
+    }
+
+    /// Look up a preset by name, ignoring ASCII case (`"gcamp6f"` works).
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    fn table(self) -> &'static [TauPoint] {
+        match self {
+            Preset::GCaMP6s => GCAMP6S,
+            Preset::GCaMP6f => GCAMP6F,
+            Preset::GCaMP7s => GCAMP7S,
+            Preset::GCaMP7f => GCAMP7F,
+            Preset::JGCaMP8s => JGCAMP8S,
+            Preset::JGCaMP8f => JGCAMP8F,
+            Preset::RCaMP2 => RCAMP2,
+            Preset::XCaMP => XCAMP,
+        }
+    }
+
+    /// (tau_rise, tau_decay) in seconds for imaging at `fs` Hz, linearly
+    /// interpolated between the published frame rates and clamped to the
+    /// nearest one outside the measured range.
+    pub fn params_at_fs(self, fs: f64) -> (f64, f64) {
+        let table = self.table();
+        let (first, last) = (table[0], table[table.len() - 1]);
+        if fs.is_nan() || fs <= first.0 {
+            return (first.1, first.2);
+        }
+        if fs >= last.0 {
+            return (last.1, last.2);
+        }
+        let i = table
+            .iter()
+            .position(|p| p.0 > fs)
+            .unwrap_or(table.len() - 1);
+        let (lo, hi) = (table[i - 1], table[i]);
+        let w = (fs - lo.0) / (hi.0 - lo.0);
+        (lo.1 + w * (hi.1 - lo.1), lo.2 + w * (hi.2 - lo.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_are_sorted_and_physical() {
+        for p in Preset::ALL {
+            let table = p.table();
+            assert!(table.windows(2).all(|w| w[0].0 < w[1].0), "{}", p.name());
+            assert!(
+                table.iter().all(|&(_, r, d)| r > 0.0 && d > r),
+                "{}",
+                p.name()
+            );
+        }
+    }
+
+    #[test]
+    fn params_interpolate_between_measured_rates() {
+        assert_eq!(Preset::GCaMP6f.params_at_fs(30.0), (0.10, 0.60));
+        let (r, d) = Preset::GCaMP6f.params_at_fs(65.0);
+        assert!((r - 0.075).abs() < 1e-12 && (d - 0.50).abs() < 1e-12);
+        // Clamped outside the table.
+        assert_eq!(Preset::GCaMP6f.params_at_fs(1.0), (0.15, 0.70));
+        assert_eq!(Preset::GCaMP6f.params_at_fs(1000.0), (0.05, 0.40));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for p in Preset::ALL {
+            assert_eq!(Preset::from_name(p.name()), Some(p));
+        }
+        assert_eq!(Preset::from_name("JGCAMP8F"), Some(Preset::JGCaMP8f));
+        assert_eq!(Preset::from_name("GCaMP9"), None);
+    }
+}
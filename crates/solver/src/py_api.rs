@@ -1,9 +1,24 @@
-use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArray2, PyUntypedArrayMethods};
+use numpy::ndarray::ArrayView2;
+use numpy::{
+    PyArray1, PyArray2, PyArrayDyn, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2,
+    PyReadonlyArrayDyn, PyUntypedArrayMethods,
+};
+use once_cell::sync::Lazy;
 use pyo3::prelude::*;
+use rayon::prelude::*;
 
+use crate::banded::{BandedAR1, BandedAR2};
 use crate::kernel::{build_kernel, compute_lipschitz};
+use crate::presets::Preset;
 use crate::simulate;
-use crate::{biexp_fit, indeca, kernel_est, upsample, Constraint, ConvMode, Solver};
+use crate::threshold::ThresholdCriterion;
+use crate::upsample::UpsampleMethod;
+use crate::{
+    biexp_fit, check_adjoint_op, indeca, kernel_est, upsample, ARpModel, ButterworthFilter,
+    CalabError, Constraint, ConvMode, ExponentialSmoothFilter, FilterType,
+    ForwardBackwardExponential, KernelNorm, MemoryMode, Regularizer, Solver, SolverBuilder,
+    SolverMode, StoppingCriterion, VRMode,
+};
 
 const BATCH_SIZE: u32 = 100;
 const CONTIGUOUS_ERR: &str =
@@ -34,8 +49,21 @@ fn parse_conv_mode(s: &str) -> PyResult<ConvMode> {
     match s {
         "fft" => Ok(ConvMode::Fft),
         "banded" => Ok(ConvMode::BandedAR2),
+        "banded_ar1" => Ok(ConvMode::BandedAR1),
+        "banded_arp" => Ok(ConvMode::BandedARp),
+        "fft_overlap_add" => Ok(ConvMode::FftOverlapAdd),
         _ => Err(pyo3::exceptions::PyValueError::new_err(
-            "conv_mode must be 'fft' or 'banded'",
+            "conv_mode must be 'fft', 'banded', 'banded_ar1', 'banded_arp' or 'fft_overlap_add'",
+        )),
+    }
+}
+
+fn parse_memory_mode(s: &str) -> PyResult<MemoryMode> {
+    match s {
+        "standard" => Ok(MemoryMode::Standard),
+        "low" => Ok(MemoryMode::Low),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(
+            "memory mode must be 'standard' or 'low'",
         )),
     }
 }
@@ -50,6 +78,49 @@ fn parse_constraint(s: &str) -> PyResult<Constraint> {
     }
 }
 
+fn parse_kernel_norm(s: &str) -> PyResult<KernelNorm> {
+    match s {
+        "peak" => Ok(KernelNorm::Peak),
+        "area" => Ok(KernelNorm::Area),
+        "l2" => Ok(KernelNorm::L2),
+        "none" => Ok(KernelNorm::None),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(
+            "kernel norm must be 'peak', 'area', 'l2' or 'none'",
+        )),
+    }
+}
+
+fn parse_boundary_mode(s: &str) -> PyResult<indeca::BoundaryMode> {
+    match s {
+        "zero" => Ok(indeca::BoundaryMode::Zero),
+        "mirror" => Ok(indeca::BoundaryMode::Mirror),
+        "periodic" => Ok(indeca::BoundaryMode::Periodic),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(
+            "boundary_mode must be 'zero', 'mirror' or 'periodic'",
+        )),
+    }
+}
+
+fn parse_upsample_method(s: &str) -> PyResult<UpsampleMethod> {
+    match s {
+        "linear" => Ok(UpsampleMethod::Linear),
+        "cubic" => Ok(UpsampleMethod::Cubic),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(
+            "upsample_method must be 'linear' or 'cubic'",
+        )),
+    }
+}
+
+fn parse_threshold_criterion(s: &str) -> PyResult<ThresholdCriterion> {
+    match s {
+        "mse" => Ok(ThresholdCriterion::Mse),
+        "aic" => Ok(ThresholdCriterion::Aic),
+        _ => Err(pyo3::exceptions::PyValueError::new_err(
+            "threshold_criterion must be 'mse' or 'aic'",
+        )),
+    }
+}
+
 /// Run the solver in batches until convergence or max_iters is reached.
 fn run_to_convergence(solver: &mut Solver, max_iters: u32) {
     let n_batches = max_iters.div_ceil(BATCH_SIZE);
@@ -82,6 +153,29 @@ impl PySolver {
         self.inner.set_params(tau_rise, tau_decay, lambda, fs);
     }
 
+    /// Use a triple-exponential kernel (shared rise, fast and slow decay mixed by
+    /// `fraction_fast`). Forces FFT convolution until the next set_params.
+    fn set_triple_exp_params(
+        &mut self,
+        tau_rise: f64,
+        tau_fast: f64,
+        tau_slow: f64,
+        fraction_fast: f64,
+        lambda: f64,
+        fs: f64,
+    ) {
+        self.inner
+            .set_triple_exp_params(tau_rise, tau_fast, tau_slow, fraction_fast, lambda, fs);
+    }
+
+    /// Use the AR(p) kernel of the given time constants (seconds, one per
+    /// exponential component). Selects "banded_arp" until the next set_params.
+    fn set_ar_params(&mut self, taus: Vec<f64>, lambda: f64, fs: f64) -> PyResult<()> {
+        validate_ar_taus(&taus, fs)?;
+        self.inner.set_ar_params(&taus, lambda, fs);
+        Ok(())
+    }
+
     /// Load a trace (numpy float32 array) for deconvolution.
     fn set_trace(&mut self, trace: PyReadonlyArray1<f32>) -> PyResult<()> {
         let slice = trace
@@ -107,11 +201,47 @@ impl PySolver {
         self.inner.iteration_count()
     }
 
+    /// Zero the solution, gradient and reconvolution buffers and restart the
+    /// iteration, keeping the trace, parameters and allocations.
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// `with PySolver() as s:` — returns the solver itself.
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Reset the solver on leaving the block. Returns False so exceptions
+    /// raised inside the block propagate.
+    #[pyo3(signature = (_exc_type=None, _exc_val=None, _exc_tb=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_val: Option<PyObject>,
+        _exc_tb: Option<PyObject>,
+    ) -> bool {
+        self.inner.reset();
+        false
+    }
+
     /// Get the deconvolved activity (non-negative spike train).
     fn get_solution<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
         PyArray1::from_vec(py, self.inner.get_solution())
     }
 
+    /// `(index, value)` pairs of the activity entries at or above `threshold`.
+    #[pyo3(signature = (threshold=1e-6))]
+    fn get_sparse_solution(&self, threshold: f32) -> Vec<(u32, f32)> {
+        self.inner.get_sparse_solution(threshold)
+    }
+
+    /// Number of activity entries at or above `threshold`.
+    #[pyo3(signature = (threshold=1e-6))]
+    fn get_spike_count(&self, threshold: f32) -> u32 {
+        self.inner.get_spike_count(threshold)
+    }
+
     /// Get reconvolution (K*s) for the active region.
     fn get_reconvolution<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
         PyArray1::from_vec(py, self.inner.get_reconvolution())
@@ -150,6 +280,77 @@ impl PySolver {
         self.inner.iteration_count()
     }
 
+    /// Lipschitz constant of the data term used for the step size.
+    fn get_lipschitz(&self) -> f64 {
+        self.inner.get_lipschitz()
+    }
+
+    /// Re-estimate the Lipschitz constant by `n_iters` power-iteration steps
+    /// with the active convolution engine; stores and returns it.
+    fn reestimate_lipschitz(&mut self, n_iters: u32) -> f64 {
+        self.inner.reestimate_lipschitz(n_iters)
+    }
+
+    /// Re-estimate the Lipschitz constant every `window_frames` FISTA
+    /// iterations by one power-iteration step on a window of that length
+    /// (0 turns this off).
+    fn set_rolling_lipschitz(&mut self, window_frames: usize) {
+        self.inner.set_rolling_lipschitz(window_frames);
+    }
+
+    /// Objective `0.5 * ||K*x + b - y||^2 + lambda * ||x||_1` at the current
+    /// solution (lambda DC-gain scaled, plus the elastic-net ridge if set).
+    fn compute_objective(&mut self) -> f64 {
+        self.inner.compute_objective()
+    }
+
+    /// The most recently computed objective (infinity until one has been).
+    fn get_objective(&self) -> f64 {
+        self.inner.get_objective()
+    }
+
+    /// Record each FISTA iteration, keeping the most recent `capacity`
+    /// (0 turns recording off). Clears any existing history.
+    fn enable_history(&mut self, capacity: usize) {
+        self.inner.enable_history(capacity);
+    }
+
+    /// Drop the recorded iterations, keeping recording enabled.
+    fn clear_history(&mut self) {
+        self.inner.clear_history();
+    }
+
+    /// Recorded iterations, oldest first, as dicts with keys `iteration`,
+    /// `diff_sq_rel`, `t_fista`, `restarted` and `objective`.
+    fn get_history<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+        self.inner
+            .get_history()
+            .iter()
+            .map(|r| {
+                let dict = pyo3::types::PyDict::new(py);
+                dict.set_item("iteration", r.iteration)?;
+                dict.set_item("diff_sq_rel", r.diff_sq_rel)?;
+                dict.set_item("t_fista", r.t_fista)?;
+                dict.set_item("restarted", r.restarted)?;
+                dict.set_item("objective", r.objective)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Refit the amplitudes on the current support by least squares
+    /// (conjugate gradient), removing the L1 shrinkage. Call after
+    /// convergence; returns the number of CG iterations run.
+    #[pyo3(signature = (max_cg_iters=50))]
+    fn run_debiasing(&mut self, max_cg_iters: u32) -> u32 {
+        self.inner.run_debiasing(max_cg_iters)
+    }
+
+    /// Whether run_debiasing has refitted the current solution.
+    fn debiased(&self) -> bool {
+        self.inner.debiased()
+    }
+
     /// Apply bandpass filter to loaded trace.
     fn apply_filter(&mut self) -> bool {
         self.inner.apply_filter()
@@ -180,7 +381,8 @@ impl PySolver {
         self.inner.filter_enabled()
     }
 
-    /// Set convolution mode: "fft" or "banded".
+    /// Set convolution mode: "fft", "banded", "banded_ar1", "banded_arp" or
+    /// "fft_overlap_add".
     fn set_conv_mode(&mut self, mode: &str) -> PyResult<()> {
         self.inner.set_conv_mode(parse_conv_mode(mode)?);
         Ok(())
@@ -191,6 +393,298 @@ impl PySolver {
         self.inner.set_constraint(parse_constraint(constraint)?);
         Ok(())
     }
+
+    /// Set kernel normalization: "peak" (default), "area" (sum(h)/fs = 1),
+    /// "l2" (sum(h^2) = 1) or "none" (raw AR impulse response).
+    fn set_kernel_norm(&mut self, norm: &str) -> PyResult<()> {
+        self.inner.set_kernel_norm(parse_kernel_norm(norm)?);
+        Ok(())
+    }
+
+    /// Set FFT memory mode: "standard" (default) or "low" (overlap-add FFT
+    /// with O(kernel_len) buffers for long traces).
+    fn set_memory_mode(&mut self, mode: &str) -> PyResult<()> {
+        self.inner.set_memory_mode(parse_memory_mode(mode)?);
+        Ok(())
+    }
+
+    /// Enable Armijo backtracking line search on the FISTA step size.
+    fn set_line_search(&mut self, enabled: bool) {
+        self.inner.set_line_search(enabled);
+    }
+
+    /// Keep nonzero solution entries at least `n` frames apart (0 = off) by
+    /// a heuristic post-hoc projection after each FISTA step.
+    fn set_refractory_frames(&mut self, n: usize) {
+        self.inner.set_refractory_frames(n);
+    }
+
+    /// Minimum spike spacing enforced by `step_batch` (0 = off).
+    fn refractory_frames(&self) -> usize {
+        self.inner.refractory_frames()
+    }
+
+    /// Restart FISTA momentum only when the gradient-mapping inner product
+    /// exceeds `relative_tol` times the product of the two step norms
+    /// (default 0 = any positive product; negative values count as 0).
+    fn set_restart_threshold(&mut self, relative_tol: f64) {
+        self.inner.set_restart_threshold(relative_tol);
+    }
+
+    /// Relative adaptive-restart margin (see `set_restart_threshold`).
+    fn restart_threshold(&self) -> f64 {
+        self.inner.restart_threshold()
+    }
+
+    /// Set the penalty: "l1" (default) or "elastic_net", which adds
+    /// `lambda2/2 * ||x||^2` and replaces the L1 lambda with `lambda1` (the
+    /// current lambda when omitted).
+    #[pyo3(signature = (regularizer, lambda1=None, lambda2=0.0))]
+    fn set_regularizer(
+        &mut self,
+        regularizer: &str,
+        lambda1: Option<f64>,
+        lambda2: f64,
+    ) -> PyResult<()> {
+        let regularizer = match regularizer {
+            "l1" => Regularizer::L1,
+            "elastic_net" => {
+                if !(lambda2 >= 0.0 && lambda2.is_finite()) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "lambda2 must be non-negative and finite",
+                    ));
+                }
+                Regularizer::ElasticNet {
+                    lambda1: lambda1.unwrap_or(self.inner.lambda),
+                    lambda2,
+                }
+            }
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "regularizer must be 'l1' or 'elastic_net'",
+                ))
+            }
+        };
+        self.inner.set_regularizer(regularizer);
+        Ok(())
+    }
+
+    /// Select the pre-solve filter: "bandpass" (default, kernel-derived cutoffs
+    /// toggled by set_hp/lp_filter_enabled), "butterworth" (zero-phase IIR with
+    /// explicit `low_hz` high-pass and/or `high_hz` low-pass edges, designed for
+    /// `fs`), "savitzky_golay" (`window`-sample, degree-`poly_order` smoothing),
+    /// "exponential" (causal one-pole smoothing with factor `smooth_alpha`) or
+    /// "zero_phase_exponential" (the same run forward and backward) or "median"
+    /// (sliding median over `2 * half_window + 1` samples).
+    #[pyo3(signature = (kind, fs=30.0, order=4, low_hz=None, high_hz=None, window=11, poly_order=3, smooth_alpha=0.3, half_window=2))]
+    #[allow(clippy::too_many_arguments)]
+    fn set_filter_type(
+        &mut self,
+        kind: &str,
+        fs: f64,
+        order: u8,
+        low_hz: Option<f64>,
+        high_hz: Option<f64>,
+        window: usize,
+        poly_order: usize,
+        smooth_alpha: f64,
+        half_window: usize,
+    ) -> PyResult<()> {
+        let filter_type = match kind {
+            "bandpass" => FilterType::Bandpass,
+            "butterworth" => {
+                FilterType::Butterworth(ButterworthFilter::new(order, low_hz, high_hz, fs)?)
+            }
+            "savitzky_golay" => {
+                if poly_order >= window || window % 2 == 0 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "savitzky_golay needs an odd window greater than poly_order",
+                    ));
+                }
+                FilterType::SavitzkyGolay { window, poly_order }
+            }
+            "exponential" => FilterType::Exponential(ExponentialSmoothFilter::new(smooth_alpha)?),
+            "zero_phase_exponential" => {
+                FilterType::ZeroPhaseExponential(ForwardBackwardExponential::new(smooth_alpha)?)
+            }
+            "median" => FilterType::Median { half_window },
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "filter kind must be 'bandpass', 'butterworth', 'savitzky_golay', \
+                     'exponential', 'zero_phase_exponential' or 'median'",
+                ))
+            }
+        };
+        self.inner.set_filter_type(filter_type);
+        Ok(())
+    }
+
+    /// Set iteration scheme: "fista" (default), "admm", "sfista" or
+    /// "diagonal". For ADMM, `rho <= 0` uses the default penalty 0.1 *
+    /// Lipschitz constant. "sfista" updates one random `block_size` block per
+    /// step, with `variance_reduction` "svrg" (default) or "sgd". "diagonal"
+    /// is FISTA with per-frame steps from the diagonal of K^T K.
+    #[pyo3(signature = (mode, rho=0.0, block_size=1024, variance_reduction="svrg"))]
+    fn set_solver_mode(
+        &mut self,
+        mode: &str,
+        rho: f64,
+        block_size: usize,
+        variance_reduction: &str,
+    ) -> PyResult<()> {
+        let mode = match mode {
+            "fista" => SolverMode::Fista,
+            "admm" => SolverMode::Admm { rho },
+            "diagonal" => SolverMode::Diagonal,
+            "sfista" => {
+                let variance_reduction = match variance_reduction {
+                    "svrg" => VRMode::Svrg,
+                    "sgd" => VRMode::Sgd,
+                    _ => {
+                        return Err(pyo3::exceptions::PyValueError::new_err(
+                            "variance_reduction must be 'svrg' or 'sgd'",
+                        ))
+                    }
+                };
+                if block_size == 0 {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "block_size must be at least 1",
+                    ));
+                }
+                SolverMode::StochasticFista {
+                    block_size,
+                    variance_reduction,
+                }
+            }
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "solver mode must be 'fista', 'admm', 'sfista' or 'diagonal'",
+                ))
+            }
+        };
+        self.inner.set_solver_mode(mode);
+        Ok(())
+    }
+
+    /// Set the FISTA convergence test: "primal_residual" (default) or
+    /// "dual_gap", which stops on a relative duality gap below the tolerance
+    /// and is evaluated every `max_iters_between_checks` iterations.
+    #[pyo3(signature = (criterion, max_iters_between_checks=10))]
+    fn set_stopping_criterion(
+        &mut self,
+        criterion: &str,
+        max_iters_between_checks: u32,
+    ) -> PyResult<()> {
+        let criterion = match criterion {
+            "primal_residual" => StoppingCriterion::PrimalResidual,
+            "dual_gap" => StoppingCriterion::DualGap {
+                max_iters_between_checks,
+            },
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "stopping criterion must be 'primal_residual' or 'dual_gap'",
+                ))
+            }
+        };
+        self.inner.set_stopping_criterion(criterion);
+        Ok(())
+    }
+}
+
+/// Fluent builder for PySolver, validating the configuration in `build()`.
+///
+/// `PySolverBuilder().tau_rise(0.02).tau_decay(0.4).lambda_(0.01).fs(30.0).build()`.
+/// Each setter returns the builder; `build()` raises ValueError naming the
+/// first missing or invalid parameter.
+#[pyclass]
+#[derive(Default)]
+pub struct PySolverBuilder {
+    inner: SolverBuilder,
+}
+
+#[pymethods]
+impl PySolverBuilder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn tau_rise(mut slf: PyRefMut<'_, Self>, tau_rise: f64) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().tau_rise(tau_rise);
+        slf
+    }
+
+    fn tau_decay(mut slf: PyRefMut<'_, Self>, tau_decay: f64) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().tau_decay(tau_decay);
+        slf
+    }
+
+    fn lambda_(mut slf: PyRefMut<'_, Self>, lambda: f64) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().lambda(lambda);
+        slf
+    }
+
+    fn fs(mut slf: PyRefMut<'_, Self>, fs: f64) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().fs(fs);
+        slf
+    }
+
+    /// "fft", "banded" or "banded_ar1".
+    fn conv_mode<'py>(mut slf: PyRefMut<'py, Self>, mode: &str) -> PyResult<PyRefMut<'py, Self>> {
+        slf.inner = slf.inner.clone().conv_mode(parse_conv_mode(mode)?);
+        Ok(slf)
+    }
+
+    /// "nonneg" or "box01".
+    fn constraint<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        constraint: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.inner = slf.inner.clone().constraint(parse_constraint(constraint)?);
+        Ok(slf)
+    }
+
+    fn hp_filter(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().hp_filter(enabled);
+        slf
+    }
+
+    fn lp_filter(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.inner = slf.inner.clone().lp_filter(enabled);
+        slf
+    }
+
+    fn build(&self) -> PyResult<PySolver> {
+        let inner = self.inner.build()?;
+        Ok(PySolver { inner })
+    }
+}
+
+/// Cancellation handle for `py_indeca_solve_trace`.
+///
+/// The solve releases the GIL, so `cancel()` can be called from another
+/// thread (or a notebook widget callback) while it runs; the solve then
+/// returns the best result found so far.
+#[pyclass]
+#[derive(Default)]
+pub struct PyCancelToken {
+    inner: indeca::CancelToken,
+}
+
+#[pymethods]
+impl PyCancelToken {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
 }
 
 /// Build a double-exponential calcium kernel, returned as numpy float32 array.
@@ -205,6 +699,13 @@ fn py_build_kernel<'py>(
     PyArray1::from_vec(py, kernel)
 }
 
+/// Default kernel length in samples for `tau_decay` at `fs` (the length
+/// `build_kernel` uses).
+#[pyfunction]
+fn py_suggest_kernel_length(tau_decay: f64, fs: f64) -> usize {
+    crate::suggest_kernel_length(tau_decay, fs)
+}
+
 /// Compute Lipschitz constant for a kernel.
 #[pyfunction]
 fn py_compute_lipschitz(kernel: PyReadonlyArray1<f32>) -> PyResult<f64> {
@@ -214,6 +715,144 @@ fn py_compute_lipschitz(kernel: PyReadonlyArray1<f32>) -> PyResult<f64> {
     Ok(compute_lipschitz(slice))
 }
 
+/// Lipschitz constant of the banded AR(2) engine (peak-normalized, as used
+/// by the solver in "banded" mode), for comparison with `py_compute_lipschitz`.
+#[pyfunction]
+fn py_compute_lipschitz_banded(tau_rise: f64, tau_decay: f64, fs: f64) -> PyResult<f64> {
+    Ok(BandedAR2::new(tau_rise, tau_decay, fs)?.lipschitz())
+}
+
+/// Reject AR(p) time constants the model cannot use.
+fn validate_ar_taus(taus: &[f64], fs: f64) -> PyResult<()> {
+    if taus.is_empty() || taus.iter().any(|&t| !(t.is_finite() && t > 0.0)) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "taus must be a non-empty list of positive time constants",
+        ));
+    }
+    if !(fs.is_finite() && fs > 0.0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "fs must be positive",
+        ));
+    }
+    Ok(())
+}
+
+/// Unit-peak impulse response of the AR(p) model with the given time
+/// constants (`ARpModel::from_time_constants`).
+#[pyfunction]
+fn py_ar_model_from_taus<'py>(
+    py: Python<'py>,
+    taus: Vec<f64>,
+    fs: f64,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    validate_ar_taus(&taus, fs)?;
+    let model = ARpModel::from_time_constants(&taus, fs);
+    Ok(PyArray1::from_vec(py, model.impulse_response()))
+}
+
+/// Estimate `(tau_rise, tau_decay)` in seconds from an empirical
+/// autocorrelation (lags 0, 1, 2, ...) by solving the AR(2) Yule-Walker
+/// equations. Raises ValueError when the fit is not a stable rise-then-decay.
+#[pyfunction]
+fn py_fit_ar2_from_acf(acf: PyReadonlyArray1<f64>, fs: f64) -> PyResult<(f64, f64)> {
+    let acf = acf.as_array().to_vec();
+    Ok(crate::banded::ar2_taus_from_acf(&acf, fs)?)
+}
+
+/// Stability of the banded AR(2) engine for these taus: `(is_stable,
+/// stability_margin)`, where the margin is `min(1 - |d|, 1 - |r|)` over the
+/// decay and rise roots. Never raises; an unstable set has a non-positive
+/// (or NaN) margin.
+#[pyfunction]
+fn py_check_ar2_stability(tau_rise: f64, tau_decay: f64, fs: f64) -> (bool, f64) {
+    crate::banded::ar2_stability(tau_rise, tau_decay, fs)
+}
+
+/// Power response `|H(e^{j2πf/fs})|^2` of the AR(2) recursion for these taus
+/// at each frequency in `freqs_hz` (unnormalized: `(1 / (1 - g1 - g2))^2` at
+/// DC). Raises RuntimeError for an unstable tau set.
+#[pyfunction]
+fn py_ar2_frequency_response<'py>(
+    py: Python<'py>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    freqs_hz: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    let banded = BandedAR2::new(tau_rise, tau_decay, fs)?;
+    let freqs = freqs_hz.as_array().to_vec();
+    Ok(PyArray1::from_vec(
+        py,
+        banded.frequency_response(&freqs, fs),
+    ))
+}
+
+/// Dot-product adjoint test of a convolution engine ("fft", "banded",
+/// "banded_ar1", "banded_arp" or "fft_overlap_add"; "banded_ar1" ignores
+/// `tau_rise`). Returns the largest relative
+/// error over `n_pairs` random pairs; raises RuntimeError above `tol`.
+#[pyfunction]
+#[pyo3(signature = (tau_rise, tau_decay, fs, n, mode, tol=1e-3, n_pairs=8))]
+fn py_check_adjoint(
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    n: usize,
+    mode: &str,
+    tol: f64,
+    n_pairs: usize,
+) -> PyResult<f64> {
+    let result = match parse_conv_mode(mode)? {
+        conv @ (ConvMode::Fft | ConvMode::FftOverlapAdd) => {
+            let kernel = build_kernel(tau_rise, tau_decay, fs);
+            let fft = std::cell::RefCell::new(crate::fft::FftConvolver::new());
+            if conv == ConvMode::Fft {
+                fft.borrow_mut().ensure_buffers(n, &kernel);
+            } else {
+                fft.borrow_mut().ensure_buffers_overlap_add(n, &kernel);
+            }
+            check_adjoint_op(
+                |x, out| fft.borrow_mut().convolve_forward(x, n, out),
+                |y, out| fft.borrow_mut().convolve_adjoint(y, n, out),
+                n,
+                tol,
+                n_pairs,
+            )
+        }
+        ConvMode::BandedAR2 => {
+            let banded = BandedAR2::new(tau_rise, tau_decay, fs)?;
+            check_adjoint_op(
+                |x, out| banded.convolve_forward(x, out),
+                |y, out| banded.convolve_adjoint(y, out),
+                n,
+                tol,
+                n_pairs,
+            )
+        }
+        ConvMode::BandedAR1 => {
+            let banded = BandedAR1::new(tau_decay, fs);
+            check_adjoint_op(
+                |x, out| banded.convolve_forward(x, out),
+                |y, out| banded.convolve_adjoint(y, out),
+                n,
+                tol,
+                n_pairs,
+            )
+        }
+        ConvMode::BandedARp => {
+            let model = ARpModel::from_time_constants(&[tau_decay, tau_rise], fs);
+            check_adjoint_op(
+                |x, out| model.convolve_forward(x, out),
+                |y, out| model.convolve_adjoint(y, out),
+                n,
+                tol,
+                n_pairs,
+            )
+        }
+    };
+    result.map_err(pyo3::exceptions::PyRuntimeError::new_err)
+}
+
 /// Configure solver conv_mode and constraint from string args.
 fn configure_solver_options(
     solver: &mut Solver,
@@ -225,13 +864,169 @@ fn configure_solver_options(
     Ok(())
 }
 
-/// One-shot deconvolution for a single 1D trace.
-/// Returns (activity, baseline, reconvolution, iterations, converged).
-#[pyfunction]
-#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
-fn deconvolve_single<'py>(
-    py: Python<'py>,
-    trace: PyReadonlyArray1<f64>,
+/// Result of a single-trace solve with named attributes.
+///
+/// Mirrors the fields of the InDeCa `InDecaResult`. For the FISTA solve of
+/// `deconvolve_single`, `s_counts` is the continuous activity, `alpha` is 1
+/// (activity is in trace units), `threshold` is 0 (no binarization),
+/// `filtered_trace` is the filtered, baseline-subtracted trace that was fit
+/// and `reconvolution` is the fitted trace including the baseline.
+#[pyclass]
+pub struct PyInDecaResult {
+    #[pyo3(get)]
+    s_counts: Py<PyArray1<f32>>,
+    #[pyo3(get)]
+    alpha: f64,
+    #[pyo3(get)]
+    baseline: f64,
+    #[pyo3(get)]
+    threshold: f64,
+    #[pyo3(get)]
+    pve: f64,
+    #[pyo3(get)]
+    iterations: u32,
+    #[pyo3(get)]
+    converged: bool,
+    #[pyo3(get)]
+    noise_std: f64,
+    #[pyo3(get)]
+    snr_db: f64,
+    #[pyo3(get)]
+    filtered_trace: Option<Py<PyArray1<f32>>>,
+    #[pyo3(get)]
+    reconvolution: Option<Py<PyArray1<f32>>>,
+}
+
+#[pymethods]
+impl PyInDecaResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "PyInDecaResult(alpha={:.4}, baseline={:.4}, threshold={:.4}, pve={:.4}, \
+             iterations={}, converged={}, noise_std={:.4}, snr_db={:.2})",
+            self.alpha,
+            self.baseline,
+            self.threshold,
+            self.pve,
+            self.iterations,
+            if self.converged { "True" } else { "False" },
+            self.noise_std,
+            self.snr_db
+        )
+    }
+
+    /// All attributes as a dict (arrays stay numpy arrays).
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("s_counts", self.s_counts.clone_ref(py))?;
+        dict.set_item("alpha", self.alpha)?;
+        dict.set_item("baseline", self.baseline)?;
+        dict.set_item("threshold", self.threshold)?;
+        dict.set_item("pve", self.pve)?;
+        dict.set_item("iterations", self.iterations)?;
+        dict.set_item("converged", self.converged)?;
+        dict.set_item("noise_std", self.noise_std)?;
+        dict.set_item("snr_db", self.snr_db)?;
+        dict.set_item(
+            "filtered_trace",
+            self.filtered_trace.as_ref().map(|a| a.clone_ref(py)),
+        )?;
+        dict.set_item(
+            "reconvolution",
+            self.reconvolution.as_ref().map(|a| a.clone_ref(py)),
+        )?;
+        Ok(dict)
+    }
+
+    /// Serialize to JSON via `InDecaResult::to_json` (arrays as base64 f32
+    /// bytes). `reconvolution` is not part of `InDecaResult` and is not
+    /// stored.
+    fn to_json(&self, py: Python<'_>) -> PyResult<String> {
+        let result = indeca::InDecaResult {
+            s_counts: self.s_counts.bind(py).to_vec()?,
+            filtered_trace: self
+                .filtered_trace
+                .as_ref()
+                .map(|a| a.bind(py).to_vec())
+                .transpose()?,
+            alpha: self.alpha,
+            baseline: self.baseline,
+            threshold: self.threshold,
+            pve: self.pve,
+            iterations: self.iterations,
+            converged: self.converged,
+            n_saturated_frames: 0,
+            noise_std: self.noise_std,
+            snr_db: self.snr_db,
+        };
+        result
+            .to_json()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    /// Rebuild a result from `to_json` output (`reconvolution` is None).
+    /// Raises ValueError on malformed JSON.
+    #[staticmethod]
+    fn from_json(py: Python<'_>, s: &str) -> PyResult<PyInDecaResult> {
+        let result = indeca::InDecaResult::from_json(s)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(PyInDecaResult::from_indeca(py, result))
+    }
+}
+
+impl PyInDecaResult {
+    /// Wrap an InDeCa pipeline result (no `reconvolution`).
+    fn from_indeca(py: Python<'_>, result: indeca::InDecaResult) -> PyInDecaResult {
+        PyInDecaResult {
+            s_counts: PyArray1::from_vec(py, result.s_counts).unbind(),
+            alpha: result.alpha,
+            baseline: result.baseline,
+            threshold: result.threshold,
+            pve: result.pve,
+            iterations: result.iterations,
+            converged: result.converged,
+            noise_std: result.noise_std,
+            snr_db: result.snr_db,
+            filtered_trace: result
+                .filtered_trace
+                .map(|t| PyArray1::from_vec(py, t).unbind()),
+            reconvolution: None,
+        }
+    }
+}
+
+/// Fraction of variance explained by `fit`, the residual std, and the fit
+/// SNR in dB (power of `fit - baseline` over residual power, as in
+/// `analysis::estimate_trace_snr`).
+fn fit_quality(y: &[f32], fit: &[f32], baseline: f64) -> (f64, f64, f64) {
+    let n = y.len().min(fit.len());
+    if n == 0 {
+        return (0.0, 0.0, f64::NAN);
+    }
+    let mean = y[..n].iter().map(|&v| v as f64).sum::<f64>() / n as f64;
+    let (mut ss_res, mut ss_tot, mut res_sum, mut ss_sig) = (0.0, 0.0, 0.0, 0.0);
+    for (&yv, &fv) in y[..n].iter().zip(&fit[..n]) {
+        let r = yv as f64 - fv as f64;
+        ss_res += r * r;
+        res_sum += r;
+        ss_tot += (yv as f64 - mean).powi(2);
+        ss_sig += (fv as f64 - baseline).powi(2);
+    }
+    let pve = if ss_tot > 0.0 {
+        1.0 - ss_res / ss_tot
+    } else {
+        0.0
+    };
+    let res_mean = res_sum / n as f64;
+    let noise_std = (ss_res / n as f64 - res_mean * res_mean).max(0.0).sqrt();
+    (pve, noise_std, 10.0 * (ss_sig / ss_res).log10())
+}
+
+/// Shared setup + solve for `deconvolve_single` / `deconvolve_single_tuple`.
+/// The GIL is released while solving.
+#[allow(clippy::too_many_arguments)]
+fn solve_single(
+    py: Python<'_>,
+    trace: &PyReadonlyArray1<f64>,
     fs: f64,
     tau_rise: f64,
     tau_decay: f64,
@@ -241,19 +1036,40 @@ fn deconvolve_single<'py>(
     max_iters: u32,
     conv_mode: &str,
     constraint: &str,
-) -> PyResult<(
-    Bound<'py, PyArray1<f32>>,
-    f64,
-    Bound<'py, PyArray1<f32>>,
-    u32,
-    bool,
-)> {
+) -> PyResult<Solver> {
+    let trace_f32 = to_f32_vec(trace)?;
+    py.allow_threads(|| {
+        solve_single_trace(
+            &trace_f32, fs, tau_rise, tau_decay, lambda_, hp_enabled, lp_enabled, max_iters,
+            conv_mode, constraint,
+        )
+    })
+}
+
+/// Filter → baseline → FISTA on one trace with a fresh `Solver`; needs no GIL.
+#[allow(clippy::too_many_arguments)]
+fn solve_single_trace(
+    trace: &[f32],
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<Solver> {
+    let lambda_ = if lambda_ == 0.0 {
+        indeca::select_lambda(trace, tau_rise, tau_decay, fs)
+    } else {
+        lambda_
+    };
+
     let mut solver = Solver::new();
     solver.set_params(tau_rise, tau_decay, lambda_, fs);
     configure_solver_options(&mut solver, conv_mode, constraint)?;
-
-    let trace_f32 = to_f32_vec(&trace)?;
-    solver.set_trace(&trace_f32);
+    solver.set_trace(trace);
 
     if hp_enabled || lp_enabled {
         solver.set_hp_filter_enabled(hp_enabled);
@@ -264,23 +1080,65 @@ fn deconvolve_single<'py>(
     solver.subtract_baseline();
 
     run_to_convergence(&mut solver, max_iters);
-
-    Ok((
-        PyArray1::from_vec(py, solver.get_solution()),
-        solver.get_baseline(),
-        PyArray1::from_vec(py, solver.get_reconvolution_with_baseline()),
-        solver.iteration_count(),
-        solver.converged(),
-    ))
+    Ok(solver)
 }
 
-/// Batch deconvolution for a 2D array of traces (n_cells x n_timepoints).
-/// Returns (activities, baselines, reconvolutions, iterations, convergeds).
+/// One-shot deconvolution for a single 1D trace, returned as a
+/// `PyInDecaResult` (see `PyInDecaResult` for the field mapping).
+///
+/// `lambda_ = 0.0` selects lambda automatically from the trace's noise floor
+/// (`indeca::select_lambda`).
 #[pyfunction]
-#[pyo3(signature = (traces, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
-fn deconvolve_batch<'py>(
+#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
+fn deconvolve_single(
+    py: Python<'_>,
+    trace: PyReadonlyArray1<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<PyInDecaResult> {
+    let mut solver = solve_single(
+        py, &trace, fs, tau_rise, tau_decay, lambda_, hp_enabled, lp_enabled, max_iters, conv_mode,
+        constraint,
+    )?;
+    Ok(single_result(py, &mut solver))
+}
+
+/// `PyInDecaResult` of a converged single-trace FISTA solve.
+fn single_result(py: Python<'_>, solver: &mut Solver) -> PyInDecaResult {
+    let fitted = solver.get_trace();
+    let reconvolution = solver.get_reconvolution_with_baseline();
+    let baseline = solver.get_baseline();
+    let (pve, noise_std, snr_db) = fit_quality(&fitted, &reconvolution, baseline);
+
+    PyInDecaResult {
+        s_counts: PyArray1::from_vec(py, solver.get_solution()).unbind(),
+        alpha: 1.0,
+        baseline,
+        threshold: 0.0,
+        pve,
+        iterations: solver.iteration_count(),
+        converged: solver.converged(),
+        noise_std,
+        snr_db,
+        filtered_trace: Some(PyArray1::from_vec(py, fitted).unbind()),
+        reconvolution: Some(PyArray1::from_vec(py, reconvolution).unbind()),
+    }
+}
+
+/// Tuple form of `deconvolve_single`, kept for backward compatibility.
+/// Returns (activity, baseline, reconvolution, iterations, converged).
+#[pyfunction]
+#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
+fn deconvolve_single_tuple<'py>(
     py: Python<'py>,
-    traces: PyReadonlyArray2<f64>,
+    trace: PyReadonlyArray1<f64>,
     fs: f64,
     tau_rise: f64,
     tau_decay: f64,
@@ -291,197 +1149,2033 @@ fn deconvolve_batch<'py>(
     conv_mode: &str,
     constraint: &str,
 ) -> PyResult<(
-    Vec<Bound<'py, PyArray1<f32>>>,
-    Vec<f64>,
-    Vec<Bound<'py, PyArray1<f32>>>,
-    Vec<u32>,
-    Vec<bool>,
+    Bound<'py, PyArray1<f32>>,
+    f64,
+    Bound<'py, PyArray1<f32>>,
+    u32,
+    bool,
 )> {
-    let shape = traces.shape();
-    let n_cells = shape[0];
+    let mut solver = solve_single(
+        py, &trace, fs, tau_rise, tau_decay, lambda_, hp_enabled, lp_enabled, max_iters, conv_mode,
+        constraint,
+    )?;
+
+    Ok((
+        PyArray1::from_vec(py, solver.get_solution()),
+        solver.get_baseline(),
+        PyArray1::from_vec(py, solver.get_reconvolution_with_baseline()),
+        solver.iteration_count(),
+        solver.converged(),
+    ))
+}
+
+/// L1 regularization path for a single 1D trace.
+///
+/// Solves for each lambda in `lambdas` (must be decreasing), warm-starting from
+/// the previous solution. Returns a float32 array of shape
+/// (len(lambdas), n_timepoints), one solution per row.
+#[pyfunction]
+#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambdas, hp_enabled=false, lp_enabled=false, max_iters_per_lambda=2000, conv_mode="fft", constraint="nonneg"))]
+fn py_solve_path<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambdas: Vec<f64>,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters_per_lambda: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    let first_lambda = lambdas.first().copied().unwrap_or(0.0);
 
     let mut solver = Solver::new();
-    solver.set_params(tau_rise, tau_decay, lambda_, fs);
+    solver.set_params(tau_rise, tau_decay, first_lambda, fs);
+    configure_solver_options(&mut solver, conv_mode, constraint)?;
+    solver.set_trace(&trace_f32);
+
+    if hp_enabled || lp_enabled {
+        solver.set_hp_filter_enabled(hp_enabled);
+        solver.set_lp_filter_enabled(lp_enabled);
+        solver.apply_filter();
+    }
+
+    solver.subtract_baseline();
+
+    let path = solver.solve_path(&lambdas, max_iters_per_lambda)?;
+    let n = trace_f32.len();
+    let flat: Vec<f32> = path.into_iter().flatten().collect();
+    let array = numpy::ndarray::Array2::from_shape_vec((lambdas.len(), n), flat)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    Ok(PyArray2::from_owned_array(py, array))
+}
+
+/// Lambda sensitivity sweep for a single 1D trace.
+///
+/// Solves `n_lambdas` log-spaced lambdas from `lambda_max` down to
+/// `lambda_min`, warm-starting each from the previous solution (as in
+/// `solve_path`), and returns per-lambda arrays
+/// (lambda, n_spikes, pve, alpha, baseline, iterations). `n_spikes` counts
+/// frames with nonzero activity, `alpha` is the mean activity over those
+/// frames (0 when there are none), and `pve` is measured on the (filtered)
+/// trace as in `deconvolve_single`.
+#[pyfunction]
+#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambda_min, lambda_max, n_lambdas=20, hp_enabled=false, lp_enabled=false, max_iters_per_lambda=2000, conv_mode="fft", constraint="nonneg"))]
+fn py_lambda_sensitivity_arrays<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_min: f64,
+    lambda_max: f64,
+    n_lambdas: usize,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters_per_lambda: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<(
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<u64>>,
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<u32>>,
+)> {
+    let lambdas = crate::path::log_spaced_lambdas(lambda_min, lambda_max, n_lambdas)?;
+    let trace_f32 = to_f32_vec(&trace)?;
+
+    let mut solver = Solver::new();
+    solver.set_params(tau_rise, tau_decay, lambdas[0], fs);
     configure_solver_options(&mut solver, conv_mode, constraint)?;
+    solver.set_trace(&trace_f32);
 
     if hp_enabled || lp_enabled {
         solver.set_hp_filter_enabled(hp_enabled);
         solver.set_lp_filter_enabled(lp_enabled);
+        solver.apply_filter();
+    }
+
+    solver.subtract_baseline();
+    let fitted = solver.get_trace();
+
+    let n = lambdas.len();
+    let mut n_spikes = Vec::with_capacity(n);
+    let mut pves = Vec::with_capacity(n);
+    let mut alphas = Vec::with_capacity(n);
+    let mut baselines = Vec::with_capacity(n);
+    let mut iterations = Vec::with_capacity(n);
+    for &lambda in &lambdas {
+        let before = solver.iteration_count();
+        // A one-point path keeps the previous solution as the warm start.
+        let solution = solver
+            .solve_path(&[lambda], max_iters_per_lambda)?
+            .swap_remove(0);
+        iterations.push(solver.iteration_count() - before);
+
+        let (count, total) = solution
+            .iter()
+            .filter(|&&v| v > 0.0)
+            .fold((0_u64, 0.0_f64), |(c, t), &v| (c + 1, t + v as f64));
+        n_spikes.push(count);
+        alphas.push(if count > 0 { total / count as f64 } else { 0.0 });
+        let (pve, _, _) = fit_quality(
+            &fitted,
+            &solver.get_reconvolution_with_baseline(),
+            solver.get_baseline(),
+        );
+        pves.push(pve);
+        baselines.push(solver.get_baseline());
+    }
+
+    Ok((
+        PyArray1::from_vec(py, lambdas),
+        PyArray1::from_vec(py, n_spikes),
+        PyArray1::from_vec(py, pves),
+        PyArray1::from_vec(py, alphas),
+        PyArray1::from_vec(py, baselines),
+        PyArray1::from_vec(py, iterations),
+    ))
+}
+
+/// Batch deconvolution for a 2D array of traces (n_cells x n_timepoints).
+/// Returns (activities, baselines, reconvolutions, iterations, convergeds).
+/// The GIL is released while solving.
+#[pyfunction]
+#[pyo3(signature = (traces, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
+fn deconvolve_batch<'py>(
+    py: Python<'py>,
+    traces: PyReadonlyArray2<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<BatchTuple<'py>> {
+    let config = BatchConfig {
+        fs,
+        tau_rise,
+        tau_decay,
+        lambda_,
+        hp_enabled,
+        lp_enabled,
+        max_iters,
+        mode: parse_conv_mode(conv_mode)?,
+        constraint: parse_constraint(constraint)?,
+    };
+    let traces_ref = traces.as_array();
+    // Solve with the GIL released; numpy arrays are built afterwards.
+    let rows = py.allow_threads(|| batch_rows(traces_ref, &config))?;
+    Ok(batch_tuple(py, rows))
+}
+
+/// Parameters shared by every cell of a batch solve.
+#[derive(Clone, Copy)]
+struct BatchConfig {
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    mode: ConvMode,
+    constraint: Constraint,
+}
+
+impl BatchConfig {
+    fn make_solver(&self) -> Solver {
+        let mut solver = Solver::new();
+        solver.set_params(self.tau_rise, self.tau_decay, self.lambda_, self.fs);
+        solver.set_conv_mode(self.mode);
+        solver.set_constraint(self.constraint);
+        if self.filter() {
+            solver.set_hp_filter_enabled(self.hp_enabled);
+            solver.set_lp_filter_enabled(self.lp_enabled);
+        }
+        solver
+    }
+
+    fn filter(&self) -> bool {
+        self.hp_enabled || self.lp_enabled
     }
+}
+
+/// Return tuple of the batch entry points.
+type BatchTuple<'py> = (
+    Vec<Bound<'py, PyArray1<f32>>>,
+    Vec<f64>,
+    Vec<Bound<'py, PyArray1<f32>>>,
+    Vec<u32>,
+    Vec<bool>,
+);
 
+fn batch_tuple(py: Python<'_>, rows: Vec<RowResult>) -> BatchTuple<'_> {
+    let n_cells = rows.len();
     let mut activities = Vec::with_capacity(n_cells);
     let mut baselines = Vec::with_capacity(n_cells);
     let mut reconvolutions = Vec::with_capacity(n_cells);
     let mut iterations = Vec::with_capacity(n_cells);
     let mut convergeds = Vec::with_capacity(n_cells);
+    for (activity, baseline, reconvolution, iters, converged) in rows {
+        activities.push(PyArray1::from_vec(py, activity));
+        baselines.push(baseline);
+        reconvolutions.push(PyArray1::from_vec(py, reconvolution));
+        iterations.push(iters);
+        convergeds.push(converged);
+    }
+    (
+        activities,
+        baselines,
+        reconvolutions,
+        iterations,
+        convergeds,
+    )
+}
 
-    let traces_ref = traces.as_array();
-    let n_timepoints = shape[1];
+/// Sequential batch solve with one reused `Solver`; needs no GIL.
+fn batch_rows(traces: ArrayView2<'_, f64>, config: &BatchConfig) -> PyResult<Vec<RowResult>> {
+    let (n_cells, n_timepoints) = traces.dim();
+    let mut solver = config.make_solver();
     let mut trace_f32: Vec<f32> = Vec::with_capacity(n_timepoints);
-
+    let mut rows = Vec::with_capacity(n_cells);
     for cell_idx in 0..n_cells {
         trace_f32.clear();
-        trace_f32.extend(traces_ref.row(cell_idx).iter().map(|&v| v as f32));
+        trace_f32.extend(traces.row(cell_idx).iter().map(|&v| v as f32));
         if let Some(i) = crate::first_nonfinite(&trace_f32) {
             return Err(pyo3::exceptions::PyValueError::new_err(format!(
                 "{NONFINITE_ERR} at row {cell_idx}, index {i}"
             )));
         }
-        solver.set_trace(&trace_f32);
+        rows.push(deconvolve_row(
+            &mut solver,
+            &trace_f32,
+            config.filter(),
+            config.max_iters,
+        ));
+    }
+    Ok(rows)
+}
+
+// `deconvolve_batch_parallel` moves one `Solver` per worker thread. Every field
+// is an owned buffer or an `Arc`'d FFT plan, so `Solver` is `Send` without any
+// unsafe impl; this fails to compile if a future field breaks that.
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<Solver>();
+};
+
+/// Per-trace output of a batch solve: (activity, baseline, reconvolution, iterations, converged).
+type RowResult = (Vec<f32>, f64, Vec<f32>, u32, bool);
+
+/// Resolve a scikit-learn style `n_jobs` to a thread count: positive values are
+/// taken as-is, -1 means all cores, -2 all but one, and so on.
+fn resolve_n_jobs(n_jobs: i32) -> PyResult<usize> {
+    let available = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    match n_jobs {
+        0 => Err(pyo3::exceptions::PyValueError::new_err(
+            "n_jobs must be a positive integer or negative (-1 = all cores)",
+        )),
+        n if n > 0 => Ok(n as usize),
+        n => Ok((available as i64 + 1 + n as i64).max(1) as usize),
+    }
+}
+
+/// Batch deconvolution parallelized across cells with a Rayon thread pool.
+///
+/// Same arguments and return tuple as `deconvolve_batch`, plus `n_jobs`
+/// (scikit-learn convention: -1 = all available cores). Each worker owns its
+/// own `Solver`; the GIL is released for the duration of the solve. A panic
+/// while solving any cell is raised as `RuntimeError` naming the row, rather
+/// than dropping that cell's result.
+#[pyfunction]
+#[pyo3(signature = (traces, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg", n_jobs=-1))]
+fn deconvolve_batch_parallel<'py>(
+    py: Python<'py>,
+    traces: PyReadonlyArray2<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+    n_jobs: i32,
+) -> PyResult<BatchTuple<'py>> {
+    let config = BatchConfig {
+        fs,
+        tau_rise,
+        tau_decay,
+        lambda_,
+        hp_enabled,
+        lp_enabled,
+        max_iters,
+        mode: parse_conv_mode(conv_mode)?,
+        constraint: parse_constraint(constraint)?,
+    };
+    let n_threads = resolve_n_jobs(n_jobs)?;
+    let traces_ref = traces.as_array();
+    let rows = py.allow_threads(|| batch_rows_parallel(traces_ref, &config, n_threads))?;
+    Ok(batch_tuple(py, rows))
+}
 
-        if hp_enabled || lp_enabled {
-            solver.apply_filter();
+/// Rayon batch solve with one `Solver` per worker; needs no GIL.
+fn batch_rows_parallel(
+    traces: ArrayView2<'_, f64>,
+    config: &BatchConfig,
+    n_threads: usize,
+) -> PyResult<Vec<RowResult>> {
+    let mut rows: Vec<Vec<f32>> = Vec::with_capacity(traces.nrows());
+    for (cell_idx, row) in traces.rows().into_iter().enumerate() {
+        let trace_f32: Vec<f32> = row.iter().map(|&v| v as f32).collect();
+        if let Some(i) = crate::first_nonfinite(&trace_f32) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "{NONFINITE_ERR} at row {cell_idx}, index {i}"
+            )));
         }
+        rows.push(trace_f32);
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_threads)
+        .build()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+
+    let results: Vec<Result<RowResult, usize>> = pool.install(|| {
+        rows.par_iter()
+            .enumerate()
+            .map_init(
+                || config.make_solver(),
+                |solver, (cell_idx, trace)| {
+                    let solved = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        deconvolve_row(solver, trace, config.filter(), config.max_iters)
+                    }));
+                    solved.map_err(|_| {
+                        // The solver may be mid-update; give the next row a fresh one.
+                        *solver = config.make_solver();
+                        cell_idx
+                    })
+                },
+            )
+            .collect()
+    });
+
+    results
+        .into_iter()
+        .map(|result| {
+            result.map_err(|row| {
+                pyo3::exceptions::PyRuntimeError::new_err(format!(
+                    "solver panicked while deconvolving row {row}"
+                ))
+            })
+        })
+        .collect()
+}
 
-        solver.subtract_baseline();
+/// Solve one trace on an already-configured solver (filter → baseline → FISTA).
+fn deconvolve_row(solver: &mut Solver, trace: &[f32], filter: bool, max_iters: u32) -> RowResult {
+    solver.set_trace(trace);
+    if filter {
+        solver.apply_filter();
+    }
+    solver.subtract_baseline();
+    run_to_convergence(solver, max_iters);
+    (
+        solver.get_solution(),
+        solver.get_baseline(),
+        solver.get_reconvolution_with_baseline(),
+        solver.iteration_count(),
+        solver.converged(),
+    )
+}
 
-        run_to_convergence(&mut solver, max_iters);
+/// Savitzky-Golay smoothing of a single trace (window clipped to the trace
+/// length). Raises ValueError for an even window or poly_order >= window.
+#[pyfunction]
+fn py_savitzky_golay<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    window: usize,
+    poly_order: usize,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    let smoothed = crate::savitzky_golay(&trace_f32, window, poly_order)?;
+    Ok(PyArray1::from_vec(py, smoothed))
+}
 
-        activities.push(PyArray1::from_vec(py, solver.get_solution()));
-        baselines.push(solver.get_baseline());
-        reconvolutions.push(PyArray1::from_vec(
-            py,
-            solver.get_reconvolution_with_baseline(),
+/// Exponential smoothing `y[t] = alpha * x[t] + (1 - alpha) * y[t-1]` of a
+/// single trace, or with `zero_phase` run forward and backward. Raises
+/// ValueError unless `alpha` is in (0, 1].
+#[pyfunction]
+#[pyo3(signature = (trace, alpha, zero_phase=false))]
+fn py_exponential_smooth<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    alpha: f64,
+    zero_phase: bool,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let mut trace_f32 = to_f32_vec(&trace)?;
+    if zero_phase {
+        ForwardBackwardExponential::new(alpha)?.apply(&mut trace_f32);
+    } else {
+        ExponentialSmoothFilter::new(alpha)?.apply(&mut trace_f32);
+    }
+    Ok(PyArray1::from_vec(py, trace_f32))
+}
+
+/// Sliding median over `2 * half_window + 1` samples, zero-padded at the
+/// ends; `half_window = 0` returns the trace unchanged.
+#[pyfunction]
+fn py_median_filter<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    half_window: usize,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::median_filter_1d(&trace_f32, half_window),
+    ))
+}
+
+/// Population variance over a sliding `window` ending at each sample (first
+/// full window's value repeated at the start; the whole trace if `window`
+/// exceeds its length).
+#[pyfunction]
+fn py_rolling_variance<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    window: usize,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::baseline::rolling_variance(&trace_f32, window),
+    ))
+}
+
+/// Anscombe transform `2 * sqrt(max(0, x) + 3/8)`; Poisson counts come out
+/// with roughly unit variance.
+#[pyfunction]
+fn py_anscombe_transform<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::baseline::anscombe_transform(&trace_f32),
+    ))
+}
+
+/// Bias-corrected inverse of `py_anscombe_transform`, floored at 0.
+#[pyfunction]
+fn py_anscombe_inverse_transform<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::baseline::anscombe_inverse_transform(&trace_f32),
+    ))
+}
+
+/// Square root of `py_rolling_variance`.
+#[pyfunction]
+fn py_rolling_std<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    window: usize,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::baseline::rolling_std(&trace_f32, window),
+    ))
+}
+
+/// The `percentile`-th value (0-100) of the rolling standard deviation, a
+/// noise estimate that ignores transients. Raises ValueError for a
+/// percentile outside [0, 100].
+#[pyfunction]
+fn py_estimate_noise_floor(
+    trace: PyReadonlyArray1<f64>,
+    window: usize,
+    percentile: f64,
+) -> PyResult<f64> {
+    if !(0.0..=100.0).contains(&percentile) {
+        return Err(CalabError::invalid("percentile", percentile, "must be in [0, 100]").into());
+    }
+    let trace_f32 = to_f32_vec(&trace)?;
+    Ok(crate::baseline::estimate_noise_floor(
+        &trace_f32, window, percentile,
+    ))
+}
+
+/// ΔF/F0 against a rolling `quantile` baseline over a causal `window`.
+/// Returns (dff, warnings); a warning message is included when F0 fell
+/// below 1e-6 somewhere and the denominator was replaced by 1.
+#[pyfunction]
+fn py_compute_dff_rolling<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    window: usize,
+    quantile: f64,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, Vec<String>)> {
+    if !(0.0..=1.0).contains(&quantile) {
+        return Err(CalabError::invalid("quantile", quantile, "must be in [0, 1]").into());
+    }
+    let trace_f32 = to_f32_vec(&trace)?;
+    let (dff, warnings) = crate::baseline::compute_dff_rolling(&trace_f32, window, quantile);
+    Ok((
+        PyArray1::from_vec(py, dff),
+        warnings.into_iter().map(|d| d.message).collect(),
+    ))
+}
+
+/// ΔF/F0 against a fixed `f0`. Returns (dff, warnings) as
+/// `py_compute_dff_rolling`.
+#[pyfunction]
+fn py_compute_dff_global<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    f0: f64,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, Vec<String>)> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    let (dff, warnings) = crate::baseline::compute_dff_global(&trace_f32, f0);
+    Ok((
+        PyArray1::from_vec(py, dff),
+        warnings.into_iter().map(|d| d.message).collect(),
+    ))
+}
+
+/// Kernel (tau_rise, tau_decay) in seconds for a named indicator preset at
+/// sampling rate `fs`. Raises ValueError for an unknown name.
+#[pyfunction]
+fn py_preset_params(name: &str, fs: f64) -> PyResult<(f64, f64)> {
+    let preset = Preset::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Preset::ALL.iter().map(|p| p.name()).collect();
+        pyo3::exceptions::PyValueError::new_err(format!(
+            "unknown preset '{name}' (available: {})",
+            names.join(", ")
+        ))
+    })?;
+    Ok(preset.params_at_fs(fs))
+}
+
+/// Names of the built-in indicator presets.
+#[pyfunction]
+fn py_list_presets() -> Vec<&'static str> {
+    Preset::ALL.iter().map(|p| p.name()).collect()
+}
+
+/// Check solver parameters. Returns one dict per finding with keys
+/// `severity` ("warning" / "error"), `field` and `message`.
+#[pyfunction]
+fn py_validate_params<'py>(
+    py: Python<'py>,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    fs: f64,
+) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+    crate::validate::validate_solver_params(tau_rise, tau_decay, lambda_, fs)
+        .into_iter()
+        .map(|d| {
+            let dict = pyo3::types::PyDict::new(py);
+            let severity = match d.severity {
+                crate::validate::Severity::Warning => "warning",
+                crate::validate::Severity::Error => "error",
+            };
+            dict.set_item("severity", severity)?;
+            dict.set_item("field", d.field)?;
+            dict.set_item("message", d.message)?;
+            Ok(dict)
+        })
+        .collect()
+}
+
+/// Load a float32/float64 `.npy` file as a float32 array of the stored shape.
+/// Raises OSError if the file cannot be read, ValueError if it is not a
+/// supported `.npy` array.
+#[pyfunction]
+fn py_read_npy<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyArrayDyn<f32>>> {
+    let (data, shape) =
+        crate::io::read_npy_f32(std::path::Path::new(path)).map_err(io_err_to_py)?;
+    let array = numpy::ndarray::ArrayD::from_shape_vec(shape, data)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok(PyArrayDyn::from_owned_array(py, array))
+}
+
+/// Read a column-per-cell CSV file. Returns (traces, cell_names) with traces
+/// shaped (n_cells, n_timepoints), float32, NaN for missing values.
+#[pyfunction]
+#[pyo3(signature = (path, delimiter=",", has_header=true))]
+fn py_read_csv_traces<'py>(
+    py: Python<'py>,
+    path: &str,
+    delimiter: &str,
+    has_header: bool,
+) -> PyResult<(Bound<'py, PyArray2<f32>>, Vec<String>)> {
+    let delimiter = match delimiter.as_bytes() {
+        [b] if b.is_ascii() => *b,
+        _ => {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "delimiter must be a single ASCII character",
+            ))
+        }
+    };
+    let traces = crate::io::read_csv_traces(std::path::Path::new(path), delimiter, has_header)
+        .map_err(io_err_to_py)?;
+    let array =
+        numpy::ndarray::Array2::from_shape_vec((traces.n_cells, traces.n_timepoints), traces.data)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok((PyArray2::from_owned_array(py, array), traces.cell_names))
+}
+
+/// Read a Suite2p plane directory given the path to its `stat.npy`. Returns
+/// (traces, iscell) with traces = F - neuropil_coeff * Fneu shaped
+/// (n_rois, n_frames), float32; raw F when `Fneu.npy` is missing.
+#[pyfunction]
+#[pyo3(signature = (stat_path, neuropil_coeff=0.7))]
+fn py_read_suite2p<'py>(
+    py: Python<'py>,
+    stat_path: &str,
+    neuropil_coeff: f64,
+) -> PyResult<(Bound<'py, PyArray2<f32>>, Vec<bool>)> {
+    let data = crate::io::read_suite2p_f(std::path::Path::new(stat_path)).map_err(io_err_to_py)?;
+    let traces = crate::io::suite2p_correct_neuropil(&data, neuropil_coeff);
+    let n_frames = traces.first().map_or(0, Vec::len);
+    let array = numpy::ndarray::Array2::from_shape_vec(
+        (traces.len(), n_frames),
+        traces.into_iter().flatten().collect(),
+    )
+    .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok((PyArray2::from_owned_array(py, array), data.iscell))
+}
+
+/// Read `F` and `Fneu` as f32 vectors of equal length.
+fn neuropil_pair(
+    f: &PyReadonlyArray1<f64>,
+    f_neu: &PyReadonlyArray1<f64>,
+) -> PyResult<(Vec<f32>, Vec<f32>)> {
+    let f = to_f32_vec(f)?;
+    let f_neu = to_f32_vec(f_neu)?;
+    if f.len() != f_neu.len() {
+        return Err(CalabError::DimensionMismatch {
+            expected: f.len(),
+            got: f_neu.len(),
+        }
+        .into());
+    }
+    Ok((f, f_neu))
+}
+
+/// Neuropil-corrected trace `F - coeff * Fneu`. Raises ValueError if the
+/// two traces differ in length.
+#[pyfunction]
+fn py_neuropil_correct<'py>(
+    py: Python<'py>,
+    f: PyReadonlyArray1<f64>,
+    f_neu: PyReadonlyArray1<f64>,
+    coeff: f64,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let (f, f_neu) = neuropil_pair(&f, &f_neu)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::io::neuropil_correct(&f, &f_neu, coeff),
+    ))
+}
+
+/// Neuropil coefficient in [0, 1] minimising the variance of
+/// `F - coeff * Fneu` over the 20% of frames with the lowest `window`-frame
+/// rolling std of `F`. Raises ValueError if the two traces differ in length.
+#[pyfunction]
+fn py_estimate_neuropil_coeff(
+    f: PyReadonlyArray1<f64>,
+    f_neu: PyReadonlyArray1<f64>,
+    window: usize,
+) -> PyResult<f64> {
+    let (f, f_neu) = neuropil_pair(&f, &f_neu)?;
+    Ok(crate::io::estimate_neuropil_coeff(&f, &f_neu, window))
+}
+
+/// Save a `PySolver`'s full solve state to a binary checkpoint file.
+/// Raises OSError if the file cannot be written, ValueError for an AR(p)
+/// kernel (not checkpointable).
+#[pyfunction]
+fn py_save_checkpoint(solver: &PySolver, path: &str) -> PyResult<()> {
+    solver
+        .inner
+        .save_checkpoint(std::path::Path::new(path))
+        .map_err(io_err_to_py)
+}
+
+/// Restore a `PySolver` from a file written by `py_save_checkpoint`; the
+/// next `step_batch` continues where the saved solver stopped. Raises
+/// OSError if the file cannot be read and ValueError if it is not a valid
+/// checkpoint or its kernel length does not match its parameters.
+#[pyfunction]
+fn py_load_checkpoint(path: &str) -> PyResult<PySolver> {
+    let inner = Solver::load_checkpoint(std::path::Path::new(path)).map_err(io_err_to_py)?;
+    Ok(PySolver { inner })
+}
+
+/// Map a file read failure to OSError and a format problem to ValueError.
+fn io_err_to_py(e: crate::io::IoError) -> PyErr {
+    match e {
+        crate::io::IoError::Io(err) => PyErr::from(err),
+        crate::io::IoError::Format(_) => pyo3::exceptions::PyValueError::new_err(e.to_string()),
+        crate::io::IoError::Solver(err) => PyErr::from(err),
+    }
+}
+
+/// Bad input surfaces as `ValueError`; numerical failures as `RuntimeError`.
+impl From<CalabError> for PyErr {
+    fn from(e: CalabError) -> Self {
+        match e {
+            CalabError::InvalidParameter { .. } | CalabError::DimensionMismatch { .. } => {
+                pyo3::exceptions::PyValueError::new_err(e.to_string())
+            }
+            CalabError::NumericalFailure(_) => {
+                pyo3::exceptions::PyRuntimeError::new_err(e.to_string())
+            }
+        }
+    }
+}
+
+/// Run peak-seeded spike detection on a single trace.
+///
+/// Returns (s_counts, alpha, baseline).
+#[pyfunction]
+fn py_seed_trace<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    fs: f64,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, f64)> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    let result = crate::peak_seed::seed_trace(&trace_f32, fs);
+    Ok((
+        PyArray1::from_vec(py, result.s_counts),
+        result.alpha,
+        result.baseline,
+    ))
+}
+
+/// Auto-estimate kernel from raw traces via peak-seeded free kernel estimation.
+///
+/// Takes a 2D array (n_cells x n_timepoints) and returns
+/// (free_kernel, tau_rise, tau_decay, tau_rise_fast, tau_decay_fast, beta_fast, n_seed_spikes).
+#[pyfunction]
+fn seed_kernel_estimate<'py>(
+    py: Python<'py>,
+    traces: PyReadonlyArray2<f64>,
+    fs: f64,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, f64, f64, f64, f64, usize)> {
+    let shape = traces.shape();
+    let n_cells = shape[0];
+    let n_timepoints = shape[1];
+
+    let mut traces_flat: Vec<f32> = Vec::with_capacity(n_cells * n_timepoints);
+    let mut trace_lengths: Vec<usize> = Vec::with_capacity(n_cells);
+
+    let traces_ref = traces.as_array();
+    for cell_idx in 0..n_cells {
+        traces_flat.extend(traces_ref.row(cell_idx).iter().map(|&v| v as f32));
+        trace_lengths.push(n_timepoints);
+    }
+    if let Some(i) = crate::first_nonfinite(&traces_flat) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "{NONFINITE_ERR} at flattened index {i}"
+        )));
+    }
+
+    let result = crate::peak_seed::seed_kernel_estimate(&traces_flat, &trace_lengths, fs)?;
+
+    Ok((
+        PyArray1::from_vec(py, result.free_kernel),
+        result.tau_rise,
+        result.tau_decay,
+        result.tau_rise_fast,
+        result.tau_decay_fast,
+        result.beta_fast,
+        result.n_seed_spikes,
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// InDeCa pipeline bindings
+// ---------------------------------------------------------------------------
+
+/// Wrap a Python callable as an InDeCa progress callback. The event is passed
+/// as a dict; an exception raised by the callable is reported through
+/// `sys.unraisablehook` instead of interrupting the solve.
+fn py_progress_fn(callback: PyObject) -> indeca::ProgressFn {
+    Box::new(move |e: indeca::ProgressEvent| {
+        Python::with_gil(|py| {
+            let call = || -> PyResult<()> {
+                let event = pyo3::types::PyDict::new(py);
+                let stage = match e.stage {
+                    indeca::Stage::Filter => "filter",
+                    indeca::Stage::ScaleIteration => "scale_iteration",
+                    indeca::Stage::Converged => "converged",
+                };
+                event.set_item("stage", stage)?;
+                event.set_item("scale_iter", e.scale_iter)?;
+                event.set_item("fista_iter", e.fista_iter)?;
+                event.set_item("pve", e.pve)?;
+                event.set_item("alpha", e.alpha)?;
+                callback.call1(py, (event,))?;
+                Ok(())
+            };
+            if let Err(err) = call() {
+                err.write_unraisable(py, None);
+            }
+        })
+    })
+}
+
+/// Parse `alpha_init` ("peak_trough", "percentile", "histogram" or "user").
+/// "percentile" takes the percentile (0–100) and "user" the alpha itself
+/// from `value`, which the others ignore.
+fn parse_alpha_init(name: &str, value: Option<f64>) -> PyResult<indeca::AlphaInit> {
+    let required = |what: &str| {
+        value.ok_or_else(|| {
+            CalabError::invalid(
+                "alpha_init_value",
+                "None",
+                format!("required for alpha_init=\"{what}\""),
+            )
+        })
+    };
+    match name {
+        "peak_trough" => Ok(indeca::AlphaInit::PeakTrough),
+        "histogram" => Ok(indeca::AlphaInit::Histogram),
+        "percentile" => {
+            let p = required(name)?;
+            if !(0.0..=100.0).contains(&p) {
+                return Err(
+                    CalabError::invalid("alpha_init_value", p, "must be in [0, 100]").into(),
+                );
+            }
+            Ok(indeca::AlphaInit::Percentile(p))
+        }
+        "user" => {
+            let v = required(name)?;
+            if !(v.is_finite() && v > 0.0) {
+                return Err(CalabError::invalid("alpha_init_value", v, "must be positive").into());
+            }
+            Ok(indeca::AlphaInit::UserProvided(v))
+        }
+        other => Err(CalabError::invalid(
+            "alpha_init",
+            other,
+            "expected \"peak_trough\", \"percentile\", \"histogram\" or \"user\"",
+        )
+        .into()),
+    }
+}
+
+/// Scale-loop stop rule for `py_indeca_solve_trace`: alpha convergence, or
+/// with `pve_min_delta` also a PVE plateau over `pve_patience` iterations.
+fn scale_stop_criterion(
+    pve_min_delta: Option<f64>,
+    pve_patience: usize,
+) -> PyResult<indeca::ScaleStopCriterion> {
+    let alpha = indeca::ScaleStopCriterion::default();
+    let Some(min_delta) = pve_min_delta else {
+        return Ok(alpha);
+    };
+    if !min_delta.is_finite() {
+        return Err(CalabError::invalid("pve_min_delta", min_delta, "must be finite").into());
+    }
+    Ok(indeca::ScaleStopCriterion::AnyOf(vec![
+        alpha,
+        indeca::ScaleStopCriterion::PveImprovement {
+            min_delta,
+            patience: pve_patience,
+        },
+    ]))
+}
+
+/// Run the full InDeCa pipeline on a single trace.
+///
+/// Returns (s_counts, alpha, baseline, threshold, pve, iterations, converged, noise_std,
+/// snr_db).
+#[pyfunction]
+#[pyo3(signature = (trace, tau_rise, tau_decay, fs, upsample_factor=1, max_iters=500, tol=1e-4, hp_enabled=false, lp_enabled=false, warm_counts=None, lambda_=0.0, noise_constrained=false, saturation_value=None, photobleaching_correction=false, boundary_mode="zero", upsample_method="linear", threshold_criterion="mse", pve_min_delta=None, pve_patience=2, alpha_init="peak_trough", alpha_init_value=None, variance_stabilize=false, on_progress=None, cancel_token=None))]
+#[allow(clippy::too_many_arguments)]
+fn py_indeca_solve_trace<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    warm_counts: Option<PyReadonlyArray1<f64>>,
+    lambda_: f64,
+    noise_constrained: bool,
+    saturation_value: Option<f64>,
+    photobleaching_correction: bool,
+    boundary_mode: &str,
+    upsample_method: &str,
+    threshold_criterion: &str,
+    pve_min_delta: Option<f64>,
+    pve_patience: usize,
+    alpha_init: &str,
+    alpha_init_value: Option<f64>,
+    variance_stabilize: bool,
+    on_progress: Option<PyObject>,
+    cancel_token: Option<PyRef<'_, PyCancelToken>>,
+) -> PyResult<IndecaTuple<'py>> {
+    let request = TraceSolve::new(
+        &trace,
+        tau_rise,
+        tau_decay,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        hp_enabled,
+        lp_enabled,
+        warm_counts,
+        lambda_,
+        noise_constrained,
+        saturation_value,
+        photobleaching_correction,
+        boundary_mode,
+        upsample_method,
+        threshold_criterion,
+        pve_min_delta,
+        pve_patience,
+        alpha_init,
+        alpha_init_value,
+        variance_stabilize,
+        on_progress,
+        cancel_token,
+    )?;
+    // Release the GIL so another thread can cancel; the progress callback
+    // re-acquires it.
+    let result = py.allow_threads(|| request.run())?;
+    Ok(indeca_tuple(py, result))
+}
+
+/// Return tuple of `py_indeca_solve_trace`.
+type IndecaTuple<'py> = (
+    Bound<'py, PyArray1<f32>>, // s_counts
+    f64,                       // alpha
+    f64,                       // baseline
+    f64,                       // threshold
+    f64,                       // pve
+    u32,                       // iterations
+    bool,                      // converged
+    f64,                       // noise_std
+    f64,                       // snr_db
+);
+
+fn indeca_tuple(py: Python<'_>, result: indeca::InDecaResult) -> IndecaTuple<'_> {
+    (
+        PyArray1::from_vec(py, result.s_counts),
+        result.alpha,
+        result.baseline,
+        result.threshold,
+        result.pve,
+        result.iterations,
+        result.converged,
+        result.noise_std,
+        result.snr_db,
+    )
+}
+
+/// Owned, validated inputs of one `solve_trace` call, parsed with the GIL
+/// held so the solve itself can run on any thread.
+struct TraceSolve {
+    trace: Vec<f32>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    warm: Option<Vec<f32>>,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    lambda_: f64,
+    opts: indeca::SolveOptions,
+    on_progress: Option<indeca::ProgressFn>,
+    cancel: Option<indeca::CancelToken>,
+}
+
+impl TraceSolve {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        trace: &PyReadonlyArray1<f64>,
+        tau_rise: f64,
+        tau_decay: f64,
+        fs: f64,
+        upsample_factor: usize,
+        max_iters: u32,
+        tol: f64,
+        hp_enabled: bool,
+        lp_enabled: bool,
+        warm_counts: Option<PyReadonlyArray1<f64>>,
+        lambda_: f64,
+        noise_constrained: bool,
+        saturation_value: Option<f64>,
+        photobleaching_correction: bool,
+        boundary_mode: &str,
+        upsample_method: &str,
+        threshold_criterion: &str,
+        pve_min_delta: Option<f64>,
+        pve_patience: usize,
+        alpha_init: &str,
+        alpha_init_value: Option<f64>,
+        variance_stabilize: bool,
+        on_progress: Option<PyObject>,
+        cancel_token: Option<PyRef<'_, PyCancelToken>>,
+    ) -> PyResult<Self> {
+        Ok(TraceSolve {
+            trace: to_f32_vec(trace)?,
+            tau_rise,
+            tau_decay,
+            fs,
+            upsample_factor,
+            max_iters,
+            tol,
+            warm: optional_to_f32_vec(warm_counts)?,
+            hp_enabled,
+            lp_enabled,
+            lambda_,
+            opts: indeca::SolveOptions {
+                noise_constrained,
+                saturation_value: saturation_value.map(|v| v as f32),
+                photobleaching_correction,
+                boundary_mode: parse_boundary_mode(boundary_mode)?,
+                upsample_method: parse_upsample_method(upsample_method)?,
+                threshold_criterion: parse_threshold_criterion(threshold_criterion)?,
+                scale_stop: scale_stop_criterion(pve_min_delta, pve_patience)?,
+                alpha_init: parse_alpha_init(alpha_init, alpha_init_value)?,
+                variance_stabilize,
+            },
+            on_progress: on_progress.map(py_progress_fn),
+            cancel: cancel_token.map(|t| t.inner.clone()),
+        })
+    }
+
+    fn run(self) -> Result<indeca::InDecaResult, CalabError> {
+        indeca::solve_trace_opts(
+            &self.trace,
+            self.tau_rise,
+            self.tau_decay,
+            self.fs,
+            self.upsample_factor,
+            self.max_iters,
+            self.tol,
+            self.warm.as_deref(),
+            self.hp_enabled,
+            self.lp_enabled,
+            self.lambda_,
+            self.opts,
+            self.on_progress,
+            self.cancel,
+        )
+    }
+}
+
+/// Tokio runtime behind the `async_*` entry points. Solves run on its
+/// blocking pool, so the asyncio event loop never waits on one.
+static ASYNC_RUNTIME: Lazy<tokio::runtime::Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the Tokio runtime for the async solver entry points")
+});
+
+/// Run `solve` on the blocking pool of `ASYNC_RUNTIME` and return an asyncio
+/// future that resolves to `convert(output)` (called with the GIL held).
+/// Must be called from a thread with a running asyncio event loop.
+fn spawn_solve<'py, T, S, C>(py: Python<'py>, solve: S, convert: C) -> PyResult<Bound<'py, PyAny>>
+where
+    T: Send + 'static,
+    S: FnOnce() -> PyResult<T> + Send + 'static,
+    C: FnOnce(Python<'_>, T) -> PyResult<PyObject> + Send + 'static,
+{
+    pyo3_async_runtimes::tokio::future_into_py(py, async move {
+        let output = tokio::task::spawn_blocking(solve).await.map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("solver task failed: {e}"))
+        })??;
+        Python::with_gil(|py| convert(py, output))
+    })
+}
+
+/// Awaitable `deconvolve_single`: same arguments, resolves to a
+/// `PyInDecaResult`.
+#[pyfunction]
+#[pyo3(signature = (trace, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg"))]
+#[allow(clippy::too_many_arguments)]
+fn async_deconvolve_single<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let trace_f32 = to_f32_vec(&trace)?;
+    let conv_mode = conv_mode.to_owned();
+    let constraint = constraint.to_owned();
+    spawn_solve(
+        py,
+        move || {
+            solve_single_trace(
+                &trace_f32,
+                fs,
+                tau_rise,
+                tau_decay,
+                lambda_,
+                hp_enabled,
+                lp_enabled,
+                max_iters,
+                &conv_mode,
+                &constraint,
+            )
+        },
+        |py, mut solver| Ok(Py::new(py, single_result(py, &mut solver))?.into_any()),
+    )
+}
+
+/// Awaitable batch deconvolution: `deconvolve_batch` for `n_jobs = 1`,
+/// otherwise `deconvolve_batch_parallel`. Resolves to the same
+/// (activities, baselines, reconvolutions, iterations, convergeds) tuple.
+#[pyfunction]
+#[pyo3(signature = (traces, fs, tau_rise, tau_decay, lambda_, hp_enabled=false, lp_enabled=false, max_iters=2000, conv_mode="fft", constraint="nonneg", n_jobs=1))]
+#[allow(clippy::too_many_arguments)]
+fn async_deconvolve_batch<'py>(
+    py: Python<'py>,
+    traces: PyReadonlyArray2<f64>,
+    fs: f64,
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda_: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    max_iters: u32,
+    conv_mode: &str,
+    constraint: &str,
+    n_jobs: i32,
+) -> PyResult<Bound<'py, PyAny>> {
+    let config = BatchConfig {
+        fs,
+        tau_rise,
+        tau_decay,
+        lambda_,
+        hp_enabled,
+        lp_enabled,
+        max_iters,
+        mode: parse_conv_mode(conv_mode)?,
+        constraint: parse_constraint(constraint)?,
+    };
+    let n_threads = resolve_n_jobs(n_jobs)?;
+    let traces = traces.as_array().to_owned();
+    spawn_solve(
+        py,
+        move || {
+            if n_threads == 1 {
+                batch_rows(traces.view(), &config)
+            } else {
+                batch_rows_parallel(traces.view(), &config, n_threads)
+            }
+        },
+        |py, rows| Ok(batch_tuple(py, rows).into_pyobject(py)?.into_any().unbind()),
+    )
+}
+
+/// Awaitable `solve_trace`: same arguments, resolves to the same tuple. The
+/// progress callback runs on a solver thread, not the event loop.
+#[pyfunction]
+#[pyo3(signature = (trace, tau_rise, tau_decay, fs, upsample_factor=1, max_iters=500, tol=1e-4, hp_enabled=false, lp_enabled=false, warm_counts=None, lambda_=0.0, noise_constrained=false, saturation_value=None, photobleaching_correction=false, boundary_mode="zero", upsample_method="linear", threshold_criterion="mse", pve_min_delta=None, pve_patience=2, alpha_init="peak_trough", alpha_init_value=None, variance_stabilize=false, on_progress=None, cancel_token=None))]
+#[allow(clippy::too_many_arguments)]
+fn async_solve_trace<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    max_iters: u32,
+    tol: f64,
+    hp_enabled: bool,
+    lp_enabled: bool,
+    warm_counts: Option<PyReadonlyArray1<f64>>,
+    lambda_: f64,
+    noise_constrained: bool,
+    saturation_value: Option<f64>,
+    photobleaching_correction: bool,
+    boundary_mode: &str,
+    upsample_method: &str,
+    threshold_criterion: &str,
+    pve_min_delta: Option<f64>,
+    pve_patience: usize,
+    alpha_init: &str,
+    alpha_init_value: Option<f64>,
+    variance_stabilize: bool,
+    on_progress: Option<PyObject>,
+    cancel_token: Option<PyRef<'_, PyCancelToken>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let request = TraceSolve::new(
+        &trace,
+        tau_rise,
+        tau_decay,
+        fs,
+        upsample_factor,
+        max_iters,
+        tol,
+        hp_enabled,
+        lp_enabled,
+        warm_counts,
+        lambda_,
+        noise_constrained,
+        saturation_value,
+        photobleaching_correction,
+        boundary_mode,
+        upsample_method,
+        threshold_criterion,
+        pve_min_delta,
+        pve_patience,
+        alpha_init,
+        alpha_init_value,
+        variance_stabilize,
+        on_progress,
+        cancel_token,
+    )?;
+    spawn_solve(
+        py,
+        move || Ok(request.run()?),
+        |py, result| {
+            Ok(indeca_tuple(py, result)
+                .into_pyobject(py)?
+                .into_any()
+                .unbind())
+        },
+    )
+}
+
+/// InDeCa pipeline over a set of traces with a scikit-learn style
+/// `fit` / `transform` / `fit_transform` interface.
+///
+/// `fit` runs `solve_trace` on every row of a 2-D `(n_cells, n_frames)`
+/// array (a 1-D array is one cell) and keeps one `PyInDecaResult` per row in
+/// `fit_results_`; fitting again replaces them. `transform` returns the
+/// stacked `s_counts` as a float32 `(n_cells, n_frames)` array.
+#[pyclass]
+pub struct PyInDeCa {
+    #[pyo3(get)]
+    tau_rise: f64,
+    #[pyo3(get)]
+    tau_decay: f64,
+    #[pyo3(get)]
+    fs: f64,
+    #[pyo3(get)]
+    upsample_factor: usize,
+    #[pyo3(get)]
+    max_iters: u32,
+    #[pyo3(get)]
+    tol: f64,
+    #[pyo3(get)]
+    hp_enabled: bool,
+    #[pyo3(get)]
+    lp_enabled: bool,
+    fit_results: Option<Vec<Py<PyInDecaResult>>>,
+}
+
+#[pymethods]
+impl PyInDeCa {
+    #[new]
+    #[pyo3(signature = (tau_rise, tau_decay, fs, upsample_factor=1, max_iters=500, tol=1e-4, hp_enabled=false, lp_enabled=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        tau_rise: f64,
+        tau_decay: f64,
+        fs: f64,
+        upsample_factor: usize,
+        max_iters: u32,
+        tol: f64,
+        hp_enabled: bool,
+        lp_enabled: bool,
+    ) -> Self {
+        PyInDeCa {
+            tau_rise,
+            tau_decay,
+            fs,
+            upsample_factor,
+            max_iters,
+            tol,
+            hp_enabled,
+            lp_enabled,
+            fit_results: None,
+        }
+    }
+
+    /// Solve every row of `traces` (2-D, or 1-D for a single cell) with the
+    /// GIL released. Returns `self`.
+    fn fit<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        traces: PyReadonlyArrayDyn<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let py = slf.py();
+        let array = traces.as_array();
+        let rows: Vec<Vec<f32>> = match array.ndim() {
+            1 => vec![array.iter().map(|&v| v as f32).collect()],
+            2 => array
+                .outer_iter()
+                .map(|row| row.iter().map(|&v| v as f32).collect())
+                .collect(),
+            d => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "traces must be 1-D or 2-D, got {d}-D"
+                )))
+            }
+        };
+        for (cell_idx, row) in rows.iter().enumerate() {
+            if let Some(i) = crate::first_nonfinite(row) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "{NONFINITE_ERR} at row {cell_idx}, index {i}"
+                )));
+            }
+        }
+
+        let this = &*slf;
+        let results: Vec<indeca::InDecaResult> = py.allow_threads(|| {
+            rows.iter()
+                .map(|row| {
+                    indeca::solve_trace(
+                        row,
+                        this.tau_rise,
+                        this.tau_decay,
+                        this.fs,
+                        this.upsample_factor,
+                        this.max_iters,
+                        this.tol,
+                        None,
+                        this.hp_enabled,
+                        this.lp_enabled,
+                        0.0,
+                        None,
+                        None,
+                    )
+                })
+                .collect::<Result<_, _>>()
+        })?;
+        let results = results
+            .into_iter()
+            .map(|r| Py::new(py, PyInDecaResult::from_indeca(py, r)))
+            .collect::<PyResult<Vec<_>>>()?;
+        slf.fit_results = Some(results);
+        Ok(slf)
+    }
+
+    /// Spike counts from the last `fit`, float32 `(n_cells, n_frames)`.
+    /// Raises ValueError before the first `fit`.
+    fn transform<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let results = self.fitted()?;
+        let n_frames = results
+            .first()
+            .map_or(0, |r| r.borrow(py).s_counts.bind(py).len());
+        let mut flat = Vec::with_capacity(results.len() * n_frames);
+        for r in results {
+            flat.extend(r.borrow(py).s_counts.bind(py).to_vec()?);
+        }
+        let array = numpy::ndarray::Array2::from_shape_vec((results.len(), n_frames), flat)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(PyArray2::from_owned_array(py, array))
+    }
+
+    /// `fit(traces)` followed by `transform()`.
+    fn fit_transform<'py>(
+        slf: PyRefMut<'py, Self>,
+        traces: PyReadonlyArrayDyn<f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let py = slf.py();
+        let slf = Self::fit(slf, traces)?;
+        slf.transform(py)
+    }
+
+    /// One `PyInDecaResult` per row of the last `fit`. Raises ValueError
+    /// before the first `fit`.
+    #[getter]
+    fn fit_results_(&self, py: Python<'_>) -> PyResult<Vec<Py<PyInDecaResult>>> {
+        Ok(self.fitted()?.iter().map(|r| r.clone_ref(py)).collect())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PyInDeCa(tau_rise={}, tau_decay={}, fs={}, upsample_factor={}, max_iters={}, \
+             tol={}, hp_enabled={}, lp_enabled={})",
+            self.tau_rise,
+            self.tau_decay,
+            self.fs,
+            self.upsample_factor,
+            self.max_iters,
+            self.tol,
+            if self.hp_enabled { "True" } else { "False" },
+            if self.lp_enabled { "True" } else { "False" },
+        )
+    }
+}
+
+impl PyInDeCa {
+    fn fitted(&self) -> PyResult<&[Py<PyInDecaResult>]> {
+        self.fit_results.as_deref().ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(
+                "this PyInDeCa instance is not fitted yet; call fit() first",
+            )
+        })
+    }
+}
+
+/// Seed the global random stream drawn on by the stochastic operations
+/// (bootstrap without an explicit seed, stochastic FISTA block order).
+/// Call before the first stochastic call for reproducible results.
+#[pyfunction]
+fn py_set_seed(seed: u64) {
+    crate::rng::set_global_seed(seed);
+}
+
+/// Bootstrap confidence band for InDeCa spike counts. `seed=None` draws the
+/// seed from the global stream (see `py_set_seed`).
+///
+/// Returns (lower_ci, upper_ci), each the length of `trace`.
+#[pyfunction]
+#[pyo3(signature = (trace, tau_rise, tau_decay, fs, upsample_factor=1, n_bootstrap=100, alpha_ci=0.05, seed=None))]
+#[allow(clippy::too_many_arguments)]
+fn py_bootstrap_spike_ci<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    n_bootstrap: usize,
+    alpha_ci: f64,
+    seed: Option<u64>,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, Bound<'py, PyArray1<f32>>)> {
+    if !(alpha_ci > 0.0 && alpha_ci < 1.0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "alpha_ci must be in (0, 1)",
+        ));
+    }
+    let trace_f32 = to_f32_vec(&trace)?;
+    let seed = seed.unwrap_or_else(crate::rng::CaLabRng::next_u64);
+    let (lower, upper) = py.allow_threads(|| {
+        indeca::bootstrap_spike_ci(
+            &trace_f32,
+            tau_rise,
+            tau_decay,
+            fs,
+            upsample_factor,
+            n_bootstrap,
+            alpha_ci,
+            seed,
+        )
+    })?;
+    Ok((PyArray1::from_vec(py, lower), PyArray1::from_vec(py, upper)))
+}
+
+/// ROC curve of a relaxed solution against a ground-truth spike train.
+///
+/// Returns (fpr, tpr, thresholds, auc). Issues a UserWarning and reports an
+/// AUC of 0.5 when the ground truth has no positive or no negative samples.
+#[pyfunction]
+#[pyo3(signature = (s_relaxed, s_true_binary, tau_decay, fs_up, n_thresholds=100))]
+fn py_roc_threshold_curve<'py>(
+    py: Python<'py>,
+    s_relaxed: PyReadonlyArray1<f64>,
+    s_true_binary: PyReadonlyArray1<f64>,
+    tau_decay: f64,
+    fs_up: f64,
+    n_thresholds: usize,
+) -> PyResult<(
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+    f64,
+)> {
+    let relaxed = to_f32_vec(&s_relaxed)?;
+    let truth = to_f32_vec(&s_true_binary)?;
+    if relaxed.len() != truth.len() {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "s_relaxed and s_true_binary must have the same length",
+        ));
+    }
+    let roc =
+        crate::threshold::roc_threshold_curve(&relaxed, &truth, tau_decay, fs_up, n_thresholds);
+    if let Some(msg) = roc.warning {
+        let msg = std::ffi::CString::new(msg).expect("warning has no NUL bytes");
+        PyErr::warn(
+            py,
+            &py.get_type::<pyo3::exceptions::PyUserWarning>(),
+            &msg,
+            1,
+        )?;
+    }
+    Ok((
+        PyArray1::from_vec(py, roc.fpr),
+        PyArray1::from_vec(py, roc.tpr),
+        PyArray1::from_vec(py, roc.thresholds),
+        roc.auc,
+    ))
+}
+
+/// Result of `py_threshold_search`: the binarized spike train and its
+/// least-squares fit to the trace.
+#[pyclass]
+pub struct PyThresholdResult {
+    #[pyo3(get)]
+    s_binary: Py<PyArray1<f32>>,
+    #[pyo3(get)]
+    alpha: f64,
+    #[pyo3(get)]
+    baseline: f64,
+    #[pyo3(get)]
+    threshold: f64,
+    #[pyo3(get)]
+    pve: f64,
+    #[pyo3(get)]
+    error: f64,
+}
+
+#[pymethods]
+impl PyThresholdResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "PyThresholdResult(alpha={:.4}, baseline={:.4}, threshold={:.4}, pve={:.4}, \
+             error={:.4})",
+            self.alpha, self.baseline, self.threshold, self.pve, self.error
+        )
+    }
+}
+
+impl PyThresholdResult {
+    fn from_threshold(py: Python<'_>, result: crate::threshold::ThresholdResult) -> Self {
+        PyThresholdResult {
+            s_binary: PyArray1::from_vec(py, result.s_binary).unbind(),
+            alpha: result.alpha,
+            baseline: result.baseline,
+            threshold: result.threshold,
+            pve: result.pve,
+            error: result.error,
+        }
+    }
+}
+
+/// Binarize a relaxed solution by the InDeCa threshold search.
+///
+/// `s_relaxed` and `y` are on the upsampled grid (`fs * upsample_factor`)
+/// and must have the same length; `s_relaxed` is expected normalized to a
+/// peak of 1, as the pipeline does before searching. Candidate binary trains
+/// are convolved through the AR(2) model and fit with a non-negative alpha
+/// plus baseline; the one with the smallest residual (highest PVE) is kept,
+/// or with `threshold_criterion="aic"` the one with the smallest AIC.
+/// `max_threshold` caps the automatic candidate grid (None for no cap).
+/// `weights` (non-negative, same length as `y`) down-weights artifact or
+/// noisy frames in the alpha/baseline fit and the residual; None weights
+/// every frame equally. Raises ValueError for an unknown criterion or bad
+/// weights.
+#[pyfunction]
+#[pyo3(signature = (s_relaxed, y, tau_rise, tau_decay, fs, upsample_factor=1, threshold_criterion="mse", max_threshold=None, weights=None))]
+#[allow(clippy::too_many_arguments)]
+fn py_threshold_search(
+    py: Python<'_>,
+    s_relaxed: PyReadonlyArray1<f64>,
+    y: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    upsample_factor: usize,
+    threshold_criterion: &str,
+    max_threshold: Option<f64>,
+    weights: Option<PyReadonlyArray1<f64>>,
+) -> PyResult<PyThresholdResult> {
+    let criterion = parse_threshold_criterion(threshold_criterion)?;
+    let relaxed = to_f32_vec(&s_relaxed)?;
+    let y = to_f32_vec(&y)?;
+    if relaxed.len() != y.len() {
+        return Err(CalabError::DimensionMismatch {
+            expected: relaxed.len(),
+            got: y.len(),
+        }
+        .into());
+    }
+    let weights = optional_to_f32_vec(weights)?;
+    if let Some(w) = &weights {
+        if w.len() != y.len() {
+            return Err(CalabError::DimensionMismatch {
+                expected: y.len(),
+                got: w.len(),
+            }
+            .into());
+        }
+        if let Some(&v) = w.iter().find(|&&v| v < 0.0) {
+            return Err(CalabError::invalid("weights", v, "must be non-negative").into());
+        }
+    }
+    if upsample_factor == 0 {
+        return Err(CalabError::invalid("upsample_factor", 0, "must be at least 1").into());
+    }
+    let fs_up = fs * upsample_factor as f64;
+    let banded = BandedAR2::new(tau_rise, tau_decay, fs_up)?;
+    let result = crate::threshold::threshold_search_capped(
+        &relaxed,
+        &y,
+        &banded,
+        tau_decay,
+        fs_up,
+        upsample_factor,
+        f64::INFINITY,
+        criterion.into(),
+        weights.as_deref(),
+        max_threshold.unwrap_or(f64::INFINITY),
+    );
+    Ok(PyThresholdResult::from_threshold(py, result))
+}
+
+/// Binarize a relaxed solution at each of `thresholds` exactly as given and
+/// keep the one with the smallest residual; no automatic grid refinement.
+/// Inputs are on the original-rate grid and must have the same length.
+/// An empty (or all-NaN) `thresholds` gives the no-spike fit.
+#[pyfunction]
+fn py_threshold_search_custom_grid(
+    py: Python<'_>,
+    s_relaxed: PyReadonlyArray1<f64>,
+    y: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    thresholds: PyReadonlyArray1<f64>,
+) -> PyResult<PyThresholdResult> {
+    let relaxed = to_f32_vec(&s_relaxed)?;
+    let y = to_f32_vec(&y)?;
+    if relaxed.len() != y.len() {
+        return Err(CalabError::DimensionMismatch {
+            expected: relaxed.len(),
+            got: y.len(),
+        }
+        .into());
+    }
+    let thresholds: Vec<f64> = thresholds.as_array().to_vec();
+    let banded = BandedAR2::new(tau_rise, tau_decay, fs)?;
+    let result = crate::threshold::threshold_search_custom_grid(
+        &relaxed,
+        &y,
+        &banded,
+        tau_decay,
+        fs,
+        &thresholds,
+    );
+    Ok(PyThresholdResult::from_threshold(py, result))
+}
+
+/// One shared binarization threshold for several cells: the threshold
+/// minimizing the sum of per-cell interior MSEs, with a separate alpha and
+/// baseline per cell. Inputs are on the original-rate grid. Returns the
+/// per-cell results and the shared threshold. Raises ValueError if the two
+/// lists, or any cell's pair of arrays, differ in length.
+#[pyfunction]
+fn py_joint_threshold_search(
+    py: Python<'_>,
+    s_relaxed_list: Vec<PyReadonlyArray1<f64>>,
+    y_list: Vec<PyReadonlyArray1<f64>>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+) -> PyResult<(Vec<PyThresholdResult>, f64)> {
+    if s_relaxed_list.len() != y_list.len() {
+        return Err(CalabError::DimensionMismatch {
+            expected: s_relaxed_list.len(),
+            got: y_list.len(),
+        }
+        .into());
+    }
+    let mut relaxed = Vec::with_capacity(s_relaxed_list.len());
+    let mut traces = Vec::with_capacity(y_list.len());
+    for (s, y) in s_relaxed_list.iter().zip(&y_list) {
+        let (s, y) = (to_f32_vec(s)?, to_f32_vec(y)?);
+        if s.len() != y.len() {
+            return Err(CalabError::DimensionMismatch {
+                expected: s.len(),
+                got: y.len(),
+            }
+            .into());
+        }
+        relaxed.push(s);
+        traces.push(y);
+    }
+    let s_refs: Vec<&[f32]> = relaxed.iter().map(Vec::as_slice).collect();
+    let y_refs: Vec<&[f32]> = traces.iter().map(Vec::as_slice).collect();
+    let banded = BandedAR2::new(tau_rise, tau_decay, fs)?;
+    let (results, threshold) =
+        crate::threshold::joint_threshold_search(&s_refs, &y_refs, &banded, tau_decay, fs);
+    let results = results
+        .into_iter()
+        .map(|r| PyThresholdResult::from_threshold(py, r))
+        .collect();
+    Ok((results, threshold))
+}
+
+/// Inter-spike intervals in seconds between adjacent frames with
+/// `s_counts >= min_count`. Empty when fewer than two frames qualify.
+#[pyfunction]
+#[pyo3(signature = (s_counts, fs, min_count=1.0))]
+fn py_spike_isis<'py>(
+    py: Python<'py>,
+    s_counts: PyReadonlyArray1<f64>,
+    fs: f64,
+    min_count: f32,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    if fs.is_nan() || fs <= 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "fs must be positive",
+        ));
+    }
+    let counts = to_f32_vec(&s_counts)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::analysis::spike_isis(&counts, fs, min_count),
+    ))
+}
+
+/// Mean ISI (NaN for no intervals).
+#[pyfunction]
+fn py_isi_mean(isis: PyReadonlyArray1<f64>) -> PyResult<f64> {
+    let isis = isis
+        .as_slice()
+        .map_err(|_| pyo3::exceptions::PyValueError::new_err(CONTIGUOUS_ERR))?;
+    Ok(crate::analysis::isi_mean(isis))
+}
+
+/// ISI coefficient of variation, std / mean (NaN for no intervals).
+#[pyfunction]
+fn py_isi_cv(isis: PyReadonlyArray1<f64>) -> PyResult<f64> {
+    let isis = isis
+        .as_slice()
+        .map_err(|_| pyo3::exceptions::PyValueError::new_err(CONTIGUOUS_ERR))?;
+    Ok(crate::analysis::isi_cv(isis))
+}
+
+/// ISI histogram over `n_bins` equal bins on [0, max_isi_s].
+///
+/// Returns (bin_edges, counts).
+#[pyfunction]
+fn py_isi_histogram<'py>(
+    py: Python<'py>,
+    isis: PyReadonlyArray1<f64>,
+    n_bins: usize,
+    max_isi_s: f64,
+) -> PyResult<(Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<u64>>)> {
+    if n_bins == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "n_bins must be at least 1",
         ));
-        iterations.push(solver.iteration_count());
-        convergeds.push(solver.converged());
     }
-
+    if max_isi_s.is_nan() || max_isi_s <= 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "max_isi_s must be positive",
+        ));
+    }
+    let isis = isis
+        .as_slice()
+        .map_err(|_| pyo3::exceptions::PyValueError::new_err(CONTIGUOUS_ERR))?;
+    let (edges, counts) = crate::analysis::isi_histogram(isis, n_bins, max_isi_s);
     Ok((
-        activities,
-        baselines,
-        reconvolutions,
-        iterations,
-        convergeds,
+        PyArray1::from_vec(py, edges),
+        PyArray1::from_vec(py, counts.into_iter().map(|c| c as u64).collect()),
     ))
 }
 
-/// Run peak-seeded spike detection on a single trace.
-///
-/// Returns (s_counts, alpha, baseline).
+/// Group spike frames into bursts whose ISIs are all at most `max_isi_s`.
+/// Returns one dict per burst with keys `start_frame`, `end_frame`,
+/// `n_spikes`, `total_amplitude` and `peak_rate_hz`.
 #[pyfunction]
-fn py_seed_trace<'py>(
+#[pyo3(signature = (s_counts, fs, max_isi_s, min_burst_spikes=2))]
+fn py_detect_bursts<'py>(
     py: Python<'py>,
-    trace: PyReadonlyArray1<f64>,
+    s_counts: PyReadonlyArray1<f64>,
     fs: f64,
-) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, f64)> {
-    let trace_f32 = to_f32_vec(&trace)?;
-    let result = crate::peak_seed::seed_trace(&trace_f32, fs);
-    Ok((
-        PyArray1::from_vec(py, result.s_counts),
-        result.alpha,
-        result.baseline,
-    ))
+    max_isi_s: f64,
+    min_burst_spikes: usize,
+) -> PyResult<Vec<Bound<'py, pyo3::types::PyDict>>> {
+    if fs.is_nan() || fs <= 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "fs must be positive",
+        ));
+    }
+    let counts = to_f32_vec(&s_counts)?;
+    crate::analysis::detect_bursts(&counts, fs, max_isi_s, min_burst_spikes)
+        .into_iter()
+        .map(|b| {
+            let dict = pyo3::types::PyDict::new(py);
+            dict.set_item("start_frame", b.start_frame)?;
+            dict.set_item("end_frame", b.end_frame)?;
+            dict.set_item("n_spikes", b.n_spikes)?;
+            dict.set_item("total_amplitude", b.total_amplitude)?;
+            dict.set_item("peak_rate_hz", b.peak_rate_hz)?;
+            Ok(dict)
+        })
+        .collect()
 }
 
-/// Auto-estimate kernel from raw traces via peak-seeded free kernel estimation.
-///
-/// Takes a 2D array (n_cells x n_timepoints) and returns
-/// (free_kernel, tau_rise, tau_decay, tau_rise_fast, tau_decay_fast, beta_fast, n_seed_spikes).
+/// Per-frame mean and standard error of `trace` over trials
+/// `event - pre_frames .. event + post_frames`; events whose window runs off
+/// either end are skipped. Returns (mean, sem), each `pre_frames +
+/// post_frames` long.
 #[pyfunction]
-fn seed_kernel_estimate<'py>(
+fn py_event_aligned_average<'py>(
     py: Python<'py>,
-    traces: PyReadonlyArray2<f64>,
-    fs: f64,
-) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, f64, f64, f64, f64, usize)> {
-    let shape = traces.shape();
-    let n_cells = shape[0];
-    let n_timepoints = shape[1];
+    trace: PyReadonlyArray1<f64>,
+    event_frames: Vec<usize>,
+    pre_frames: usize,
+    post_frames: usize,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, Bound<'py, PyArray1<f32>>)> {
+    let trace = to_f32_vec(&trace)?;
+    let (mean, sem) =
+        crate::analysis::event_aligned_average(&trace, &event_frames, pre_frames, post_frames);
+    Ok((PyArray1::from_vec(py, mean), PyArray1::from_vec(py, sem)))
+}
 
-    let mut traces_flat: Vec<f32> = Vec::with_capacity(n_cells * n_timepoints);
-    let mut trace_lengths: Vec<usize> = Vec::with_capacity(n_cells);
+/// `s_counts` around each event as an `(n_trials, pre_frames + post_frames)`
+/// array, with the windows and edge rule of `py_event_aligned_average`.
+#[pyfunction]
+fn py_event_aligned_raster<'py>(
+    py: Python<'py>,
+    s_counts: PyReadonlyArray1<f64>,
+    event_frames: Vec<usize>,
+    pre_frames: usize,
+    post_frames: usize,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let counts = to_f32_vec(&s_counts)?;
+    let rows =
+        crate::analysis::event_aligned_raster(&counts, &event_frames, pre_frames, post_frames);
+    let shape = (rows.len(), pre_frames + post_frames);
+    let flat: Vec<f32> = rows.into_iter().flatten().collect();
+    let array = numpy::ndarray::Array2::from_shape_vec(shape, flat)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    Ok(PyArray2::from_owned_array(py, array))
+}
 
-    let traces_ref = traces.as_array();
-    for cell_idx in 0..n_cells {
-        traces_flat.extend(traces_ref.row(cell_idx).iter().map(|&v| v as f32));
-        trace_lengths.push(n_timepoints);
-    }
-    if let Some(i) = crate::first_nonfinite(&traces_flat) {
-        return Err(pyo3::exceptions::PyValueError::new_err(format!(
-            "{NONFINITE_ERR} at flattened index {i}"
-        )));
-    }
+/// Mean and standard error of `trace` over the windows
+/// `t - pre_frames ..= t + post_frames` around each spike frame
+/// (`s_counts[t] >= min_count`); spikes whose window runs off either end are
+/// skipped. Returns (mean, sem), each `pre_frames + post_frames + 1` long.
+#[pyfunction]
+fn py_spike_triggered_average<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    s_counts: PyReadonlyArray1<f64>,
+    pre_frames: usize,
+    post_frames: usize,
+    min_count: f32,
+) -> PyResult<(Bound<'py, PyArray1<f32>>, Bound<'py, PyArray1<f32>>)> {
+    let trace = to_f32_vec(&trace)?;
+    let counts = to_f32_vec(&s_counts)?;
+    let (mean, sem) = crate::analysis::spike_triggered_average(
+        &trace,
+        &counts,
+        pre_frames,
+        post_frames,
+        min_count,
+    );
+    Ok((PyArray1::from_vec(py, mean), PyArray1::from_vec(py, sem)))
+}
 
-    let result = crate::peak_seed::seed_kernel_estimate(&traces_flat, &trace_lengths, fs);
+/// Split an `(n_cells, n_frames)` array into one `Vec` per cell.
+fn matrix_rows(matrix: &PyReadonlyArray2<f64>) -> PyResult<Vec<Vec<f32>>> {
+    let n_frames = matrix.shape()[1];
+    let flat = rows_to_f32_vec(matrix)?;
+    Ok(flat.chunks(n_frames.max(1)).map(<[f32]>::to_vec).collect())
+}
 
-    Ok((
-        PyArray1::from_vec(py, result.free_kernel),
-        result.tau_rise,
-        result.tau_decay,
-        result.tau_rise_fast,
-        result.tau_decay_fast,
-        result.beta_fast,
-        result.n_seed_spikes,
+/// Mean Pearson correlation over all cell pairs of `spike_matrix`
+/// (`(n_cells, n_frames)`), each train summed into `bin_size_frames`-frame
+/// bins. Pairs with a silent (constant) cell are skipped; NaN with fewer than
+/// two cells.
+#[pyfunction]
+fn py_pairwise_correlation_index(
+    spike_matrix: PyReadonlyArray2<f64>,
+    bin_size_frames: usize,
+) -> PyResult<f64> {
+    let cells = matrix_rows(&spike_matrix)?;
+    Ok(crate::analysis::pairwise_correlation_index(
+        &cells,
+        bin_size_frames,
     ))
 }
 
-// ---------------------------------------------------------------------------
-// InDeCa pipeline bindings
-// ---------------------------------------------------------------------------
+/// Per-pair binned correlations, ordered (0, 1), (0, 2), ..., (1, 2), ...
+/// (`n_cells * (n_cells - 1) / 2` values; NaN for pairs with a silent cell).
+#[pyfunction]
+fn py_cell_pair_correlations<'py>(
+    py: Python<'py>,
+    spike_matrix: PyReadonlyArray2<f64>,
+    bin_size_frames: usize,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    let cells = matrix_rows(&spike_matrix)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::analysis::cell_pair_correlations(&cells, bin_size_frames),
+    ))
+}
 
-/// Run the full InDeCa pipeline on a single trace.
-///
-/// Returns (s_counts, alpha, baseline, threshold, pve, iterations, converged).
+/// `(x - mean) / std` (population std; a constant trace gives zeros).
+/// Raises ValueError for an empty trace.
 #[pyfunction]
-#[pyo3(signature = (trace, tau_rise, tau_decay, fs, upsample_factor=1, max_iters=500, tol=1e-4, hp_enabled=false, lp_enabled=false, warm_counts=None, lambda_=0.0, noise_constrained=false))]
-#[allow(clippy::too_many_arguments)]
-fn py_indeca_solve_trace<'py>(
+fn py_zscore<'py>(
     py: Python<'py>,
     trace: PyReadonlyArray1<f64>,
-    tau_rise: f64,
-    tau_decay: f64,
-    fs: f64,
-    upsample_factor: usize,
-    max_iters: u32,
-    tol: f64,
-    hp_enabled: bool,
-    lp_enabled: bool,
-    warm_counts: Option<PyReadonlyArray1<f64>>,
-    lambda_: f64,
-    noise_constrained: bool,
-) -> PyResult<(
-    Bound<'py, PyArray1<f32>>, // s_counts
-    f64,                       // alpha
-    f64,                       // baseline
-    f64,                       // threshold
-    f64,                       // pve
-    u32,                       // iterations
-    bool,                      // converged
-)> {
-    let trace_f32 = to_f32_vec(&trace)?;
-    let warm = optional_to_f32_vec(warm_counts)?;
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(py, crate::analysis::zscore(&trace)?))
+}
 
-    let result = indeca::solve_trace_opts(
-        &trace_f32,
-        tau_rise,
-        tau_decay,
-        fs,
-        upsample_factor,
-        max_iters,
-        tol,
-        warm.as_deref(),
-        hp_enabled,
-        lp_enabled,
-        lambda_,
-        indeca::SolveOptions { noise_constrained },
-    );
+/// `(x - median) / (1.4826 * MAD)`. Raises ValueError for an empty trace.
+#[pyfunction]
+fn py_robust_zscore<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace = to_f32_vec(&trace)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::analysis::robust_zscore(&trace)?,
+    ))
+}
 
-    Ok((
-        PyArray1::from_vec(py, result.s_counts),
-        result.alpha,
-        result.baseline,
-        result.threshold,
-        result.pve,
-        result.iterations,
-        result.converged,
+/// Elementwise `(trace - baseline) / max(baseline, 1e-6)`. Raises ValueError
+/// for an empty trace or mismatched lengths.
+#[pyfunction]
+fn py_normalize_dff<'py>(
+    py: Python<'py>,
+    trace: PyReadonlyArray1<f64>,
+    baseline: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    let trace = to_f32_vec(&trace)?;
+    let baseline = to_f32_vec(&baseline)?;
+    Ok(PyArray1::from_vec(
+        py,
+        crate::analysis::normalize_dff(&trace, &baseline)?,
     ))
 }
 
+/// Pick the lambda from `lambdas` with the lowest `k`-fold cross-validated
+/// held-out error (contiguous folds, `k` capped so each fold spans a kernel
+/// length). Returns NaN if the trace is too short to split. The GIL is
+/// released while solving.
+#[pyfunction]
+#[pyo3(signature = (trace, tau_rise, tau_decay, fs, lambdas, k=5))]
+fn py_select_lambda_cv(
+    py: Python<'_>,
+    trace: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+    lambdas: Vec<f64>,
+    k: usize,
+) -> PyResult<f64> {
+    if lambdas.is_empty() {
+        return Err(CalabError::invalid("lambdas", "[]", "must not be empty").into());
+    }
+    let trace = to_f32_vec(&trace)?;
+    Ok(py.allow_threads(|| {
+        crate::analysis::select_lambda_cv(&trace, tau_rise, tau_decay, fs, &lambdas, k)
+    }))
+}
+
 /// Estimate a free-form kernel from multiple traces and their spike trains.
 ///
 /// Returns the estimated kernel as a numpy float32 array.
 #[pyfunction]
-#[pyo3(signature = (traces_flat, spikes_flat, trace_lengths, alphas, baselines, kernel_length, max_iters=200, tol=1e-4, warm_kernel=None, smooth_lambda=0.0))]
+#[pyo3(signature = (traces_flat, spikes_flat, trace_lengths, alphas, baselines, kernel_length, max_iters=200, tol=1e-4, warm_kernel=None, smooth_lambda=0.0, curvature_lambda=0.0))]
 fn py_indeca_estimate_kernel<'py>(
     py: Python<'py>,
     traces_flat: PyReadonlyArray1<f64>,
@@ -494,6 +3188,7 @@ fn py_indeca_estimate_kernel<'py>(
     tol: f64,
     warm_kernel: Option<PyReadonlyArray1<f64>>,
     smooth_lambda: f64,
+    curvature_lambda: f64,
 ) -> PyResult<Bound<'py, PyArray1<f32>>> {
     let traces_f32 = to_f32_vec(&traces_flat)?;
     let spikes_f32 = to_f32_vec(&spikes_flat)?;
@@ -537,11 +3232,180 @@ fn py_indeca_estimate_kernel<'py>(
         tol,
         warm.as_deref(),
         smooth_lambda,
-    );
+        curvature_lambda,
+    )?;
+
+    Ok(PyArray1::from_vec(py, result))
+}
+
+/// Flatten a `(n_cells, n_timepoints)` array row by row into f32.
+fn rows_to_f32_vec(arr: &PyReadonlyArray2<f64>) -> PyResult<Vec<f32>> {
+    let v: Vec<f32> = arr.as_array().iter().map(|&x| x as f32).collect();
+    if let Some(i) = crate::first_nonfinite(&v) {
+        let n_cols = arr.shape()[1].max(1);
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "{NONFINITE_ERR} at row {}, index {}",
+            i / n_cols,
+            i % n_cols
+        )));
+    }
+    Ok(v)
+}
+
+/// `py_indeca_estimate_kernel` for equal-length traces stacked as
+/// `(n_cells, n_timepoints)` arrays; `trace_lengths` follows from the shape.
+///
+/// Returns the estimated kernel as a numpy float32 array.
+#[pyfunction]
+#[pyo3(signature = (traces, spike_trains, alphas, baselines, kernel_length, max_iters=200, tol=1e-4, warm_start=None, smooth_lambda=0.0, curvature_lambda=0.0))]
+fn py_estimate_free_kernel<'py>(
+    py: Python<'py>,
+    traces: PyReadonlyArray2<f64>,
+    spike_trains: PyReadonlyArray2<f64>,
+    alphas: PyReadonlyArray1<f64>,
+    baselines: PyReadonlyArray1<f64>,
+    kernel_length: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_start: Option<PyReadonlyArray1<f64>>,
+    smooth_lambda: f64,
+    curvature_lambda: f64,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    if traces.shape() != spike_trains.shape() {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "traces and spike_trains must have the same shape, got {:?} and {:?}",
+            traces.shape(),
+            spike_trains.shape()
+        )));
+    }
+    let (n_cells, n_timepoints) = (traces.shape()[0], traces.shape()[1]);
+    let traces_f32 = rows_to_f32_vec(&traces)?;
+    let spikes_f32 = rows_to_f32_vec(&spike_trains)?;
+    let alphas_slice = alphas
+        .as_slice()
+        .map_err(|_| pyo3::exceptions::PyValueError::new_err(CONTIGUOUS_ERR))?;
+    let baselines_slice = baselines
+        .as_slice()
+        .map_err(|_| pyo3::exceptions::PyValueError::new_err(CONTIGUOUS_ERR))?;
+    let warm = optional_to_f32_vec(warm_start)?;
+
+    let result = kernel_est::estimate_free_kernel(
+        &traces_f32,
+        &spikes_f32,
+        alphas_slice,
+        baselines_slice,
+        &vec![n_timepoints; n_cells],
+        kernel_length,
+        max_iters,
+        tol,
+        warm.as_deref(),
+        smooth_lambda,
+        curvature_lambda,
+    )?;
+
+    Ok(PyArray1::from_vec(py, result))
+}
+
+/// `py_estimate_free_kernel` over several recording sessions, one shared
+/// kernel. `sessions` is a list of dicts with keys `traces` and
+/// `spike_trains` (`(n_cells, n_timepoints)`, same shape within a session),
+/// `alphas` and `baselines` (one per cell); sessions may differ in length.
+/// Per-session gradients are computed in parallel with the GIL released.
+///
+/// Returns the estimated kernel as a numpy float32 array.
+#[pyfunction]
+#[pyo3(signature = (sessions, kernel_length, max_iters=200, tol=1e-4, warm_start=None, smooth_lambda=0.0, curvature_lambda=0.0))]
+fn py_estimate_free_kernel_parallel<'py>(
+    py: Python<'py>,
+    sessions: Vec<Bound<'py, pyo3::types::PyDict>>,
+    kernel_length: usize,
+    max_iters: u32,
+    tol: f64,
+    warm_start: Option<PyReadonlyArray1<f64>>,
+    smooth_lambda: f64,
+    curvature_lambda: f64,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    struct OwnedSession {
+        traces: Vec<f32>,
+        spikes: Vec<f32>,
+        alphas: Vec<f64>,
+        baselines: Vec<f64>,
+        lengths: Vec<usize>,
+    }
+
+    let mut owned = Vec::with_capacity(sessions.len());
+    for (i, session) in sessions.iter().enumerate() {
+        let get = |key: &str| -> PyResult<Bound<'py, PyAny>> {
+            session.get_item(key)?.ok_or_else(|| {
+                pyo3::exceptions::PyKeyError::new_err(format!("sessions[{i}] is missing '{key}'"))
+            })
+        };
+        let traces: PyReadonlyArray2<f64> = get("traces")?.extract()?;
+        let spike_trains: PyReadonlyArray2<f64> = get("spike_trains")?.extract()?;
+        if traces.shape() != spike_trains.shape() {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "sessions[{i}]: traces and spike_trains must have the same shape, got {:?} and {:?}",
+                traces.shape(),
+                spike_trains.shape()
+            )));
+        }
+        let alphas: Vec<f64> = get("alphas")?.extract()?;
+        let baselines: Vec<f64> = get("baselines")?.extract()?;
+        let (n_cells, n_timepoints) = (traces.shape()[0], traces.shape()[1]);
+        owned.push(OwnedSession {
+            traces: rows_to_f32_vec(&traces)?,
+            spikes: rows_to_f32_vec(&spike_trains)?,
+            alphas,
+            baselines,
+            lengths: vec![n_timepoints; n_cells],
+        });
+    }
+    let warm = optional_to_f32_vec(warm_start)?;
+
+    let result = py.allow_threads(|| {
+        let inputs: Vec<kernel_est::KernelEstInput> = owned
+            .iter()
+            .map(|s| kernel_est::KernelEstInput {
+                traces: &s.traces,
+                spike_trains: &s.spikes,
+                alphas: &s.alphas,
+                baselines: &s.baselines,
+                trace_lengths: &s.lengths,
+            })
+            .collect();
+        kernel_est::estimate_free_kernel_parallel(
+            &inputs,
+            kernel_length,
+            max_iters,
+            tol,
+            warm.as_deref(),
+            smooth_lambda,
+            curvature_lambda,
+        )
+    })?;
 
     Ok(PyArray1::from_vec(py, result))
 }
 
+/// Convolve each row of `spike_trains` (`(n_cells, n_timepoints)`) with
+/// `kernel`, truncated to the row length: `out[t] = sum_k kernel[k] * s[t - k]`.
+/// The forward model of `py_estimate_free_kernel`, for checking a fit.
+#[pyfunction]
+fn py_convolve_spikes_kernel<'py>(
+    py: Python<'py>,
+    spike_trains: PyReadonlyArray2<f64>,
+    kernel: PyReadonlyArray1<f64>,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let (n_cells, n_timepoints) = (spike_trains.shape()[0], spike_trains.shape()[1]);
+    let spikes = rows_to_f32_vec(&spike_trains)?;
+    let kernel = to_f32_vec(&kernel)?;
+    let mut out = vec![0.0_f32; spikes.len()];
+    kernel_est::convolve_spikes_kernel(&spikes, &vec![n_timepoints; n_cells], &kernel, &mut out);
+    let array = numpy::ndarray::Array2::from_shape_vec((n_cells, n_timepoints), out)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok(PyArray2::from_owned_array(py, array))
+}
+
 /// Fit a bi-exponential model to a free-form kernel.
 ///
 /// Returns (tau_rise, tau_decay, beta, residual, tau_rise_fast, tau_decay_fast, beta_fast).
@@ -593,6 +3457,149 @@ fn py_indeca_fit_biexponential(
     ))
 }
 
+/// Slow-component result of `py_fit_biexponential` /
+/// `py_fit_biexponential_bounded`. `fit_mode` is the `FitMode` variant name.
+#[pyclass]
+pub struct PyBiexpResult {
+    #[pyo3(get)]
+    tau_rise: f64,
+    #[pyo3(get)]
+    tau_decay: f64,
+    #[pyo3(get)]
+    beta: f64,
+    #[pyo3(get)]
+    residual: f64,
+    #[pyo3(get)]
+    fit_mode: String,
+}
+
+#[pymethods]
+impl PyBiexpResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "PyBiexpResult(tau_rise={:.4}, tau_decay={:.4}, beta={:.4}, residual={:.4e}, \
+             fit_mode={})",
+            self.tau_rise, self.tau_decay, self.beta, self.residual, self.fit_mode
+        )
+    }
+}
+
+impl From<biexp_fit::BiexpResult> for PyBiexpResult {
+    fn from(r: biexp_fit::BiexpResult) -> Self {
+        PyBiexpResult {
+            tau_rise: r.tau_rise,
+            tau_decay: r.tau_decay,
+            beta: r.beta,
+            residual: r.residual,
+            fit_mode: r.fit_mode.as_str().to_string(),
+        }
+    }
+}
+
+/// Fit a bi-exponential to a free-form kernel, returning the slow component.
+///
+/// Cold-start counterpart of `py_indeca_fit_biexponential` for interactive
+/// kernel fitting; the fast (artifact) component is fit but not reported.
+#[pyfunction]
+#[pyo3(signature = (kernel, fs, refine=true))]
+fn py_fit_biexponential(
+    kernel: PyReadonlyArray1<f64>,
+    fs: f64,
+    refine: bool,
+) -> PyResult<PyBiexpResult> {
+    let h = to_f32_vec(&kernel)?;
+    Ok(biexp_fit::fit_biexponential(&h, fs, refine, 0, None).into())
+}
+
+/// Fit a slow-only bi-exponential with tau_rise and tau_decay (seconds)
+/// restricted to the given bounds.
+#[pyfunction]
+#[pyo3(signature = (kernel, fs, tau_r_min, tau_r_max, tau_d_min, tau_d_max, refine=true))]
+fn py_fit_biexponential_bounded(
+    kernel: PyReadonlyArray1<f64>,
+    fs: f64,
+    tau_r_min: f64,
+    tau_r_max: f64,
+    tau_d_min: f64,
+    tau_d_max: f64,
+    refine: bool,
+) -> PyResult<PyBiexpResult> {
+    let h = to_f32_vec(&kernel)?;
+    let result = biexp_fit::fit_biexponential_bounded(
+        &h, fs, tau_r_min, tau_r_max, tau_d_min, tau_d_max, refine,
+    )?;
+    Ok(result.into())
+}
+
+/// Sample `beta * (exp(-t/tau_decay) - exp(-t/tau_rise))` at `n_samples`
+/// points spaced `1/fs` apart, for plotting a fit against its kernel.
+#[pyfunction]
+fn py_eval_biexp<'py>(
+    py: Python<'py>,
+    tau_rise: f64,
+    tau_decay: f64,
+    beta: f64,
+    fs: f64,
+    n_samples: usize,
+) -> PyResult<Bound<'py, PyArray1<f32>>> {
+    if !(fs.is_finite() && fs > 0.0) {
+        return Err(CalabError::invalid("fs", fs, "must be positive and finite").into());
+    }
+    let curve = biexp_fit::eval_biexp(tau_rise, tau_decay, beta, fs, n_samples);
+    Ok(PyArray1::from_vec(py, curve))
+}
+
+/// Result of `py_kernel_quality`.
+#[pyclass]
+pub struct PyKernelQuality {
+    #[pyo3(get)]
+    pve_biexp: f64,
+    #[pyo3(get)]
+    peak_sharpness: f64,
+    #[pyo3(get)]
+    negativity_fraction: f64,
+    #[pyo3(get)]
+    peak_time_s: f64,
+    #[pyo3(get)]
+    l2_norm: f64,
+}
+
+#[pymethods]
+impl PyKernelQuality {
+    fn __repr__(&self) -> String {
+        format!(
+            "PyKernelQuality(pve_biexp={:.4}, peak_sharpness={:.4}, negativity_fraction={:.4}, \
+             peak_time_s={:.4}, l2_norm={:.4})",
+            self.pve_biexp,
+            self.peak_sharpness,
+            self.negativity_fraction,
+            self.peak_time_s,
+            self.l2_norm
+        )
+    }
+}
+
+/// Shape diagnostics of an estimated kernel against the bi-exponential with
+/// `tau_rise` / `tau_decay` (seconds): variance explained, peak sharpness,
+/// fraction of negative samples, peak time and L2 norm.
+#[pyfunction]
+fn py_kernel_quality(
+    kernel: PyReadonlyArray1<f64>,
+    tau_rise: f64,
+    tau_decay: f64,
+    fs: f64,
+) -> PyResult<PyKernelQuality> {
+    let h = to_f32_vec(&kernel)?;
+    let q = kernel_est::kernel_quality(&h, tau_rise, tau_decay, fs);
+    Ok(PyKernelQuality {
+        pve_biexp: q.pve_biexp,
+        peak_sharpness: q.peak_sharpness,
+        negativity_fraction: q.negativity_fraction,
+        peak_time_s: q.peak_time_s,
+        l2_norm: q.l2_norm,
+    })
+}
+
 /// Compute the upsample factor for a given sampling rate and target rate.
 #[pyfunction]
 fn py_indeca_compute_upsample_factor(fs: f64, target_fs: f64) -> usize {
@@ -659,18 +3666,90 @@ fn py_simulate_traces<'py>(
 /// The function name must match the leaf of module-name in pyproject.toml: "calab._solver" → "_solver".
 #[pymodule]
 fn _solver(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Err only means a runtime is already registered, i.e. this same static
+    // from an earlier initialization of the module.
+    pyo3_async_runtimes::tokio::init_with_runtime(&ASYNC_RUNTIME).ok();
     m.add_class::<PySolver>()?;
+    m.add_class::<PySolverBuilder>()?;
+    m.add_class::<PyCancelToken>()?;
+    m.add_class::<PyInDecaResult>()?;
+    m.add_class::<PyInDeCa>()?;
+    m.add_class::<PyThresholdResult>()?;
+    m.add_class::<PyBiexpResult>()?;
+    m.add_class::<PyKernelQuality>()?;
     m.add_function(wrap_pyfunction!(py_build_kernel, m)?)?;
+    m.add_function(wrap_pyfunction!(py_suggest_kernel_length, m)?)?;
     m.add_function(wrap_pyfunction!(py_compute_lipschitz, m)?)?;
+    m.add_function(wrap_pyfunction!(py_compute_lipschitz_banded, m)?)?;
+    m.add_function(wrap_pyfunction!(py_check_adjoint, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ar_model_from_taus, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fit_ar2_from_acf, m)?)?;
+    m.add_function(wrap_pyfunction!(py_check_ar2_stability, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ar2_frequency_response, m)?)?;
     m.add_function(wrap_pyfunction!(deconvolve_single, m)?)?;
+    m.add_function(wrap_pyfunction!(deconvolve_single_tuple, m)?)?;
     m.add_function(wrap_pyfunction!(deconvolve_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(deconvolve_batch_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(async_deconvolve_single, m)?)?;
+    m.add_function(wrap_pyfunction!(async_deconvolve_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(async_solve_trace, m)?)?;
+    m.add_function(wrap_pyfunction!(py_solve_path, m)?)?;
+    m.add_function(wrap_pyfunction!(py_lambda_sensitivity_arrays, m)?)?;
+    m.add_function(wrap_pyfunction!(py_savitzky_golay, m)?)?;
+    m.add_function(wrap_pyfunction!(py_exponential_smooth, m)?)?;
+    m.add_function(wrap_pyfunction!(py_median_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_variance, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_std, m)?)?;
+    m.add_function(wrap_pyfunction!(py_estimate_noise_floor, m)?)?;
+    m.add_function(wrap_pyfunction!(py_anscombe_transform, m)?)?;
+    m.add_function(wrap_pyfunction!(py_anscombe_inverse_transform, m)?)?;
+    m.add_function(wrap_pyfunction!(py_compute_dff_rolling, m)?)?;
+    m.add_function(wrap_pyfunction!(py_compute_dff_global, m)?)?;
+    m.add_function(wrap_pyfunction!(py_preset_params, m)?)?;
+    m.add_function(wrap_pyfunction!(py_list_presets, m)?)?;
+    m.add_function(wrap_pyfunction!(py_validate_params, m)?)?;
+    m.add_function(wrap_pyfunction!(py_read_npy, m)?)?;
+    m.add_function(wrap_pyfunction!(py_read_csv_traces, m)?)?;
+    m.add_function(wrap_pyfunction!(py_read_suite2p, m)?)?;
+    m.add_function(wrap_pyfunction!(py_neuropil_correct, m)?)?;
+    m.add_function(wrap_pyfunction!(py_estimate_neuropil_coeff, m)?)?;
+    m.add_function(wrap_pyfunction!(py_save_checkpoint, m)?)?;
+    m.add_function(wrap_pyfunction!(py_load_checkpoint, m)?)?;
     m.add_function(wrap_pyfunction!(py_seed_trace, m)?)?;
     m.add_function(wrap_pyfunction!(seed_kernel_estimate, m)?)?;
     // InDeCa pipeline
     m.add_function(wrap_pyfunction!(py_indeca_solve_trace, m)?)?;
+    m.add_function(wrap_pyfunction!(py_set_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(py_bootstrap_spike_ci, m)?)?;
+    m.add_function(wrap_pyfunction!(py_roc_threshold_curve, m)?)?;
+    m.add_function(wrap_pyfunction!(py_threshold_search, m)?)?;
+    m.add_function(wrap_pyfunction!(py_threshold_search_custom_grid, m)?)?;
+    m.add_function(wrap_pyfunction!(py_joint_threshold_search, m)?)?;
     m.add_function(wrap_pyfunction!(py_indeca_estimate_kernel, m)?)?;
+    m.add_function(wrap_pyfunction!(py_estimate_free_kernel, m)?)?;
+    m.add_function(wrap_pyfunction!(py_estimate_free_kernel_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(py_convolve_spikes_kernel, m)?)?;
     m.add_function(wrap_pyfunction!(py_indeca_fit_biexponential, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fit_biexponential, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fit_biexponential_bounded, m)?)?;
+    m.add_function(wrap_pyfunction!(py_eval_biexp, m)?)?;
+    m.add_function(wrap_pyfunction!(py_kernel_quality, m)?)?;
     m.add_function(wrap_pyfunction!(py_indeca_compute_upsample_factor, m)?)?;
+    // Spike-train analysis
+    m.add_function(wrap_pyfunction!(py_spike_isis, m)?)?;
+    m.add_function(wrap_pyfunction!(py_isi_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_isi_cv, m)?)?;
+    m.add_function(wrap_pyfunction!(py_isi_histogram, m)?)?;
+    m.add_function(wrap_pyfunction!(py_detect_bursts, m)?)?;
+    m.add_function(wrap_pyfunction!(py_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_robust_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_normalize_dff, m)?)?;
+    m.add_function(wrap_pyfunction!(py_event_aligned_average, m)?)?;
+    m.add_function(wrap_pyfunction!(py_event_aligned_raster, m)?)?;
+    m.add_function(wrap_pyfunction!(py_spike_triggered_average, m)?)?;
+    m.add_function(wrap_pyfunction!(py_pairwise_correlation_index, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cell_pair_correlations, m)?)?;
+    m.add_function(wrap_pyfunction!(py_select_lambda_cv, m)?)?;
     // Simulation
     m.add_function(wrap_pyfunction!(py_simulate_traces, m)?)?;
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
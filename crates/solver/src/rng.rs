@@ -0,0 +1,108 @@
+//! Process-wide seeded random stream for the stochastic operations.
+//!
+//! `CaLabRng` is the 64-bit LCG (Knuth's MMIX constants) already used for
+//! the crate's test noise, with its state in a thread-local `Cell<u64>`.
+//! `set_global_seed` (Python `set_seed`, WASM `indeca_set_seed`) resets that
+//! state; stochastic functions without an explicit seed draw theirs from it
+//! with `CaLabRng::next_u64`, so a run is reproducible when the seed is set
+//! before the first stochastic call and the calls happen in the same order.
+//!
+//! The state is per thread: work spawned on other threads (rayon batches)
+//! starts from the default seed there. `simulate` keeps its own xorshift32
+//! generator seeded from `SimulationConfig::seed`, which matches the web
+//! simulator bit for bit.
+
+use std::cell::Cell;
+
+/// State before any `set_global_seed` call.
+pub const DEFAULT_SEED: u64 = 0x9E3779B97F4A7C15;
+
+thread_local! {
+    static STATE: Cell<u64> = const { Cell::new(DEFAULT_SEED) };
+}
+
+/// Reset the calling thread's global random stream to `seed`.
+pub fn set_global_seed(seed: u64) {
+    STATE.with(|s| s.set(seed));
+}
+
+/// One step of the MMIX LCG recurrence.
+fn lcg_step(state: u64) -> u64 {
+    state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407)
+}
+
+/// Deterministic white-ish test noise in [-amp, amp) from a local LCG seeded
+/// with `seed` (the global stream is left untouched). Zero-mean in
+/// expectation; variance ≈ amp²/3.
+#[cfg(test)]
+pub(crate) fn lcg_noise(n: usize, amp: f32, seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = lcg_step(state);
+            let u = ((state >> 32) as f64) / ((1u64 << 31) as f64) - 1.0;
+            (u as f32) * amp
+        })
+        .collect()
+}
+
+/// Handle to the thread-local global random stream.
+pub struct CaLabRng;
+
+impl CaLabRng {
+    /// Advance the global stream and return its new 64-bit state.
+    pub fn next_u64() -> u64 {
+        STATE.with(|s| {
+            let next = lcg_step(s.get());
+            s.set(next);
+            next
+        })
+    }
+
+    /// Uniform draw in (0, 1] from the top 53 bits of `next_u64`.
+    pub fn next_f64() -> f64 {
+        ((Self::next_u64() >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    /// Standard normal draw (Box–Muller, one value per call).
+    pub fn next_gaussian() -> f64 {
+        let u1 = Self::next_f64();
+        let u2 = Self::next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_makes_stream_reproducible() {
+        set_global_seed(42);
+        let first: Vec<u64> = (0..5).map(|_| CaLabRng::next_u64()).collect();
+        set_global_seed(42);
+        let second: Vec<u64> = (0..5).map(|_| CaLabRng::next_u64()).collect();
+        assert_eq!(first, second);
+        set_global_seed(43);
+        assert_ne!(CaLabRng::next_u64(), first[0]);
+
+        // Same recurrence as `indeca::lcg_gaussian`.
+        set_global_seed(7);
+        let mut state = 7_u64;
+        for _ in 0..10 {
+            assert_eq!(
+                CaLabRng::next_gaussian(),
+                crate::indeca::lcg_gaussian(&mut state)
+            );
+        }
+
+        set_global_seed(1);
+        let n = 20_000;
+        let draws: Vec<f64> = (0..n).map(|_| CaLabRng::next_gaussian()).collect();
+        let mean = draws.iter().sum::<f64>() / n as f64;
+        let var = draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.03 && (var - 1.0).abs() < 0.05);
+    }
+}
@@ -0,0 +1,391 @@
+//! Stochastic block proximal gradient for very long traces.
+//!
+//! The data term splits over contiguous time blocks B of `block_size`
+//! samples, f(x) = sum_B f_B(x) with f_B(x) = (1/2)||(K*x + b - y)_B||^2.
+//! Each step samples one block and evaluates the gradient of f_B, which is
+//! supported on the window W = [start - k + 1, end) (k = kernel length), so
+//! both convolutions run on a local FFT of length ~block_size + 2k instead of
+//! the whole trace.
+//!
+//! Only coordinates in W move. A coordinate u lies in the windows of `c_u`
+//! blocks, and its share of the L1 penalty and of the snapshot gradient is
+//! divided by `c_u`, so one pass over all blocks applies each exactly once.
+//!
+//! - `VRMode::Sgd`: plain block gradient with a per-block baseline estimate.
+//!   Never touches the full trace, but with a fixed step it settles in a
+//!   neighbourhood of the optimum rather than on it.
+//! - `VRMode::Svrg`: every epoch (one step per block) takes a snapshot x~ and
+//!   its full gradient mu~, and steps with grad f_B(x) - grad f_B(x~) + mu~/c.
+//!   The optimum is a fixed point of every step, so it converges exactly.
+//!
+//! Buffer roles while in stochastic mode:
+//! - `solution`: x, updated in place block by block
+//! - `reconvolution`, `residual_buf`, `gradient`: scratch for the Svrg snapshot
+
+use crate::fft::FftConvolver;
+use crate::rng::CaLabRng;
+use crate::{Constraint, ConvMode, Solver};
+
+/// Variance reduction used by `SolverMode::StochasticFista`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VRMode {
+    /// Plain stochastic block gradient; no full-trace passes.
+    Sgd,
+    /// Stochastic variance-reduced gradient with a full-gradient snapshot
+    /// refreshed every epoch.
+    Svrg,
+}
+
+/// Local convolver, epoch snapshot and sampling state.
+pub(crate) struct StochasticState {
+    fft: FftConvolver,
+    /// x~, the solution at the start of the epoch.
+    snapshot: Vec<f32>,
+    /// mu~ = grad f(x~) (Svrg only).
+    full_grad: Vec<f32>,
+    window: Vec<f32>,
+    window_out: Vec<f32>,
+    /// Steps taken in the current epoch; 0 forces a new snapshot.
+    epoch_step: usize,
+    /// False until the first snapshot of the current solve.
+    has_snapshot: bool,
+}
+
+impl StochasticState {
+    pub(crate) fn new() -> Self {
+        StochasticState {
+            fft: FftConvolver::new(),
+            snapshot: Vec::new(),
+            full_grad: Vec::new(),
+            window: Vec::new(),
+            window_out: Vec::new(),
+            epoch_step: 0,
+            has_snapshot: false,
+        }
+    }
+
+    /// Restart the epoch and force a fresh snapshot on the next step.
+    pub(crate) fn reset(&mut self) {
+        self.epoch_step = 0;
+        self.has_snapshot = false;
+    }
+
+    /// Padded FFT length of the local convolver (0 before the first step).
+    pub(crate) fn fft_len(&self) -> usize {
+        self.fft.fft_len()
+    }
+}
+
+/// Block index drawn from the global random stream (see `rng`), so the
+/// stream only advances while a stochastic solve is actually stepping.
+fn next_block(n_blocks: usize) -> usize {
+    ((CaLabRng::next_u64() >> 33) as usize) % n_blocks
+}
+
+impl Solver {
+    /// Run n_steps of stochastic block updates. Returns true if converged.
+    ///
+    /// Convergence is checked once per epoch: the relative change of x over
+    /// the epoch, ||x - x~|| < tol * ||x~||.
+    pub(crate) fn step_batch_stochastic(
+        &mut self,
+        n_steps: u32,
+        block_size: usize,
+        variance_reduction: VRMode,
+    ) -> bool {
+        let n = self.active_len;
+        if n == 0 {
+            self.converged = true;
+            return true;
+        }
+
+        let m = block_size.clamp(1, n);
+        let k = self.kernel.len();
+        let n_blocks = n.div_ceil(m);
+        let step = 1.0 / self.smooth_lipschitz();
+        let lambda2_f32 = self.lambda2 as f32;
+        let step_f32 = step as f32;
+        let thresh_f32 = (step * self.effective_lambda()) as f32;
+        let (lo, hi) = match self.constraint {
+            Constraint::NonNegative => (0.0_f32, f32::INFINITY),
+            Constraint::Box01 => (0.0_f32, 1.0_f32),
+        };
+        let tol = self.tolerance;
+        let tol_sq = tol * tol;
+
+        for _ in 0..n_steps {
+            if self.converged {
+                return true;
+            }
+
+            if self.sfista.epoch_step == 0 && self.start_epoch(m, variance_reduction, tol_sq) {
+                self.converged = true;
+                return true;
+            }
+            self.sfista.epoch_step = (self.sfista.epoch_step + 1) % n_blocks;
+
+            // 1. Sample a block B = [start, end) and its gradient window [w0, end).
+            let start = next_block(n_blocks) * m;
+            let end = (start + m).min(n);
+            let w0 = start.saturating_sub(k - 1);
+            let len = end - w0;
+            let offset = start - w0;
+
+            // 2. Forward convolution on the window. Svrg convolves x - x~ (the
+            //    baseline cancels in the gradient difference); Sgd convolves x.
+            let st = &mut self.sfista;
+            match variance_reduction {
+                VRMode::Svrg => {
+                    for i in 0..len {
+                        st.window[i] = self.solution[w0 + i] - st.snapshot[w0 + i];
+                    }
+                }
+                VRMode::Sgd => st.window[..len].copy_from_slice(&self.solution[w0..end]),
+            }
+            st.fft
+                .convolve_forward(&st.window[..len], len, &mut st.window_out[..len]);
+
+            // 3. Residual restricted to B (zero on the lead-in [w0, start)).
+            st.window[..offset].fill(0.0);
+            match variance_reduction {
+                VRMode::Svrg => {
+                    st.window[offset..len].copy_from_slice(&st.window_out[offset..len]);
+                }
+                VRMode::Sgd => {
+                    if !self.filtered {
+                        let raw = crate::compute_raw_baseline(
+                            &self.trace[start..end],
+                            &st.window_out[offset..len],
+                            end - start,
+                        );
+                        self.update_baseline_ema(raw);
+                    }
+                    let st = &mut self.sfista;
+                    let baseline_f32 = self.baseline as f32;
+                    for i in offset..len {
+                        st.window[i] = st.window_out[i] + baseline_f32 - self.trace[w0 + i];
+                    }
+                }
+            }
+            let st = &mut self.sfista;
+
+            // 4. Adjoint: block gradient on W.
+            st.fft
+                .convolve_adjoint(&st.window[..len], len, &mut st.window_out[..len]);
+
+            // 5. Proximal step on W, with penalties and snapshot gradient split
+            //    across the c_u blocks whose windows contain u.
+            for i in 0..len {
+                let u = w0 + i;
+                let c = ((u + k - 1).min(n - 1) / m - u / m + 1) as f32;
+                let mut g = st.window_out[i] + lambda2_f32 * self.solution[u] / c;
+                if variance_reduction == VRMode::Svrg {
+                    g += st.full_grad[u] / c;
+                }
+                let z = self.solution[u] - step_f32 * g;
+                self.solution[u] = (z - thresh_f32 / c).clamp(lo, hi);
+            }
+
+            self.iteration += 1;
+            self.reconvolution_stale = true;
+        }
+
+        self.converged
+    }
+
+    /// Begin an epoch: size the local convolver, test convergence against the
+    /// previous snapshot, then record the new snapshot (and, for Svrg, its
+    /// full gradient). Returns true if the epoch change was below tolerance.
+    fn start_epoch(&mut self, m: usize, variance_reduction: VRMode, tol_sq: f64) -> bool {
+        let n = self.active_len;
+        let k = self.kernel.len();
+
+        let st = &mut self.sfista;
+        let window_len = m + k - 1;
+        if st.window.len() < window_len {
+            st.window.resize(window_len, 0.0);
+            st.window_out.resize(window_len, 0.0);
+        }
+        // Re-prepare the kernel every epoch so a set_params mid-solve is picked up.
+        let prev_len = st.fft.fft_len();
+        st.fft.ensure_buffers(window_len, &self.kernel);
+        if st.fft.fft_len() == prev_len {
+            st.fft.prepare_kernel(&self.kernel);
+        }
+
+        if st.snapshot.len() < n {
+            st.snapshot.resize(n, 0.0);
+        }
+        if st.has_snapshot {
+            let mut diff_sq = 0.0_f64;
+            let mut xk_sq = 0.0_f64;
+            for i in 0..n {
+                let old = st.snapshot[i] as f64;
+                let d = self.solution[i] as f64 - old;
+                diff_sq += d * d;
+                xk_sq += old * old;
+            }
+            if diff_sq < tol_sq * (xk_sq + 1e-20) {
+                return true;
+            }
+        }
+        st.snapshot[..n].copy_from_slice(&self.solution[..n]);
+        st.has_snapshot = true;
+
+        if variance_reduction == VRMode::Svrg {
+            self.snapshot_gradient();
+        }
+        false
+    }
+
+    /// Full gradient at the snapshot, mu~ = K^T (K*x~ + b - y), with the
+    /// baseline estimated from the full reconvolution.
+    fn snapshot_gradient(&mut self) {
+        let n = self.active_len;
+        self.ensure_fft_buffers(n);
+        match self.conv_mode {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                self.fft
+                    .convolve_forward(&self.solution[..n], n, &mut self.reconvolution[..n])
+            }
+            ConvMode::BandedAR2 => self
+                .banded
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+            ConvMode::BandedAR1 => self
+                .banded_ar1
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+            ConvMode::BandedARp => self
+                .arp
+                .convolve_forward(&self.solution[..n], &mut self.reconvolution[..n]),
+        }
+        if !self.filtered {
+            let raw = crate::compute_raw_baseline(&self.trace[..n], &self.reconvolution[..n], n);
+            self.update_baseline_ema(raw);
+        }
+        let baseline_f32 = self.baseline as f32;
+        for i in 0..n {
+            self.residual_buf[i] = self.reconvolution[i] + baseline_f32 - self.trace[i];
+        }
+        match self.conv_mode {
+            ConvMode::Fft | ConvMode::FftOverlapAdd => {
+                self.fft
+                    .convolve_adjoint(&self.residual_buf[..n], n, &mut self.gradient[..n])
+            }
+            ConvMode::BandedAR2 => self
+                .banded
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            ConvMode::BandedAR1 => self
+                .banded_ar1
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+            ConvMode::BandedARp => self
+                .arp
+                .convolve_adjoint(&self.residual_buf[..n], &mut self.gradient[..n]),
+        }
+        let st = &mut self.sfista;
+        if st.full_grad.len() < n {
+            st.full_grad.resize(n, 0.0);
+        }
+        st.full_grad[..n].copy_from_slice(&self.gradient[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::build_kernel;
+    use crate::rng::{set_global_seed, CaLabRng};
+    use crate::test_util::{build_trace, relative_error, solve_to_convergence};
+    use crate::{Solver, SolverMode, VRMode};
+
+    #[test]
+    fn stochastic_modes_match_fista() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let spikes: Vec<(usize, f32)> = (0..6)
+            .map(|j| (60 + j * 160, 0.5 + 0.2 * (j % 4) as f32))
+            .collect();
+        let trace = build_trace(&kernel, 1000, &spikes);
+
+        let fista = solve_to_convergence(SolverMode::Fista, None, None, &trace, 2000);
+        // An epoch without momentum moves x only a fraction of its remaining
+        // distance, so match FISTA with a tighter test.
+        let svrg = solve_to_convergence(
+            SolverMode::StochasticFista {
+                block_size: 256,
+                variance_reduction: VRMode::Svrg,
+            },
+            None,
+            Some(1e-5),
+            &trace,
+            20000,
+        );
+        assert!(fista.converged() && svrg.converged());
+        let err = relative_error(&svrg.get_solution(), &fista.get_solution());
+        assert!(err < 0.02, "SVRG vs FISTA relative error {err}");
+
+        let sgd = solve_to_convergence(
+            SolverMode::StochasticFista {
+                block_size: 256,
+                variance_reduction: VRMode::Sgd,
+            },
+            None,
+            Some(1e-5),
+            &trace,
+            1000,
+        );
+        let err = relative_error(&sgd.get_solution(), &fista.get_solution());
+        // Sgd has no exact fixed point; it only gets close.
+        assert!(err < 0.05, "SGD vs FISTA relative error {err}");
+    }
+
+    #[test]
+    fn step_cost_is_independent_of_trace_length() {
+        // Per-step work is one forward and one adjoint FFT of the local
+        // convolver, whose length depends on block_size and the kernel only.
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let mode = SolverMode::StochasticFista {
+            block_size: 256,
+            variance_reduction: VRMode::Sgd,
+        };
+        let mut lens = Vec::new();
+        for n in [4_000, 200_000] {
+            let trace = build_trace(&kernel, n, &[(100, 1.0), (n / 2, 1.0)]);
+            let mut solver = Solver::new();
+            solver.set_params(0.02, 0.4, 0.01, 30.0);
+            solver.set_solver_mode(mode);
+            solver.set_trace(&trace);
+            solver.step_batch(5);
+            assert_eq!(solver.iteration_count(), 5);
+            lens.push(solver.sfista.fft_len());
+        }
+        let expected = (256 + 2 * (kernel.len() - 1)).next_power_of_two();
+        assert_eq!(lens, [expected, expected]);
+    }
+
+    #[test]
+    fn only_stochastic_steps_draw_from_the_global_stream() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 300, &[(50, 1.0), (200, 0.5)]);
+        set_global_seed(5);
+        let first = CaLabRng::next_u64();
+
+        set_global_seed(5);
+        let mut solver = Solver::new();
+        solver.set_params(0.02, 0.4, 0.01, 30.0);
+        solver.set_trace(&trace);
+        solver.step_batch(10);
+        solver.set_solver_mode(SolverMode::Admm { rho: 0.0 });
+        solver.step_batch(10);
+        solver.reset();
+        assert_eq!(CaLabRng::next_u64(), first);
+
+        set_global_seed(5);
+        solver.set_solver_mode(SolverMode::StochasticFista {
+            block_size: 64,
+            variance_reduction: VRMode::Sgd,
+        });
+        solver.set_trace(&trace);
+        assert_eq!(CaLabRng::next_u64(), first);
+        set_global_seed(5);
+        solver.step_batch(1);
+        assert_ne!(CaLabRng::next_u64(), first);
+    }
+}
@@ -0,0 +1,310 @@
+//! Online (sample-by-sample) deconvolution over a sliding window.
+//!
+//! `StreamingSolver` keeps the most recent `window_len` samples. Each new
+//! sample shifts the window by one, warm-starts the inner `Solver` from the
+//! previous window's solution shifted by the same amount, and runs a single
+//! FISTA step. A sample therefore receives `window_len` refinement steps
+//! before it leaves the window, at which point its spike estimate is emitted.
+//!
+//! Optionally each sample first has a causal rolling-percentile baseline
+//! subtracted (`set_rolling_baseline`, via `StreamingPercentile`), the online
+//! counterpart of the batch `subtract_rolling_baseline`.
+
+use std::collections::VecDeque;
+
+use crate::baseline::StreamingPercentile;
+use crate::{CalabError, ConvMode, Solver};
+
+/// Default window length as a multiple of the kernel length.
+const DEFAULT_WINDOW_KERNELS: usize = 10;
+
+/// Iteration cap for `flush`, which solves the final partial window outright.
+const FLUSH_MAX_ITERS: u32 = 2000;
+
+/// Sliding-window online deconvolution with one FISTA step per sample.
+pub struct StreamingSolver {
+    inner: Solver,
+    buffer: VecDeque<f32>,
+    window_len: usize,
+    // Scratch: contiguous copy of `buffer` and the shifted warm-start solution.
+    window: Vec<f32>,
+    warm: Vec<f32>,
+    /// Rolling baseline subtracted on entry (`set_rolling_baseline`).
+    baseline: Option<StreamingPercentile>,
+}
+
+impl StreamingSolver {
+    /// Create a streaming solver with the default window of 10 kernel lengths.
+    pub fn new(tau_rise: f64, tau_decay: f64, lambda: f64, fs: f64) -> Self {
+        let inner = Self::build_inner(tau_rise, tau_decay, lambda, fs);
+        let window_len = DEFAULT_WINDOW_KERNELS * inner.kernel.len();
+        Self::from_parts(inner, window_len)
+    }
+
+    /// Create a streaming solver with an explicit window length.
+    ///
+    /// Errors when `window_len` is shorter than the kernel, since the window
+    /// could then never contain a full transient.
+    pub fn with_window(
+        tau_rise: f64,
+        tau_decay: f64,
+        lambda: f64,
+        fs: f64,
+        window_len: usize,
+    ) -> Result<Self, CalabError> {
+        let inner = Self::build_inner(tau_rise, tau_decay, lambda, fs);
+        let kernel_len = inner.kernel.len();
+        if window_len < kernel_len {
+            return Err(CalabError::invalid(
+                "window_len",
+                window_len,
+                format!("must be at least the kernel length ({kernel_len})"),
+            ));
+        }
+        Ok(Self::from_parts(inner, window_len))
+    }
+
+    fn build_inner(tau_rise: f64, tau_decay: f64, lambda: f64, fs: f64) -> Solver {
+        let mut inner = Solver::new();
+        inner.set_params(tau_rise, tau_decay, lambda, fs);
+        // The window is re-solved every sample: use the O(T) banded engine
+        // (set_params already picked BandedAR1 for tau_rise == 0).
+        if inner.conv_mode == ConvMode::Fft {
+            inner.set_conv_mode(ConvMode::BandedAR2);
+        }
+        inner
+    }
+
+    fn from_parts(inner: Solver, window_len: usize) -> Self {
+        StreamingSolver {
+            inner,
+            buffer: VecDeque::with_capacity(window_len),
+            window_len,
+            window: Vec::with_capacity(window_len),
+            warm: vec![0.0; window_len],
+            baseline: None,
+        }
+    }
+
+    /// Window length in samples.
+    pub fn window_len(&self) -> usize {
+        self.window_len
+    }
+
+    /// Subtract the `quantile`-th value of the last `window` raw samples from
+    /// each sample before it is buffered, tracking a drifting floor as
+    /// `subtract_rolling_baseline` does offline. `window == 0` turns it off.
+    /// Errors when `quantile` is outside [0, 1].
+    pub fn set_rolling_baseline(&mut self, window: usize, quantile: f64) -> Result<(), CalabError> {
+        if !(0.0..=1.0).contains(&quantile) {
+            return Err(CalabError::invalid(
+                "quantile",
+                quantile,
+                "must be in [0, 1]",
+            ));
+        }
+        self.baseline = (window > 0).then(|| StreamingPercentile::new(window, quantile));
+        Ok(())
+    }
+
+    /// Pre-fill the rolling-baseline window with samples recorded before the
+    /// stream (no-op when the baseline is off).
+    pub fn warmup_baseline(&mut self, data: &[f32]) {
+        if let Some(baseline) = &mut self.baseline {
+            baseline.warmup(data);
+        }
+    }
+
+    /// Number of samples currently buffered (not yet emitted).
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Append one sample. Returns the spike estimate for the oldest sample
+    /// once the window is full, `None` while it is still filling.
+    ///
+    /// A non-finite sample (e.g. a dropped frame) is replaced by the previous
+    /// buffered sample, or by 0.0 (the baseline) when nothing is buffered,
+    /// and then goes through the window like any other. Every call therefore
+    /// still stands for one input sample and the emitted estimates stay
+    /// aligned with the input. The substitute is not fed to the rolling
+    /// baseline.
+    pub fn push_sample(&mut self, value: f32) -> Option<f32> {
+        let value = if !value.is_finite() {
+            self.buffer.back().copied().unwrap_or(0.0)
+        } else {
+            match &mut self.baseline {
+                Some(baseline) => value - baseline.push(value),
+                None => value,
+            }
+        };
+        self.buffer.push_back(value);
+        let n = self.buffer.len();
+        if n < self.window_len {
+            return None;
+        }
+
+        // Warm start: the previous window's solution, shifted left by one.
+        // `warm` holds the solution over the previous n - 1 buffered samples.
+        self.load_window();
+        let m = n - 1;
+        self.inner.solution[..m].copy_from_slice(&self.warm[..m]);
+        self.inner.solution[m] = 0.0;
+        self.inner.solution_prev[..n].copy_from_slice(&self.inner.solution[..n]);
+        self.inner.step_batch(1);
+
+        let emitted = self.inner.solution[0];
+        self.warm[..m].copy_from_slice(&self.inner.solution[1..n]);
+        self.buffer.pop_front();
+        Some(emitted)
+    }
+
+    /// Drain the buffer, solving the remaining samples to convergence.
+    ///
+    /// Returns one spike estimate per buffered sample, oldest first, and
+    /// leaves the solver (and rolling baseline) empty and ready for a new
+    /// stream.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let n = self.buffer.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        // Samples that passed through a full window carry a warm solution from
+        // push_sample; a stream that never filled starts from zeros.
+        self.load_window();
+        self.inner.solution[..n].copy_from_slice(&self.warm[..n]);
+        self.inner.solution_prev[..n].copy_from_slice(&self.warm[..n]);
+        let mut iters = 0;
+        while iters < FLUSH_MAX_ITERS && !self.inner.step_batch(10) {
+            iters += 10;
+        }
+        let out = self.inner.solution[..n].to_vec();
+        self.buffer.clear();
+        self.warm.fill(0.0);
+        if let Some(baseline) = &mut self.baseline {
+            baseline.reset();
+        }
+        out
+    }
+
+    /// Copy the buffered samples into the inner solver as a fresh trace.
+    fn load_window(&mut self) {
+        self.window.clear();
+        self.window.extend(self.buffer.iter().copied());
+        self.inner.set_trace(&self.window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingSolver;
+    use crate::kernel::build_kernel;
+    use crate::test_util::build_trace;
+
+    #[test]
+    fn streamed_spikes_land_near_true_times() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        assert_eq!(
+            StreamingSolver::new(0.02, 0.4, 0.01, 30.0).window_len(),
+            10 * kernel.len()
+        );
+
+        // Spikes placed so that all but the last are emitted by push_sample
+        // (window 400 → the first 1100 samples leave the window while streaming).
+        let spikes = [150, 500, 900, 1300];
+        let trace = build_trace(&kernel, 1500, &spikes.map(|s| (s, 1.0)));
+        let mut stream = StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, 400).unwrap();
+
+        let mut estimates: Vec<f32> = trace
+            .iter()
+            .filter_map(|&v| stream.push_sample(v))
+            .collect();
+        assert_eq!(estimates.len(), 1500 - 399);
+        estimates.extend(stream.flush());
+        assert_eq!(estimates.len(), trace.len());
+        assert_eq!(stream.buffered(), 0);
+
+        for &s in &spikes {
+            let local = estimates[s - 3..s + 3]
+                .iter()
+                .cloned()
+                .fold(0.0_f32, f32::max);
+            assert!(local > 0.3, "expected a spike near {s}, local max {local}");
+        }
+        let background = estimates[250..450].iter().cloned().fold(0.0_f32, f32::max);
+        assert!(
+            background < 0.1,
+            "quiet stretch should stay near zero, got {background}"
+        );
+    }
+
+    #[test]
+    fn rolling_baseline_removes_drift() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let spikes = [150, 500, 900];
+        let mut trace = build_trace(&kernel, 1200, &spikes.map(|s| (s, 1.0)));
+        // Slow upward drift of 3 units: far larger than a transient.
+        for (t, v) in trace.iter_mut().enumerate() {
+            *v += 3.0 * t as f32 / 1200.0 + 5.0;
+        }
+
+        let mut stream = StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, 400).unwrap();
+        assert!(stream.set_rolling_baseline(300, 1.5).is_err());
+        stream.set_rolling_baseline(300, 0.2).unwrap();
+        stream.warmup_baseline(&[5.0; 300]);
+        let mut estimates: Vec<f32> = trace
+            .iter()
+            .filter_map(|&v| stream.push_sample(v))
+            .collect();
+        estimates.extend(stream.flush());
+        assert_eq!(estimates.len(), trace.len());
+
+        for &s in &spikes {
+            let local = estimates[s - 3..s + 3]
+                .iter()
+                .cloned()
+                .fold(0.0_f32, f32::max);
+            assert!(local > 0.3, "expected a spike near {s}, local max {local}");
+        }
+        let background = estimates[250..450].iter().cloned().fold(0.0_f32, f32::max);
+        assert!(background < 0.1, "drift leaked into spikes: {background}");
+    }
+
+    #[test]
+    fn nan_samples_hold_the_previous_value() {
+        let kernel = build_kernel(0.02, 0.4, 30.0);
+        let trace = build_trace(&kernel, 400, &[(40, 1.0), (150, 1.0), (260, 1.0)]);
+        let mut held = trace.clone();
+        let mut gappy = trace.clone();
+        for t in [0, 100, 101, 300] {
+            held[t] = if t == 0 { 0.0 } else { held[t - 1] };
+            gappy[t] = f32::NAN;
+        }
+
+        let mut reference = StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, 200).unwrap();
+        let mut stream = StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, 200).unwrap();
+        for (&h, &g) in held.iter().zip(&gappy) {
+            assert_eq!(stream.push_sample(g), reference.push_sample(h));
+        }
+        assert_eq!(stream.buffered(), 199);
+        assert_eq!(stream.flush(), reference.flush());
+    }
+
+    #[test]
+    fn window_shorter_than_kernel_is_rejected() {
+        let kernel_len = build_kernel(0.02, 0.4, 30.0).len();
+        assert!(StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, kernel_len - 1).is_err());
+        assert!(StreamingSolver::with_window(0.02, 0.4, 0.01, 30.0, kernel_len).is_ok());
+    }
+
+    #[test]
+    fn partial_window_flushes_every_sample() {
+        let mut stream = StreamingSolver::new(0.02, 0.4, 0.01, 30.0);
+        for _ in 0..25 {
+            assert_eq!(stream.push_sample(0.0), None);
+        }
+        let out = stream.flush();
+        assert_eq!(out.len(), 25);
+        assert!(stream.flush().is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+//! Fixtures shared by the solver unit tests.
+
+use crate::{ConvMode, Solver, SolverMode};
+
+/// Noise-free trace of length `n`: `kernel` placed at each `(frame, amplitude)`
+/// spike and truncated at the end of the trace.
+pub(crate) fn build_trace(kernel: &[f32], n: usize, spikes: &[(usize, f32)]) -> Vec<f32> {
+    let mut trace = vec![0.0_f32; n];
+    for &(s, amp) in spikes {
+        for (k, &kv) in kernel.iter().enumerate() {
+            if s + k < n {
+                trace[s + k] += amp * kv;
+            }
+        }
+    }
+    trace
+}
+
+/// Relative L2 distance ||a - b|| / ||b||.
+pub(crate) fn relative_error(a: &[f32], b: &[f32]) -> f64 {
+    let num: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| (x as f64 - y as f64).powi(2))
+        .sum();
+    let den: f64 = b.iter().map(|&y| (y as f64).powi(2)).sum();
+    (num / den).sqrt()
+}
+
+/// Solve `trace` in `mode` with the shared test parameters (tau_rise 0.02 s,
+/// tau_decay 0.4 s, lambda 0.01, 30 Hz), stepping in batches of 10 until it
+/// converges or `max_batches` run out. `conv` and `tolerance` override the
+/// default convolution mode and convergence tolerance.
+pub(crate) fn solve_to_convergence(
+    mode: SolverMode,
+    conv: Option<ConvMode>,
+    tolerance: Option<f64>,
+    trace: &[f32],
+    max_batches: u32,
+) -> Solver {
+    let mut solver = Solver::new();
+    solver.set_params(0.02, 0.4, 0.01, 30.0);
+    if let Some(conv) = conv {
+        solver.set_conv_mode(conv);
+    }
+    solver.set_solver_mode(mode);
+    if let Some(tolerance) = tolerance {
+        solver.tolerance = tolerance;
+    }
+    solver.set_trace(trace);
+    for _ in 0..max_batches {
+        if solver.step_batch(10) {
+            break;
+        }
+    }
+    solver
+}
@@ -7,8 +7,13 @@
 /// For each candidate threshold, the binary spike train is convolved through
 /// the peak-normalized AR2 model and fit with least-squares alpha + baseline.
 /// Alpha is constrained non-negative (spikes must add signal, not subtract).
-use crate::banded::BandedAR2;
+///
+/// The grid search minimizes either the residual SSE or, to curb the MSE
+/// criterion's bias toward low thresholds, an AIC that charges for every spike
+/// (see [`ThresholdCriterion`]).
+use crate::banded::{BandedAR2, SPARSE_MAX_DENSITY};
 
+#[cfg_attr(feature = "jsbindings", derive(serde::Serialize))]
 pub struct ThresholdResult {
     pub s_binary: Vec<f32>,
     pub alpha: f64,
@@ -30,6 +35,30 @@ pub enum Selection {
     /// at original rate (data-derived, no tuning knob). Realizes an OASIS-style
     /// "don't fit below the noise" sparsity at the stage where counts are decided.
     NoiseFloor { sigma: f64 },
+    /// Threshold minimizing `AIC = n·ln(SSE/n) + 2k` over the same coarse→fine
+    /// grid as `MaxPve`, with `n` the interior sample count and `k` the number
+    /// of spikes. The penalty stops the search from buying small SSE gains
+    /// with spurious low-threshold spikes.
+    MinAic,
+}
+
+/// Objective of the coarse→fine threshold grid search.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ThresholdCriterion {
+    /// Smallest residual SSE (highest PVE): [`Selection::MaxPve`].
+    #[default]
+    Mse,
+    /// Smallest AIC: [`Selection::MinAic`].
+    Aic,
+}
+
+impl From<ThresholdCriterion> for Selection {
+    fn from(criterion: ThresholdCriterion) -> Self {
+        match criterion {
+            ThresholdCriterion::Mse => Selection::MaxPve,
+            ThresholdCriterion::Aic => Selection::MinAic,
+        }
+    }
 }
 
 /// Compute boundary padding for threshold search: ceil(2 * tau_d * fs_up).
@@ -45,6 +74,15 @@ pub fn boundary_padding(tau_decay: f64, fs_up: f64) -> usize {
 /// the AR2 model and fit with least-squares alpha + baseline.
 ///
 /// Alpha is constrained non-negative (spikes must add signal, not subtract).
+///
+/// `max_threshold` caps the automatic grid: no candidate, coarse or fine, is
+/// above it (`f64::INFINITY` for no cap). If every relaxed value exceeds the
+/// cap, `max_threshold` itself is the only candidate.
+///
+/// `weights` (same length as `y`) down-weights artifact or noisy frames in
+/// the alpha/baseline fit, the residual and the PVE, as in
+/// [`threshold_search_opts`]; `None` weights every frame equally.
+#[allow(clippy::too_many_arguments)]
 pub fn threshold_search(
     s_relaxed: &[f32],
     y: &[f32],
@@ -53,8 +91,10 @@ pub fn threshold_search(
     fs_up: f64,
     upsample_factor: usize,
     max_alpha: f64,
+    max_threshold: f64,
+    weights: Option<&[f32]>,
 ) -> ThresholdResult {
-    threshold_search_opts(
+    threshold_search_capped(
         s_relaxed,
         y,
         banded,
@@ -63,10 +103,95 @@ pub fn threshold_search(
         upsample_factor,
         max_alpha,
         Selection::MaxPve,
+        weights,
+        max_threshold,
+    )
+}
+
+/// Binarize at each of `thresholds` exactly as given (no coarse→fine
+/// refinement and no minimum-threshold floor) and keep the one with the
+/// smallest interior residual SSE; ties go to the earlier entry. Use it when
+/// the automatic grid misses the optimum, e.g. for a multimodal relaxed
+/// solution. Original-rate grid, alpha uncapped. NaN entries are skipped;
+/// with no usable entry the result is the no-spike fit.
+pub fn threshold_search_custom_grid(
+    s_relaxed: &[f32],
+    y: &[f32],
+    banded: &BandedAR2,
+    tau_decay: f64,
+    fs_up: f64,
+    thresholds: &[f64],
+) -> ThresholdResult {
+    let n = s_relaxed.len();
+    let pad = boundary_padding(tau_decay, fs_up).min(n / 4);
+    let mut s_bin = vec![0.0_f32; n];
+    let mut conv_buf = vec![0.0_f32; n];
+
+    let mut best: Option<(f64, f64)> = None;
+    for &thresh in thresholds.iter().filter(|t| !t.is_nan()) {
+        let err = evaluate_threshold(
+            s_relaxed,
+            y,
+            banded,
+            thresh,
+            pad,
+            1,
+            f64::INFINITY,
+            None,
+            &mut s_bin,
+            &mut conv_buf,
+        );
+        if best.is_none_or(|(_, best_err)| err < best_err) {
+            best = Some((thresh, err));
+        }
+    }
+    match best {
+        Some((threshold, error)) => result_at_threshold(
+            s_relaxed,
+            y,
+            banded,
+            threshold,
+            pad,
+            f64::INFINITY,
+            None,
+            s_bin,
+            &mut conv_buf,
+            error,
+        ),
+        None => empty_result(y),
+    }
+}
+
+/// Threshold search minimizing AIC instead of the residual (see
+/// [`Selection::MinAic`]), on an original-rate grid (`upsample_factor = 1`)
+/// with alpha uncapped.
+pub fn threshold_search_aic(
+    s_relaxed: &[f32],
+    y: &[f32],
+    banded: &BandedAR2,
+    tau_decay: f64,
+    fs_up: f64,
+) -> ThresholdResult {
+    threshold_search_opts(
+        s_relaxed,
+        y,
+        banded,
+        tau_decay,
+        fs_up,
+        1,
+        f64::INFINITY,
+        Selection::MinAic,
+        None,
     )
 }
 
 /// Threshold search with a selectable criterion. See [`Selection`].
+/// `error` in the result is the minimized objective: the residual SSE for
+/// `MaxPve`, the AIC for `MinAic`, and infinity for `NoiseFloor`.
+///
+/// `weights`: optional per-sample weights (same length as `y`) for the
+/// alpha/baseline fit, the residual and the PVE. A weight of 0 removes a
+/// sample from the fit entirely — used to ignore detector-saturated frames.
 #[allow(clippy::too_many_arguments)]
 pub fn threshold_search_opts(
     s_relaxed: &[f32],
@@ -77,7 +202,40 @@ pub fn threshold_search_opts(
     upsample_factor: usize,
     max_alpha: f64,
     selection: Selection,
+    weights: Option<&[f32]>,
+) -> ThresholdResult {
+    threshold_search_capped(
+        s_relaxed,
+        y,
+        banded,
+        tau_decay,
+        fs_up,
+        upsample_factor,
+        max_alpha,
+        selection,
+        weights,
+        f64::INFINITY,
+    )
+}
+
+/// [`threshold_search_opts`] with the candidate thresholds capped at
+/// `max_threshold` (see [`threshold_search`]).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn threshold_search_capped(
+    s_relaxed: &[f32],
+    y: &[f32],
+    banded: &BandedAR2,
+    tau_decay: f64,
+    fs_up: f64,
+    upsample_factor: usize,
+    max_alpha: f64,
+    selection: Selection,
+    weights: Option<&[f32]>,
+    max_threshold: f64,
 ) -> ThresholdResult {
+    if let Some(w) = weights {
+        debug_assert_eq!(w.len(), y.len());
+    }
     let n = s_relaxed.len();
     let pad = boundary_padding(tau_decay, fs_up).min(n / 4);
 
@@ -93,73 +251,45 @@ pub fn threshold_search_opts(
     vals.dedup_by(|a, b| (*a - *b).abs() < 1e-10);
 
     if vals.is_empty() {
-        // No nonzero values — return zero result
-        return ThresholdResult {
-            s_binary: vec![0.0; n],
-            alpha: 0.0,
-            baseline: y.iter().map(|&v| v as f64).sum::<f64>() / n as f64,
-            threshold: 0.0,
-            pve: 0.0,
-            error: f64::INFINITY,
-        };
+        return empty_result(y);
+    }
+    if vals[0] as f64 > max_threshold {
+        vals = vec![max_threshold as f32];
+    } else {
+        vals.retain(|&v| v as f64 <= max_threshold);
     }
 
     // Reusable buffers
     let mut s_bin = vec![0.0_f32; n];
     let mut conv_buf = vec![0.0_f32; n];
 
-    let mut best = ThresholdResult {
-        s_binary: Vec::new(),
-        alpha: 0.0,
-        baseline: 0.0,
-        threshold: 0.0,
-        pve: 0.0,
-        error: f64::INFINITY,
-    };
-
     // Noise-constrained selection scans for the sparsest support within the
-    // noise floor; the max-PVE path keeps the original coarse→fine search.
-    if let Selection::NoiseFloor { sigma } = selection {
-        best.threshold = select_noise_floor_threshold(
+    // noise floor; MaxPve and MinAic share the original coarse→fine search.
+    let (threshold, error) = if let Selection::NoiseFloor { sigma } = selection {
+        let threshold = select_noise_floor_threshold(
             s_relaxed,
             y,
             banded,
             pad,
             upsample_factor,
             max_alpha,
+            weights,
             sigma,
             &vals,
             min_threshold,
             &mut s_bin,
             &mut conv_buf,
         );
+        (threshold, f64::INFINITY)
     } else {
-        // Phase 1: Coarse search — ~50 evenly spaced thresholds
-        let coarse_n = 50.min(vals.len());
-        let coarse_step = if vals.len() > 1 {
-            (vals.len() - 1) as f64 / (coarse_n - 1).max(1) as f64
-        } else {
-            1.0
-        };
-
-        let mut coarse_thresholds: Vec<f64> = Vec::with_capacity(coarse_n);
-        for i in 0..coarse_n {
-            let idx = (i as f64 * coarse_step).round() as usize;
-            let idx = idx.min(vals.len() - 1);
-            coarse_thresholds.push(vals[idx] as f64);
-        }
-        coarse_thresholds.dedup_by(|a, b| (*a - *b).abs() < 1e-10);
-
-        // Enforce minimum threshold floor
-        coarse_thresholds.retain(|&t| t >= min_threshold);
-        if coarse_thresholds.is_empty() {
-            // All candidates below minimum — use min_threshold as the only candidate
-            coarse_thresholds.push(min_threshold);
-        }
-
-        let mut consecutive_increases = 0;
-        for &thresh in &coarse_thresholds {
-            let err = evaluate_threshold(
+        // MaxPve scores the residual SSE directly; MinAic adds the spike-count
+        // penalty on top of it.
+        let n_fit = (pad..n.saturating_sub(pad))
+            .filter(|&i| weight_at(weights, i) > 0.0)
+            .count();
+        let aic = matches!(selection, Selection::MinAic);
+        grid_search(&vals, min_threshold, max_threshold, |thresh| {
+            let sse = evaluate_threshold(
                 s_relaxed,
                 y,
                 banded,
@@ -167,100 +297,426 @@ pub fn threshold_search_opts(
                 pad,
                 1,
                 max_alpha,
+                weights,
                 &mut s_bin,
                 &mut conv_buf,
             );
-            if err < best.error {
-                best.error = err;
-                best.threshold = thresh;
-                consecutive_increases = 0;
+            if aic {
+                let k = s_bin.iter().filter(|&&v| v > 0.0).count();
+                aic_score(sse, n_fit, k)
             } else {
-                consecutive_increases += 1;
-                if consecutive_increases >= 10 {
-                    break;
-                }
+                sse
             }
-        }
+        })
+    };
+
+    // Final pass: compute full result at best threshold
+    result_at_threshold(
+        s_relaxed,
+        y,
+        banded,
+        threshold,
+        pad,
+        max_alpha,
+        weights,
+        s_bin,
+        &mut conv_buf,
+        error,
+    )
+}
+
+/// Result when the relaxed solution has no nonzero values: no spikes, the
+/// mean of `y` as baseline and an infinite error.
+fn empty_result(y: &[f32]) -> ThresholdResult {
+    ThresholdResult {
+        s_binary: vec![0.0; y.len()],
+        alpha: 0.0,
+        baseline: y.iter().map(|&v| v as f64).sum::<f64>() / y.len() as f64,
+        threshold: 0.0,
+        pve: 0.0,
+        error: f64::INFINITY,
+    }
+}
+
+/// Coarse→fine search for the threshold minimizing `score`: ~50 candidates
+/// evenly spaced through the sorted unique values `vals` (at least
+/// `min_threshold`), then ~50 evenly spaced around the best of those, up to
+/// `max_threshold`. Each phase stops after 10 consecutive non-improving
+/// candidates. Returns the best threshold and its score.
+fn grid_search(
+    vals: &[f32],
+    min_threshold: f64,
+    max_threshold: f64,
+    mut score: impl FnMut(f64) -> f64,
+) -> (f64, f64) {
+    // The cap wins over the floor when they cross.
+    let min_threshold = min_threshold.min(max_threshold);
+    let mut best_threshold = 0.0;
+    let mut best_error = f64::INFINITY;
+
+    // Phase 1: Coarse search — ~50 evenly spaced thresholds
+    let coarse_n = 50.min(vals.len());
+    let coarse_step = if vals.len() > 1 {
+        (vals.len() - 1) as f64 / (coarse_n - 1).max(1) as f64
+    } else {
+        1.0
+    };
+
+    let mut coarse_thresholds: Vec<f64> = Vec::with_capacity(coarse_n);
+    for i in 0..coarse_n {
+        let idx = (i as f64 * coarse_step).round() as usize;
+        let idx = idx.min(vals.len() - 1);
+        coarse_thresholds.push(vals[idx] as f64);
+    }
+    coarse_thresholds.dedup_by(|a, b| (*a - *b).abs() < 1e-10);
 
-        // Phase 2: Fine search — ~50 thresholds around the best coarse result
-        let spread = if vals.len() > 1 {
-            (vals[vals.len() - 1] - vals[0]) as f64 / coarse_n as f64 * 2.0
+    // Enforce minimum threshold floor
+    coarse_thresholds.retain(|&t| t >= min_threshold);
+    if coarse_thresholds.is_empty() {
+        // All candidates below minimum — use min_threshold as the only candidate
+        coarse_thresholds.push(min_threshold);
+    }
+
+    let mut consecutive_increases = 0;
+    for &thresh in &coarse_thresholds {
+        let err = score(thresh);
+        if err < best_error {
+            best_error = err;
+            best_threshold = thresh;
+            consecutive_increases = 0;
         } else {
-            best.threshold * 0.2
-        };
-        let fine_lo = (best.threshold - spread).max(min_threshold);
-        let fine_hi = best.threshold + spread;
-        let fine_n = 50;
-        let fine_step = (fine_hi - fine_lo) / (fine_n - 1).max(1) as f64;
-
-        consecutive_increases = 0;
-        for i in 0..fine_n {
-            let thresh = fine_lo + i as f64 * fine_step;
-            if thresh < 0.0 {
-                continue;
-            }
-            let err = evaluate_threshold(
-                s_relaxed,
-                y,
-                banded,
-                thresh,
-                pad,
-                1,
-                max_alpha,
-                &mut s_bin,
-                &mut conv_buf,
-            );
-            if err < best.error {
-                best.error = err;
-                best.threshold = thresh;
-                consecutive_increases = 0;
-            } else {
-                consecutive_increases += 1;
-                if consecutive_increases >= 10 {
-                    break;
-                }
+            consecutive_increases += 1;
+            if consecutive_increases >= 10 {
+                break;
             }
         }
-    } // end max-PVE search branch
+    }
 
-    // Final pass: compute full result at best threshold
-    binarize(s_relaxed, best.threshold, &mut s_bin);
-    banded.convolve_forward(&s_bin, &mut conv_buf);
+    // Phase 2: Fine search — ~50 thresholds around the best coarse result
+    let spread = if vals.len() > 1 {
+        (vals[vals.len() - 1] - vals[0]) as f64 / coarse_n as f64 * 2.0
+    } else {
+        best_threshold * 0.2
+    };
+    let fine_lo = (best_threshold - spread).max(min_threshold);
+    let fine_hi = (best_threshold + spread).min(max_threshold);
+    let fine_n = 50;
+    let fine_step = (fine_hi - fine_lo) / (fine_n - 1).max(1) as f64;
+
+    consecutive_increases = 0;
+    for i in 0..fine_n {
+        let thresh = fine_lo + i as f64 * fine_step;
+        if thresh < 0.0 {
+            continue;
+        }
+        let err = score(thresh);
+        if err < best_error {
+            best_error = err;
+            best_threshold = thresh;
+            consecutive_increases = 0;
+        } else {
+            consecutive_increases += 1;
+            if consecutive_increases >= 10 {
+                break;
+            }
+        }
+    }
+    (best_threshold, best_error)
+}
 
-    let (alpha, baseline) = lstsq_alpha_baseline(&conv_buf, y, pad, max_alpha);
-    best.alpha = alpha;
-    best.baseline = baseline;
-    best.s_binary = s_bin;
+/// Binarize at `threshold` and fit alpha/baseline over the interior, reporting
+/// the fit's PVE. `s_bin` becomes the result's `s_binary`; `error` is stored
+/// as given.
+#[allow(clippy::too_many_arguments)]
+fn result_at_threshold(
+    s_relaxed: &[f32],
+    y: &[f32],
+    banded: &BandedAR2,
+    threshold: f64,
+    pad: usize,
+    max_alpha: f64,
+    weights: Option<&[f32]>,
+    mut s_bin: Vec<f32>,
+    conv_buf: &mut [f32],
+    error: f64,
+) -> ThresholdResult {
+    let n = y.len();
+    binarize(s_relaxed, threshold, &mut s_bin);
+    banded.convolve_forward(&s_bin, conv_buf);
+
+    let (alpha, baseline) = fit_alpha_baseline(conv_buf, y, weights, pad, max_alpha);
+    let mut result = ThresholdResult {
+        s_binary: s_bin,
+        alpha,
+        baseline,
+        threshold,
+        pve: 0.0,
+        error,
+    };
 
     // Compute PVE (proportion of variance explained)
     let inner_range = pad..n.saturating_sub(pad);
     let inner_len = inner_range.len();
     if inner_len > 0 {
         let mut y_sum = 0.0_f64;
+        let mut w_sum = 0.0_f64;
         for i in inner_range.clone() {
-            y_sum += y[i] as f64;
+            let w = weight_at(weights, i);
+            y_sum += w * y[i] as f64;
+            w_sum += w;
         }
-        let y_mean = y_sum / inner_len as f64;
+        let y_mean = if w_sum > 0.0 { y_sum / w_sum } else { 0.0 };
 
         let mut ss_tot = 0.0_f64;
         let mut ss_res = 0.0_f64;
         for i in inner_range {
+            let w = weight_at(weights, i);
             let yi = y[i] as f64;
             let d = yi - y_mean;
-            ss_tot += d * d;
+            ss_tot += w * d * d;
             let pred = alpha * conv_buf[i] as f64 + baseline;
             let r = yi - pred;
-            ss_res += r * r;
+            ss_res += w * r * r;
         }
 
-        best.pve = if ss_tot > 1e-20 {
+        result.pve = if ss_tot > 1e-20 {
             1.0 - ss_res / ss_tot
         } else {
             0.0
         };
     }
 
-    best
+    result
+}
+
+/// Shared-threshold search over several cells: the one threshold minimizing
+/// the sum of per-cell interior MSEs, over the coarse→fine grid of
+/// [`threshold_search`] built from all cells' relaxed values pooled. Each
+/// cell keeps its own alpha/baseline fit (alpha uncapped, no upsampling
+/// floor beyond `upsample_factor = 1`).
+///
+/// Returns one result per cell, each carrying the shared threshold and its
+/// own interior SSE as `error`, plus the shared threshold. An empty list
+/// gives `(vec![], 0.0)`, and if no cell has a nonzero relaxed value every
+/// cell gets the no-spike result of [`threshold_search`]. Cells are paired
+/// with `y_list` by index and each pair must have equal lengths.
+pub fn joint_threshold_search(
+    s_relaxed_list: &[&[f32]],
+    y_list: &[&[f32]],
+    banded: &BandedAR2,
+    tau_decay: f64,
+    fs_up: f64,
+) -> (Vec<ThresholdResult>, f64) {
+    debug_assert_eq!(s_relaxed_list.len(), y_list.len());
+    let cells: Vec<(&[f32], &[f32])> = s_relaxed_list
+        .iter()
+        .zip(y_list)
+        .map(|(&s, &y)| (s, y))
+        .collect();
+    if cells.is_empty() {
+        return (Vec::new(), 0.0);
+    }
+    let min_threshold = 0.5;
+    let pads: Vec<usize> = cells
+        .iter()
+        .map(|(s, _)| boundary_padding(tau_decay, fs_up).min(s.len() / 4))
+        .collect();
+
+    let mut vals: Vec<f32> = cells
+        .iter()
+        .flat_map(|(s, _)| s.iter().copied())
+        .filter(|&v| v > 1e-10)
+        .collect();
+    vals.sort_unstable_by(|a, b| a.total_cmp(b));
+    vals.dedup_by(|a, b| (*a - *b).abs() < 1e-10);
+
+    if vals.is_empty() {
+        return (cells.iter().map(|&(_, y)| empty_result(y)).collect(), 0.0);
+    }
+
+    let max_len = cells.iter().map(|(s, _)| s.len()).max().unwrap_or(0);
+    let mut s_bin = vec![0.0_f32; max_len];
+    let mut conv_buf = vec![0.0_f32; max_len];
+    let (threshold, _) = grid_search(&vals, min_threshold, f64::INFINITY, |thresh| {
+        cells
+            .iter()
+            .zip(&pads)
+            .map(|(&(s, y), &pad)| {
+                let n = s.len();
+                let sse = evaluate_threshold(
+                    s,
+                    y,
+                    banded,
+                    thresh,
+                    pad,
+                    1,
+                    f64::INFINITY,
+                    None,
+                    &mut s_bin[..n],
+                    &mut conv_buf[..n],
+                );
+                sse / n.saturating_sub(2 * pad).max(1) as f64
+            })
+            .sum()
+    });
+
+    let results = cells
+        .iter()
+        .zip(&pads)
+        .map(|(&(s, y), &pad)| {
+            let n = s.len();
+            let sse = evaluate_threshold(
+                s,
+                y,
+                banded,
+                threshold,
+                pad,
+                1,
+                f64::INFINITY,
+                None,
+                &mut s_bin[..n],
+                &mut conv_buf[..n],
+            );
+            result_at_threshold(
+                s,
+                y,
+                banded,
+                threshold,
+                pad,
+                f64::INFINITY,
+                None,
+                vec![0.0; n],
+                &mut conv_buf[..n],
+                sse,
+            )
+        })
+        .collect();
+    (results, threshold)
+}
+
+/// ROC curve of a relaxed solution against a ground-truth spike train.
+pub struct RocCurve {
+    /// False-positive rate per threshold, from 0 up to 1.
+    pub fpr: Vec<f64>,
+    /// True-positive rate per threshold, from 0 up to 1.
+    pub tpr: Vec<f64>,
+    /// Thresholds, descending; the first is +infinity (nothing detected).
+    pub thresholds: Vec<f64>,
+    /// Area under the curve (trapezoidal), 0.5 when undefined.
+    pub auc: f64,
+    /// Set when the AUC is undefined because the ground truth has no positive
+    /// (or no negative) samples in the interior.
+    pub warning: Option<String>,
+}
+
+/// Evaluate binarization thresholds against ground truth with an ROC curve.
+///
+/// For each of `n_thresholds` (at least 2) candidates spaced evenly from the
+/// largest to the smallest value of `s_relaxed`, a sample is detected when
+/// `s_relaxed >= threshold` and is a true spike when `s_true_binary > 0.5`.
+/// Only the interior excluded by [`boundary_padding`] is scored, as in the
+/// threshold search. Needs ground truth on the same (upsampled) grid, e.g.
+/// from simulated data or paired electrophysiology.
+pub fn roc_threshold_curve(
+    s_relaxed: &[f32],
+    s_true_binary: &[f32],
+    tau_decay: f64,
+    fs_up: f64,
+    n_thresholds: usize,
+) -> RocCurve {
+    debug_assert_eq!(s_relaxed.len(), s_true_binary.len());
+    let n = s_relaxed.len().min(s_true_binary.len());
+    let pad = boundary_padding(tau_decay, fs_up).min(n / 4);
+    let interior = pad..n - pad;
+    let scores = &s_relaxed[interior.clone()];
+    let truth = &s_true_binary[interior];
+
+    let positives = truth.iter().filter(|&&t| t > 0.5).count();
+    let negatives = truth.len() - positives;
+    let (lo, hi) = scores
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v as f64), hi.max(v as f64))
+        });
+
+    let n_thresholds = n_thresholds.max(2);
+    let mut thresholds = Vec::with_capacity(n_thresholds + 1);
+    thresholds.push(f64::INFINITY);
+    if hi >= lo {
+        for i in 0..n_thresholds {
+            thresholds.push(hi - (hi - lo) * i as f64 / (n_thresholds - 1) as f64);
+        }
+    }
+
+    if positives == 0 || negatives == 0 {
+        let which = if positives == 0 {
+            "positive"
+        } else {
+            "negative"
+        };
+        return RocCurve {
+            fpr: vec![0.0, 1.0],
+            tpr: vec![0.0, 1.0],
+            thresholds: vec![f64::INFINITY, if lo.is_finite() { lo } else { 0.0 }],
+            auc: 0.5,
+            warning: Some(format!(
+                "ground truth has no {which} samples in the interior; AUC is undefined (reported as 0.5)"
+            )),
+        };
+    }
+
+    let (mut fpr, mut tpr) = (Vec::with_capacity(thresholds.len()), Vec::new());
+    for &th in &thresholds {
+        let (mut tp, mut fp) = (0usize, 0usize);
+        for (&v, &t) in scores.iter().zip(truth) {
+            if v as f64 >= th {
+                if t > 0.5 {
+                    tp += 1;
+                } else {
+                    fp += 1;
+                }
+            }
+        }
+        tpr.push(tp as f64 / positives as f64);
+        fpr.push(fp as f64 / negatives as f64);
+    }
+
+    let auc = fpr
+        .windows(2)
+        .zip(tpr.windows(2))
+        .map(|(f, t)| (f[1] - f[0]) * (t[1] + t[0]) * 0.5)
+        .sum();
+
+    RocCurve {
+        fpr,
+        tpr,
+        thresholds,
+        auc,
+        warning: None,
+    }
+}
+
+/// Weight of sample `i` (1.0 when unweighted).
+#[inline]
+fn weight_at(weights: Option<&[f32]>, i: usize) -> f64 {
+    match weights {
+        Some(w) => w[i] as f64,
+        None => 1.0,
+    }
+}
+
+/// Alpha/baseline fit, dispatching to the weighted solve when weights are given.
+fn fit_alpha_baseline(
+    conv: &[f32],
+    y: &[f32],
+    weights: Option<&[f32]>,
+    pad: usize,
+    max_alpha: f64,
+) -> (f64, f64) {
+    match weights {
+        Some(w) => lstsq_alpha_baseline_weighted(conv, y, w, pad, max_alpha),
+        None => lstsq_alpha_baseline(conv, y, pad, max_alpha),
+    }
 }
 
 /// Binarize: s_bin[i] = 1 if s[i] >= threshold, else 0.
@@ -271,6 +727,25 @@ fn binarize(s: &[f32], threshold: f64, s_bin: &mut [f32]) {
     }
 }
 
+/// Convolve a binarized train into `conv_buf`, through the sparse
+/// per-event path while at most `SPARSE_MAX_DENSITY` of the samples are set
+/// (the usual case at the thresholds worth evaluating).
+fn convolve_binary(banded: &BandedAR2, s_bin: &[f32], conv_buf: &mut [f32]) {
+    let n = s_bin.len();
+    let max_events = (SPARSE_MAX_DENSITY * n as f64) as usize;
+    let mut spikes = Vec::new();
+    for (t, &v) in s_bin.iter().enumerate() {
+        if v != 0.0 {
+            if spikes.len() == max_events {
+                banded.convolve_forward(s_bin, conv_buf);
+                return;
+            }
+            spikes.push((t, v));
+        }
+    }
+    banded.convolve_forward_sparse(&spikes, n, conv_buf);
+}
+
 /// Evaluate a single threshold: binarize → convolve → lstsq → residual SSE.
 ///
 /// Alpha/baseline are always fit over the full interior; the SSE is accumulated
@@ -287,13 +762,14 @@ fn evaluate_threshold(
     pad: usize,
     stride: usize,
     max_alpha: f64,
+    weights: Option<&[f32]>,
     s_bin: &mut [f32],
     conv_buf: &mut [f32],
 ) -> f64 {
     binarize(s_relaxed, threshold, s_bin);
-    banded.convolve_forward(s_bin, conv_buf);
+    convolve_binary(banded, s_bin, conv_buf);
 
-    let (alpha, baseline) = lstsq_alpha_baseline(conv_buf, y, pad, max_alpha);
+    let (alpha, baseline) = fit_alpha_baseline(conv_buf, y, weights, pad, max_alpha);
 
     let n = y.len();
     let mut err = 0.0_f64;
@@ -301,12 +777,19 @@ fn evaluate_threshold(
     while i < n.saturating_sub(pad) {
         let pred = alpha * conv_buf[i] as f64 + baseline;
         let d = y[i] as f64 - pred;
-        err += d * d;
+        err += weight_at(weights, i) * d * d;
         i += stride.max(1);
     }
     err
 }
 
+/// `n·ln(SSE/n) + 2k` for `n` fitted samples and `k` spikes. The SSE is
+/// floored so a perfect fit stays finite.
+fn aic_score(sse: f64, n: usize, k: usize) -> f64 {
+    let n = n.max(1) as f64;
+    n * (sse / n).max(1e-300).ln() + 2.0 * k as f64
+}
+
 /// Scan candidate thresholds and return the highest (sparsest) one whose
 /// grid-residual stays within the noise budget `sigma^2 * n_grid_interior`.
 /// Residual increases as the threshold rises (fewer spikes → worse fit), so the
@@ -322,6 +805,7 @@ fn select_noise_floor_threshold(
     pad: usize,
     upsample_factor: usize,
     max_alpha: f64,
+    weights: Option<&[f32]>,
     sigma: f64,
     vals: &[f32],
     min_threshold: f64,
@@ -334,7 +818,10 @@ fn select_noise_floor_threshold(
     // samples, avoiding the correlated, reduced-variance noise at interpolated
     // positions, so the budget can use a noise std estimated at the original rate.
     let stride = upsample_factor.max(1);
-    let n_grid = (pad..n.saturating_sub(pad)).step_by(stride).count();
+    let n_grid = (pad..n.saturating_sub(pad))
+        .step_by(stride)
+        .filter(|&i| weight_at(weights, i) > 0.0)
+        .count();
     let budget = sigma * sigma * n_grid as f64;
 
     // Up to `cap` candidate thresholds, evenly spaced through the sorted values.
@@ -365,7 +852,7 @@ fn select_noise_floor_threshold(
     let mut best_effort_sse = f64::INFINITY;
     for &thr in candidates.iter().rev() {
         let sse = evaluate_threshold(
-            s_relaxed, y, banded, thr, pad, stride, max_alpha, s_bin, conv_buf,
+            s_relaxed, y, banded, thr, pad, stride, max_alpha, weights, s_bin, conv_buf,
         );
         if sse <= budget {
             return thr;
@@ -428,14 +915,72 @@ fn lstsq_alpha_baseline(conv: &[f32], y: &[f32], pad: usize, max_alpha: f64) ->
     (alpha, baseline)
 }
 
+/// Weighted least-squares fit for alpha and baseline: minimizes
+/// Σ w_i (y_i - alpha*conv_i - baseline)² over the inner region [pad..n-pad].
+/// Same constraints and fallbacks as [`lstsq_alpha_baseline`]; zero-weight
+/// samples do not influence the fit at all.
+fn lstsq_alpha_baseline_weighted(
+    conv: &[f32],
+    y: &[f32],
+    w: &[f32],
+    pad: usize,
+    max_alpha: f64,
+) -> (f64, f64) {
+    let n = y.len();
+    let lo = pad;
+    let hi = n.saturating_sub(pad);
+    if hi <= lo {
+        return (0.0, 0.0);
+    }
+
+    let mut sum_w = 0.0_f64;
+    let mut sum_c = 0.0_f64;
+    let mut sum_y = 0.0_f64;
+    let mut sum_cc = 0.0_f64;
+    let mut sum_cy = 0.0_f64;
+
+    for i in lo..hi {
+        let wi = w[i] as f64;
+        let c = conv[i] as f64;
+        let yi = y[i] as f64;
+        sum_w += wi;
+        sum_c += wi * c;
+        sum_y += wi * yi;
+        sum_cc += wi * c * c;
+        sum_cy += wi * c * yi;
+    }
+    if sum_w <= 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let det = sum_cc * sum_w - sum_c * sum_c;
+    if det.abs() < 1e-30 {
+        return (0.0, sum_y / sum_w);
+    }
+
+    let alpha = (sum_cy * sum_w - sum_c * sum_y) / det;
+    let baseline = (sum_cc * sum_y - sum_c * sum_cy) / det;
+
+    if alpha < 0.0 {
+        return (0.0, sum_y / sum_w);
+    }
+    if alpha > max_alpha {
+        let baseline = (sum_y - max_alpha * sum_c) / sum_w;
+        return (max_alpha, baseline);
+    }
+
+    (alpha, baseline)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::banded::BandedAR2;
+    use crate::rng::lcg_noise;
 
     #[test]
     fn perfect_binary_recovery() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 300;
 
         let mut s_true = vec![0.0_f32; n];
@@ -454,7 +999,17 @@ mod tests {
             .map(|&c| alpha_true * c + baseline_true as f32)
             .collect();
 
-        let result = threshold_search(&s_true, &y, &banded, 0.4, 30.0, 1, f64::INFINITY);
+        let result = threshold_search(
+            &s_true,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
 
         let spike_count: f32 = result.s_binary.iter().sum();
         assert!(
@@ -471,7 +1026,7 @@ mod tests {
 
     #[test]
     fn alpha_baseline_recovery() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 300;
 
         let mut s_true = vec![0.0_f32; n];
@@ -488,7 +1043,17 @@ mod tests {
             .map(|&c| (alpha_true * c as f64 + baseline_true) as f32)
             .collect();
 
-        let result = threshold_search(&s_true, &y, &banded, 0.4, 30.0, 1, f64::INFINITY);
+        let result = threshold_search(
+            &s_true,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
 
         assert!(
             (result.alpha - alpha_true).abs() < 0.5,
@@ -504,9 +1069,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn weights_ignore_artifact_frames() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 300;
+        let mut s_true = vec![0.0_f32; n];
+        s_true[60] = 1.0;
+        s_true[180] = 1.0;
+        let mut conv = vec![0.0_f32; n];
+        banded.convolve_forward(&s_true, &mut conv);
+        let mut y: Vec<f32> = conv.iter().map(|&c| 2.0 * c + 1.0).collect();
+        // A motion artifact the relaxed solution does not explain.
+        for v in &mut y[110..130] {
+            *v += 6.0;
+        }
+
+        let search = |weights: Option<&[f32]>| {
+            threshold_search(
+                &s_true,
+                &y,
+                &banded,
+                0.4,
+                30.0,
+                1,
+                f64::INFINITY,
+                f64::INFINITY,
+                weights,
+            )
+        };
+        let plain = search(None);
+        let mut weights = vec![1.0_f32; n];
+        weights[110..130].fill(0.0);
+        let weighted = search(Some(&weights));
+
+        assert!(
+            (weighted.alpha - 2.0).abs() < 1e-3,
+            "alpha {}",
+            weighted.alpha
+        );
+        assert!((weighted.baseline - 1.0).abs() < 1e-3);
+        assert!(weighted.pve > 0.999);
+        assert!((plain.baseline - 1.0).abs() > 0.1, "{}", plain.baseline);
+        // Uniform weights reproduce the unweighted search.
+        let uniform = search(Some(&vec![1.0; n]));
+        assert_eq!(uniform.threshold, plain.threshold);
+        assert!((uniform.alpha - plain.alpha).abs() < 1e-9);
+    }
+
     #[test]
     fn pve_high_on_clean_data() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 500;
 
         let mut s_relaxed = vec![0.0_f32; n];
@@ -528,7 +1140,17 @@ mod tests {
         banded.convolve_forward(&s_binary, &mut conv);
         let y: Vec<f32> = conv.iter().map(|&c| 3.0 * c + 1.0).collect();
 
-        let result = threshold_search(&s_relaxed, &y, &banded, 0.4, 30.0, 1, f64::INFINITY);
+        let result = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
         assert!(
             result.pve > 0.9,
             "PVE should be > 0.9 on clean data, got {}",
@@ -538,12 +1160,22 @@ mod tests {
 
     #[test]
     fn alpha_non_negative() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 100;
         let s_relaxed = vec![0.5_f32; n];
         let y = vec![1.0_f32; n];
 
-        let result = threshold_search(&s_relaxed, &y, &banded, 0.4, 30.0, 1, f64::INFINITY);
+        let result = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
         assert!(
             result.alpha >= 0.0,
             "Alpha should be non-negative, got {}",
@@ -553,12 +1185,22 @@ mod tests {
 
     #[test]
     fn empty_spikes_handled() {
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 100;
         let s_relaxed = vec![0.0_f32; n];
         let y = vec![1.0_f32; n];
 
-        let result = threshold_search(&s_relaxed, &y, &banded, 0.4, 30.0, 1, f64::INFINITY);
+        let result = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
         assert_eq!(result.s_binary.iter().sum::<f32>(), 0.0);
     }
 
@@ -569,21 +1211,6 @@ mod tests {
         assert_eq!(boundary_padding(1.0, 10.0), 20);
     }
 
-    /// Deterministic white-ish noise in [-amp, amp) via an LCG (tests must not
-    /// use real RNG). Zero-mean in expectation; variance ≈ amp²/3.
-    fn lcg_noise(n: usize, amp: f32, seed: u64) -> Vec<f32> {
-        let mut state = seed;
-        (0..n)
-            .map(|_| {
-                state = state
-                    .wrapping_mul(6364136223846793005)
-                    .wrapping_add(1442695040888963407);
-                let u = ((state >> 32) as f64) / ((1u64 << 31) as f64) - 1.0;
-                (u as f32) * amp
-            })
-            .collect()
-    }
-
     /// Build a graded relaxed solution + noisy observed trace with three true
     /// spikes whose relaxed values differ (1.0 / 0.85 / 0.7) plus weaker spurious
     /// bumps (0.55), so the budget genuinely controls how many survive.
@@ -620,7 +1247,7 @@ mod tests {
         // never yield more spikes than a tight budget. With a huge budget the
         // sparsest support (highest candidate) is immediately feasible; with a
         // budget just above the noise floor, all three true spikes are required.
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 600;
         let (s_relaxed, y, noise_std) = graded_case(&banded, n);
 
@@ -635,6 +1262,7 @@ mod tests {
             Selection::NoiseFloor {
                 sigma: 1.5 * noise_std,
             },
+            None,
         );
         let loose = threshold_search_opts(
             &s_relaxed,
@@ -645,6 +1273,7 @@ mod tests {
             1,
             f64::INFINITY,
             Selection::NoiseFloor { sigma: 100.0 },
+            None,
         );
 
         let tight_count: f32 = tight.s_binary.iter().sum();
@@ -674,12 +1303,157 @@ mod tests {
         );
     }
 
+    #[test]
+    fn aic_drops_spikes_that_barely_reduce_error() {
+        // Four full spikes plus seven bumps of 0.45 that the relaxed solution
+        // rates at 0.6. Counting the bumps as spikes lowers the SSE a little,
+        // so MSE keeps them; it does not pay the AIC's 2-per-spike charge.
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 900;
+        let mut s_relaxed = vec![0.0_f32; n];
+        let mut s_true = vec![0.0_f32; n];
+        for &p in &[100usize, 300, 500, 700] {
+            s_relaxed[p] = 1.0;
+            s_true[p] = 1.0;
+        }
+        for p in (150..800).step_by(100) {
+            s_relaxed[p] = 0.6;
+            s_true[p] = 0.45;
+        }
+        let mut conv = vec![0.0_f32; n];
+        banded.convolve_forward(&s_true, &mut conv);
+        let noise = lcg_noise(n, 0.5, 0x5EED);
+        let y: Vec<f32> = conv
+            .iter()
+            .zip(&noise)
+            .map(|(&c, &e)| c + 1.0 + e)
+            .collect();
+
+        let mse = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
+        let aic = threshold_search_aic(&s_relaxed, &y, &banded, 0.4, 30.0);
+        let count = |r: &ThresholdResult| r.s_binary.iter().sum::<f32>();
+        assert_eq!(count(&mse), 11.0);
+        assert_eq!(count(&aic), 4.0);
+        assert!(aic.threshold > mse.threshold && aic.pve < mse.pve);
+    }
+
+    #[test]
+    fn joint_search_shares_one_threshold() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 600;
+        let (s_a, y_a, _) = graded_case(&banded, n);
+        // Second cell: two clean spikes at a different amplitude and baseline.
+        let mut s_b = vec![0.0_f32; n];
+        s_b[200] = 1.0;
+        s_b[400] = 0.9;
+        let mut conv = vec![0.0_f32; n];
+        banded.convolve_forward(
+            &s_b.iter().map(|&v| v.ceil()).collect::<Vec<_>>(),
+            &mut conv,
+        );
+        let y_b: Vec<f32> = conv.iter().map(|&c| 3.0 * c + 0.5).collect();
+
+        let (results, threshold) =
+            joint_threshold_search(&[&s_a, &s_b], &[&y_a, &y_b], &banded, 0.4, 30.0);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.threshold == threshold));
+        assert!((0.5..=0.7).contains(&threshold), "threshold {threshold}");
+        assert_eq!(results[1].s_binary.iter().sum::<f32>(), 2.0);
+        assert!((results[1].alpha - 3.0).abs() < 1e-3);
+        assert!((results[1].baseline - 0.5).abs() < 1e-3);
+
+        // One cell reduces to the plain MSE search.
+        let (single, shared) = joint_threshold_search(&[&s_a], &[&y_a], &banded, 0.4, 30.0);
+        let plain = threshold_search(
+            &s_a,
+            &y_a,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            f64::INFINITY,
+            None,
+        );
+        assert_eq!(shared, plain.threshold);
+        assert_eq!(single[0].s_binary, plain.s_binary);
+
+        assert!(joint_threshold_search(&[], &[], &banded, 0.4, 30.0)
+            .0
+            .is_empty());
+        let zeros = vec![0.0_f32; n];
+        let (flat, _) = joint_threshold_search(&[&zeros], &[&y_b], &banded, 0.4, 30.0);
+        assert_eq!(flat[0].s_binary, zeros);
+    }
+
+    #[test]
+    fn custom_grid_and_threshold_cap() {
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 600;
+        let (s_relaxed, y, _) = graded_case(&banded, n);
+
+        let grid = [0.95, f64::NAN, 0.65, 0.4];
+        let custom = threshold_search_custom_grid(&s_relaxed, &y, &banded, 0.4, 30.0, &grid);
+        let single =
+            |t: f64| threshold_search_custom_grid(&s_relaxed, &y, &banded, 0.4, 30.0, &[t]);
+        let best = [0.95, 0.65, 0.4]
+            .into_iter()
+            .map(single)
+            .min_by(|a, b| a.error.total_cmp(&b.error))
+            .unwrap();
+        assert_eq!(custom.threshold, best.threshold);
+        assert_eq!(custom.error, best.error);
+        // 0.65 keeps exactly the three true spikes.
+        assert_eq!(single(0.65).s_binary.iter().sum::<f32>(), 3.0);
+        assert_eq!(
+            threshold_search_custom_grid(&s_relaxed, &y, &banded, 0.4, 30.0, &[]).s_binary,
+            vec![0.0; n]
+        );
+
+        let capped = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            0.6,
+            None,
+        );
+        assert!(capped.threshold <= 0.6, "threshold {}", capped.threshold);
+        // A cap below every value (and below the 0.5 floor) keeps them all.
+        let all = threshold_search(
+            &s_relaxed,
+            &y,
+            &banded,
+            0.4,
+            30.0,
+            1,
+            f64::INFINITY,
+            0.3,
+            None,
+        );
+        assert!((all.threshold - 0.3).abs() < 1e-6);
+        assert_eq!(all.s_binary.iter().sum::<f32>(), 8.0);
+    }
+
     #[test]
     fn noise_floor_falls_back_when_infeasible() {
         // sigma → 0 makes the budget unreachable, so no threshold is feasible and
         // the scan must fall back to the minimum-residual threshold — which on
         // this signal recovers the true spikes rather than returning garbage.
-        let banded = BandedAR2::new(0.02, 0.4, 30.0);
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
         let n = 600;
         let (s_relaxed, y, _) = graded_case(&banded, n);
 
@@ -692,6 +1466,7 @@ mod tests {
             1,
             f64::INFINITY,
             Selection::NoiseFloor { sigma: 1e-9 },
+            None,
         );
         assert!(result.threshold.is_finite());
         let count: f32 = result.s_binary.iter().sum();
@@ -706,4 +1481,75 @@ mod tests {
             result.pve
         );
     }
+
+    #[test]
+    fn zero_weights_exclude_samples_from_fit() {
+        // Corrupt a stretch of samples; with zero weight there the fit must
+        // recover the clean alpha/baseline exactly as if they were absent.
+        let banded = BandedAR2::new(0.02, 0.4, 30.0).unwrap();
+        let n = 300;
+        let mut s_true = vec![0.0_f32; n];
+        s_true[50] = 1.0;
+        s_true[150] = 1.0;
+        let mut conv = vec![0.0_f32; n];
+        banded.convolve_forward(&s_true, &mut conv);
+
+        let mut y: Vec<f32> = conv.iter().map(|&c| 4.0 * c + 1.0).collect();
+        let mut w = vec![1.0_f32; n];
+        for i in 152..157 {
+            y[i] = 2.0;
+            w[i] = 0.0;
+        }
+
+        let (a_w, b_w) = lstsq_alpha_baseline_weighted(&conv, &y, &w, 0, f64::INFINITY);
+        assert!((a_w - 4.0).abs() < 1e-3, "weighted alpha {}", a_w);
+        assert!((b_w - 1.0).abs() < 1e-3, "weighted baseline {}", b_w);
+
+        let (a_u, _) = lstsq_alpha_baseline(&conv, &y, 0, f64::INFINITY);
+        assert!(
+            a_u < a_w,
+            "clipped samples should bias unweighted alpha down"
+        );
+    }
+
+    #[test]
+    fn roc_curve_separates_and_integrates() {
+        let n = 300;
+        let mut truth = vec![0.0_f32; n];
+        let mut relaxed = vec![0.0_f32; n];
+        for (k, &i) in [60, 110, 170, 230].iter().enumerate() {
+            truth[i] = 1.0;
+            relaxed[i] = 0.6 + 0.1 * k as f32;
+            relaxed[i + 1] = 0.2; // halo below every true spike
+        }
+
+        let roc = roc_threshold_curve(&relaxed, &truth, 0.4, 30.0, 50);
+        assert!(roc.warning.is_none());
+        assert_eq!(roc.thresholds.len(), 51);
+        assert_eq!(roc.thresholds[0], f64::INFINITY);
+        assert_eq!((roc.fpr[0], roc.tpr[0]), (0.0, 0.0));
+        assert_eq!(
+            (*roc.fpr.last().unwrap(), *roc.tpr.last().unwrap()),
+            (1.0, 1.0)
+        );
+        assert!(roc.fpr.windows(2).all(|w| w[1] >= w[0]));
+        assert!(roc.tpr.windows(2).all(|w| w[1] >= w[0]));
+        assert!((roc.auc - 1.0).abs() < 1e-12, "auc {}", roc.auc);
+
+        // Scoring the halo instead of the spikes inverts the ranking.
+        let shifted: Vec<f32> = (0..n)
+            .map(|i| if i > 0 { truth[i - 1] } else { 0.0 })
+            .collect();
+        let inverted = roc_threshold_curve(&shifted, &truth, 0.4, 30.0, 10);
+        assert!(inverted.auc < 0.5, "auc {}", inverted.auc);
+    }
+
+    #[test]
+    fn roc_curve_without_positives_reports_half() {
+        let relaxed: Vec<f32> = (0..200).map(|i| (i % 7) as f32 / 7.0).collect();
+        let roc = roc_threshold_curve(&relaxed, &[0.0; 200], 0.4, 30.0, 20);
+        assert_eq!(roc.auc, 0.5);
+        assert!(roc.warning.as_deref().unwrap().contains("no positive"));
+        assert_eq!(roc.fpr.len(), roc.thresholds.len());
+    }
 }
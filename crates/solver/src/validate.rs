@@ -0,0 +1,240 @@
+//! Parameter sanity checks with diagnostic warnings.
+//!
+//! `validate_solver_params` flags parameter combinations that are invalid
+//! (`Severity::Error`) or that run but are likely to give a poor solve
+//! (`Severity::Warning`): a rise time the frame rate cannot resolve, a penalty
+//! large enough to zero the whole solution, or a very low frame rate.
+//! `Solver::set_params` runs the same checks and keeps the result available
+//! through `Solver::param_diagnostics`.
+
+use crate::kernel::{build_kernel, compute_lipschitz};
+
+/// Frame rates below this are flagged as too coarse for kernel estimation.
+const MIN_RECOMMENDED_FS: f64 = 5.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One finding about a parameter set.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Name of the offending parameter.
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(field: &'static str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            field,
+            message,
+        }
+    }
+
+    pub(crate) fn warning(field: &'static str, message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            field,
+            message,
+        }
+    }
+}
+
+/// Check solver parameters; an empty result means nothing was flagged.
+///
+/// `tau_rise = 0` (single-exponential sentinel) is accepted and skips the
+/// rise-time aliasing check.
+pub fn validate_solver_params(
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda: f64,
+    fs: f64,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = check_basic(tau_rise, tau_decay, lambda, fs);
+    if !has_errors(&diagnostics) {
+        let lipschitz = compute_lipschitz(&build_kernel(tau_rise, tau_decay, fs));
+        diagnostics.extend(check_lambda(lambda, lipschitz));
+    }
+    diagnostics
+}
+
+/// Same as [`validate_solver_params`], reusing an already computed Lipschitz
+/// constant (`Solver::set_params` has one on hand).
+pub(crate) fn validate_with_lipschitz(
+    tau_rise: f64,
+    tau_decay: f64,
+    lambda: f64,
+    fs: f64,
+    lipschitz: f64,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = check_basic(tau_rise, tau_decay, lambda, fs);
+    if !has_errors(&diagnostics) {
+        diagnostics.extend(check_lambda(lambda, lipschitz));
+    }
+    diagnostics
+}
+
+/// Warn when a fixed kernel length cuts the decay off early: fewer than
+/// `ceil(3 * tau_decay * fs)` frames keeps less than ~95% of the decay.
+pub(crate) fn check_kernel_length(len: usize, tau_decay: f64, fs: f64) -> Option<Diagnostic> {
+    let min_len = (3.0 * tau_decay * fs).ceil();
+    ((len as f64) < min_len).then(|| {
+        Diagnostic::warning(
+            "kernel_length",
+            format!(
+                "kernel of {len} frames is shorter than 3 decay time constants \
+                 ({min_len} frames at tau_decay = {tau_decay} s, {fs} Hz); the tail is truncated"
+            ),
+        )
+    })
+}
+
+pub(crate) fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+fn check_basic(tau_rise: f64, tau_decay: f64, lambda: f64, fs: f64) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    if !(fs > 0.0 && fs.is_finite()) {
+        out.push(Diagnostic::error(
+            "fs",
+            format!("sampling rate must be positive and finite, got {fs}"),
+        ));
+    }
+    if !(tau_decay > 0.0 && tau_decay.is_finite()) {
+        out.push(Diagnostic::error(
+            "tau_decay",
+            format!("tau_decay must be positive and finite, got {tau_decay}"),
+        ));
+    }
+    if !(tau_rise >= 0.0 && tau_rise.is_finite()) {
+        out.push(Diagnostic::error(
+            "tau_rise",
+            format!("tau_rise must be non-negative and finite, got {tau_rise}"),
+        ));
+    } else if tau_rise >= tau_decay {
+        out.push(Diagnostic::error(
+            "tau_rise",
+            format!("tau_rise ({tau_rise} s) must be shorter than tau_decay ({tau_decay} s)"),
+        ));
+    }
+    if !(lambda >= 0.0 && lambda.is_finite()) {
+        out.push(Diagnostic::error(
+            "lambda",
+            format!("lambda must be non-negative and finite, got {lambda}"),
+        ));
+    }
+    if has_errors(&out) {
+        return out;
+    }
+
+    if tau_rise > 0.0 && 1.0 / (2.0 * tau_rise) > fs / 2.0 {
+        out.push(Diagnostic::warning(
+            "tau_rise",
+            format!(
+                "rise time {tau_rise} s is faster than {fs} Hz sampling can resolve \
+                 (1/(2·tau_rise) = {:.1} Hz > Nyquist {:.1} Hz); the rise will alias",
+                1.0 / (2.0 * tau_rise),
+                fs / 2.0
+            ),
+        ));
+    }
+    if fs < MIN_RECOMMENDED_FS {
+        out.push(Diagnostic::warning(
+            "fs",
+            format!("very low frame rate ({fs} Hz); spike timing will be coarse"),
+        ));
+    }
+    out
+}
+
+fn check_lambda(lambda: f64, lipschitz: f64) -> Option<Diagnostic> {
+    (lambda > lipschitz / 2.0).then(|| {
+        Diagnostic::warning(
+            "lambda",
+            format!(
+                "lambda {lambda} exceeds half the Lipschitz constant ({:.4}); \
+                 the solution is likely all zero",
+                lipschitz / 2.0
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(d: &[Diagnostic], severity: Severity) -> Vec<&'static str> {
+        d.iter()
+            .filter(|d| d.severity == severity)
+            .map(|d| d.field)
+            .collect()
+    }
+
+    #[test]
+    fn flags_each_check() {
+        // Slow rise, moderate lambda, 30 Hz: nothing to report.
+        assert!(validate_solver_params(0.1, 0.6, 0.01, 30.0).is_empty());
+
+        let d = validate_solver_params(0.5, 0.4, 0.01, 30.0);
+        assert_eq!(fields(&d, Severity::Error), ["tau_rise"]);
+
+        let d = validate_solver_params(0.02, 0.4, 0.01, 30.0);
+        assert_eq!(fields(&d, Severity::Warning), ["tau_rise"]);
+
+        let d = validate_solver_params(0.0, 0.4, 1e6, 30.0);
+        assert_eq!(fields(&d, Severity::Warning), ["lambda"]);
+
+        let d = validate_solver_params(0.5, 2.0, 0.0, 2.0);
+        assert_eq!(fields(&d, Severity::Warning), ["fs"]);
+    }
+
+    #[test]
+    fn set_params_records_diagnostics() {
+        let mut solver = crate::Solver::new();
+        solver.set_params(0.1, 0.6, 1e6, 30.0);
+        assert_eq!(
+            fields(solver.param_diagnostics(), Severity::Warning),
+            ["lambda"]
+        );
+        solver.set_params(0.1, 0.6, 0.01, 30.0);
+        assert!(solver.param_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn fixed_kernel_length_is_applied_and_checked() {
+        use crate::{KernelLengthMode, Solver};
+
+        let mut solver = Solver::new();
+        solver.set_kernel_length_mode(KernelLengthMode::FixedFrames(20));
+        solver.set_params(0.1, 0.6, 0.01, 30.0);
+        assert_eq!(solver.get_kernel().len(), 20);
+        // ceil(3 * 0.6 * 30) = 54 frames.
+        assert_eq!(
+            fields(solver.param_diagnostics(), Severity::Warning),
+            ["kernel_length"]
+        );
+
+        solver.set_kernel_length_mode(KernelLengthMode::FixedSeconds(3.0));
+        solver.set_params(0.1, 0.6, 0.01, 30.0);
+        let kernel = solver.get_kernel();
+        assert_eq!(kernel.len(), 90);
+        assert!(solver.param_diagnostics().is_empty());
+        // 3 s is within the auto length, so this is the auto kernel's prefix.
+        let auto = crate::kernel::build_kernel(0.1, 0.6, 30.0);
+        assert_eq!(kernel[..], auto[..90]);
+
+        solver.set_kernel_length_mode(KernelLengthMode::Auto);
+        solver.set_params(0.1, 0.6, 0.01, 30.0);
+        assert_eq!(
+            solver.get_kernel().len(),
+            crate::suggest_kernel_length(0.6, 30.0)
+        );
+    }
+}
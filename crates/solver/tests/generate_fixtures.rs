@@ -4,6 +4,9 @@
 //!
 //! Outputs JSON fixtures to `../../python/tests/fixtures/`.
 
+// Needs the heap-allocating `Solver`, which only exists with `std`.
+#![cfg(feature = "std")]
+
 use calab_solver::Solver;
 use serde::Serialize;
 use std::fs;
@@ -0,0 +1,199 @@
+// Typed declarations for the InDeCa exports in src/js_indeca.rs.
+//
+// Embedded into the generated pkg/calab_solver.d.ts through a
+// `typescript_custom_section`. Functions returning serialized structs are
+// marked `skip_typescript` on the Rust side and declared here instead, so keep
+// each signature in sync with its `#[wasm_bindgen]` function.
+
+/** Serialized `indeca::InDecaResult`. */
+export interface InDecaResult {
+  /** Spike counts at the original sampling rate. */
+  s_counts: number[];
+  /** Filtered trace at the original rate; absent when no filter ran. */
+  filtered_trace?: number[];
+  alpha: number;
+  baseline: number;
+  threshold: number;
+  /** Proportion of variance explained by the fit. */
+  pve: number;
+  iterations: number;
+  converged: boolean;
+  n_saturated_frames: number;
+  noise_std: number;
+  /** Fit SNR in dB (reconvolved-spike power over residual power). */
+  snr_db: number;
+}
+
+/** Outcome classification of a bi-exponential fit. */
+export type FitMode = 'TwoComponent' | 'SlowOnly' | 'Degenerate' | 'Empty';
+
+/** Serialized `biexp_fit::BiexpResult` (time constants in seconds). */
+export interface BiexpResult {
+  tau_rise: number;
+  tau_decay: number;
+  beta: number;
+  residual: number;
+  tau_rise_fast: number;
+  tau_decay_fast: number;
+  beta_fast: number;
+  fit_mode: FitMode;
+}
+
+/** Serialized `peak_seed::SeedTraceResult`. */
+export interface SeedTraceResult {
+  s_counts: number[];
+  alpha: number;
+  baseline: number;
+}
+
+/** Serialized `threshold::ThresholdResult`. */
+export interface ThresholdResult {
+  /** Binary spike train on the input grid. */
+  s_binary: number[];
+  alpha: number;
+  baseline: number;
+  threshold: number;
+  /** Proportion of variance explained by the fit. */
+  pve: number;
+  /** Residual of the selected fit. */
+  error: number;
+}
+
+/** Kernel time constants of an indicator preset, in seconds. */
+export interface PresetParams {
+  tau_rise: number;
+  tau_decay: number;
+}
+
+/** Contents of a `.npy` file, flattened in row-major order. */
+export interface NpyArray {
+  data: number[];
+  shape: number[];
+}
+
+/** Argument of the `indeca_solve_trace` progress callback. */
+export interface InDecaProgress {
+  /** Scale iteration just finished, counting from 0. */
+  iter: number;
+  pve: number;
+  alpha: number;
+  fista_iters: number;
+}
+
+export function indeca_solve_trace(
+  trace: Float32Array,
+  tau_r: number,
+  tau_d: number,
+  fs: number,
+  upsample_factor: number,
+  max_iters: number,
+  tol: number,
+  hp_enabled: boolean,
+  lp_enabled: boolean,
+  warm_counts: Float32Array,
+  lambda: number,
+  noise_constrained: boolean,
+  cancel_token_id?: number | null,
+  on_progress?: ((progress: InDecaProgress) => void) | null,
+): InDecaResult;
+
+export function init_thread_pool(n_threads: number): Promise<void>;
+
+export function indeca_solve_trace_threaded(
+  trace: Float32Array,
+  tau_r: number,
+  tau_d: number,
+  fs: number,
+  upsample_factor: number,
+  max_iters: number,
+  tol: number,
+  hp_enabled: boolean,
+  lp_enabled: boolean,
+  warm_counts: Float32Array,
+  lambda: number,
+  noise_constrained: boolean,
+  cancel_token_id?: number | null,
+): Promise<InDecaResult>;
+
+/**
+ * `indeca_solve_trace` stopped by an `AbortSignal`; resolves (never rejects on
+ * abort) with the best result so far.
+ *
+ * The solve runs synchronously and does not yield to the event loop between
+ * scale iterations, so `controller.abort()` from a timer, message or UI
+ * handler only lands after the solve has finished. To abort mid-solve, call
+ * it from `on_progress` (or throw there), which runs after every scale
+ * iteration.
+ */
+export function indeca_solve_trace_abortable(
+  trace: Float32Array,
+  tau_r: number,
+  tau_d: number,
+  fs: number,
+  upsample_factor: number,
+  max_iters: number,
+  tol: number,
+  hp_enabled: boolean,
+  lp_enabled: boolean,
+  warm_counts: Float32Array,
+  lambda: number,
+  noise_constrained: boolean,
+  signal: AbortSignal,
+  on_progress?: ((progress: InDecaProgress) => void) | null,
+): Promise<InDecaResult>;
+
+export function indeca_solve_batch(
+  traces_flat: Float32Array,
+  trace_lengths: Uint32Array,
+  tau_r: number,
+  tau_d: number,
+  fs: number,
+  upsample_factor: number,
+  max_iters: number,
+  tol: number,
+  hp_enabled: boolean,
+  lp_enabled: boolean,
+): InDecaResult[];
+
+export function indeca_fit_biexponential(
+  h_free: Float32Array,
+  fs: number,
+  refine: boolean,
+  skip: number,
+  warm_tau_rise: number,
+  warm_tau_decay: number,
+  warm_tau_rise_fast: number,
+  warm_tau_decay_fast: number,
+  warm_beta: number,
+  warm_beta_fast: number,
+  warm_residual: number,
+  use_warm: boolean,
+): BiexpResult;
+
+export function indeca_fit_biexponential_bounded(
+  h_free: Float32Array,
+  fs: number,
+  tau_r_min: number,
+  tau_r_max: number,
+  tau_d_min: number,
+  tau_d_max: number,
+  refine: boolean,
+): BiexpResult;
+
+export function indeca_threshold_search(
+  s_relaxed: Float32Array,
+  y: Float32Array,
+  tau_rise: number,
+  tau_decay: number,
+  fs: number,
+  weights?: Float32Array | null,
+): ThresholdResult;
+
+export function seed_trace(trace: Float32Array, fs: number): SeedTraceResult;
+
+export function indeca_preset_params(name: string, fs: number): PresetParams;
+
+export function indeca_read_npy(bytes: Uint8Array): NpyArray;
+
+/** Seed the global random stream of the stochastic operations (u64, so a bigint). */
+export function indeca_set_seed(seed: bigint): void;
@@ -1,23 +1,81 @@
 """CaLab: calcium imaging analysis tools — deconvolution and data preparation."""
 
+from ._async import async_deconvolve_batch, async_deconvolve_single, async_solve_trace
 from ._bridge import DeconConfig, HeadlessBrowser, decon, tune
 from ._compute import (
     BiexpFitResult,
     CaDeconResult,
+    CancelToken,
     DeconvolutionResult,
+    InDeCa,
+    RocCurve,
     SolveTraceResult,
+    anscombe_inverse_transform,
+    anscombe_transform,
+    ar2_frequency_response,
+    ar_model_from_taus,
     bandpass_filter,
+    bootstrap_spike_ci,
     build_kernel,
+    burst_rate,
+    cell_pair_correlations,
+    check_adjoint,
+    check_ar2_stability,
+    compute_dff_global,
+    compute_dff_rolling,
     compute_lipschitz,
+    compute_lipschitz_banded,
     compute_upsample_factor,
+    detect_bursts,
     estimate_kernel,
+    estimate_noise_floor,
+    event_aligned_average,
+    event_aligned_raster,
+    exponential_smooth,
+    fit_ar2_from_acf,
     fit_biexponential,
+    isi_cv,
+    isi_histogram,
+    isi_mean,
+    joint_threshold_search,
+    lambda_sensitivity,
+    lambda_sensitivity_arrays,
+    list_presets,
+    median_filter,
+    normalize_dff,
+    pairwise_correlation_index,
+    preset_params,
+    roc_threshold_curve,
+    robust_zscore,
+    rolling_std,
+    rolling_variance,
     run_deconvolution,
     run_deconvolution_full,
+    savitzky_golay,
+    select_lambda_cv,
+    set_seed,
+    solve_path,
     solve_trace,
+    spike_isis,
+    spike_triggered_average,
+    suggest_kernel_length,
     tau_to_ar2,
+    threshold_search,
+    threshold_search_custom_grid,
+    validate_params,
+    zscore,
+)
+from ._io import (
+    deconvolve_from_export,
+    estimate_neuropil_coeff,
+    load_export_params,
+    load_tuning_data,
+    neuropil_correct,
+    read_csv_traces,
+    read_npy,
+    read_suite2p,
+    save_for_tuning,
 )
-from ._io import deconvolve_from_export, load_export_params, load_tuning_data, save_for_tuning
 from ._loaders import load_caiman, load_minian
 from ._simulate import (
     CellGroundTruth,
@@ -41,6 +99,10 @@ from importlib.metadata import version as _pkg_version
 
 __version__ = _pkg_version("calab")
 __all__ = [
+    # Async
+    "async_deconvolve_batch",
+    "async_deconvolve_single",
+    "async_solve_trace",
     # Bridge
     "DeconConfig",
     "HeadlessBrowser",
@@ -49,22 +111,74 @@ __all__ = [
     # Compute
     "BiexpFitResult",
     "CaDeconResult",
+    "CancelToken",
     "DeconvolutionResult",
+    "InDeCa",
+    "RocCurve",
     "SolveTraceResult",
+    "anscombe_inverse_transform",
+    "anscombe_transform",
+    "ar2_frequency_response",
+    "ar_model_from_taus",
     "bandpass_filter",
+    "bootstrap_spike_ci",
     "build_kernel",
+    "burst_rate",
+    "cell_pair_correlations",
+    "check_adjoint",
+    "check_ar2_stability",
+    "compute_dff_global",
+    "compute_dff_rolling",
     "compute_lipschitz",
+    "compute_lipschitz_banded",
     "compute_upsample_factor",
+    "detect_bursts",
     "estimate_kernel",
+    "estimate_noise_floor",
+    "event_aligned_average",
+    "event_aligned_raster",
+    "exponential_smooth",
+    "fit_ar2_from_acf",
     "fit_biexponential",
+    "isi_cv",
+    "isi_histogram",
+    "isi_mean",
+    "joint_threshold_search",
+    "lambda_sensitivity",
+    "lambda_sensitivity_arrays",
+    "list_presets",
+    "median_filter",
+    "normalize_dff",
+    "pairwise_correlation_index",
+    "preset_params",
+    "roc_threshold_curve",
+    "robust_zscore",
+    "rolling_std",
+    "rolling_variance",
     "run_deconvolution",
     "run_deconvolution_full",
+    "savitzky_golay",
+    "select_lambda_cv",
+    "set_seed",
+    "solve_path",
     "solve_trace",
+    "spike_isis",
+    "spike_triggered_average",
+    "suggest_kernel_length",
     "tau_to_ar2",
+    "threshold_search",
+    "threshold_search_custom_grid",
+    "validate_params",
+    "zscore",
     # I/O
     "deconvolve_from_export",
+    "estimate_neuropil_coeff",
     "load_export_params",
     "load_tuning_data",
+    "neuropil_correct",
+    "read_csv_traces",
+    "read_npy",
+    "read_suite2p",
     "save_for_tuning",
     # Loaders
     "load_caiman",
@@ -0,0 +1,162 @@
+"""Awaitable wrappers around the Rust solver for asyncio code (e.g. Jupyter).
+
+Called from inside a running event loop, each function returns an awaitable.
+The solve itself runs in Rust on a shared Tokio blocking pool
+(``pyo3-async-runtimes``) with the GIL released, and resolves as an asyncio
+future on the awaiting loop, which stays responsive meanwhile. Called from
+synchronous code, each function falls back to the blocking call and emits a
+``DeprecationWarning``.
+"""
+
+from __future__ import annotations
+
+import asyncio
+import warnings
+from collections.abc import Awaitable, Callable
+from typing import Any, TypeVar
+
+import numpy as np
+
+from ._compute import (
+    DeconvolutionResult,
+    SolveTraceResult,
+    _batch_full_result,
+    _single_full_result,
+    _solve_trace_result,
+    run_deconvolution_full,
+    solve_trace,
+)
+from ._solver import (
+    async_deconvolve_batch as _async_deconvolve_batch,
+    async_deconvolve_single as _async_deconvolve_single,
+    async_solve_trace as _async_solve_trace,
+    deconvolve_single as _deconvolve_single,
+)
+
+T = TypeVar("T")
+
+
+def _dispatch(
+    name: str,
+    blocking: Callable[..., T],
+    awaitable: Callable[..., Awaitable[T]],
+    *args: Any,
+    **kwargs: Any,
+) -> Awaitable[T] | T:
+    """Start ``awaitable`` on the running loop, or run ``blocking`` without one."""
+    try:
+        asyncio.get_running_loop()
+    except RuntimeError:
+        warnings.warn(
+            f"{name} was called outside a running event loop and ran "
+            "synchronously; call the blocking function instead.",
+            DeprecationWarning,
+            stacklevel=3,
+        )
+        return blocking(*args, **kwargs)
+    return awaitable(*args, **kwargs)
+
+
+async def _await_batch(
+    traces: np.ndarray, fs: float, tau_r: float, tau_d: float, lam: float, **kwargs: Any
+) -> DeconvolutionResult:
+    if traces.ndim == 1:
+        kwargs.pop("n_jobs", None)  # a single trace is not split across workers
+        trace_1d = np.ascontiguousarray(traces)
+        single = await _async_deconvolve_single(trace_1d, fs, tau_r, tau_d, lam, **kwargs)
+        return _single_full_result(single)
+    traces_2d = np.ascontiguousarray(np.atleast_2d(traces))
+    batch = await _async_deconvolve_batch(traces_2d, fs, tau_r, tau_d, lam, **kwargs)
+    return _batch_full_result(batch)
+
+
+async def _await_solve_trace(
+    trace_1d: np.ndarray, tau_rise: float, tau_decay: float, fs: float, **kwargs: Any
+) -> SolveTraceResult:
+    warm_counts = kwargs.pop("warm_counts", None)
+    if warm_counts is not None:
+        warm_counts = np.ascontiguousarray(warm_counts, dtype=np.float64)
+    raw = await _async_solve_trace(
+        trace_1d, tau_rise, tau_decay, fs, warm_counts=warm_counts, **kwargs
+    )
+    return _solve_trace_result(raw)
+
+
+def async_deconvolve_single(
+    trace: np.ndarray,
+    fs: float,
+    tau_rise: float,
+    tau_decay: float,
+    lambda_: float,
+    **kwargs: Any,
+) -> Awaitable[Any] | Any:
+    """Awaitable ``deconvolve_single``; resolves to a ``PyInDecaResult``.
+
+    Keyword arguments (``hp_enabled``, ``lp_enabled``, ``max_iters``,
+    ``conv_mode``, ``constraint``) are passed through unchanged.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return _dispatch(
+        "async_deconvolve_single",
+        _deconvolve_single,
+        _async_deconvolve_single,
+        trace_1d,
+        fs,
+        tau_rise,
+        tau_decay,
+        lambda_,
+        **kwargs,
+    )
+
+
+def async_deconvolve_batch(
+    traces: np.ndarray,
+    fs: float,
+    tau_r: float,
+    tau_d: float,
+    lam: float,
+    **kwargs: Any,
+) -> Awaitable[DeconvolutionResult] | DeconvolutionResult:
+    """Awaitable :func:`calab.run_deconvolution_full` for one or many traces.
+
+    Keyword arguments (``max_iters``, ``conv_mode``, ``constraint``,
+    ``n_jobs``) are passed through unchanged.
+    """
+    traces = np.asarray(traces, dtype=np.float64)
+    return _dispatch(
+        "async_deconvolve_batch",
+        run_deconvolution_full,
+        _await_batch,
+        traces,
+        fs,
+        tau_r,
+        tau_d,
+        lam,
+        **kwargs,
+    )
+
+
+def async_solve_trace(
+    trace: np.ndarray,
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+    **kwargs: Any,
+) -> Awaitable[SolveTraceResult] | SolveTraceResult:
+    """Awaitable :func:`calab.solve_trace` (InDeCa single-trace solve).
+
+    Keyword arguments are those of :func:`calab.solve_trace`, including
+    ``on_progress`` and ``cancel_token``. A progress callback runs on a
+    solver thread, not the event loop.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return _dispatch(
+        "async_solve_trace",
+        solve_trace,
+        _await_solve_trace,
+        trace_1d,
+        tau_rise,
+        tau_decay,
+        fs,
+        **kwargs,
+    )
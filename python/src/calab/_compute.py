@@ -6,20 +6,66 @@ build_kernel, etc.) but delegates to the native Rust solver via calab._solver.
 
 from __future__ import annotations
 
-from typing import NamedTuple
+import warnings
+from typing import Any, Callable, NamedTuple
 
 import numpy as np
 
 from ._solver import (
+    PyCancelToken,
+    PyInDeCa,
     PySolver,
     deconvolve_batch as _deconvolve_batch,
+    deconvolve_batch_parallel as _deconvolve_batch_parallel,
     deconvolve_single as _deconvolve_single,
     py_build_kernel as _build_kernel,
+    py_suggest_kernel_length as _suggest_kernel_length,
+    py_ar_model_from_taus as _ar_model_from_taus,
+    py_check_ar2_stability as _check_ar2_stability,
+    py_ar2_frequency_response as _ar2_frequency_response,
+    py_fit_ar2_from_acf as _fit_ar2_from_acf,
+    py_check_adjoint as _check_adjoint,
     py_compute_lipschitz as _compute_lipschitz,
+    py_compute_lipschitz_banded as _compute_lipschitz_banded,
+    py_list_presets as _list_presets,
+    py_preset_params as _preset_params,
+    py_savitzky_golay as _savitzky_golay,
+    py_exponential_smooth as _exponential_smooth,
+    py_median_filter as _median_filter,
+    py_rolling_variance as _rolling_variance,
+    py_rolling_std as _rolling_std,
+    py_estimate_noise_floor as _estimate_noise_floor,
+    py_anscombe_transform as _anscombe_transform,
+    py_anscombe_inverse_transform as _anscombe_inverse_transform,
+    py_compute_dff_rolling as _compute_dff_rolling,
+    py_compute_dff_global as _compute_dff_global,
+    py_validate_params as _validate_params,
+    py_solve_path as _solve_path,
+    py_lambda_sensitivity_arrays as _lambda_sensitivity_arrays,
     py_indeca_solve_trace as _indeca_solve_trace,
+    py_bootstrap_spike_ci as _bootstrap_spike_ci,
+    py_set_seed as _set_seed,
+    py_roc_threshold_curve as _roc_threshold_curve,
+    py_threshold_search as _threshold_search,
+    py_threshold_search_custom_grid as _threshold_search_custom_grid,
+    py_joint_threshold_search as _joint_threshold_search,
     py_indeca_estimate_kernel as _indeca_estimate_kernel,
     py_indeca_fit_biexponential as _indeca_fit_biexponential,
     py_indeca_compute_upsample_factor as _indeca_compute_upsample_factor,
+    py_spike_isis as _spike_isis,
+    py_isi_mean as _isi_mean,
+    py_isi_cv as _isi_cv,
+    py_isi_histogram as _isi_histogram,
+    py_detect_bursts as _detect_bursts,
+    py_zscore as _zscore,
+    py_robust_zscore as _robust_zscore,
+    py_normalize_dff as _normalize_dff,
+    py_event_aligned_average as _event_aligned_average,
+    py_event_aligned_raster as _event_aligned_raster,
+    py_spike_triggered_average as _spike_triggered_average,
+    py_pairwise_correlation_index as _pairwise_correlation_index,
+    py_cell_pair_correlations as _cell_pair_correlations,
+    py_select_lambda_cv as _select_lambda_cv,
 )
 
 
@@ -97,11 +143,84 @@ def build_kernel(tau_rise: float, tau_decay: float, fs: float) -> np.ndarray:
     return np.asarray(_build_kernel(tau_rise, tau_decay, fs))
 
 
+def suggest_kernel_length(tau_decay: float, fs: float) -> int:
+    """Default kernel length in samples, as used by ``build_kernel``. Delegates to Rust."""
+    return _suggest_kernel_length(tau_decay, fs)
+
+
 def compute_lipschitz(kernel: np.ndarray) -> float:
     """Compute Lipschitz constant. Delegates to Rust."""
     return _compute_lipschitz(np.ascontiguousarray(kernel, dtype=np.float32))
 
 
+def compute_lipschitz_banded(tau_rise: float, tau_decay: float, fs: float) -> float:
+    """Lipschitz constant the solver uses in ``"banded"`` conv mode. Delegates to Rust.
+
+    Compare with ``compute_lipschitz(build_kernel(...))`` for the FFT-mode value.
+    """
+    return _compute_lipschitz_banded(tau_rise, tau_decay, fs)
+
+
+def check_ar2_stability(tau_rise: float, tau_decay: float, fs: float) -> tuple[bool, float]:
+    """Whether the banded AR(2) model for these taus is stable. Delegates to Rust.
+
+    Returns ``(is_stable, margin)`` with ``margin = min(1 - |d|, 1 - |r|)``
+    over the decay and rise roots; non-positive taus or ``fs`` give an
+    unstable model, which the solver rejects.
+    """
+    return _check_ar2_stability(tau_rise, tau_decay, fs)
+
+
+def ar2_frequency_response(
+    tau_rise: float, tau_decay: float, fs: float, freqs_hz: np.ndarray
+) -> np.ndarray:
+    """Power response of the AR(2) model at each frequency. Delegates to Rust.
+
+    Returns ``|H(exp(2j * pi * f / fs))|**2`` for ``H(z) = 1 / (1 - g1 z^-1 -
+    g2 z^-2)``, float64, unnormalized (``(1 / (1 - g1 - g2))**2`` at DC). The
+    model is low-pass; divide by the DC value to read off attenuation. Raises
+    ``RuntimeError`` for an unstable tau set.
+    """
+    freqs = np.ascontiguousarray(np.atleast_1d(freqs_hz), dtype=np.float64)
+    return np.asarray(_ar2_frequency_response(tau_rise, tau_decay, fs, freqs))
+
+
+def ar_model_from_taus(taus: list[float], fs: float) -> np.ndarray:
+    """Unit-peak impulse response of the AR(p) model with one root per time constant.
+
+    Two taus reproduce ``build_kernel(min(taus), max(taus), fs)``. Delegates to Rust.
+    """
+    return np.asarray(_ar_model_from_taus([float(t) for t in taus], fs))
+
+
+def fit_ar2_from_acf(acf: np.ndarray, fs: float) -> tuple[float, float]:
+    """Estimate ``(tau_rise, tau_decay)`` from an autocorrelation by AR(2) Yule-Walker.
+
+    ``acf`` holds lags 0, 1, 2, ... (only the first three are used). Raises
+    ``ValueError`` when the fitted roots are not a stable rise-then-decay
+    (``tau_decay <= tau_rise``, oscillating or unstable). Delegates to Rust.
+    """
+    return _fit_ar2_from_acf(np.ascontiguousarray(acf, dtype=np.float64), fs)
+
+
+def check_adjoint(
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+    n: int,
+    mode: str = "fft",
+    tol: float = 1e-3,
+    n_pairs: int = 8,
+) -> float:
+    """Dot-product test ``<Kx, y> == <x, K^T y>`` of a convolution engine.
+
+    ``mode`` is ``"fft"``, ``"banded"``, ``"banded_ar1"``, ``"banded_arp"`` or
+    ``"fft_overlap_add"``. Returns the largest relative error over ``n_pairs`` deterministic random pairs of
+    length ``n``; raises ``RuntimeError`` if it exceeds ``tol``.
+    """
+    return _check_adjoint(tau_rise, tau_decay, fs, n, mode, tol, n_pairs)
+
+
 def tau_to_ar2(
     tau_rise: float, tau_decay: float, fs: float,
 ) -> tuple[float, float, float, float]:
@@ -145,6 +264,161 @@ def bandpass_filter(
     return np.asarray(solver.get_trace(), dtype=np.float64)
 
 
+def preset_params(name: str, fs: float) -> tuple[float, float]:
+    """Kernel ``(tau_rise, tau_decay)`` in seconds for an indicator preset.
+
+    Literature time constants are interpolated between the frame rates they
+    were measured at (clamped outside that range). ``name`` is matched
+    case-insensitively against :func:`list_presets`; unknown names raise
+    ``ValueError``.
+    """
+    tau_rise, tau_decay = _preset_params(name, fs)
+    return float(tau_rise), float(tau_decay)
+
+
+def list_presets() -> list[str]:
+    """Names of the built-in indicator presets (e.g. ``"GCaMP6f"``)."""
+    return list(_list_presets())
+
+
+def validate_params(
+    tau_rise: float, tau_decay: float, lambda_: float, fs: float,
+) -> list[dict[str, str]]:
+    """Check solver parameters before a solve. Delegates to Rust.
+
+    Returns a list of ``{"severity", "field", "message"}`` dicts (empty when
+    nothing is flagged). ``severity`` is ``"error"`` for invalid combinations
+    (e.g. ``tau_rise >= tau_decay``) and ``"warning"`` for ones likely to give
+    a poor solve: a rise time faster than the frame rate resolves, a
+    ``lambda_`` above half the Lipschitz constant (all-zero solution), or a
+    frame rate below 5 Hz.
+    """
+    return list(_validate_params(tau_rise, tau_decay, lambda_, fs))
+
+
+def savitzky_golay(trace: np.ndarray, window: int, poly_order: int) -> np.ndarray:
+    """Savitzky-Golay smoothing (local polynomial fit). Delegates to Rust.
+
+    ``window`` must be odd and is clipped to the trace length; polynomials of
+    degree <= ``poly_order`` pass through unchanged. Raises ``ValueError`` if
+    ``poly_order >= window``.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_savitzky_golay(trace_1d, window, poly_order), dtype=np.float64)
+
+
+def exponential_smooth(
+    trace: np.ndarray, alpha: float, *, zero_phase: bool = False
+) -> np.ndarray:
+    """Exponential smoothing ``y[t] = alpha * x[t] + (1 - alpha) * y[t-1]``. Delegates to Rust.
+
+    The state starts at ``trace[0]``, so a constant trace is unchanged. With
+    ``zero_phase=True`` the smoother also runs backward, removing its lag and
+    squaring its power response. Raises ``ValueError`` unless
+    ``0 < alpha <= 1``.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_exponential_smooth(trace_1d, alpha, zero_phase), dtype=np.float64)
+
+
+def median_filter(trace: np.ndarray, half_window: int) -> np.ndarray:
+    """Sliding median over ``2 * half_window + 1`` samples. Delegates to Rust.
+
+    Removes isolated impulsive artifacts while keeping step edges. Samples
+    beyond either end count as zero, so the first and last ``half_window``
+    samples of an offset trace are pulled toward 0. ``half_window=0`` returns
+    a copy.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_median_filter(trace_1d, half_window), dtype=np.float64)
+
+
+def rolling_variance(trace: np.ndarray, window: int) -> np.ndarray:
+    """Population variance over a sliding window ending at each sample. Delegates to Rust.
+
+    The first ``window - 1`` samples repeat the first full window's value; a
+    window longer than the trace uses the whole trace. O(N) in ``window``.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_rolling_variance(trace_1d, window), dtype=np.float64)
+
+
+def rolling_std(trace: np.ndarray, window: int) -> np.ndarray:
+    """Square root of :func:`rolling_variance`."""
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_rolling_std(trace_1d, window), dtype=np.float64)
+
+
+def estimate_noise_floor(trace: np.ndarray, window: int, percentile: float = 10.0) -> float:
+    """Robust noise level: the ``percentile``-th value of :func:`rolling_std`.
+
+    A low percentile picks the quiet stretches between transients. Returns NaN
+    for an empty trace; raises ``ValueError`` if ``percentile`` is outside
+    ``[0, 100]``.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return float(_estimate_noise_floor(trace_1d, window, percentile))
+
+
+def anscombe_transform(trace: np.ndarray) -> np.ndarray:
+    """Anscombe transform ``2 * sqrt(max(0, x) + 3/8)``. Delegates to Rust.
+
+    Maps Poisson (photon-count) data to values with roughly unit variance
+    regardless of the mean, matching the Gaussian noise model of the solver.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_anscombe_transform(trace_1d), dtype=np.float64)
+
+
+def anscombe_inverse_transform(trace: np.ndarray) -> np.ndarray:
+    """Inverse of :func:`anscombe_transform`. Delegates to Rust.
+
+    Uses the bias-corrected form ``(x/2)**2 - 3/8 + 1/(4*sqrt(x))``, floored
+    at 0; non-positive inputs map to 0.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_anscombe_inverse_transform(trace_1d), dtype=np.float64)
+
+
+def compute_dff_rolling(
+    trace: np.ndarray, window: int, quantile: float = 0.2
+) -> np.ndarray:
+    """ΔF/F0 with F0 the rolling ``quantile`` baseline over a causal ``window``.
+
+    Where F0 is below 1e-6 the denominator is replaced by 1 and a
+    ``RuntimeWarning`` is issued. Prefer passing raw fluorescence to
+    :func:`solve_trace`, which estimates its own baseline and scale; dividing
+    by a noisy F0 first distorts transient shapes.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    dff, messages = _compute_dff_rolling(trace_1d, window, quantile)
+    for message in messages:
+        warnings.warn(message, RuntimeWarning, stacklevel=2)
+    return np.asarray(dff, dtype=np.float64)
+
+
+def compute_dff_global(trace: np.ndarray, f0: float) -> np.ndarray:
+    """ΔF/F0 against a fixed ``f0`` (see :func:`compute_dff_rolling`)."""
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    dff, messages = _compute_dff_global(trace_1d, f0)
+    for message in messages:
+        warnings.warn(message, RuntimeWarning, stacklevel=2)
+    return np.asarray(dff, dtype=np.float64)
+
+
+def _run_batch(traces_2d, fs, tau_r, tau_d, lam, max_iters, conv_mode, constraint, n_jobs):
+    """Dispatch a multi-trace solve to the sequential or Rayon-parallel binding."""
+    if n_jobs == 1:
+        return _deconvolve_batch(
+            traces_2d, fs, tau_r, tau_d, lam, max_iters=max_iters,
+            conv_mode=conv_mode, constraint=constraint,
+        )
+    return _deconvolve_batch_parallel(
+        traces_2d, fs, tau_r, tau_d, lam, max_iters=max_iters,
+        conv_mode=conv_mode, constraint=constraint, n_jobs=n_jobs,
+    )
+
+
 def run_deconvolution(
     traces: np.ndarray,
     fs: float,
@@ -154,6 +428,7 @@ def run_deconvolution(
     max_iters: int = 2000,
     conv_mode: str = "fft",
     constraint: str = "nonneg",
+    n_jobs: int = 1,
 ) -> np.ndarray:
     """Run FISTA deconvolution on one or more calcium traces.
 
@@ -179,6 +454,9 @@ def run_deconvolution(
     constraint : str, optional
         Constraint type: ``'nonneg'`` (default, L1 + non-negative) or
         ``'box01'`` (box constraint [0, 1], no L1 penalty).
+    n_jobs : int, optional
+        Worker threads for multi-trace input (scikit-learn convention:
+        ``-1`` = all cores). Default 1 solves cells sequentially.
 
     Returns
     -------
@@ -189,16 +467,15 @@ def run_deconvolution(
     traces_2d = np.atleast_2d(np.asarray(traces, dtype=np.float64))
 
     if traces_2d.shape[0] == 1:
-        activity, _, _, _, _ = _deconvolve_single(
+        single = _deconvolve_single(
             traces_2d[0], fs, tau_r, tau_d, lam, max_iters=max_iters,
             conv_mode=conv_mode, constraint=constraint,
         )
-        result = np.asarray(activity, dtype=np.float64)
+        result = np.asarray(single.s_counts, dtype=np.float64)
         return result if single_trace else result.reshape(1, -1)
 
-    activities, _, _, _, _ = _deconvolve_batch(
-        traces_2d, fs, tau_r, tau_d, lam, max_iters=max_iters,
-        conv_mode=conv_mode, constraint=constraint,
+    activities, _, _, _, _ = _run_batch(
+        traces_2d, fs, tau_r, tau_d, lam, max_iters, conv_mode, constraint, n_jobs,
     )
     return np.stack([np.asarray(a, dtype=np.float64) for a in activities])
 
@@ -212,6 +489,7 @@ def run_deconvolution_full(
     max_iters: int = 2000,
     conv_mode: str = "fft",
     constraint: str = "nonneg",
+    n_jobs: int = 1,
 ) -> DeconvolutionResult:
     """Run FISTA deconvolution returning full results.
 
@@ -235,6 +513,9 @@ def run_deconvolution_full(
     constraint : str, optional
         Constraint type: ``'nonneg'`` (default, L1 + non-negative) or
         ``'box01'`` (box constraint [0, 1], no L1 penalty).
+    n_jobs : int, optional
+        Worker threads for multi-trace input (scikit-learn convention:
+        ``-1`` = all cores). Default 1 solves cells sequentially.
 
     Returns
     -------
@@ -246,23 +527,31 @@ def run_deconvolution_full(
     traces_2d = np.atleast_2d(np.asarray(traces, dtype=np.float64))
 
     if single_trace:
-        activity, baseline, reconvolution, iterations, converged = _deconvolve_single(
+        single = _deconvolve_single(
             traces_2d[0], fs, tau_r, tau_d, lam, max_iters=max_iters,
             conv_mode=conv_mode, constraint=constraint,
         )
-        return DeconvolutionResult(
-            activity=np.asarray(activity, dtype=np.float64),
-            baseline=baseline,
-            reconvolution=np.asarray(reconvolution, dtype=np.float64),
-            iterations=int(iterations),
-            converged=bool(converged),
-        )
+        return _single_full_result(single)
 
-    activities, baselines, reconvolutions, iterations, convergeds = _deconvolve_batch(
-        traces_2d, fs, tau_r, tau_d, lam, max_iters=max_iters,
-        conv_mode=conv_mode, constraint=constraint,
+    return _batch_full_result(_run_batch(
+        traces_2d, fs, tau_r, tau_d, lam, max_iters, conv_mode, constraint, n_jobs,
+    ))
+
+
+def _single_full_result(single: Any) -> DeconvolutionResult:
+    """``DeconvolutionResult`` of one ``deconvolve_single`` solve."""
+    return DeconvolutionResult(
+        activity=np.asarray(single.s_counts, dtype=np.float64),
+        baseline=single.baseline,
+        reconvolution=np.asarray(single.reconvolution, dtype=np.float64),
+        iterations=int(single.iterations),
+        converged=bool(single.converged),
     )
 
+
+def _batch_full_result(rows: tuple) -> DeconvolutionResult:
+    """``DeconvolutionResult`` of a batch binding's 5-tuple."""
+    activities, baselines, reconvolutions, iterations, convergeds = rows
     return DeconvolutionResult(
         activity=np.stack([np.asarray(a, dtype=np.float64) for a in activities]),
         baseline=np.array(baselines),
@@ -272,11 +561,166 @@ def run_deconvolution_full(
     )
 
 
+def solve_path(
+    trace: np.ndarray,
+    fs: float,
+    tau_r: float,
+    tau_d: float,
+    lambdas: np.ndarray,
+    max_iters_per_lambda: int = 2000,
+    conv_mode: str = "fft",
+    constraint: str = "nonneg",
+) -> np.ndarray:
+    """Solve the L1 regularization path for a single trace.
+
+    Each lambda is warm-started from the previous solution, which is several
+    times cheaper than independent solves.
+
+    Parameters
+    ----------
+    trace : np.ndarray
+        1-D calcium trace.
+    fs : float
+        Sampling rate in Hz.
+    tau_r, tau_d : float
+        Rise / decay time constants in seconds.
+    lambdas : np.ndarray
+        L1 penalties in decreasing order.
+    max_iters_per_lambda : int, optional
+        FISTA iteration cap per lambda, by default 2000.
+    conv_mode : str, optional
+        Convolution mode: ``'fft'`` (default) or ``'banded'`` (O(T) AR2).
+    constraint : str, optional
+        Constraint type: ``'nonneg'`` (default) or ``'box01'``.
+
+    Returns
+    -------
+    np.ndarray
+        Activity estimates, shape ``(len(lambdas), n_timepoints)``.
+
+    Raises
+    ------
+    ValueError
+        If ``lambdas`` is not in decreasing order or contains negative values.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    path = _solve_path(
+        trace_1d, fs, tau_r, tau_d, [float(lam) for lam in np.atleast_1d(lambdas)],
+        max_iters_per_lambda=max_iters_per_lambda,
+        conv_mode=conv_mode, constraint=constraint,
+    )
+    return np.asarray(path, dtype=np.float64)
+
+
+def lambda_sensitivity_arrays(
+    trace: np.ndarray,
+    fs: float,
+    tau_r: float,
+    tau_d: float,
+    lambda_min: float,
+    lambda_max: float,
+    n_lambdas: int = 20,
+    max_iters_per_lambda: int = 2000,
+    conv_mode: str = "fft",
+    constraint: str = "nonneg",
+) -> dict[str, np.ndarray]:
+    """Sweep lambda over a log-spaced grid and summarize each solve.
+
+    Lambdas run from ``lambda_max`` down to ``lambda_min``, each solve
+    warm-started from the previous one as in :func:`solve_path`.
+
+    Parameters
+    ----------
+    trace : np.ndarray
+        1-D calcium trace.
+    fs : float
+        Sampling rate in Hz.
+    tau_r, tau_d : float
+        Rise / decay time constants in seconds.
+    lambda_min, lambda_max : float
+        Grid bounds; ``0 < lambda_min <= lambda_max``.
+    n_lambdas : int, optional
+        Number of grid points, by default 20.
+    max_iters_per_lambda : int, optional
+        FISTA iteration cap per lambda, by default 2000.
+    conv_mode : str, optional
+        Convolution mode: ``'fft'`` (default) or ``'banded'`` (O(T) AR2).
+    constraint : str, optional
+        Constraint type: ``'nonneg'`` (default) or ``'box01'``.
+
+    Returns
+    -------
+    dict[str, np.ndarray]
+        One array per column, each of length ``n_lambdas``: ``lambda``
+        (decreasing), ``n_spikes`` (frames with nonzero activity), ``pve``,
+        ``alpha`` (mean activity over those frames), ``baseline`` and
+        ``iterations``.
+
+    Raises
+    ------
+    ValueError
+        If the lambda bounds or ``n_lambdas`` are invalid.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    columns = _lambda_sensitivity_arrays(
+        trace_1d, fs, tau_r, tau_d, lambda_min, lambda_max,
+        n_lambdas=n_lambdas, max_iters_per_lambda=max_iters_per_lambda,
+        conv_mode=conv_mode, constraint=constraint,
+    )
+    names = ("lambda", "n_spikes", "pve", "alpha", "baseline", "iterations")
+    return {name: np.asarray(col) for name, col in zip(names, columns)}
+
+
+def lambda_sensitivity(
+    trace: np.ndarray,
+    fs: float,
+    tau_r: float,
+    tau_d: float,
+    lambda_min: float,
+    lambda_max: float,
+    n_lambdas: int = 20,
+    **kwargs: Any,
+) -> Any:
+    """:func:`lambda_sensitivity_arrays` as a ``pandas.DataFrame``.
+
+    Takes the same arguments and returns one row per lambda, with columns
+    ``lambda``, ``n_spikes``, ``pve``, ``alpha``, ``baseline`` and
+    ``iterations``.
+
+    Raises
+    ------
+    ImportError
+        If pandas is not installed.
+    """
+    try:
+        import pandas as pd
+    except ImportError:
+        raise ImportError(
+            "pandas is required for lambda_sensitivity. "
+            "Install it with: pip install pandas, or use lambda_sensitivity_arrays"
+        ) from None
+    return pd.DataFrame(
+        lambda_sensitivity_arrays(
+            trace, fs, tau_r, tau_d, lambda_min, lambda_max, n_lambdas, **kwargs
+        )
+    )
+
+
 # ---------------------------------------------------------------------------
 # InDeCa pipeline wrappers
 # ---------------------------------------------------------------------------
 
 
+CancelToken = PyCancelToken
+"""Cancellation handle for :func:`solve_trace`: ``token.cancel()``, from any
+thread, stops the solve at its next scale iteration."""
+
+InDeCa = PyInDeCa
+"""scikit-learn style InDeCa estimator: ``InDeCa(tau_rise, tau_decay, fs)``,
+then ``fit(traces)`` / ``transform()`` / ``fit_transform(traces)`` over a
+``(n_cells, n_frames)`` array; per-cell results in ``fit_results_``."""
+
+
 class SolveTraceResult(NamedTuple):
     """Result from a single-trace InDeCa solve.
 
@@ -296,6 +740,12 @@ class SolveTraceResult(NamedTuple):
         Number of FISTA iterations run.
     converged : bool
         Whether the solver converged.
+    noise_std : float
+        Residual standard deviation over inactive frames (all frames if
+        every frame contains a spike).
+    snr_db : float
+        Fit signal-to-noise ratio in dB: power of the reconvolved spikes
+        (baseline excluded) over the power of the fit residual.
     """
 
     s_counts: np.ndarray
@@ -305,6 +755,8 @@ class SolveTraceResult(NamedTuple):
     pve: float
     iterations: int
     converged: bool
+    noise_std: float
+    snr_db: float
 
 
 class BiexpFitResult(NamedTuple):
@@ -356,6 +808,18 @@ def solve_trace(
     warm_counts: np.ndarray | None = None,
     lambda_: float = 0.0,
     noise_constrained: bool = False,
+    saturation_value: float | None = None,
+    photobleaching_correction: bool = False,
+    boundary_mode: str = "zero",
+    upsample_method: str = "linear",
+    threshold_criterion: str = "mse",
+    pve_min_delta: float | None = None,
+    pve_patience: int = 2,
+    alpha_init: str = "peak_trough",
+    alpha_init_value: float | None = None,
+    variance_stabilize: bool = False,
+    on_progress: Callable[[dict[str, Any]], None] | None = None,
+    cancel_token: CancelToken | None = None,
 ) -> SolveTraceResult:
     """Run the InDeCa pipeline on a single trace. Delegates to Rust.
 
@@ -385,6 +849,63 @@ def solve_trace(
         that maximizes fit. Suppresses noise fit as spurious spikes; the effect
         concentrates at low SNR. Knob-free (the noise floor is measured from the
         trace). Default False.
+    saturation_value : float, optional
+        Detector saturation level. Frames with ``trace >= saturation_value``
+        are excluded from the rolling baseline and the alpha/baseline fit, so
+        clipped peaks do not bias the amplitude estimate. Default None.
+    photobleaching_correction : bool
+        Fit an exponential bleaching trend ``F0 * exp(-t / tau_bl)`` to the
+        lower envelope of the trace and subtract it before baseline removal.
+        Default False.
+    boundary_mode : str
+        Edge handling for the solve. ``"zero"`` solves the trace as-is and
+        normalizes by the interior peak; ``"mirror"`` / ``"periodic"`` extend
+        the trace by the boundary padding (reflected / wrapped) and trim the
+        solution back, keeping spikes near the edges. Default ``"zero"``.
+    upsample_method : str
+        Interpolation onto the upsampled grid when ``upsample_factor > 1``:
+        ``"linear"`` or ``"cubic"`` (natural cubic spline, which follows the
+        sharp rise of a transient without kinks at the samples). Default
+        ``"linear"``.
+    threshold_criterion : str
+        Objective of the binarization threshold search when
+        ``noise_constrained`` is off: ``"mse"`` (smallest residual) or
+        ``"aic"`` (``n * log(SSE / n) + 2 * n_spikes``, which stops the search
+        from adding spikes that barely improve the fit). Default ``"mse"``.
+    pve_min_delta : float, optional
+        Also stop the scale iteration loop once PVE has improved by less than
+        ``pve_min_delta`` in each of the last ``pve_patience`` iterations,
+        besides the default stop when the fitted alpha matches the prescale.
+        Default None (alpha convergence only).
+    pve_patience : int
+        Consecutive small-improvement iterations before a PVE-plateau stop
+        (0 counts as 1). Default 2.
+    alpha_init : str
+        First amplitude estimate of the scale loop: ``"peak_trough"`` (the
+        trace range), ``"percentile"`` (the ``alpha_init_value``-th
+        percentile above the minimum), ``"histogram"`` (gap between the two
+        modes of a Gaussian mixture fit to the value histogram, robust to
+        noise extremes) or ``"user"`` (``alpha_init_value`` itself). Default
+        ``"peak_trough"``.
+    alpha_init_value : float, optional
+        Percentile (0-100) for ``"percentile"`` or alpha for ``"user"``.
+    variance_stabilize : bool
+        Apply :func:`anscombe_transform` to the trace before any other step,
+        for shot-noise-limited (photon-count) data. ``alpha``, ``baseline``,
+        ``noise_std`` and ``snr_db`` are then in Anscombe units; map a
+        reconvolved fit back with :func:`anscombe_inverse_transform`.
+        ``saturation_value`` still refers to the raw trace. Default False.
+    on_progress : callable, optional
+        Called as ``on_progress(event)`` after filtering, after each scale
+        iteration and when the scale loop stops. ``event`` is a dict with keys
+        ``stage`` (``"filter"``, ``"scale_iteration"`` or ``"converged"``),
+        ``scale_iter``, ``fista_iter``, ``pve`` and ``alpha``. Exceptions
+        raised by the callback are reported but do not stop the solve.
+    cancel_token : CancelToken, optional
+        Token whose ``cancel()`` stops the solve at the next scale iteration;
+        the best result found so far is returned (all-zero spikes with
+        ``alpha = 0`` if no iteration had finished). The GIL is released while
+        solving, so the token can be cancelled from another thread.
 
     Returns
     -------
@@ -395,12 +916,20 @@ def solve_trace(
     if warm_counts is not None:
         warm = np.ascontiguousarray(warm_counts, dtype=np.float64)
 
-    s_counts, alpha, baseline, threshold, pve, iterations, converged = _indeca_solve_trace(
+    return _solve_trace_result(_indeca_solve_trace(
         trace_1d, tau_rise, tau_decay, fs,
         upsample_factor, max_iters, tol,
         hp_enabled, lp_enabled, warm, lambda_,
-        noise_constrained,
-    )
+        noise_constrained, saturation_value, photobleaching_correction,
+        boundary_mode, upsample_method, threshold_criterion,
+        pve_min_delta, pve_patience, alpha_init, alpha_init_value,
+        variance_stabilize, on_progress, cancel_token,
+    ))
+
+
+def _solve_trace_result(raw: tuple) -> SolveTraceResult:
+    """``SolveTraceResult`` of the ``py_indeca_solve_trace`` 9-tuple."""
+    s_counts, alpha, baseline, threshold, pve, iterations, converged, noise_std, snr_db = raw
     return SolveTraceResult(
         s_counts=np.asarray(s_counts),
         alpha=float(alpha),
@@ -409,6 +938,233 @@ def solve_trace(
         pve=float(pve),
         iterations=int(iterations),
         converged=bool(converged),
+        noise_std=float(noise_std),
+        snr_db=float(snr_db),
+    )
+
+
+def set_seed(seed: int) -> None:
+    """Seed the global random stream used by the stochastic operations.
+
+    Bootstrap calls without an explicit ``seed`` and the block order of the
+    stochastic FISTA solver draw from this stream. Set it before the first
+    stochastic call; the same seed and call order then reproduce the results.
+    The stream is per thread.
+    """
+    _set_seed(seed)
+
+
+def bootstrap_spike_ci(
+    trace: np.ndarray,
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+    *,
+    upsample_factor: int = 1,
+    n_bootstrap: int = 100,
+    alpha_ci: float = 0.05,
+    seed: int | None = None,
+) -> tuple[np.ndarray, np.ndarray]:
+    """Bootstrap confidence band for InDeCa spike counts. Delegates to Rust.
+
+    The trace is solved once to estimate the residual noise variance, then
+    ``n_bootstrap`` noise-perturbed copies are re-solved and the per-frame
+    spike-count quantiles taken.
+
+    Parameters
+    ----------
+    trace : np.ndarray
+        1-D calcium trace.
+    tau_rise, tau_decay : float
+        Time constants in seconds.
+    fs : float
+        Sampling rate in Hz.
+    upsample_factor : int
+        Upsampling multiplier (1 = no upsampling).
+    n_bootstrap : int
+        Number of perturbed replicates.
+    alpha_ci : float
+        Two-sided miscoverage; the band spans the ``alpha_ci / 2`` and
+        ``1 - alpha_ci / 2`` quantiles. Default 0.05 (95% band).
+    seed : int, optional
+        Seed for the noise generator. Default None draws it from the global
+        stream, so results are reproducible after :func:`set_seed`.
+
+    Returns
+    -------
+    tuple[np.ndarray, np.ndarray]
+        ``(lower_ci, upper_ci)``, each shape ``(n_timepoints,)``, float32.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    lower, upper = _bootstrap_spike_ci(
+        trace_1d, tau_rise, tau_decay, fs,
+        upsample_factor, n_bootstrap, alpha_ci, seed,
+    )
+    return np.asarray(lower), np.asarray(upper)
+
+
+class RocCurve(NamedTuple):
+    """ROC curve of a relaxed solution against ground-truth spikes.
+
+    Attributes
+    ----------
+    fpr : np.ndarray
+        False-positive rate per threshold, float64, from 0 up to 1.
+    tpr : np.ndarray
+        True-positive rate per threshold, float64, from 0 up to 1.
+    thresholds : np.ndarray
+        Thresholds, descending; the first is ``inf`` (nothing detected).
+    auc : float
+        Trapezoidal area under the curve (0.5 when undefined).
+    """
+
+    fpr: np.ndarray
+    tpr: np.ndarray
+    thresholds: np.ndarray
+    auc: float
+
+
+def roc_threshold_curve(
+    s_relaxed: np.ndarray,
+    s_true_binary: np.ndarray,
+    tau_decay: float,
+    fs_up: float,
+    *,
+    n_thresholds: int = 100,
+) -> RocCurve:
+    """Evaluate binarization thresholds against ground truth. Delegates to Rust.
+
+    Sweeps ``n_thresholds`` thresholds evenly from the largest to the smallest
+    value of ``s_relaxed``; a sample is detected when ``s_relaxed >= threshold``
+    and is a true spike when ``s_true_binary > 0.5``. Samples within
+    ``ceil(2 * tau_decay * fs_up)`` of either end are excluded, as in the
+    threshold search. Both arrays must be on the same (upsampled) grid.
+
+    Warns (``UserWarning``) and reports ``auc = 0.5`` when ``s_true_binary``
+    has no positive or no negative samples.
+    """
+    fpr, tpr, thresholds, auc = _roc_threshold_curve(
+        np.ascontiguousarray(s_relaxed, dtype=np.float64),
+        np.ascontiguousarray(s_true_binary, dtype=np.float64),
+        tau_decay, fs_up, n_thresholds,
+    )
+    return RocCurve(np.asarray(fpr), np.asarray(tpr), np.asarray(thresholds), float(auc))
+
+
+def threshold_search(
+    s_relaxed: np.ndarray,
+    y: np.ndarray,
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+    *,
+    upsample_factor: int = 1,
+    threshold_criterion: str = "mse",
+    max_threshold: float | None = None,
+    weights: np.ndarray | None = None,
+) -> Any:
+    """Binarize a relaxed spike solution by InDeCa's threshold search.
+
+    Delegates to Rust. ``s_relaxed`` (peak-normalized, e.g. a ``PySolver``
+    Box01 solution divided by its maximum) and ``y`` are on the upsampled
+    grid at ``fs * upsample_factor``. Each candidate binary train is
+    convolved through the AR(2) kernel and fit to ``y`` with a non-negative
+    ``alpha`` plus baseline; the best fit is returned. With
+    ``threshold_criterion="aic"`` the search minimizes
+    ``n * log(SSE / n) + 2 * n_spikes`` instead of the residual, so it keeps
+    fewer spikes that barely improve the fit. ``max_threshold`` caps the
+    automatic candidate grid; see :func:`threshold_search_custom_grid` to
+    evaluate an explicit list of thresholds instead. ``weights`` (non-negative,
+    same length as ``y``) down-weights artifact or noisy frames in the
+    alpha/baseline fit and the residual; a weight of 0 ignores a frame and
+    ``None`` weights every frame equally.
+
+    Returns
+    -------
+    PyThresholdResult
+        Attributes ``s_binary`` (float32 array), ``alpha``, ``baseline``,
+        ``threshold``, ``pve`` and ``error``.
+
+    Raises
+    ------
+    ValueError
+        If the arrays differ in length, ``upsample_factor`` is 0,
+        ``threshold_criterion`` is not ``"mse"`` or ``"aic"`` or a weight is
+        negative.
+    """
+    return _threshold_search(
+        np.ascontiguousarray(s_relaxed, dtype=np.float64),
+        np.ascontiguousarray(y, dtype=np.float64),
+        tau_rise, tau_decay, fs, upsample_factor, threshold_criterion,
+        max_threshold,
+        None if weights is None else np.ascontiguousarray(weights, dtype=np.float64),
+    )
+
+
+def threshold_search_custom_grid(
+    s_relaxed: np.ndarray,
+    y: np.ndarray,
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+    thresholds: np.ndarray,
+) -> Any:
+    """Binarize a relaxed spike solution at each of ``thresholds`` exactly.
+
+    Delegates to Rust. Unlike :func:`threshold_search` there is no
+    coarse-then-fine refinement and no minimum-threshold floor: every listed
+    threshold is evaluated on the original-rate grid and the one with the
+    smallest residual is kept (ties go to the earlier entry). NaN entries
+    are skipped; an empty grid gives the no-spike fit.
+
+    Returns
+    -------
+    PyThresholdResult
+        Same attributes as :func:`threshold_search`.
+
+    Raises
+    ------
+    ValueError
+        If ``s_relaxed`` and ``y`` differ in length.
+    """
+    return _threshold_search_custom_grid(
+        np.ascontiguousarray(s_relaxed, dtype=np.float64),
+        np.ascontiguousarray(y, dtype=np.float64),
+        tau_rise, tau_decay, fs,
+        np.ascontiguousarray(thresholds, dtype=np.float64),
+    )
+
+
+def joint_threshold_search(
+    s_relaxed_list: list[np.ndarray],
+    y_list: list[np.ndarray],
+    tau_rise: float,
+    tau_decay: float,
+    fs: float,
+) -> tuple[list[Any], float]:
+    """Binarize several cells' relaxed solutions with one shared threshold.
+
+    Delegates to Rust. The threshold minimizes the sum of the per-cell
+    interior MSEs over the same coarse-to-fine grid as
+    :func:`threshold_search`, built from all cells' values; each cell keeps
+    its own ``alpha`` and baseline. Inputs are at the original rate ``fs``.
+
+    Returns
+    -------
+    results : list of PyThresholdResult
+        One per cell, each with the shared ``threshold``.
+    threshold : float
+        The shared threshold (0.0 for an empty list).
+
+    Raises
+    ------
+    ValueError
+        If the lists, or a cell's ``s_relaxed`` and ``y``, differ in length.
+    """
+    return _joint_threshold_search(
+        [np.ascontiguousarray(s, dtype=np.float64) for s in s_relaxed_list],
+        [np.ascontiguousarray(y, dtype=np.float64) for y in y_list],
+        tau_rise, tau_decay, fs,
     )
 
 
@@ -424,6 +1180,7 @@ def estimate_kernel(
     tol: float = 1e-4,
     warm_kernel: np.ndarray | None = None,
     smooth_lambda: float = 0.0,
+    curvature_lambda: float = 0.0,
 ) -> np.ndarray:
     """Estimate a free-form kernel from traces and spike trains. Delegates to Rust.
 
@@ -449,6 +1206,9 @@ def estimate_kernel(
         Kernel from a previous iteration for warm-start.
     smooth_lambda : float
         Total-variation smoothness penalty weight.
+    curvature_lambda : float
+        Weight of the squared second-difference penalty, which damps
+        oscillations in kernels estimated from sparse spikes. Default 0 (off).
 
     Returns
     -------
@@ -466,7 +1226,7 @@ def estimate_kernel(
 
     result = _indeca_estimate_kernel(
         tf, sf, tl, al, bl, kernel_length,
-        max_iters, tol, wk, smooth_lambda,
+        max_iters, tol, wk, smooth_lambda, curvature_lambda,
     )
     return np.asarray(result)
 
@@ -530,3 +1290,218 @@ def compute_upsample_factor(fs: float, target_fs: float) -> int:
         Upsampling multiplier (>= 1).
     """
     return int(_indeca_compute_upsample_factor(fs, target_fs))
+
+
+# ---------------------------------------------------------------------------
+# Spike-train analysis
+# ---------------------------------------------------------------------------
+
+
+def spike_isis(s_counts: np.ndarray, fs: float, *, min_count: float = 1.0) -> np.ndarray:
+    """Inter-spike intervals in seconds. Delegates to Rust.
+
+    Intervals are measured between adjacent frames with
+    ``s_counts >= min_count``; several spikes in one frame count as one event.
+    Returns an empty array when fewer than two frames qualify.
+    """
+    counts = np.ascontiguousarray(np.atleast_1d(s_counts), dtype=np.float64)
+    return np.asarray(_spike_isis(counts, fs, min_count))
+
+
+def isi_mean(isis: np.ndarray) -> float:
+    """Mean inter-spike interval (NaN when there are no intervals)."""
+    return float(_isi_mean(np.ascontiguousarray(isis, dtype=np.float64)))
+
+
+def isi_cv(isis: np.ndarray) -> float:
+    """Coefficient of variation of the ISIs, population std / mean.
+
+    About 1 for Poisson firing and 0 for perfectly regular firing; NaN when
+    there are no intervals.
+    """
+    return float(_isi_cv(np.ascontiguousarray(isis, dtype=np.float64)))
+
+
+def isi_histogram(
+    isis: np.ndarray, n_bins: int, max_isi_s: float
+) -> tuple[np.ndarray, np.ndarray]:
+    """Histogram of ISIs over ``n_bins`` equal bins on ``[0, max_isi_s]``.
+
+    Returns ``(bin_edges, counts)`` following the ``numpy.histogram``
+    convention (last bin closed); intervals longer than ``max_isi_s`` are not
+    counted. Raises ``ValueError`` if ``n_bins < 1`` or ``max_isi_s <= 0``.
+    """
+    edges, counts = _isi_histogram(np.ascontiguousarray(isis, dtype=np.float64), n_bins, max_isi_s)
+    return np.asarray(edges), np.asarray(counts)
+
+
+def detect_bursts(
+    s_counts: np.ndarray,
+    fs: float,
+    max_isi_s: float,
+    *,
+    min_burst_spikes: int = 2,
+) -> list[dict[str, Any]]:
+    """Group spike frames (``s_counts > 0``) into bursts. Delegates to Rust.
+
+    A burst is a maximal run of spike frames whose successive ISIs are all at
+    most ``max_isi_s`` and that holds at least ``min_burst_spikes`` frames;
+    with ``min_burst_spikes=1`` every spike is a burst. Each burst is a dict
+    with ``start_frame``, ``end_frame`` (inclusive), ``n_spikes``,
+    ``total_amplitude`` (sum of ``s_counts``) and ``peak_rate_hz``
+    (1 / shortest ISI, 0 for a single spike).
+    """
+    counts = np.ascontiguousarray(np.atleast_1d(s_counts), dtype=np.float64)
+    return _detect_bursts(counts, fs, max_isi_s, min_burst_spikes)
+
+
+def burst_rate(events: list[dict[str, Any]], total_duration_s: float) -> float:
+    """Bursts per second over ``total_duration_s`` (NaN for a non-positive duration)."""
+    if not total_duration_s > 0:
+        return float("nan")
+    return len(events) / total_duration_s
+
+
+def zscore(trace: np.ndarray) -> np.ndarray:
+    """``(x - mean) / std`` with the population std. Delegates to Rust.
+
+    A constant trace maps to zeros. Raises ``ValueError`` for an empty trace.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_zscore(trace_1d), dtype=np.float64)
+
+
+def robust_zscore(trace: np.ndarray) -> np.ndarray:
+    """``(x - median) / (1.4826 * MAD)``, an outlier-resistant z-score. Delegates to Rust.
+
+    The MAD is scaled so Gaussian noise has unit spread; a zero MAD leaves
+    ``x - median``. Raises ``ValueError`` for an empty trace.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return np.asarray(_robust_zscore(trace_1d), dtype=np.float64)
+
+
+def normalize_dff(trace: np.ndarray, baseline: np.ndarray) -> np.ndarray:
+    """Elementwise ``(trace - baseline) / max(baseline, 1e-6)``. Delegates to Rust.
+
+    Raises ``ValueError`` for an empty trace or a ``baseline`` of a different
+    length.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    baseline_1d = np.ascontiguousarray(np.atleast_1d(baseline), dtype=np.float64)
+    return np.asarray(_normalize_dff(trace_1d, baseline_1d), dtype=np.float64)
+
+
+def event_aligned_average(
+    trace: np.ndarray,
+    event_frames: np.ndarray | list[int],
+    pre_frames: int,
+    post_frames: int,
+) -> tuple[np.ndarray, np.ndarray]:
+    """Event-triggered mean and standard error of ``trace``. Delegates to Rust.
+
+    Each trial is ``trace[e - pre_frames : e + post_frames]``, so index
+    ``pre_frames`` of the result is the event frame. Events whose window runs
+    off either end of the trace are skipped. Returns ``(mean, sem)``, float32;
+    ``sem`` is NaN with fewer than two trials and both are NaN with none.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    events = [int(e) for e in np.atleast_1d(event_frames)]
+    mean, sem = _event_aligned_average(trace_1d, events, pre_frames, post_frames)
+    return np.asarray(mean), np.asarray(sem)
+
+
+def event_aligned_raster(
+    s_counts: np.ndarray,
+    event_frames: np.ndarray | list[int],
+    pre_frames: int,
+    post_frames: int,
+) -> np.ndarray:
+    """``s_counts`` around each event, one trial per row. Delegates to Rust.
+
+    Uses the windows and edge rule of :func:`event_aligned_average`; returns a
+    float32 array of shape ``(n_trials, pre_frames + post_frames)``.
+    """
+    counts = np.ascontiguousarray(np.atleast_1d(s_counts), dtype=np.float64)
+    events = [int(e) for e in np.atleast_1d(event_frames)]
+    return np.asarray(_event_aligned_raster(counts, events, pre_frames, post_frames))
+
+
+def spike_triggered_average(
+    trace: np.ndarray,
+    s_counts: np.ndarray,
+    pre_frames: int,
+    post_frames: int,
+    *,
+    min_count: float = 0.5,
+) -> tuple[np.ndarray, np.ndarray]:
+    """Mean and standard error of ``trace`` around each spike. Delegates to Rust.
+
+    Each snippet is ``trace[t - pre_frames : t + post_frames + 1]`` for a frame
+    with ``s_counts[t] >= min_count``, so index ``pre_frames`` of the result is
+    the spike frame. Spikes whose window runs off either end are skipped.
+    Returns ``(mean, sem)``, float32, with the NaN rules of
+    :func:`event_aligned_average`. Compare ``mean`` with the fitted kernel to
+    sanity-check a kernel estimate.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    counts = np.ascontiguousarray(np.atleast_1d(s_counts), dtype=np.float64)
+    mean, sem = _spike_triggered_average(trace_1d, counts, pre_frames, post_frames, min_count)
+    return np.asarray(mean), np.asarray(sem)
+
+
+def pairwise_correlation_index(
+    spike_matrix: np.ndarray,
+    bin_size_frames: int = 1,
+) -> float:
+    """Mean Pearson correlation over all cell pairs. Delegates to Rust.
+
+    ``spike_matrix`` is ``(n_cells, n_frames)``; each cell's train is summed
+    into consecutive ``bin_size_frames``-frame bins (a trailing partial bin is
+    dropped) before correlating. Pairs involving a cell that is constant over
+    the bins, e.g. silent, are skipped. Returns NaN with fewer than two cells.
+    """
+    matrix = np.ascontiguousarray(np.atleast_2d(spike_matrix), dtype=np.float64)
+    return _pairwise_correlation_index(matrix, bin_size_frames)
+
+
+def cell_pair_correlations(
+    spike_matrix: np.ndarray,
+    bin_size_frames: int = 1,
+) -> np.ndarray:
+    """Binned Pearson correlation of every cell pair. Delegates to Rust.
+
+    Uses the binning of :func:`pairwise_correlation_index`. Returns a float64
+    array of ``n_cells * (n_cells - 1) / 2`` values ordered ``(0, 1), (0, 2),
+    ..., (1, 2), ...`` -- the upper triangle of the correlation matrix, row by
+    row. Pairs involving a constant cell are NaN.
+    """
+    matrix = np.ascontiguousarray(np.atleast_2d(spike_matrix), dtype=np.float64)
+    return np.asarray(_cell_pair_correlations(matrix, bin_size_frames))
+
+
+def select_lambda_cv(
+    trace: np.ndarray,
+    tau_r: float,
+    tau_d: float,
+    fs: float,
+    lambdas: np.ndarray,
+    *,
+    k: int = 5,
+) -> float:
+    """Pick the lambda with the lowest k-fold cross-validated error. Delegates to Rust.
+
+    The trace is split into ``k`` contiguous folds; each is held out in turn,
+    the solver is fitted to the rest, and the fit's squared error on the
+    held-out frames is pooled over folds. ``k`` is reduced so every fold spans
+    at least one kernel length. Returns NaN if the trace is too short to split.
+
+    Raises
+    ------
+    ValueError
+        If ``lambdas`` is empty.
+    """
+    trace_1d = np.ascontiguousarray(np.atleast_1d(trace), dtype=np.float64)
+    return _select_lambda_cv(
+        trace_1d, tau_r, tau_d, fs, [float(lam) for lam in np.atleast_1d(lambdas)], k
+    )
@@ -12,6 +12,12 @@ from pathlib import Path
 
 import numpy as np
 
+from ._solver import py_estimate_neuropil_coeff as _estimate_neuropil_coeff
+from ._solver import py_neuropil_correct as _neuropil_correct
+from ._solver import py_read_csv_traces as _read_csv_traces
+from ._solver import py_read_npy as _read_npy
+from ._solver import py_read_suite2p as _read_suite2p
+
 
 def save_for_tuning(
     traces: np.ndarray,
@@ -146,6 +152,112 @@ def load_tuning_data(path: str | Path) -> tuple[np.ndarray, dict]:
     return traces, metadata
 
 
+def read_npy(path: str | Path) -> np.ndarray:
+    """Read a float ``.npy`` file with the Rust parser, without ``np.load``.
+
+    Accepts float32 or float64 data in either byte order and C or Fortran
+    order; the result is always a C-ordered float32 array of the stored
+    shape.
+
+    Raises
+    ------
+    OSError
+        If the file cannot be read.
+    ValueError
+        If the file is not a supported ``.npy`` float array.
+    """
+    return np.asarray(_read_npy(str(path)))
+
+
+def read_csv_traces(
+    path: str | Path,
+    delimiter: str = ",",
+    has_header: bool = True,
+) -> tuple[np.ndarray, list[str]]:
+    """Read a CSV/TSV file with one column per cell and one row per frame.
+
+    Parsed in Rust, streaming line by line. Empty fields and ``NaN``/``NA``
+    become NaN; columns whose first value is not numeric (frame labels,
+    timestamps as text) are dropped.
+
+    Returns
+    -------
+    traces : np.ndarray
+        Shape ``(n_cells, n_timepoints)``, float32.
+    cell_names : list[str]
+        Header names of the kept columns, or ``cell_0``, ``cell_1``, ... when
+        ``has_header`` is False.
+
+    Raises
+    ------
+    OSError
+        If the file cannot be read.
+    ValueError
+        On rows with inconsistent field counts or stray non-numeric values.
+    """
+    traces, names = _read_csv_traces(str(path), delimiter, has_header)
+    return np.asarray(traces), list(names)
+
+
+def read_suite2p(
+    stat_path: str | Path,
+    neuropil_coeff: float = 0.7,
+) -> tuple[np.ndarray, np.ndarray]:
+    """Read neuropil-corrected traces from a Suite2p plane directory.
+
+    Loads ``F.npy``, ``Fneu.npy`` and ``iscell.npy`` from the directory
+    containing ``stat_path`` (Suite2p's ``plane0/stat.npy``, say) and returns
+    ``F - neuropil_coeff * Fneu``. A missing ``Fneu.npy`` returns raw ``F``;
+    a missing ``iscell.npy`` marks every ROI as a cell.
+
+    Returns
+    -------
+    traces : np.ndarray
+        Shape ``(n_rois, n_frames)``, float32, for all ROIs.
+    iscell : np.ndarray
+        Boolean mask of ROIs Suite2p classified as cells; select them with
+        ``traces[iscell]``.
+
+    Raises
+    ------
+    OSError
+        If ``F.npy`` (or a present ``Fneu.npy``/``iscell.npy``) cannot be read.
+    ValueError
+        If the files are not valid ``.npy`` arrays or their shapes disagree.
+    """
+    traces, iscell = _read_suite2p(str(stat_path), neuropil_coeff)
+    return np.asarray(traces), np.asarray(iscell, dtype=bool)
+
+
+def neuropil_correct(
+    f: np.ndarray, f_neu: np.ndarray, coeff: float = 0.7
+) -> np.ndarray:
+    """Neuropil-corrected trace ``F - coeff * Fneu`` for one ROI.
+
+    Raises ``ValueError`` if ``f`` and ``f_neu`` differ in length.
+    """
+    f_1d = np.ascontiguousarray(np.atleast_1d(f), dtype=np.float64)
+    fneu_1d = np.ascontiguousarray(np.atleast_1d(f_neu), dtype=np.float64)
+    return np.asarray(_neuropil_correct(f_1d, fneu_1d, coeff), dtype=np.float64)
+
+
+def estimate_neuropil_coeff(
+    f: np.ndarray, f_neu: np.ndarray, window: int = 30
+) -> float:
+    """Estimate the neuropil coefficient for one ROI.
+
+    Returns the ``coeff`` in ``[0, 1]`` minimising the variance of
+    ``F - coeff * Fneu`` over the 20% of frames with the lowest
+    ``window``-frame rolling std of ``F``, where the signal should be mostly
+    neuropil. Returns 0.0 if ``f_neu`` is flat on those frames.
+
+    Raises ``ValueError`` if ``f`` and ``f_neu`` differ in length.
+    """
+    f_1d = np.ascontiguousarray(np.atleast_1d(f), dtype=np.float64)
+    fneu_1d = np.ascontiguousarray(np.atleast_1d(f_neu), dtype=np.float64)
+    return float(_estimate_neuropil_coeff(f_1d, fneu_1d, int(window)))
+
+
 def load_export_params(path: str | Path) -> dict:
     """Load deconvolution parameters from a CaTune export JSON.
 
@@ -0,0 +1,222 @@
+"""Spike-train analysis tests (ISIs, bursts, CV lambda selection, event alignment, pairwise correlations, spike-triggered average, noise floor, Anscombe transform, dF/F0, normalisation) -- mirrors Rust analysis.rs and baseline.rs tests."""
+
+from __future__ import annotations
+
+import math
+
+import numpy as np
+from numpy.testing import assert_allclose, assert_array_equal
+
+import pytest
+
+from calab import (
+    anscombe_inverse_transform,
+    anscombe_transform,
+    build_kernel,
+    burst_rate,
+    cell_pair_correlations,
+    compute_dff_global,
+    compute_dff_rolling,
+    detect_bursts,
+    estimate_noise_floor,
+    event_aligned_average,
+    event_aligned_raster,
+    isi_cv,
+    isi_histogram,
+    isi_mean,
+    normalize_dff,
+    pairwise_correlation_index,
+    robust_zscore,
+    rolling_std,
+    rolling_variance,
+    select_lambda_cv,
+    spike_isis,
+    spike_triggered_average,
+    zscore,
+)
+
+
+def test_spike_isis_between_spike_frames() -> None:
+    s = np.zeros(100)
+    s[[10, 25, 40, 70]] = [1.0, 2.0, 0.4, 1.0]
+    isis = spike_isis(s, 10.0, min_count=0.5)
+    assert_allclose(isis, [1.5, 4.5])
+    assert_allclose(isi_mean(isis), 3.0)
+    assert_allclose(isi_cv(isis), 0.5)
+    assert isi_cv(np.array([2.0, 2.0, 2.0])) == 0.0
+
+
+def test_fewer_than_two_spikes() -> None:
+    s = np.zeros(50)
+    s[5] = 1.0
+    isis = spike_isis(s, 30.0)
+    assert isis.shape == (0,)
+    assert math.isnan(isi_mean(isis))
+    assert math.isnan(isi_cv(isis))
+
+
+def test_isi_histogram_matches_numpy() -> None:
+    isis = np.array([0.0, 0.1, 0.25, 0.5, 0.99, 1.0, 1.5])
+    edges, counts = isi_histogram(isis, 4, 1.0)
+    np_counts, np_edges = np.histogram(isis, bins=4, range=(0.0, 1.0))
+    assert_allclose(edges, np_edges)
+    assert_array_equal(counts, np_counts)
+
+
+def test_isi_histogram_rejects_bad_args() -> None:
+    with pytest.raises(ValueError):
+        isi_histogram(np.array([0.1]), 0, 1.0)
+    with pytest.raises(ValueError):
+        isi_histogram(np.array([0.1]), 4, 0.0)
+
+
+def test_detect_bursts() -> None:
+    s = np.zeros(200)
+    s[[10, 12, 16, 60, 120, 121]] = [1.0, 2.0, 1.0, 1.0, 1.0, 1.0]
+    bursts = detect_bursts(s, 20.0, 0.2)
+    assert len(bursts) == 2
+    assert bursts[0] == {
+        "start_frame": 10,
+        "end_frame": 16,
+        "n_spikes": 3,
+        "total_amplitude": 4.0,
+        "peak_rate_hz": 10.0,
+    }
+    assert (bursts[1]["start_frame"], bursts[1]["end_frame"]) == (120, 121)
+    assert_allclose(burst_rate(bursts, 10.0), 0.2)
+    assert math.isnan(burst_rate(bursts, 0.0))
+
+
+def test_detect_bursts_min_one_keeps_every_spike() -> None:
+    s = np.zeros(100)
+    s[[5, 50, 90]] = 1.0
+    bursts = detect_bursts(s, 30.0, 0.1, min_burst_spikes=1)
+    assert [b["start_frame"] for b in bursts] == [5, 50, 90]
+    assert all(b["n_spikes"] == 1 and b["peak_rate_hz"] == 0.0 for b in bursts)
+
+
+def test_select_lambda_cv_rejects_extremes() -> None:
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    n = 900
+    rng = np.random.default_rng(7)
+    trace = rng.uniform(-0.05, 0.05, n)
+    for j, s in enumerate(range(20, n, 47)):
+        seg = kernel[: n - s]
+        trace[s : s + len(seg)] += (0.6 + 0.2 * (j % 3)) * seg
+    # A lambda far below the noise level overfits the held-out folds.
+    assert select_lambda_cv(trace, 0.02, 0.4, 30.0, [50.0, 0.3, 0.001]) in (50.0, 0.3)
+    assert math.isnan(select_lambda_cv(np.ones(1), 0.02, 0.4, 30.0, [0.1]))
+    with pytest.raises(ValueError):
+        select_lambda_cv(trace, 0.02, 0.4, 30.0, [])
+
+
+def test_event_aligned_average_and_raster() -> None:
+    trace = np.arange(50, dtype=np.float64)
+    events = [2, 10, 20, 30, 48]  # first and last run off the edges
+    raster = event_aligned_raster(trace, events, 3, 4)
+    assert raster.shape == (3, 7)
+    assert_array_equal(raster[0], np.arange(7, 14))
+
+    mean, sem = event_aligned_average(trace, events, 3, 4)
+    assert_allclose(mean, raster.mean(axis=0), rtol=1e-6)
+    assert_allclose(sem, raster.std(axis=0, ddof=1) / np.sqrt(3), rtol=1e-5)
+
+    assert event_aligned_raster(trace, [], 3, 4).shape == (0, 7)
+    mean, _ = event_aligned_average(trace, [1], 3, 4)
+    assert np.all(np.isnan(mean))
+
+
+def test_spike_triggered_average_recovers_kernel() -> None:
+    kernel = np.array([0.0, 1.0, 0.6, 0.3])
+    s = np.zeros(60)
+    s[[1, 10, 25, 40, 58]] = 1.0  # 1 and 58 run off the edges
+    s[33] = 0.2  # below min_count
+    trace = np.convolve(s, kernel)[:60]
+    mean, sem = spike_triggered_average(trace, s, 2, 3)
+    assert mean.shape == (6,)
+    assert_allclose(mean, [0.0, 0.0, *kernel], atol=1e-6)
+    assert_allclose(sem, 0.0, atol=1e-6)
+
+    mean, sem = spike_triggered_average(trace, np.zeros(60), 2, 3)
+    assert np.isnan(mean).all() and np.isnan(sem).all()
+
+
+def test_pairwise_correlations_match_numpy() -> None:
+    rng = np.random.default_rng(5)
+    spikes = (rng.random((4, 200)) < 0.1).astype(np.float64)
+    spikes[1] = np.maximum(spikes[1], spikes[0])  # a correlated pair
+    binned = spikes[:, :198].reshape(4, 66, 3).sum(axis=2)
+    expected = np.corrcoef(binned)[np.triu_indices(4, k=1)]
+
+    r = cell_pair_correlations(spikes, 3)
+    assert r.shape == (6,)
+    assert_allclose(r, expected, rtol=1e-9)
+    assert_allclose(pairwise_correlation_index(spikes, 3), expected.mean(), rtol=1e-9)
+
+    spikes[2] = 0.0  # silent cell: its pairs are NaN and skipped
+    r = cell_pair_correlations(spikes, 3)
+    assert np.isnan(r[[1, 3, 5]]).all()
+    assert_allclose(pairwise_correlation_index(spikes, 3), np.nanmean(r), rtol=1e-9)
+    assert math.isnan(pairwise_correlation_index(spikes[:1], 3))
+
+
+def test_rolling_variance_matches_numpy() -> None:
+    rng = np.random.default_rng(3)
+    trace = rng.normal(0.0, 0.5, 300)
+    window = 25
+    var = rolling_variance(trace, window)
+    expected = np.lib.stride_tricks.sliding_window_view(trace, window).var(axis=1)
+    assert var.shape == trace.shape
+    assert_allclose(var[window - 1 :], expected, atol=1e-5)
+    assert_allclose(var[: window - 1], expected[0], atol=1e-5)
+    assert_allclose(rolling_std(trace, window), np.sqrt(var), atol=1e-6)
+    # A window past the end uses the whole trace.
+    assert_allclose(rolling_variance(trace, 10_000), trace.var(), rtol=1e-4)
+
+
+def test_estimate_noise_floor() -> None:
+    trace = np.tile([0.1, -0.1], 1000)
+    for s in range(100, 2000, 400):
+        trace[s : s + 60] += 5.0 * np.exp(-np.arange(60) / 15.0)
+    assert abs(estimate_noise_floor(trace, 20, 10.0) - 0.1) < 0.01
+    assert math.isnan(estimate_noise_floor(np.array([]), 20))
+    with pytest.raises(ValueError, match="percentile"):
+        estimate_noise_floor(trace, 20, 150.0)
+
+
+def test_anscombe_stabilizes_poisson() -> None:
+    rng = np.random.default_rng(5)
+    for lam in (5.0, 50.0, 400.0):
+        counts = rng.poisson(lam, 20_000).astype(np.float64)
+        assert abs(anscombe_transform(counts).var() - 1.0) < 0.1
+    x = np.array([0.0, 3.0, 100.0, 1e4])
+    y = anscombe_transform(x)
+    assert_allclose(y, 2.0 * np.sqrt(x + 0.375), rtol=1e-6)
+    back = anscombe_inverse_transform(y)
+    assert np.all(back >= x - 1e-3)
+    assert np.all(back - x < 0.25)
+    assert_array_equal(anscombe_inverse_transform(np.array([-1.0, 0.0])), [0.0, 0.0])
+
+
+def test_compute_dff() -> None:
+    trace = np.full(100, 2.0)
+    trace[40] = 3.0
+    assert_allclose(compute_dff_rolling(trace, 20)[40], 0.5, atol=1e-6)
+    dff = compute_dff_global(trace, 2.0)
+    assert dff[40] == 0.5 and dff[0] == 0.0
+    with pytest.warns(RuntimeWarning, match="F0 below"):
+        assert_array_equal(compute_dff_global(trace, 0.0), trace)
+    with pytest.raises(ValueError, match="quantile"):
+        compute_dff_rolling(trace, 20, 1.5)
+
+
+def test_normalisations() -> None:
+    trace = np.array([1.0, 2.0, 3.0, 4.0, 100.0])
+    assert_allclose(zscore(trace), (trace - trace.mean()) / trace.std(), rtol=1e-5)
+    assert_allclose(robust_zscore(trace), (trace - 3.0) / 1.4826, rtol=1e-5)
+    assert_allclose(normalize_dff(np.array([3.0, 2.0]), np.array([2.0, 2.0])), [0.5, 0.0])
+    for fn in (zscore, robust_zscore):
+        with pytest.raises(ValueError):
+            fn(np.array([]))
+    with pytest.raises(ValueError):
+        normalize_dff(trace, np.ones(4))
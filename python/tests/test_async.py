@@ -0,0 +1,63 @@
+"""Tests for the awaitable solver wrappers."""
+
+from __future__ import annotations
+
+import asyncio
+import inspect
+
+import numpy as np
+import numpy.testing as npt
+import pytest
+
+from calab import (
+    async_deconvolve_batch,
+    async_deconvolve_single,
+    async_solve_trace,
+    build_kernel,
+    run_deconvolution_full,
+    solve_trace,
+)
+
+
+def _trace(n: int = 200) -> np.ndarray:
+    kernel = np.asarray(build_kernel(0.02, 0.4, 30.0), dtype=np.float64)
+    spikes = np.zeros(n)
+    spikes[[30, 90, 150]] = 1.0
+    return np.convolve(spikes, kernel)[:n]
+
+
+def test_async_results_match_blocking_calls():
+    trace = _trace()
+    traces = np.stack([trace, np.roll(trace, 20)])
+
+    async def run():
+        return await asyncio.gather(
+            async_deconvolve_single(trace, 30.0, 0.02, 0.4, 0.01),
+            async_deconvolve_batch(traces, 30.0, 0.02, 0.4, 0.01),
+            async_solve_trace(trace, 0.02, 0.4, 30.0),
+        )
+
+    single, batch, indeca = asyncio.run(run())
+    full = run_deconvolution_full(trace, 30.0, 0.02, 0.4, 0.01)
+    npt.assert_allclose(single.s_counts, full.activity, rtol=1e-6)
+    assert batch.activity.shape == traces.shape
+    npt.assert_array_equal(indeca.s_counts, solve_trace(trace, 0.02, 0.4, 30.0).s_counts)
+
+
+def test_calls_inside_a_running_loop_return_awaitables():
+    trace = _trace()
+
+    async def run():
+        pending = async_solve_trace(trace, 0.02, 0.4, 30.0)
+        assert inspect.isawaitable(pending)
+        return await pending
+
+    result = asyncio.run(run())
+    assert result.s_counts.shape == trace.shape
+
+
+def test_without_event_loop_runs_blocking_with_warning():
+    trace = _trace()
+    with pytest.warns(DeprecationWarning, match="outside a running event loop"):
+        result = async_solve_trace(trace, 0.02, 0.4, 30.0)
+    assert result.s_counts.shape == trace.shape
@@ -7,8 +7,9 @@ DC removal, short trace handling, and invalid cutoff handling.
 from __future__ import annotations
 
 import numpy as np
+import pytest
 
-from calab import bandpass_filter
+from calab import bandpass_filter, exponential_smooth, median_filter, savitzky_golay
 
 # ---------------------------------------------------------------------------
 # Test 1: Passband preservation
@@ -105,3 +106,64 @@ def test_output_length_matches_input():
 
     filtered = bandpass_filter(trace, tau_rise=0.02, tau_decay=0.4, fs=100.0)
     assert len(filtered) == n
+
+
+# ---------------------------------------------------------------------------
+# Test 7: Savitzky-Golay passes low-degree polynomials unchanged
+# ---------------------------------------------------------------------------
+
+def test_savitzky_golay_recovers_polynomial():
+    """A cubic survives window-11, order-3 smoothing, ends included."""
+    t = np.linspace(-0.5, 0.5, 60)
+    trace = 2.0 - 3.0 * t + 4.0 * t**2 - 5.0 * t**3
+
+    smoothed = savitzky_golay(trace, window=11, poly_order=3)
+    np.testing.assert_allclose(smoothed, trace, atol=1e-4)
+
+
+def test_savitzky_golay_rejects_order_at_least_window():
+    with pytest.raises(ValueError):
+        savitzky_golay(np.zeros(50), window=5, poly_order=5)
+
+
+# ---------------------------------------------------------------------------
+# Test 8: Exponential smoothing
+# ---------------------------------------------------------------------------
+
+def test_exponential_smooth_matches_recurrence():
+    trace = np.array([1.0, 0.0, 0.0, 2.0])
+    np.testing.assert_allclose(exponential_smooth(trace, 0.5), [1.0, 0.5, 0.25, 1.125])
+    np.testing.assert_allclose(exponential_smooth(np.full(20, 3.0), 0.3, zero_phase=True), 3.0)
+    with pytest.raises(ValueError):
+        exponential_smooth(trace, 0.0)
+
+
+def test_zero_phase_exponential_attenuates_quarter_rate():
+    """alpha=0.3 forward-backward: > 20 dB down at fs/4."""
+    t = np.arange(2048)
+    trace = np.sin(np.pi / 2 * t)
+    out = exponential_smooth(trace, 0.3, zero_phase=True)
+    ratio = np.std(trace[256:-256]) / np.std(out[256:-256])
+    assert 20.0 * np.log10(ratio) > 20.0
+
+
+# ---------------------------------------------------------------------------
+# Test 9: Median filter removes impulse artifacts
+# ---------------------------------------------------------------------------
+
+def test_median_filter_removes_impulses():
+    """Isolated 5x artifacts end up within 2x the clean range."""
+    rng = np.random.default_rng(2)
+    clean = 0.8 * np.sin(2 * np.pi * 0.5 * np.arange(400) / 30.0) + 0.2 * rng.normal(size=400)
+    span = np.abs(clean).max()
+    trace = clean.copy()
+    trace[20::37] += 5.0 * span
+    filtered = median_filter(trace, 3)
+    assert filtered.shape == trace.shape
+    assert np.abs(filtered).max() <= 2.0 * span
+
+    small = np.array([4.0, -1.0, 7.0, 7.0, 2.0, 9.0, -3.0])
+    padded = np.concatenate([np.zeros(2), small, np.zeros(2)])
+    expected = np.median(np.lib.stride_tricks.sliding_window_view(padded, 5), axis=1)
+    np.testing.assert_array_equal(median_filter(small, 2), expected)
+    np.testing.assert_array_equal(median_filter(small, 0), small)
@@ -198,6 +198,24 @@ def test_multi_trace_2d_input():
         )
 
 
+def test_multi_trace_parallel_matches_sequential():
+    """n_jobs != 1 routes through the Rayon binding with identical results."""
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    n = 200
+    traces = np.zeros((4, n))
+    for i, loc in enumerate([30, 80, 140, 170]):
+        activity = np.zeros(n)
+        activity[loc] = 1.0
+        traces[i] = np.convolve(activity, kernel)[:n]
+
+    seq = run_deconvolution(traces, 30.0, 0.02, 0.4, 0.01)
+    par = run_deconvolution(traces, 30.0, 0.02, 0.4, 0.01, n_jobs=-1)
+    npt.assert_array_equal(seq, par)
+
+    with pytest.raises(ValueError):
+        run_deconvolution(traces, 30.0, 0.02, 0.4, 0.01, n_jobs=0)
+
+
 # ---------------------------------------------------------------------------
 # Test 9: Various parameter sets
 # ---------------------------------------------------------------------------
@@ -325,3 +343,541 @@ def test_full_result_multi_trace():
     assert result.reconvolution.shape == (2, n)
     assert result.iterations.shape == (2,)
     assert result.converged.shape == (2,)
+
+
+# ---------------------------------------------------------------------------
+# Test 15: deconvolve_single result object and tuple alias
+# ---------------------------------------------------------------------------
+
+def test_deconvolve_single_result_object():
+    """PyInDecaResult attributes agree with the backward-compatible tuple form."""
+    from calab._solver import deconvolve_single, deconvolve_single_tuple
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150])
+
+    result = deconvolve_single(trace, 30.0, 0.02, 0.4, 0.01)
+    activity, baseline, reconvolution, iterations, converged = deconvolve_single_tuple(
+        trace, 30.0, 0.02, 0.4, 0.01,
+    )
+    npt.assert_array_equal(result.s_counts, activity)
+    npt.assert_array_equal(result.reconvolution, reconvolution)
+    assert result.baseline == baseline
+    assert result.iterations == iterations
+    assert result.converged == converged
+    assert result.pve > 0.5
+
+    as_dict = result.to_dict()
+    assert set(as_dict) >= {"s_counts", "alpha", "pve", "noise_std", "snr_db", "filtered_trace"}
+    assert as_dict["iterations"] == iterations
+    assert repr(result).startswith("PyInDecaResult(alpha=")
+
+
+# ---------------------------------------------------------------------------
+# Test 16: PySolver context manager resets on exit
+# ---------------------------------------------------------------------------
+
+def test_pysolver_context_manager_resets_and_propagates():
+    """``with PySolver() as s`` resets the solve state on exit without
+    suppressing exceptions."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150]).astype(np.float32)
+
+    solver = PySolver()
+    with solver as s:
+        assert s is solver
+        s.set_params(0.02, 0.4, 0.01, 30.0)
+        s.set_trace(trace)
+        assert s.solve(500) > 0
+    assert solver.iteration_count() == 0
+    assert not solver.converged()
+    assert not np.any(solver.get_solution())
+
+    with pytest.raises(RuntimeError, match="inside"):
+        with solver:
+            raise RuntimeError("raised inside the block")
+
+
+# ---------------------------------------------------------------------------
+# Test 17: Banded batches of eight match per-cell solves
+# ---------------------------------------------------------------------------
+
+def test_banded_batch_groups_match_single_solves():
+    """With conv_mode="banded", ``deconvolve_batch`` shares the final
+    reconvolution across groups of eight cells; results must match solving
+    each cell on its own (up to f32 rounding in the reconvolution)."""
+    from calab._solver import deconvolve_batch, deconvolve_single
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    n = 200
+    # 11 cells: one group of eight plus three handled one at a time.
+    traces = np.stack([
+        make_synthetic_trace(kernel, n, [15 + 13 * i, 120 + 5 * i], [1.0, 0.5 + 0.1 * i]) + 0.1 * i
+        for i in range(11)
+    ])
+
+    activities, baselines, reconvolutions, iterations, _ = deconvolve_batch(
+        traces, 30.0, 0.02, 0.4, 0.01, conv_mode="banded"
+    )
+    for i in range(11):
+        single = deconvolve_single(traces[i], 30.0, 0.02, 0.4, 0.01, conv_mode="banded")
+        npt.assert_array_equal(activities[i], single.s_counts)
+        assert iterations[i] == single.iterations
+        npt.assert_allclose(reconvolutions[i], single.reconvolution, rtol=1e-5, atol=1e-6)
+        assert baselines[i] == pytest.approx(single.baseline, rel=1e-5, abs=1e-6)
+
+
+# ---------------------------------------------------------------------------
+# Test 18: Sparse solution getters
+# ---------------------------------------------------------------------------
+
+def test_pysolver_sparse_solution_matches_dense():
+    """``get_sparse_solution`` lists exactly the dense entries above threshold."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150]).astype(np.float32)
+
+    solver = PySolver()
+    solver.set_params(0.02, 0.4, 0.01, 30.0)
+    solver.set_trace(trace)
+    solver.solve(500)
+    dense = solver.get_solution()
+
+    sparse = solver.get_sparse_solution()
+    expected = [(i, float(v)) for i, v in enumerate(dense) if v >= np.float32(1e-6)]
+    assert [i for i, _ in sparse] == [i for i, _ in expected]
+    npt.assert_allclose([v for _, v in sparse], [v for _, v in expected])
+    assert solver.get_spike_count() == len(sparse)
+    assert solver.get_spike_count(threshold=np.inf) == 0
+
+
+# ---------------------------------------------------------------------------
+# Test 19: Dual-gap stopping criterion
+# ---------------------------------------------------------------------------
+
+def test_pysolver_dual_gap_stopping():
+    """The dual-gap criterion converges to the same solution as the default."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150]).astype(np.float32)
+
+    def solve(criterion: str | None) -> tuple[np.ndarray, int]:
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        if criterion is not None:
+            solver.set_stopping_criterion(criterion, max_iters_between_checks=5)
+        solver.set_trace(trace)
+        iters = solver.solve(5000)
+        assert solver.converged()
+        return solver.get_solution(), iters
+
+    default, _ = solve(None)
+    gap, gap_iters = solve("dual_gap")
+    assert gap_iters % 5 == 0
+    npt.assert_allclose(gap, default, atol=0.05)
+
+    with pytest.raises(ValueError):
+        PySolver().set_stopping_criterion("bogus")
+
+
+# ---------------------------------------------------------------------------
+# Test 20: Backtracking line search
+# ---------------------------------------------------------------------------
+
+def test_pysolver_line_search_matches_fixed_step():
+    """With the exact Lipschitz constant, line search never backtracks and
+    reproduces the fixed-step solve."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150]).astype(np.float32)
+
+    solutions = []
+    for line_search in (False, True):
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        solver.set_line_search(line_search)
+        solver.set_trace(trace)
+        solver.solve(2000)
+        solutions.append(solver.get_solution())
+    npt.assert_array_equal(solutions[0], solutions[1])
+
+
+# ---------------------------------------------------------------------------
+# Test 21: Stochastic block solver
+# ---------------------------------------------------------------------------
+
+def test_pysolver_sfista_matches_fista():
+    """SVRG block steps converge to the full-gradient FISTA solution."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 1000, [60, 220, 380, 540, 700, 860]).astype(np.float32)
+
+    solutions = []
+    for mode in ("fista", "sfista"):
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        solver.set_solver_mode(mode, block_size=256)
+        solver.set_trace(trace)
+        solver.solve(20000)
+        assert solver.converged()
+        solutions.append(solver.get_solution())
+    err = np.linalg.norm(solutions[1] - solutions[0]) / np.linalg.norm(solutions[0])
+    assert err < 0.02
+
+    with pytest.raises(ValueError):
+        PySolver().set_solver_mode("sfista", variance_reduction="saga")
+
+
+# ---------------------------------------------------------------------------
+# Test 22: Elastic-net regularizer
+# ---------------------------------------------------------------------------
+
+def test_pysolver_elastic_net_shrinks_solution():
+    """The ridge term shrinks the solution; lambda2=0 reproduces plain L1."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 300, [20, 90, 95, 200]).astype(np.float32)
+
+    solutions = []
+    for regularizer, lambda2 in (("l1", 0.0), ("elastic_net", 0.0), ("elastic_net", 5.0)):
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        solver.set_regularizer(regularizer, lambda2=lambda2)
+        solver.set_trace(trace)
+        solver.solve(5000)
+        solutions.append(solver.get_solution())
+    npt.assert_array_equal(solutions[0], solutions[1])
+    assert np.sum(solutions[2] ** 2) < np.sum(solutions[0] ** 2)
+
+    with pytest.raises(ValueError):
+        PySolver().set_regularizer("elastic_net", lambda2=-1.0)
+    with pytest.raises(ValueError):
+        PySolver().set_regularizer("ridge")
+
+
+# ---------------------------------------------------------------------------
+# Test 23: Least-squares debiasing
+# ---------------------------------------------------------------------------
+
+def test_pysolver_debiasing_restores_amplitude():
+    """Debiasing undoes the L1 shrinkage of an isolated spike."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 300, [100]).astype(np.float32)
+
+    solver = PySolver()
+    solver.set_params(0.02, 0.4, 0.05, 30.0)
+    solver.set_trace(trace)
+    solver.solve(5000)
+    shrunk = solver.get_solution()[98:103].sum()
+    assert not solver.debiased()
+
+    assert solver.run_debiasing() > 0
+    assert solver.debiased()
+    debiased = solver.get_solution()[98:103].sum()
+    assert shrunk < 0.95
+    assert abs(debiased - 1.0) < 0.02
+
+
+
+# ---------------------------------------------------------------------------
+# Test 24: Lipschitz getter and power-iteration re-estimate
+# ---------------------------------------------------------------------------
+
+@pytest.mark.parametrize("mode", ["fft", "banded"])
+def test_pysolver_reestimate_lipschitz(mode):
+    """Power iteration agrees with the analytic constant in FFT and banded modes."""
+    from calab import compute_lipschitz, compute_lipschitz_banded
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 2000, [100, 900]).astype(np.float32)
+
+    solver = PySolver()
+    solver.set_conv_mode(mode)
+    solver.set_params(0.02, 0.4, 0.01, 30.0)
+    solver.set_trace(trace)
+    analytic = solver.get_lipschitz()
+    if mode == "fft":
+        expected = compute_lipschitz(kernel)
+    else:
+        expected = compute_lipschitz_banded(0.02, 0.4, 30.0)
+    npt.assert_allclose(analytic, expected, rtol=1e-6)
+
+    estimate = solver.reestimate_lipschitz(300)
+    assert solver.get_lipschitz() == estimate
+    assert 0.95 * analytic <= estimate <= 1.001 * analytic
+
+
+# ---------------------------------------------------------------------------
+# Test 25: Kernel normalization modes
+# ---------------------------------------------------------------------------
+
+@pytest.mark.parametrize("mode", ["fft", "banded"])
+def test_pysolver_kernel_norm(mode):
+    """Area and L2 normalization give unit area / unit energy and rescale L."""
+    from calab._solver import PySolver
+
+    fs = 30.0
+    solver = PySolver()
+    solver.set_conv_mode(mode)
+    solver.set_params(0.02, 0.4, 0.01, fs)
+    peak_lipschitz = solver.get_lipschitz()
+
+    solver.set_kernel_norm("area")
+    kernel = solver.get_kernel().astype(np.float64)
+    npt.assert_allclose(kernel.sum() / fs, 1.0, rtol=1e-4)
+    npt.assert_allclose(solver.get_lipschitz(), peak_lipschitz * kernel.max() ** 2, rtol=1e-4)
+
+    solver.set_kernel_norm("l2")
+    kernel = solver.get_kernel().astype(np.float64)
+    npt.assert_allclose((kernel**2).sum(), 1.0, rtol=1e-4)
+
+    solver.set_kernel_norm("peak")
+    npt.assert_allclose(solver.get_kernel().max(), 1.0, rtol=1e-6)
+    npt.assert_allclose(solver.get_lipschitz(), peak_lipschitz, rtol=1e-9)
+
+    with pytest.raises(ValueError):
+        solver.set_kernel_norm("max")
+
+
+# ---------------------------------------------------------------------------
+# Test 26: Low memory mode (overlap-add FFT)
+# ---------------------------------------------------------------------------
+
+def test_pysolver_low_memory_matches_fft():
+    """Overlap-add FFT gives the same solution as the whole-trace FFT."""
+    from calab._solver import PySolver
+
+    kernel = np.asarray(build_kernel(0.02, 0.4, 30.0))
+    n = 20 * len(kernel)
+    trace = np.zeros(n)
+    for s in [40, 700, 1500, n - 60]:
+        end = min(n, s + len(kernel))
+        trace[s:end] += kernel[: end - s]
+
+    solutions = []
+    for memory_mode in ["standard", "low"]:
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        solver.set_conv_mode("fft")
+        solver.set_memory_mode(memory_mode)
+        solver.set_trace(trace)
+        solver.solve(5000)
+        solutions.append(solver.get_solution())
+    npt.assert_allclose(solutions[1], solutions[0], atol=1e-3)
+
+    with pytest.raises(ValueError):
+        PySolver().set_memory_mode("tiny")
+
+
+# ---------------------------------------------------------------------------
+# Test 27: Refractory period projection
+# ---------------------------------------------------------------------------
+
+def test_pysolver_refractory_frames():
+    """Nonzero solution entries stay at least n frames apart."""
+    from calab._solver import PySolver
+
+    kernel = np.asarray(build_kernel(0.02, 0.4, 30.0))
+    trace = np.zeros(400)
+    for s in [50, 52, 150, 300, 303]:
+        end = min(400, s + len(kernel))
+        trace[s:end] += kernel[: end - s]
+
+    solver = PySolver()
+    solver.set_params(0.02, 0.4, 0.01, 30.0)
+    solver.set_refractory_frames(5)
+    assert solver.refractory_frames() == 5
+    solver.set_trace(trace)
+    solver.solve(3000)
+    support = np.flatnonzero(solver.get_solution() > 0)
+    assert support.size > 0
+    assert np.all(np.diff(support) >= 5)
+
+
+# ---------------------------------------------------------------------------
+# Test 28: PyInDecaResult JSON round trip
+# ---------------------------------------------------------------------------
+
+def test_deconvolve_single_result_json_round_trip():
+    """to_json/from_json restore arrays bit-exactly and drop reconvolution."""
+    import json
+
+    from calab._solver import PyInDecaResult, deconvolve_single
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 200, [30, 90, 150])
+    result = deconvolve_single(trace, 30.0, 0.02, 0.4, 0.01)
+
+    text = result.to_json()
+    assert json.loads(text)["iterations"] == result.iterations
+
+    restored = PyInDecaResult.from_json(text)
+    npt.assert_array_equal(restored.s_counts, result.s_counts)
+    npt.assert_array_equal(restored.filtered_trace, result.filtered_trace)
+    assert restored.pve == result.pve
+    assert restored.converged == result.converged
+    assert restored.reconvolution is None
+
+    with pytest.raises(ValueError):
+        PyInDecaResult.from_json("{}")
+
+
+# ---------------------------------------------------------------------------
+# Test 29: solver checkpoints
+# ---------------------------------------------------------------------------
+
+def test_solver_checkpoint_resumes(tmp_path):
+    """A loaded checkpoint continues the solve exactly where it stopped."""
+    from calab._solver import PySolver, py_load_checkpoint, py_save_checkpoint
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 300, [30, 120, 200])
+
+    solver = PySolver()
+    solver.set_params(0.02, 0.4, 0.02, 30.0)
+    solver.set_trace(trace)
+    solver.step_batch(20)
+
+    path = tmp_path / "solver.ckpt"
+    py_save_checkpoint(solver, str(path))
+    loaded = py_load_checkpoint(str(path))
+    assert loaded.iteration_count() == 20
+
+    solver.step_batch(15)
+    loaded.step_batch(15)
+    npt.assert_array_equal(loaded.get_solution(), solver.get_solution())
+
+    path.write_bytes(b"not a checkpoint")
+    with pytest.raises(ValueError):
+        py_load_checkpoint(str(path))
+
+
+# ---------------------------------------------------------------------------
+# Test 30: kernel length helper
+# ---------------------------------------------------------------------------
+
+def test_suggest_kernel_length_matches_build_kernel():
+    """The suggested length is the one build_kernel produces."""
+    from calab import suggest_kernel_length
+
+    for tau_decay, fs in [(0.4, 30.0), (1.2, 100.0), (0.001, 10.0)]:
+        assert suggest_kernel_length(tau_decay, fs) == len(build_kernel(0.0, tau_decay, fs))
+    assert suggest_kernel_length(0.001, 10.0) == 2
+
+
+
+# ---------------------------------------------------------------------------
+# Test 31: lambda sensitivity sweep
+# ---------------------------------------------------------------------------
+
+def test_lambda_sensitivity_sweep():
+    """Lower lambdas explain more variance; the DataFrame mirrors the arrays."""
+    from calab import lambda_sensitivity, lambda_sensitivity_arrays
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 400, [40, 150, 260, 330])
+
+    cols = lambda_sensitivity_arrays(trace, 30.0, 0.02, 0.4, 1e-3, 1.0, n_lambdas=6)
+    assert list(cols) == ["lambda", "n_spikes", "pve", "alpha", "baseline", "iterations"]
+    assert np.all(np.diff(cols["lambda"]) < 0)
+    npt.assert_allclose(cols["lambda"][[0, -1]], [1.0, 1e-3])
+    assert cols["pve"][-1] > cols["pve"][0]
+    assert cols["n_spikes"][-1] > 0
+    assert np.all(cols["iterations"] > 0)
+
+    with pytest.raises(ValueError):
+        lambda_sensitivity_arrays(trace, 30.0, 0.02, 0.4, 1.0, 0.1)
+
+    pd = pytest.importorskip("pandas")
+    df = lambda_sensitivity(trace, 30.0, 0.02, 0.4, 1e-3, 1.0, n_lambdas=6)
+    assert isinstance(df, pd.DataFrame)
+    npt.assert_array_equal(df["n_spikes"].to_numpy(), cols["n_spikes"])
+
+
+# ---------------------------------------------------------------------------
+# Test 32: convergence history
+# ---------------------------------------------------------------------------
+
+def test_pysolver_history():
+    """enable_history keeps the newest records as dicts; clear_history empties it."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 500, [40, 150, 320]).astype(np.float32)
+
+    solver = PySolver()
+    solver.set_params(0.02, 0.4, 0.01, 30.0)
+    solver.set_trace(trace)
+    assert solver.get_history() == []
+
+    solver.enable_history(8)
+    solver.step_batch(20)
+    history = solver.get_history()
+    assert len(history) == 8
+    assert set(history[0]) == {"iteration", "diff_sq_rel", "t_fista", "restarted", "objective"}
+    assert [r["iteration"] for r in history] == list(range(13, 21))
+    assert history[-1]["objective"] == solver.get_objective()
+    assert solver.compute_objective() == solver.get_objective()
+
+    solver.clear_history()
+    assert solver.get_history() == []
+
+
+# ---------------------------------------------------------------------------
+# Test 33: Adaptive restart threshold
+# ---------------------------------------------------------------------------
+
+def test_pysolver_restart_threshold():
+    """A restart margin is stored, clamped at 0, and still converges."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 400, [50, 150, 300])
+    trace = trace + np.random.default_rng(0).normal(0.0, 0.1, 400)
+
+    solver = PySolver()
+    assert solver.restart_threshold() == 0.0
+    solver.set_restart_threshold(-1.0)
+    assert solver.restart_threshold() == 0.0
+    solver.set_params(0.02, 0.4, 0.01, 30.0)
+    solver.set_restart_threshold(0.1)
+    assert solver.restart_threshold() == 0.1
+    solver.set_trace(trace)
+    solver.solve(3000)
+    assert solver.converged()
+
+
+# ---------------------------------------------------------------------------
+# Test 34: Diagonally preconditioned FISTA
+# ---------------------------------------------------------------------------
+
+def test_pysolver_diagonal_matches_fista():
+    """Per-frame diagonal steps reach the FISTA objective, tail spikes included."""
+    from calab._solver import PySolver
+
+    kernel = build_kernel(0.02, 0.4, 30.0)
+    trace = make_synthetic_trace(kernel, 600, [40, 150, 400, 592]).astype(np.float32)
+
+    objectives = []
+    for mode in ("fista", "diagonal"):
+        solver = PySolver()
+        solver.set_params(0.02, 0.4, 0.01, 30.0)
+        solver.set_solver_mode(mode)
+        solver.set_trace(trace)
+        solver.solve(5000)
+        assert solver.converged()
+        objectives.append(solver.compute_objective())
+    assert objectives[1] <= objectives[0] * (1 + 1e-3)
+
+    with pytest.raises(ValueError, match="diagonal"):
+        PySolver().set_solver_mode("jacobi")
@@ -45,6 +45,7 @@ def test_seed_kernel_estimate_rejects_nan():
     # The 2D auto-estimate path builds its flat buffer inline (not via the
     # shared 1D converter), so it needs its own guard.
     import calab._solver as _solver
+from calab import validate_params
 
     traces = np.zeros((2, 100), dtype=np.float64)
     traces[0, 30] = np.inf
@@ -59,3 +60,24 @@ def test_pysolver_set_trace_rejects_nan():
     trace = np.array([0.0, 1.0, np.nan], dtype=np.float32)
     with pytest.raises(ValueError, match="non-finite"):
         solver.set_trace(trace)
+
+
+def test_solver_builder_reports_missing_tau_decay():
+    import calab._solver as _solver
+
+    builder = _solver.PySolverBuilder().tau_rise(0.02).lambda_(0.01).fs(30.0)
+    with pytest.raises(ValueError, match="tau_decay"):
+        builder.build()
+
+    solver = builder.tau_decay(0.4).conv_mode("banded").build()
+    solver.set_trace(np.zeros(100, dtype=np.float32))
+    assert solver.solve(100) > 0
+
+
+def test_validate_params_reports_errors_and_warnings() -> None:
+    assert validate_params(0.1, 0.6, 0.01, 30.0) == []
+    errors = validate_params(0.5, 0.4, 0.01, 30.0)
+    assert [(d["severity"], d["field"]) for d in errors] == [("error", "tau_rise")]
+    warnings = validate_params(0.1, 0.6, 1e6, 30.0)
+    assert [(d["severity"], d["field"]) for d in warnings] == [("warning", "lambda")]
+    assert "all zero" in warnings[0]["message"]
@@ -14,7 +14,16 @@ import numpy as np
 import numpy.testing as npt
 import pytest
 
-from calab import build_kernel, load_tuning_data, save_for_tuning
+from calab import (
+    build_kernel,
+    load_tuning_data,
+    read_csv_traces,
+    read_npy,
+    estimate_neuropil_coeff,
+    neuropil_correct,
+    read_suite2p,
+    save_for_tuning,
+)
 from calab._io import deconvolve_from_export, load_export_params
 
 # ---------------------------------------------------------------------------
@@ -327,3 +336,94 @@ def test_deconvolve_from_export_full(tmp_path: Path):
     assert hasattr(result, "reconvolution")
     assert hasattr(result, "iterations")
     assert hasattr(result, "converged")
+
+
+# ---------------------------------------------------------------------------
+# Rust .npy reader
+# ---------------------------------------------------------------------------
+
+@pytest.mark.parametrize("dtype", ["<f4", ">f4", "<f8", ">f8"])
+@pytest.mark.parametrize("order", ["C", "F"])
+def test_read_npy_matches_np_load(tmp_path: Path, dtype: str, order: str):
+    """read_npy handles both byte orders, float64 and Fortran order."""
+    arr = np.arange(12, dtype=np.float64).reshape(3, 4) * 0.5
+    path = tmp_path / "traces.npy"
+    np.save(path, np.asarray(arr, dtype=dtype, order=order))
+
+    loaded = read_npy(path)
+    assert loaded.dtype == np.float32
+    assert loaded.shape == (3, 4)
+    npt.assert_array_equal(loaded, arr.astype(np.float32))
+
+
+def test_read_npy_rejects_integer_arrays(tmp_path: Path):
+    path = tmp_path / "ints.npy"
+    np.save(path, np.arange(5, dtype=np.int32))
+    with pytest.raises(ValueError):
+        read_npy(path)
+
+
+# ---------------------------------------------------------------------------
+# Rust CSV reader
+# ---------------------------------------------------------------------------
+
+def test_read_csv_traces_column_per_cell(tmp_path: Path):
+    """Columns become cells; text columns are dropped; blanks become NaN."""
+    path = tmp_path / "traces.csv"
+    path.write_text("frame,roi1,roi2\nf0,1.0,2.0\nf1,,4.5\n")
+
+    traces, names = read_csv_traces(path)
+    assert names == ["roi1", "roi2"]
+    assert traces.shape == (2, 2)
+    assert traces[0, 0] == 1.0 and np.isnan(traces[0, 1])
+    npt.assert_array_equal(traces[1], [2.0, 4.5])
+
+
+def test_read_csv_traces_rejects_ragged_rows(tmp_path: Path):
+    path = tmp_path / "ragged.csv"
+    path.write_text("a,b\n1,2\n3\n")
+    with pytest.raises(ValueError):
+        read_csv_traces(path)
+
+
+# ---------------------------------------------------------------------------
+# Suite2p reader
+# ---------------------------------------------------------------------------
+
+def test_read_suite2p_corrects_neuropil(tmp_path: Path):
+    """Traces are F - coeff * Fneu; iscell comes from the first column."""
+    f = np.array([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]], dtype=np.float32)
+    fneu = np.ones_like(f)
+    np.save(tmp_path / "F.npy", f)
+    np.save(tmp_path / "Fneu.npy", fneu)
+    np.save(tmp_path / "iscell.npy", np.array([[1.0, 0.95], [0.0, 0.1]]))
+
+    traces, iscell = read_suite2p(tmp_path / "stat.npy", neuropil_coeff=0.5)
+    npt.assert_allclose(traces, f - 0.5)
+    npt.assert_array_equal(iscell, [True, False])
+
+
+def test_read_suite2p_without_fneu_returns_raw_f(tmp_path: Path):
+    f = np.arange(6, dtype=np.float32).reshape(2, 3)
+    np.save(tmp_path / "F.npy", f)
+
+    traces, iscell = read_suite2p(tmp_path / "stat.npy")
+    npt.assert_array_equal(traces, f)
+    assert iscell.all()
+
+
+def test_estimate_neuropil_coeff_recovers_contamination():
+    """The coefficient comes from quiet frames, so transients don't bias it."""
+    rng = np.random.default_rng(3)
+    t = np.arange(3000)
+    fneu = 1.0 + 0.3 * np.sin(0.05 * t) + 0.05 * rng.standard_normal(t.size)
+    f = 0.6 * fneu + 0.01 * rng.standard_normal(t.size)
+    for start in (300, 1200, 2100):
+        f[start : start + 150] += 4.0 * np.exp(-np.arange(150) / 30.0)
+
+    coeff = estimate_neuropil_coeff(f, fneu, window=20)
+    assert abs(coeff - 0.6) < 0.05
+    npt.assert_allclose(neuropil_correct(f, fneu, coeff), f - coeff * fneu, atol=1e-5)
+    with pytest.raises(ValueError):
+        neuropil_correct(f, fneu[:-1])
+
@@ -9,7 +9,20 @@ from __future__ import annotations
 import numpy as np
 from numpy.testing import assert_allclose
 
-from calab import build_kernel, tau_to_ar2, compute_lipschitz
+import pytest
+
+from calab import (
+    ar2_frequency_response,
+    ar_model_from_taus,
+    build_kernel,
+    check_adjoint,
+    check_ar2_stability,
+    compute_lipschitz,
+    fit_ar2_from_acf,
+    list_presets,
+    preset_params,
+    tau_to_ar2,
+)
 
 
 # --- build_kernel tests ---
@@ -181,3 +194,96 @@ def test_lipschitz_single_element() -> None:
     kernel = np.array([3.0])
     result = compute_lipschitz(kernel)
     assert_allclose(result, 9.0, rtol=1e-10)
+
+
+# --- indicator presets ---
+
+
+def test_presets_listed_and_interpolated() -> None:
+    """Mirrors Rust presets.rs: exact at measured fps, linear in between."""
+    assert "GCaMP6f" in list_presets()
+    assert preset_params("GCaMP6f", 30.0) == (0.1, 0.6)
+    tau_rise, tau_decay = preset_params("gcamp6f", 65.0)
+    assert_allclose([tau_rise, tau_decay], [0.075, 0.5], rtol=1e-10)
+    with pytest.raises(ValueError):
+        preset_params("GCaMP9", 30.0)
+
+
+# --- check_adjoint tests ---
+
+
+@pytest.mark.parametrize("mode", ["fft", "banded", "banded_ar1", "banded_arp"])
+def test_check_adjoint_engines(mode: str) -> None:
+    """Each convolution engine passes the dot-product adjoint test."""
+    err = check_adjoint(0.02, 0.4, 30.0, 256, mode)
+    assert 0.0 <= err < 1e-3
+
+
+def test_check_adjoint_overlap_add() -> None:
+    """Overlap-add FFT passes the adjoint test on a trace long enough to block."""
+    err = check_adjoint(0.02, 0.4, 30.0, 4000, "fft_overlap_add")
+    assert 0.0 <= err < 1e-3
+
+
+def test_check_adjoint_rejects_unknown_mode() -> None:
+    with pytest.raises(ValueError):
+        check_adjoint(0.02, 0.4, 30.0, 256, "wavelet")
+
+
+
+# --- ar_model_from_taus tests ---
+
+
+def test_ar_model_two_taus_match_kernel(standard_params: dict) -> None:
+    """An AR(2) model reproduces the double-exponential kernel."""
+    kernel = build_kernel(**standard_params)
+    taus = [standard_params["tau_decay"], standard_params["tau_rise"]]
+    response = ar_model_from_taus(taus, standard_params["fs"])
+    assert response.shape == kernel.shape
+    assert_allclose(response, kernel, atol=1e-5)
+
+
+def test_ar_model_three_taus() -> None:
+    """A three-component response starts at zero, peaks at 1.0 and decays."""
+    response = ar_model_from_taus([0.03, 0.3, 1.5], 30.0)
+    assert response[0] == 0.0
+    assert_allclose(response.max(), 1.0, rtol=1e-6)
+    assert abs(response[-1]) < 1e-4
+    with pytest.raises(ValueError):
+        ar_model_from_taus([], 30.0)
+    with pytest.raises(ValueError):
+        ar_model_from_taus([0.4, -0.1], 30.0)
+
+
+def test_check_ar2_stability() -> None:
+    """Positive taus give roots inside the unit circle; a negative tau does not."""
+    stable, margin = check_ar2_stability(0.02, 0.4, 30.0)
+    assert stable
+    assert_allclose(margin, 1.0 - np.exp(-1.0 / (30.0 * 0.4)), rtol=1e-12)
+    stable, margin = check_ar2_stability(0.02, -0.4, 30.0)
+    assert not stable
+    assert margin < 0.0
+
+
+def test_ar2_frequency_response() -> None:
+    """DC power is (1 / (1 - g1 - g2))^2 and the response falls toward Nyquist."""
+    g1, g2, _, _ = tau_to_ar2(0.02, 0.4, 30.0)
+    freqs = np.array([0.0, 1.0, 5.0, 15.0])
+    power = ar2_frequency_response(0.02, 0.4, 30.0, freqs)
+    w = 2.0 * np.pi * freqs / 30.0
+    expected = 1.0 / np.abs(1.0 - g1 * np.exp(-1j * w) - g2 * np.exp(-2j * w)) ** 2
+    assert_allclose(power, expected, rtol=1e-10)
+    assert_allclose(power[0], (1.0 / (1.0 - g1 - g2)) ** 2, rtol=1e-12)
+    assert np.all(np.diff(power) < 0.0)
+    with pytest.raises(RuntimeError):
+        ar2_frequency_response(0.02, -0.4, 30.0, freqs)
+
+
+def test_fit_ar2_from_acf_recovers_taus() -> None:
+    """The theoretical AR(2) autocorrelation gives back the generating taus."""
+    g1, g2, _, _ = tau_to_ar2(0.1, 0.8, 30.0)
+    r1 = g1 / (1.0 - g2)
+    tau_rise, tau_decay = fit_ar2_from_acf(np.array([1.0, r1, g1 * r1 + g2]), 30.0)
+    assert_allclose([tau_rise, tau_decay], [0.1, 0.8], rtol=1e-8)
+    with pytest.raises(ValueError):
+        fit_ar2_from_acf(np.array([1.0, 0.9, 0.81]), 30.0)
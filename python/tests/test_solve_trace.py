@@ -1,17 +1,27 @@
-"""Tests for InDeCa PyO3 bindings: solve_trace, estimate_kernel, fit_biexponential."""
+"""Tests for InDeCa PyO3 bindings: solve_trace, estimate_kernel, fit_biexponential,
+roc_threshold_curve."""
 
 from __future__ import annotations
 
 import numpy as np
+import pytest
 
 from calab import (
     BiexpFitResult,
+    CancelToken,
+    InDeCa,
     SolveTraceResult,
+    bootstrap_spike_ci,
     build_kernel,
     compute_upsample_factor,
     estimate_kernel,
     fit_biexponential,
+    joint_threshold_search,
+    roc_threshold_curve,
+    set_seed,
     solve_trace,
+    threshold_search,
+    threshold_search_custom_grid,
 )
 
 
@@ -79,12 +89,99 @@ class TestSolveTrace:
 
     def test_tuple_unpacking(self):
         trace = _make_trace(0.02, 0.4, 30.0, 200, [20, 80])
-        s_counts, alpha, baseline, threshold, pve, iterations, converged = solve_trace(
-            trace, 0.02, 0.4, 30.0,
-        )
+        (
+            s_counts, alpha, baseline, threshold, pve, iterations, converged, noise_std, snr_db,
+        ) = solve_trace(trace, 0.02, 0.4, 30.0)
         assert s_counts.shape == (200,)
         assert isinstance(alpha, float)
         assert isinstance(converged, bool)
+        assert isinstance(noise_std, float)
+        assert isinstance(snr_db, float)
+
+    def test_on_progress_reports_stages(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150])
+        events = []
+        result = solve_trace(trace, 0.02, 0.4, 30.0, on_progress=events.append)
+        stages = [e["stage"] for e in events]
+        assert stages[0] == "filter"
+        assert stages[-1] == "converged"
+        assert "scale_iteration" in stages
+        assert events[-1]["alpha"] == pytest.approx(result.alpha)
+
+    def test_pve_plateau_stops_scale_loop(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150])
+        events = []
+        solve_trace(
+            trace, 0.02, 0.4, 30.0, pve_min_delta=1.0, pve_patience=1, on_progress=events.append,
+        )
+        n_scale = sum(e["stage"] == "scale_iteration" for e in events)
+        assert n_scale <= 2
+        with pytest.raises(ValueError, match="pve_min_delta"):
+            solve_trace(trace, 0.02, 0.4, 30.0, pve_min_delta=float("nan"))
+
+    def test_alpha_init_strategies(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150], alpha=3.0, baseline=100.0)
+        for init, value in [("histogram", None), ("percentile", 99.0), ("user", 3.0)]:
+            result = solve_trace(trace, 0.02, 0.4, 30.0, alpha_init=init, alpha_init_value=value)
+            assert result.alpha == pytest.approx(3.0, rel=0.1)
+        with pytest.raises(ValueError, match="alpha_init_value"):
+            solve_trace(trace, 0.02, 0.4, 30.0, alpha_init="user")
+        with pytest.raises(ValueError, match="alpha_init"):
+            solve_trace(trace, 0.02, 0.4, 30.0, alpha_init="median")
+
+    def test_variance_stabilize_photon_counts(self):
+        clean = _make_trace(0.05, 0.5, 30.0, 600, [60, 200, 330, 470], alpha=60.0, baseline=20.0)
+        photons = np.random.default_rng(17).poisson(clean).astype(np.float64)
+        result = solve_trace(photons, 0.05, 0.5, 30.0, variance_stabilize=True)
+        for s in (60, 200, 330, 470):
+            assert result.s_counts[s - 1 : s + 2].sum() >= 1.0
+        # alpha is in Anscombe units, well below the 60-photon amplitude.
+        assert result.alpha < 20.0
+
+    @pytest.mark.filterwarnings("ignore::pytest.PytestUnraisableExceptionWarning")
+    def test_on_progress_exception_does_not_abort(self):
+        def boom(event):
+            raise RuntimeError("callback failure")
+
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150])
+        plain = solve_trace(trace, 0.02, 0.4, 30.0)
+        result = solve_trace(trace, 0.02, 0.4, 30.0, on_progress=boom)
+        np.testing.assert_array_equal(result.s_counts, plain.s_counts)
+
+    def test_cancelled_token_returns_zeroed_result(self):
+        token = CancelToken()
+        token.cancel()
+        assert token.is_cancelled()
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150])
+        result = solve_trace(trace, 0.02, 0.4, 30.0, cancel_token=token)
+        assert result.s_counts.shape == (300,)
+        assert result.s_counts.sum() == 0
+        assert result.alpha == 0.0
+
+    def test_cancel_from_progress_keeps_first_round(self):
+        token = CancelToken()
+        rounds = []
+
+        def on_progress(event):
+            if event["stage"] == "scale_iteration":
+                rounds.append(event)
+                token.cancel()
+
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [20, 80, 150], alpha=5.0, baseline=1.0)
+        result = solve_trace(
+            trace, 0.02, 0.4, 30.0, on_progress=on_progress, cancel_token=token,
+        )
+        assert len(rounds) == 1
+        assert result.alpha > 0
+
+    def test_noise_std_tracks_added_noise(self):
+        rng = np.random.default_rng(1)
+        clean = _make_trace(0.02, 0.4, 30.0, 600, [50, 200, 400], alpha=5.0, baseline=1.0)
+        trace = clean + rng.normal(0.0, 0.2, size=clean.shape)
+        result = solve_trace(trace, 0.02, 0.4, 30.0)
+        assert 0.1 < result.noise_std < 0.3
+        quieter = solve_trace(clean + 0.1 * rng.normal(0.0, 0.2, size=clean.shape), 0.02, 0.4, 30.0)
+        assert quieter.snr_db > result.snr_db
 
     def test_noise_constrained_accepted(self):
         # The noise_constrained knob is exposed through the binding and produces
@@ -111,6 +208,80 @@ class TestSolveTrace:
         assert result.s_counts.sum() >= 1
         assert result.pve > 0.5
 
+    @pytest.mark.parametrize("mode", ["zero", "mirror", "periodic"])
+    def test_boundary_mode_keeps_edge_spikes(self, mode):
+        trace = _make_trace(0.02, 0.4, 30.0, 400, [8, 120, 250, 392], alpha=4.0, baseline=1.0)
+        result = solve_trace(trace, 0.02, 0.4, 30.0, boundary_mode=mode)
+        assert result.s_counts.shape == (400,)
+        np.testing.assert_array_equal(np.flatnonzero(result.s_counts), [8, 120, 250, 392])
+
+    def test_boundary_mode_invalid(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [30, 100, 200])
+        with pytest.raises(ValueError, match="boundary_mode"):
+            solve_trace(trace, 0.02, 0.4, 30.0, boundary_mode="reflect")
+
+    @pytest.mark.parametrize("method", ["linear", "cubic"])
+    def test_upsample_method(self, method):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [30, 120, 210], alpha=4.0, baseline=1.0)
+        result = solve_trace(trace, 0.02, 0.4, 30.0, upsample_factor=4, upsample_method=method)
+        assert result.s_counts.shape == (300,)
+        np.testing.assert_array_equal(np.flatnonzero(result.s_counts), [30, 120, 210])
+
+    def test_upsample_method_invalid(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [30, 100, 200])
+        with pytest.raises(ValueError, match="upsample_method"):
+            solve_trace(trace, 0.02, 0.4, 30.0, upsample_method="quadratic")
+
+    def test_bootstrap_global_seed(self):
+        trace = _make_trace(0.02, 0.4, 30.0, 200, [30, 100, 160])
+        trace = trace + np.random.default_rng(2).normal(0.0, 0.05, trace.size)
+        runs = []
+        for _ in range(2):
+            set_seed(123)
+            runs.append(bootstrap_spike_ci(trace, 0.02, 0.4, 30.0, n_bootstrap=5))
+        np.testing.assert_array_equal(runs[0][0], runs[1][0])
+        np.testing.assert_array_equal(runs[0][1], runs[1][1])
+        # An explicit seed does not depend on the global stream.
+        explicit = bootstrap_spike_ci(trace, 0.02, 0.4, 30.0, n_bootstrap=5, seed=9)
+        again = bootstrap_spike_ci(trace, 0.02, 0.4, 30.0, n_bootstrap=5, seed=9)
+        np.testing.assert_array_equal(explicit[1], again[1])
+
+
+# ---------------------------------------------------------------------------
+# InDeCa estimator
+# ---------------------------------------------------------------------------
+
+
+class TestInDeCaEstimator:
+    def test_fit_transform_matches_solve_trace(self):
+        traces = np.stack([
+            _make_trace(0.02, 0.4, 30.0, 300, [30, 100, 200], alpha=5.0, baseline=1.0),
+            _make_trace(0.02, 0.4, 30.0, 300, [60, 250], alpha=3.0, baseline=0.5),
+        ])
+        model = InDeCa(0.02, 0.4, 30.0)
+        spikes = model.fit_transform(traces)
+        assert spikes.shape == (2, 300)
+        assert len(model.fit_results_) == 2
+        for row, trace, result in zip(spikes, traces, model.fit_results_):
+            np.testing.assert_array_equal(row, solve_trace(trace, 0.02, 0.4, 30.0).s_counts)
+            np.testing.assert_array_equal(row, result.s_counts)
+
+    def test_refit_replaces_results_and_accepts_1d(self):
+        model = InDeCa(0.02, 0.4, 30.0)
+        with pytest.raises(ValueError, match="fit"):
+            model.transform()
+        model.fit(np.zeros((3, 200)))
+        assert len(model.fit_results_) == 3
+
+        trace = _make_trace(0.02, 0.4, 30.0, 300, [30, 100, 200], alpha=5.0, baseline=1.0)
+        assert model.fit(trace) is model
+        assert model.transform().shape == (1, 300)
+        assert len(model.fit_results_) == 1
+
+    def test_rejects_3d_input(self):
+        with pytest.raises(ValueError, match="1-D or 2-D"):
+            InDeCa(0.02, 0.4, 30.0).fit(np.zeros((2, 2, 10)))
+
 
 # ---------------------------------------------------------------------------
 # estimate_kernel
@@ -118,6 +289,52 @@ class TestSolveTrace:
 
 
 class TestEstimateKernel:
+    def test_2d_matches_flat(self):
+        from calab._solver import py_convolve_spikes_kernel, py_estimate_free_kernel
+
+        rng = np.random.default_rng(3)
+        true_kernel = np.asarray(build_kernel(0.02, 0.4, 30.0), dtype=np.float64)[:30]
+        spikes = (rng.random((3, 200)) < 0.03).astype(np.float64)
+        traces = np.asarray(py_convolve_spikes_kernel(spikes, true_kernel), dtype=np.float64)
+        np.testing.assert_allclose(
+            traces[0], np.convolve(spikes[0], true_kernel)[:200], rtol=1e-5, atol=1e-6,
+        )
+
+        alphas, baselines = np.ones(3), np.zeros(3)
+        kernel_2d = py_estimate_free_kernel(traces, spikes, alphas, baselines, 30)
+        kernel_flat = estimate_kernel(
+            traces.ravel(), spikes.ravel(), np.full(3, 200, dtype=np.int64),
+            alphas, baselines, 30,
+        )
+        np.testing.assert_array_equal(kernel_2d, kernel_flat)
+
+        with pytest.raises(ValueError, match="same shape"):
+            py_estimate_free_kernel(traces, spikes[:2], alphas, baselines, 30)
+
+    def test_parallel_sessions_match_single_session(self):
+        from calab._solver import (
+            py_convolve_spikes_kernel,
+            py_estimate_free_kernel,
+            py_estimate_free_kernel_parallel,
+        )
+
+        rng = np.random.default_rng(4)
+        true_kernel = np.asarray(build_kernel(0.02, 0.4, 30.0), dtype=np.float64)[:30]
+        spikes = (rng.random((4, 200)) < 0.03).astype(np.float64)
+        traces = np.asarray(py_convolve_spikes_kernel(spikes, true_kernel), dtype=np.float64)
+        alphas, baselines = np.ones(4), np.zeros(4)
+
+        single = py_estimate_free_kernel(traces, spikes, alphas, baselines, 30)
+        sessions = [
+            {"traces": traces[:2], "spike_trains": spikes[:2], "alphas": alphas[:2], "baselines": baselines[:2]},
+            {"traces": traces[2:], "spike_trains": spikes[2:], "alphas": alphas[2:], "baselines": baselines[2:]},
+        ]
+        parallel = py_estimate_free_kernel_parallel(sessions, 30)
+        np.testing.assert_allclose(parallel, single, atol=1e-4)
+
+        with pytest.raises(KeyError, match="alphas"):
+            py_estimate_free_kernel_parallel([{"traces": traces, "spike_trains": spikes}], 30)
+
     def test_basic_output_shape(self):
         kernel = estimate_kernel(
             traces_flat=np.ones(100, dtype=np.float64),
@@ -212,6 +429,54 @@ class TestFitBiexponential:
         assert isinstance(residual, float)
         assert isinstance(fit_mode, str)
 
+    def test_pyclass_bindings(self):
+        from calab._solver import (
+            PyBiexpResult,
+            py_eval_biexp,
+            py_fit_biexponential,
+            py_fit_biexponential_bounded,
+        )
+
+        fs = 30.0
+        h = py_eval_biexp(0.08, 0.5, 2.0, fs, 60)
+        assert h.dtype == np.float32 and h.shape == (60,)
+        t = np.arange(60) / fs
+        np.testing.assert_allclose(h, 2.0 * (np.exp(-t / 0.5) - np.exp(-t / 0.08)), atol=1e-6)
+
+        result = py_fit_biexponential(h.astype(np.float64), fs)
+        assert isinstance(result, PyBiexpResult)
+        assert abs(result.tau_rise - 0.08) < 0.015
+        assert abs(result.tau_decay - 0.5) < 0.075
+
+        # True taus lie outside the box: the fit stays inside it.
+        bounded = py_fit_biexponential_bounded(h.astype(np.float64), fs, 0.01, 0.04, 0.8, 2.0)
+        assert 0.01 <= bounded.tau_rise <= 0.04
+        assert 0.8 <= bounded.tau_decay <= 2.0
+        assert bounded.residual > result.residual
+        assert "PyBiexpResult(" in repr(bounded)
+
+        with pytest.raises(ValueError):
+            py_fit_biexponential_bounded(h.astype(np.float64), fs, 0.2, 0.1, 0.2, 1.0)
+        with pytest.raises(ValueError, match="tau_d_min"):
+            py_fit_biexponential_bounded(h.astype(np.float64), fs, 0.02, 0.3, 0.2, 1.0)
+
+    def test_kernel_quality(self):
+        from calab._solver import PyKernelQuality, py_kernel_quality
+
+        fs = 30.0
+        t = np.arange(60) / fs
+        h = np.exp(-t / 0.5) - np.exp(-t / 0.05)
+        q = py_kernel_quality(h, 0.05, 0.5, fs)
+        assert isinstance(q, PyKernelQuality)
+        assert q.pve_biexp > 0.99
+        assert q.negativity_fraction == 0.0
+        assert q.peak_sharpness == pytest.approx(h.max() / h.mean(), rel=1e-5)
+        assert q.l2_norm == pytest.approx(np.linalg.norm(h), rel=1e-5)
+
+        noisy = h.copy()
+        noisy[20::2] = -0.3
+        assert py_kernel_quality(noisy, 0.05, 0.5, fs).negativity_fraction > 0.25
+
 
 # ---------------------------------------------------------------------------
 # compute_upsample_factor
@@ -227,3 +492,118 @@ class TestComputeUpsampleFactor:
 
     def test_minimum_1(self):
         assert compute_upsample_factor(100.0, 30.0) == 1
+
+
+# ---------------------------------------------------------------------------
+# roc_threshold_curve
+# ---------------------------------------------------------------------------
+
+
+class TestRocThresholdCurve:
+    def test_perfect_ranking(self):
+        truth = np.zeros(300)
+        truth[[60, 110, 170, 230]] = 1.0
+        relaxed = truth * 0.8
+        relaxed[[61, 111, 171, 231]] = 0.2  # halo below every spike
+        roc = roc_threshold_curve(relaxed, truth, 0.4, 30.0, n_thresholds=20)
+        assert roc.thresholds[0] == np.inf
+        assert roc.fpr[0] == 0.0 and roc.tpr[-1] == 1.0
+        assert np.all(np.diff(roc.fpr) >= 0)
+        assert roc.auc == pytest.approx(1.0)
+
+    def test_no_positives_warns(self):
+        relaxed = np.linspace(0.0, 1.0, 200)
+        with pytest.warns(UserWarning, match="no positive"):
+            roc = roc_threshold_curve(relaxed, np.zeros(200), 0.4, 30.0)
+        assert roc.auc == 0.5
+
+    def test_length_mismatch(self):
+        with pytest.raises(ValueError):
+            roc_threshold_curve(np.zeros(100), np.zeros(99), 0.4, 30.0)
+
+
+# ---------------------------------------------------------------------------
+# threshold_search
+# ---------------------------------------------------------------------------
+
+
+class TestThresholdSearch:
+    def test_recovers_binary_spikes(self):
+        spikes = [50, 120, 200]
+        y = _make_trace(0.02, 0.4, 30.0, 300, spikes, alpha=5.0, baseline=1.0)
+        s_relaxed = np.zeros(300)
+        s_relaxed[spikes] = 1.0
+
+        result = threshold_search(s_relaxed, y, 0.02, 0.4, 30.0)
+        np.testing.assert_array_equal(np.flatnonzero(result.s_binary), spikes)
+        assert result.alpha == pytest.approx(5.0, rel=1e-3)
+        assert result.baseline == pytest.approx(1.0, abs=1e-3)
+        assert result.pve > 0.999
+        assert repr(result).startswith("PyThresholdResult(")
+
+    def test_length_mismatch(self):
+        with pytest.raises(ValueError, match="dimension mismatch"):
+            threshold_search(np.zeros(100), np.zeros(99), 0.02, 0.4, 30.0)
+
+    def test_aic_criterion(self):
+        spikes = [50, 120, 200]
+        y = _make_trace(0.02, 0.4, 30.0, 300, spikes, alpha=5.0, baseline=1.0)
+        s_relaxed = np.zeros(300)
+        s_relaxed[spikes] = 1.0
+
+        result = threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, threshold_criterion="aic")
+        np.testing.assert_array_equal(np.flatnonzero(result.s_binary), spikes)
+        with pytest.raises(ValueError, match="threshold_criterion"):
+            threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, threshold_criterion="bic")
+
+    def test_custom_grid_and_cap(self):
+        spikes = [50, 120, 200]
+        y = _make_trace(0.02, 0.4, 30.0, 300, spikes, alpha=5.0, baseline=1.0)
+        s_relaxed = np.zeros(300)
+        s_relaxed[spikes] = [1.0, 0.8, 0.6]
+        s_relaxed[90] = 0.2
+
+        result = threshold_search_custom_grid(s_relaxed, y, 0.02, 0.4, 30.0, [0.9, 0.5, 0.1])
+        assert result.threshold == 0.5
+        np.testing.assert_array_equal(np.flatnonzero(result.s_binary), spikes)
+        empty = threshold_search_custom_grid(s_relaxed, y, 0.02, 0.4, 30.0, [])
+        assert not empty.s_binary.any()
+
+        capped = threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, max_threshold=0.3)
+        assert capped.threshold <= 0.3
+
+    def test_weights_ignore_artifact(self):
+        spikes = [60, 180]
+        y = _make_trace(0.02, 0.4, 30.0, 300, spikes, alpha=2.0, baseline=1.0)
+        y[110:130] += 6.0  # artifact the spikes do not explain
+        s_relaxed = np.zeros(300)
+        s_relaxed[spikes] = 1.0
+        weights = np.ones(300)
+        weights[110:130] = 0.0
+
+        weighted = threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, weights=weights)
+        assert weighted.alpha == pytest.approx(2.0, rel=1e-3)
+        assert weighted.baseline == pytest.approx(1.0, abs=1e-3)
+        plain = threshold_search(s_relaxed, y, 0.02, 0.4, 30.0)
+        assert abs(plain.baseline - 1.0) > 0.1
+        with pytest.raises(ValueError):
+            threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, weights=np.ones(299))
+        with pytest.raises(ValueError, match="weights"):
+            threshold_search(s_relaxed, y, 0.02, 0.4, 30.0, weights=-weights)
+
+    def test_joint_search_shares_threshold(self):
+        spikes_a, spikes_b = [50, 120, 200], [80, 240]
+        y_a = _make_trace(0.02, 0.4, 30.0, 300, spikes_a, alpha=5.0, baseline=1.0)
+        y_b = _make_trace(0.02, 0.4, 30.0, 300, spikes_b, alpha=2.0, baseline=0.2)
+        s_a, s_b = np.zeros(300), np.zeros(300)
+        s_a[spikes_a] = 1.0
+        s_b[spikes_b] = 0.8
+
+        results, threshold = joint_threshold_search([s_a, s_b], [y_a, y_b], 0.02, 0.4, 30.0)
+        assert [r.threshold for r in results] == [threshold, threshold]
+        np.testing.assert_array_equal(np.flatnonzero(results[1].s_binary), spikes_b)
+        assert results[0].alpha == pytest.approx(5.0, rel=1e-3)
+        assert results[1].alpha == pytest.approx(2.0, rel=1e-3)
+        assert joint_threshold_search([], [], 0.02, 0.4, 30.0) == ([], 0.0)
+        with pytest.raises(ValueError):
+            joint_threshold_search([s_a], [y_a[:-1]], 0.02, 0.4, 30.0)